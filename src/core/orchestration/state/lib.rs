@@ -2,7 +2,9 @@
 
 pub mod engine;
 
-use crdts::{GCounter, CmRDT, CvRDT};
+use crdts::{Dot, GCounter, Orswot, PNCounter, VClock, CmRDT, CvRDT};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use wasm_bindgen::prelude::*;
 use num_traits::cast::ToPrimitive;
 
@@ -35,4 +37,316 @@ impl StateManager {
     pub fn reset(&mut self) {
         self.counter = GCounter::new();
     }
+
+    /// Snapshot of the full per-actor counter state, for checkpointing or transfer
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(&self.counter).expect("GCounter serialization is infallible")
+    }
+
+    /// Per-actor entries where this replica is ahead of `other`
+    pub fn delta_since(&self, other: &StateManager) -> StateDelta {
+        let theirs = other.actor_counts();
+        let entries = self.actor_counts().dots.into_iter()
+            .filter(|(actor, count)| theirs.get(actor) < *count)
+            .collect();
+        StateDelta { entries }
+    }
+
+    /// Apply a delta from another replica; stale or repeated deltas are no-ops
+    pub fn apply_delta(&mut self, delta: StateDelta) {
+        for (actor, count) in delta.entries {
+            self.counter.apply(Dot::new(actor, count));
+        }
+    }
+}
+
+impl StateManager {
+    /// Restore a snapshot produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<StateManager, bincode::Error> {
+        let counter = bincode::deserialize(bytes)?;
+        Ok(StateManager { counter })
+    }
+
+    /// Per-actor counts. `GCounter` keeps its clock private but serializes as nothing
+    /// more than that clock, so its snapshot decodes as one.
+    fn actor_counts(&self) -> VClock<String> {
+        bincode::deserialize(&self.to_bytes()).expect("a GCounter snapshot is a VClock")
+    }
+}
+
+// `JsError` can only be built on wasm targets, so decode errors become one here and
+// nowhere else
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl StateManager {
+    #[wasm_bindgen(js_name = from_bytes)]
+    pub fn from_bytes_js(bytes: &[u8]) -> Result<StateManager, JsError> {
+        Ok(Self::from_bytes(bytes)?)
+    }
+}
+
+/// Changed per-actor counts, as produced by `StateManager::delta_since`
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateDelta {
+    entries: BTreeMap<String, u64>,
+}
+
+#[wasm_bindgen]
+impl StateDelta {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("StateDelta serialization is infallible")
+    }
+}
+
+impl StateDelta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<StateDelta, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl StateDelta {
+    #[wasm_bindgen(js_name = from_bytes)]
+    pub fn from_bytes_js(bytes: &[u8]) -> Result<StateDelta, JsError> {
+        Ok(Self::from_bytes(bytes)?)
+    }
+}
+
+/// Counter that can also go down, e.g. the number of open positions.
+/// Unlike `StateManager`, the value starts at zero.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct PNStateManager {
+    counter: PNCounter<String>,
+}
+
+#[wasm_bindgen]
+impl PNStateManager {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        PNStateManager { counter: PNCounter::new() }
+    }
+
+    pub fn increment(&mut self, actor_id: String) {
+        self.counter.apply(self.counter.inc(actor_id));
+    }
+
+    pub fn decrement(&mut self, actor_id: String) {
+        self.counter.apply(self.counter.dec(actor_id));
+    }
+
+    pub fn value(&self) -> i64 {
+        self.counter.read().to_i64().unwrap_or(0)
+    }
+
+    pub fn merge(&mut self, other: PNStateManager) {
+        self.counter.merge(other.counter);
+    }
+
+    pub fn reset(&mut self) {
+        self.counter = PNCounter::new();
+    }
+}
+
+/// Position ids cross the wasm boundary in their UUID string form
+pub type PositionId = String;
+
+/// Replicated set of monitored positions. Removes only cancel the adds this replica
+/// has observed, so a concurrent re-add of the same position wins after a merge.
+#[wasm_bindgen]
+pub struct OrSetStateManager {
+    actor_id: String,
+    positions: Orswot<PositionId, String>,
+}
+
+#[wasm_bindgen]
+impl OrSetStateManager {
+    #[wasm_bindgen(constructor)]
+    pub fn new(actor_id: String) -> Self {
+        OrSetStateManager { actor_id, positions: Orswot::new() }
+    }
+
+    pub fn add(&mut self, position_id: PositionId) {
+        let ctx = self.positions.read_ctx().derive_add_ctx(self.actor_id.clone());
+        self.positions.apply(self.positions.add(position_id, ctx));
+    }
+
+    pub fn remove(&mut self, position_id: PositionId) {
+        let ctx = self.positions.contains(&position_id).derive_rm_ctx();
+        self.positions.apply(self.positions.rm(position_id, ctx));
+    }
+
+    pub fn contains(&self, position_id: PositionId) -> bool {
+        self.positions.contains(&position_id).val
+    }
+
+    /// Current positions, sorted so every converged replica lists them identically
+    pub fn elements(&self) -> Vec<PositionId> {
+        let mut elements: Vec<PositionId> = self.positions.read().val.into_iter().collect();
+        elements.sort();
+        elements
+    }
+
+    pub fn merge(&mut self, other: OrSetStateManager) {
+        self.positions.merge(other.positions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const ACTORS: [&str; 3] = ["a", "b", "c"];
+
+    fn counter_with(ops: &[usize]) -> StateManager {
+        let mut manager = StateManager::new(ACTORS[0].to_string());
+        for op in ops {
+            manager.increment(ACTORS[*op].to_string());
+        }
+        manager
+    }
+
+    proptest! {
+        #[test]
+        fn snapshot_round_trip_preserves_state(
+            ops in prop::collection::vec(0..ACTORS.len(), 0..64),
+            later in prop::collection::vec(0..ACTORS.len(), 0..64),
+        ) {
+            let original = counter_with(&ops);
+            let restored = StateManager::from_bytes(&original.to_bytes()).unwrap();
+            prop_assert!(restored.counter == original.counter);
+            prop_assert_eq!(restored.value(), original.value());
+
+            // A live replica that kept counting absorbs the older snapshot unchanged,
+            // and the snapshot catches up to it
+            let mut live = counter_with(&ops);
+            for op in &later {
+                live.increment(ACTORS[*op].to_string());
+            }
+            let live_value = live.value();
+            let mut caught_up = StateManager::from_bytes(&live.to_bytes()).unwrap();
+            live.merge(restored);
+            prop_assert_eq!(live.value(), live_value);
+
+            caught_up.merge(StateManager::from_bytes(&original.to_bytes()).unwrap());
+            prop_assert!(caught_up.counter == live.counter);
+        }
+
+        #[test]
+        fn deltas_converge_like_full_merge(
+            a_ops in prop::collection::vec(0..ACTORS.len(), 0..32),
+            b_ops in prop::collection::vec(0..ACTORS.len(), 0..32),
+            c_ops in prop::collection::vec(0..ACTORS.len(), 0..32),
+        ) {
+            let a = counter_with(&a_ops);
+            let peers = [counter_with(&b_ops), counter_with(&c_ops)];
+
+            let mut via_delta = StateManager::from_bytes(&a.to_bytes()).unwrap();
+            let mut via_merge = StateManager::from_bytes(&a.to_bytes()).unwrap();
+            for peer in &peers {
+                let delta = StateDelta::from_bytes(&peer.delta_since(&via_delta).to_bytes()).unwrap();
+                via_delta.apply_delta(delta);
+                via_merge.merge(StateManager::from_bytes(&peer.to_bytes()).unwrap());
+            }
+            prop_assert!(via_delta.counter == via_merge.counter);
+
+            // A stale delta changes nothing, and a caught-up replica has nothing to send
+            via_delta.apply_delta(peers[0].delta_since(&a));
+            prop_assert!(via_delta.counter == via_merge.counter);
+            prop_assert!(peers[1].delta_since(&via_delta).is_empty());
+        }
+    }
+
+    fn replica(ops: &[(&str, bool)]) -> PNStateManager {
+        let mut manager = PNStateManager::new();
+        for (actor, up) in ops {
+            if *up {
+                manager.increment(actor.to_string());
+            } else {
+                manager.decrement(actor.to_string());
+            }
+        }
+        manager
+    }
+
+    #[test]
+    fn pn_counters_converge_after_concurrent_updates() {
+        let a_ops = [("a", true), ("a", true), ("a", true), ("a", false)];
+        let b_ops = [("b", true), ("b", false), ("b", false), ("b", false)];
+
+        let mut a = replica(&a_ops);
+        let mut b = replica(&b_ops);
+        assert_eq!(a.value(), 2);
+        assert_eq!(b.value(), -2);
+
+        a.merge(replica(&b_ops));
+        b.merge(replica(&a_ops));
+        assert_eq!(a.value(), 0);
+        assert_eq!(b.value(), 0);
+
+        // Merging the same state again is a no-op
+        a.merge(replica(&b_ops));
+        assert_eq!(a.value(), 0);
+
+        a.decrement("a".to_string());
+        b.merge(replica(&[("a", true), ("a", true), ("a", true), ("a", false), ("a", false)]));
+        assert_eq!(b.value(), a.value());
+        assert_eq!(b.value(), -1);
+    }
+
+    fn copy(set: &OrSetStateManager, actor_id: &str) -> OrSetStateManager {
+        OrSetStateManager { actor_id: actor_id.to_string(), positions: set.positions.clone() }
+    }
+
+    #[test]
+    fn or_set_concurrent_add_wins_over_remove() {
+        let mut a = OrSetStateManager::new("a".to_string());
+        a.add("pos-1".to_string());
+        a.add("pos-2".to_string());
+        let mut b = copy(&a, "b");
+
+        // Partition: a drops both positions while b re-adds pos-1
+        a.remove("pos-1".to_string());
+        a.remove("pos-2".to_string());
+        b.add("pos-1".to_string());
+        b.add("pos-3".to_string());
+        assert!(!a.contains("pos-1".to_string()));
+        assert!(b.contains("pos-2".to_string()));
+
+        let a_state = copy(&a, "a");
+        a.merge(copy(&b, "b"));
+        b.merge(a_state);
+
+        let expected = vec!["pos-1".to_string(), "pos-3".to_string()];
+        assert_eq!(a.elements(), expected);
+        assert_eq!(b.elements(), expected);
+    }
+
+    #[test]
+    fn or_set_remove_ignores_unobserved_adds() {
+        let mut a = OrSetStateManager::new("a".to_string());
+        let mut b = OrSetStateManager::new("b".to_string());
+
+        // a removes a position it has never seen b add
+        b.add("pos-1".to_string());
+        a.remove("pos-1".to_string());
+        a.merge(copy(&b, "b"));
+        assert!(a.contains("pos-1".to_string()));
+
+        // Once observed, the remove propagates
+        a.remove("pos-1".to_string());
+        b.merge(copy(&a, "a"));
+        assert!(b.elements().is_empty());
+    }
 }
@@ -0,0 +1,306 @@
+use crate::monitoring::{AlertAuditEntry, AlertQuery};
+use crate::types::{CalculationError, HealthFactor, Position, PositionError, PositionId, RiskAlert};
+use crate::AegisSatellite;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+/// Alerts returned by `GET /alerts` when no `limit` is given
+pub const DEFAULT_ALERT_PAGE_SIZE: usize = 100;
+/// Largest `limit` `GET /alerts` honours; larger values are lowered to it
+pub const MAX_ALERT_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error(transparent)]
+    Position(#[from] PositionError),
+    #[error(transparent)]
+    Calculation(#[from] CalculationError),
+    #[error("Alert not found: {id}")]
+    AlertNotFound { id: Uuid },
+    /// The request itself could not be read, e.g. a malformed id or body
+    #[error("{message}")]
+    Rejected { status: StatusCode, message: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Position(PositionError::NotFound { .. }) => StatusCode::NOT_FOUND,
+            ApiError::Position(PositionError::AlreadyExists { .. }) => StatusCode::CONFLICT,
+            ApiError::Position(PositionError::Invalid { .. }) => StatusCode::BAD_REQUEST,
+            ApiError::Position(PositionError::Persistence { .. }) => StatusCode::INTERNAL_SERVER_ERROR,
+            // Price problems are usually transient, so clients should retry
+            ApiError::Calculation(CalculationError::MissingPriceData { .. })
+            | ApiError::Calculation(CalculationError::StalePriceData { .. })
+            | ApiError::Calculation(CalculationError::PriceCircuitOpen { .. }) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Calculation(CalculationError::InvalidPosition { .. })
+            | ApiError::Calculation(CalculationError::UnsupportedProtocol { .. }) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Calculation(CalculationError::CalculationFailed { .. }) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::AlertNotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Rejected { status, .. } => *status,
+            ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for ApiError {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        ApiError::Internal { message: error.to_string() }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::Rejected { status: rejection.status(), message: rejection.body_text() }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::Rejected { status: rejection.status(), message: rejection.body_text() }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::Rejected { status: rejection.status(), message: rejection.body_text() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(ErrorBody { error: self.to_string() })).into_response()
+    }
+}
+
+/// Body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+/// Optional body of `POST /alerts/:id/ack`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcknowledgeAlert {
+    /// Recorded in the audit trail as given. The API does not authenticate callers, so
+    /// this is a label chosen by the caller, not a verified identity.
+    pub acknowledged_by: String,
+    pub note: Option<String>,
+}
+
+/// Recorded as the acknowledging actor when the request names none
+pub const ANONYMOUS_API_ACTOR: &str = "api";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedPosition {
+    pub id: PositionId,
+}
+
+/// Routes over `satellite`:
+///
+/// - `POST /positions` adds a position and returns its id
+/// - `GET /positions/:id/health` calculates the position's current health factor
+/// - `GET /alerts` lists alerts newest first, filtered and paged by the fields of
+///   `AlertQuery`, e.g. `?position_id=...&acknowledged=false&limit=50`. Pages hold
+///   `DEFAULT_ALERT_PAGE_SIZE` alerts unless `limit` asks for more, up to `MAX_ALERT_PAGE_SIZE`.
+/// - `POST /alerts/:id/ack` acknowledges an alert, stopping its escalation. An optional
+///   `AcknowledgeAlert` body records who acknowledged it and why; the name is taken on
+///   trust, so deployments needing verified actors should authenticate in front of it.
+/// - `GET /alerts/:id/audit` lists the actions taken on an alert, oldest first
+///
+/// Every error, including a malformed id or body, is answered with an `ErrorBody`.
+pub fn router(satellite: Arc<AegisSatellite>) -> Router {
+    Router::new()
+        .route("/positions", post(create_position))
+        .route("/positions/:id/health", get(position_health))
+        .route("/alerts", get(list_alerts))
+        .route("/alerts/:id/ack", post(acknowledge_alert))
+        .route("/alerts/:id/audit", get(alert_audit))
+        .with_state(satellite)
+}
+
+/// Serve the API on `address` until the task is cancelled
+pub async fn serve(satellite: Arc<AegisSatellite>, address: SocketAddr) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    info!("Serving Aegis API on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(satellite)).await
+}
+
+async fn create_position(
+    State(satellite): State<Arc<AegisSatellite>>,
+    position: Result<Json<Position>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreatedPosition>), ApiError> {
+    let Json(position) = position?;
+    let id = satellite.add_position(position).await?;
+    Ok((StatusCode::CREATED, Json(CreatedPosition { id })))
+}
+
+async fn position_health(
+    State(satellite): State<Arc<AegisSatellite>>,
+    id: Result<Path<PositionId>, PathRejection>,
+) -> Result<Json<HealthFactor>, ApiError> {
+    let Path(id) = id?;
+    // The monitor reports unknown ids as a failed calculation; surface them as a 404
+    if satellite.get_position(id).is_none() {
+        return Err(PositionError::NotFound { id }.into());
+    }
+    Ok(Json(satellite.get_position_health(id).await?))
+}
+
+async fn list_alerts(
+    State(satellite): State<Arc<AegisSatellite>>,
+    query: Result<Query<AlertQuery>, QueryRejection>,
+) -> Result<Json<Vec<RiskAlert>>, ApiError> {
+    let Query(mut query) = query?;
+    query.limit = Some(query.limit.unwrap_or(DEFAULT_ALERT_PAGE_SIZE).min(MAX_ALERT_PAGE_SIZE));
+    Ok(Json(satellite.get_alerts_filtered(&query)))
+}
+
+async fn acknowledge_alert(
+    State(satellite): State<Arc<AegisSatellite>>,
+    id: Result<Path<Uuid>, PathRejection>,
+    body: Option<Json<AcknowledgeAlert>>,
+) -> Result<StatusCode, ApiError> {
+    let Path(id) = id?;
+    if !satellite.get_alerts(None).await?.iter().any(|alert| alert.id == id) {
+        return Err(ApiError::AlertNotFound { id });
+    }
+    match body {
+        Some(Json(ack)) => satellite.acknowledge_alert_with_note(id, &ack.acknowledged_by, ack.note.as_deref()).await?,
+        None => satellite.acknowledge_alert_with_note(id, ANONYMOUS_API_ACTOR, None).await?,
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn alert_audit(
+    State(satellite): State<Arc<AegisSatellite>>,
+    id: Result<Path<Uuid>, PathRejection>,
+) -> Result<Json<Vec<AlertAuditEntry>>, ApiError> {
+    let Path(id) = id?;
+    if !satellite.get_alerts(None).await?.iter().any(|alert| alert.id == id) {
+        return Err(ApiError::AlertNotFound { id });
+    }
+    Ok(Json(satellite.get_alert_audit(id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{StaticPriceFeed, UnusedExecutor};
+    use axum::body::Body;
+    use axum::http::Request;
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    async fn test_router() -> Router {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(UnusedExecutor), None).await.unwrap();
+        router(Arc::new(satellite))
+    }
+
+    fn position_json(id: Uuid, eth: i64, usdc_debt: i64) -> serde_json::Value {
+        let token = |address: &str, amount: i64| serde_json::json!({
+            "token_address": address,
+            "amount": amount,
+            "value_usd": 0,
+            "price_per_token": 0,
+        });
+        serde_json::json!({
+            "id": id,
+            "protocol": "aave",
+            "collateral_tokens": { "ETH": token("ETH", eth) },
+            "debt_tokens": { "USDC": token("USDC", usdc_debt) },
+            "created_at": chrono::Utc::now(),
+            "updated_at": chrono::Utc::now(),
+        })
+    }
+
+    async fn send(router: &Router, method: &str, uri: &str, body: Option<serde_json::Value>) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => request.header("content-type", "application/json").body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }.unwrap();
+
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() { serde_json::Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_position_and_health_endpoints() {
+        let router = test_router().await;
+        let id = Uuid::new_v4();
+
+        let (status, body) = send(&router, "POST", "/positions", Some(position_json(id, 10, 1_000))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["id"], id.to_string());
+
+        let (status, _) = send(&router, "POST", "/positions", Some(position_json(id, 10, 1_000))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, body) = send(&router, "GET", &format!("/positions/{}/health", id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let health: HealthFactor = serde_json::from_value(body).unwrap();
+        assert!(health.value > Decimal::ONE);
+        assert_eq!(health.collateral_value, Decimal::from(20_000));
+
+        let unknown = Uuid::new_v4();
+        let (status, body) = send(&router, "GET", &format!("/positions/{}/health", unknown), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], format!("Position not found: {}", unknown));
+
+        let (status, body) = send(&router, "GET", "/positions/not-a-uuid/health", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("UUID parsing failed"));
+    }
+
+    #[tokio::test]
+    async fn test_alert_endpoints() {
+        let router = test_router().await;
+        let safe = Uuid::new_v4();
+        let at_risk = Uuid::new_v4();
+        send(&router, "POST", "/positions", Some(position_json(safe, 10, 1_000))).await;
+        // Health ~0.94 raises an alert on add
+        send(&router, "POST", "/positions", Some(position_json(at_risk, 10, 17_000))).await;
+
+        let (status, body) = send(&router, "GET", "/alerts", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let alerts: Vec<RiskAlert> = serde_json::from_value(body).unwrap();
+        assert!(!alerts.is_empty());
+        assert!(alerts.iter().all(|alert| alert.position_id == at_risk && !alert.acknowledged));
+
+        let (_, body) = send(&router, "GET", &format!("/alerts?position_id={}", safe), None).await;
+        assert_eq!(body, serde_json::json!([]));
+
+        let ack = serde_json::json!({ "acknowledged_by": "risk-desk", "note": "hedged off-chain" });
+        let (status, body) = send(&router, "POST", &format!("/alerts/{}/ack", alerts[0].id), Some(ack)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(body, serde_json::Value::Null);
+        let (_, body) = send(&router, "GET", &format!("/alerts?position_id={}", at_risk), None).await;
+        let alerts_after: Vec<RiskAlert> = serde_json::from_value(body).unwrap();
+        assert!(alerts_after.iter().any(|alert| alert.id == alerts[0].id && alert.acknowledged));
+
+        let (status, body) = send(&router, "GET", &format!("/alerts/{}/audit", alerts[0].id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let audit: Vec<AlertAuditEntry> = serde_json::from_value(body).unwrap();
+        let last = audit.last().unwrap();
+        assert_eq!((last.actor.as_str(), last.note.as_deref()), ("risk-desk", Some("hedged off-chain")));
+
+        let (status, body) = send(&router, "POST", &format!("/alerts/{}/ack", Uuid::new_v4()), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body["error"].as_str().unwrap().starts_with("Alert not found"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Monte Carlo throughput by worker count.
+//!
+//! Run with `cargo bench --bench monte_carlo`. Path generation is independent per path,
+//! so time per run should fall close to linearly with `workers` up to the core count.
+
+use aegis_satellite::simulation::{
+    MonteCarloConfig, MonteCarloExecutionMode, SimulationPosition, StressTestingConfig, StressTestingFramework,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+
+const PATHS: u32 = 100_000;
+
+fn positions() -> Vec<SimulationPosition> {
+    ["ETH", "BTC", "LINK", "UNI"].iter()
+        .map(|token| SimulationPosition {
+            token_address: token.to_string(),
+            quantity: 10.0,
+            entry_price: 1000.0,
+            current_price: 1000.0,
+            collateral_value: 10_000.0,
+            debt_value: 5_000.0,
+            liquidation_threshold: 0.8,
+            health_factor: 2.0,
+            debt_tokens: HashMap::new(),
+        })
+        .collect()
+}
+
+fn monte_carlo_scaling(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let framework = StressTestingFramework::new(StressTestingConfig::default());
+    let positions = positions();
+
+    let mut group = c.benchmark_group("monte_carlo_paths");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(PATHS as u64));
+
+    for workers in [1, 2, 4, 8] {
+        let config = MonteCarloConfig {
+            iterations: PATHS,
+            time_horizon_days: 30,
+            confidence_level: 0.95,
+            price_volatility: 0.5,
+            drift_rates: HashMap::new(),
+            seed: Some(42),
+            execution_mode: MonteCarloExecutionMode::DeterministicParallel { workers },
+            correlations: None,
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(workers), &config, |b, config| {
+            b.iter(|| runtime.block_on(framework.run_monte_carlo_simulation(&positions, config)).unwrap());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, monte_carlo_scaling);
+criterion_main!(benches);
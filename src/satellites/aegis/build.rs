@@ -0,0 +1,14 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/// Generate the gRPC service and messages, using a vendored `protoc` so the build
+/// doesn't depend on one being installed
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("no vendored protoc for this platform");
+    std::env::set_var("PROTOC", protoc);
+    println!("cargo:rerun-if-changed=proto/aegis.proto");
+    tonic_build::compile_protos("proto/aegis.proto").expect("failed to compile proto/aegis.proto");
+}
@@ -0,0 +1,310 @@
+use crate::liquidation::PriceFeedProvider;
+use crate::types::{PriceData, TokenAddress};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use dashmap::DashMap;
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// `latestRoundData()` function selector
+const LATEST_ROUND_DATA: &str = "0xfeaf968c";
+/// `decimals()` function selector
+const DECIMALS: &str = "0x313ce567";
+
+/// One answer of a Chainlink aggregator, already scaled by the feed's decimals
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainlinkRound {
+    pub round_id: u128,
+    pub answer: Decimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Price feed reading Chainlink aggregator contracts over JSON-RPC.
+///
+/// Tokens without a configured aggregator, or whose aggregator can't be read, are left out
+/// of `get_prices`, so the monitor reports them as missing rather than failing the whole
+/// fetch. `fetch_prices` gives the reason for each.
+pub struct ChainlinkPriceFeed {
+    rpc_url: String,
+    aggregators: HashMap<TokenAddress, String>,
+    /// Age at which an answer's confidence reaches zero
+    heartbeat: std::time::Duration,
+    http_client: reqwest::Client,
+    decimals: DashMap<String, u32>,
+}
+
+impl ChainlinkPriceFeed {
+    pub fn new(rpc_url: &str, aggregators: HashMap<TokenAddress, String>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()?;
+
+        Ok(Self {
+            rpc_url: rpc_url.to_string(),
+            aggregators,
+            heartbeat: std::time::Duration::from_secs(60 * 60),
+            http_client,
+            decimals: DashMap::new(),
+        })
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat: std::time::Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    async fn eth_call(&self, to: &str, data: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [{ "to": to, "data": data }, "latest"],
+        });
+
+        let response: serde_json::Value = self.http_client
+            .post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(format!("eth_call to {} failed: {}", to, error).into());
+        }
+        response["result"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("eth_call to {} returned no result", to).into())
+    }
+
+    async fn aggregator_decimals(&self, aggregator: &str) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(decimals) = self.decimals.get(aggregator) {
+            return Ok(*decimals);
+        }
+
+        let result = self.eth_call(aggregator, DECIMALS).await?;
+        let words = abi_words(&result)?;
+        let decimals = match words.first() {
+            Some(word) => decode_uint(word)? as u32,
+            None => return Err(format!("Empty decimals() response from {}", aggregator).into()),
+        };
+        self.decimals.insert(aggregator.to_string(), decimals);
+        Ok(decimals)
+    }
+
+    async fn latest_round(&self, aggregator: &str) -> Result<ChainlinkRound, Box<dyn std::error::Error + Send + Sync>> {
+        let decimals = self.aggregator_decimals(aggregator).await?;
+        let result = self.eth_call(aggregator, LATEST_ROUND_DATA).await?;
+        decode_latest_round_data(&result, decimals)
+    }
+
+    /// Read every token's aggregator concurrently, keeping each token's price or the
+    /// error that prevented it, so one bad aggregator doesn't hide the others
+    pub async fn fetch_prices(
+        &self,
+        token_addresses: &[TokenAddress],
+    ) -> HashMap<TokenAddress, Result<PriceData, Box<dyn std::error::Error + Send + Sync>>> {
+        let responses = join_all(token_addresses.iter().map(|token| self.get_price(token))).await;
+        token_addresses.iter().cloned().zip(responses).collect()
+    }
+}
+
+/// Confidence falling linearly from 1 for a fresh answer to 0 at `heartbeat`
+pub fn staleness_confidence(updated_at: DateTime<Utc>, now: DateTime<Utc>, heartbeat: std::time::Duration) -> Decimal {
+    let age_ms = (now - updated_at).num_milliseconds().max(0);
+    let heartbeat_ms = heartbeat.as_millis().max(1) as i64;
+    if age_ms >= heartbeat_ms {
+        return Decimal::ZERO;
+    }
+    Decimal::ONE - Decimal::from(age_ms) / Decimal::from(heartbeat_ms)
+}
+
+/// Decode the ABI-encoded return value of `latestRoundData()`
+pub fn decode_latest_round_data(result: &str, decimals: u32) -> Result<ChainlinkRound, Box<dyn std::error::Error + Send + Sync>> {
+    let words = abi_words(result)?;
+    if words.len() < 5 {
+        return Err(format!("latestRoundData() returned {} words, expected 5", words.len()).into());
+    }
+
+    let answer = decode_uint(words[1])?;
+    if answer == 0 || answer > i128::MAX as u128 {
+        return Err(format!("Aggregator answer 0x{} is not a positive price", words[1]).into());
+    }
+    let answer = Decimal::try_from_i128_with_scale(answer as i128, decimals)
+        .map_err(|e| format!("Aggregator answer out of range: {}", e))?;
+
+    let updated_at = decode_uint(words[3])?;
+    let updated_at = Utc.timestamp_opt(updated_at as i64, 0)
+        .single()
+        .ok_or_else(|| format!("Invalid updatedAt timestamp {}", updated_at))?;
+
+    Ok(ChainlinkRound {
+        round_id: decode_uint(words[0])?,
+        answer,
+        updated_at,
+    })
+}
+
+fn abi_words(result: &str) -> Result<Vec<&str>, Box<dyn std::error::Error + Send + Sync>> {
+    let hex = result.strip_prefix("0x").unwrap_or(result);
+    if !hex.len().is_multiple_of(64) || !hex.is_ascii() {
+        return Err(format!("Malformed ABI response of {} hex characters", hex.len()).into());
+    }
+    Ok((0..hex.len()).step_by(64).map(|start| &hex[start..start + 64]).collect())
+}
+
+/// Decode a 32-byte word whose value must fit in 128 bits
+fn decode_uint(word: &str) -> Result<u128, Box<dyn std::error::Error + Send + Sync>> {
+    let (high, low) = word.split_at(32);
+    if high.chars().any(|c| c != '0') {
+        return Err(format!("ABI word 0x{} exceeds 128 bits", word).into());
+    }
+    Ok(u128::from_str_radix(low, 16)?)
+}
+
+#[async_trait]
+impl PriceFeedProvider for ChainlinkPriceFeed {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let configured: Vec<TokenAddress> = token_addresses.iter()
+            .filter(|token| self.aggregators.contains_key(*token))
+            .cloned()
+            .collect();
+
+        let mut prices = HashMap::new();
+        for (token_address, response) in self.fetch_prices(&configured).await {
+            match response {
+                Ok(price) => {
+                    prices.insert(token_address, price);
+                }
+                Err(e) => warn!("Leaving {} out of Chainlink prices: {}", token_address, e),
+            }
+        }
+        Ok(prices)
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        let aggregator = self.aggregators.get(token_address)
+            .ok_or_else(|| format!("No Chainlink aggregator configured for {}", token_address))?;
+        let round = self.latest_round(aggregator).await?;
+
+        Ok(PriceData {
+            token_address: token_address.clone(),
+            price_usd: round.answer,
+            timestamp: round.updated_at,
+            source: format!("chainlink:{}", aggregator),
+            confidence: staleness_confidence(round.updated_at, Utc::now(), self.heartbeat),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn word(value: u128) -> String {
+        format!("{:064x}", value)
+    }
+
+    #[test]
+    fn test_decode_latest_round_data_scales_by_decimals() {
+        // roundId, answer = 2000.12345678 at 8 decimals, startedAt, updatedAt, answeredInRound
+        let result = format!(
+            "0x{}{}{}{}{}",
+            word(110_680_464_442_257_320_247), word(200_012_345_678), word(1_700_000_000), word(1_700_000_012), word(110_680_464_442_257_320_247)
+        );
+
+        let round = decode_latest_round_data(&result, 8).unwrap();
+        assert_eq!(round.round_id, 110_680_464_442_257_320_247);
+        assert_eq!(round.answer, Decimal::new(200_012_345_678, 8));
+        assert_eq!(round.updated_at, Utc.timestamp_opt(1_700_000_012, 0).unwrap());
+    }
+
+    #[test]
+    fn test_decode_rejects_negative_and_truncated_answers() {
+        let negative = format!("0x{}{}{}{}{}", word(1), "f".repeat(64), word(0), word(0), word(1));
+        assert!(decode_latest_round_data(&negative, 8).is_err());
+
+        let truncated = format!("0x{}{}", word(1), word(2000));
+        assert!(decode_latest_round_data(&truncated, 8).is_err());
+    }
+
+    /// JSON-RPC endpoint answering `eth_call`s from `results`, keyed by (to, data); any
+    /// other call gets a JSON-RPC error
+    async fn serve_eth_calls(results: HashMap<(String, String), String>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                // Read the headers, then the body they announce
+                let body = loop {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                        let length: usize = headers.lines()
+                            .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse().unwrap()))
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break body.to_string();
+                        }
+                    }
+                };
+
+                let call: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let key = (call["params"][0]["to"].as_str().unwrap().to_string(), call["params"][0]["data"].as_str().unwrap().to_string());
+                let response = match results.get(&key) {
+                    Some(result) => serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result }),
+                    None => serde_json::json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "execution reverted" } }),
+                }.to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(), response
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_one_failing_aggregator_leaves_the_others_priced() {
+        let updated_at = Utc::now().timestamp() as u128;
+        let eth_round = format!("0x{}{}{}{}{}", word(1), word(200_000_000_000), word(updated_at), word(updated_at), word(1));
+        let rpc_url = serve_eth_calls(HashMap::from([
+            (("0xeth_feed".to_string(), DECIMALS.to_string()), format!("0x{}", word(8))),
+            (("0xeth_feed".to_string(), LATEST_ROUND_DATA.to_string()), eth_round),
+            // BTC's aggregator answers decimals() but reverts on latestRoundData()
+            (("0xbtc_feed".to_string(), DECIMALS.to_string()), format!("0x{}", word(8))),
+        ])).await;
+        let feed = ChainlinkPriceFeed::new(&rpc_url, HashMap::from([
+            ("ETH".to_string(), "0xeth_feed".to_string()),
+            ("BTC".to_string(), "0xbtc_feed".to_string()),
+        ])).unwrap();
+        let tokens = vec!["ETH".to_string(), "BTC".to_string(), "DOGE".to_string()];
+
+        let results = feed.fetch_prices(&tokens).await;
+        assert_eq!(results["ETH"].as_ref().unwrap().price_usd, Decimal::from(2000));
+        assert!(results["BTC"].as_ref().unwrap_err().to_string().contains("execution reverted"));
+        assert!(results["DOGE"].as_ref().unwrap_err().to_string().contains("No Chainlink aggregator"));
+
+        let prices = feed.get_prices(&tokens).await.unwrap();
+        assert_eq!(prices.keys().collect::<Vec<_>>(), vec!["ETH"]);
+    }
+
+    #[test]
+    fn test_confidence_decays_with_staleness() {
+        let now = Utc::now();
+        let heartbeat = std::time::Duration::from_secs(3600);
+
+        assert_eq!(staleness_confidence(now, now, heartbeat), Decimal::ONE);
+        assert_eq!(staleness_confidence(now - chrono::Duration::minutes(15), now, heartbeat), Decimal::new(75, 2));
+        assert_eq!(staleness_confidence(now - chrono::Duration::hours(2), now, heartbeat), Decimal::ZERO);
+    }
+}
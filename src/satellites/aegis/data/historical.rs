@@ -0,0 +1,242 @@
+use crate::risk::HistoricalDataProvider;
+use crate::simulation::HistoricalPricePoint;
+use crate::types::{AssetPrice, TokenAddress};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Daily token prices read from `timestamp,token,price` CSV rows.
+///
+/// Timestamps may be RFC 3339 or plain `YYYY-MM-DD` dates; prices are bucketed by UTC
+/// day, keeping the last row of each day. Days without a row are linearly interpolated
+/// between their neighbours, but a window reaching past the first or last row for a
+/// token is an error rather than an extrapolation.
+#[derive(Debug, Clone)]
+pub struct CsvHistoricalDataProvider {
+    prices: HashMap<TokenAddress, BTreeMap<NaiveDate, AssetPrice>>,
+    /// Last day of the windows requested through `HistoricalDataProvider`
+    as_of: Option<NaiveDate>,
+}
+
+impl CsvHistoricalDataProvider {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref();
+        let contents = tokio::fs::read_to_string(path).await
+            .map_err(|e| format!("Failed to read historical prices from {}: {}", path.display(), e))?;
+        Self::from_csv(&contents)
+    }
+
+    pub fn from_csv(contents: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut prices: HashMap<TokenAddress, BTreeMap<NaiveDate, AssetPrice>> = HashMap::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (index == 0 && line.starts_with("timestamp")) {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() != 3 {
+                return Err(format!("Line {}: expected timestamp,token,price but found {:?}", index + 1, line).into());
+            }
+            let day = parse_day(fields[0])
+                .ok_or_else(|| format!("Line {}: invalid timestamp {:?}", index + 1, fields[0]))?;
+            let price = Decimal::from_str(fields[2])
+                .map_err(|e| format!("Line {}: invalid price {:?}: {}", index + 1, fields[2], e))?;
+            if price <= Decimal::ZERO {
+                return Err(format!("Line {}: price must be positive, found {}", index + 1, price).into());
+            }
+
+            prices.entry(fields[1].to_string()).or_default().insert(day, price);
+        }
+
+        Ok(Self { prices, as_of: None })
+    }
+
+    /// End `HistoricalDataProvider` windows on `as_of` instead of the file's last day
+    pub fn with_as_of(mut self, as_of: NaiveDate) -> Self {
+        self.as_of = Some(as_of);
+        self
+    }
+
+    /// One price per day from `start` to `end` inclusive
+    pub fn daily_prices(
+        &self,
+        token_address: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<(NaiveDate, AssetPrice)>, Box<dyn std::error::Error + Send + Sync>> {
+        let series = self.prices.get(token_address)
+            .ok_or_else(|| format!("No historical prices for {}", token_address))?;
+        let (first, last) = match (series.keys().next(), series.keys().next_back()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return Err(format!("No historical prices for {}", token_address).into()),
+        };
+        if start > end || start < first || end > last {
+            return Err(format!(
+                "Historical prices for {} cover {} to {}, which does not include {} to {}",
+                token_address, first, last, start, end
+            ).into());
+        }
+
+        let mut daily = Vec::with_capacity((end - start).num_days() as usize + 1);
+        let mut day = start;
+        while day <= end {
+            daily.push((day, interpolate(series, day)));
+            day += Duration::days(1);
+        }
+        Ok(daily)
+    }
+
+    /// Daily price points for `run_backtesting`, stamped at midnight UTC
+    pub fn price_points(
+        &self,
+        token_address: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<HistoricalPricePoint>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.daily_prices(token_address, start, end)?
+            .into_iter()
+            .map(|(day, price)| HistoricalPricePoint {
+                timestamp: day.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                price: price.to_f64().unwrap_or(0.0),
+                volume: 0.0,
+                market_cap: None,
+            })
+            .collect())
+    }
+
+    fn last_day(&self) -> Option<NaiveDate> {
+        self.prices.values().filter_map(|series| series.keys().next_back()).max().copied()
+    }
+}
+
+fn parse_day(timestamp: &str) -> Option<NaiveDate> {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(timestamp) => Some(timestamp.with_timezone(&Utc).date_naive()),
+        Err(_) => NaiveDate::parse_from_str(timestamp, "%Y-%m-%d").ok(),
+    }
+}
+
+/// Price on `day`, interpolated linearly between the nearest days with data. `day` must
+/// lie within the series.
+fn interpolate(series: &BTreeMap<NaiveDate, AssetPrice>, day: NaiveDate) -> AssetPrice {
+    let (before_day, before) = match series.range(..=day).next_back() {
+        Some((before_day, before)) => (*before_day, *before),
+        None => return Decimal::ZERO,
+    };
+    if before_day == day {
+        return before;
+    }
+    match series.range(day..).next() {
+        Some((after_day, after)) => {
+            let elapsed = Decimal::from((day - before_day).num_days());
+            let span = Decimal::from((*after_day - before_day).num_days());
+            before + (*after - before) * elapsed / span
+        }
+        None => before,
+    }
+}
+
+#[async_trait]
+impl HistoricalDataProvider for CsvHistoricalDataProvider {
+    /// Daily prices for the `days` days ending on the `as_of` day, oldest first
+    async fn get_historical_prices(&self, token_address: &TokenAddress, days: u32) -> Result<Vec<AssetPrice>, Box<dyn std::error::Error + Send + Sync>> {
+        let end = self.as_of
+            .or_else(|| self.last_day())
+            .ok_or("Historical price file has no rows")?;
+        let start = end - Duration::days(days.saturating_sub(1) as i64);
+        Ok(self.daily_prices(token_address, start, end)?
+            .into_iter()
+            .map(|(_, price)| price)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationPosition, StressTestingConfig, StressTestingFramework};
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/historical_prices.csv");
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_missing_days_are_interpolated() {
+        let provider = CsvHistoricalDataProvider::open(FIXTURE).await.unwrap();
+
+        let eth = provider.daily_prices("ETH", day(3), day(5)).unwrap();
+        assert_eq!(eth, vec![
+            (day(3), Decimal::from(3300)),
+            (day(4), Decimal::from(3200)),
+            (day(5), Decimal::from(3100)),
+        ]);
+
+        // USDC has rows three days apart: 1.00 -> 0.97 -> 1.00
+        let usdc: Vec<AssetPrice> = provider.get_historical_prices(&"USDC".to_string(), 7).await.unwrap();
+        assert_eq!(usdc, vec![
+            Decimal::new(100, 2), Decimal::new(99, 2), Decimal::new(98, 2), Decimal::new(97, 2),
+            Decimal::new(98, 2), Decimal::new(99, 2), Decimal::new(100, 2),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_uncovered_range_is_an_error() {
+        let provider = CsvHistoricalDataProvider::open(FIXTURE).await.unwrap();
+
+        let error = provider.get_historical_prices(&"ETH".to_string(), 30).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Historical prices for ETH cover 2024-03-01 to 2024-03-07, which does not include 2024-02-07 to 2024-03-07"
+        );
+
+        let later = provider.clone().with_as_of(day(10));
+        assert!(later.get_historical_prices(&"ETH".to_string(), 3).await.is_err());
+        assert!(provider.daily_prices("BTC", day(1), day(2)).is_err());
+    }
+
+    #[test]
+    fn test_malformed_rows_are_rejected() {
+        assert!(CsvHistoricalDataProvider::from_csv("timestamp,token,price\n2024-03-01,ETH").is_err());
+        assert!(CsvHistoricalDataProvider::from_csv("2024-13-01,ETH,3400").is_err());
+        assert!(CsvHistoricalDataProvider::from_csv("2024-03-01,ETH,-1").is_err());
+
+        let rfc3339 = CsvHistoricalDataProvider::from_csv("2024-03-01T23:30:00Z,ETH,3400\n2024-03-02T00:15:00+02:00,ETH,3500").unwrap();
+        // The second row is still 1 March in UTC and replaces the first
+        assert_eq!(rfc3339.daily_prices("ETH", day(1), day(1)).unwrap(), vec![(day(1), Decimal::from(3500))]);
+    }
+
+    #[tokio::test]
+    async fn test_backtest_replays_csv_prices() {
+        let provider = CsvHistoricalDataProvider::open(FIXTURE).await.unwrap();
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        framework.set_historical_prices("ETH", provider.price_points("ETH", day(1), day(7)).unwrap()).await;
+
+        let positions = vec![SimulationPosition {
+            token_address: "ETH".to_string(),
+            quantity: 10.0,
+            entry_price: 3400.0,
+            current_price: 3400.0,
+            collateral_value: 34000.0,
+            debt_value: 20000.0,
+            liquidation_threshold: 0.8,
+            health_factor: 1.7,
+            debt_tokens: HashMap::new(),
+        }];
+        let start = day(1).and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let end = day(7).and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+        let result = framework.run_backtesting(&positions, start, end).await.unwrap();
+        assert_eq!(result.initial_portfolio_value, 14000.0);
+        assert_eq!(result.final_portfolio_value, 11500.0);
+        // Peak equity 14500 on 2 March falls to 10000 on 6 March
+        assert!((result.risk_metrics.max_drawdown - 4500.0 / 14500.0).abs() < 1e-12);
+    }
+}
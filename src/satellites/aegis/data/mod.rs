@@ -1,3 +1,15 @@
+pub mod historical;
 pub mod price_feed_integration;
+pub mod volatility;
+#[cfg(feature = "chainlink")]
+pub mod chainlink;
+#[cfg(feature = "redis")]
+pub mod redis_cache;
 
-pub use price_feed_integration::*; 
\ No newline at end of file
+pub use historical::*;
+pub use price_feed_integration::*;
+pub use volatility::*;
+#[cfg(feature = "chainlink")]
+pub use chainlink::*;
+#[cfg(feature = "redis")]
+pub use redis_cache::*;
@@ -0,0 +1,235 @@
+use crate::liquidation::PriceFeedProvider;
+use crate::types::{PriceData, TokenAddress};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// Caches another feed's prices in Redis, so repeated lookups within a token's TTL don't
+/// reach a rate-limited upstream.
+///
+/// Entries are keyed by `{prefix}:{source}:{token}`, letting feeds from different sources
+/// share one Redis without serving each other's prices. Redis expires entries itself.
+/// Cached prices keep their original timestamps, so staleness checks still see their age.
+/// An unreachable cache is logged and bypassed rather than failing the lookup.
+pub struct RedisPriceCache {
+    inner: Arc<dyn PriceFeedProvider>,
+    connection: ConnectionManager,
+    source: String,
+    key_prefix: String,
+    default_ttl: Duration,
+    token_ttls: HashMap<TokenAddress, Duration>,
+}
+
+impl RedisPriceCache {
+    /// Connect to `redis_url` and cache prices from `inner`, which `source` names in cache keys
+    pub async fn connect(
+        redis_url: &str,
+        inner: Arc<dyn PriceFeedProvider>,
+        source: impl Into<String>,
+        default_ttl: Duration,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self::new(connection, inner, source, default_ttl))
+    }
+
+    pub fn new(
+        connection: ConnectionManager,
+        inner: Arc<dyn PriceFeedProvider>,
+        source: impl Into<String>,
+        default_ttl: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            connection,
+            source: source.into(),
+            key_prefix: "aegis:price".to_string(),
+            default_ttl,
+            token_ttls: HashMap::new(),
+        }
+    }
+
+    pub fn with_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefix = prefix.into();
+        self
+    }
+
+    /// Cache `token_address` for `ttl` instead of the default, e.g. longer for stablecoins
+    pub fn with_token_ttl(mut self, token_address: impl Into<TokenAddress>, ttl: Duration) -> Self {
+        self.token_ttls.insert(token_address.into(), ttl);
+        self
+    }
+
+    pub fn ttl_for(&self, token_address: &TokenAddress) -> Duration {
+        self.token_ttls.get(token_address).copied().unwrap_or(self.default_ttl)
+    }
+
+    fn cache_key(&self, token_address: &TokenAddress) -> String {
+        format!("{}:{}:{}", self.key_prefix, self.source, token_address)
+    }
+
+    /// Cached prices for whichever of `token_addresses` are still within their TTL
+    async fn cached(&self, token_addresses: &[TokenAddress]) -> HashMap<TokenAddress, PriceData> {
+        if token_addresses.is_empty() {
+            return HashMap::new();
+        }
+
+        let keys: Vec<String> = token_addresses.iter().map(|token| self.cache_key(token)).collect();
+        let mut connection = self.connection.clone();
+        let values: Vec<Option<String>> = match redis::cmd("MGET").arg(&keys).query_async(&mut connection).await {
+            Ok(values) => values,
+            Err(e) => {
+                warn!("Price cache read failed, fetching from {}: {}", self.source, e);
+                return HashMap::new();
+            }
+        };
+
+        token_addresses.iter()
+            .zip(values)
+            .filter_map(|(token_address, value)| {
+                let price = serde_json::from_str::<PriceData>(&value?)
+                    .map_err(|e| warn!("Discarding unreadable cached price for {}: {}", token_address, e))
+                    .ok()?;
+                Some((token_address.clone(), price))
+            })
+            .collect()
+    }
+
+    async fn store(&self, prices: &HashMap<TokenAddress, PriceData>) {
+        if prices.is_empty() {
+            return;
+        }
+
+        let mut pipeline = redis::pipe();
+        for (token_address, price) in prices {
+            let value = match serde_json::to_string(price) {
+                Ok(value) => value,
+                Err(e) => {
+                    warn!("Not caching price for {}: {}", token_address, e);
+                    continue;
+                }
+            };
+            let ttl_ms = self.ttl_for(token_address).as_millis().max(1) as u64;
+            pipeline.cmd("SET").arg(self.cache_key(token_address)).arg(value).arg("PX").arg(ttl_ms).ignore();
+        }
+
+        let mut connection = self.connection.clone();
+        if let Err(e) = pipeline.query_async::<_, ()>(&mut connection).await {
+            warn!("Price cache write failed for {} prices: {}", self.source, e);
+        }
+    }
+}
+
+#[async_trait]
+impl PriceFeedProvider for RedisPriceCache {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut prices = self.cached(token_addresses).await;
+        let misses: Vec<TokenAddress> = token_addresses.iter()
+            .filter(|token| !prices.contains_key(*token))
+            .cloned()
+            .collect();
+        if misses.is_empty() {
+            return Ok(prices);
+        }
+
+        let fetched = self.inner.get_prices(&misses).await?;
+        self.store(&fetched).await;
+        prices.extend(fetched);
+        Ok(prices)
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(price) = self.cached(std::slice::from_ref(token_address)).await.remove(token_address) {
+            return Ok(price);
+        }
+
+        let price = self.inner.get_price(token_address).await?;
+        self.store(&HashMap::from([(token_address.clone(), price.clone())])).await;
+        Ok(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Quotes ETH at $2000 plus one dollar per upstream call
+    struct CountingFeed {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl PriceFeedProvider for CountingFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd: Decimal::from(2000 + call),
+                timestamp: Utc::now(),
+                source: "counting".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    /// Cache on the server at `AEGIS_TEST_REDIS_URL`; run these with `--ignored` when one is up
+    async fn test_cache(inner: Arc<CountingFeed>, ttl: Duration) -> RedisPriceCache {
+        let url = std::env::var("AEGIS_TEST_REDIS_URL").expect("AEGIS_TEST_REDIS_URL must point at a Redis server");
+        // A fresh prefix per test keeps runs from reading each other's entries
+        RedisPriceCache::connect(&url, inner, "counting", ttl).await.unwrap()
+            .with_key_prefix(format!("aegis-test:{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    #[ignore = "needs AEGIS_TEST_REDIS_URL"]
+    async fn test_miss_then_hit_serves_cached_price() {
+        let inner = Arc::new(CountingFeed { calls: AtomicU32::new(0) });
+        let cache = test_cache(inner.clone(), Duration::from_secs(60)).await;
+        let eth = "ETH".to_string();
+
+        let first = cache.get_price(&eth).await.unwrap();
+        assert_eq!(first.price_usd, Decimal::from(2001));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        let second = cache.get_price(&eth).await.unwrap();
+        assert_eq!(second.price_usd, Decimal::from(2001));
+        assert_eq!(second.timestamp, first.timestamp);
+
+        // Only the uncached token reaches the upstream feed
+        let prices = cache.get_prices(&[eth.clone(), "WBTC".to_string()]).await.unwrap();
+        assert_eq!(prices[&eth].price_usd, Decimal::from(2001));
+        assert_eq!(prices["WBTC"].price_usd, Decimal::from(2002));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs AEGIS_TEST_REDIS_URL"]
+    async fn test_entries_expire_after_token_ttl() {
+        let inner = Arc::new(CountingFeed { calls: AtomicU32::new(0) });
+        let cache = test_cache(inner.clone(), Duration::from_millis(200)).await
+            .with_token_ttl("USDC", Duration::from_secs(60));
+        let tokens = ["ETH".to_string(), "USDC".to_string()];
+
+        cache.get_prices(&tokens).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        cache.get_prices(&tokens).await.unwrap();
+        // ETH expired and was refetched; USDC's longer TTL kept it cached
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+}
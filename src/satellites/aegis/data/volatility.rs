@@ -0,0 +1,153 @@
+use crate::types::{PriceData, TokenAddress};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+/// Default EWMA decay factor, the RiskMetrics value for daily returns
+pub const DEFAULT_VOLATILITY_DECAY: f64 = 0.94;
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+struct TokenVolatility {
+    last_price: Decimal,
+    last_timestamp: DateTime<Utc>,
+    /// EWMA of squared returns; `None` until a second price arrives
+    variance: Option<f64>,
+    /// EWMA of the time between prices, used to annualize
+    mean_interval_secs: Option<f64>,
+}
+
+/// Realized volatility per token, as an exponentially weighted moving average of squared
+/// price returns.
+///
+/// Each new price updates `variance = decay * variance + (1 - decay) * return²`, seeded
+/// with the first return. Prices stamped at or before the last one recorded for a token
+/// are ignored, so re-reading the same quote doesn't dilute the estimate with zero returns.
+pub struct VolatilityTracker {
+    decay: f64,
+    tokens: DashMap<TokenAddress, TokenVolatility>,
+}
+
+impl Default for VolatilityTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_VOLATILITY_DECAY)
+    }
+}
+
+impl VolatilityTracker {
+    /// `decay` is the weight kept by the previous estimate on each update, within (0, 1)
+    pub fn new(decay: f64) -> Self {
+        Self {
+            decay: if decay > 0.0 && decay < 1.0 { decay } else { DEFAULT_VOLATILITY_DECAY },
+            tokens: DashMap::new(),
+        }
+    }
+
+    pub fn decay(&self) -> f64 {
+        self.decay
+    }
+
+    pub fn record(&self, price: &PriceData) {
+        if price.price_usd <= Decimal::ZERO {
+            return;
+        }
+
+        let mut state = match self.tokens.get_mut(&price.token_address) {
+            Some(state) => state,
+            None => {
+                self.tokens.insert(price.token_address.clone(), TokenVolatility {
+                    last_price: price.price_usd,
+                    last_timestamp: price.timestamp,
+                    variance: None,
+                    mean_interval_secs: None,
+                });
+                return;
+            }
+        };
+        if price.timestamp <= state.last_timestamp {
+            return;
+        }
+
+        let period_return = ((price.price_usd - state.last_price) / state.last_price).to_f64().unwrap_or(0.0);
+        let interval_secs = (price.timestamp - state.last_timestamp).num_milliseconds() as f64 / 1000.0;
+        let ewma = |previous: Option<f64>, sample: f64| match previous {
+            Some(previous) => self.decay * previous + (1.0 - self.decay) * sample,
+            None => sample,
+        };
+
+        state.variance = Some(ewma(state.variance, period_return * period_return));
+        state.mean_interval_secs = Some(ewma(state.mean_interval_secs, interval_secs));
+        state.last_price = price.price_usd;
+        state.last_timestamp = price.timestamp;
+    }
+
+    pub fn record_all<'a>(&self, prices: impl IntoIterator<Item = &'a PriceData>) {
+        for price in prices {
+            self.record(price);
+        }
+    }
+
+    /// Standard deviation of returns between consecutive prices of `token_address`, as a
+    /// fraction. `None` until two prices have been recorded.
+    pub fn get_volatility(&self, token_address: &TokenAddress) -> Option<Decimal> {
+        let variance = self.tokens.get(token_address)?.variance?;
+        Decimal::from_f64(variance.sqrt())
+    }
+
+    /// `get_volatility` scaled to a year from the average time between prices
+    pub fn annualized_volatility(&self, token_address: &TokenAddress) -> Option<Decimal> {
+        let state = self.tokens.get(token_address)?;
+        let (variance, interval_secs) = (state.variance?, state.mean_interval_secs?);
+        if interval_secs <= 0.0 {
+            return None;
+        }
+        Decimal::from_f64((variance * SECONDS_PER_YEAR / interval_secs).sqrt())
+    }
+
+    pub fn tracked_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(token: &str, day: i64, price_usd: Decimal) -> PriceData {
+        PriceData {
+            token_address: token.to_string(),
+            price_usd,
+            timestamp: DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap().with_timezone(&Utc)
+                + chrono::Duration::days(day),
+            source: "test".to_string(),
+            confidence: Decimal::ONE,
+        }
+    }
+
+    #[test]
+    fn test_ewma_matches_hand_calculation() {
+        let tracker = VolatilityTracker::new(0.94);
+        let eth = "ETH".to_string();
+
+        // Daily returns of +2%, -2% and +5%
+        tracker.record(&price("ETH", 0, Decimal::from(100)));
+        assert_eq!(tracker.get_volatility(&eth), None);
+        tracker.record(&price("ETH", 1, Decimal::from(102)));
+        tracker.record(&price("ETH", 2, Decimal::new(9996, 2)));
+        // A repeated quote is not a new return
+        tracker.record(&price("ETH", 2, Decimal::new(9996, 2)));
+        tracker.record(&price("ETH", 3, Decimal::new(104958, 3)));
+
+        // 0.02² = 0.0004 seeds the average; 0.94 * 0.0004 + 0.06 * 0.0004 = 0.0004;
+        // 0.94 * 0.0004 + 0.06 * 0.05² = 0.000526
+        let expected = 0.000526f64.sqrt();
+        let volatility = tracker.get_volatility(&eth).unwrap().to_f64().unwrap();
+        assert!((volatility - expected).abs() < 1e-9, "{} != {}", volatility, expected);
+
+        // One price a day annualizes by sqrt(365.25)
+        let annualized = tracker.annualized_volatility(&eth).unwrap().to_f64().unwrap();
+        assert!((annualized - expected * 365.25f64.sqrt()).abs() < 1e-9, "{}", annualized);
+        assert_eq!(tracker.get_volatility(&"BTC".to_string()), None);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod service;
+
+pub use service::*;
+
+/// Types generated from `proto/aegis.proto`
+pub mod proto {
+    tonic::include_proto!("aegis.v1");
+}
@@ -0,0 +1,330 @@
+// Handlers answer with tonic's `Status`, so the conversions feeding them return it unboxed
+#![allow(clippy::result_large_err)]
+
+use crate::grpc::proto;
+use crate::grpc::proto::aegis_service_server::{AegisService, AegisServiceServer};
+use crate::types::{CalculationError, HealthFactor, Position, PositionError, PositionId, PositionToken, RiskAlert, RiskParameters, TokenAddress};
+use crate::AegisSatellite;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+/// gRPC front end over an `AegisSatellite`, for the inter-satellite integration
+pub struct AegisGrpcService {
+    satellite: Arc<AegisSatellite>,
+}
+
+impl AegisGrpcService {
+    pub fn new(satellite: Arc<AegisSatellite>) -> Self {
+        Self { satellite }
+    }
+
+    pub fn into_server(self) -> AegisServiceServer<Self> {
+        AegisServiceServer::new(self)
+    }
+}
+
+/// Serve the gRPC API on `address` until the task is cancelled
+pub async fn serve(satellite: Arc<AegisSatellite>, address: SocketAddr) -> Result<(), tonic::transport::Error> {
+    info!("Serving Aegis gRPC API on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(AegisGrpcService::new(satellite).into_server())
+        .serve(address)
+        .await
+}
+
+#[tonic::async_trait]
+impl AegisService for AegisGrpcService {
+    type WatchAlertsStream = Pin<Box<dyn Stream<Item = Result<proto::RiskAlert, Status>> + Send>>;
+
+    async fn add_position(&self, request: Request<proto::Position>) -> Result<Response<proto::PositionIdResponse>, Status> {
+        let position = position_from_proto(request.into_inner())?;
+        let id = self.satellite.add_position(position).await.map_err(position_status)?;
+        Ok(Response::new(proto::PositionIdResponse { id: id.to_string() }))
+    }
+
+    async fn get_position(&self, request: Request<proto::PositionIdRequest>) -> Result<Response<proto::Position>, Status> {
+        let id = parse_id(&request.into_inner().id)?;
+        match self.satellite.get_position(id) {
+            Some(position) => Ok(Response::new(position_to_proto(&position))),
+            None => Err(position_status(PositionError::NotFound { id })),
+        }
+    }
+
+    async fn update_position(&self, request: Request<proto::Position>) -> Result<Response<proto::UpdatePositionResponse>, Status> {
+        let position = position_from_proto(request.into_inner())?;
+        self.satellite.update_position(position).await.map_err(position_status)?;
+        Ok(Response::new(proto::UpdatePositionResponse {}))
+    }
+
+    async fn remove_position(&self, request: Request<proto::PositionIdRequest>) -> Result<Response<proto::Position>, Status> {
+        let id = parse_id(&request.into_inner().id)?;
+        let position = self.satellite.remove_position(id).await.map_err(position_status)?;
+        Ok(Response::new(position_to_proto(&position)))
+    }
+
+    async fn get_position_health(&self, request: Request<proto::PositionIdRequest>) -> Result<Response<proto::HealthFactor>, Status> {
+        let id = parse_id(&request.into_inner().id)?;
+        // The monitor reports unknown ids as a failed calculation; surface them as NOT_FOUND
+        if self.satellite.get_position(id).is_none() {
+            return Err(position_status(PositionError::NotFound { id }));
+        }
+        let health = self.satellite.get_position_health(id).await.map_err(calculation_status)?;
+        Ok(Response::new(health_to_proto(&health)))
+    }
+
+    async fn watch_alerts(&self, request: Request<proto::WatchAlertsRequest>) -> Result<Response<Self::WatchAlertsStream>, Status> {
+        let position_filter = match request.into_inner().position_id {
+            Some(id) => Some(parse_id(&id)?),
+            None => None,
+        };
+
+        let alerts = BroadcastStream::new(self.satellite.subscribe_alerts()).filter_map(move |alert| match alert {
+            Ok(alert) => match position_filter {
+                Some(id) if alert.position_id != id => None,
+                _ => Some(Ok(alert_to_proto(&alert))),
+            },
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!("Alert watcher fell behind and skipped {} alerts", skipped);
+                None
+            }
+        });
+        Ok(Response::new(Box::pin(alerts)))
+    }
+}
+
+fn position_status(error: PositionError) -> Status {
+    match &error {
+        PositionError::NotFound { .. } => Status::not_found(error.to_string()),
+        PositionError::AlreadyExists { .. } => Status::already_exists(error.to_string()),
+        PositionError::Invalid { .. } => Status::invalid_argument(error.to_string()),
+        PositionError::Persistence { .. } => Status::internal(error.to_string()),
+    }
+}
+
+fn calculation_status(error: CalculationError) -> Status {
+    match &error {
+        // Price problems are usually transient, so callers should retry
+        CalculationError::MissingPriceData { .. }
+        | CalculationError::StalePriceData { .. }
+        | CalculationError::PriceCircuitOpen { .. } => Status::unavailable(error.to_string()),
+        CalculationError::InvalidPosition { .. } | CalculationError::UnsupportedProtocol { .. } => Status::failed_precondition(error.to_string()),
+        CalculationError::CalculationFailed { .. } => Status::internal(error.to_string()),
+    }
+}
+
+fn parse_id(id: &str) -> Result<PositionId, Status> {
+    PositionId::parse_str(id).map_err(|e| Status::invalid_argument(format!("Invalid id '{}': {}", id, e)))
+}
+
+fn parse_decimal(field: &str, value: &str) -> Result<Decimal, Status> {
+    Decimal::from_str(value).map_err(|e| Status::invalid_argument(format!("Invalid {} '{}': {}", field, value, e)))
+}
+
+fn parse_timestamp(field: &str, millis: i64) -> Result<DateTime<Utc>, Status> {
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| Status::invalid_argument(format!("Invalid {} {}", field, millis)))
+}
+
+fn tokens_from_proto(tokens: Vec<proto::PositionToken>) -> Result<HashMap<TokenAddress, PositionToken>, Status> {
+    tokens.into_iter()
+        .map(|token| {
+            let entry_price_usd = match &token.entry_price_usd {
+                Some(price) => Some(parse_decimal("entry_price_usd", price)?),
+                None => None,
+            };
+            Ok((token.token_address.clone(), PositionToken {
+                amount: parse_decimal("amount", &token.amount)?,
+                value_usd: parse_decimal("value_usd", &token.value_usd)?,
+                price_per_token: parse_decimal("price_per_token", &token.price_per_token)?,
+                entry_price_usd,
+                token_address: token.token_address,
+            }))
+        })
+        .collect()
+}
+
+/// Tokens ordered by address, so the same position always encodes the same way
+fn tokens_to_proto(tokens: &HashMap<TokenAddress, PositionToken>) -> Vec<proto::PositionToken> {
+    let mut tokens: Vec<proto::PositionToken> = tokens.values()
+        .map(|token| proto::PositionToken {
+            token_address: token.token_address.clone(),
+            amount: token.amount.to_string(),
+            value_usd: token.value_usd.to_string(),
+            price_per_token: token.price_per_token.to_string(),
+            entry_price_usd: token.entry_price_usd.map(|price| price.to_string()),
+        })
+        .collect();
+    tokens.sort_by(|a, b| a.token_address.cmp(&b.token_address));
+    tokens
+}
+
+fn risk_parameters_from_proto(parameters: proto::RiskParameters) -> Result<RiskParameters, Status> {
+    Ok(RiskParameters {
+        safe_health_threshold: parse_decimal("safe_health_threshold", &parameters.safe_health_threshold)?,
+        warning_health_threshold: parse_decimal("warning_health_threshold", &parameters.warning_health_threshold)?,
+        critical_health_threshold: parse_decimal("critical_health_threshold", &parameters.critical_health_threshold)?,
+        emergency_health_threshold: parse_decimal("emergency_health_threshold", &parameters.emergency_health_threshold)?,
+        max_position_size_usd: parse_decimal("max_position_size_usd", &parameters.max_position_size_usd)?,
+        max_protocol_exposure_percent: parse_decimal("max_protocol_exposure_percent", &parameters.max_protocol_exposure_percent)?,
+        max_price_age: Duration::from_millis(parameters.max_price_age_ms),
+    })
+}
+
+fn risk_parameters_to_proto(parameters: &RiskParameters) -> proto::RiskParameters {
+    proto::RiskParameters {
+        safe_health_threshold: parameters.safe_health_threshold.to_string(),
+        warning_health_threshold: parameters.warning_health_threshold.to_string(),
+        critical_health_threshold: parameters.critical_health_threshold.to_string(),
+        emergency_health_threshold: parameters.emergency_health_threshold.to_string(),
+        max_position_size_usd: parameters.max_position_size_usd.to_string(),
+        max_protocol_exposure_percent: parameters.max_protocol_exposure_percent.to_string(),
+        max_price_age_ms: u64::try_from(parameters.max_price_age.as_millis()).unwrap_or(u64::MAX),
+    }
+}
+
+pub fn position_from_proto(position: proto::Position) -> Result<Position, Status> {
+    let risk_overrides = match position.risk_overrides {
+        Some(parameters) => Some(risk_parameters_from_proto(parameters)?),
+        None => None,
+    };
+    Ok(Position {
+        id: parse_id(&position.id)?,
+        protocol: position.protocol,
+        collateral_tokens: tokens_from_proto(position.collateral_tokens)?,
+        debt_tokens: tokens_from_proto(position.debt_tokens)?,
+        created_at: parse_timestamp("created_at_ms", position.created_at_ms)?,
+        updated_at: parse_timestamp("updated_at_ms", position.updated_at_ms)?,
+        risk_overrides,
+    })
+}
+
+pub fn position_to_proto(position: &Position) -> proto::Position {
+    proto::Position {
+        id: position.id.to_string(),
+        protocol: position.protocol.clone(),
+        collateral_tokens: tokens_to_proto(&position.collateral_tokens),
+        debt_tokens: tokens_to_proto(&position.debt_tokens),
+        created_at_ms: position.created_at.timestamp_millis(),
+        updated_at_ms: position.updated_at.timestamp_millis(),
+        risk_overrides: position.risk_overrides.as_ref().map(risk_parameters_to_proto),
+    }
+}
+
+pub fn health_to_proto(health: &HealthFactor) -> proto::HealthFactor {
+    proto::HealthFactor {
+        value: health.value.to_string(),
+        liquidation_threshold: health.liquidation_threshold.to_string(),
+        collateral_value: health.collateral_value.to_string(),
+        debt_value: health.debt_value.to_string(),
+        calculated_at_ms: health.calculated_at.timestamp_millis(),
+    }
+}
+
+pub fn alert_to_proto(alert: &RiskAlert) -> proto::RiskAlert {
+    proto::RiskAlert {
+        id: alert.id.to_string(),
+        position_id: alert.position_id.to_string(),
+        alert_type: format!("{:?}", alert.alert_type),
+        risk_level: format!("{:?}", alert.risk_level),
+        health_factor: Some(health_to_proto(&alert.health_factor)),
+        message: alert.message.clone(),
+        created_at_ms: alert.created_at.timestamp_millis(),
+        protocol: alert.protocol.clone(),
+        related_tokens: alert.related_tokens.clone(),
+        projected_seconds_to_liquidation: alert.projected_seconds_to_liquidation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::proto::aegis_service_client::AegisServiceClient;
+    use crate::test_support::{StaticPriceFeed, UnusedExecutor};
+    use tonic::Code;
+
+    fn position(eth: i64, usdc_debt: i64) -> proto::Position {
+        let token = |address: &str, amount: i64| proto::PositionToken {
+            token_address: address.to_string(),
+            amount: amount.to_string(),
+            value_usd: "0".to_string(),
+            price_per_token: "0".to_string(),
+            entry_price_usd: None,
+        };
+        let now = Utc::now().timestamp_millis();
+        proto::Position {
+            id: uuid::Uuid::new_v4().to_string(),
+            protocol: "aave".to_string(),
+            collateral_tokens: vec![token("ETH", eth)],
+            debt_tokens: vec![token("USDC", usdc_debt)],
+            created_at_ms: now,
+            updated_at_ms: now,
+            risk_overrides: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_round_trip_and_alert_stream() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(UnusedExecutor), None).await.unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(AegisGrpcService::new(Arc::new(satellite)).into_server())
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+        let mut client = AegisServiceClient::connect(format!("http://{}", address)).await.unwrap();
+
+        let safe = position(10, 1_000);
+        let id = client.add_position(safe.clone()).await.unwrap().into_inner().id;
+        assert_eq!(id, safe.id);
+        assert_eq!(client.get_position(proto::PositionIdRequest { id: id.clone() }).await.unwrap().into_inner(), safe);
+
+        let health = client.get_position_health(proto::PositionIdRequest { id: id.clone() }).await.unwrap().into_inner();
+        assert_eq!(Decimal::from_str(&health.collateral_value).unwrap(), Decimal::from(20_000));
+
+        let duplicate = client.add_position(safe.clone()).await.unwrap_err();
+        assert_eq!(duplicate.code(), Code::AlreadyExists);
+        let unknown = proto::PositionIdRequest { id: uuid::Uuid::new_v4().to_string() };
+        assert_eq!(client.get_position_health(unknown.clone()).await.unwrap_err().code(), Code::NotFound);
+        let malformed = proto::PositionIdRequest { id: "not-a-uuid".to_string() };
+        assert_eq!(client.get_position(malformed).await.unwrap_err().code(), Code::InvalidArgument);
+
+        // Health ~0.94 raises an alert as soon as the position is added
+        let at_risk = position(10, 17_000);
+        let mut alerts = client.watch_alerts(proto::WatchAlertsRequest { position_id: Some(at_risk.id.clone()) })
+            .await.unwrap().into_inner();
+        client.add_position(at_risk.clone()).await.unwrap();
+        let alert = tokio::time::timeout(Duration::from_secs(5), alerts.message())
+            .await.unwrap().unwrap().expect("alert stream ended");
+        assert_eq!(alert.position_id, at_risk.id);
+        assert_eq!(alert.alert_type, "LiquidationRisk");
+        assert_eq!(alert.protocol.as_deref(), Some("aave"));
+
+        let mut updated = safe.clone();
+        updated.debt_tokens[0].amount = "2000".to_string();
+        let overrides = RiskParameters {
+            max_position_size_usd: Decimal::from(50_000),
+            ..Default::default()
+        };
+        updated.risk_overrides = Some(risk_parameters_to_proto(&overrides));
+        client.update_position(updated.clone()).await.unwrap();
+        let stored = client.get_position(proto::PositionIdRequest { id: id.clone() }).await.unwrap().into_inner();
+        assert_eq!(stored.risk_overrides.unwrap().max_position_size_usd, "50000");
+        let removed = client.remove_position(proto::PositionIdRequest { id: id.clone() }).await.unwrap().into_inner();
+        assert_eq!(removed.debt_tokens[0].amount, "2000");
+        assert_eq!(removed.risk_overrides, updated.risk_overrides);
+        assert_eq!(client.get_position(proto::PositionIdRequest { id }).await.unwrap_err().code(), Code::NotFound);
+
+        server.abort();
+    }
+}
@@ -6,10 +6,18 @@ pub mod security;
 pub mod intelligence;
 pub mod data;
 pub mod simulation;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "http-api")]
+pub mod api;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(test)]
+pub(crate) mod test_support;
 
-use crate::liquidation::{LiquidationMonitor, PriceFeedProvider};
+use crate::liquidation::{LiquidationMonitor, PriceFeedProvider, AlertSystem};
 use crate::risk::{PriceImpactSimulator, AutomatedPositionManager, TradeExecutor};
-use crate::monitoring::EscalatingAlertSystem;
+use crate::monitoring::{EscalatingAlertSystem, AuditBundle};
 use crate::simulation::{
     StressTestingFramework, 
     StressTestingConfig, 
@@ -21,17 +29,35 @@ use crate::simulation::{
 use crate::types::*;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{info, error, warn};
+use tracing::{info, warn};
 use rust_decimal::prelude::ToPrimitive;
 
 pub struct AegisSatellite {
     liquidation_monitor: Arc<LiquidationMonitor>,
+    price_feeds: Arc<dyn PriceFeedProvider>,
     price_impact_simulator: Arc<PriceImpactSimulator>,
     alert_system: Arc<EscalatingAlertSystem>,
+    /// `alert_system` behind `PersistingAlertSystem` when a position store is configured;
+    /// raise and acknowledge alerts through this so the store sees them
+    monitored_alert_system: Arc<dyn AlertSystem>,
     position_manager: Arc<AutomatedPositionManager>,
     stress_testing_framework: Arc<StressTestingFramework>,
     visualization_framework: Arc<VisualizationFramework>,
+    correlation_analysis: Arc<risk::CorrelationAnalysisSystem>,
+    volatility_tracker: Arc<data::VolatilityTracker>,
+    position_store: Option<Arc<dyn liquidation::PositionStore>>,
     config: Arc<RwLock<AegisConfig>>,
+    /// Wakes the monitoring and memory budget loops to pick up a replaced config
+    config_changed: Arc<tokio::sync::watch::Sender<()>>,
+    /// Set when memory was shed, until usage falls below the budget's relief point
+    memory_shed: Arc<std::sync::atomic::AtomicBool>,
+    /// Set while a correlation spike has been alerted, until the regime check clears
+    correlation_spike_active: Arc<std::sync::atomic::AtomicBool>,
+    /// Scans transactions against `AegisConfig::exploit_signatures`, when configured
+    exploit_monitor: Option<security::ExploitDiscoveryMonitor>,
+    /// Exporter started by `start`, until taken with `take_metrics_server`
+    #[cfg(feature = "metrics")]
+    metrics_server: std::sync::Mutex<Option<metrics::MetricsServer>>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +68,409 @@ pub struct AegisConfig {
     pub enable_smart_contract_analysis: bool,
     pub enable_mev_protection: bool,
     pub max_concurrent_positions: usize,
+    /// Consecutive missing-price cycles before a position is quarantined; `None` never quarantines
+    pub quarantine_after_missing_prices: Option<u32>,
+    /// Random delay before monitoring starts, so instances sharing a feed don't tick together
+    pub monitoring_jitter: Option<MonitoringJitter>,
+    /// Number of slices the book is split into; one slice is checked every
+    /// `monitoring_interval_secs / monitoring_batches`, phase-offset from the others
+    pub monitoring_batches: usize,
+    /// Whether each tick recalculates its whole batch or only positions whose prices moved
+    pub monitoring_mode: MonitoringMode,
+    /// Persist health history so it survives restarts; `None` keeps it in memory only
+    pub health_history_persistence: Option<liquidation::HealthHistoryPersistence>,
+    /// Shed caches and old history when memory use approaches this budget
+    pub memory_budget: Option<monitoring::MemoryBudget>,
+    /// Health factors kept per position for `get_health_history`
+    pub health_history_capacity: usize,
+    /// Health calculations, and their price fetches, run at once by each monitoring pass
+    pub max_concurrent_health_calcs: usize,
+    /// Rolling-correlation settings for the regime-break check run with each monitoring sweep
+    pub correlation_analysis: risk::CorrelationAnalysisConfig,
+    /// Retry trade executions that failed before reaching the chain; `None`, the default,
+    /// gives up on the first failure
+    pub trade_retry: Option<RetryPolicy>,
+    /// Hold back health checks on prices that jump implausibly far within a short window,
+    /// alerting at Critical until the move is confirmed; `None`, the default, trusts every price
+    pub price_circuit_breaker: Option<liquidation::PriceCircuitBreakerConfig>,
+    /// How long completed automated trades are remembered so a repeat runs only once;
+    /// `None` turns deduplication off
+    pub trade_idempotency_ttl: Option<std::time::Duration>,
+    /// Key sealing exported audit bundles and risk policies; `None` leaves nothing to seal
+    /// them with, so they can't be exported
+    pub integrity_key: Option<monitoring::IntegrityKey>,
+    /// Exploit signatures `scan_transaction` matches against; `None` scans nothing
+    pub exploit_signatures: Option<Arc<security::ExploitSignatureDb>>,
+    /// Serve Prometheus metrics while monitoring runs; `None` leaves them in-process only
+    #[cfg(feature = "metrics")]
+    pub metrics_exporter: Option<metrics::PrometheusExporterConfig>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MonitoringJitter {
+    pub max_start_delay_ms: u64,
+    /// Fixed seed for a reproducible delay; `None` draws from entropy
+    pub seed: Option<u64>,
+}
+
+impl MonitoringJitter {
+    pub fn start_delay(&self) -> std::time::Duration {
+        use rand::{Rng, SeedableRng};
+
+        let delay_ms = match self.seed {
+            Some(seed) => rand::rngs::StdRng::seed_from_u64(seed).gen_range(0..=self.max_start_delay_ms),
+            None => rand::thread_rng().gen_range(0..=self.max_start_delay_ms),
+        };
+        std::time::Duration::from_millis(delay_ms)
+    }
+}
+
+/// How the monitoring loop decides which positions to recalculate on each tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitoringMode {
+    /// Recalculate every position in the tick's batch
+    #[default]
+    Interval,
+    /// Fetch prices once and recalculate only positions holding a token whose price moved
+    /// or went stale; batches are ignored
+    PriceChanges,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ConfigError {
+    #[error("Monitoring interval must be at least one second")]
+    InvalidMonitoringInterval,
+    #[error("Max concurrent positions must be at least 1")]
+    InvalidMaxConcurrentPositions,
+    #[error("Quarantine threshold must be at least one missed cycle")]
+    InvalidQuarantineThreshold,
+    #[error("{batches} monitoring batches cannot be spread over a {interval_secs}s interval")]
+    InvalidMonitoringBatches { batches: usize, interval_secs: u64 },
+    #[error("Health history capacity must be at least 1")]
+    InvalidHealthHistoryCapacity,
+    #[error("Max concurrent health calculations must be at least 1")]
+    InvalidMaxConcurrentHealthCalcs,
+    #[error("Start jitter of {max_start_delay_ms}ms exceeds the {interval_secs}s monitoring interval")]
+    JitterExceedsInterval { max_start_delay_ms: u64, interval_secs: u64 },
+    #[error("Invalid memory budget: {message}")]
+    InvalidMemoryBudget { message: String },
+    #[error("Correlation thresholds must satisfy 0 <= high ({high}) <= critical ({critical}) <= 1")]
+    InvalidCorrelationThresholds { high: f64, critical: f64 },
+    #[error("Price circuit breaker must allow a positive move over a non-zero window")]
+    InvalidPriceCircuitBreaker,
+    #[error("Failed to load configuration: {message}")]
+    Load { message: String },
+}
+
+/// The part of `AegisConfig` that can be set from a TOML file or environment variables.
+/// Anything left unset keeps its default; stores, probes and other runtime objects can
+/// only be configured in code.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AegisSettings {
+    pub monitoring_interval_secs: Option<u64>,
+    pub enable_automated_actions: Option<bool>,
+    pub enable_price_impact_simulation: Option<bool>,
+    pub enable_smart_contract_analysis: Option<bool>,
+    pub enable_mev_protection: Option<bool>,
+    pub max_concurrent_positions: Option<usize>,
+    /// `0` turns quarantine off
+    pub quarantine_after_missing_prices: Option<u32>,
+    pub monitoring_jitter: Option<MonitoringJitter>,
+    pub monitoring_batches: Option<usize>,
+    pub monitoring_mode: Option<MonitoringMode>,
+    pub health_history_capacity: Option<usize>,
+    pub max_concurrent_health_calcs: Option<usize>,
+}
+
+impl AegisSettings {
+    fn load(path: Option<&std::path::Path>, env_prefix: Option<&str>) -> Result<AegisSettings, ConfigError> {
+        use figment::providers::{Env, Format, Toml};
+
+        let mut figment = figment::Figment::new();
+        if let Some(path) = path {
+            // Read the file ourselves: figment treats a missing file as empty
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| ConfigError::Load { message: format!("{}: {}", path.display(), e) })?;
+            figment = figment.merge(Toml::string(&contents));
+        }
+        if let Some(prefix) = env_prefix {
+            // Only variables naming a setting, so others sharing the prefix, e.g.
+            // `AEGIS_LOG_LEVEL`, don't fail `deny_unknown_fields`
+            let settings: Vec<String> = match serde_json::to_value(AegisSettings::default()) {
+                Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+                _ => Vec::new(),
+            };
+            let env = Env::prefixed(prefix)
+                .filter(move |key| {
+                    let setting = key.as_str().split("__").next().unwrap_or_default();
+                    settings.iter().any(|name| name.eq_ignore_ascii_case(setting))
+                })
+                .split("__");
+            figment = figment.merge(env);
+        }
+
+        figment.extract().map_err(|e| ConfigError::Load { message: e.to_string() })
+    }
+
+    /// Overlay the settings that are present onto `builder`
+    pub fn apply(self, mut builder: AegisConfigBuilder) -> AegisConfigBuilder {
+        if let Some(secs) = self.monitoring_interval_secs {
+            builder = builder.monitoring_interval_secs(secs);
+        }
+        if let Some(enabled) = self.enable_automated_actions {
+            builder = builder.enable_automated_actions(enabled);
+        }
+        if let Some(enabled) = self.enable_price_impact_simulation {
+            builder = builder.enable_price_impact_simulation(enabled);
+        }
+        if let Some(enabled) = self.enable_smart_contract_analysis {
+            builder = builder.enable_smart_contract_analysis(enabled);
+        }
+        if let Some(enabled) = self.enable_mev_protection {
+            builder = builder.enable_mev_protection(enabled);
+        }
+        if let Some(max) = self.max_concurrent_positions {
+            builder = builder.max_concurrent_positions(max);
+        }
+        if let Some(cycles) = self.quarantine_after_missing_prices {
+            builder = builder.quarantine_after_missing_prices((cycles > 0).then_some(cycles));
+        }
+        if let Some(jitter) = self.monitoring_jitter {
+            builder = builder.monitoring_jitter(jitter);
+        }
+        if let Some(batches) = self.monitoring_batches {
+            builder = builder.monitoring_batches(batches);
+        }
+        if let Some(mode) = self.monitoring_mode {
+            builder = builder.monitoring_mode(mode);
+        }
+        if let Some(capacity) = self.health_history_capacity {
+            builder = builder.health_history_capacity(capacity);
+        }
+        if let Some(limit) = self.max_concurrent_health_calcs {
+            builder = builder.max_concurrent_health_calcs(limit);
+        }
+        builder
+    }
+}
+
+impl AegisConfig {
+    pub fn builder() -> AegisConfigBuilder {
+        AegisConfigBuilder::default()
+    }
+
+    /// Load settings from a TOML file, e.g. `monitoring_interval_secs = 15`
+    pub fn from_toml(path: impl AsRef<std::path::Path>) -> Result<AegisConfig, ConfigError> {
+        Self::load(Some(path.as_ref()), None)
+    }
+
+    /// Load settings from environment variables named `{prefix}{FIELD}`, e.g.
+    /// `AEGIS_MONITORING_INTERVAL_SECS`; nested fields are separated by `__`, as in
+    /// `AEGIS_MONITORING_JITTER__MAX_START_DELAY_MS`
+    pub fn from_env(prefix: &str) -> Result<AegisConfig, ConfigError> {
+        Self::load(None, Some(prefix))
+    }
+
+    /// Load settings from a TOML file, with environment variables overriding its values
+    pub fn from_toml_and_env(path: impl AsRef<std::path::Path>, prefix: &str) -> Result<AegisConfig, ConfigError> {
+        Self::load(Some(path.as_ref()), Some(prefix))
+    }
+
+    fn load(path: Option<&std::path::Path>, env_prefix: Option<&str>) -> Result<AegisConfig, ConfigError> {
+        AegisSettings::load(path, env_prefix)?.apply(AegisConfig::builder()).build()
+    }
+
+    /// Check for values the satellite can't run with, reporting the first problem found
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.monitoring_interval_secs == 0 {
+            return Err(ConfigError::InvalidMonitoringInterval);
+        }
+        if self.max_concurrent_positions == 0 {
+            return Err(ConfigError::InvalidMaxConcurrentPositions);
+        }
+        if self.quarantine_after_missing_prices == Some(0) {
+            return Err(ConfigError::InvalidQuarantineThreshold);
+        }
+        // Each batch needs a tick of at least a millisecond
+        if self.monitoring_batches == 0 || self.monitoring_batches as u64 > self.monitoring_interval_secs * 1000 {
+            return Err(ConfigError::InvalidMonitoringBatches {
+                batches: self.monitoring_batches,
+                interval_secs: self.monitoring_interval_secs,
+            });
+        }
+        if self.health_history_capacity == 0 {
+            return Err(ConfigError::InvalidHealthHistoryCapacity);
+        }
+        if self.max_concurrent_health_calcs == 0 {
+            return Err(ConfigError::InvalidMaxConcurrentHealthCalcs);
+        }
+        if let Some(jitter) = &self.monitoring_jitter {
+            if jitter.max_start_delay_ms > self.monitoring_interval_secs * 1000 {
+                return Err(ConfigError::JitterExceedsInterval {
+                    max_start_delay_ms: jitter.max_start_delay_ms,
+                    interval_secs: self.monitoring_interval_secs,
+                });
+            }
+        }
+        if let Some(budget) = &self.memory_budget {
+            let message = if budget.soft_limit_bytes == 0 {
+                Some("soft limit must be non-zero".to_string())
+            } else if !(budget.pressure_ratio > 0.0 && budget.pressure_ratio <= 1.0) {
+                Some(format!("pressure ratio {} is outside (0, 1]", budget.pressure_ratio))
+            } else if !(budget.relief_ratio > 0.0 && budget.relief_ratio <= budget.pressure_ratio) {
+                Some(format!("relief ratio {} is outside (0, {}]", budget.relief_ratio, budget.pressure_ratio))
+            } else if !(budget.shed_fraction > 0.0 && budget.shed_fraction <= 1.0) {
+                Some(format!("shed fraction {} is outside (0, 1]", budget.shed_fraction))
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                return Err(ConfigError::InvalidMemoryBudget { message });
+            }
+        }
+        let high = self.correlation_analysis.correlation_threshold_high;
+        let critical = self.correlation_analysis.correlation_threshold_critical;
+        if !(0.0 <= high && high <= critical && critical <= 1.0) {
+            return Err(ConfigError::InvalidCorrelationThresholds { high, critical });
+        }
+        if let Some(breaker) = &self.price_circuit_breaker {
+            if breaker.max_move <= rust_decimal::Decimal::ZERO || breaker.window.is_zero() {
+                return Err(ConfigError::InvalidPriceCircuitBreaker);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fluent construction of a validated `AegisConfig`, starting from the defaults.
+///
+/// Fields that aren't set keep their default, so callers only name what they change.
+#[derive(Debug, Clone, Default)]
+pub struct AegisConfigBuilder {
+    config: AegisConfig,
+}
+
+impl From<AegisConfig> for AegisConfigBuilder {
+    /// Start from an existing config rather than the defaults
+    fn from(config: AegisConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl AegisConfigBuilder {
+    pub fn monitoring_interval_secs(mut self, secs: u64) -> Self {
+        self.config.monitoring_interval_secs = secs;
+        self
+    }
+
+    pub fn enable_automated_actions(mut self, enabled: bool) -> Self {
+        self.config.enable_automated_actions = enabled;
+        self
+    }
+
+    pub fn enable_price_impact_simulation(mut self, enabled: bool) -> Self {
+        self.config.enable_price_impact_simulation = enabled;
+        self
+    }
+
+    pub fn enable_smart_contract_analysis(mut self, enabled: bool) -> Self {
+        self.config.enable_smart_contract_analysis = enabled;
+        self
+    }
+
+    pub fn enable_mev_protection(mut self, enabled: bool) -> Self {
+        self.config.enable_mev_protection = enabled;
+        self
+    }
+
+    pub fn max_concurrent_positions(mut self, max: usize) -> Self {
+        self.config.max_concurrent_positions = max;
+        self
+    }
+
+    /// `None` never quarantines positions without prices
+    pub fn quarantine_after_missing_prices(mut self, cycles: Option<u32>) -> Self {
+        self.config.quarantine_after_missing_prices = cycles;
+        self
+    }
+
+    pub fn monitoring_jitter(mut self, jitter: MonitoringJitter) -> Self {
+        self.config.monitoring_jitter = Some(jitter);
+        self
+    }
+
+    pub fn monitoring_batches(mut self, batches: usize) -> Self {
+        self.config.monitoring_batches = batches;
+        self
+    }
+
+    pub fn monitoring_mode(mut self, mode: MonitoringMode) -> Self {
+        self.config.monitoring_mode = mode;
+        self
+    }
+
+    pub fn health_history_persistence(mut self, persistence: liquidation::HealthHistoryPersistence) -> Self {
+        self.config.health_history_persistence = Some(persistence);
+        self
+    }
+
+    pub fn memory_budget(mut self, budget: monitoring::MemoryBudget) -> Self {
+        self.config.memory_budget = Some(budget);
+        self
+    }
+
+    pub fn health_history_capacity(mut self, capacity: usize) -> Self {
+        self.config.health_history_capacity = capacity;
+        self
+    }
+
+    pub fn max_concurrent_health_calcs(mut self, limit: usize) -> Self {
+        self.config.max_concurrent_health_calcs = limit;
+        self
+    }
+
+    pub fn correlation_analysis(mut self, config: risk::CorrelationAnalysisConfig) -> Self {
+        self.config.correlation_analysis = config;
+        self
+    }
+
+    pub fn trade_retry(mut self, policy: Option<RetryPolicy>) -> Self {
+        self.config.trade_retry = policy;
+        self
+    }
+
+    pub fn price_circuit_breaker(mut self, config: Option<liquidation::PriceCircuitBreakerConfig>) -> Self {
+        self.config.price_circuit_breaker = config;
+        self
+    }
+
+    pub fn trade_idempotency_ttl(mut self, ttl: Option<std::time::Duration>) -> Self {
+        self.config.trade_idempotency_ttl = ttl;
+        self
+    }
+
+    pub fn integrity_key(mut self, key: monitoring::IntegrityKey) -> Self {
+        self.config.integrity_key = Some(key);
+        self
+    }
+
+    pub fn exploit_signatures(mut self, signatures: Arc<security::ExploitSignatureDb>) -> Self {
+        self.config.exploit_signatures = Some(signatures);
+        self
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics_exporter(mut self, config: metrics::PrometheusExporterConfig) -> Self {
+        self.config.metrics_exporter = Some(config);
+        self
+    }
+
+    pub fn build(self) -> Result<AegisConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
 }
 
 impl Default for AegisConfig {
@@ -53,6 +482,22 @@ impl Default for AegisConfig {
             enable_smart_contract_analysis: true,
             enable_mev_protection: true,
             max_concurrent_positions: 1000,
+            quarantine_after_missing_prices: None,
+            monitoring_jitter: None,
+            monitoring_batches: 1,
+            monitoring_mode: MonitoringMode::Interval,
+            health_history_persistence: None,
+            memory_budget: None,
+            health_history_capacity: liquidation::DEFAULT_HEALTH_HISTORY_CAPACITY,
+            max_concurrent_health_calcs: liquidation::DEFAULT_MAX_CONCURRENT_HEALTH_CALCS,
+            correlation_analysis: risk::CorrelationAnalysisConfig::default(),
+            trade_retry: None,
+            price_circuit_breaker: None,
+            trade_idempotency_ttl: Some(risk::DEFAULT_IDEMPOTENCY_TTL),
+            integrity_key: None,
+            exploit_signatures: None,
+            #[cfg(feature = "metrics")]
+            metrics_exporter: None,
         }
     }
 }
@@ -62,200 +507,995 @@ impl AegisSatellite {
         price_feeds: Arc<dyn PriceFeedProvider>,
         trade_executor: Arc<dyn TradeExecutor>,
         config: Option<AegisConfig>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::build(price_feeds, trade_executor, config, None).await
+    }
+
+    /// Like `new`, but persists positions and alerts to `store` and restores them from it on startup
+    pub async fn with_position_store(
+        price_feeds: Arc<dyn PriceFeedProvider>,
+        trade_executor: Arc<dyn TradeExecutor>,
+        config: Option<AegisConfig>,
+        store: Arc<dyn liquidation::PositionStore>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Self::build(price_feeds, trade_executor, config, Some(store)).await
+    }
+
+    async fn build(
+        price_feeds: Arc<dyn PriceFeedProvider>,
+        trade_executor: Arc<dyn TradeExecutor>,
+        config: Option<AegisConfig>,
+        position_store: Option<Arc<dyn liquidation::PositionStore>>,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let config = Arc::new(RwLock::new(config.unwrap_or_default()));
-        
+
         // Initialize alert system
-        let alert_system = Arc::new(EscalatingAlertSystem::new(
-            monitoring::AlertConfiguration::default()
-        ));
+        let mut alert_system = EscalatingAlertSystem::new(monitoring::AlertConfiguration::default());
+        if let Some(store) = &position_store {
+            alert_system = alert_system.with_audit_store(store.clone());
+        }
+        let alert_system = Arc::new(alert_system);
+        let monitored_alert_system: Arc<dyn AlertSystem> = match &position_store {
+            Some(store) => Arc::new(liquidation::PersistingAlertSystem::new(alert_system.clone(), store.clone())),
+            None => alert_system.clone(),
+        };
+
+        // Fed by the monitor, read by price impact and simulation
+        let volatility_tracker = Arc::new(data::VolatilityTracker::default());
 
         // Initialize liquidation monitor
-        let liquidation_monitor = Arc::new(LiquidationMonitor::new(
+        let mut liquidation_monitor = LiquidationMonitor::new(
             price_feeds.clone(),
-            alert_system.clone(),
-        ));
+            monitored_alert_system.clone(),
+        ).with_volatility_tracker(volatility_tracker.clone())
+            .with_health_history_capacity(config.read().await.health_history_capacity)
+            .with_max_concurrent_health_calcs(config.read().await.max_concurrent_health_calcs);
+        if let Some(failures) = config.read().await.quarantine_after_missing_prices {
+            liquidation_monitor = liquidation_monitor.with_unpriceable_quarantine(failures);
+        }
+        if let Some(breaker) = config.read().await.price_circuit_breaker.clone() {
+            liquidation_monitor = liquidation_monitor
+                .with_price_circuit_breaker(Arc::new(liquidation::PriceCircuitBreaker::new(breaker)));
+        }
+        if let Some(persistence) = config.read().await.health_history_persistence.clone() {
+            liquidation_monitor = liquidation_monitor.with_health_history_persistence(persistence);
+            if let Err(e) = liquidation_monitor.hydrate_health_history().await {
+                warn!("Starting without persisted health history: {}", e);
+            }
+        }
+        let liquidation_monitor = Arc::new(liquidation_monitor);
+
+        if let Some(store) = &position_store {
+            alert_system.restore_alert_history(store.load_alerts().await?);
+            alert_system.restore_alert_audit(store.load_audit_entries().await?);
+            let mut positions = store.load_all();
+            let mut restored = 0;
+            while let Some(position) = futures::StreamExt::next(&mut positions).await {
+                let position = position?;
+                let position_id = position.id;
+                match liquidation_monitor.add_position(position).await {
+                    Ok(_) => restored += 1,
+                    Err(e) => warn!("Failed to restore position {}: {}", position_id, e),
+                }
+            }
+            info!("Restored {} persisted positions", restored);
+        }
 
         // Initialize price impact simulator
         let price_impact_simulator = Arc::new(PriceImpactSimulator::new(
             Box::new(MockHistoricalDataProvider)
-        ));
+        ).with_volatility_tracker(volatility_tracker.clone()));
 
         // Initialize automated position manager
+        let trade_executor: Arc<dyn TradeExecutor> = match config.read().await.trade_retry.clone() {
+            Some(policy) => Arc::new(risk::RetryingTradeExecutor::new(trade_executor, policy)),
+            None => trade_executor,
+        };
+        // Outside the retries, so a duplicate waits on the whole retried attempt
+        let trade_executor: Arc<dyn TradeExecutor> = match config.read().await.trade_idempotency_ttl {
+            Some(ttl) => Arc::new(risk::IdempotentTradeExecutor::new(trade_executor, ttl)),
+            None => trade_executor,
+        };
         let position_manager = Arc::new(AutomatedPositionManager::new(
             liquidation_monitor.clone(),
             price_impact_simulator.clone(),
-            alert_system.clone(),
+            monitored_alert_system.clone(),
             trade_executor,
         ));
+        Self::apply_automation_flag(&position_manager, config.read().await.enable_automated_actions).await;
 
         // Initialize stress testing framework
         let stress_testing_config = StressTestingConfig::default();
-        let stress_testing_framework = Arc::new(StressTestingFramework::new(stress_testing_config));
+        let stress_testing_framework = Arc::new(
+            StressTestingFramework::new(stress_testing_config).with_volatility_tracker(volatility_tracker.clone())
+        );
 
         // Initialize visualization framework
         let visualization_framework = Arc::new(VisualizationFramework::new());
 
+        let correlation_analysis = Arc::new(risk::CorrelationAnalysisSystem::new(
+            config.read().await.correlation_analysis.clone()
+        ));
+
+        let exploit_monitor = config.read().await.exploit_signatures.clone().map(|signatures| {
+            let (monitor, _security_alerts) = security::ExploitDiscoveryMonitor::new();
+            monitor.with_signature_db(signatures).with_alert_system(monitored_alert_system.clone())
+        });
+
         info!("Aegis Satellite initialized successfully");
 
         Ok(Self {
             liquidation_monitor,
+            price_feeds,
             price_impact_simulator,
             alert_system,
+            monitored_alert_system,
             position_manager,
             stress_testing_framework,
             visualization_framework,
+            correlation_analysis,
+            volatility_tracker,
+            position_store,
             config,
+            config_changed: Arc::new(tokio::sync::watch::Sender::new(())),
+            memory_shed: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            correlation_spike_active: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            exploit_monitor,
+            #[cfg(feature = "metrics")]
+            metrics_server: std::sync::Mutex::new(None),
+        })
+    }
+
+    pub async fn get_config(&self) -> AegisConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Validate and swap in `new_config`. The monitoring and memory budget loops switch to
+    /// the new interval and batch count straight away, and `enable_automated_actions`
+    /// applies from the next evaluation cycle. Settings used only during construction, such as stores and
+    /// history persistence, still need a restart.
+    pub async fn update_config(&self, new_config: AegisConfig) -> Result<(), ConfigError> {
+        Self::apply_config(&self.config, &self.position_manager, &self.config_changed, |_| Ok(new_config)).await
+    }
+
+    /// Poll `path` every `poll_interval` and apply its settings whenever the file changes.
+    /// Keys missing from the file keep their current values; invalid files are logged and
+    /// ignored, leaving the running config in place.
+    pub fn watch_config_file(&self, path: impl AsRef<std::path::Path>, poll_interval: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        let path = path.as_ref().to_path_buf();
+        let config = self.config.clone();
+        let position_manager = self.position_manager.clone();
+        let config_changed = self.config_changed.clone();
+        let modified_at = |path: &std::path::Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+
+        tokio::spawn(async move {
+            let mut last_modified = modified_at(&path);
+            let mut interval = tokio::time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                let modified = modified_at(&path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                // Layered onto the config as it stands under the write lock, so a concurrent
+                // update_config is never overwritten with stale values
+                let result = match AegisSettings::load(Some(&path), None) {
+                    Ok(settings) => Self::apply_config(&config, &position_manager, &config_changed, |current| {
+                        settings.apply(current.clone().into()).build()
+                    }).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => info!("Reloaded configuration from {}", path.display()),
+                    Err(e) => warn!("Ignoring configuration change in {}: {}", path.display(), e),
+                }
+            }
         })
     }
 
+    /// Replace the config with what `update` builds from the current one, holding the write
+    /// lock throughout so concurrent updates apply one after the other
+    async fn apply_config(
+        config: &RwLock<AegisConfig>,
+        position_manager: &AutomatedPositionManager,
+        config_changed: &tokio::sync::watch::Sender<()>,
+        update: impl FnOnce(&AegisConfig) -> Result<AegisConfig, ConfigError>,
+    ) -> Result<(), ConfigError> {
+        let mut current = config.write().await;
+        let new_config = update(&current)?;
+        new_config.validate()?;
+        Self::apply_automation_flag(position_manager, new_config.enable_automated_actions).await;
+        *current = new_config;
+        drop(current);
+        // Receivers keep the change marked, so one made mid-cycle is seen once the cycle finishes
+        config_changed.send_replace(());
+        Ok(())
+    }
+
+    async fn apply_automation_flag(position_manager: &AutomatedPositionManager, enabled: bool) {
+        let mut automation = position_manager.get_config().await;
+        if automation.enabled != enabled {
+            automation.enabled = enabled;
+            position_manager.update_config(automation).await;
+        }
+    }
+
+    /// Tick length and batch count of the monitoring loop
+    fn monitoring_cadence(config: &AegisConfig) -> (std::time::Duration, usize) {
+        let batches = config.monitoring_batches.max(1);
+        (std::time::Duration::from_secs(config.monitoring_interval_secs) / batches as u32, batches)
+    }
+
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting Aegis Satellite monitoring systems...");
 
         let config = self.config.read().await;
+        let start_delay = config.monitoring_jitter.as_ref()
+            .map(|jitter| jitter.start_delay())
+            .unwrap_or_default();
+        if !start_delay.is_zero() {
+            info!("Delaying monitoring start by {:?}", start_delay);
+        }
         
         // Start position monitoring
         let position_manager = self.position_manager.clone();
         tokio::spawn(async move {
+            tokio::time::sleep(start_delay).await;
             position_manager.start_monitoring().await;
         });
 
-        // Start periodic health checks
+        // Start periodic health checks, one batch per tick, checking for correlation
+        // regime breaks once per full sweep
         let liquidation_monitor = self.liquidation_monitor.clone();
-        let monitoring_interval = config.monitoring_interval_secs;
+        let correlation_analysis = self.correlation_analysis.clone();
+        let price_feeds = self.price_feeds.clone();
+        let correlation_spike_active = self.correlation_spike_active.clone();
+        let alert_system = self.alert_system.clone();
+        let monitored_alert_system = self.monitored_alert_system.clone();
+        let stress_testing_framework = self.stress_testing_framework.clone();
+        let shared_config = self.config.clone();
+        let mut config_changed = self.config_changed.subscribe();
+        let (mut tick, mut batches) = Self::monitoring_cadence(&config);
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(
-                std::time::Duration::from_secs(monitoring_interval)
-            );
+            tokio::time::sleep(start_delay).await;
+            let mut interval = tokio::time::interval(tick);
+            let mut batch = 0;
             
             loop {
-                interval.tick().await;
-                match liquidation_monitor.monitor_positions().await {
-                    Ok(alerts) => {
-                        if !alerts.is_empty() {
-                            info!("Generated {} risk alerts", alerts.len());
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    changed = config_changed.changed() => {
+                        if changed.is_err() {
+                            break;
                         }
+                        let cadence = Self::monitoring_cadence(&*shared_config.read().await);
+                        if cadence != (tick, batches) {
+                            (tick, batches) = cadence;
+                            info!("Monitoring cadence changed to {} batches every {:?}", batches, tick);
+                            interval = tokio::time::interval_at(tokio::time::Instant::now() + tick, tick);
+                            batch = 0;
+                        }
+                        continue;
                     }
-                    Err(e) => {
-                        error!("Error during position monitoring: {}", e);
-                    }
                 }
+                let mode = shared_config.read().await.monitoring_mode;
+                let alerts = match mode {
+                    MonitoringMode::Interval => liquidation_monitor.monitor_position_batch(batch, batches).await,
+                    MonitoringMode::PriceChanges => liquidation_monitor.monitor_price_changes().await,
+                };
+                if !alerts.is_empty() {
+                    info!("Generated {} risk alerts", alerts.len());
+                }
+                if batch == 0 {
+                    Self::check_correlation_spike(
+                        &correlation_analysis,
+                        price_feeds.as_ref(),
+                        &correlation_spike_active,
+                        monitored_alert_system.as_ref(),
+                    ).await;
+                }
+                Self::refresh_gauges(&liquidation_monitor, &alert_system, &stress_testing_framework).await;
+                batch = (batch + 1) % batches;
             }
         });
 
+        #[cfg(feature = "metrics")]
+        if let Some(exporter_config) = config.metrics_exporter.clone() {
+            let server = self.serve_metrics(exporter_config).await?;
+            *self.metrics_server.lock().unwrap() = Some(server);
+        }
+
+        // Watch the memory budget on the monitoring cadence, following interval changes
+        if let Some(budget) = config.memory_budget.clone() {
+            let liquidation_monitor = self.liquidation_monitor.clone();
+            let stress_testing_framework = self.stress_testing_framework.clone();
+            let alert_system = self.monitored_alert_system.clone();
+            let memory_shed = self.memory_shed.clone();
+            let shared_config = self.config.clone();
+            let mut config_changed = self.config_changed.subscribe();
+            let mut check_interval = std::time::Duration::from_secs(config.monitoring_interval_secs);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(check_interval);
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        changed = config_changed.changed() => {
+                            if changed.is_err() {
+                                break;
+                            }
+                            let updated = std::time::Duration::from_secs(shared_config.read().await.monitoring_interval_secs);
+                            if updated != check_interval {
+                                check_interval = updated;
+                                interval = tokio::time::interval_at(tokio::time::Instant::now() + check_interval, check_interval);
+                            }
+                            continue;
+                        }
+                    }
+                    Self::shed_memory(&budget, &memory_shed, &liquidation_monitor, &stress_testing_framework, alert_system.as_ref()).await;
+                }
+            });
+        }
+
+        // Persist health history off the monitoring loop
+        if let Some(flush_interval) = self.liquidation_monitor.health_history_flush_interval() {
+            let liquidation_monitor = self.liquidation_monitor.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(flush_interval);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = liquidation_monitor.flush_health_history().await {
+                        warn!("Health history flush failed: {}", e);
+                    }
+                }
+            });
+        }
+
         info!("Aegis Satellite started successfully");
         Ok(())
     }
 
+    /// Write out health history recorded since the last flush and shut down the metrics
+    /// exporter if it wasn't taken; call before dropping the satellite
+    pub async fn stop(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        info!("Stopping Aegis Satellite...");
+        let flushed = self.liquidation_monitor.flush_health_history().await?;
+        if flushed > 0 {
+            info!("Persisted {} health records on shutdown", flushed);
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(server) = self.take_metrics_server() {
+            server.shutdown().await;
+        }
+        Ok(())
+    }
+
     pub async fn add_position(&self, position: Position) -> Result<PositionId, PositionError> {
-        self.liquidation_monitor.add_position(position).await
+        let stored = position.clone();
+        let position_id = self.liquidation_monitor.add_position(position).await?;
+        if let Err(e) = self.persist_position(&stored).await {
+            // Don't monitor a position that would silently vanish on restart
+            let _ = self.liquidation_monitor.remove_position(position_id);
+            return Err(e);
+        }
+        Ok(position_id)
+    }
+
+    /// Add a position from an external JSON payload shaped like `types::position_schema()`,
+    /// rejecting malformed payloads and broken invariants before anything is stored
+    pub async fn add_position_json(&self, json: &str) -> Result<PositionId, PositionError> {
+        let position: Position = serde_json::from_str(json)
+            .map_err(|e| PositionError::Invalid { message: format!("malformed position JSON: {}", e) })?;
+        position.validate()?;
+        self.add_position(position).await
     }
 
     pub async fn update_position(&self, position: Position) -> Result<(), PositionError> {
-        self.liquidation_monitor.update_position(position).await
+        let stored = position.clone();
+        self.liquidation_monitor.update_position(position).await?;
+        self.persist_position(&stored).await
     }
 
     pub async fn remove_position(&self, position_id: PositionId) -> Result<Position, PositionError> {
-        self.liquidation_monitor.remove_position(position_id)
+        let removed = self.liquidation_monitor.remove_position(position_id)?;
+        if let Some(store) = &self.position_store {
+            if let Err(e) = store.delete(position_id).await {
+                // Keep monitoring a position that would reappear on restart
+                let _ = self.liquidation_monitor.add_position(removed).await;
+                return Err(PositionError::Persistence { id: position_id, message: e.to_string() });
+            }
+        }
+        Ok(removed)
     }
 
-    pub async fn get_position_health(&self, position_id: PositionId) -> Result<HealthFactor, CalculationError> {
-        self.liquidation_monitor.calculate_health(position_id).await
+    async fn persist_position(&self, position: &Position) -> Result<(), PositionError> {
+        match &self.position_store {
+            Some(store) => store.save(position).await
+                .map_err(|e| PositionError::Persistence { id: position.id, message: e.to_string() }),
+            None => Ok(()),
+        }
     }
 
-    pub async fn simulate_trade_impact(
+    /// Add every position in a CSV laid out as `liquidation::POSITION_CSV_HEADER`.
+    /// Invalid rows and positions that fail to add are reported without stopping the import.
+    pub async fn import_positions_csv(
         &self,
-        position_id: PositionId,
-        token_address: &str,
-        amount: rust_decimal::Decimal,
-    ) -> Result<risk::TradeSimulation, risk::PriceImpactError> {
-        self.price_impact_simulator
-            .simulate_liquidation_trade(position_id, token_address, amount)
-            .await
+        mut reader: impl std::io::Read,
+    ) -> Result<liquidation::PositionImportReport, Box<dyn std::error::Error + Send + Sync>> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        let (positions, mut errors) = liquidation::parse_positions_csv(&contents);
+        let mut imported = Vec::with_capacity(positions.len());
+        for (line, position) in positions {
+            let position_id = position.id;
+            match self.add_position(position).await {
+                Ok(position_id) => imported.push(position_id),
+                Err(e) => errors.push(liquidation::PositionCsvRowError {
+                    line,
+                    position_id: Some(position_id),
+                    message: e.to_string(),
+                }),
+            }
+        }
+        errors.sort_by_key(|error| error.line);
+
+        info!("Imported {} positions from CSV, {} rows rejected", imported.len(), errors.len());
+        Ok(liquidation::PositionImportReport { imported, errors })
     }
 
-    pub async fn get_alerts(&self, position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
-        self.alert_system.get_alerts(position_id).await
+    /// Write all monitored positions as CSV, returning how many were written
+    pub fn export_positions_csv(&self, writer: impl std::io::Write) -> std::io::Result<usize> {
+        liquidation::write_positions_csv(&self.liquidation_monitor.list_positions(), writer)
     }
 
-    pub async fn acknowledge_alert(&self, alert_id: uuid::Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.alert_system.acknowledge_alert(alert_id).await
+    pub fn get_position(&self, position_id: PositionId) -> Option<Position> {
+        self.liquidation_monitor.get_position(position_id)
     }
 
-    pub fn get_statistics(&self) -> AegisStatistics {
-        AegisStatistics {
-            total_positions: self.liquidation_monitor.position_count(),
-            active_alerts: self.alert_system.active_alerts.len(),
-            supported_protocols: liquidation::HealthCalculatorFactory::supported_protocols().len(),
-        }
+    pub async fn get_position_health(&self, position_id: PositionId) -> Result<HealthFactor, CalculationError> {
+        self.liquidation_monitor.calculate_health(position_id).await
     }
 
-    // Simulation and Stress Testing API Methods
+    /// Register the calculator this satellite uses for positions on `protocol`, replacing
+    /// any existing one
+    pub fn register_health_calculator(&self, protocol: &str, calculator: Box<dyn HealthCalculator>) {
+        self.liquidation_monitor.health_calculators().register(protocol, calculator);
+    }
 
-    /// Run a stress test on the given positions with a specific scenario
-    pub async fn run_stress_test(
+    /// Health of several positions from a single price fetch; unknown ids map to an error
+    pub async fn get_positions_health(
         &self,
-        positions: &[SimulationPosition],
-        scenario: &SimulationScenario,
-    ) -> Result<simulation::SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
-        self.stress_testing_framework.run_stress_test(positions, scenario).await
+        position_ids: &[PositionId],
+    ) -> std::collections::HashMap<PositionId, Result<HealthFactor, CalculationError>> {
+        self.liquidation_monitor.calculate_health_batch(position_ids).await
     }
 
-    /// Run Monte Carlo simulation on the given positions
-    pub async fn run_monte_carlo_simulation(
-        &self,
-        positions: &[SimulationPosition],
-        config: &simulation::MonteCarloConfig,
-    ) -> Result<Vec<simulation::SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
-        self.stress_testing_framework.run_monte_carlo_simulation(positions, config).await
+    /// Health factors of a position recorded by monitoring cycles at or after `since`,
+    /// oldest first
+    pub fn get_health_history(&self, position_id: PositionId, since: chrono::DateTime<chrono::Utc>) -> Vec<(chrono::DateTime<chrono::Utc>, HealthFactor)> {
+        self.liquidation_monitor.get_health_history(position_id, since)
     }
 
-    /// Run backtesting on historical data
-    pub async fn run_backtesting(
+    /// Positions removed from monitoring because a token can no longer be priced
+    pub fn get_quarantined_positions(&self) -> Vec<liquidation::QuarantinedPosition> {
+        self.liquidation_monitor.get_quarantined_positions()
+    }
+
+    pub fn release_quarantined_position(&self, position_id: PositionId) -> Result<PositionId, PositionError> {
+        self.liquidation_monitor.release_quarantined_position(position_id)
+    }
+
+    /// Evaluate all positions against one consistent price snapshot
+    pub async fn get_risk_snapshot(&self) -> Result<liquidation::RiskSnapshot, CalculationError> {
+        self.liquidation_monitor.evaluate_snapshot().await
+    }
+
+    /// Monitored positions with their health and risk level, riskiest first, all valued
+    /// from one price snapshot; positions that couldn't be valued lead with their error.
+    /// Optionally limited to `protocol` and to the first `limit`.
+    pub async fn list_positions_by_risk(
         &self,
-        positions: &[SimulationPosition],
-        start_date: chrono::DateTime<chrono::Utc>,
-        end_date: chrono::DateTime<chrono::Utc>,
-    ) -> Result<simulation::SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
-        self.stress_testing_framework.run_backtesting(positions, start_date, end_date).await
+        limit: Option<usize>,
+        protocol: Option<&str>,
+    ) -> Result<Vec<liquidation::RankedPosition>, CalculationError> {
+        self.liquidation_monitor.positions_by_risk(protocol, limit).await
     }
 
-    /// Get cache statistics for the simulation framework
-    pub async fn get_simulation_cache_stats(&self) -> Result<std::collections::HashMap<String, usize>, Box<dyn std::error::Error + Send + Sync>> {
-        self.stress_testing_framework.get_cache_stats().await
+    /// Portfolio health, concentration and protocol exposure before and after hypothetical
+    /// position changes, computed on a copy without touching the monitored positions
+    pub async fn whatif(&self, changes: Vec<liquidation::PositionChange>) -> Result<liquidation::WhatIfResult, CalculationError> {
+        self.liquidation_monitor.what_if(&changes).await
     }
 
-    /// Clear the simulation cache
-    pub async fn clear_simulation_cache(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.stress_testing_framework.clear_cache().await
+    /// Health after applying several simultaneous price moves to one consistent price set
+    pub async fn health_with_price_changes(
+        &self,
+        position_id: PositionId,
+        changes: &[liquidation::PriceChange],
+        merge: liquidation::PriceChangeMerge,
+    ) -> Result<HealthFactor, CalculationError> {
+        self.liquidation_monitor.health_with_price_changes(position_id, changes, merge).await
     }
 
-    /// Convert real positions to simulation positions for testing
-    pub async fn convert_positions_to_simulation(
+    /// Find the smallest sale of one collateral token that restores a target health factor
+    pub async fn minimal_safe_reduction(
+        &self,
+        position_id: PositionId,
+        token_address: &TokenAddress,
+        target_health: rust_decimal::Decimal,
+    ) -> Result<risk::SafeReduction, Box<dyn std::error::Error + Send + Sync>> {
+        self.position_manager.minimal_safe_reduction(position_id, token_address, target_health).await
+    }
+
+    /// Automated actions waiting for an explicit approve or reject decision
+    pub async fn get_pending_approvals(&self) -> Vec<risk::ActionApproval> {
+        self.position_manager.get_pending_approvals().await
+    }
+
+    /// Approve a queued automated action and execute its trade
+    pub async fn approve_action(
+        &self,
+        id: uuid::Uuid,
+        approved_by: &str,
+    ) -> Result<risk::AutomatedActionExecution, Box<dyn std::error::Error + Send + Sync>> {
+        self.position_manager.approve_action(id, approved_by).await
+    }
+
+    /// Reject a queued automated action without executing it
+    pub async fn reject_action(
+        &self,
+        id: uuid::Uuid,
+        rejected_by: &str,
+    ) -> Result<risk::AutomatedActionExecution, Box<dyn std::error::Error + Send + Sync>> {
+        self.position_manager.reject_action(id, rejected_by).await
+    }
+
+    /// Compute the collateral top-up or debt repayment needed to reach a target health factor
+    pub async fn required_topup(
+        &self,
+        position_id: PositionId,
+        target_health: rust_decimal::Decimal,
+    ) -> Result<liquidation::RequiredTopup, CalculationError> {
+        self.liquidation_monitor.required_topup(position_id, target_health).await
+    }
+
+    /// Project a position's health along a stablecoin depeg trajectory
+    pub async fn health_under_depeg(
+        &self,
+        position_id: PositionId,
+        scenario: &simulation::DepegScenario,
+    ) -> Result<Vec<liquidation::DepegHealthPoint>, CalculationError> {
+        self.liquidation_monitor.health_under_depeg(position_id, scenario).await
+    }
+
+    /// Total collateral notional that becomes liquidatable at each price level of `token`
+    pub async fn liquidation_ladder(
+        &self,
+        token: &TokenAddress,
+        price_levels: &[rust_decimal::Decimal],
+    ) -> Result<Vec<(rust_decimal::Decimal, rust_decimal::Decimal)>, CalculationError> {
+        self.liquidation_monitor.liquidation_ladder(token, price_levels).await
+    }
+
+    /// Unrealized PnL of a position from its recorded entry prices
+    pub async fn position_pnl(&self, position_id: PositionId) -> Result<liquidation::PositionPnl, CalculationError> {
+        self.liquidation_monitor.position_pnl(position_id).await
+    }
+
+    pub async fn simulate_trade_impact(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: rust_decimal::Decimal,
+    ) -> Result<risk::TradeSimulation, risk::PriceImpactError> {
+        self.price_impact_simulator
+            .simulate_liquidation_trade(position_id, token_address, amount)
+            .await
+    }
+
+    pub async fn get_alerts(&self, position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        self.alert_system.get_alerts(position_id).await
+    }
+
+    /// Alerts matching `query`, newest first and paged by its `offset` and `limit`
+    pub fn get_alerts_filtered(&self, query: &monitoring::AlertQuery) -> Vec<RiskAlert> {
+        self.alert_system.query_alerts(query)
+    }
+
+    /// Stream of alerts as they are raised, see `EscalatingAlertSystem::subscribe`
+    pub fn subscribe_alerts(&self) -> tokio::sync::broadcast::Receiver<RiskAlert> {
+        self.alert_system.subscribe()
+    }
+
+    pub async fn acknowledge_alert(&self, alert_id: uuid::Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.monitored_alert_system.acknowledge_alert(alert_id).await
+    }
+
+    /// Acknowledge an alert on behalf of `acknowledged_by`, optionally noting why
+    pub async fn acknowledge_alert_with_note(
+        &self,
+        alert_id: uuid::Uuid,
+        acknowledged_by: &str,
+        note: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.monitored_alert_system.acknowledge_alert_with_note(alert_id, acknowledged_by, note).await
+    }
+
+    /// Who raised and acknowledged an alert, and when, oldest first
+    pub fn get_alert_audit(&self, alert_id: uuid::Uuid) -> Vec<monitoring::AlertAuditEntry> {
+        self.alert_system.get_alert_audit(alert_id)
+    }
+
+    /// Package positions, prices, health evaluations, alerts and automated actions
+    /// recorded within `range` into a single bundle sealed with the configured
+    /// `integrity_key`. Fails when no key is configured.
+    pub async fn export_audit_bundle(
+        &self,
+        range: std::ops::Range<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<AuditBundle, Box<dyn std::error::Error + Send + Sync>> {
+        let key = self.config.read().await.integrity_key.clone()
+            .ok_or("No integrity key configured to seal the audit bundle")?;
+        let health_records = self.liquidation_monitor.get_health_records(&range).await;
+        let alerts = self.alert_system.get_alerts(None).await?;
+        let actions = self.position_manager.get_execution_history().await;
+
+        Ok(AuditBundle::new(
+            range,
+            self.liquidation_monitor.list_positions(),
+            health_records,
+            alerts,
+            actions,
+            &key,
+        ))
+    }
+
+    /// Export the effective risk thresholds and automation settings for review, sealed
+    /// with the configured `integrity_key`
+    pub async fn export_risk_policy(&self) -> Result<risk::RiskPolicyDocument, risk::RiskPolicyError> {
+        let key = self.policy_integrity_key().await?;
+        Ok(risk::RiskPolicyDocument::new(
+            self.liquidation_monitor.get_risk_parameters().await,
+            self.position_manager.get_config().await,
+            &key,
+        ))
+    }
+
+    /// Validate and apply a policy document sealed with the configured `integrity_key`,
+    /// returning the settings it changed
+    pub async fn import_risk_policy(
+        &self,
+        document: risk::RiskPolicyDocument,
+    ) -> Result<Vec<risk::PolicyChange>, risk::RiskPolicyError> {
+        document.validate(&self.policy_integrity_key().await?)?;
+
+        let changes = document.diff(&self.export_risk_policy().await?);
+        self.liquidation_monitor.update_risk_parameters(document.risk_parameters).await;
+        self.position_manager.update_config(document.automation).await;
+
+        info!("Imported risk policy with {} changed settings", changes.len());
+        Ok(changes)
+    }
+
+    async fn policy_integrity_key(&self) -> Result<monitoring::IntegrityKey, risk::RiskPolicyError> {
+        self.config.read().await.integrity_key.clone()
+            .ok_or(risk::RiskPolicyError::MissingIntegrityKey)
+    }
+
+    /// Scan contract runtime bytecode for unguarded external calls followed by state writes
+    pub async fn detect_reentrancy(
+        &self,
+        bytecode: &[u8],
+    ) -> Result<Vec<security::Finding>, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.config.read().await.enable_smart_contract_analysis {
+            return Err("Smart contract analysis is disabled".into());
+        }
+        Ok(security::detect_reentrancy(bytecode))
+    }
+
+    /// Match a transaction against the configured exploit signatures, raising a
+    /// `ContractVulnerability` alert for each match. Without signatures nothing matches.
+    pub async fn scan_transaction(&self, transaction: &security::TransactionData) -> Vec<RiskAlert> {
+        match &self.exploit_monitor {
+            Some(monitor) => monitor.scan_transaction(transaction).await,
+            None => Vec::new(),
+        }
+    }
+
+    /// Shed in-memory data if usage is near the configured memory budget. Sheds once per
+    /// pressure event: nothing more is dropped until usage has fallen below the relief point.
+    pub async fn check_memory_pressure(&self) -> Option<monitoring::MemoryPressureReport> {
+        let budget = self.config.read().await.memory_budget.clone()?;
+        Self::shed_memory(&budget, &self.memory_shed, &self.liquidation_monitor, &self.stress_testing_framework, self.monitored_alert_system.as_ref()).await
+    }
+
+    async fn shed_memory(
+        budget: &monitoring::MemoryBudget,
+        memory_shed: &std::sync::atomic::AtomicBool,
+        liquidation_monitor: &LiquidationMonitor,
+        stress_testing_framework: &StressTestingFramework,
+        alert_system: &dyn AlertSystem,
+    ) -> Option<monitoring::MemoryPressureReport> {
+        use std::sync::atomic::Ordering;
+
+        if memory_shed.load(Ordering::SeqCst) {
+            if !budget.relieved() {
+                return None;
+            }
+            memory_shed.store(false, Ordering::SeqCst);
+        }
+        let used_bytes = budget.usage_under_pressure()?;
+        memory_shed.store(true, Ordering::SeqCst);
+
+        // Cached simulations can be recomputed and old history is the least likely to be
+        // read, so both go before anything the monitoring loop depends on
+        let report = monitoring::MemoryPressureReport {
+            used_bytes,
+            soft_limit_bytes: budget.soft_limit_bytes,
+            shed_simulation_cache_entries: stress_testing_framework.shed_cache(budget.shed_fraction).await,
+            shed_health_records: liquidation_monitor.shed_health_records(budget.shed_fraction).await,
+            shed_health_history_entries: liquidation_monitor.shed_health_history(budget.shed_fraction),
+        };
+        warn!("Memory pressure at {} of {} bytes: shed {} cached simulations, {} health records and {} charted health factors",
+              used_bytes, budget.soft_limit_bytes, report.shed_simulation_cache_entries, report.shed_health_records,
+              report.shed_health_history_entries);
+
+        let alert = RiskAlert::portfolio(AlertType::MemoryPressure, RiskLevel::Warning, format!(
+            "Memory use {} bytes is near the {} byte budget; shed {} cached simulations, {} health records and {} charted health factors",
+            used_bytes, budget.soft_limit_bytes, report.shed_simulation_cache_entries, report.shed_health_records,
+            report.shed_health_history_entries
+        ));
+        if let Err(e) = alert_system.send_alert(alert).await {
+            warn!("Failed to send memory pressure alert: {}", e);
+        }
+
+        Some(report)
+    }
+
+    /// Track an asset's price history for correlation regime checks. The asset is sampled
+    /// from the price feed under its symbol; see `add_correlation_asset_for_token`.
+    pub async fn add_correlation_asset(&self, asset: risk::Asset) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.correlation_analysis.add_asset(asset).await
+    }
+
+    /// Track an asset the price feed prices under `token_address`, e.g. the address the
+    /// monitored positions hold it by
+    pub async fn add_correlation_asset_for_token(
+        &self,
+        asset: risk::Asset,
+        token_address: &TokenAddress,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.correlation_analysis.set_asset_token_address(&asset.symbol, token_address).await;
+        self.correlation_analysis.add_asset(asset).await
+    }
+
+    pub async fn update_asset_price(&self, symbol: &str, price_point: risk::PricePoint) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.correlation_analysis.update_asset_price(symbol, price_point).await
+    }
+
+    /// Sample the price feed for the tracked assets and alert if their average correlation
+    /// has jumped above its trailing baseline. A spike is alerted once, when it starts; the
+    /// alert is returned only then.
+    pub async fn check_correlation_regime(&self) -> Option<RiskAlert> {
+        Self::check_correlation_spike(
+            &self.correlation_analysis,
+            self.price_feeds.as_ref(),
+            &self.correlation_spike_active,
+            self.monitored_alert_system.as_ref(),
+        ).await
+    }
+
+    async fn check_correlation_spike(
+        correlation_analysis: &risk::CorrelationAnalysisSystem,
+        price_feeds: &dyn PriceFeedProvider,
+        spike_active: &std::sync::atomic::AtomicBool,
+        alert_system: &dyn AlertSystem,
+    ) -> Option<RiskAlert> {
+        let token_addresses = correlation_analysis.asset_token_addresses().await;
+        if token_addresses.len() < 2 {
+            return None;
+        }
+        let symbols: Vec<String> = token_addresses.iter().map(|(symbol, _)| symbol.clone()).collect();
+        let addresses: Vec<TokenAddress> = token_addresses.iter().map(|(_, address)| address.clone()).collect();
+
+        // Correlation histories are hourly, so the feed is sampled at most once an hour
+        match price_feeds.get_prices(&addresses).await {
+            Ok(prices) => {
+                for (symbol, token_address) in &token_addresses {
+                    let price = match prices.get(token_address) {
+                        Some(price) => price,
+                        None => continue,
+                    };
+                    let price_point = risk::PricePoint {
+                        timestamp: price.timestamp,
+                        price: price.price_usd.to_f64().unwrap_or(0.0),
+                        volume: 0.0,
+                        market_cap: None,
+                    };
+                    if let Err(e) = correlation_analysis.sample_asset_price(symbol, price_point, chrono::Duration::hours(1)).await {
+                        warn!("Failed to record {} price for correlation checks: {}", symbol, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to sample prices for correlation checks: {}", e),
+        }
+
+        let alert = match correlation_analysis.detect_correlation_spike(&symbols).await {
+            Ok(Some(alert)) => alert,
+            Ok(None) => {
+                spike_active.store(false, std::sync::atomic::Ordering::SeqCst);
+                return None;
+            }
+            Err(e) => {
+                warn!("Correlation regime check failed: {}", e);
+                return None;
+            }
+        };
+        if spike_active.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return None;
+        }
+        if let Err(e) = alert_system.send_alert(alert.clone()).await {
+            warn!("Failed to send correlation spike alert: {}", e);
+        }
+        Some(alert)
+    }
+
+    /// Get alert incidents, most recently active first
+    pub async fn get_incidents(&self) -> Vec<monitoring::Incident> {
+        self.alert_system.get_incidents().await
+    }
+
+    /// Realized volatility of the prices seen while monitoring
+    pub fn volatility_tracker(&self) -> Arc<data::VolatilityTracker> {
+        self.volatility_tracker.clone()
+    }
+
+    /// Metrics recorded by the monitoring loop and health calculations
+    pub fn metrics(&self) -> Arc<monitoring::AegisMetrics> {
+        self.liquidation_monitor.metrics()
+    }
+
+    /// Refresh the gauges and serve all metrics on `config.path` until the returned server is shut down
+    #[cfg(feature = "metrics")]
+    pub async fn serve_metrics(
+        &self,
+        config: metrics::PrometheusExporterConfig,
+    ) -> Result<metrics::MetricsServer, Box<dyn std::error::Error + Send + Sync>> {
+        Self::refresh_gauges(&self.liquidation_monitor, &self.alert_system, &self.stress_testing_framework).await;
+        Ok(metrics::PrometheusExporter::new(self.metrics(), config).serve().await?)
+    }
+
+    /// The exporter `start` launched for `metrics_exporter`, handed over so the caller can
+    /// find its address or shut it down; `None` if there is none or it was already taken
+    #[cfg(feature = "metrics")]
+    pub fn take_metrics_server(&self) -> Option<metrics::MetricsServer> {
+        self.metrics_server.lock().unwrap().take()
+    }
+
+    async fn refresh_gauges(
+        liquidation_monitor: &LiquidationMonitor,
+        alert_system: &EscalatingAlertSystem,
+        stress_testing_framework: &StressTestingFramework,
+    ) {
+        let metrics = liquidation_monitor.metrics();
+        metrics.set_total_positions(liquidation_monitor.position_count());
+        metrics.set_active_alerts(alert_system.active_alert_count());
+        metrics.set_simulation_cache_hit_ratio(stress_testing_framework.cache_hit_ratio().await);
+    }
+
+    pub fn get_statistics(&self) -> AegisStatistics {
+        AegisStatistics {
+            total_positions: self.liquidation_monitor.position_count(),
+            active_alerts: self.alert_system.active_alerts.len(),
+            supported_protocols: self.liquidation_monitor.health_calculators().registered_protocols().len(),
+            health_calcs_in_flight: self.liquidation_monitor.health_calcs_in_flight(),
+            health_calc_queue_depth: self.liquidation_monitor.health_calc_queue_depth(),
+        }
+    }
+
+    // Simulation and Stress Testing API Methods
+
+    /// Run a stress test on the given positions with a specific scenario
+    pub async fn run_stress_test(
+        &self,
+        positions: &[SimulationPosition],
+        scenario: &SimulationScenario,
+    ) -> Result<simulation::SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_stress_test(positions, scenario).await
+    }
+
+    /// Run Monte Carlo simulation on the given positions
+    pub async fn run_monte_carlo_simulation(
+        &self,
+        positions: &[SimulationPosition],
+        config: &simulation::MonteCarloConfig,
+    ) -> Result<Vec<simulation::SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_monte_carlo_simulation(positions, config).await
+    }
+
+    /// Run a stress test, reporting progress to `progress`
+    pub async fn run_stress_test_with_progress(
+        &self,
+        positions: &[SimulationPosition],
+        scenario: &SimulationScenario,
+        progress: Option<simulation::ProgressCallback>,
+    ) -> Result<simulation::SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_stress_test_with_progress(positions, scenario, progress).await
+    }
+
+    /// Run Monte Carlo simulation, reporting throttled progress to `progress`
+    pub async fn run_monte_carlo_simulation_with_progress(
+        &self,
+        positions: &[SimulationPosition],
+        config: &simulation::MonteCarloConfig,
+        progress: Option<simulation::ProgressCallback>,
+    ) -> Result<Vec<simulation::SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_monte_carlo_simulation_with_progress(positions, config, progress).await
+    }
+
+    /// Run Monte Carlo simulation that stops with `SimulationError::Cancelled` once
+    /// `cancellation` fires
+    pub async fn run_monte_carlo_simulation_cancellable(
+        &self,
+        positions: &[SimulationPosition],
+        config: &simulation::MonteCarloConfig,
+        progress: Option<simulation::ProgressCallback>,
+        cancellation: tokio_util::sync::CancellationToken,
+    ) -> Result<Vec<simulation::SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_monte_carlo_simulation_cancellable(positions, config, progress, cancellation).await
+    }
+
+    /// Run backtesting on historical data
+    pub async fn run_backtesting(
+        &self,
+        positions: &[SimulationPosition],
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<simulation::SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.run_backtesting(positions, start_date, end_date).await
+    }
+
+    /// Get cache statistics for the simulation framework
+    pub async fn get_simulation_cache_stats(&self) -> Result<std::collections::HashMap<String, usize>, Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.get_cache_stats().await
+    }
+
+    /// Get correlation cache size and hit/miss counts
+    pub async fn get_correlation_cache_stats(&self) -> Result<std::collections::HashMap<String, usize>, Box<dyn std::error::Error + Send + Sync>> {
+        self.correlation_analysis.get_cache_stats().await
+    }
+
+    /// Clear the simulation cache
+    pub async fn clear_simulation_cache(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.stress_testing_framework.clear_cache().await
+    }
+
+    /// Convert real positions to simulation positions for testing, one per collateral
+    /// token. Each leg carries a share of the position's debt proportional to its
+    /// collateral value, so legs can be shocked per asset; a position without collateral
+    /// value gets one debt-only leg per debt token instead. Legs are valued from the same
+    /// guarded, rebased prices monitoring uses. Unknown or unpriceable positions are skipped.
+    pub async fn convert_positions_to_simulation(
         &self,
         position_ids: &[PositionId],
     ) -> Result<Vec<SimulationPosition>, Box<dyn std::error::Error + Send + Sync>> {
         let mut simulation_positions = Vec::new();
         
         for position_id in position_ids {
-            match self.get_position_health(*position_id).await {
-                Ok(health_factor) => {
-                    // Get position details from liquidation monitor
-                    // This is a simplified conversion - in practice, you'd get full position data
-                    let simulation_position = SimulationPosition {
-                        token_address: format!("position_{}", position_id),
-                        quantity: 1.0, // Placeholder
-                        entry_price: 100.0, // Placeholder
-                        current_price: 100.0, // Placeholder
-                        collateral_value: health_factor.collateral_value.to_f64().unwrap_or(0.0),
-                        debt_value: health_factor.debt_value.to_f64().unwrap_or(0.0),
-                        liquidation_threshold: health_factor.liquidation_threshold.to_f64().unwrap_or(0.0),
-                        health_factor: health_factor.health_factor.to_f64().unwrap_or(0.0),
-                    };
-                    simulation_positions.push(simulation_position);
+            let position = match self.liquidation_monitor.get_position(*position_id) {
+                Some(position) => position,
+                None => {
+                    warn!("Position {} not found, skipping simulation conversion", position_id);
+                    continue;
                 }
+            };
+
+            match self.simulation_legs(position.id).await {
+                Ok(legs) => simulation_positions.extend(legs),
                 Err(e) => {
-                    warn!("Failed to get health for position {}: {}", position_id, e);
+                    warn!("Failed to convert position {} for simulation: {}", position_id, e);
                 }
             }
         }
@@ -263,6 +1503,73 @@ impl AegisSatellite {
         Ok(simulation_positions)
     }
 
+    async fn simulation_legs(&self, position_id: PositionId) -> Result<Vec<SimulationPosition>, Box<dyn std::error::Error + Send + Sync>> {
+        // One snapshot for both the legs and their health factor
+        let (position, prices) = self.liquidation_monitor.priced_position(position_id).await?;
+        let health_factor = self.liquidation_monitor.calculate_health_with_prices(&position, &prices)?;
+        let liquidation_threshold = health_factor.liquidation_threshold.to_f64().unwrap_or(0.0);
+        let health_factor = health_factor.value.to_f64().unwrap_or(0.0);
+
+        let current_price = |token: &PositionToken| -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+            match prices.get(&token.token_address).and_then(|price| price.price_usd.to_f64()) {
+                Some(price) if price > 0.0 => Ok(price),
+                _ => Err(format!("No price for {}", token.token_address).into()),
+            }
+        };
+
+        let mut debt_tokens = std::collections::HashMap::new();
+        for token in position.debt_tokens.values() {
+            let value = token.amount.to_f64().unwrap_or(0.0) * current_price(token)?;
+            *debt_tokens.entry(token.token_address.clone()).or_insert(0.0) += value;
+        }
+        let debt_value: f64 = debt_tokens.values().sum();
+
+        let mut legs = Vec::new();
+        for token in position.collateral_tokens.values() {
+            let price = current_price(token)?;
+            let quantity = token.amount.to_f64().unwrap_or(0.0);
+            legs.push(SimulationPosition {
+                token_address: token.token_address.clone(),
+                quantity,
+                entry_price: token.entry_price_usd.and_then(|price| price.to_f64()).unwrap_or(price),
+                current_price: price,
+                collateral_value: quantity * price,
+                debt_value: 0.0,
+                liquidation_threshold,
+                health_factor,
+                debt_tokens: std::collections::HashMap::new(),
+            });
+        }
+
+        let collateral_value: f64 = legs.iter().map(|leg| leg.collateral_value).sum();
+        if collateral_value > 0.0 {
+            for leg in &mut legs {
+                let share = leg.collateral_value / collateral_value;
+                leg.debt_value = debt_value * share;
+                leg.debt_tokens = debt_tokens.iter().map(|(token, value)| (token.clone(), value * share)).collect();
+            }
+        } else {
+            // Nothing to spread the debt over; keep it as bare debt in each token
+            for token in position.debt_tokens.values() {
+                let price = current_price(token)?;
+                let value = debt_tokens[&token.token_address];
+                legs.push(SimulationPosition {
+                    token_address: token.token_address.clone(),
+                    quantity: 0.0,
+                    entry_price: price,
+                    current_price: price,
+                    collateral_value: 0.0,
+                    debt_value: value,
+                    liquidation_threshold,
+                    health_factor,
+                    debt_tokens: std::collections::HashMap::from([(token.token_address.clone(), value)]),
+                });
+            }
+        }
+        legs.sort_by(|a, b| a.token_address.cmp(&b.token_address));
+        Ok(legs)
+    }
+
     // Visualization and Reporting API Methods
 
     /// Generate a comprehensive simulation report
@@ -274,6 +1581,11 @@ impl AegisSatellite {
         self.visualization_framework.generate_report(simulation_result, template_name).await
     }
 
+    /// Register a named statistic that every generated report includes in its custom metrics
+    pub fn register_report_aggregation(&self, name: &str, aggregation: simulation::ReportAggregation) {
+        self.visualization_framework.register_aggregation(name, aggregation);
+    }
+
     /// Export simulation report to JSON format
     pub async fn export_report_json(
         &self,
@@ -290,6 +1602,19 @@ impl AegisSatellite {
         self.visualization_framework.export_report_csv(report).await
     }
 
+    /// Stream the CSV export of a simulation report to `writer`
+    pub fn export_report_csv_to<W: std::io::Write>(&self, report: &SimulationReport, writer: W) -> std::io::Result<()> {
+        self.visualization_framework.export_report_csv_to(report, writer)
+    }
+
+    /// Export simulation report to Markdown, following its report template's sections
+    pub async fn export_report_markdown(
+        &self,
+        report: &SimulationReport,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.visualization_framework.export_report_markdown(report).await
+    }
+
     /// Get available report templates
     pub fn get_report_templates(&self) -> Vec<String> {
         self.visualization_framework.get_report_templates()
@@ -306,6 +1631,10 @@ pub struct AegisStatistics {
     pub total_positions: usize,
     pub active_alerts: usize,
     pub supported_protocols: usize,
+    /// Health calculations running in the current monitoring pass
+    pub health_calcs_in_flight: usize,
+    /// Health calculations waiting for a concurrency slot
+    pub health_calc_queue_depth: usize,
 }
 
 // Mock implementation for testing
@@ -323,4 +1652,1307 @@ impl risk::HistoricalDataProvider for MockHistoricalDataProvider {
             rust_decimal::Decimal::from(90),
         ])
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::StaticPriceFeed;
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+
+    struct SucceedingTradeExecutor;
+
+    impl SucceedingTradeExecutor {
+        fn result() -> risk::ExecutionResult {
+            risk::ExecutionResult {
+                success: true,
+                transaction_hash: Some("0xabc".to_string()),
+                amount_executed: None,
+                actual_price_impact: None,
+                gas_used: None,
+                error_message: None,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TradeExecutor for SucceedingTradeExecutor {
+        async fn execute_position_reduction(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<risk::ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Self::result())
+        }
+
+        async fn emergency_exit_position(&self, _position_id: PositionId) -> Result<risk::ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Self::result())
+        }
+
+        async fn add_collateral(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<risk::ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Self::result())
+        }
+
+        async fn repay_debt(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<risk::ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Self::result())
+        }
+    }
+
+    fn token(address: &str, amount: i64) -> (TokenAddress, PositionToken) {
+        (address.to_string(), PositionToken {
+            token_address: address.to_string(),
+            amount: Decimal::from(amount),
+            value_usd: Decimal::ZERO,
+            price_per_token: Decimal::ZERO,
+            entry_price_usd: None,
+        })
+    }
+
+    /// 10 ETH of collateral against `debt` USDC
+    fn eth_position(protocol: &str, debt: i64) -> Position {
+        Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: protocol.to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", debt)]),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            risk_overrides: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_bundle_links_alert_to_action() {
+        let key = monitoring::IntegrityKey::generate();
+        let satellite = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            Some(AegisConfig::builder().integrity_key(key.clone()).build().unwrap()),
+        ).await.unwrap();
+        let start = chrono::Utc::now();
+
+        // 10 ETH at $2000 against $17,000 debt: Aave health ~0.94, below the emergency exit rule
+        let position_id = satellite.add_position(Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 17_000)]),
+            created_at: start,
+            updated_at: start,
+            risk_overrides: None,
+        }).await.unwrap();
+        satellite.liquidation_monitor.monitor_positions().await;
+        satellite.position_manager.evaluate_all_positions().await.unwrap();
+
+        let end = chrono::Utc::now() + chrono::Duration::seconds(1);
+        let bundle = satellite.export_audit_bundle(start..end).await.unwrap();
+
+        assert_eq!(bundle.positions.len(), 1);
+        assert_eq!(bundle.positions[0].id, position_id);
+        assert!(bundle.alerts.iter().all(|alert| alert.position_id == position_id));
+        // Repeats of the same alert are counted on it rather than raised again
+        let occurrences: u32 = bundle.alerts.iter().map(|alert| alert.occurrence_count).sum();
+        assert!(occurrences >= 2, "monitor and automation alerts expected");
+
+        let record = &bundle.health_records[0];
+        let sources: Vec<(&str, &str)> = record.prices_used.iter()
+            .map(|price| (price.token_address.as_str(), price.source.as_str()))
+            .collect();
+        assert_eq!(sources, vec![("ETH", "test"), ("USDC", "test")]);
+
+        let exit = bundle.actions.iter()
+            .find(|action| matches!(action.action, risk::AutomatedAction::EmergencyExit { .. }))
+            .expect("emergency exit action");
+        let decision = bundle.decisions.iter()
+            .find(|decision| decision.action_id == exit.id)
+            .unwrap();
+        assert_eq!(decision.position_id, position_id);
+        assert_eq!(decision.alert_ids, bundle.alerts.iter().map(|alert| alert.id).collect::<Vec<_>>());
+        let health_record_id = decision.health_record_id.unwrap();
+        assert!(bundle.health_records.iter().any(|record| record.id == health_record_id));
+
+        assert!(bundle.verify_integrity(&key));
+        let restored = AuditBundle::from_json(&bundle.to_json().unwrap()).unwrap();
+        assert!(restored.verify_integrity(&key));
+        assert!(!restored.verify_integrity(&monitoring::IntegrityKey::new("some other key")));
+
+        // Resealing after an edit needs the key
+        let mut tampered = restored;
+        tampered.alerts.clear();
+        assert!(!tampered.verify_integrity(&key));
+        tampered.integrity_hash = {
+            let mut content = tampered.clone();
+            content.integrity_hash = String::new();
+            monitoring::audit::content_mac(&monitoring::IntegrityKey::new("guessed key"), &content)
+        };
+        assert!(!tampered.verify_integrity(&key));
+    }
+
+    #[tokio::test]
+    async fn test_audit_bundle_needs_an_integrity_key() {
+        let satellite = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            None,
+        ).await.unwrap();
+        let now = chrono::Utc::now();
+
+        let err = satellite.export_audit_bundle(now - chrono::Duration::hours(1)..now).await.unwrap_err();
+        assert!(err.to_string().contains("integrity key"), "{}", err);
+    }
+
+    /// Feed shared by several satellites that records when each token was requested
+    struct RecordingPriceFeed {
+        requests: std::sync::Mutex<Vec<(TokenAddress, tokio::time::Instant)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for RecordingPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            self.requests.lock().unwrap().push((token_address.clone(), tokio::time::Instant::now()));
+            StaticPriceFeed.get_price(token_address).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_jittered_satellites_are_phase_offset() {
+        let feed = Arc::new(RecordingPriceFeed { requests: std::sync::Mutex::new(Vec::new()) });
+        let mut satellites = Vec::new();
+
+        // Seeds 3 and 1 draw different start delays
+        for (seed, collateral) in [(3, "ETH"), (1, "WETH")] {
+            let satellite = AegisSatellite::new(
+                feed.clone(),
+                Arc::new(SucceedingTradeExecutor),
+                Some(AegisConfig {
+                    monitoring_interval_secs: 1,
+                    monitoring_jitter: Some(MonitoringJitter { max_start_delay_ms: 1000, seed: Some(seed) }),
+                    ..AegisConfig::default()
+                }),
+            ).await.unwrap();
+            satellite.add_position(Position {
+                id: uuid::Uuid::new_v4(),
+                protocol: "aave".to_string(),
+                collateral_tokens: HashMap::from([token(collateral, 10)]),
+                debt_tokens: HashMap::from([token("USDC", 1_000)]),
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+                risk_overrides: None,
+            }).await.unwrap();
+            satellites.push(satellite);
+        }
+
+        feed.requests.lock().unwrap().clear();
+        let started = tokio::time::Instant::now();
+        for satellite in &satellites {
+            satellite.start().await.unwrap();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+        let requests = feed.requests.lock().unwrap().clone();
+        let first_request = |token: &str| requests.iter()
+            .find(|(address, _)| address == token)
+            .map(|(_, at)| at.duration_since(started))
+            .expect("token was never priced");
+
+        let max_start_delay = std::time::Duration::from_millis(1000);
+        assert_ne!(first_request("ETH"), first_request("WETH"));
+        assert!(first_request("ETH") <= max_start_delay);
+        assert!(first_request("WETH") <= max_start_delay);
+    }
+
+    #[tokio::test]
+    async fn test_risk_policy_round_trip_and_tamper_detection() {
+        let satellite = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            Some(AegisConfig::builder().integrity_key(monitoring::IntegrityKey::generate()).build().unwrap()),
+        ).await.unwrap();
+
+        let exported = satellite.export_risk_policy().await.unwrap();
+        let json = exported.to_json().unwrap();
+
+        // Drift the live configuration away from the exported policy
+        let mut drifted = satellite.liquidation_monitor.get_risk_parameters().await;
+        drifted.critical_health_threshold = Decimal::new(12, 1);
+        satellite.liquidation_monitor.update_risk_parameters(drifted).await;
+
+        let changes = satellite.import_risk_policy(risk::RiskPolicyDocument::from_json(&json).unwrap()).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "risk_parameters.critical_health_threshold");
+
+        let reexported = satellite.export_risk_policy().await.unwrap();
+        assert!(reexported.diff(&exported).is_empty());
+        assert_eq!(
+            serde_json::to_value(&reexported.risk_parameters).unwrap(),
+            serde_json::to_value(&exported.risk_parameters).unwrap(),
+        );
+        assert_eq!(
+            serde_json::to_value(&reexported.automation).unwrap(),
+            serde_json::to_value(&exported.automation).unwrap(),
+        );
+
+        let mut tampered = risk::RiskPolicyDocument::from_json(&json).unwrap();
+        tampered.risk_parameters.max_position_size_usd = Decimal::from(50_000_000);
+        assert!(matches!(
+            satellite.import_risk_policy(tampered.clone()).await,
+            Err(risk::RiskPolicyError::IntegrityMismatch)
+        ));
+
+        // Resealing the edit without the satellite's key doesn't get it through either
+        let resealed = risk::RiskPolicyDocument::new(
+            tampered.risk_parameters,
+            tampered.automation,
+            &monitoring::IntegrityKey::new("guessed key"),
+        );
+        assert!(matches!(
+            satellite.import_risk_policy(resealed).await,
+            Err(risk::RiskPolicyError::IntegrityMismatch)
+        ));
+        assert!(satellite.export_risk_policy().await.unwrap().diff(&exported).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_risk_policy_needs_an_integrity_key() {
+        let keyed = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            Some(AegisConfig::builder().integrity_key(monitoring::IntegrityKey::generate()).build().unwrap()),
+        ).await.unwrap();
+        let exported = keyed.export_risk_policy().await.unwrap();
+
+        let unkeyed = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            None,
+        ).await.unwrap();
+        assert!(matches!(unkeyed.export_risk_policy().await, Err(risk::RiskPolicyError::MissingIntegrityKey)));
+        assert!(matches!(
+            unkeyed.import_risk_policy(exported).await,
+            Err(risk::RiskPolicyError::MissingIntegrityKey)
+        ));
+    }
+
+    struct FakeMemoryProbe {
+        used_bytes: std::sync::atomic::AtomicU64,
+    }
+
+    impl monitoring::MemoryProbe for FakeMemoryProbe {
+        fn used_bytes(&self) -> Option<u64> {
+            Some(self.used_bytes.load(std::sync::atomic::Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_pressure_sheds_caches_and_alerts() {
+        let probe = Arc::new(FakeMemoryProbe { used_bytes: std::sync::atomic::AtomicU64::new(100) });
+        let satellite = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            Some(AegisConfig {
+                memory_budget: Some(monitoring::MemoryBudget {
+                    probe: probe.clone(),
+                    ..monitoring::MemoryBudget::new(1_000)
+                }),
+                ..AegisConfig::default()
+            }),
+        ).await.unwrap();
+
+        let position_id = satellite.add_position(Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 1_000)]),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            risk_overrides: None,
+        }).await.unwrap();
+        // Four health records, one per monitoring cycle
+        for _ in 0..4 {
+            satellite.liquidation_monitor.monitor_positions().await;
+        }
+
+        let positions = vec![SimulationPosition {
+            token_address: "ETH".to_string(),
+            quantity: 10.0,
+            entry_price: 2000.0,
+            current_price: 2000.0,
+            collateral_value: 20000.0,
+            debt_value: 1000.0,
+            liquidation_threshold: 0.8,
+            health_factor: 16.0,
+            debt_tokens: HashMap::new(),
+        }];
+        for scenario in [
+            SimulationScenario::HistoricalMarketCrash,
+            SimulationScenario::CryptoWinter,
+            SimulationScenario::DeFiContagion,
+            SimulationScenario::RegulatoryShock,
+        ] {
+            satellite.run_stress_test(&positions, &scenario).await.unwrap();
+        }
+        let cache_entries = |stats: HashMap<String, usize>| stats["simulation_cache_entries"];
+        assert_eq!(cache_entries(satellite.get_simulation_cache_stats().await.unwrap()), 4);
+
+        // Well under budget: nothing happens
+        assert!(satellite.check_memory_pressure().await.is_none());
+
+        probe.used_bytes.store(950, std::sync::atomic::Ordering::SeqCst);
+        let report = satellite.check_memory_pressure().await.expect("pressure detected");
+        assert_eq!(report.shed_simulation_cache_entries, 2);
+        assert_eq!(report.shed_health_records, 2);
+        assert_eq!(report.shed_health_history_entries, 2);
+        assert_eq!(cache_entries(satellite.get_simulation_cache_stats().await.unwrap()), 2);
+
+        let memory_alerts = |alerts: Vec<RiskAlert>| alerts.iter()
+            .filter(|alert| matches!(alert.alert_type, AlertType::MemoryPressure))
+            .map(|alert| alert.occurrence_count)
+            .sum::<u32>();
+        assert_eq!(memory_alerts(satellite.get_alerts(None).await.unwrap()), 1);
+
+        // Monitoring keeps working after shedding
+        satellite.liquidation_monitor.monitor_positions().await;
+        let health = satellite.get_position_health(position_id).await.unwrap();
+        assert!(health.value > rust_decimal::Decimal::ONE);
+        let everything = chrono::Utc::now() - chrono::Duration::days(1)..chrono::Utc::now() + chrono::Duration::days(1);
+        assert_eq!(satellite.liquidation_monitor.get_health_records(&everything).await.len(), 3);
+
+        // Still over the pressure point, but nothing more is shed until usage has come down
+        // below the relief point at 750
+        assert!(satellite.check_memory_pressure().await.is_none());
+        probe.used_bytes.store(800, std::sync::atomic::Ordering::SeqCst);
+        assert!(satellite.check_memory_pressure().await.is_none());
+        probe.used_bytes.store(700, std::sync::atomic::Ordering::SeqCst);
+        assert!(satellite.check_memory_pressure().await.is_none());
+        probe.used_bytes.store(950, std::sync::atomic::Ordering::SeqCst);
+        assert!(satellite.check_memory_pressure().await.is_some());
+        assert_eq!(memory_alerts(satellite.get_alerts(None).await.unwrap()), 2);
+    }
+
+    #[tokio::test]
+    async fn test_positions_are_restored_from_store() {
+        // The store keeps alert logs next to its file
+        let dir = std::env::temp_dir().join(format!("aegis-store-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir(&dir).await.unwrap();
+        let path = dir.join("positions.json");
+        let store: Arc<dyn liquidation::PositionStore> = Arc::new(liquidation::JsonFilePositionStore::open(&path).await.unwrap());
+
+        let satellite = AegisSatellite::with_position_store(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None, store).await.unwrap();
+        // Health ~0.94 raises an alert on add
+        let at_risk_id = satellite.add_position(eth_position("aave", 17_000)).await.unwrap();
+        let removed_id = satellite.add_position(eth_position("aave", 1_000)).await.unwrap();
+        satellite.remove_position(removed_id).await.unwrap();
+        let alert_ids: Vec<uuid::Uuid> = satellite.get_alerts(Some(at_risk_id)).await.unwrap().iter().map(|alert| alert.id).collect();
+        assert!(!alert_ids.is_empty());
+        satellite.acknowledge_alert_with_note(alert_ids[0], "ops", None).await.unwrap();
+        drop(satellite);
+
+        // A fresh satellite over a freshly opened file sees the same book
+        let reopened: Arc<dyn liquidation::PositionStore> = Arc::new(liquidation::JsonFilePositionStore::open(&path).await.unwrap());
+        let restarted = AegisSatellite::with_position_store(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None, reopened).await.unwrap();
+        assert_eq!(restarted.get_statistics().total_positions, 1);
+        assert!(restarted.get_position_health(at_risk_id).await.is_ok());
+        assert!(restarted.get_position_health(removed_id).await.is_err());
+        let restored = restarted.get_alerts(Some(at_risk_id)).await.unwrap();
+        assert!(alert_ids.iter().all(|id| restored.iter().any(|alert| alert.id == *id)));
+        assert!(restored.iter().any(|alert| alert.id == alert_ids[0] && alert.acknowledged));
+        let audit: Vec<(monitoring::AlertAuditAction, String)> = restarted.get_alert_audit(alert_ids[0]).into_iter()
+            .map(|entry| (entry.action, entry.actor))
+            .collect();
+        assert_eq!(audit, vec![
+            (monitoring::AlertAuditAction::Raised, monitoring::SYSTEM_ACTOR.to_string()),
+            (monitoring::AlertAuditAction::Acknowledged, "ops".to_string()),
+        ]);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    /// Keeps everything in memory but cannot delete
+    struct UndeletableStore;
+
+    #[async_trait::async_trait]
+    impl liquidation::PositionStore for UndeletableStore {
+        async fn save(&self, _position: &Position) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        fn load_all(&self) -> futures::stream::BoxStream<'_, Result<Position, Box<dyn std::error::Error + Send + Sync>>> {
+            Box::pin(futures::stream::empty())
+        }
+
+        async fn delete(&self, _position_id: PositionId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err("store is read-only".into())
+        }
+
+        async fn save_alert(&self, _alert: &RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        async fn load_alerts(&self) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Vec::new())
+        }
+
+        async fn append_audit_entry(&self, _entry: &monitoring::AlertAuditEntry) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        async fn load_audit_entries(&self) -> Result<Vec<monitoring::AlertAuditEntry>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_removal_keeps_position_monitored() {
+        let satellite = AegisSatellite::with_position_store(
+            Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None, Arc::new(UndeletableStore),
+        ).await.unwrap();
+        let position_id = satellite.add_position(eth_position("aave", 1_000)).await.unwrap();
+
+        let result = satellite.remove_position(position_id).await;
+        assert!(matches!(result, Err(PositionError::Persistence { id, .. }) if id == position_id));
+        assert_eq!(satellite.get_statistics().total_positions, 1);
+        assert!(satellite.get_position_health(position_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_correlation_spike_is_alerted() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        assert!(satellite.check_correlation_regime().await.is_none());
+
+        // Hourly returns correlated at ~0.2 that move together at ~0.9 over the last three days
+        let end = chrono::Utc::now();
+        let mut eth = vec![100.0];
+        let mut btc = vec![100.0];
+        for i in 1..242 {
+            let common = 0.01 * (0.7 * i as f64).sin();
+            let own = 0.01 * (1.9 * i as f64 + 1.0).sin();
+            let rho: f64 = if i > 168 { 0.9 } else { 0.2 };
+            eth.push(eth[i - 1] * (1.0 + common));
+            btc.push(btc[i - 1] * (1.0 + rho * common + (1.0 - rho * rho).sqrt() * own));
+        }
+        for (symbol, prices) in [("ETH", eth), ("BTC", btc)] {
+            satellite.add_correlation_asset(risk::Asset {
+                symbol: symbol.to_string(),
+                name: symbol.to_string(),
+                asset_type: risk::AssetType::Cryptocurrency,
+                price_history: prices.iter().enumerate()
+                    .map(|(i, price)| risk::PricePoint {
+                        timestamp: end - chrono::Duration::hours(241 - i as i64),
+                        price: *price,
+                        volume: 1_000.0,
+                        market_cap: None,
+                    })
+                    .collect(),
+                volatility: 0.0,
+                beta: 1.0,
+                market_cap: None,
+            }).await.unwrap();
+        }
+
+        let alert = satellite.check_correlation_regime().await.expect("spike detected");
+        assert!(matches!(alert.alert_type, AlertType::CorrelationSpike));
+        assert!(alert.position_id.is_nil());
+        let alerts = satellite.get_alerts(None).await.unwrap();
+        assert!(alerts.iter().any(|a| a.id == alert.id));
+
+        // A spike that persists isn't alerted again on the next sweep
+        assert!(satellite.check_correlation_regime().await.is_none());
+        let spikes = satellite.get_alerts(None).await.unwrap().iter()
+            .filter(|a| matches!(a.alert_type, AlertType::CorrelationSpike))
+            .map(|a| a.occurrence_count)
+            .sum::<u32>();
+        assert_eq!(spikes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_assets_sample_the_price_feed() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let now = chrono::Utc::now();
+        for (symbol, age_hours) in [("ETH", 2), ("BTC", 0)] {
+            satellite.add_correlation_asset(risk::Asset {
+                symbol: symbol.to_string(),
+                name: symbol.to_string(),
+                asset_type: risk::AssetType::Cryptocurrency,
+                price_history: vec![risk::PricePoint {
+                    timestamp: now - chrono::Duration::hours(age_hours),
+                    price: 100.0,
+                    volume: 1_000.0,
+                    market_cap: None,
+                }],
+                volatility: 0.0,
+                beta: 1.0,
+                market_cap: None,
+            }).await.unwrap();
+        }
+
+        satellite.check_correlation_regime().await;
+
+        // ETH's last price is two hours old, so the feed's $2000 is recorded; BTC's is current
+        let eth = satellite.correlation_analysis.get_asset("ETH").await.unwrap();
+        assert_eq!(eth.price_history.iter().map(|p| p.price).collect::<Vec<_>>(), vec![100.0, 2000.0]);
+        let btc = satellite.correlation_analysis.get_asset("BTC").await.unwrap();
+        assert_eq!(btc.price_history.len(), 1);
+    }
+
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const WBTC: &str = "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599";
+
+    /// Prices WETH and WBTC by contract address and rejects anything else
+    struct AddressKeyedPriceFeed;
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for AddressKeyedPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = match token_address.as_str() {
+                WETH => Decimal::from(2000),
+                WBTC => Decimal::from(60_000),
+                other => return Err(format!("Unknown token {}", other).into()),
+            };
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd,
+                timestamp: chrono::Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_correlation_assets_are_priced_by_token_address() {
+        let satellite = AegisSatellite::new(Arc::new(AddressKeyedPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let two_hours_ago = chrono::Utc::now() - chrono::Duration::hours(2);
+        for (symbol, token_address) in [("ETH", WETH), ("BTC", WBTC)] {
+            satellite.add_correlation_asset_for_token(risk::Asset {
+                symbol: symbol.to_string(),
+                name: symbol.to_string(),
+                asset_type: risk::AssetType::Cryptocurrency,
+                price_history: vec![risk::PricePoint {
+                    timestamp: two_hours_ago,
+                    price: 100.0,
+                    volume: 1_000.0,
+                    market_cap: None,
+                }],
+                volatility: 0.0,
+                beta: 1.0,
+                market_cap: None,
+            }, &token_address.to_string()).await.unwrap();
+        }
+
+        satellite.check_correlation_regime().await;
+
+        let sampled = |asset: risk::Asset| asset.price_history.iter().map(|p| p.price).collect::<Vec<_>>();
+        assert_eq!(sampled(satellite.correlation_analysis.get_asset("ETH").await.unwrap()), vec![100.0, 2000.0]);
+        assert_eq!(sampled(satellite.correlation_analysis.get_asset("BTC").await.unwrap()), vec![100.0, 60_000.0]);
+    }
+
+    #[tokio::test]
+    async fn test_simulation_positions_use_position_data() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let now = chrono::Utc::now();
+        let (eth, mut eth_token) = token("ETH", 10);
+        eth_token.entry_price_usd = Some(Decimal::from(1500));
+        let position = Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([(eth, eth_token), token("USDC", 5_000)]),
+            debt_tokens: HashMap::from([token("DAI", 8_000)]),
+            created_at: now,
+            updated_at: now,
+            risk_overrides: None,
+        };
+        let position_id = satellite.add_position(position.clone()).await.unwrap();
+        let health = satellite.get_position_health(position_id).await.unwrap();
+
+        let legs = satellite.convert_positions_to_simulation(&[position_id, uuid::Uuid::new_v4()]).await.unwrap();
+        assert_eq!(legs.len(), 2);
+
+        // $20,000 of ETH and $5,000 of USDC split the $8,000 DAI debt 80/20
+        let (eth, usdc) = (&legs[0], &legs[1]);
+        assert_eq!(eth.token_address, "ETH");
+        assert_eq!(eth.quantity, position.collateral_tokens["ETH"].amount.to_f64().unwrap());
+        assert_eq!(eth.current_price, 2000.0);
+        assert_eq!(eth.entry_price, 1500.0);
+        assert_eq!(eth.collateral_value, 20_000.0);
+        assert!((eth.debt_value - 6_400.0).abs() < 1e-9);
+
+        assert_eq!(usdc.token_address, "USDC");
+        assert_eq!(usdc.quantity, 5_000.0);
+        assert_eq!(usdc.entry_price, usdc.current_price);
+        assert_eq!(usdc.collateral_value, 5_000.0);
+        assert!((usdc.debt_value - 1_600.0).abs() < 1e-9);
+
+        for leg in &legs {
+            assert_eq!(leg.health_factor, health.value.to_f64().unwrap());
+            assert_eq!(leg.liquidation_threshold, health.liquidation_threshold.to_f64().unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_simulation_keeps_debt_only_positions_from_one_price_fetch() {
+        let feed = Arc::new(RecordingPriceFeed { requests: std::sync::Mutex::new(Vec::new()) });
+        let satellite = AegisSatellite::new(feed.clone(), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let now = chrono::Utc::now();
+        let position_id = satellite.add_position(Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 0)]),
+            debt_tokens: HashMap::from([token("DAI", 3_000), token("USDC", 1_000)]),
+            created_at: now,
+            updated_at: now,
+            risk_overrides: None,
+        }).await.unwrap();
+        feed.requests.lock().unwrap().clear();
+
+        let legs = satellite.convert_positions_to_simulation(&[position_id]).await.unwrap();
+        // Each token priced once, for the legs and their health factor alike
+        assert_eq!(feed.requests.lock().unwrap().len(), 3);
+
+        // The worthless ETH leg, then the debt as it stands in each token
+        assert_eq!(legs.iter().map(|leg| leg.token_address.as_str()).collect::<Vec<_>>(), vec!["DAI", "ETH", "USDC"]);
+        assert_eq!(legs.iter().map(|leg| leg.debt_value).sum::<f64>(), 4_000.0);
+        let dai = &legs[0];
+        assert_eq!(dai.collateral_value, 0.0);
+        assert_eq!(dai.debt_value, 3_000.0);
+        assert_eq!(dai.debt_tokens, HashMap::from([("DAI".to_string(), 3_000.0)]));
+        assert_eq!(legs[1].debt_value, 0.0);
+    }
+
+    /// A span's name and the fields recorded on it
+    type CapturedSpan = (String, HashMap<String, String>);
+
+    /// Records every span created and the fields recorded on it, in creation order
+    #[derive(Clone, Default)]
+    struct SpanCapture {
+        spans: Arc<std::sync::Mutex<Vec<CapturedSpan>>>,
+        /// Span ids are reused once a span closes, so each id maps to its latest span
+        indices: Arc<std::sync::Mutex<HashMap<u64, usize>>>,
+    }
+
+    struct FieldRecorder<'a>(&'a mut HashMap<String, String>);
+
+    impl tracing::field::Visit for FieldRecorder<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanCapture {
+        fn on_new_span(&self, attrs: &tracing::span::Attributes<'_>, id: &tracing::span::Id, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let mut fields = HashMap::new();
+            attrs.record(&mut FieldRecorder(&mut fields));
+            let mut spans = self.spans.lock().unwrap();
+            self.indices.lock().unwrap().insert(id.into_u64(), spans.len());
+            spans.push((attrs.metadata().name().to_string(), fields));
+        }
+
+        fn on_record(&self, id: &tracing::span::Id, values: &tracing::span::Record<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+            if let Some(index) = self.indices.lock().unwrap().get(&id.into_u64()) {
+                values.record(&mut FieldRecorder(&mut self.spans.lock().unwrap()[*index].1));
+            }
+        }
+    }
+
+    impl SpanCapture {
+        fn named(&self, name: &str) -> Vec<HashMap<String, String>> {
+            self.spans.lock().unwrap().iter()
+                .filter(|(span_name, _)| span_name == name)
+                .map(|(_, fields)| fields.clone())
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_emits_trace_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = SpanCapture::default();
+        // The test runtime is single-threaded, so a thread-local default sees every span
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let position_id = satellite.add_position(Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 17_000)]),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            risk_overrides: None,
+        }).await.unwrap();
+        satellite.liquidation_monitor.monitor_positions().await;
+
+        let health_spans = capture.named("calculate_health");
+        assert_eq!(health_spans.len(), 2);
+        assert!(health_spans.iter().all(|fields| {
+            fields["position_id"] == position_id.to_string() && fields["protocol"] == "aave"
+        }));
+        let monitor_spans = capture.named("monitor_positions");
+        assert_eq!(monitor_spans.len(), 1);
+        assert_eq!(monitor_spans[0]["positions_monitored"], "1");
+        assert_eq!(monitor_spans[0]["alerts"], "1");
+
+        let positions = satellite.convert_positions_to_simulation(&[position_id]).await.unwrap();
+        satellite.run_stress_test(&positions, &SimulationScenario::BlackSwan).await.unwrap();
+        let stress_spans = capture.named("run_stress_test");
+        assert_eq!(stress_spans.len(), 1);
+        assert_eq!(stress_spans[0]["scenario"], "black_swan");
+        assert_eq!(stress_spans[0]["positions"], "1");
+        assert_eq!(stress_spans[0]["cached"], "false");
+
+        let transaction = security::TransactionData {
+            hash: "0xswap".to_string(),
+            from_address: "0xuser".to_string(),
+            to_address: "0xpool".to_string(),
+            value: Decimal::from(10),
+            gas_used: 150_000,
+            gas_price: Decimal::from(30),
+            timestamp: chrono::Utc::now(),
+            function_selector: Some("0x7ff36ab5".to_string()),
+            input_data: "0x7ff36ab5".to_string(),
+            success: true,
+            block_number: 1000,
+            transaction_index: 0,
+            raw_transaction: None,
+        };
+        let mev = security::MevProtectionSystem::new(security::MevProtectionConfig::default());
+        let threats = mev.analyze_transaction_mev_risk(&transaction, &[]).await.unwrap();
+        let mev_spans = capture.named("detect_mev");
+        assert_eq!(mev_spans.len(), 1);
+        assert_eq!(mev_spans[0]["transaction_hash"], "0xswap");
+        assert_eq!(mev_spans[0]["threats"], threats.len().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_scan_transaction_raises_exploit_signature_alerts() {
+        let path = std::env::temp_dir().join(format!("aegis-signatures-{}.json", uuid::Uuid::new_v4()));
+        tokio::fs::write(&path, r#"{"signatures": [{
+            "id": "zero_from",
+            "selector": "0x23b872dd",
+            "calldata_pattern": "^0x23b872dd0{64}",
+            "description": "transferFrom out of the zero address",
+            "severity": "Critical"
+        }]}"#).await.unwrap();
+        let signatures = Arc::new(security::ExploitSignatureDb::load(&path).await.unwrap());
+        let config = AegisConfig::builder().exploit_signatures(signatures).build().unwrap();
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), Some(config)).await.unwrap();
+
+        let transaction = security::TransactionData {
+            hash: "0xdrain".to_string(),
+            from_address: "0xattacker".to_string(),
+            to_address: "0xvault".to_string(),
+            value: Decimal::ZERO,
+            gas_used: 80_000,
+            gas_price: Decimal::from(30),
+            timestamp: chrono::Utc::now(),
+            function_selector: None,
+            input_data: format!("0x23b872dd{:0>64}{:0>64}{:0>64}", "0", "beef", "64"),
+            success: true,
+            block_number: 1,
+            transaction_index: 0,
+            raw_transaction: None,
+        };
+        let detections = satellite.scan_transaction(&transaction).await;
+        assert_eq!(detections.len(), 1);
+
+        let alerts = satellite.get_alerts(None).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].id, detections[0].id);
+        assert_eq!(alerts[0].alert_type, AlertType::ContractVulnerability);
+        assert_eq!(alerts[0].risk_level, RiskLevel::Emergency);
+
+        // Without signatures configured nothing is scanned
+        let unconfigured = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        assert!(unconfigured.scan_transaction(&transaction).await.is_empty());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[test]
+    fn test_config_builder_builds_valid_config() {
+        let config = AegisConfig::builder()
+            .monitoring_interval_secs(10)
+            .monitoring_batches(5)
+            .enable_automated_actions(false)
+            .quarantine_after_missing_prices(None)
+            .monitoring_jitter(MonitoringJitter { max_start_delay_ms: 2_000, seed: Some(7) })
+            .health_history_capacity(64)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.monitoring_interval_secs, 10);
+        assert_eq!(config.monitoring_batches, 5);
+        assert!(!config.enable_automated_actions);
+        assert_eq!(config.quarantine_after_missing_prices, None);
+        assert_eq!(config.health_history_capacity, 64);
+        // Untouched fields keep their defaults
+        assert_eq!(config.max_concurrent_positions, AegisConfig::default().max_concurrent_positions);
+        assert!(AegisConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_builder_rejects_invalid_values() {
+        let mut budget = monitoring::MemoryBudget::new(1024);
+        budget.shed_fraction = 1.5;
+        let correlation = risk::CorrelationAnalysisConfig {
+            correlation_threshold_high: 0.9,
+            correlation_threshold_critical: 0.8,
+            ..Default::default()
+        };
+
+        let cases = [
+            (AegisConfig::builder().monitoring_interval_secs(0), ConfigError::InvalidMonitoringInterval),
+            (AegisConfig::builder().max_concurrent_positions(0), ConfigError::InvalidMaxConcurrentPositions),
+            (AegisConfig::builder().quarantine_after_missing_prices(Some(0)), ConfigError::InvalidQuarantineThreshold),
+            (
+                AegisConfig::builder().monitoring_batches(0),
+                ConfigError::InvalidMonitoringBatches { batches: 0, interval_secs: 30 },
+            ),
+            (
+                AegisConfig::builder().monitoring_interval_secs(1).monitoring_batches(1001),
+                ConfigError::InvalidMonitoringBatches { batches: 1001, interval_secs: 1 },
+            ),
+            (AegisConfig::builder().health_history_capacity(0), ConfigError::InvalidHealthHistoryCapacity),
+            (AegisConfig::builder().max_concurrent_health_calcs(0), ConfigError::InvalidMaxConcurrentHealthCalcs),
+            (
+                AegisConfig::builder().monitoring_interval_secs(1).monitoring_jitter(MonitoringJitter { max_start_delay_ms: 1_500, seed: None }),
+                ConfigError::JitterExceedsInterval { max_start_delay_ms: 1_500, interval_secs: 1 },
+            ),
+            (
+                AegisConfig::builder().memory_budget(budget),
+                ConfigError::InvalidMemoryBudget { message: "shed fraction 1.5 is outside (0, 1]".to_string() },
+            ),
+            (
+                AegisConfig::builder().correlation_analysis(correlation),
+                ConfigError::InvalidCorrelationThresholds { high: 0.9, critical: 0.8 },
+            ),
+            (
+                AegisConfig::builder().price_circuit_breaker(Some(liquidation::PriceCircuitBreakerConfig {
+                    max_move: rust_decimal::Decimal::ZERO,
+                    ..Default::default()
+                })),
+                ConfigError::InvalidPriceCircuitBreaker,
+            ),
+        ];
+
+        for (builder, expected) in cases {
+            assert_eq!(builder.build().unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn test_config_loaded_from_toml_with_env_override() {
+        let path = std::env::temp_dir().join(format!("aegis-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"
+monitoring_interval_secs = 15
+enable_automated_actions = false
+monitoring_batches = 3
+
+[monitoring_jitter]
+max_start_delay_ms = 500
+"#).unwrap();
+
+        let config = AegisConfig::from_toml(&path).unwrap();
+        assert_eq!(config.monitoring_interval_secs, 15);
+        assert!(!config.enable_automated_actions);
+        assert_eq!(config.monitoring_batches, 3);
+        assert_eq!(config.monitoring_jitter.as_ref().map(|jitter| jitter.max_start_delay_ms), Some(500));
+        assert_eq!(config.health_history_capacity, AegisConfig::default().health_history_capacity);
+
+        // A prefix unique to this test keeps parallel tests from seeing the variables
+        let prefix = format!("AEGIS_TEST_{}_", uuid::Uuid::new_v4().simple()).to_uppercase();
+        std::env::set_var(format!("{}MONITORING_INTERVAL_SECS", prefix), "5");
+        std::env::set_var(format!("{}MONITORING_JITTER__SEED", prefix), "42");
+        std::env::set_var(format!("{}QUARANTINE_AFTER_MISSING_PRICES", prefix), "0");
+        // Variables that aren't settings are left for whoever else uses the prefix
+        std::env::set_var(format!("{}LOG_LEVEL", prefix), "debug");
+        let config = AegisConfig::from_toml_and_env(&path, &prefix).unwrap();
+        assert_eq!(config.monitoring_interval_secs, 5);
+        assert!(!config.enable_automated_actions);
+        assert_eq!(config.monitoring_jitter.as_ref().and_then(|jitter| jitter.seed), Some(42));
+        assert_eq!(config.quarantine_after_missing_prices, None);
+        // Without the file the jitter section is incomplete
+        match AegisConfig::from_env(&prefix) {
+            Err(ConfigError::Load { message }) => assert!(message.contains("missing field `max_start_delay_ms`"), "{}", message),
+            other => panic!("expected a load error, got {:?}", other.map(|config| config.monitoring_interval_secs)),
+        }
+
+        // The merged result is validated, wherever each value came from
+        std::env::set_var(format!("{}MONITORING_BATCHES", prefix), "0");
+        assert!(matches!(
+            AegisConfig::from_toml_and_env(&path, &prefix),
+            Err(ConfigError::InvalidMonitoringBatches { batches: 0, .. })
+        ));
+        assert!(matches!(AegisConfig::from_toml(path.with_extension("missing")), Err(ConfigError::Load { .. })));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_config_changes_monitoring_cadence() {
+        let satellite = AegisSatellite::new(
+            Arc::new(StaticPriceFeed),
+            Arc::new(SucceedingTradeExecutor),
+            // Automation off keeps the position manager from recording health of its own
+            Some(AegisConfig { monitoring_interval_secs: 3600, enable_automated_actions: false, ..AegisConfig::default() }),
+        ).await.unwrap();
+        assert!(!satellite.position_manager.get_config().await.enabled);
+        let id = satellite.add_position(Position {
+            id: uuid::Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 1_000)]),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            risk_overrides: None,
+        }).await.unwrap();
+        let since = chrono::Utc::now() - chrono::Duration::hours(1);
+        let passes = || satellite.get_health_history(id, since).len();
+
+        // Sleeps below only move the paused clock, firing each timer in order once every
+        // task has gone idle, so tick counts are exact and no real time passes
+        tokio::time::pause();
+        satellite.start().await.unwrap();
+        // The first tick is immediate, the next would be an hour away
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        let after_start = passes();
+
+        let mut fast = satellite.get_config().await;
+        fast.monitoring_interval_secs = 1;
+        satellite.update_config(fast.clone()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+        let after_fast = passes();
+        assert_eq!(after_fast - after_start, 2);
+
+        let slow = AegisConfig { monitoring_interval_secs: 3600, ..fast };
+        satellite.update_config(slow.clone()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert_eq!(passes(), after_fast);
+
+        assert_eq!(
+            satellite.update_config(AegisConfig { monitoring_interval_secs: 0, ..slow }).await,
+            Err(ConfigError::InvalidMonitoringInterval)
+        );
+        assert_eq!(satellite.get_config().await.monitoring_interval_secs, 3600);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_start_hands_over_metrics_server() {
+        let config = AegisConfig::builder()
+            .metrics_exporter(metrics::PrometheusExporterConfig {
+                bind_address: std::net::SocketAddr::from(([127, 0, 0, 1], 0)),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), Some(config)).await.unwrap();
+        satellite.start().await.unwrap();
+
+        let server = satellite.take_metrics_server().unwrap();
+        assert!(satellite.take_metrics_server().is_none());
+        let url = format!("http://{}/metrics", server.local_address());
+        assert_eq!(reqwest::get(&url).await.unwrap().status(), 200);
+        server.shutdown().await;
+        assert!(reqwest::get(&url).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_flushes_health_history_for_the_next_start() {
+        let backend = Arc::new(liquidation::InMemoryPersistenceBackend::new());
+        let config = AegisConfig::builder()
+            .health_history_persistence(liquidation::HealthHistoryPersistence {
+                backend: backend.clone(),
+                flush_interval: std::time::Duration::from_secs(3600),
+                retention: 100,
+            })
+            .build()
+            .unwrap();
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), Some(config.clone())).await.unwrap();
+        let position_id = satellite.add_position(eth_position("aave", 10000)).await.unwrap();
+        satellite.liquidation_monitor.monitor_positions().await;
+        satellite.stop().await.unwrap();
+
+        let since = chrono::Utc::now() - chrono::Duration::hours(1);
+        let charted = |satellite: &AegisSatellite| satellite.liquidation_monitor.get_health_history(position_id, since)
+            .into_iter()
+            .map(|(at, health)| (at, health.value))
+            .collect::<Vec<_>>();
+        assert!(!charted(&satellite).is_empty());
+
+        let restarted = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), Some(config)).await.unwrap();
+        assert_eq!(charted(&restarted), charted(&satellite));
+    }
+
+    /// Write `contents` to `path` with a modification time `seconds` past the epoch, so
+    /// each write is seen as a change however coarse the filesystem's timestamps are
+    fn write_config(path: &std::path::Path, contents: &str, seconds: u64) {
+        std::fs::write(path, contents).unwrap();
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_config_file_reloads_on_change() {
+        let path = std::env::temp_dir().join(format!("aegis-config-{}.toml", uuid::Uuid::new_v4()));
+        write_config(&path, "monitoring_interval_secs = 60\n", 1);
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        // As above, sleeps only move the paused clock
+        tokio::time::pause();
+        let watcher = satellite.watch_config_file(&path, std::time::Duration::from_millis(50));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(satellite.position_manager.get_config().await.enabled);
+
+        write_config(&path, "monitoring_interval_secs = 15\nenable_automated_actions = false\n", 2);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let config = satellite.get_config().await;
+        assert_eq!(config.monitoring_interval_secs, 15);
+        assert!(!config.enable_automated_actions);
+        assert!(!satellite.position_manager.get_config().await.enabled);
+
+        // An invalid file leaves the running config alone
+        write_config(&path, "monitoring_interval_secs = 0\n", 3);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(satellite.get_config().await.monitoring_interval_secs, 15);
+
+        watcher.abort();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_positions_csv_round_trip() {
+        let csv = concat!(
+            "position_id,protocol,created_at,updated_at,side,token_address,amount,value_usd,price_per_token,entry_price_usd,",
+            "safe_health_threshold,warning_health_threshold,critical_health_threshold,emergency_health_threshold,",
+            "max_position_size_usd,max_protocol_exposure_percent,max_price_age_secs\n",
+            "1b4e28ba-2fa1-41d2-883f-0016d3cca427,aave,2024-03-01T00:00:00Z,2024-03-02T12:00:00Z,collateral,ETH,10,20000,2000,1800.50,,,,,,,\n",
+            "1b4e28ba-2fa1-41d2-883f-0016d3cca427,aave,2024-03-01T00:00:00Z,2024-03-02T12:00:00Z,debt,USDC,5000,5000,1,,,,,,,,\n",
+            "6fa459ea-ee8a-4ca4-894e-db77e160355e,compound,2024-03-05T08:30:00Z,2024-03-05T08:30:00Z,collateral,ETH,2.5,5000,2000,,,,,,,,\n",
+            "6fa459ea-ee8a-4ca4-894e-db77e160355e,compound,2024-03-05T08:30:00Z,2024-03-05T08:30:00Z,collateral,WBTC,0.1,6000,60000,58000,,,,,,,\n",
+            "6fa459ea-ee8a-4ca4-894e-db77e160355e,compound,2024-03-05T08:30:00Z,2024-03-05T08:30:00Z,debt,DAI,3000,3000,1,,,,,,,,\n",
+            "0e3c6f1a-9d2b-4c8e-a1f7-5b6d4e3c2a10,makerdao,2024-03-09T16:45:10.250Z,2024-03-10T00:00:00Z,collateral,ETH,4,8000,2000,,,,,,,,\n",
+            "0e3c6f1a-9d2b-4c8e-a1f7-5b6d4e3c2a10,makerdao,2024-03-09T16:45:10.250Z,2024-03-10T00:00:00Z,debt,DAI,2000,2000,1,,,,,,,,\n",
+        );
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+
+        let report = satellite.import_positions_csv(csv.as_bytes()).await.unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.imported.len(), 3);
+        let compound = satellite.get_position("6fa459ea-ee8a-4ca4-894e-db77e160355e".parse().unwrap()).unwrap();
+        assert_eq!(compound.collateral_tokens["WBTC"].amount, Decimal::new(1, 1));
+        assert_eq!(compound.collateral_tokens["WBTC"].entry_price_usd, Some(Decimal::from(58000)));
+
+        let mut exported = Vec::new();
+        assert_eq!(satellite.export_positions_csv(&mut exported).unwrap(), 3);
+        assert_eq!(String::from_utf8(exported).unwrap(), csv);
+
+        // Re-importing reports every position as a duplicate without aborting
+        let report = satellite.import_positions_csv(csv.as_bytes()).await.unwrap();
+        assert!(report.imported.is_empty());
+        let lines: Vec<usize> = report.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 4, 7]);
+        assert!(report.errors.iter().all(|error| error.message.contains("already exists")));
+    }
+
+    fn position_json(edit: impl FnOnce(&mut serde_json::Value)) -> String {
+        let mut payload = serde_json::json!({
+            "id": uuid::Uuid::new_v4(),
+            "protocol": "aave",
+            "collateral_tokens": {
+                "ETH": { "token_address": "ETH", "amount": 10, "value_usd": 20000, "price_per_token": 2000, "entry_price_usd": 1800 }
+            },
+            "debt_tokens": {
+                "USDC": { "token_address": "USDC", "amount": 5000, "value_usd": 5000, "price_per_token": 1 }
+            },
+            "created_at": "2024-03-01T00:00:00Z",
+            "updated_at": "2024-03-01T00:00:00Z",
+            "risk_overrides": {
+                "safe_health_threshold": 1.8,
+                "warning_health_threshold": 1.5,
+                "critical_health_threshold": 1.2,
+                "emergency_health_threshold": 1.1,
+                "max_position_size_usd": 500000,
+                "max_protocol_exposure_percent": 25
+            }
+        });
+        edit(&mut payload);
+        payload.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_add_position_json_accepts_valid_payload() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+
+        let position_id = satellite.add_position_json(&position_json(|_| {})).await.unwrap();
+        let position = satellite.get_position(position_id).unwrap();
+        assert_eq!(position.protocol, "aave");
+        assert_eq!(position.collateral_tokens["ETH"].entry_price_usd, Some(Decimal::from(1800)));
+        assert_eq!(position.risk_overrides.unwrap().critical_health_threshold, Decimal::new(12, 1));
+
+        // Every field a position serializes to is described by the schema
+        let schema = types::position_schema();
+        let serialized = serde_json::to_value(satellite.get_position(position_id).unwrap()).unwrap();
+        for field in serialized.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{} missing from schema", field);
+        }
+        for field in schema["required"].as_array().unwrap() {
+            assert!(serialized.get(field.as_str().unwrap()).is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_position_json_rejects_invalid_payloads() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        let cases: Vec<(String, &str)> = vec![
+            (position_json(|p| p["protocol"] = serde_json::json!("  ")), "protocol: must not be empty"),
+            (
+                position_json(|p| p["collateral_tokens"]["ETH"]["amount"] = serde_json::json!(-1)),
+                "collateral_tokens.ETH.amount: must not be negative, got -1",
+            ),
+            (
+                position_json(|p| p["debt_tokens"]["USDC"]["entry_price_usd"] = serde_json::json!(-0.5)),
+                "debt_tokens.USDC.entry_price_usd: must not be negative",
+            ),
+            (
+                position_json(|p| p["debt_tokens"]["USDC"]["token_address"] = serde_json::json!("DAI")),
+                "debt_tokens.USDC.token_address: must match its key",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["warning_health_threshold"] = serde_json::json!(2.5)),
+                "risk_overrides.warning_health_threshold: must be within (0, 2], got 2.5",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["emergency_health_threshold"] = serde_json::json!(0)),
+                "risk_overrides.emergency_health_threshold: must be within (0, 2]",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["critical_health_threshold"] = serde_json::json!(1.6)),
+                "risk_overrides.critical_health_threshold: must be below warning_health_threshold (1.5), got 1.6",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["max_position_size_usd"] = serde_json::json!(-1)),
+                "risk_overrides.max_position_size_usd: must not be negative, got -1",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["max_protocol_exposure_percent"] = serde_json::json!(0)),
+                "risk_overrides.max_protocol_exposure_percent: must be within (0, 100], got 0",
+            ),
+            (
+                position_json(|p| p["risk_overrides"]["max_protocol_exposure_percent"] = serde_json::json!(150)),
+                "risk_overrides.max_protocol_exposure_percent: must be within (0, 100], got 150",
+            ),
+            (position_json(|p| { p.as_object_mut().unwrap().remove("debt_tokens"); }), "malformed position JSON: missing field `debt_tokens`"),
+            ("{\"id\": ".to_string(), "malformed position JSON"),
+        ];
+
+        for (payload, expected) in cases {
+            match satellite.add_position_json(&payload).await {
+                Err(PositionError::Invalid { message }) => assert!(message.starts_with(expected), "{:?} does not start with {:?}", message, expected),
+                other => panic!("expected {:?} to be rejected, got {:?}", expected, other),
+            }
+        }
+        assert!(satellite.liquidation_monitor.list_positions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_positions_by_risk_orders_riskiest_first() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+
+        // $16,000 of threshold-weighted Aave collateral: health 2.0, 1.33, 1.14, 1.07 and 1.6
+        let mut ids = Vec::new();
+        for debt in [8_000, 12_000, 14_000, 15_000, 10_000] {
+            ids.push(satellite.add_position(eth_position("aave", debt)).await.unwrap());
+        }
+        let compound_id = satellite.add_position(eth_position("compound", 1_000)).await.unwrap();
+
+        let ranked = satellite.list_positions_by_risk(None, Some("aave")).await.unwrap();
+        let order: Vec<PositionId> = ranked.iter().map(|(id, _)| *id).collect();
+        assert_eq!(order, vec![ids[3], ids[2], ids[1], ids[4], ids[0]]);
+        let rated: Vec<&(HealthFactor, RiskLevel)> = ranked.iter().map(|(_, rated)| rated.as_ref().unwrap()).collect();
+        assert!(rated.windows(2).all(|pair| pair[0].0.value <= pair[1].0.value));
+        let levels: Vec<RiskLevel> = rated.iter().map(|(_, level)| level.clone()).collect();
+        assert_eq!(levels, vec![RiskLevel::Critical, RiskLevel::Warning, RiskLevel::Safe, RiskLevel::Safe, RiskLevel::Safe]);
+        // Every position was valued from the same ETH price
+        assert!(rated.iter().all(|(health, _)| health.collateral_value == Decimal::from(20_000)));
+
+        let top = satellite.list_positions_by_risk(Some(2), None).await.unwrap();
+        assert_eq!(top.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![ids[3], ids[2]]);
+        let all = satellite.list_positions_by_risk(None, None).await.unwrap();
+        assert_eq!(all.len(), 6);
+        assert!(all.iter().any(|(id, _)| *id == compound_id));
+    }
+
+    #[tokio::test]
+    async fn test_whatif_leaves_live_positions_untouched() {
+        let satellite = AegisSatellite::new(Arc::new(StaticPriceFeed), Arc::new(SucceedingTradeExecutor), None).await.unwrap();
+        // Health 2.0 and 1.33
+        let healthy = satellite.add_position(eth_position("aave", 8_000)).await.unwrap();
+        let stretched = satellite.add_position(eth_position("aave", 12_000)).await.unwrap();
+        let alerts_before = satellite.get_alerts(None).await.unwrap().len();
+
+        // A hypothetical position at health 1.07 replaces the healthy one
+        let hypothetical = eth_position("aave", 15_000);
+        let result = satellite.whatif(vec![
+            liquidation::PositionChange::Add { position: Box::new(hypothetical.clone()) },
+            liquidation::PositionChange::Remove { position_id: healthy },
+        ]).await.unwrap();
+
+        let close = |actual: Option<Decimal>, expected: Decimal| {
+            actual.is_some_and(|actual| (actual - expected).abs() < Decimal::new(1, 20))
+        };
+        assert_eq!(result.before.position_count, 2);
+        assert_eq!(result.before.total_debt_usd, Decimal::from(20_000));
+        assert!(close(result.before.aggregate_health_factor, Decimal::new(16, 1)));
+        assert_eq!(result.before.positions_at_risk, 0);
+        assert_eq!(result.after.position_count, 2);
+        assert_eq!(result.after.total_debt_usd, Decimal::from(27_000));
+        // $32,000 of threshold-weighted collateral over $27,000 of debt
+        assert!(close(result.after.aggregate_health_factor, Decimal::from(32_000) / Decimal::from(27_000)));
+        assert!(close(result.after.lowest_health_factor, Decimal::from(16_000) / Decimal::from(15_000)));
+        assert_eq!(result.after.positions_at_risk, 1);
+        assert!((result.after.concentration_index - 0.5).abs() < 1e-12);
+        assert_eq!(result.after.protocol_exposures.len(), 1);
+        assert_eq!(result.after.protocol_exposures[0].exposure_usd, Decimal::from(40_000));
+
+        let changes: HashMap<PositionId, (Option<Decimal>, Option<Decimal>)> = result.position_changes.iter()
+            .map(|change| (change.position_id, (change.before, change.after)))
+            .collect();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[&healthy], (Some(Decimal::from(2)), None));
+        assert_eq!(changes[&hypothetical.id].0, None);
+        assert!(close(changes[&hypothetical.id].1, Decimal::from(16_000) / Decimal::from(15_000)));
+
+        // Nothing was added, removed, recorded or alerted on
+        let mut live: Vec<PositionId> = satellite.liquidation_monitor.list_positions().iter().map(|p| p.id).collect();
+        live.sort();
+        let mut expected = vec![healthy, stretched];
+        expected.sort();
+        assert_eq!(live, expected);
+        assert!(satellite.get_position(hypothetical.id).is_none());
+        assert!(satellite.get_health_history(hypothetical.id, chrono::DateTime::<chrono::Utc>::MIN_UTC).is_empty());
+        assert_eq!(satellite.get_alerts(None).await.unwrap().len(), alerts_before);
+
+        let unknown = satellite.whatif(vec![liquidation::PositionChange::Remove { position_id: uuid::Uuid::new_v4() }]).await;
+        assert!(matches!(unknown, Err(CalculationError::InvalidPosition { .. })));
+        let mut duplicate = eth_position("aave", 1_000);
+        duplicate.id = stretched;
+        let duplicate = satellite.whatif(vec![liquidation::PositionChange::Add { position: Box::new(duplicate) }]).await;
+        assert!(matches!(duplicate, Err(CalculationError::InvalidPosition { .. })));
+    }
+}
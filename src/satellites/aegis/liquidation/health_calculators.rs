@@ -1,9 +1,10 @@
 use crate::types::{
-    HealthCalculator, HealthFactor, Position, PriceData, TokenAddress, CalculationError
+    HealthCalculator, HealthFactor, Position, PriceData, Protocol, TokenAddress, CalculationError
 };
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use chrono::Utc;
 
 pub struct AaveHealthCalculator {
@@ -78,6 +79,76 @@ impl AaveHealthCalculator {
     }
 }
 
+/// Aave v3 health factor using the per-asset liquidation thresholds of a [`Protocol`]:
+/// `sum(collateral_i * threshold_i) / total_debt`.
+pub struct AaveV3HealthCalculator {
+    protocol: Protocol,
+}
+
+impl AaveV3HealthCalculator {
+    pub fn new(protocol: Protocol) -> Self {
+        Self { protocol }
+    }
+
+    /// USD value of `collateral_token` a liquidator receives for repaying `debt_repaid_usd`
+    pub fn collateral_seized_usd(&self, collateral_token: &str, debt_repaid_usd: Decimal) -> Decimal {
+        debt_repaid_usd * (Decimal::ONE + self.protocol.liquidation_bonus_for(collateral_token))
+    }
+}
+
+impl HealthCalculator for AaveV3HealthCalculator {
+    fn calculate_health(&self, position: &Position, prices: &HashMap<TokenAddress, PriceData>) -> Result<HealthFactor, CalculationError> {
+        let mut total_collateral_value = Decimal::ZERO;
+        let mut weighted_collateral_value = Decimal::ZERO;
+        let mut total_debt_value = Decimal::ZERO;
+
+        for (token_address, token_position) in &position.collateral_tokens {
+            let price_data = prices.get(token_address)
+                .ok_or_else(|| CalculationError::MissingPriceData {
+                    token: token_address.clone()
+                })?;
+
+            let token_value = token_position.amount * price_data.price_usd;
+            total_collateral_value += token_value;
+            weighted_collateral_value += token_value * self.protocol.liquidation_threshold_for(token_address);
+        }
+
+        for (token_address, token_position) in &position.debt_tokens {
+            let price_data = prices.get(token_address)
+                .ok_or_else(|| CalculationError::MissingPriceData {
+                    token: token_address.clone()
+                })?;
+
+            total_debt_value += token_position.amount * price_data.price_usd;
+        }
+
+        let health_factor_value = if total_debt_value > Decimal::ZERO {
+            weighted_collateral_value / total_debt_value
+        } else {
+            Decimal::MAX
+        };
+
+        // Report the collateral-weighted average threshold of this position
+        let liquidation_threshold = if total_collateral_value > Decimal::ZERO {
+            weighted_collateral_value / total_collateral_value
+        } else {
+            self.protocol.liquidation_threshold
+        };
+
+        Ok(HealthFactor {
+            value: health_factor_value,
+            liquidation_threshold,
+            collateral_value: total_collateral_value,
+            debt_value: total_debt_value,
+            calculated_at: Utc::now(),
+        })
+    }
+
+    fn protocol(&self) -> &str {
+        "aave_v3"
+    }
+}
+
 pub struct CompoundHealthCalculator {
     liquidation_incentive: Decimal,
 }
@@ -210,9 +281,55 @@ impl HealthCalculator for MakerDaoHealthCalculator {
     }
 }
 
-pub struct HealthCalculatorFactory;
+/// Registry of health calculators, keyed by lowercase protocol name.
+///
+/// Each registry starts with the built-in protocols; integrations can add their own with
+/// [`HealthCalculatorFactory::register`] without changes to this crate. Registries are not
+/// shared unless the same one is handed to several monitors.
+pub struct HealthCalculatorFactory {
+    calculators: RwLock<HashMap<String, Arc<dyn HealthCalculator>>>,
+}
+
+impl Default for HealthCalculatorFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl HealthCalculatorFactory {
+    pub fn new() -> Self {
+        let mut calculators: HashMap<String, Arc<dyn HealthCalculator>> = HashMap::new();
+        for protocol in Self::supported_protocols() {
+            if let Some(calculator) = Self::create_calculator(protocol) {
+                calculators.insert(protocol.to_string(), Arc::from(calculator));
+            }
+        }
+        Self { calculators: RwLock::new(calculators) }
+    }
+
+    /// Register the calculator used for positions on `protocol`, replacing any existing one
+    pub fn register(&self, protocol: &str, calculator: Box<dyn HealthCalculator>) {
+        self.calculators
+            .write()
+            .unwrap()
+            .insert(protocol.to_lowercase(), Arc::from(calculator));
+    }
+
+    pub fn get(&self, protocol: &str) -> Option<Arc<dyn HealthCalculator>> {
+        let key = match protocol.to_lowercase().as_str() {
+            "maker" => "makerdao".to_string(),
+            other => other.to_string(),
+        };
+        self.calculators.read().unwrap().get(&key).cloned()
+    }
+
+    /// Protocols with a registered calculator, built-in or custom
+    pub fn registered_protocols(&self) -> Vec<String> {
+        let mut protocols: Vec<String> = self.calculators.read().unwrap().keys().cloned().collect();
+        protocols.sort();
+        protocols
+    }
+
     pub fn create_calculator(protocol: &str) -> Option<Box<dyn HealthCalculator>> {
         match protocol.to_lowercase().as_str() {
             "aave" => Some(Box::new(AaveHealthCalculator::new())),
@@ -225,4 +342,89 @@ impl HealthCalculatorFactory {
     pub fn supported_protocols() -> Vec<&'static str> {
         vec!["aave", "compound", "makerdao"]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PositionToken;
+    use uuid::Uuid;
+
+    fn token(address: &str, amount: i64) -> (TokenAddress, PositionToken) {
+        (address.to_string(), PositionToken {
+            token_address: address.to_string(),
+            amount: Decimal::from(amount),
+            value_usd: Decimal::ZERO,
+            price_per_token: Decimal::ZERO,
+            entry_price_usd: None,
+        })
+    }
+
+    fn price(address: &str, price_usd: i64) -> (TokenAddress, PriceData) {
+        (address.to_string(), PriceData {
+            token_address: address.to_string(),
+            price_usd: Decimal::from(price_usd),
+            timestamp: Utc::now(),
+            source: "test".to_string(),
+            confidence: Decimal::ONE,
+        })
+    }
+
+    fn aave_v3_market() -> Protocol {
+        Protocol {
+            id: "aave_v3".to_string(),
+            name: "Aave v3".to_string(),
+            liquidation_threshold: Decimal::new(80, 2),
+            loan_to_value_ratio: Decimal::new(75, 2),
+            supported_tokens: vec!["WETH".to_string(), "WBTC".to_string(), "USDC".to_string()],
+            risk_score: Decimal::from(20),
+            token_liquidation_thresholds: HashMap::from([
+                ("WETH".to_string(), Decimal::new(825, 3)),
+                ("WBTC".to_string(), Decimal::new(78, 2)),
+            ]),
+            token_liquidation_bonuses: HashMap::from([
+                ("WBTC".to_string(), Decimal::new(65, 3)),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_aave_v3_weights_each_collateral_by_its_threshold() {
+        let calculator = AaveV3HealthCalculator::new(aave_v3_market());
+        let position = Position {
+            id: Uuid::new_v4(),
+            protocol: "aave_v3".to_string(),
+            collateral_tokens: HashMap::from([token("WETH", 10), token("WBTC", 1)]),
+            debt_tokens: HashMap::from([token("USDC", 50_000)]),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            risk_overrides: None,
+        };
+        let prices = HashMap::from([price("WETH", 2_000), price("WBTC", 60_000), price("USDC", 1)]);
+
+        let health = calculator.calculate_health(&position, &prices).unwrap();
+        // (20,000 * 0.825 + 60,000 * 0.78) / 50,000
+        assert_eq!(health.value, Decimal::new(1266, 3));
+        assert_eq!(health.collateral_value, Decimal::from(80_000));
+        assert_eq!(health.debt_value, Decimal::from(50_000));
+        // 63,300 / 80,000
+        assert_eq!(health.liquidation_threshold, Decimal::new(79125, 5));
+
+        // A flat 80% threshold would have over-valued the WBTC leg
+        assert_ne!(health.value, Decimal::new(128, 2));
+
+        let mut without_price = prices.clone();
+        without_price.remove("WBTC");
+        assert!(matches!(
+            calculator.calculate_health(&position, &without_price),
+            Err(CalculationError::MissingPriceData { token }) if token == "WBTC"
+        ));
+    }
+
+    #[test]
+    fn test_aave_v3_liquidation_bonus_falls_back_to_zero() {
+        let calculator = AaveV3HealthCalculator::new(aave_v3_market());
+        assert_eq!(calculator.collateral_seized_usd("WBTC", Decimal::from(1_000)), Decimal::from(1_065));
+        assert_eq!(calculator.collateral_seized_usd("WETH", Decimal::from(1_000)), Decimal::from(1_000));
+    }
+}
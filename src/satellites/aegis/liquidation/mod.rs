@@ -1,5 +1,21 @@
 pub mod health_calculators;
 pub mod monitor;
+pub mod persistence;
+pub mod position_csv;
+#[cfg(feature = "postgres")]
+pub mod postgres_store;
+pub mod price_aggregation;
+pub mod price_guard;
+pub mod rebasing;
+pub mod twap;
 
 pub use health_calculators::*;
-pub use monitor::*;
\ No newline at end of file
+pub use monitor::*;
+pub use persistence::*;
+pub use position_csv::*;
+#[cfg(feature = "postgres")]
+pub use postgres_store::PostgresPositionStore;
+pub use price_aggregation::*;
+pub use price_guard::*;
+pub use rebasing::*;
+pub use twap::*;
@@ -1,48 +1,232 @@
 use crate::types::{
     PositionId, Position, HealthFactor, RiskParameters, RiskAlert, RiskLevel, 
-    AlertType, PriceData, TokenAddress, PositionError, CalculationError,
-    HealthCalculator
+    AlertType, PriceData, TokenAddress, PositionError, CalculationError, ProtocolId
 };
 use crate::liquidation::health_calculators::HealthCalculatorFactory;
+use crate::liquidation::rebasing::RebasingValuation;
+use crate::liquidation::price_guard::{PriceDeviationGuard, PriceDeviation, PriceCircuitBreaker, PriceTrip};
+use crate::liquidation::persistence::HealthHistoryPersistence;
+use crate::simulation::DepegScenario;
+use crate::data::VolatilityTracker;
+use crate::monitoring::AegisMetrics;
 use dashmap::DashMap;
-use std::collections::HashMap;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::RwLock;
 use uuid::Uuid;
-use chrono::Utc;
-use tracing::{info, warn, error, debug};
+use chrono::{DateTime, Utc};
+use tracing::{info, warn, error, debug, instrument, Span};
 
 pub struct LiquidationMonitor {
     positions: DashMap<PositionId, Position>,
     price_feeds: Arc<dyn PriceFeedProvider>,
     risk_parameters: Arc<RwLock<RiskParameters>>,
     alert_system: Arc<dyn AlertSystem>,
-    health_calculators: HashMap<String, Box<dyn HealthCalculator>>,
+    rebasing_valuation: Option<Arc<RebasingValuation>>,
+    price_guard: Option<Arc<PriceDeviationGuard>>,
+    circuit_breaker: Option<Arc<PriceCircuitBreaker>>,
+    volatility_tracker: Option<Arc<VolatilityTracker>>,
+    health_records: RwLock<VecDeque<HealthRecord>>,
+    metrics: Arc<AegisMetrics>,
+    /// Calculators positions are routed to by protocol
+    health_calculators: Arc<HealthCalculatorFactory>,
+    quarantine_after_failures: Option<u32>,
+    missing_price_failures: DashMap<PositionId, u32>,
+    quarantined_positions: DashMap<PositionId, QuarantinedPosition>,
+    history_persistence: Option<HealthHistoryPersistence>,
+    /// Records awaiting the next persistence flush, at most `retention` of them
+    pending_health_records: std::sync::Mutex<VecDeque<HealthRecord>>,
+    risk_levels: DashMap<PositionId, RiskLevel>,
+    transition_hooks: Vec<Arc<dyn RiskLevelTransitionHook>>,
+    /// Latest health factors of each position, oldest first, for charting
+    health_history: DashMap<PositionId, VecDeque<(DateTime<Utc>, HealthFactor)>>,
+    health_history_capacity: usize,
+    /// Positions holding each token, so a price move only touches the positions it affects
+    token_positions: DashMap<TokenAddress, HashSet<PositionId>>,
+    /// Prices seen by the last `monitor_price_changes` pass
+    last_seen_prices: DashMap<TokenAddress, Decimal>,
+    /// Bounds simultaneous health calculations, and so price fetches, while monitoring
+    health_calc_limit: tokio::sync::Semaphore,
+    max_concurrent_health_calcs: usize,
+    /// Calculations waiting for a permit
+    queued_health_calcs: std::sync::atomic::AtomicUsize,
+    /// Protocols over the exposure limit at the last check, so a standing breach alerts once
+    exposure_breaches: std::sync::Mutex<HashSet<ProtocolId>>,
+    min_protocols_for_exposure_alerts: usize,
+    /// Positions over the size limit at their last check, so a standing breach alerts once
+    oversized_positions: dashmap::DashSet<PositionId>,
+    /// Positions whose health is held back by an open price circuit breaker
+    circuit_held_positions: dashmap::DashSet<PositionId>,
+    /// Tokens each position saw deviate from their reference price at its last check, so a
+    /// standing deviation alerts once
+    deviating_tokens: DashMap<PositionId, HashSet<TokenAddress>>,
 }
 
+/// Maximum number of health evaluations retained for audit export
+const MAX_HEALTH_RECORDS: usize = 10_000;
+
+/// Default number of health factors kept per position
+pub const DEFAULT_HEALTH_HISTORY_CAPACITY: usize = 1_000;
+
+/// Default number of health calculations run at once by `monitor_positions`
+pub const DEFAULT_MAX_CONCURRENT_HEALTH_CALCS: usize = 16;
+
+/// Default number of protocols a book must span before exposure to any one is flagged
+pub const DEFAULT_MIN_PROTOCOLS_FOR_EXPOSURE_ALERTS: usize = 2;
+
+/// Recent health factors fitted when projecting time to liquidation
+const LIQUIDATION_PROJECTION_SAMPLES: usize = 10;
+
+/// Upper bound on the what-if evaluations spent bisecting each top-up; the search
+/// normally stops sooner, once the bracket can't be halved at `Decimal` precision
+const TOPUP_BISECTION_ITERATIONS: usize = 128;
+
+/// Times a collateral top-up estimate is doubled before the token is judged unable to help
+const TOPUP_MAX_DOUBLINGS: usize = 64;
+
 impl LiquidationMonitor {
     pub fn new(
         price_feeds: Arc<dyn PriceFeedProvider>,
         alert_system: Arc<dyn AlertSystem>,
     ) -> Self {
-        let mut health_calculators: HashMap<String, Box<dyn HealthCalculator>> = HashMap::new();
-        
-        for protocol in HealthCalculatorFactory::supported_protocols() {
-            if let Some(calculator) = HealthCalculatorFactory::create_calculator(protocol) {
-                health_calculators.insert(protocol.to_string(), calculator);
-            }
-        }
-
         Self {
             positions: DashMap::new(),
             price_feeds,
             risk_parameters: Arc::new(RwLock::new(RiskParameters::default())),
             alert_system,
-            health_calculators,
+            rebasing_valuation: None,
+            price_guard: None,
+            circuit_breaker: None,
+            volatility_tracker: None,
+            health_records: RwLock::new(VecDeque::new()),
+            metrics: Arc::new(AegisMetrics::new()),
+            health_calculators: Arc::new(HealthCalculatorFactory::new()),
+            quarantine_after_failures: None,
+            missing_price_failures: DashMap::new(),
+            quarantined_positions: DashMap::new(),
+            history_persistence: None,
+            pending_health_records: std::sync::Mutex::new(VecDeque::new()),
+            risk_levels: DashMap::new(),
+            transition_hooks: Vec::new(),
+            health_history: DashMap::new(),
+            health_history_capacity: DEFAULT_HEALTH_HISTORY_CAPACITY,
+            token_positions: DashMap::new(),
+            last_seen_prices: DashMap::new(),
+            health_calc_limit: tokio::sync::Semaphore::new(DEFAULT_MAX_CONCURRENT_HEALTH_CALCS),
+            max_concurrent_health_calcs: DEFAULT_MAX_CONCURRENT_HEALTH_CALCS,
+            queued_health_calcs: std::sync::atomic::AtomicUsize::new(0),
+            exposure_breaches: std::sync::Mutex::new(HashSet::new()),
+            min_protocols_for_exposure_alerts: DEFAULT_MIN_PROTOCOLS_FOR_EXPOSURE_ALERTS,
+            oversized_positions: dashmap::DashSet::new(),
+            circuit_held_positions: dashmap::DashSet::new(),
+            deviating_tokens: DashMap::new(),
         }
     }
 
+    /// Value rebasing collateral through the given hook before computing health
+    pub fn with_rebasing_valuation(mut self, valuation: Arc<RebasingValuation>) -> Self {
+        self.rebasing_valuation = Some(valuation);
+        self
+    }
+
+    /// Check feed prices against a reference and fall back to the conservative price on divergence
+    pub fn with_price_deviation_guard(mut self, guard: Arc<PriceDeviationGuard>) -> Self {
+        self.price_guard = Some(guard);
+        self
+    }
+
+    /// Reject implausible price jumps instead of recomputing health from them
+    pub fn with_price_circuit_breaker(mut self, breaker: Arc<PriceCircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Feed every price accepted for a health calculation into `tracker`
+    pub fn with_volatility_tracker(mut self, tracker: Arc<VolatilityTracker>) -> Self {
+        self.volatility_tracker = Some(tracker);
+        self
+    }
+
+    /// Record into a shared metrics registry instead of a private one
+    pub fn with_metrics(mut self, metrics: Arc<AegisMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<AegisMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Route health calculations through a shared calculator registry instead of a private one
+    pub fn with_health_calculators(mut self, calculators: Arc<HealthCalculatorFactory>) -> Self {
+        self.health_calculators = calculators;
+        self
+    }
+
+    /// Registry this monitor routes health calculations through; calculators registered
+    /// here apply to this monitor's positions only
+    pub fn health_calculators(&self) -> Arc<HealthCalculatorFactory> {
+        self.health_calculators.clone()
+    }
+
+    /// Buffer health records for periodic writes to a persistence backend
+    pub fn with_health_history_persistence(mut self, persistence: HealthHistoryPersistence) -> Self {
+        self.history_persistence = Some(persistence);
+        self
+    }
+
+    /// Notify `hook` whenever a monitoring cycle finds a position has moved between risk
+    /// levels
+    pub fn with_risk_level_transition_hook(mut self, hook: Arc<dyn RiskLevelTransitionHook>) -> Self {
+        self.transition_hooks.push(hook);
+        self
+    }
+
+    /// Only flag protocol exposure once the book spans at least `count` protocols. The
+    /// default of 2 leaves single-protocol books, which have nowhere to diversify to,
+    /// alone; 1 flags them too.
+    pub fn with_min_protocols_for_exposure_alerts(mut self, count: usize) -> Self {
+        self.min_protocols_for_exposure_alerts = count;
+        self
+    }
+
+    /// Keep at most `capacity` health factors per position, dropping the oldest first
+    pub fn with_health_history_capacity(mut self, capacity: usize) -> Self {
+        self.health_history_capacity = capacity.max(1);
+        self
+    }
+
+    /// Run at most `limit` health calculations at once while monitoring, so a large book
+    /// doesn't flood the price provider
+    pub fn with_max_concurrent_health_calcs(mut self, limit: usize) -> Self {
+        let limit = limit.max(1);
+        self.health_calc_limit = tokio::sync::Semaphore::new(limit);
+        self.max_concurrent_health_calcs = limit;
+        self
+    }
+
+    /// Monitoring health calculations currently running
+    pub fn health_calcs_in_flight(&self) -> usize {
+        self.max_concurrent_health_calcs - self.health_calc_limit.available_permits()
+    }
+
+    /// Monitoring health calculations waiting for one of the concurrency slots
+    pub fn health_calc_queue_depth(&self) -> usize {
+        self.queued_health_calcs.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Quarantine positions whose health could not be computed for `failures` consecutive
+    /// monitoring cycles because a token price is missing (e.g. the token was delisted)
+    pub fn with_unpriceable_quarantine(mut self, failures: u32) -> Self {
+        self.quarantine_after_failures = Some(failures.max(1));
+        self
+    }
+
     pub async fn add_position(&self, position: Position) -> Result<PositionId, PositionError> {
         let position_id = position.id;
         
@@ -51,7 +235,7 @@ impl LiquidationMonitor {
         }
 
         info!("Adding position {} for protocol {}", position_id, position.protocol);
-        self.positions.insert(position_id, position);
+        self.insert_position(position);
         
         // Immediately check health after adding
         if let Err(e) = self.check_position_health(position_id).await {
@@ -69,7 +253,7 @@ impl LiquidationMonitor {
         }
 
         info!("Updating position {} for protocol {}", position_id, position.protocol);
-        self.positions.insert(position_id, position);
+        self.insert_position(position);
         
         // Check health after update
         if let Err(e) = self.check_position_health(position_id).await {
@@ -80,41 +264,47 @@ impl LiquidationMonitor {
     }
 
     pub fn remove_position(&self, position_id: PositionId) -> Result<Position, PositionError> {
-        self.positions.remove(&position_id)
-            .map(|(_, position)| {
-                info!("Removed position {}", position_id);
-                position
-            })
+        self.risk_levels.remove(&position_id);
+        self.oversized_positions.remove(&position_id);
+        self.circuit_held_positions.remove(&position_id);
+        self.deviating_tokens.remove(&position_id);
+        self.missing_price_failures.remove(&position_id);
+        self.clear_health_history(position_id);
+        self.take_position(position_id)
+            .inspect(|_| info!("Removed position {}", position_id))
             .ok_or(PositionError::NotFound { id: position_id })
     }
 
+    /// Current health of a position. Nothing is recorded: health history, deviation alerts,
+    /// transition hooks and the circuit breaker are only updated by monitoring cycles.
     pub async fn calculate_health(&self, position_id: PositionId) -> Result<HealthFactor, CalculationError> {
+        self.evaluate_position(position_id, false).await
+    }
+
+    /// Health of a position, with `record` set also adding it to the position's history,
+    /// alerting deviations and firing transition hooks as a monitoring cycle does
+    #[instrument(name = "calculate_health", skip(self), fields(position_id = %position_id, protocol = tracing::field::Empty))]
+    async fn evaluate_position(&self, position_id: PositionId, record: bool) -> Result<HealthFactor, CalculationError> {
         let start_time = Instant::now();
         
-        let position = self.positions.get(&position_id)
+        let position = self.get_position(position_id)
             .ok_or(CalculationError::CalculationFailed { 
                 message: format!("Position {} not found", position_id) 
             })?;
+        Span::current().record("protocol", position.protocol.as_str());
+        let position = self.apply_rebasing(vec![position]).await?.remove(0);
 
-        let calculator = self.health_calculators.get(&position.protocol)
-            .ok_or(CalculationError::UnsupportedProtocol { 
-                protocol: position.protocol.clone() 
-            })?;
-
-        // Get required token addresses
-        let mut required_tokens: Vec<TokenAddress> = Vec::new();
-        required_tokens.extend(position.collateral_tokens.keys().cloned());
-        required_tokens.extend(position.debt_tokens.keys().cloned());
-
-        // Fetch price data
-        let prices = self.price_feeds.get_prices(&required_tokens).await
-            .map_err(|e| CalculationError::CalculationFailed { 
-                message: format!("Failed to fetch prices: {}", e) 
-            })?;
+        let (prices, deviations) = self.fetch_guarded_position_prices(&position, record).await?;
 
-        let health_factor = calculator.calculate_health(&position, &prices)?;
+        let health_factor = self.calculate_health_with_prices(&position, &prices)?;
+        if record {
+            self.record_health(&position, &health_factor, &prices).await;
+            self.raise_deviation_alert(&position, &health_factor, &deviations).await;
+            self.track_risk_level(&position, &health_factor).await;
+        }
         
         let calculation_time = start_time.elapsed();
+        self.metrics.record_health_calculation(calculation_time);
         debug!("Health calculation for {} took {:?}", position_id, calculation_time);
         
         // Log warning if calculation takes too long (requirement: <100ms)
@@ -126,144 +316,3248 @@ impl LiquidationMonitor {
         Ok(health_factor)
     }
 
-    pub async fn monitor_positions(&self) -> Vec<RiskAlert> {
-        let mut alerts = Vec::new();
+    /// Evaluate every monitored position against a single price fetch so that
+    /// tokens shared between positions are valued identically within the snapshot.
+    /// Like `calculate_health`, this records nothing.
+    pub async fn evaluate_snapshot(&self) -> Result<RiskSnapshot, CalculationError> {
+        self.evaluate_positions(self.list_positions()).await
+    }
+
+    /// Positions ordered riskiest first (ascending health factor), valued from one price
+    /// fetch. `protocol` narrows the book before prices are fetched. Positions whose health
+    /// can't be calculated, e.g. for a missing or stale price, come first with their error,
+    /// since nothing can be said about how close they are to liquidation.
+    pub async fn positions_by_risk(
+        &self,
+        protocol: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<RankedPosition>, CalculationError> {
+        let positions: Vec<Position> = self.list_positions().into_iter()
+            .filter(|position| protocol.is_none_or(|protocol| position.protocol == protocol))
+            .collect();
+        let mut snapshot = self.evaluate_positions(positions.clone()).await?;
+
         let risk_params = self.risk_parameters.read().await;
+        let mut ranked: Vec<RankedPosition> = positions.iter()
+            .filter_map(|position| {
+                let rated = snapshot.health_factors.remove(&position.id)?.map(|health_factor| {
+                    let risk_level = health_factor.risk_level(position.risk_parameters(&risk_params));
+                    (health_factor, risk_level)
+                });
+                Some((position.id, rated))
+            })
+            .collect();
+        ranked.sort_by(|a, b| match (&a.1, &b.1) {
+            (Ok((a_health, _)), Ok((b_health, _))) => a_health.value.cmp(&b_health.value),
+            (Err(_), Ok(_)) => std::cmp::Ordering::Less,
+            (Ok(_), Err(_)) => std::cmp::Ordering::Greater,
+            (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+        }.then(a.0.cmp(&b.0)));
+        if let Some(limit) = limit {
+            ranked.truncate(limit);
+        }
+        Ok(ranked)
+    }
 
-        for position_ref in self.positions.iter() {
-            let position_id = *position_ref.key();
-            
-            match self.calculate_health(position_id).await {
-                Ok(health_factor) => {
-                    if health_factor.is_at_risk(&risk_params) {
-                        let risk_level = health_factor.risk_level(&risk_params);
-                        let alert = self.create_liquidation_alert(
-                            position_id,
-                            &health_factor,
-                            risk_level,
-                        );
-                        alerts.push(alert);
+    /// Portfolio risk before and after applying `changes` to a copy of the monitored
+    /// positions.
+    ///
+    /// Both books are valued from one price fetch, with the same staleness, circuit
+    /// breaker and deviation checks as `evaluate_snapshot`, which likewise records nothing,
+    /// so live positions, health history and breaker state are left as they were.
+    /// Positions that cannot be priced are left out of both aggregates, except that a
+    /// changed position which cannot be priced fails the whole call.
+    pub async fn what_if(&self, changes: &[PositionChange]) -> Result<WhatIfResult, CalculationError> {
+        let current = self.list_positions();
+        let changed = Self::apply_position_changes(current.clone(), changes)?;
+        let current_count = current.len();
+
+        let valued = self.apply_rebasing(current.into_iter().chain(changed).collect()).await?;
+        let (current, changed) = valued.split_at(current_count);
+        let (prices, reference_prices) = self.fetch_snapshot_prices(&valued).await?;
+        let health_before = self.evaluate_with_prices(current, &prices, reference_prices.as_ref()).await;
+        let mut health_after = self.evaluate_with_prices(changed, &prices, reference_prices.as_ref()).await;
+
+        let mut changed_ids: Vec<PositionId> = changes.iter().map(PositionChange::position_id).collect();
+        changed_ids.sort();
+        changed_ids.dedup();
+        // A changed position that can't be valued makes the comparison meaningless
+        if let Some(position_id) = changed_ids.iter().find(|id| health_after.get(*id).is_some_and(Result::is_err)) {
+            if let Some(Err(e)) = health_after.remove(position_id) {
+                return Err(e);
+            }
+        }
+
+        let health_value = |healths: &HashMap<PositionId, Result<HealthFactor, CalculationError>>, position_id: &PositionId| {
+            healths.get(position_id).and_then(|health| health.as_ref().ok()).map(|health| health.value)
+        };
+        let position_changes = changed_ids.iter()
+            .map(|position_id| PositionHealthChange {
+                position_id: *position_id,
+                before: health_value(&health_before, position_id),
+                after: health_value(&health_after, position_id),
+            })
+            .collect();
+
+        let risk_params = self.risk_parameters.read().await.clone();
+        Ok(WhatIfResult {
+            before: Self::portfolio_health(current, &health_before, &risk_params),
+            after: Self::portfolio_health(changed, &health_after, &risk_params),
+            position_changes,
+        })
+    }
+
+    /// `positions` with `changes` applied in order
+    fn apply_position_changes(mut positions: Vec<Position>, changes: &[PositionChange]) -> Result<Vec<Position>, CalculationError> {
+        let invalid = |message: String| CalculationError::InvalidPosition { message };
+        for change in changes {
+            let index = positions.iter().position(|position| position.id == change.position_id());
+            match (change, index) {
+                (PositionChange::Add { position }, None) => {
+                    position.validate().map_err(|e| invalid(e.to_string()))?;
+                    positions.push(position.as_ref().clone());
+                }
+                (PositionChange::Add { position }, Some(_)) => {
+                    return Err(invalid(format!("Position {} already exists", position.id)));
+                }
+                (PositionChange::Remove { .. }, Some(index)) => {
+                    positions.remove(index);
+                }
+                (PositionChange::Resize { factor, .. }, Some(_)) if *factor <= Decimal::ZERO => {
+                    return Err(invalid(format!("Resize factor must be positive, found {}", factor)));
+                }
+                (PositionChange::Resize { factor, .. }, Some(index)) => {
+                    let position = &mut positions[index];
+                    for token in position.collateral_tokens.values_mut().chain(position.debt_tokens.values_mut()) {
+                        token.amount *= *factor;
+                        token.value_usd *= *factor;
                     }
                 }
-                Err(e) => {
-                    error!("Failed to calculate health for position {}: {}", position_id, e);
-                    // Create an error alert
-                    let alert = RiskAlert {
-                        id: Uuid::new_v4(),
-                        position_id,
-                        alert_type: AlertType::LiquidationRisk,
-                        risk_level: RiskLevel::Critical,
-                        health_factor: HealthFactor {
-                            value: rust_decimal::Decimal::ZERO,
-                            liquidation_threshold: rust_decimal::Decimal::ZERO,
-                            collateral_value: rust_decimal::Decimal::ZERO,
-                            debt_value: rust_decimal::Decimal::ZERO,
-                            calculated_at: Utc::now(),
-                        },
-                        message: format!("Health calculation failed: {}", e),
-                        created_at: Utc::now(),
-                        acknowledged: false,
-                    };
-                    alerts.push(alert);
+                (PositionChange::Remove { position_id } | PositionChange::Resize { position_id, .. }, None) => {
+                    return Err(invalid(format!("Position {} not found", position_id)));
                 }
             }
         }
+        Ok(positions)
+    }
 
-        // Send alerts through alert system
-        for alert in &alerts {
-            if let Err(e) = self.alert_system.send_alert(alert.clone()).await {
-                error!("Failed to send alert {}: {}", alert.id, e);
+    fn portfolio_health(
+        positions: &[Position],
+        healths: &HashMap<PositionId, Result<HealthFactor, CalculationError>>,
+        risk_params: &RiskParameters,
+    ) -> PortfolioHealth {
+        let priced: Vec<(&Position, &HealthFactor)> = positions.iter()
+            .filter_map(|position| match healths.get(&position.id) {
+                Some(Ok(health_factor)) => Some((position, health_factor)),
+                _ => None,
+            })
+            .collect();
+
+        let total_collateral_usd: Decimal = priced.iter().map(|(_, health)| health.collateral_value).sum();
+        let total_debt_usd: Decimal = priced.iter().map(|(_, health)| health.debt_value).sum();
+        let indebted: Vec<&HealthFactor> = priced.iter()
+            .map(|(_, health)| *health)
+            .filter(|health| health.debt_value > Decimal::ZERO)
+            .collect();
+        // Weighting each health factor by debt gives risk-adjusted collateral over total debt
+        let aggregate_health_factor = (total_debt_usd > Decimal::ZERO).then(|| {
+            indebted.iter().map(|health| health.value * health.debt_value).sum::<Decimal>() / total_debt_usd
+        });
+        let collateral_values: Vec<f64> = priced.iter()
+            .map(|(_, health)| health.collateral_value.to_f64().unwrap_or(0.0))
+            .collect();
+
+        PortfolioHealth {
+            position_count: priced.len(),
+            total_collateral_usd,
+            total_debt_usd,
+            aggregate_health_factor,
+            lowest_health_factor: indebted.iter().map(|health| health.value).min(),
+            positions_at_risk: priced.iter()
+                .filter(|(position, health)| health.risk_level(position.risk_parameters(risk_params)) >= RiskLevel::Warning)
+                .count(),
+            concentration_index: crate::risk::herfindahl_index(&collateral_values),
+            protocol_exposures: Self::exposures_by_protocol(
+                priced.iter().map(|(position, health)| (position.protocol.clone(), health.collateral_value)),
+            ),
+        }
+    }
+
+    /// Health of each requested position computed from one price fetch, instead of a
+    /// fetch per position as repeated `calculate_health` calls would do
+    pub async fn calculate_health_batch(
+        &self,
+        position_ids: &[PositionId],
+    ) -> HashMap<PositionId, Result<HealthFactor, CalculationError>> {
+        let mut results = HashMap::new();
+        let mut positions = Vec::new();
+        for position_id in position_ids {
+            match self.get_position(*position_id) {
+                Some(position) => positions.push(position),
+                None => {
+                    results.insert(*position_id, Err(CalculationError::CalculationFailed {
+                        message: format!("Position {} not found", position_id)
+                    }));
+                }
             }
         }
 
-        alerts
+        match self.evaluate_positions(positions.clone()).await {
+            Ok(snapshot) => results.extend(snapshot.health_factors),
+            Err(e) => {
+                // Every position shares the failed fetch
+                let message = e.to_string();
+                for position in &positions {
+                    results.insert(position.id, Err(CalculationError::CalculationFailed { message: message.clone() }));
+                }
+            }
+        }
+
+        results
     }
 
-    async fn check_position_health(&self, position_id: PositionId) -> Result<(), CalculationError> {
-        let health_factor = self.calculate_health(position_id).await?;
-        let risk_params = self.risk_parameters.read().await;
-        
-        if health_factor.is_at_risk(&risk_params) {
-            let risk_level = health_factor.risk_level(&risk_params);
-            let alert = self.create_liquidation_alert(position_id, &health_factor, risk_level);
-            
-            if let Err(e) = self.alert_system.send_alert(alert).await {
-                error!("Failed to send immediate alert for position {}: {}", position_id, e);
+    async fn evaluate_positions(&self, positions: Vec<Position>) -> Result<RiskSnapshot, CalculationError> {
+        let positions = self.apply_rebasing(positions).await?;
+        let (prices, reference_prices) = self.fetch_snapshot_prices(&positions).await?;
+        let health_factors = self.evaluate_with_prices(&positions, &prices, reference_prices.as_ref()).await;
+
+        Ok(RiskSnapshot {
+            prices,
+            health_factors,
+            taken_at: Utc::now(),
+        })
+    }
+
+    /// Feed prices for every token in `positions`, plus the guard's reference prices when
+    /// a deviation guard is set
+    async fn fetch_snapshot_prices(
+        &self,
+        positions: &[Position],
+    ) -> Result<(HashMap<TokenAddress, PriceData>, Option<HashMap<TokenAddress, PriceData>>), CalculationError> {
+        let mut required_tokens: Vec<TokenAddress> = positions.iter()
+            .flat_map(|p| p.collateral_tokens.keys().chain(p.debt_tokens.keys()).cloned())
+            .collect();
+        required_tokens.sort();
+        required_tokens.dedup();
+
+        let prices = self.price_feeds.get_prices(&required_tokens).await
+            .map_err(|e| CalculationError::CalculationFailed { 
+                message: format!("Failed to fetch prices: {}", e) 
+            })?;
+
+        let reference_prices = match &self.price_guard {
+            Some(guard) => Some(guard.fetch_reference_prices(&required_tokens).await?),
+            None => None,
+        };
+        Ok((prices, reference_prices))
+    }
+
+    /// Health of each of `positions` at `prices`, rejecting stale prices and tripped
+    /// circuits and guarding against `reference_prices` when given. Nothing is recorded
+    /// and the circuit breaker is only queried.
+    async fn evaluate_with_prices(
+        &self,
+        positions: &[Position],
+        prices: &HashMap<TokenAddress, PriceData>,
+        reference_prices: Option<&HashMap<TokenAddress, PriceData>>,
+    ) -> HashMap<PositionId, Result<HealthFactor, CalculationError>> {
+        let max_price_age = self.risk_parameters.read().await.max_price_age;
+        let mut health_factors = HashMap::new();
+        for position in positions {
+            let position_tokens: Vec<TokenAddress> = position.collateral_tokens.keys()
+                .chain(position.debt_tokens.keys())
+                .cloned()
+                .collect();
+            if let Err(e) = Self::reject_stale_prices(prices, &position_tokens, max_price_age) {
+                health_factors.insert(position.id, Err(e));
+                continue;
+            }
+            if let Err(e) = self.query_price_circuit(position, prices) {
+                health_factors.insert(position.id, Err(e));
+                continue;
             }
-        }
 
-        Ok(())
+            let health = match (&self.price_guard, reference_prices) {
+                (Some(guard), Some(reference_prices)) => {
+                    let (guarded, _) = guard.guard(position, prices, reference_prices);
+                    self.calculate_health_with_prices(position, &guarded)
+                }
+                _ => self.calculate_health_with_prices(position, prices),
+            };
+            health_factors.insert(position.id, health);
+        }
+        health_factors
     }
 
-    fn create_liquidation_alert(
+    /// Health of a position after several simultaneous price moves.
+    ///
+    /// Every change is applied to the same current price snapshot, so the result does not
+    /// depend on the order the changes are supplied in. Several changes for one token are
+    /// handled according to `merge`.
+    pub async fn health_with_price_changes(
         &self,
         position_id: PositionId,
-        health_factor: &HealthFactor,
-        risk_level: RiskLevel,
-    ) -> RiskAlert {
-        let message = match risk_level {
-            RiskLevel::Emergency => format!(
-                "EMERGENCY: Position {} is at immediate liquidation risk! Health factor: {:.4}",
-                position_id, health_factor.value
-            ),
-            RiskLevel::Critical => format!(
-                "CRITICAL: Position {} approaching liquidation. Health factor: {:.4}",
-                position_id, health_factor.value
-            ),
-            RiskLevel::Warning => format!(
-                "WARNING: Position {} health declining. Health factor: {:.4}",
-                position_id, health_factor.value
-            ),
-            RiskLevel::Safe => format!(
-                "Position {} is healthy. Health factor: {:.4}",
-                position_id, health_factor.value
-            ),
-        };
+        changes: &[PriceChange],
+        merge: PriceChangeMerge,
+    ) -> Result<HealthFactor, CalculationError> {
+        let position = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let position = self.apply_rebasing(vec![position]).await?.remove(0);
+        let (mut prices, _) = self.fetch_guarded_position_prices(&position, false).await?;
 
-        RiskAlert {
-            id: Uuid::new_v4(),
-            position_id,
-            alert_type: AlertType::LiquidationRisk,
-            risk_level,
-            health_factor: health_factor.clone(),
-            message,
-            created_at: Utc::now(),
-            acknowledged: false,
+        let mut changes_by_token: BTreeMap<&TokenAddress, Vec<Decimal>> = BTreeMap::new();
+        for change in changes {
+            if !position.collateral_tokens.contains_key(&change.token_address)
+                && !position.debt_tokens.contains_key(&change.token_address) {
+                return Err(CalculationError::InvalidPosition {
+                    message: format!("Position {} does not hold {}", position_id, change.token_address)
+                });
+            }
+            if change.relative_change < -Decimal::ONE {
+                return Err(CalculationError::CalculationFailed {
+                    message: format!("Price change for {} would make the price negative: {}", change.token_address, change.relative_change)
+                });
+            }
+            changes_by_token.entry(&change.token_address).or_default().push(Decimal::ONE + change.relative_change);
+        }
+
+        for (token_address, mut multipliers) in changes_by_token {
+            if multipliers.len() > 1 && merge == PriceChangeMerge::Reject {
+                return Err(CalculationError::CalculationFailed {
+                    message: format!("{} price changes supplied for {}", multipliers.len(), token_address)
+                });
+            }
+
+            // Sorting first keeps the rounding of the compounded multiplier order-independent
+            multipliers.sort();
+            let multiplier = multipliers.into_iter().fold(Decimal::ONE, |acc, m| acc * m);
+            let price_data = prices.get_mut(token_address)
+                .ok_or_else(|| CalculationError::MissingPriceData { token: token_address.clone() })?;
+            price_data.price_usd *= multiplier;
         }
+
+        self.calculate_health_with_prices(&position, &prices)
     }
 
-    pub async fn update_risk_parameters(&self, new_params: RiskParameters) {
-        let mut params = self.risk_parameters.write().await;
-        *params = new_params;
-        info!("Updated risk parameters");
+    /// Compute, at current prices, how much extra collateral (per collateral token) or
+    /// debt repayment (per debt token) in USD would bring the position to `target_health`.
+    ///
+    /// Each amount is found by bisecting over `what_if`, so it is whatever the what-if
+    /// engine needs to see to report the target health.
+    pub async fn required_topup(
+        &self,
+        position_id: PositionId,
+        target_health: Decimal,
+    ) -> Result<RequiredTopup, CalculationError> {
+        if target_health <= Decimal::ZERO {
+            return Err(CalculationError::CalculationFailed {
+                message: format!("Target health factor must be positive, got {}", target_health)
+            });
+        }
+
+        let stored = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let position = self.apply_rebasing(vec![stored.clone()]).await?.remove(0);
+
+        let (prices, _) = self.fetch_guarded_position_prices(&position, false).await?;
+        let current = self.calculate_health_with_prices(&position, &prices)?;
+
+        let mut collateral_topups = HashMap::new();
+        let mut debt_repayments = HashMap::new();
+
+        if current.value < target_health {
+            let health_gap = target_health - current.value;
+
+            for (token_address, token) in &stored.collateral_tokens {
+                let price = Self::price_of(&prices, token_address)?;
+                if price <= Decimal::ZERO {
+                    continue;
+                }
+                // A rebasing token is held in raw units, each worth its index in tokens
+                let rebased_amount = position.collateral_tokens.get(token_address).map_or(token.amount, |t| t.amount);
+                let usd_per_unit = if token.amount > Decimal::ZERO {
+                    price * rebased_amount / token.amount
+                } else {
+                    price
+                };
+
+                // Fully weighted collateral would need exactly this much; real weights need more
+                let estimate = health_gap * current.debt_value / usd_per_unit;
+                let units = self.bisect_with_what_if(&stored, target_health, estimate, true, |position, units| {
+                    if let Some(token) = position.collateral_tokens.get_mut(token_address) {
+                        token.amount += units;
+                    }
+                }).await?;
+                if let Some(units) = units {
+                    collateral_topups.insert(token_address.clone(), units * usd_per_unit);
+                }
+            }
+
+            for (token_address, token) in &stored.debt_tokens {
+                let price = Self::price_of(&prices, token_address)?;
+                let units = self.bisect_with_what_if(&stored, target_health, token.amount, false, |position, units| {
+                    if let Some(token) = position.debt_tokens.get_mut(token_address) {
+                        token.amount -= units;
+                    }
+                }).await?;
+                if let Some(units) = units {
+                    debt_repayments.insert(token_address.clone(), units * price);
+                }
+            }
+        }
+
+        Ok(RequiredTopup {
+            position_id,
+            current_health: current.value,
+            target_health,
+            collateral_topups,
+            debt_repayments,
+            calculated_at: Utc::now(),
+        })
     }
 
-    pub async fn get_risk_parameters(&self) -> RiskParameters {
-        self.risk_parameters.read().await.clone()
+    /// Smallest amount in `[0, high]` that `adjust` can apply to `position` for `what_if`
+    /// to report `target_health`, or `None` if even `high` falls short. With `grow`,
+    /// `high` is doubled until it is enough.
+    async fn bisect_with_what_if(
+        &self,
+        position: &Position,
+        target_health: Decimal,
+        mut high: Decimal,
+        grow: bool,
+        adjust: impl Fn(&mut Position, Decimal),
+    ) -> Result<Option<Decimal>, CalculationError> {
+        let adjusted = |amount: Decimal| {
+            let mut adjusted = position.clone();
+            adjust(&mut adjusted, amount);
+            adjusted
+        };
+
+        let mut doublings = 0;
+        while self.what_if_health(adjusted(high)).await? < target_health {
+            if !grow || doublings == TOPUP_MAX_DOUBLINGS {
+                return Ok(None);
+            }
+            high *= Decimal::TWO;
+            doublings += 1;
+        }
+
+        let mut low = Decimal::ZERO;
+        for _ in 0..TOPUP_BISECTION_ITERATIONS {
+            let mid = (low + high) / Decimal::TWO;
+            if mid == low || mid == high {
+                break;
+            }
+            if self.what_if_health(adjusted(mid)).await? >= target_health {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(Some(high))
     }
 
-    pub fn get_position(&self, position_id: PositionId) -> Option<Position> {
-        self.positions.get(&position_id).map(|p| p.clone())
+    /// Health factor `what_if` reports for a monitored position replaced by `hypothetical`
+    async fn what_if_health(&self, hypothetical: Position) -> Result<Decimal, CalculationError> {
+        let position_id = hypothetical.id;
+        let result = self.what_if(&[
+            PositionChange::Remove { position_id },
+            PositionChange::Add { position: Box::new(hypothetical) },
+        ]).await?;
+        result.position_changes.into_iter()
+            .find(|change| change.position_id == position_id)
+            .and_then(|change| change.after)
+            .ok_or(CalculationError::CalculationFailed {
+                message: format!("Position {} could not be valued", position_id)
+            })
     }
 
-    pub fn list_positions(&self) -> Vec<Position> {
-        self.positions.iter().map(|p| p.value().clone()).collect()
+    /// A position with rebasing applied, together with its guarded current prices, for
+    /// evaluating hypothetical changes with `calculate_health_with_prices`
+    pub(crate) async fn priced_position(
+        &self,
+        position_id: PositionId,
+    ) -> Result<(Position, HashMap<TokenAddress, PriceData>), CalculationError> {
+        let position = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let position = self.apply_rebasing(vec![position]).await?.remove(0);
+        let (prices, _) = self.fetch_guarded_position_prices(&position, false).await?;
+        Ok((position, prices))
     }
 
-    pub fn position_count(&self) -> usize {
-        self.positions.len()
+    /// Scale rebasing collateral by its current index, fetching all indices in one call
+    async fn apply_rebasing(&self, positions: Vec<Position>) -> Result<Vec<Position>, CalculationError> {
+        let valuation = match &self.rebasing_valuation {
+            Some(valuation) => valuation,
+            None => return Ok(positions),
+        };
+
+        let indices = valuation.fetch_indices(&positions).await?;
+        positions.iter()
+            .map(|position| valuation.apply(position, &indices))
+            .collect()
     }
-}
 
-#[async_trait::async_trait]
-pub trait PriceFeedProvider: Send + Sync {
-    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>>;
-    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>>;
-}
+    async fn record_health(
+        &self,
+        position: &Position,
+        health_factor: &HealthFactor,
+        prices: &HashMap<TokenAddress, PriceData>,
+    ) {
+        let mut prices_used: Vec<PriceData> = position.collateral_tokens.keys()
+            .chain(position.debt_tokens.keys())
+            .filter_map(|token| prices.get(token).cloned())
+            .collect();
+        prices_used.sort_by(|a, b| a.token_address.cmp(&b.token_address));
 
-#[async_trait::async_trait]
-pub trait AlertSystem: Send + Sync {
+        let record = HealthRecord {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            health_factor: health_factor.clone(),
+            prices_used,
+            recorded_at: Utc::now(),
+        };
+
+        // Only buffered here; the backend write happens in `flush_health_history`. Records
+        // past retention would be truncated on the next flush anyway, so drop the oldest
+        // rather than let a failing backend grow the buffer.
+        if let Some(persistence) = &self.history_persistence {
+            let mut pending = self.pending_health_records.lock().unwrap();
+            pending.push_back(record.clone());
+            while pending.len() > persistence.retention {
+                pending.pop_front();
+            }
+        }
+
+        {
+            let mut history = self.health_history.entry(position.id).or_default();
+            if history.len() >= self.health_history_capacity {
+                history.pop_front();
+            }
+            history.push_back((record.recorded_at, health_factor.clone()));
+        }
+
+        let mut records = self.health_records.write().await;
+        if records.len() >= MAX_HEALTH_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Health factors of a position recorded at or after `since`, oldest first
+    pub fn get_health_history(&self, position_id: PositionId, since: DateTime<Utc>) -> Vec<(DateTime<Utc>, HealthFactor)> {
+        self.health_history.get(&position_id)
+            .map(|history| history.iter()
+                .filter(|(recorded_at, _)| *recorded_at >= since)
+                .cloned()
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Seconds until the position's health factor reaches 1.0 if it keeps falling at its
+    /// recent rate, fitted over the last few recorded health factors. `None` when there is
+    /// too little history or health is steady or improving.
+    pub fn projected_seconds_to_liquidation(&self, position_id: PositionId) -> Option<f64> {
+        let history = self.health_history.get(&position_id)?;
+        let recent: Vec<&(DateTime<Utc>, HealthFactor)> = history.iter()
+            .skip(history.len().saturating_sub(LIQUIDATION_PROJECTION_SAMPLES))
+            .collect();
+        let start = recent.first()?.0;
+        let samples: Vec<(f64, f64)> = recent.iter()
+            .filter_map(|(recorded_at, health)| Some((
+                (*recorded_at - start).num_microseconds()? as f64 / 1_000_000.0,
+                health.value.to_f64()?,
+            )))
+            .collect();
+        if samples.len() < 2 {
+            return None;
+        }
+
+        // Least-squares slope of health against time
+        let n = samples.len() as f64;
+        let mean_t = samples.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_h = samples.iter().map(|(_, h)| h).sum::<f64>() / n;
+        let covariance: f64 = samples.iter().map(|(t, h)| (t - mean_t) * (h - mean_h)).sum();
+        let variance: f64 = samples.iter().map(|(t, _)| (t - mean_t).powi(2)).sum();
+        if variance <= 0.0 {
+            return None;
+        }
+        let slope = covariance / variance;
+        if slope >= 0.0 {
+            return None;
+        }
+
+        let (_, current) = samples[samples.len() - 1];
+        Some(((current - 1.0) / -slope).max(0.0))
+    }
+
+    pub fn clear_health_history(&self, position_id: PositionId) {
+        self.health_history.remove(&position_id);
+    }
+
+    /// Drop the oldest `fraction` of in-memory health history, returning how many records
+    /// were removed. Persisted history is unaffected.
+    pub async fn shed_health_records(&self, fraction: f64) -> usize {
+        let mut records = self.health_records.write().await;
+        let to_remove = (records.len() as f64 * fraction.clamp(0.0, 1.0)).ceil() as usize;
+        records.drain(..to_remove);
+        to_remove
+    }
+
+    /// Drop the oldest `fraction` of each position's charted health factors, keeping the
+    /// latest point so trends can pick up from it. Returns how many points were removed.
+    pub fn shed_health_history(&self, fraction: f64) -> usize {
+        self.health_history.iter_mut()
+            .map(|mut history| {
+                let to_remove = ((history.len() as f64 * fraction.clamp(0.0, 1.0)).ceil() as usize)
+                    .min(history.len().saturating_sub(1));
+                history.drain(..to_remove);
+                to_remove
+            })
+            .sum()
+    }
+
+    pub fn health_history_flush_interval(&self) -> Option<std::time::Duration> {
+        self.history_persistence.as_ref().map(|p| p.flush_interval)
+    }
+
+    /// Write buffered health records to the persistence backend and apply retention.
+    /// Returns the number of records written.
+    pub async fn flush_health_history(&self) -> Result<usize, CalculationError> {
+        let persistence = match &self.history_persistence {
+            Some(persistence) => persistence,
+            None => return Ok(0),
+        };
+
+        let pending = std::mem::take(&mut *self.pending_health_records.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let pending = Vec::from(pending);
+        if let Err(e) = persistence.backend.append_health_records(&pending).await {
+            // Keep the records for the next attempt, ahead of anything recorded since
+            let mut buffer = self.pending_health_records.lock().unwrap();
+            let newer = std::mem::replace(&mut *buffer, VecDeque::from(pending));
+            buffer.extend(newer);
+            while buffer.len() > persistence.retention {
+                buffer.pop_front();
+            }
+            return Err(CalculationError::CalculationFailed {
+                message: format!("Failed to persist health history: {}", e)
+            });
+        }
+
+        persistence.backend.truncate_health_records(persistence.retention).await
+            .map_err(|e| CalculationError::CalculationFailed {
+                message: format!("Failed to apply health history retention: {}", e)
+            })?;
+
+        debug!("Persisted {} health records", pending.len());
+        Ok(pending.len())
+    }
+
+    /// Load persisted health history into memory, ahead of anything recorded since startup,
+    /// rebuilding both the records and each position's chart history from it.
+    /// Returns the number of records restored.
+    pub async fn hydrate_health_history(&self) -> Result<usize, CalculationError> {
+        let persistence = match &self.history_persistence {
+            Some(persistence) => persistence,
+            None => return Ok(0),
+        };
+
+        let persisted = persistence.backend.load_health_records().await
+            .map_err(|e| CalculationError::CalculationFailed {
+                message: format!("Failed to load health history: {}", e)
+            })?;
+        let restored = persisted.len();
+
+        let mut charted: HashMap<PositionId, VecDeque<(DateTime<Utc>, HealthFactor)>> = HashMap::new();
+        for record in &persisted {
+            charted.entry(record.position_id).or_default()
+                .push_back((record.recorded_at, record.health_factor.clone()));
+        }
+        for (position_id, mut history) in charted {
+            let mut entry = self.health_history.entry(position_id).or_default();
+            history.extend(entry.drain(..));
+            while history.len() > self.health_history_capacity {
+                history.pop_front();
+            }
+            *entry = history;
+        }
+
+        let mut records = self.health_records.write().await;
+        let recent = std::mem::take(&mut *records);
+        records.extend(persisted);
+        records.extend(recent);
+        while records.len() > MAX_HEALTH_RECORDS {
+            records.pop_front();
+        }
+
+        info!("Restored {} persisted health records", restored);
+        Ok(restored)
+    }
+
+    /// Health evaluations recorded within `range`, oldest first
+    pub async fn get_health_records(&self, range: &Range<DateTime<Utc>>) -> Vec<HealthRecord> {
+        self.health_records.read().await.iter()
+            .filter(|record| range.contains(&record.recorded_at))
+            .cloned()
+            .collect()
+    }
+
+    /// The position's current prices, rejecting stale ones and any the circuit breaker
+    /// would hold back. With `record` set the prices go through the breaker and into the
+    /// volatility tracker; otherwise the breaker is only queried.
+    async fn fetch_position_prices(&self, position: &Position, record: bool) -> Result<HashMap<TokenAddress, PriceData>, CalculationError> {
+        let mut required_tokens: Vec<TokenAddress> = Vec::new();
+        required_tokens.extend(position.collateral_tokens.keys().cloned());
+        required_tokens.extend(position.debt_tokens.keys().cloned());
+
+        let prices = self.price_feeds.get_prices(&required_tokens).await
+            .map_err(|e| CalculationError::CalculationFailed { 
+                message: format!("Failed to fetch prices: {}", e) 
+            })?;
+
+        let max_price_age = self.risk_parameters.read().await.max_price_age;
+        Self::reject_stale_prices(&prices, &required_tokens, max_price_age)?;
+        if !record {
+            self.query_price_circuit(position, &prices)?;
+            return Ok(prices);
+        }
+        self.check_price_circuit(position, &prices).await?;
+        if let Some(tracker) = &self.volatility_tracker {
+            tracker.record_all(prices.values());
+        }
+        Ok(prices)
+    }
+
+    /// Run the position's prices through the circuit breaker, if one is configured. Each
+    /// position the breaker holds back is alerted once, at Critical, since its health goes
+    /// untracked until the breaker closes.
+    async fn check_price_circuit(
+        &self,
+        position: &Position,
+        prices: &HashMap<TokenAddress, PriceData>,
+    ) -> Result<(), CalculationError> {
+        let breaker = match &self.circuit_breaker {
+            Some(breaker) => breaker,
+            None => return Ok(()),
+        };
+
+        let tokens: Vec<TokenAddress> = position.collateral_tokens.keys()
+            .chain(position.debt_tokens.keys())
+            .cloned()
+            .collect();
+        let trips = breaker.check(prices, &tokens);
+        let trip = match trips.first() {
+            Some(trip) => trip.clone(),
+            None => {
+                self.circuit_held_positions.remove(&position.id);
+                return Ok(());
+            }
+        };
+        if self.circuit_held_positions.insert(position.id) {
+            self.raise_circuit_breaker_alert(position, &trips, prices).await;
+        }
+        Err(Self::circuit_open_error(trip))
+    }
+
+    /// Whether the circuit breaker would reject the position's prices, asked without
+    /// accepting any price, alerting or holding the position back. For valuations that
+    /// must leave live state alone.
+    fn query_price_circuit(
+        &self,
+        position: &Position,
+        prices: &HashMap<TokenAddress, PriceData>,
+    ) -> Result<(), CalculationError> {
+        let breaker = match &self.circuit_breaker {
+            Some(breaker) => breaker,
+            None => return Ok(()),
+        };
+
+        let tokens: Vec<TokenAddress> = position.collateral_tokens.keys()
+            .chain(position.debt_tokens.keys())
+            .cloned()
+            .collect();
+        match breaker.would_trip(prices, &tokens).into_iter().next() {
+            Some(trip) => Err(Self::circuit_open_error(trip)),
+            None => Ok(()),
+        }
+    }
+
+    fn circuit_open_error(trip: PriceTrip) -> CalculationError {
+        CalculationError::PriceCircuitOpen {
+            token: trip.token_address,
+            last_accepted_price: trip.last_accepted_price,
+            rejected_price: trip.rejected_price,
+        }
+    }
+
+    fn reject_stale_prices(
+        prices: &HashMap<TokenAddress, PriceData>,
+        token_addresses: &[TokenAddress],
+        max_price_age: std::time::Duration,
+    ) -> Result<(), CalculationError> {
+        let now = Utc::now();
+        for token_address in token_addresses {
+            if let Some(price_data) = prices.get(token_address) {
+                let age = now - price_data.timestamp;
+                if age.to_std().is_ok_and(|age| age > max_price_age) {
+                    return Err(CalculationError::StalePriceData {
+                        token: token_address.clone(),
+                        age_secs: age.num_seconds(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Unrealized PnL of each token with a known entry price, valued at current prices.
+    /// Debt legs count against the position when the borrowed token appreciates.
+    pub async fn position_pnl(&self, position_id: PositionId) -> Result<PositionPnl, CalculationError> {
+        let position = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let prices = self.fetch_position_prices(&position, false).await?;
+
+        let mut tokens = Vec::new();
+        let mut untracked_tokens = Vec::new();
+        let legs = position.collateral_tokens.values().map(|token| (token, false))
+            .chain(position.debt_tokens.values().map(|token| (token, true)));
+
+        for (token_position, is_debt) in legs {
+            let entry_price = match token_position.entry_price_usd {
+                Some(entry_price) => entry_price,
+                None => {
+                    untracked_tokens.push(token_position.token_address.clone());
+                    continue;
+                }
+            };
+            let current_price = Self::price_of(&prices, &token_position.token_address)?;
+
+            let price_change = current_price - entry_price;
+            let unrealized_pnl_usd = if is_debt {
+                -price_change * token_position.amount
+            } else {
+                price_change * token_position.amount
+            };
+            let unrealized_pnl_percent = if entry_price > Decimal::ZERO {
+                unrealized_pnl_usd / (entry_price * token_position.amount) * Decimal::from(100)
+            } else {
+                Decimal::ZERO
+            };
+
+            tokens.push(TokenPnl {
+                token_address: token_position.token_address.clone(),
+                is_debt,
+                amount: token_position.amount,
+                entry_price_usd: entry_price,
+                current_price_usd: current_price,
+                unrealized_pnl_usd,
+                unrealized_pnl_percent,
+            });
+        }
+
+        tokens.sort_by(|a, b| a.token_address.cmp(&b.token_address));
+        untracked_tokens.sort();
+
+        Ok(PositionPnl {
+            position_id,
+            total_unrealized_pnl_usd: tokens.iter().map(|t| t.unrealized_pnl_usd).sum(),
+            tokens,
+            untracked_tokens,
+            calculated_at: Utc::now(),
+        })
+    }
+
+    /// Health of a position for each day of a stablecoin depeg, holding all other
+    /// prices at their current values. The depegged token is repriced on both legs,
+    /// so depegged collateral lowers health while depegged debt raises it.
+    pub async fn health_under_depeg(
+        &self,
+        position_id: PositionId,
+        scenario: &DepegScenario,
+    ) -> Result<Vec<DepegHealthPoint>, CalculationError> {
+        let position = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let position = self.apply_rebasing(vec![position]).await?.remove(0);
+        let mut prices = self.fetch_position_prices(&position, false).await?;
+
+        let mut trajectory = Vec::new();
+        for day in 0..=scenario.duration_days() {
+            let stablecoin_price = Decimal::new(scenario.price_bps_at_day(day) as i64, 4);
+            if let Some(price_data) = prices.get_mut(&scenario.stablecoin) {
+                price_data.price_usd = stablecoin_price;
+            }
+
+            trajectory.push(DepegHealthPoint {
+                day,
+                stablecoin_price,
+                health_factor: self.calculate_health_with_prices(&position, &prices)?,
+            });
+        }
+
+        Ok(trajectory)
+    }
+
+    /// Liquidation ladder for a token: for each price level, the total collateral notional
+    /// (valued at current prices) of positions that would be liquidatable were the token to
+    /// trade at that level, all other prices unchanged. Only positions holding the token are
+    /// considered.
+    pub async fn liquidation_ladder(
+        &self,
+        token_address: &TokenAddress,
+        price_levels: &[Decimal],
+    ) -> Result<Vec<(Decimal, Decimal)>, CalculationError> {
+        let positions: Vec<Position> = self.apply_rebasing(self.list_positions()).await?
+            .into_iter()
+            .filter(|p| p.collateral_tokens.contains_key(token_address) || p.debt_tokens.contains_key(token_address))
+            .collect();
+
+        let mut required_tokens: Vec<TokenAddress> = positions.iter()
+            .flat_map(|p| p.collateral_tokens.keys().chain(p.debt_tokens.keys()).cloned())
+            .collect();
+        required_tokens.sort();
+        required_tokens.dedup();
+
+        let mut prices = self.price_feeds.get_prices(&required_tokens).await
+            .map_err(|e| CalculationError::CalculationFailed { 
+                message: format!("Failed to fetch prices: {}", e) 
+            })?;
+        let max_price_age = self.risk_parameters.read().await.max_price_age;
+        Self::reject_stale_prices(&prices, &required_tokens, max_price_age)?;
+
+        let mut current_notional = HashMap::new();
+        for position in &positions {
+            let health_factor = self.calculate_health_with_prices(position, &prices)?;
+            current_notional.insert(position.id, health_factor.collateral_value);
+        }
+
+        let mut ladder = Vec::with_capacity(price_levels.len());
+        for level in price_levels {
+            if let Some(price_data) = prices.get_mut(token_address) {
+                price_data.price_usd = *level;
+            }
+
+            let mut liquidatable = Decimal::ZERO;
+            for position in &positions {
+                if self.calculate_health_with_prices(position, &prices)?.value < Decimal::ONE {
+                    liquidatable += current_notional[&position.id];
+                }
+            }
+            ladder.push((*level, liquidatable));
+        }
+
+        Ok(ladder)
+    }
+
+    /// Position prices with the deviation guard applied, if one is configured
+    async fn fetch_guarded_position_prices(
+        &self,
+        position: &Position,
+        record: bool,
+    ) -> Result<(HashMap<TokenAddress, PriceData>, Vec<PriceDeviation>), CalculationError> {
+        let prices = self.fetch_position_prices(position, record).await?;
+
+        match &self.price_guard {
+            Some(guard) => {
+                let tokens: Vec<TokenAddress> = prices.keys().cloned().collect();
+                let reference_prices = guard.fetch_reference_prices(&tokens).await?;
+                Ok(guard.guard(position, &prices, &reference_prices))
+            }
+            None => Ok((prices, Vec::new())),
+        }
+    }
+
+    /// Fire transition hooks if the position's classification changed since its last
+    /// evaluation. The first evaluation only establishes the baseline.
+    async fn track_risk_level(&self, position: &Position, health_factor: &HealthFactor) {
+        let new_level = health_factor.risk_level(position.risk_parameters(&*self.risk_parameters.read().await));
+        let old_level = match self.risk_levels.insert(position.id, new_level.clone()) {
+            Some(old_level) if old_level != new_level => old_level,
+            _ => return,
+        };
+
+        let transition = RiskLevelTransition {
+            position: position.clone(),
+            old_level,
+            new_level,
+            health_factor: health_factor.value,
+            occurred_at: Utc::now(),
+        };
+        info!("Position {} moved from {:?} to {:?}", position.id, transition.old_level, transition.new_level);
+        for hook in &self.transition_hooks {
+            hook.on_risk_level_transition(&transition).await;
+        }
+    }
+
+    async fn raise_deviation_alert(
+        &self,
+        position: &Position,
+        health_factor: &HealthFactor,
+        deviations: &[PriceDeviation],
+    ) {
+        // Alert only when a token starts deviating, not on every check while it stays apart
+        let deviating: HashSet<TokenAddress> = deviations.iter().map(|d| d.token_address.clone()).collect();
+        let previous = if deviating.is_empty() {
+            self.deviating_tokens.remove(&position.id).map(|(_, tokens)| tokens)
+        } else {
+            self.deviating_tokens.insert(position.id, deviating.clone())
+        };
+        if deviating.iter().all(|token| previous.as_ref().is_some_and(|tokens| tokens.contains(token))) {
+            return;
+        }
+
+        let details: Vec<String> = deviations.iter()
+            .map(|d| format!(
+                "{} spot ${:.2} vs reference ${:.2} ({:.1}% apart, using ${:.2})",
+                d.token_address, d.spot_price, d.reference_price,
+                d.deviation * Decimal::from(100), d.price_used,
+            ))
+            .collect();
+
+        let mut related_tokens: Vec<TokenAddress> = deviations.iter().map(|d| d.token_address.clone()).collect();
+        related_tokens.sort();
+
+        let alert = RiskAlert {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            alert_type: AlertType::OracleDeviation,
+            risk_level: RiskLevel::Warning,
+            health_factor: health_factor.clone(),
+            message: format!("Oracle price deviation, health computed conservatively: {}", details.join("; ")),
+            created_at: Utc::now(),
+            occurrence_count: 1,
+            last_seen: Utc::now(),
+            acknowledged: false,
+            protocol: Some(position.protocol.clone()),
+            related_tokens,
+            projected_seconds_to_liquidation: None,
+        };
+
+        warn!("{}", alert.message);
+        self.metrics.record_alerts_raised(1);
+        if let Err(e) = self.alert_system.send_alert(alert).await {
+            error!("Failed to send oracle deviation alert for position {}: {}", position.id, e);
+        }
+    }
+
+    async fn raise_circuit_breaker_alert(
+        &self,
+        position: &Position,
+        trips: &[PriceTrip],
+        prices: &HashMap<TokenAddress, PriceData>,
+    ) {
+        // Health is not recomputed from the rejected prices, so report the last known value
+        let health_factor = self.health_history.get(&position.id)
+            .and_then(|history| history.back().map(|(_, health)| health.clone()))
+            .unwrap_or_else(|| HealthFactor {
+                value: Decimal::ZERO,
+                liquidation_threshold: Decimal::ZERO,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: Utc::now(),
+            });
+
+        let details: Vec<String> = trips.iter()
+            .map(|trip| format!(
+                "{} ${:.2} is {:.1}% from the last accepted ${:.2} (at {})",
+                trip.token_address, trip.rejected_price, trip.change * Decimal::from(100),
+                trip.last_accepted_price, trip.last_accepted_at,
+            ))
+            .collect();
+        // If the move is real the position may already be in trouble, so say how much
+        let at_rejected_prices = match self.calculate_health_with_prices(position, prices) {
+            Ok(health) => format!("health would be {:.4} at the rejected prices", health.value),
+            Err(_) => "health at the rejected prices is unknown".to_string(),
+        };
+
+        let alert = RiskAlert {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            alert_type: AlertType::PriceCircuitBreaker,
+            risk_level: RiskLevel::Critical,
+            health_factor,
+            message: format!(
+                "Price circuit breaker open, health not tracked until it closes: {}; {}",
+                details.join("; "), at_rejected_prices,
+            ),
+            created_at: Utc::now(),
+            occurrence_count: 1,
+            last_seen: Utc::now(),
+            acknowledged: false,
+            protocol: Some(position.protocol.clone()),
+            related_tokens: trips.iter().map(|trip| trip.token_address.clone()).collect(),
+            projected_seconds_to_liquidation: None,
+        };
+
+        warn!("{}", alert.message);
+        self.metrics.record_alerts_raised(1);
+        if let Err(e) = self.alert_system.send_alert(alert).await {
+            error!("Failed to send circuit breaker alert for position {}: {}", position.id, e);
+        }
+    }
+
+    fn price_of(prices: &HashMap<TokenAddress, PriceData>, token_address: &TokenAddress) -> Result<Decimal, CalculationError> {
+        prices.get(token_address)
+            .map(|price_data| price_data.price_usd)
+            .ok_or_else(|| CalculationError::MissingPriceData { token: token_address.clone() })
+    }
+
+    pub(crate) fn calculate_health_with_prices(
+        &self,
+        position: &Position,
+        prices: &HashMap<TokenAddress, PriceData>,
+    ) -> Result<HealthFactor, CalculationError> {
+        let calculator = self.health_calculators.get(&position.protocol)
+            .ok_or(CalculationError::UnsupportedProtocol { 
+                protocol: position.protocol.clone() 
+            })?;
+
+        // Check every leg up front so the error names the same token regardless of map order
+        let mut collateral: Vec<&TokenAddress> = position.collateral_tokens.keys().collect();
+        let mut debt: Vec<&TokenAddress> = position.debt_tokens.keys().collect();
+        collateral.sort();
+        debt.sort();
+        if let Some(token_address) = collateral.into_iter().chain(debt).find(|t| !prices.contains_key(*t)) {
+            return Err(CalculationError::MissingPriceData { token: token_address.clone() });
+        }
+
+        calculator.calculate_health(position, prices)
+    }
+
+    pub async fn monitor_positions(&self) -> Vec<RiskAlert> {
+        self.monitor_position_batch(0, 1).await
+    }
+
+    /// Monitor one of `batch_count` disjoint slices of the book, so a full pass can be
+    /// spread across several ticks instead of hitting the price feed all at once
+    #[instrument(
+        name = "monitor_positions",
+        skip(self),
+        fields(positions_monitored = tracing::field::Empty, alerts = tracing::field::Empty)
+    )]
+    pub async fn monitor_position_batch(&self, batch: usize, batch_count: usize) -> Vec<RiskAlert> {
+        let batch_count = batch_count.max(1) as u128;
+
+        // Snapshot the slice so the map isn't locked while prices are fetched
+        let positions: Vec<Position> = self.positions.iter()
+            .filter(|position_ref| position_ref.key().as_u128() % batch_count == batch as u128 % batch_count)
+            .map(|position_ref| position_ref.value().clone())
+            .collect();
+        self.monitor_position_set(positions).await
+    }
+
+    /// Recalculate the positions holding any of `token_addresses`, e.g. when a push feed
+    /// reports new prices for them, alerting as a monitoring pass would
+    pub async fn on_price_update(&self, token_addresses: &[TokenAddress]) -> Vec<RiskAlert> {
+        let mut position_ids: Vec<PositionId> = token_addresses.iter()
+            .flat_map(|token| self.positions_holding(token))
+            .collect();
+        position_ids.sort();
+        position_ids.dedup();
+
+        let positions = position_ids.into_iter().filter_map(|id| self.get_position(id)).collect();
+        self.monitor_position_set(positions).await
+    }
+
+    /// Fetch the price of every held token once and recalculate only the positions holding
+    /// a token whose price moved since the previous pass. The first pass recalculates
+    /// everything; tokens without a price are always rechecked so quarantine still applies,
+    /// and so are prices older than `max_price_age`, so a frozen oracle is still reported.
+    #[instrument(
+        name = "monitor_price_changes",
+        skip(self),
+        fields(changed_tokens = tracing::field::Empty, positions_monitored = tracing::field::Empty, alerts = tracing::field::Empty)
+    )]
+    pub async fn monitor_price_changes(&self) -> Vec<RiskAlert> {
+        let mut tokens: Vec<TokenAddress> = self.token_positions.iter().map(|entry| entry.key().clone()).collect();
+        tokens.sort();
+
+        let prices = match self.price_feeds.get_prices(&tokens).await {
+            Ok(prices) => prices,
+            Err(e) => {
+                // A full pass reports the failure against every position, as interval polling does
+                warn!("Price change check failed, falling back to a full pass: {}", e);
+                return self.monitor_positions().await;
+            }
+        };
+
+        let max_price_age = self.risk_parameters.read().await.max_price_age;
+        let now = Utc::now();
+        let changed: Vec<TokenAddress> = tokens.into_iter()
+            .filter(|token| match prices.get(token) {
+                Some(price) => {
+                    let moved = self.last_seen_prices.insert(token.clone(), price.price_usd) != Some(price.price_usd);
+                    let stale = (now - price.timestamp).to_std().is_ok_and(|age| age > max_price_age);
+                    moved || stale
+                }
+                None => true,
+            })
+            .collect();
+        Span::current().record("changed_tokens", changed.len());
+        if changed.is_empty() {
+            return Vec::new();
+        }
+        debug!("Prices moved for {} tokens", changed.len());
+        self.on_price_update(&changed).await
+    }
+
+    async fn monitor_position_set(&self, positions: Vec<Position>) -> Vec<RiskAlert> {
+        let mut alerts = Vec::new();
+        let mut to_quarantine = Vec::new();
+        let monitored = positions.len();
+
+        // Calculations run concurrently, with at most `max_concurrent_health_calcs` in flight
+        let results = futures::future::join_all(positions.iter().map(|position| async move {
+            self.queued_health_calcs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let permit = self.health_calc_limit.acquire().await;
+            self.queued_health_calcs.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+            let health = match permit {
+                Ok(_permit) => self.evaluate_position(position.id, true).await,
+                Err(e) => Err(CalculationError::CalculationFailed { message: e.to_string() }),
+            };
+            (position, health)
+        })).await;
+
+        let risk_params = self.risk_parameters.read().await;
+        for (position, health) in results {
+            let position_id = position.id;
+            match health {
+                Ok(health_factor) => {
+                    self.missing_price_failures.remove(&position_id);
+                    let position_params = position.risk_parameters(&risk_params);
+                    if health_factor.is_at_risk(position_params) {
+                        let risk_level = health_factor.risk_level(position_params);
+                        let alert = self.create_liquidation_alert(
+                            position,
+                            &health_factor,
+                            risk_level,
+                        );
+                        alerts.push(alert);
+                    }
+                    alerts.extend(self.check_position_size(position, &health_factor, position_params));
+                }
+                Err(CalculationError::MissingPriceData { token }) if self.record_missing_price(position_id) => {
+                    let failures = self.missing_price_failures.get(&position_id).map(|f| *f).unwrap_or(0);
+                    warn!("Quarantining position {} after {} cycles without a price for {}", position_id, failures, token);
+                    alerts.push(self.create_unpriceable_alert(position, &token, failures));
+                    to_quarantine.push((position_id, token, failures));
+                }
+                Err(CalculationError::PriceCircuitOpen { token, .. }) => {
+                    // Alerted at Critical when the breaker first held the position back
+                    debug!("Skipping position {} while the {} price is held by the circuit breaker", position_id, token);
+                }
+                Err(e) => {
+                    error!("Failed to calculate health for position {}: {}", position_id, e);
+                    // Create an error alert
+                    let alert = RiskAlert {
+                        id: Uuid::new_v4(),
+                        position_id,
+                        alert_type: AlertType::LiquidationRisk,
+                        risk_level: RiskLevel::Critical,
+                        health_factor: HealthFactor {
+                            value: rust_decimal::Decimal::ZERO,
+                            liquidation_threshold: rust_decimal::Decimal::ZERO,
+                            collateral_value: rust_decimal::Decimal::ZERO,
+                            debt_value: rust_decimal::Decimal::ZERO,
+                            calculated_at: Utc::now(),
+                        },
+                        message: format!("Health calculation failed: {}", e),
+                        created_at: Utc::now(),
+                        occurrence_count: 1,
+                        last_seen: Utc::now(),
+                        acknowledged: false,
+                        protocol: Some(position.protocol.clone()),
+                        related_tokens: Self::alert_tokens(position),
+                        projected_seconds_to_liquidation: None,
+                    };
+                    alerts.push(alert);
+                }
+            }
+        }
+
+        alerts.extend(self.check_protocol_exposure(&risk_params));
+        drop(risk_params);
+
+        // Quarantined positions are moved out only after the whole slice was evaluated. Their
+        // health history is kept as the record of how they got there.
+        for (position_id, missing_token, failure_count) in to_quarantine {
+            self.missing_price_failures.remove(&position_id);
+            self.risk_levels.remove(&position_id);
+            self.deviating_tokens.remove(&position_id);
+            if let Some(position) = self.take_position(position_id) {
+                self.quarantined_positions.insert(position_id, QuarantinedPosition {
+                    position,
+                    missing_token,
+                    failure_count,
+                    quarantined_at: Utc::now(),
+                });
+            }
+        }
+
+        self.metrics.set_positions_monitored(monitored);
+        self.metrics.record_alerts_raised(alerts.len());
+        Span::current().record("positions_monitored", monitored);
+        Span::current().record("alerts", alerts.len());
+
+        // Send alerts through alert system
+        for alert in &alerts {
+            if let Err(e) = self.alert_system.send_alert(alert.clone()).await {
+                error!("Failed to send alert {}: {}", alert.id, e);
+            }
+        }
+
+        alerts
+    }
+
+    async fn check_position_health(&self, position_id: PositionId) -> Result<(), CalculationError> {
+        let health_factor = self.calculate_health(position_id).await?;
+        let risk_params = self.risk_parameters.read().await;
+        let position = self.get_position(position_id)
+            .ok_or(CalculationError::CalculationFailed { 
+                message: format!("Position {} not found", position_id) 
+            })?;
+        let position_params = position.risk_parameters(&risk_params);
+        
+        let mut alerts = Vec::new();
+        if health_factor.is_at_risk(position_params) {
+            let risk_level = health_factor.risk_level(position_params);
+            alerts.push(self.create_liquidation_alert(&position, &health_factor, risk_level));
+        }
+        alerts.extend(self.check_position_size(&position, &health_factor, position_params));
+        self.metrics.record_alerts_raised(alerts.len());
+
+        for alert in alerts {
+            if let Err(e) = self.alert_system.send_alert(alert).await {
+                error!("Failed to send immediate alert for position {}: {}", position_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Alert when a position's collateral first grows past `max_position_size_usd`. It is
+    /// flagged again only after coming back within the limit.
+    fn check_position_size(&self, position: &Position, health_factor: &HealthFactor, params: &RiskParameters) -> Option<RiskAlert> {
+        if health_factor.collateral_value <= params.max_position_size_usd {
+            self.oversized_positions.remove(&position.id);
+            return None;
+        }
+        if !self.oversized_positions.insert(position.id) {
+            return None;
+        }
+
+        warn!("Position {} holds ${:.2} of collateral, above the ${} limit",
+              position.id, health_factor.collateral_value, params.max_position_size_usd);
+        Some(RiskAlert {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            alert_type: AlertType::PositionSizeExceeded,
+            risk_level: RiskLevel::Warning,
+            health_factor: health_factor.clone(),
+            message: format!(
+                "Position {} holds ${:.2} of collateral, above the ${} position size limit",
+                position.id, health_factor.collateral_value, params.max_position_size_usd
+            ),
+            created_at: Utc::now(),
+            occurrence_count: 1,
+            last_seen: Utc::now(),
+            acknowledged: false,
+            protocol: Some(position.protocol.clone()),
+            related_tokens: Self::alert_tokens(position),
+            projected_seconds_to_liquidation: None,
+        })
+    }
+
+    fn create_liquidation_alert(
+        &self,
+        position: &Position,
+        health_factor: &HealthFactor,
+        risk_level: RiskLevel,
+    ) -> RiskAlert {
+        let position_id = position.id;
+        let message = match risk_level {
+            RiskLevel::Emergency => format!(
+                "EMERGENCY: Position {} is at immediate liquidation risk! Health factor: {:.4}",
+                position_id, health_factor.value
+            ),
+            RiskLevel::Critical => format!(
+                "CRITICAL: Position {} approaching liquidation. Health factor: {:.4}",
+                position_id, health_factor.value
+            ),
+            RiskLevel::Warning => format!(
+                "WARNING: Position {} health declining. Health factor: {:.4}",
+                position_id, health_factor.value
+            ),
+            RiskLevel::Safe => format!(
+                "Position {} is healthy. Health factor: {:.4}",
+                position_id, health_factor.value
+            ),
+        };
+
+        RiskAlert {
+            id: Uuid::new_v4(),
+            position_id,
+            alert_type: AlertType::LiquidationRisk,
+            risk_level,
+            health_factor: health_factor.clone(),
+            message,
+            created_at: Utc::now(),
+            occurrence_count: 1,
+            last_seen: Utc::now(),
+            acknowledged: false,
+            protocol: Some(position.protocol.clone()),
+            related_tokens: Self::alert_tokens(position),
+            projected_seconds_to_liquidation: self.projected_seconds_to_liquidation(position_id),
+        }
+    }
+
+    /// Collateral tokens whose price moves drive a position's liquidation risk
+    fn alert_tokens(position: &Position) -> Vec<TokenAddress> {
+        let mut tokens: Vec<TokenAddress> = position.collateral_tokens.keys().cloned().collect();
+        tokens.sort();
+        tokens
+    }
+
+    pub async fn update_risk_parameters(&self, new_params: RiskParameters) {
+        let mut params = self.risk_parameters.write().await;
+        *params = new_params;
+        info!("Updated risk parameters");
+    }
+
+    pub async fn get_risk_parameters(&self) -> RiskParameters {
+        self.risk_parameters.read().await.clone()
+    }
+
+    /// Collateral value held in each protocol, from each position's latest health
+    /// calculation, sorted by protocol. Positions not yet calculated are left out.
+    pub fn protocol_exposures(&self) -> Vec<ProtocolExposure> {
+        Self::exposures_by_protocol(self.positions.iter().filter_map(|position| {
+            let history = self.health_history.get(position.key())?;
+            let (_, health) = history.back()?;
+            Some((position.protocol.clone(), health.collateral_value))
+        }))
+    }
+
+    fn exposures_by_protocol(collateral_values: impl Iterator<Item = (ProtocolId, Decimal)>) -> Vec<ProtocolExposure> {
+        let mut exposures: BTreeMap<ProtocolId, Decimal> = BTreeMap::new();
+        for (protocol, collateral_value) in collateral_values {
+            *exposures.entry(protocol).or_default() += collateral_value;
+        }
+
+        let total: Decimal = exposures.values().copied().sum();
+        exposures.into_iter()
+            .map(|(protocol, exposure_usd)| ProtocolExposure {
+                protocol,
+                exposure_usd,
+                portfolio_percent: if total > Decimal::ZERO {
+                    exposure_usd / total * Decimal::from(100)
+                } else {
+                    Decimal::ZERO
+                },
+            })
+            .collect()
+    }
+
+    /// Alerts for protocols that have gone over `max_protocol_exposure_percent` of the
+    /// portfolio since the last check. A protocol is flagged again only after falling back
+    /// under the limit.
+    fn check_protocol_exposure(&self, risk_params: &RiskParameters) -> Vec<RiskAlert> {
+        let exposures = self.protocol_exposures();
+        let breaching: Vec<ProtocolExposure> = if exposures.len() < self.min_protocols_for_exposure_alerts {
+            Vec::new()
+        } else {
+            exposures.into_iter()
+                .filter(|exposure| exposure.portfolio_percent > risk_params.max_protocol_exposure_percent)
+                .collect()
+        };
+
+        let mut breaches = self.exposure_breaches.lock().unwrap();
+        let previous = std::mem::replace(
+            &mut *breaches,
+            breaching.iter().map(|exposure| exposure.protocol.clone()).collect(),
+        );
+        breaching.into_iter()
+            .filter(|exposure| !previous.contains(&exposure.protocol))
+            .map(|exposure| {
+                warn!("Exposure to {} is {:.2}% of the portfolio, above the {}% limit",
+                      exposure.protocol, exposure.portfolio_percent, risk_params.max_protocol_exposure_percent);
+                let message = format!(
+                    "Exposure to {} is ${:.2} ({:.2}% of the portfolio), above the {}% limit",
+                    exposure.protocol, exposure.exposure_usd, exposure.portfolio_percent,
+                    risk_params.max_protocol_exposure_percent
+                );
+                RiskAlert {
+                    protocol: Some(exposure.protocol),
+                    ..RiskAlert::portfolio(AlertType::ProtocolExposureExceeded, RiskLevel::Warning, message)
+                }
+            })
+            .collect()
+    }
+
+    /// Store `position`, re-indexing its tokens if it replaces an earlier version
+    fn insert_position(&self, position: Position) {
+        let position_id = position.id;
+        let tokens = Self::held_tokens(&position);
+        if let Some(previous) = self.positions.insert(position_id, position) {
+            self.unindex_tokens(position_id, &Self::held_tokens(&previous));
+        }
+        for token in tokens {
+            self.token_positions.entry(token).or_default().insert(position_id);
+        }
+    }
+
+    fn take_position(&self, position_id: PositionId) -> Option<Position> {
+        let (_, position) = self.positions.remove(&position_id)?;
+        self.unindex_tokens(position_id, &Self::held_tokens(&position));
+        Some(position)
+    }
+
+    /// Collateral and debt tokens of `position`, each once
+    fn held_tokens(position: &Position) -> Vec<TokenAddress> {
+        let mut tokens: Vec<TokenAddress> = position.collateral_tokens.keys()
+            .chain(position.debt_tokens.keys())
+            .cloned()
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+        tokens
+    }
+
+    fn unindex_tokens(&self, position_id: PositionId, tokens: &[TokenAddress]) {
+        for token in tokens {
+            if let Some(mut holders) = self.token_positions.get_mut(token) {
+                holders.remove(&position_id);
+            }
+            if self.token_positions.remove_if(token, |_, holders| holders.is_empty()).is_some() {
+                self.last_seen_prices.remove(token);
+            }
+        }
+    }
+
+    /// Monitored positions with `token_address` as collateral or debt
+    pub fn positions_holding(&self, token_address: &TokenAddress) -> Vec<PositionId> {
+        let mut positions: Vec<PositionId> = self.token_positions.get(token_address)
+            .map(|holders| holders.iter().copied().collect())
+            .unwrap_or_default();
+        positions.sort();
+        positions
+    }
+
+    pub fn get_position(&self, position_id: PositionId) -> Option<Position> {
+        self.positions.get(&position_id).map(|p| p.clone())
+    }
+
+    pub fn list_positions(&self) -> Vec<Position> {
+        self.positions.iter().map(|p| p.value().clone()).collect()
+    }
+
+    pub fn position_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Positions taken out of monitoring because a token price went missing
+    pub fn get_quarantined_positions(&self) -> Vec<QuarantinedPosition> {
+        self.quarantined_positions.iter().map(|q| q.value().clone()).collect()
+    }
+
+    /// Return a quarantined position to monitoring, e.g. once its token is priced again
+    pub fn release_quarantined_position(&self, position_id: PositionId) -> Result<PositionId, PositionError> {
+        let (_, quarantined) = self.quarantined_positions.remove(&position_id)
+            .ok_or(PositionError::NotFound { id: position_id })?;
+
+        info!("Releasing position {} from quarantine", position_id);
+        self.insert_position(quarantined.position);
+        Ok(position_id)
+    }
+
+    /// Count a missing-price failure; returns whether the position should now be quarantined.
+    /// Feed outages surface as other errors and are deliberately not counted.
+    fn record_missing_price(&self, position_id: PositionId) -> bool {
+        let threshold = match self.quarantine_after_failures {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+
+        let mut failures = self.missing_price_failures.entry(position_id).or_insert(0);
+        *failures += 1;
+        *failures >= threshold
+    }
+
+    fn create_unpriceable_alert(&self, position: &Position, missing_token: &TokenAddress, failures: u32) -> RiskAlert {
+        RiskAlert {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            alert_type: AlertType::UnpriceablePosition,
+            risk_level: RiskLevel::Warning,
+            health_factor: HealthFactor {
+                value: Decimal::ZERO,
+                liquidation_threshold: Decimal::ZERO,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: Utc::now(),
+            },
+            message: format!(
+                "Position quarantined: no price for {} in {} consecutive checks",
+                missing_token, failures
+            ),
+            created_at: Utc::now(),
+            occurrence_count: 1,
+            last_seen: Utc::now(),
+            acknowledged: false,
+            protocol: Some(position.protocol.clone()),
+            related_tokens: vec![missing_token.clone()],
+            projected_seconds_to_liquidation: None,
+        }
+    }
+}
+
+/// A position in a risk ranking with its health and risk level, or why they couldn't be calculated
+pub type RankedPosition = (PositionId, Result<(HealthFactor, RiskLevel), CalculationError>);
+
+/// Collateral value held in one protocol across all monitored positions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolExposure {
+    pub protocol: ProtocolId,
+    pub exposure_usd: Decimal,
+    /// Share of the collateral value of all monitored positions, 0-100
+    pub portfolio_percent: Decimal,
+}
+
+/// A hypothetical change to the monitored positions, see `LiquidationMonitor::what_if`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PositionChange {
+    Add { position: Box<Position> },
+    Remove { position_id: PositionId },
+    /// Scale every collateral and debt leg of a position, e.g. by 1.5 to make it half again as large
+    Resize { position_id: PositionId, factor: Decimal },
+}
+
+impl PositionChange {
+    pub fn position_id(&self) -> PositionId {
+        match self {
+            PositionChange::Add { position } => position.id,
+            PositionChange::Remove { position_id } | PositionChange::Resize { position_id, .. } => *position_id,
+        }
+    }
+}
+
+/// Aggregate risk of a set of positions valued from one price snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioHealth {
+    /// Positions that could be priced and are counted below
+    pub position_count: usize,
+    pub total_collateral_usd: Decimal,
+    pub total_debt_usd: Decimal,
+    /// Debt-weighted average health factor; `None` when nothing is borrowed
+    pub aggregate_health_factor: Option<Decimal>,
+    /// Lowest health factor among positions with debt
+    pub lowest_health_factor: Option<Decimal>,
+    /// Positions at `RiskLevel::Warning` or worse
+    pub positions_at_risk: usize,
+    /// Herfindahl index of position collateral values, from 1/n for equal sizes up to 1.0
+    pub concentration_index: f64,
+    pub protocol_exposures: Vec<ProtocolExposure>,
+}
+
+/// Health of one added, removed or resized position; `None` where it doesn't exist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionHealthChange {
+    pub position_id: PositionId,
+    pub before: Option<Decimal>,
+    pub after: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhatIfResult {
+    pub before: PortfolioHealth,
+    pub after: PortfolioHealth,
+    /// Changed positions in id order
+    pub position_changes: Vec<PositionHealthChange>,
+}
+
+/// A position excluded from monitoring because it cannot be priced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedPosition {
+    pub position: Position,
+    pub missing_token: TokenAddress,
+    pub failure_count: u32,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// Health of all monitored positions computed from one consistent set of prices.
+#[derive(Debug)]
+pub struct RiskSnapshot {
+    pub prices: HashMap<TokenAddress, PriceData>,
+    pub health_factors: HashMap<PositionId, Result<HealthFactor, CalculationError>>,
+    pub taken_at: DateTime<Utc>,
+}
+
+/// A health evaluation together with the prices it was computed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthRecord {
+    pub id: Uuid,
+    pub position_id: PositionId,
+    pub health_factor: HealthFactor,
+    pub prices_used: Vec<PriceData>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepegHealthPoint {
+    pub day: u32,
+    pub stablecoin_price: Decimal,
+    pub health_factor: HealthFactor,
+}
+
+/// Unrealized gain or loss of one leg of a position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPnl {
+    pub token_address: TokenAddress,
+    pub is_debt: bool,
+    pub amount: Decimal,
+    pub entry_price_usd: Decimal,
+    pub current_price_usd: Decimal,
+    pub unrealized_pnl_usd: Decimal,
+    pub unrealized_pnl_percent: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionPnl {
+    pub position_id: PositionId,
+    pub tokens: Vec<TokenPnl>,
+    pub total_unrealized_pnl_usd: Decimal,
+    /// Tokens without an entry price, left out of the totals
+    pub untracked_tokens: Vec<TokenAddress>,
+    pub calculated_at: DateTime<Utc>,
+}
+
+/// A relative move in one token's price, e.g. -0.2 for a 20% drop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub token_address: TokenAddress,
+    pub relative_change: Decimal,
+}
+
+/// How several changes supplied for the same token are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PriceChangeMerge {
+    /// Treat duplicates as a caller error
+    #[default]
+    Reject,
+    /// Compound the moves multiplicatively
+    Compound,
+}
+
+/// USD amounts needed to lift a position to a target health factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequiredTopup {
+    pub position_id: PositionId,
+    pub current_health: Decimal,
+    pub target_health: Decimal,
+    /// Additional collateral value required if topping up with only this token
+    pub collateral_topups: HashMap<TokenAddress, Decimal>,
+    /// Repayment required if repaying only this debt token; omitted when its outstanding debt is too small
+    pub debt_repayments: HashMap<TokenAddress, Decimal>,
+    pub calculated_at: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait PriceFeedProvider: Send + Sync {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>>;
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+#[async_trait::async_trait]
+pub trait AlertSystem: Send + Sync {
     async fn send_alert(&self, alert: RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     async fn get_alerts(&self, position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>>;
-    async fn acknowledge_alert(&self, alert_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
-}
\ No newline at end of file
+    /// Mark `alert_id` acknowledged on behalf of `UNATTRIBUTED_ACTOR`
+    async fn acknowledge_alert(&self, alert_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.acknowledge_alert_with_note(alert_id, UNATTRIBUTED_ACTOR, None).await
+    }
+    /// Mark `alert_id` acknowledged by `acknowledged_by`, with an optional note on why
+    async fn acknowledge_alert_with_note(&self, alert_id: Uuid, acknowledged_by: &str, note: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Recorded as the acknowledging actor when an alert is acknowledged without naming one
+pub const UNATTRIBUTED_ACTOR: &str = "unattributed";
+
+/// A change in a position's risk classification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskLevelTransition {
+    pub position: Position,
+    pub old_level: RiskLevel,
+    pub new_level: RiskLevel,
+    pub health_factor: Decimal,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+pub trait RiskLevelTransitionHook: Send + Sync {
+    async fn on_risk_level_transition(&self, transition: &RiskLevelTransition);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::NullAlertSystem;
+    use crate::types::{HealthCalculator, PositionToken};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Price feed whose ETH price moves by $100 on every call.
+    struct DriftingPriceFeed {
+        calls: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for DriftingPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = if token_address == "ETH" {
+                let call = self.calls.fetch_add(1, Ordering::SeqCst);
+                Decimal::from(2000 + 100 * call)
+            } else {
+                Decimal::ONE
+            };
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd,
+                timestamp: Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    struct StaticPriceFeed {
+        prices: HashMap<TokenAddress, Decimal>,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for StaticPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = *self.prices.get(token_address)
+                .ok_or_else(|| format!("no price for {}", token_address))?;
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd,
+                timestamp: Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    fn static_feed(prices: &[(&str, i64)]) -> Arc<StaticPriceFeed> {
+        Arc::new(StaticPriceFeed {
+            prices: prices.iter().map(|(token, price)| (token.to_string(), Decimal::from(*price))).collect(),
+        })
+    }
+
+    fn assert_health_close(actual: Decimal, expected: Decimal) {
+        let tolerance = Decimal::new(1, 18);
+        assert!((actual - expected).abs() < tolerance, "health {} != {}", actual, expected);
+    }
+
+    fn token(address: &str, amount: i64) -> (TokenAddress, PositionToken) {
+        (address.to_string(), PositionToken {
+            token_address: address.to_string(),
+            amount: Decimal::from(amount),
+            value_usd: Decimal::ZERO,
+            price_per_token: Decimal::ZERO,
+            entry_price_usd: None,
+        })
+    }
+
+    fn eth_position(eth_amount: i64, usdc_debt: i64) -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", eth_amount)]),
+            debt_tokens: HashMap::from([token("USDC", usdc_debt)]),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            risk_overrides: None,
+        }
+    }
+
+    /// Index provider whose stETH index is advanced by the test to simulate accrual.
+    struct SettableIndexProvider {
+        index: std::sync::Mutex<Decimal>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::liquidation::RebaseIndexProvider for SettableIndexProvider {
+        async fn get_indices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, Decimal>, Box<dyn std::error::Error + Send + Sync>> {
+            let index = *self.index.lock().unwrap();
+            Ok(token_addresses.iter().map(|token| (token.clone(), index)).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_uses_one_price_for_shared_token() {
+        let monitor = LiquidationMonitor::new(
+            Arc::new(DriftingPriceFeed { calls: AtomicU64::new(0) }),
+            Arc::new(NullAlertSystem),
+        );
+
+        let first = eth_position(1, 1000);
+        let second = eth_position(3, 2000);
+        monitor.add_position(first.clone()).await.unwrap();
+        monitor.add_position(second.clone()).await.unwrap();
+
+        let snapshot = monitor.evaluate_snapshot().await.unwrap();
+        let eth_price = snapshot.prices["ETH"].price_usd;
+
+        let first_health = snapshot.health_factors[&first.id].as_ref().unwrap();
+        let second_health = snapshot.health_factors[&second.id].as_ref().unwrap();
+
+        assert_eq!(first_health.collateral_value, eth_price);
+        assert_eq!(second_health.collateral_value, eth_price * Decimal::from(3));
+    }
+
+    #[tokio::test]
+    async fn test_required_topup_reaches_target_health() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        );
+        let position = eth_position(1, 1500);
+        monitor.add_position(position.clone()).await.unwrap();
+
+        let target = Decimal::new(15, 1);
+        let topup = monitor.required_topup(position.id, target).await.unwrap();
+
+        // 1 ETH at $2000 weighted 80% against $1500 debt needs $812.50 more ETH to reach 1.5
+        let eth_topup_usd = topup.collateral_topups["ETH"];
+        assert_health_close(eth_topup_usd, Decimal::new(8125, 1));
+
+        let health_after = |changed: Position| {
+            let monitor = &monitor;
+            async move {
+                let result = monitor.what_if(&[
+                    PositionChange::Remove { position_id: changed.id },
+                    PositionChange::Add { position: Box::new(changed) },
+                ]).await.unwrap();
+                result.position_changes[0].after.unwrap()
+            }
+        };
+
+        let mut topped_up = position.clone();
+        topped_up.collateral_tokens.get_mut("ETH").unwrap().amount += eth_topup_usd / Decimal::from(2000);
+        assert_health_close(health_after(topped_up).await, target);
+
+        let mut repaid = position.clone();
+        repaid.debt_tokens.get_mut("USDC").unwrap().amount -= topup.debt_repayments["USDC"];
+        assert_health_close(health_after(repaid).await, target);
+    }
+
+    #[tokio::test]
+    async fn test_required_topup_is_empty_when_already_healthy() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        );
+        let position = eth_position(10, 1000);
+        monitor.add_position(position.clone()).await.unwrap();
+
+        let topup = monitor.required_topup(position.id, Decimal::new(15, 1)).await.unwrap();
+        assert!(topup.collateral_topups.is_empty());
+        assert!(topup.debt_repayments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rebasing_collateral_health_rises_at_flat_price() {
+        let indices = Arc::new(SettableIndexProvider { index: std::sync::Mutex::new(Decimal::ONE) });
+        let valuation = Arc::new(RebasingValuation::new(indices.clone(), vec!["stETH".to_string()]));
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("stETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        ).with_rebasing_valuation(valuation);
+
+        let mut position = eth_position(10, 15_000);
+        position.collateral_tokens = HashMap::from([token("stETH", 10)]);
+        let position_id = monitor.add_position(position).await.unwrap();
+
+        let mut previous = Decimal::ZERO;
+        for index in [Decimal::ONE, Decimal::new(102, 2), Decimal::new(105, 2)] {
+            *indices.index.lock().unwrap() = index;
+
+            let health = monitor.calculate_health(position_id).await.unwrap();
+            // Aave: 10 scaled stETH * index * $2000 * 0.8 / $15,000 debt
+            let expected = Decimal::from(16_000) * index / Decimal::from(15_000);
+            assert_health_close(health.value, expected);
+            assert_eq!(health.collateral_value, Decimal::from(20_000) * index);
+            assert!(health.value > previous);
+            previous = health.value;
+        }
+    }
+
+    struct RecordingAlertSystem {
+        alerts: std::sync::Mutex<Vec<RiskAlert>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AlertSystem for RecordingAlertSystem {
+        async fn send_alert(&self, alert: RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.alerts.lock().unwrap().push(alert);
+            Ok(())
+        }
+
+        async fn get_alerts(&self, _position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(self.alerts.lock().unwrap().clone())
+        }
+
+        async fn acknowledge_alert_with_note(&self, _alert_id: Uuid, _acknowledged_by: &str, _note: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    struct TwapFeed(Arc<StaticPriceFeed>);
+
+    #[async_trait::async_trait]
+    impl crate::liquidation::ReferencePriceProvider for TwapFeed {
+        async fn get_reference_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            self.0.get_prices(token_addresses).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_deviation_uses_conservative_price_and_alerts() {
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let guard = Arc::new(crate::liquidation::PriceDeviationGuard::new(
+            Arc::new(TwapFeed(static_feed(&[("ETH", 1500), ("USDC", 1)]))),
+            Decimal::new(10, 2), // 10%
+        ));
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            alerts.clone(),
+        ).with_price_deviation_guard(guard);
+
+        let position_id = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+
+        let health = monitor.calculate_health(position_id).await.unwrap();
+
+        // Spot $2000 is 33% above the $1500 TWAP, so collateral is valued at the TWAP
+        assert_health_close(health.value, Decimal::new(12, 1));
+        assert_eq!(health.collateral_value, Decimal::from(15_000));
+
+        // Raised by the monitoring cycle, not by calculating health
+        assert!(alerts.alerts.lock().unwrap().is_empty());
+        monitor.monitor_positions().await;
+        let raised = alerts.alerts.lock().unwrap().clone();
+        assert_eq!(raised.len(), 1);
+        assert!(matches!(raised[0].alert_type, AlertType::OracleDeviation));
+        assert_eq!(raised[0].position_id, position_id);
+        assert_eq!(raised[0].related_tokens, vec!["ETH".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_price_deviation_alerts_once_until_it_clears() {
+        let spot = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let guard = Arc::new(crate::liquidation::PriceDeviationGuard::new(
+            Arc::new(TwapFeed(static_feed(&[("ETH", 1500), ("USDC", 1)]))),
+            Decimal::new(10, 2), // 10%
+        ));
+        let monitor = LiquidationMonitor::new(spot.clone(), alerts.clone()).with_price_deviation_guard(guard);
+        let deviation_alerts = || alerts.alerts.lock().unwrap().iter()
+            .filter(|alert| matches!(alert.alert_type, AlertType::OracleDeviation))
+            .count();
+
+        monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+        monitor.monitor_positions().await;
+        assert_eq!(deviation_alerts(), 1);
+
+        // A deviation that persists doesn't alert again
+        monitor.monitor_positions().await;
+        monitor.monitor_positions().await;
+        assert_eq!(deviation_alerts(), 1);
+
+        // Once spot is back in line, a fresh deviation does
+        *spot.eth_price.lock().unwrap() = Decimal::from(1500);
+        monitor.monitor_positions().await;
+        *spot.eth_price.lock().unwrap() = Decimal::from(2000);
+        monitor.monitor_positions().await;
+        assert_eq!(deviation_alerts(), 2);
+    }
+
+    fn circuit_breaker() -> Arc<crate::liquidation::PriceCircuitBreaker> {
+        Arc::new(crate::liquidation::PriceCircuitBreaker::new(crate::liquidation::PriceCircuitBreakerConfig {
+            max_move: Decimal::new(30, 2), // 30%
+            window: std::time::Duration::from_secs(300),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_rejects_spike_and_resets() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let breaker = circuit_breaker();
+        let monitor = LiquidationMonitor::new(feed.clone(), alerts.clone())
+            .with_price_circuit_breaker(breaker.clone());
+
+        // Health is ETH price / 1250: 1.6 at $2000
+        let position_id = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+        monitor.monitor_positions().await;
+        alerts.alerts.lock().unwrap().clear();
+
+        // A glitch reports a 90% drop
+        *feed.eth_price.lock().unwrap() = Decimal::from(200);
+        match monitor.calculate_health(position_id).await {
+            Err(CalculationError::PriceCircuitOpen { token, last_accepted_price, rejected_price }) => {
+                assert_eq!(token, "ETH");
+                assert_eq!(last_accepted_price, Decimal::from(2000));
+                assert_eq!(rejected_price, Decimal::from(200));
+            }
+            other => panic!("expected the circuit breaker to trip, got {:?}", other),
+        }
+        // Only a monitoring cycle trips the breaker
+        assert!(!breaker.is_tripped(&"ETH".to_string()));
+        assert!(monitor.monitor_positions().await.is_empty());
+        assert!(breaker.is_tripped(&"ETH".to_string()));
+        assert_eq!(monitor.get_health_history(position_id, Utc::now() - chrono::Duration::hours(1)).len(), 1);
+
+        // Further cycles on the glitched price raise neither liquidation nor repeat alerts
+        assert!(monitor.monitor_positions().await.is_empty());
+        let raised = alerts.alerts.lock().unwrap().clone();
+        assert_eq!(raised.len(), 1);
+        assert!(matches!(raised[0].alert_type, AlertType::PriceCircuitBreaker));
+        assert_eq!(raised[0].related_tokens, vec!["ETH".to_string()]);
+        assert_eq!(raised[0].risk_level, RiskLevel::Critical);
+        assert!(raised[0].message.contains("health would be 0.1600"), "{}", raised[0].message);
+        assert_health_close(raised[0].health_factor.value, Decimal::new(16, 1));
+
+        *feed.eth_price.lock().unwrap() = Decimal::from(1990);
+        let health = monitor.calculate_health(position_id).await.unwrap();
+        assert_health_close(health.value, Decimal::new(1592, 3));
+        monitor.monitor_positions().await;
+        assert!(breaker.tripped_tokens().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_alerts_every_held_position_and_records_other_prices() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let breaker = circuit_breaker();
+        let monitor = LiquidationMonitor::new(feed.clone(), alerts.clone())
+            .with_price_circuit_breaker(breaker.clone());
+        let first = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+        let second = monitor.add_position(eth_position(20, 10_000)).await.unwrap();
+        monitor.monitor_positions().await;
+        alerts.alerts.lock().unwrap().clear();
+
+        *feed.eth_price.lock().unwrap() = Decimal::from(200);
+        monitor.monitor_positions().await;
+        monitor.monitor_positions().await;
+        let mut held: Vec<PositionId> = alerts.alerts.lock().unwrap().iter()
+            .filter(|alert| alert.alert_type == AlertType::PriceCircuitBreaker)
+            .map(|alert| alert.position_id)
+            .collect();
+        held.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(held, expected);
+
+        // USDC is checked and accepted alongside the rejected ETH price
+        let prices = HashMap::from([
+            ("ETH".to_string(), PriceData { price_usd: Decimal::from(100), ..feed.get_price(&"ETH".to_string()).await.unwrap() }),
+            ("USDC".to_string(), PriceData { price_usd: Decimal::new(99, 2), ..feed.get_price(&"USDC".to_string()).await.unwrap() }),
+        ]);
+        let trips = breaker.check(&prices, &["USDC".to_string(), "ETH".to_string()]);
+        assert_eq!(trips.iter().map(|trip| trip.token_address.as_str()).collect::<Vec<_>>(), vec!["ETH"]);
+        let tripped_from_penny = breaker.check(&HashMap::from([
+            ("USDC".to_string(), PriceData { price_usd: Decimal::new(50, 2), ..prices["USDC"].clone() }),
+        ]), &["USDC".to_string()]);
+        // Measured against the 0.99 accepted above, not the original 1.00
+        assert!((tripped_from_penny[0].last_accepted_price - Decimal::new(99, 2)).is_zero());
+    }
+
+    #[tokio::test]
+    async fn test_what_if_leaves_the_circuit_breaker_untouched() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let breaker = circuit_breaker();
+        let monitor = LiquidationMonitor::new(feed.clone(), alerts.clone())
+            .with_price_circuit_breaker(breaker.clone());
+        monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+        monitor.monitor_positions().await;
+        alerts.alerts.lock().unwrap().clear();
+
+        *feed.eth_price.lock().unwrap() = Decimal::from(200);
+        let hypothetical = eth_position(20, 10_000);
+        let result = monitor.what_if(&[PositionChange::Add { position: Box::new(hypothetical.clone()) }]).await;
+
+        assert!(matches!(result, Err(CalculationError::PriceCircuitOpen { .. })), "{:?}", result.map(|_| ()));
+        assert!(alerts.alerts.lock().unwrap().is_empty());
+        assert_eq!(breaker.last_accepted_price(&"ETH".to_string()), Some(Decimal::from(2000)));
+        assert!(!breaker.is_tripped(&"ETH".to_string()));
+        assert!(monitor.circuit_held_positions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_accepts_gradual_large_move() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let monitor = LiquidationMonitor::new(feed.clone(), alerts.clone())
+            .with_price_circuit_breaker(circuit_breaker());
+        let position_id = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+
+        // A 60% fall overall, but no single step moves more than 25%
+        for price in [1800, 1600, 1400, 1200, 1000, 800] {
+            *feed.eth_price.lock().unwrap() = Decimal::from(price);
+            let health = monitor.calculate_health(position_id).await.unwrap();
+            assert_health_close(health.value, Decimal::from(price) / Decimal::from(1250));
+        }
+
+        let raised = alerts.alerts.lock().unwrap().clone();
+        assert!(!raised.iter().any(|alert| matches!(alert.alert_type, AlertType::PriceCircuitBreaker)));
+        // The position is now genuinely at risk and monitoring says so
+        let monitored = monitor.monitor_positions().await;
+        assert_eq!(monitored.len(), 1);
+        assert!(matches!(monitored[0].alert_type, AlertType::LiquidationRisk));
+    }
+
+    /// Feed that tracks how many price fetches overlap, optionally taking a while to answer
+    struct ConcurrencyTrackingFeed {
+        slow: std::sync::atomic::AtomicBool,
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for ConcurrencyTrackingFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            if self.slow.load(Ordering::SeqCst) {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd: if token_address == "ETH" { Decimal::from(2000) } else { Decimal::ONE },
+                timestamp: Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_bounds_concurrent_health_calculations() {
+        let feed = Arc::new(ConcurrencyTrackingFeed {
+            slow: std::sync::atomic::AtomicBool::new(false),
+            in_flight: std::sync::atomic::AtomicUsize::new(0),
+            max_in_flight: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let monitor = Arc::new(LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_max_concurrent_health_calcs(4));
+        for _ in 0..40 {
+            monitor.add_position(eth_position(10, 1_000)).await.unwrap();
+        }
+
+        feed.slow.store(true, Ordering::SeqCst);
+        feed.max_in_flight.store(0, Ordering::SeqCst);
+        let monitoring = tokio::spawn({
+            let monitor = monitor.clone();
+            async move { monitor.monitor_positions().await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(monitor.health_calcs_in_flight() <= 4);
+        assert!(monitor.health_calc_queue_depth() > 0);
+
+        assert!(monitoring.await.unwrap().is_empty());
+        // Calculations did overlap, but never beyond the limit
+        assert_eq!(feed.max_in_flight.load(Ordering::SeqCst), 4);
+        assert_eq!(monitor.health_calcs_in_flight(), 0);
+        assert_eq!(monitor.health_calc_queue_depth(), 0);
+    }
+
+    struct SettablePriceFeed {
+        prices: std::sync::Mutex<HashMap<TokenAddress, Decimal>>,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for SettablePriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = *self.prices.lock().unwrap().get(token_address)
+                .ok_or_else(|| format!("no price for {}", token_address))?;
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd,
+                timestamp: Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_change_only_recalculates_positions_holding_token() {
+        let feed = Arc::new(SettablePriceFeed {
+            prices: std::sync::Mutex::new(HashMap::from([
+                ("ETH".to_string(), Decimal::from(2000)),
+                ("WBTC".to_string(), Decimal::from(60_000)),
+                ("USDC".to_string(), Decimal::ONE),
+                ("DAI".to_string(), Decimal::ONE),
+            ])),
+        });
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem));
+        let position = |collateral: &str, debt: &str| Position {
+            collateral_tokens: HashMap::from([token(collateral, 10)]),
+            debt_tokens: HashMap::from([token(debt, 1_000)]),
+            ..eth_position(0, 0)
+        };
+        let eth_usdc = monitor.add_position(position("ETH", "USDC")).await.unwrap();
+        let eth_dai = monitor.add_position(position("ETH", "DAI")).await.unwrap();
+        let wbtc_usdc = monitor.add_position(position("WBTC", "USDC")).await.unwrap();
+
+        let mut expected = vec![eth_usdc, eth_dai];
+        expected.sort();
+        assert_eq!(monitor.positions_holding(&"ETH".to_string()), expected);
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let recalculations = |position_id| monitor.get_health_history(position_id, since).len();
+
+        // The first pass has nothing to compare against, so everything is recalculated
+        monitor.monitor_price_changes().await;
+        assert_eq!([recalculations(eth_usdc), recalculations(eth_dai), recalculations(wbtc_usdc)], [1, 1, 1]);
+
+        // Unchanged prices recalculate nothing
+        monitor.monitor_price_changes().await;
+        assert_eq!([recalculations(eth_usdc), recalculations(eth_dai), recalculations(wbtc_usdc)], [1, 1, 1]);
+
+        feed.prices.lock().unwrap().insert("ETH".to_string(), Decimal::from(1900));
+        monitor.monitor_price_changes().await;
+        assert_eq!([recalculations(eth_usdc), recalculations(eth_dai), recalculations(wbtc_usdc)], [2, 2, 1]);
+
+        // Removed positions drop out of the index
+        monitor.remove_position(eth_dai).unwrap();
+        assert_eq!(monitor.positions_holding(&"ETH".to_string()), vec![eth_usdc]);
+        assert!(monitor.positions_holding(&"DAI".to_string()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_position_pnl_reports_collateral_appreciation() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2500), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        );
+
+        let mut position = eth_position(10, 10_000);
+        position.collateral_tokens.get_mut("ETH").unwrap().entry_price_usd = Some(Decimal::from(2000));
+        let position_id = monitor.add_position(position).await.unwrap();
+
+        let pnl = monitor.position_pnl(position_id).await.unwrap();
+
+        // 10 ETH bought at $2000, now $2500
+        assert_eq!(pnl.tokens.len(), 1);
+        assert_eq!(pnl.tokens[0].token_address, "ETH");
+        assert_eq!(pnl.tokens[0].unrealized_pnl_usd, Decimal::from(5_000));
+        assert_eq!(pnl.tokens[0].unrealized_pnl_percent, Decimal::from(25));
+        assert_eq!(pnl.total_unrealized_pnl_usd, Decimal::from(5_000));
+        assert_eq!(pnl.untracked_tokens, vec!["USDC".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_depeg_hurts_stablecoin_collateral_and_helps_stablecoin_debt() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        );
+        let depeg = DepegScenario {
+            stablecoin: "USDC".to_string(),
+            trough_price_bps: 9_000,
+            days_to_trough: 2,
+            recovery_days: 4,
+            recovered_price_bps: 9_900,
+        };
+
+        // 10,000 USDC collateral against 3 ETH ($6,000) of debt
+        let mut usdc_collateral = eth_position(0, 0);
+        usdc_collateral.collateral_tokens = HashMap::from([token("USDC", 10_000)]);
+        usdc_collateral.debt_tokens = HashMap::from([token("ETH", 3)]);
+        let collateral_id = monitor.add_position(usdc_collateral).await.unwrap();
+
+        // 10 ETH ($20,000) collateral against 10,000 USDC of debt
+        let debt_id = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+
+        let collateral_path = monitor.health_under_depeg(collateral_id, &depeg).await.unwrap();
+        let debt_path = monitor.health_under_depeg(debt_id, &depeg).await.unwrap();
+        assert_eq!(collateral_path.len(), 7);
+        assert_eq!(collateral_path[2].stablecoin_price, Decimal::new(9, 1));
+        assert_eq!(collateral_path[6].stablecoin_price, Decimal::new(99, 2));
+
+        // Before the depeg: 8,000 / 6,000 and 16,000 / 10,000
+        assert_health_close(collateral_path[0].health_factor.value, Decimal::from(8_000) / Decimal::from(6_000));
+        assert_health_close(debt_path[0].health_factor.value, Decimal::new(16, 1));
+
+        // At the $0.90 trough: 7,200 / 6,000 and 16,000 / 9,000
+        assert_health_close(collateral_path[2].health_factor.value, Decimal::new(12, 1));
+        assert_health_close(debt_path[2].health_factor.value, Decimal::from(16_000) / Decimal::from(9_000));
+        assert!(collateral_path[2].health_factor.value < collateral_path[0].health_factor.value);
+        assert!(debt_path[2].health_factor.value > debt_path[0].health_factor.value);
+    }
+
+    struct RecordingStatsdSender {
+        lines: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl crate::monitoring::StatsdSender for RecordingStatsdSender {
+        fn send(&self, payload: &str) -> std::io::Result<()> {
+            self.lines.lock().unwrap().push(payload.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_metrics_exported_as_statsd_and_prometheus() {
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem));
+        monitor.add_position(eth_position(10, 1000)).await.unwrap();
+        monitor.add_position(eth_position(1, 1900)).await.unwrap();
+
+        // Adding a position checks it immediately, so both passes are counted
+        let alerts = monitor.monitor_positions().await;
+        assert_eq!(alerts.len(), 1);
+
+        let sender = Arc::new(RecordingStatsdSender { lines: std::sync::Mutex::new(Vec::new()) });
+        let emitter = crate::monitoring::StatsdEmitter::new(sender.clone(), "aegis");
+        let metrics = monitor.metrics();
+        emitter.flush(&metrics);
+
+        let lines = sender.lines.lock().unwrap().clone();
+        assert!(lines.contains(&"aegis.positions_monitored:2|g".to_string()));
+        assert!(lines.contains(&"aegis.health_calculations:4|c".to_string()));
+        assert!(lines.contains(&"aegis.alerts_raised:2|c".to_string()));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("aegis.health_calculation_duration_ms:") && line.ends_with("|ms")).count(), 4);
+
+        // Counters are sent as deltas, so an idle flush only repeats the gauge
+        sender.lines.lock().unwrap().clear();
+        emitter.flush(&metrics);
+        assert_eq!(*sender.lines.lock().unwrap(), vec!["aegis.positions_monitored:2|g".to_string()]);
+
+        let prometheus = metrics.render_prometheus();
+        assert!(prometheus.contains("# TYPE aegis_alerts_raised_total counter\naegis_alerts_raised_total 2\n"));
+        assert!(prometheus.contains("aegis_positions_monitored 2\n"));
+        assert!(prometheus.contains("aegis_health_calculation_duration_ms_count 4\n"));
+    }
+
+    /// Feed that silently omits tokens it has no price for, as happens after a delisting
+    struct PartialPriceFeed(Arc<StaticPriceFeed>);
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for PartialPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                if let Ok(price) = self.0.get_price(token).await {
+                    prices.insert(token.clone(), price);
+                }
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            self.0.get_price(token_address).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unpriceable_positions_rank_first() {
+        let feed = Arc::new(PartialPriceFeed(static_feed(&[("ETH", 2000), ("USDC", 1)])));
+        let monitor = LiquidationMonitor::new(feed, Arc::new(NullAlertSystem));
+        let stretched = monitor.add_position(eth_position(10, 15_000)).await.unwrap();
+        let mut delisted = eth_position(10, 1000);
+        delisted.collateral_tokens = HashMap::from([token("DELISTED", 10)]);
+        let delisted = monitor.add_position(delisted).await.unwrap();
+
+        let ranked = monitor.positions_by_risk(None, None).await.unwrap();
+        assert_eq!(ranked.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![delisted, stretched]);
+        assert!(matches!(&ranked[0].1, Err(CalculationError::MissingPriceData { token }) if token == "DELISTED"));
+        assert!(matches!(&ranked[1].1, Ok((_, RiskLevel::Critical))));
+
+        let top = monitor.positions_by_risk(None, Some(1)).await.unwrap();
+        assert_eq!(top[0].0, delisted);
+    }
+
+    #[tokio::test]
+    async fn test_delisted_token_position_is_quarantined() {
+        let feed = Arc::new(PartialPriceFeed(static_feed(&[("ETH", 2000), ("USDC", 1)])));
+        let monitor = LiquidationMonitor::new(feed, Arc::new(NullAlertSystem))
+            .with_unpriceable_quarantine(3);
+
+        let healthy = eth_position(10, 1000);
+        let mut delisted = eth_position(10, 1000);
+        delisted.collateral_tokens = HashMap::from([token("DELISTED", 10)]);
+        let healthy_id = monitor.add_position(healthy).await.unwrap();
+        let delisted_id = monitor.add_position(delisted).await.unwrap();
+
+        for _ in 0..2 {
+            let alerts = monitor.monitor_positions().await;
+            assert!(alerts.iter().all(|a| !matches!(a.alert_type, AlertType::UnpriceablePosition)));
+            assert!(monitor.get_quarantined_positions().is_empty());
+        }
+
+        let alerts = monitor.monitor_positions().await;
+        let unpriceable: Vec<&RiskAlert> = alerts.iter()
+            .filter(|a| matches!(a.alert_type, AlertType::UnpriceablePosition))
+            .collect();
+        assert_eq!(unpriceable.len(), 1);
+        assert_eq!(unpriceable[0].position_id, delisted_id);
+        assert_eq!(unpriceable[0].related_tokens, vec!["DELISTED".to_string()]);
+
+        let quarantined = monitor.get_quarantined_positions();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].position.id, delisted_id);
+        assert_eq!(quarantined[0].missing_token, "DELISTED");
+        assert_eq!(quarantined[0].failure_count, 3);
+
+        // The quarantined position no longer fails every cycle
+        assert!(monitor.get_position(delisted_id).is_none());
+        assert!(monitor.get_position(healthy_id).is_some());
+        assert!(monitor.monitor_positions().await.is_empty());
+
+        monitor.release_quarantined_position(delisted_id).unwrap();
+        assert!(monitor.get_position(delisted_id).is_some());
+        assert!(monitor.get_quarantined_positions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_readded_position_starts_without_missing_price_failures() {
+        let feed = Arc::new(PartialPriceFeed(static_feed(&[("ETH", 2000), ("USDC", 1)])));
+        let monitor = LiquidationMonitor::new(feed, Arc::new(NullAlertSystem))
+            .with_unpriceable_quarantine(2);
+        let mut delisted = eth_position(10, 1000);
+        delisted.collateral_tokens = HashMap::from([token("DELISTED", 10)]);
+        let position_id = monitor.add_position(delisted).await.unwrap();
+
+        monitor.monitor_positions().await;
+        let removed = monitor.remove_position(position_id).unwrap();
+        monitor.add_position(removed).await.unwrap();
+
+        // One more miss after re-adding is the first, not the second
+        monitor.monitor_positions().await;
+        assert!(monitor.get_quarantined_positions().is_empty());
+        monitor.monitor_positions().await;
+        assert_eq!(monitor.get_quarantined_positions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quarantine_keeps_health_history() {
+        let feed = Arc::new(PartialPriceFeed(static_feed(&[("ETH", 2000), ("USDC", 1)])));
+        let monitor = LiquidationMonitor::new(feed, Arc::new(NullAlertSystem))
+            .with_unpriceable_quarantine(1);
+        let mut position = eth_position(10, 1000);
+        let position_id = monitor.add_position(position.clone()).await.unwrap();
+        monitor.monitor_positions().await;
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let everything = since..Utc::now() + chrono::Duration::hours(1);
+        assert!(!monitor.get_health_history(position_id, since).is_empty());
+
+        position.collateral_tokens = HashMap::from([token("DELISTED", 10)]);
+        monitor.update_position(position).await.unwrap();
+        monitor.monitor_positions().await;
+        assert_eq!(monitor.get_quarantined_positions().len(), 1);
+
+        assert!(!monitor.get_health_history(position_id, since).is_empty());
+        assert!(monitor.get_health_records(&everything).await.iter().any(|record| record.position_id == position_id));
+    }
+
+    #[tokio::test]
+    async fn test_liquidation_ladder_accumulates_as_price_falls() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1), ("WBTC", 60000)]),
+            Arc::new(NullAlertSystem),
+        );
+        // Liquidatable below $1500, $1000 and $500 respectively (80% threshold)
+        monitor.add_position(eth_position(10, 12000)).await.unwrap();
+        monitor.add_position(eth_position(5, 4000)).await.unwrap();
+        monitor.add_position(eth_position(2, 800)).await.unwrap();
+        // Does not hold ETH and is ignored even though it is underwater
+        let mut btc_position = eth_position(0, 60000);
+        btc_position.collateral_tokens = HashMap::from([token("WBTC", 1)]);
+        monitor.add_position(btc_position).await.unwrap();
+
+        let levels: Vec<Decimal> = [1800, 1400, 900, 400].into_iter().map(Decimal::from).collect();
+        let ladder = monitor.liquidation_ladder(&"ETH".to_string(), &levels).await.unwrap();
+
+        assert_eq!(ladder, vec![
+            (Decimal::from(1800), Decimal::ZERO),
+            (Decimal::from(1400), Decimal::from(20000)),
+            (Decimal::from(900), Decimal::from(30000)),
+            (Decimal::from(400), Decimal::from(34000)),
+        ]);
+        assert!(ladder.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    fn change(token_address: &str, relative_change: Decimal) -> PriceChange {
+        PriceChange { token_address: token_address.to_string(), relative_change }
+    }
+
+    #[tokio::test]
+    async fn test_simultaneous_price_changes_are_order_independent() {
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem));
+        let position_id = monitor.add_position(eth_position(10, 10000)).await.unwrap();
+
+        let eth_drop = change("ETH", Decimal::new(-2, 1));
+        let usdc_rise = change("USDC", Decimal::new(5, 2));
+
+        let forward = monitor.health_with_price_changes(position_id, &[eth_drop.clone(), usdc_rise.clone()], PriceChangeMerge::Reject).await.unwrap();
+        let reverse = monitor.health_with_price_changes(position_id, &[usdc_rise, eth_drop], PriceChangeMerge::Reject).await.unwrap();
+        assert_eq!(forward.value, reverse.value);
+        // 10 ETH at $1600 weighted at 80%, against 10000 USDC at $1.05
+        assert_health_close(forward.value, Decimal::from(12800) / Decimal::from(10500));
+
+        let moves = [
+            change("ETH", Decimal::new(-1, 1)),
+            change("ETH", Decimal::new(3, 2)),
+            change("USDC", Decimal::new(1, 2)),
+        ];
+        let compounded = monitor.health_with_price_changes(position_id, &moves, PriceChangeMerge::Compound).await.unwrap();
+        let reordered = [moves[2].clone(), moves[1].clone(), moves[0].clone()];
+        let compounded_reordered = monitor.health_with_price_changes(position_id, &reordered, PriceChangeMerge::Compound).await.unwrap();
+        assert_eq!(compounded.value, compounded_reordered.value);
+
+        assert!(monitor.health_with_price_changes(position_id, &moves, PriceChangeMerge::Reject).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health_history_survives_restart_within_retention() {
+        use crate::liquidation::PersistenceBackend;
+
+        let backend = Arc::new(crate::liquidation::InMemoryPersistenceBackend::new());
+        let persistence = crate::liquidation::HealthHistoryPersistence {
+            backend: backend.clone(),
+            flush_interval: std::time::Duration::from_secs(60),
+            retention: 3,
+        };
+        let feed = static_feed(&[("ETH", 2000), ("USDC", 1)]);
+        let position = eth_position(10, 10000);
+
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_health_history_persistence(persistence.clone());
+        let position_id = monitor.add_position(position.clone()).await.unwrap();
+        for _ in 0..3 {
+            monitor.monitor_positions().await;
+        }
+
+        // Nothing reaches the backend until a flush
+        assert!(backend.load_health_records().await.unwrap().is_empty());
+        assert_eq!(monitor.flush_health_history().await.unwrap(), 3);
+
+        let everything = Utc::now() - chrono::Duration::days(1)..Utc::now() + chrono::Duration::days(1);
+        let before_restart = monitor.get_health_records(&everything).await;
+
+        let restarted = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_health_history_persistence(persistence.clone());
+        assert_eq!(restarted.hydrate_health_history().await.unwrap(), 3);
+        let restored: Vec<Uuid> = restarted.get_health_records(&everything).await.iter().map(|r| r.id).collect();
+        assert_eq!(restored, before_restart.iter().map(|r| r.id).collect::<Vec<_>>());
+        // The chart history comes back too
+        let charted = |monitor: &LiquidationMonitor| monitor.get_health_history(position_id, everything.start)
+            .into_iter()
+            .map(|(at, health)| (at, health.value))
+            .collect::<Vec<_>>();
+        assert_eq!(charted(&restarted).len(), 3);
+        assert_eq!(charted(&restarted), charted(&monitor));
+
+        // Retention keeps only the newest records in the backend
+        monitor.monitor_positions().await;
+        monitor.monitor_positions().await;
+        assert_eq!(monitor.flush_health_history().await.unwrap(), 2);
+        let persisted = backend.load_health_records().await.unwrap();
+        assert_eq!(persisted.len(), 3);
+        let newest: Vec<Uuid> = monitor.get_health_records(&everything).await.iter().rev().take(3).rev().map(|r| r.id).collect();
+        assert_eq!(persisted.iter().map(|r| r.id).collect::<Vec<_>>(), newest);
+    }
+
+    #[tokio::test]
+    async fn test_pending_health_records_are_bounded_by_retention() {
+        use crate::liquidation::PersistenceBackend;
+
+        let backend = Arc::new(crate::liquidation::InMemoryPersistenceBackend::new());
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem))
+            .with_health_history_persistence(crate::liquidation::HealthHistoryPersistence {
+                backend: backend.clone(),
+                flush_interval: std::time::Duration::from_secs(60),
+                retention: 2,
+            });
+        monitor.add_position(eth_position(10, 10000)).await.unwrap();
+        for _ in 0..4 {
+            monitor.monitor_positions().await;
+        }
+
+        // Only the newest records still within retention wait for the flush
+        assert_eq!(monitor.flush_health_history().await.unwrap(), 2);
+        let everything = Utc::now() - chrono::Duration::days(1)..Utc::now() + chrono::Duration::days(1);
+        let newest: Vec<Uuid> = monitor.get_health_records(&everything).await.iter().rev().take(2).rev().map(|r| r.id).collect();
+        assert_eq!(backend.load_health_records().await.unwrap().iter().map(|r| r.id).collect::<Vec<_>>(), newest);
+    }
+
+    struct AdjustableEthFeed {
+        eth_price: std::sync::Mutex<Decimal>,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for AdjustableEthFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = if token_address == "ETH" { *self.eth_price.lock().unwrap() } else { Decimal::ONE };
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd,
+                timestamp: Utc::now(),
+                source: "test".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    struct RecordingTransitionHook {
+        transitions: std::sync::Mutex<Vec<RiskLevelTransition>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RiskLevelTransitionHook for RecordingTransitionHook {
+        async fn on_risk_level_transition(&self, transition: &RiskLevelTransition) {
+            self.transitions.lock().unwrap().push(transition.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_risk_level_transitions_fire_once_per_crossing() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let hook = Arc::new(RecordingTransitionHook { transitions: std::sync::Mutex::new(Vec::new()) });
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_risk_level_transition_hook(hook.clone());
+
+        // Health is ETH price / 1250: 1.6 at $2000
+        let position_id = monitor.add_position(eth_position(10, 10000)).await.unwrap();
+
+        monitor.monitor_positions().await;
+
+        // Calculating health on demand fires nothing; only monitoring cycles do
+        *feed.eth_price.lock().unwrap() = Decimal::from(1290);
+        monitor.calculate_health(position_id).await.unwrap();
+        monitor.calculate_health_batch(&[position_id]).await;
+        assert!(hook.transitions.lock().unwrap().is_empty());
+
+        for price in [1600, 1350, 1300, 1290, 2000] {
+            *feed.eth_price.lock().unwrap() = Decimal::from(price);
+            monitor.monitor_positions().await;
+        }
+
+        let transitions = hook.transitions.lock().unwrap().clone();
+        let levels: Vec<(RiskLevel, RiskLevel)> = transitions.iter()
+            .map(|t| (t.old_level.clone(), t.new_level.clone()))
+            .collect();
+        assert_eq!(levels, vec![
+            (RiskLevel::Safe, RiskLevel::Warning),
+            (RiskLevel::Warning, RiskLevel::Critical),
+            (RiskLevel::Critical, RiskLevel::Emergency),
+            (RiskLevel::Emergency, RiskLevel::Safe),
+        ]);
+        assert!(transitions.iter().all(|t| t.position.id == position_id));
+        assert!(transitions.windows(2).all(|pair| pair[0].occurred_at <= pair[1].occurred_at));
+        assert_health_close(transitions[2].health_factor, Decimal::new(104, 2));
+    }
+
+    #[tokio::test]
+    async fn test_health_queries_record_nothing() {
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let breaker = circuit_breaker();
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), alerts.clone())
+            .with_price_circuit_breaker(breaker.clone());
+        let position_id = monitor.add_position(eth_position(10, 10_000)).await.unwrap();
+
+        monitor.calculate_health(position_id).await.unwrap();
+        assert!(monitor.calculate_health_batch(&[position_id]).await[&position_id].is_ok());
+        monitor.evaluate_snapshot().await.unwrap();
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        assert!(monitor.get_health_history(position_id, since).is_empty());
+        assert!(monitor.risk_levels.is_empty());
+        assert_eq!(breaker.last_accepted_price(&"ETH".to_string()), None);
+        assert!(alerts.alerts.lock().unwrap().is_empty());
+
+        monitor.monitor_positions().await;
+        assert_eq!(monitor.get_health_history(position_id, since).len(), 1);
+        assert_eq!(monitor.risk_levels.get(&position_id).map(|level| level.clone()), Some(RiskLevel::Safe));
+        assert_eq!(breaker.last_accepted_price(&"ETH".to_string()), Some(Decimal::from(2000)));
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_feeds_volatility_tracker() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let tracker = Arc::new(VolatilityTracker::default());
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_volatility_tracker(tracker.clone());
+        monitor.add_position(eth_position(10, 1000)).await.unwrap();
+
+        for price in [2040, 2000] {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            *feed.eth_price.lock().unwrap() = Decimal::from(price);
+            monitor.monitor_positions().await;
+        }
+
+        // +2% then about -2%, starting from the price seen when the position was added
+        let volatility = tracker.get_volatility(&"ETH".to_string()).unwrap();
+        assert!(volatility > Decimal::new(19, 3) && volatility < Decimal::new(21, 3), "{}", volatility);
+        // USDC never moved
+        assert_eq!(tracker.get_volatility(&"USDC".to_string()), Some(Decimal::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_position_size_limit_is_checked_on_update() {
+        let alerts = Arc::new(RecordingAlertSystem { alerts: std::sync::Mutex::new(Vec::new()) });
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), alerts.clone());
+        let size_alerts = || alerts.alerts.lock().unwrap().iter()
+            .filter(|alert| alert.alert_type == AlertType::PositionSizeExceeded)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // 500 ETH at $2000 is exactly the default $1M limit
+        let mut position = eth_position(500, 1000);
+        let position_id = monitor.add_position(position.clone()).await.unwrap();
+        assert!(size_alerts().is_empty());
+
+        // $999,999.80
+        position.collateral_tokens.get_mut("ETH").unwrap().amount = Decimal::new(4999999, 4);
+        monitor.update_position(position.clone()).await.unwrap();
+        assert!(size_alerts().is_empty());
+
+        // $1,000,000.20
+        position.collateral_tokens.get_mut("ETH").unwrap().amount = Decimal::new(5000001, 4);
+        monitor.update_position(position.clone()).await.unwrap();
+        let flagged = size_alerts();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].position_id, position_id);
+        assert!(flagged[0].message.contains("$1000000.20"), "{}", flagged[0].message);
+
+        // Monitoring passes don't flag it again while it stays oversized
+        monitor.monitor_positions().await;
+        assert_eq!(size_alerts().len(), 1);
+
+        // Back within the limit and over it again is a new breach
+        position.collateral_tokens.get_mut("ETH").unwrap().amount = Decimal::from(400);
+        monitor.update_position(position.clone()).await.unwrap();
+        position.collateral_tokens.get_mut("ETH").unwrap().amount = Decimal::from(600);
+        monitor.update_position(position.clone()).await.unwrap();
+        assert_eq!(size_alerts().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_exposure_breach_raises_alert() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        );
+
+        // $20k in Aave, $20k in Compound and $40k in MakerDAO, all well collateralized
+        for (protocol, eth_amount) in [("aave", 10), ("compound", 10), ("makerdao", 20)] {
+            let mut position = eth_position(eth_amount, 1000);
+            position.protocol = protocol.to_string();
+            monitor.add_position(position).await.unwrap();
+        }
+
+        let alerts = monitor.monitor_positions().await;
+
+        // Aave and Compound sit exactly at the 25% limit; only MakerDAO exceeds it
+        assert_eq!(alerts.len(), 1, "{:?}", alerts);
+        assert_eq!(alerts[0].alert_type, AlertType::ProtocolExposureExceeded);
+        assert_eq!(alerts[0].protocol.as_deref(), Some("makerdao"));
+        assert!(alerts[0].message.contains("50.00%"), "{}", alerts[0].message);
+
+        // The standing breach isn't raised again
+        assert!(monitor.monitor_positions().await.is_empty());
+
+        let exposures = monitor.protocol_exposures();
+        let shares: Vec<(&str, Decimal)> = exposures.iter()
+            .map(|exposure| (exposure.protocol.as_str(), exposure.portfolio_percent))
+            .collect();
+        assert_eq!(shares, vec![("aave", Decimal::from(25)), ("compound", Decimal::from(25)), ("makerdao", Decimal::from(50))]);
+    }
+
+    #[tokio::test]
+    async fn test_exposure_alert_rearms_after_breach_clears() {
+        let monitor = LiquidationMonitor::new(
+            static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            Arc::new(NullAlertSystem),
+        ).with_min_protocols_for_exposure_alerts(1);
+        let exposure_alerts = |alerts: Vec<RiskAlert>| alerts.into_iter()
+            .filter(|alert| alert.alert_type == AlertType::ProtocolExposureExceeded)
+            .filter_map(|alert| alert.protocol)
+            .collect::<Vec<_>>();
+
+        // A single-protocol book is flagged when configured to be
+        let aave = monitor.add_position(eth_position(10, 1000)).await.unwrap();
+        assert_eq!(exposure_alerts(monitor.monitor_positions().await), vec!["aave".to_string()]);
+        assert!(exposure_alerts(monitor.monitor_positions().await).is_empty());
+
+        // Spread evenly over three protocols the book is back within a 50% limit
+        monitor.update_risk_parameters(RiskParameters {
+            max_protocol_exposure_percent: Decimal::from(50),
+            ..RiskParameters::default()
+        }).await;
+        let mut others = Vec::new();
+        for protocol in ["compound", "makerdao"] {
+            let mut position = eth_position(10, 1000);
+            position.protocol = protocol.to_string();
+            others.push(monitor.add_position(position).await.unwrap());
+        }
+        assert!(exposure_alerts(monitor.monitor_positions().await).is_empty());
+
+        // Concentrating again raises a fresh alert
+        for id in others {
+            monitor.remove_position(id).unwrap();
+        }
+        assert_eq!(exposure_alerts(monitor.monitor_positions().await), vec!["aave".to_string()]);
+        assert!(monitor.get_position(aave).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_liquidation_alerts_project_time_to_liquidation() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(1375)) });
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem));
+
+        // Health is ETH price / 1250, falling 0.012 per cycle from 1.1
+        let position_id = monitor.add_position(eth_position(10, 10000)).await.unwrap();
+        let mut projections = Vec::new();
+        for price in [1375, 1360, 1345, 1330, 1315] {
+            *feed.eth_price.lock().unwrap() = Decimal::from(price);
+            let alerts = monitor.monitor_positions().await;
+            assert_eq!(alerts.len(), 1);
+            projections.push(alerts[0].projected_seconds_to_liquidation);
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        // A single health factor gives no trajectory
+        assert_eq!(projections[0], None);
+        let projections: Vec<f64> = projections[1..].iter().map(|p| p.unwrap()).collect();
+        assert!(projections.iter().all(|p| p.is_finite() && *p > 0.0), "{:?}", projections);
+        assert!(projections.windows(2).all(|pair| pair[1] < pair[0]), "{:?}", projections);
+
+        // Once the recovery outweighs the decline, no liquidation is projected
+        for price in [1500, 1600, 1700] {
+            *feed.eth_price.lock().unwrap() = Decimal::from(price);
+            monitor.monitor_positions().await;
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(monitor.projected_seconds_to_liquidation(position_id), None);
+    }
+
+    struct AgedPriceFeed {
+        age: chrono::Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for AgedPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd: if token_address == "ETH" { Decimal::from(2000) } else { Decimal::ONE },
+                timestamp: Utc::now() - self.age,
+                source: "slow-l2-oracle".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stale_prices_are_rejected() {
+        let monitor = LiquidationMonitor::new(
+            Arc::new(AgedPriceFeed { age: chrono::Duration::minutes(10) }),
+            Arc::new(NullAlertSystem),
+        );
+        let position_id = monitor.add_position(eth_position(10, 1000)).await.unwrap();
+
+        // Within the default one hour limit
+        assert!(monitor.calculate_health(position_id).await.is_ok());
+
+        let mut params = monitor.get_risk_parameters().await;
+        params.max_price_age = std::time::Duration::from_secs(5 * 60);
+        monitor.update_risk_parameters(params).await;
+
+        match monitor.calculate_health(position_id).await {
+            Err(CalculationError::StalePriceData { token, age_secs }) => {
+                assert!(token == "ETH" || token == "USDC");
+                assert!(age_secs >= 600);
+            }
+            other => panic!("expected stale price error, got {:?}", other),
+        }
+
+        let snapshot = monitor.evaluate_snapshot().await.unwrap();
+        assert!(matches!(snapshot.health_factors[&position_id], Err(CalculationError::StalePriceData { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_price_change_mode_reports_frozen_prices() {
+        let monitor = LiquidationMonitor::new(
+            Arc::new(AgedPriceFeed { age: chrono::Duration::minutes(10) }),
+            Arc::new(NullAlertSystem),
+        );
+        monitor.add_position(eth_position(10, 1000)).await.unwrap();
+        let mut params = monitor.get_risk_parameters().await;
+        params.max_price_age = std::time::Duration::from_secs(5 * 60);
+        monitor.update_risk_parameters(params).await;
+
+        // The price never moves, but its age keeps the position under watch
+        for _ in 0..2 {
+            let alerts = monitor.monitor_price_changes().await;
+            assert_eq!(alerts.len(), 1);
+            assert!(alerts[0].message.contains("Stale price data"), "{}", alerts[0].message);
+        }
+    }
+
+    /// Values collateral at a flat 50% against debt, ignoring prices entirely
+    struct FlatHalfCalculator;
+
+    impl HealthCalculator for FlatHalfCalculator {
+        fn calculate_health(&self, position: &Position, _prices: &HashMap<TokenAddress, PriceData>) -> Result<HealthFactor, CalculationError> {
+            let collateral: Decimal = position.collateral_tokens.values().map(|t| t.amount).sum();
+            let debt: Decimal = position.debt_tokens.values().map(|t| t.amount).sum();
+            Ok(HealthFactor {
+                value: collateral / Decimal::TWO / debt,
+                liquidation_threshold: Decimal::new(5, 1),
+                collateral_value: collateral,
+                debt_value: debt,
+                calculated_at: Utc::now(),
+            })
+        }
+
+        fn protocol(&self) -> &str {
+            "flatlend"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_protocol_calculator_is_routed_by_protocol() {
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem));
+
+        let mut unsupported = eth_position(10, 1000);
+        unsupported.protocol = "unlisted-lend".to_string();
+        let unsupported_id = monitor.add_position(unsupported).await.unwrap();
+        assert!(matches!(
+            monitor.calculate_health(unsupported_id).await,
+            Err(CalculationError::UnsupportedProtocol { protocol }) if protocol == "unlisted-lend"
+        ));
+
+        monitor.health_calculators().register("FlatLend", Box::new(FlatHalfCalculator));
+        assert!(monitor.health_calculators().registered_protocols().contains(&"flatlend".to_string()));
+
+        let mut custom = eth_position(10, 4);
+        custom.protocol = "flatlend".to_string();
+        let custom_id = monitor.add_position(custom.clone()).await.unwrap();
+        assert_eq!(monitor.calculate_health(custom_id).await.unwrap().value, Decimal::new(125, 2));
+
+        // Another monitor keeps its own registry
+        let other = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem));
+        assert!(!other.health_calculators().registered_protocols().contains(&"flatlend".to_string()));
+        let other_id = other.add_position(custom).await.unwrap();
+        assert!(matches!(
+            other.calculate_health(other_id).await,
+            Err(CalculationError::UnsupportedProtocol { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_health_combines_every_collateral_and_debt_token() {
+        let prices = [("ETH", 2000), ("WBTC", 60000), ("LINK", 15), ("USDC", 1), ("DAI", 1)];
+        let monitor = LiquidationMonitor::new(static_feed(&prices), Arc::new(NullAlertSystem));
+
+        let mut position = eth_position(10, 30000);
+        position.collateral_tokens = HashMap::from([token("ETH", 10), token("WBTC", 1), token("LINK", 1000)]);
+        position.debt_tokens = HashMap::from([token("USDC", 30000), token("DAI", 10000)]);
+        let position_id = monitor.add_position(position.clone()).await.unwrap();
+
+        let health = monitor.calculate_health(position_id).await.unwrap();
+        assert_eq!(health.collateral_value, Decimal::from(95000));
+        assert_eq!(health.debt_value, Decimal::from(40000));
+        // 95,000 * 0.8 / 40,000
+        assert_health_close(health.value, Decimal::new(19, 1));
+
+        // Only LINK and DAI are unpriced; the collateral leg is reported first
+        let feed = Arc::new(PartialPriceFeed(static_feed(&[("ETH", 2000), ("WBTC", 60000), ("USDC", 1)])));
+        let monitor = LiquidationMonitor::new(feed, Arc::new(NullAlertSystem));
+        let position_id = monitor.add_position(position).await.unwrap();
+        assert!(matches!(
+            monitor.calculate_health(position_id).await,
+            Err(CalculationError::MissingPriceData { token }) if token == "LINK"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_health_history_is_bounded_per_position() {
+        let feed = Arc::new(AdjustableEthFeed { eth_price: std::sync::Mutex::new(Decimal::from(2000)) });
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem))
+            .with_health_history_capacity(3);
+        let started = Utc::now();
+
+        let position_id = monitor.add_position(eth_position(10, 10000)).await.unwrap();
+        let other_id = monitor.add_position(eth_position(10, 5000)).await.unwrap();
+        for eth_price in [1900, 1800, 1700] {
+            *feed.eth_price.lock().unwrap() = Decimal::from(eth_price);
+            monitor.monitor_positions().await;
+        }
+
+        // add_position evaluated once, then three cycles; only the newest three remain
+        let history = monitor.get_health_history(position_id, started);
+        let values: Vec<Decimal> = history.iter().map(|(_, health)| health.value).collect();
+        assert_eq!(values.len(), 3);
+        assert_health_close(values[0], Decimal::new(152, 2));
+        assert_health_close(values[2], Decimal::new(136, 2));
+        assert!(history.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert!(monitor.get_health_history(position_id, Utc::now() + chrono::Duration::seconds(1)).is_empty());
+
+        monitor.remove_position(position_id).unwrap();
+        assert!(monitor.get_health_history(position_id, started).is_empty());
+        assert_eq!(monitor.get_health_history(other_id, started).len(), 3);
+    }
+
+    struct CountingPriceFeed {
+        inner: Arc<StaticPriceFeed>,
+        requests: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for CountingPriceFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            self.requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.get_prices(token_addresses).await
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            self.requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.get_price(token_address).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_health_fetches_prices_once() {
+        let feed = Arc::new(CountingPriceFeed {
+            inner: static_feed(&[("ETH", 2000), ("USDC", 1)]),
+            requests: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let monitor = LiquidationMonitor::new(feed.clone(), Arc::new(NullAlertSystem));
+
+        let mut position_ids = Vec::new();
+        for debt in [1000, 2000, 4000] {
+            position_ids.push(monitor.add_position(eth_position(10, debt)).await.unwrap());
+        }
+        let unknown_id = Uuid::new_v4();
+        position_ids.push(unknown_id);
+        let requests_before = feed.requests.load(std::sync::atomic::Ordering::SeqCst);
+
+        let health = monitor.calculate_health_batch(&position_ids).await;
+        assert_eq!(feed.requests.load(std::sync::atomic::Ordering::SeqCst), requests_before + 1);
+        assert_eq!(health.len(), 4);
+        for position_id in &position_ids[..3] {
+            let looped = monitor.calculate_health(*position_id).await.unwrap();
+            assert_eq!(health[position_id].as_ref().unwrap().value, looped.value);
+        }
+        assert!(matches!(health[&unknown_id], Err(CalculationError::CalculationFailed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_risk_overrides_change_level_for_identical_health() {
+        let monitor = LiquidationMonitor::new(static_feed(&[("ETH", 2000), ("USDC", 1)]), Arc::new(NullAlertSystem));
+
+        let volatile_params = RiskParameters {
+            warning_health_threshold: Decimal::new(140, 2),
+            critical_health_threshold: Decimal::new(120, 2),
+            emergency_health_threshold: Decimal::new(115, 2),
+            ..RiskParameters::default()
+        };
+        let stable_params = RiskParameters {
+            warning_health_threshold: Decimal::new(110, 2),
+            critical_health_threshold: Decimal::new(105, 2),
+            emergency_health_threshold: Decimal::new(102, 2),
+            ..RiskParameters::default()
+        };
+
+        // 10 ETH at 2000 against 14,000 USDC: health ~1.143 for all three
+        let default_id = monitor.add_position(eth_position(10, 14000)).await.unwrap();
+        let mut volatile = eth_position(10, 14000);
+        volatile.risk_overrides = Some(volatile_params);
+        let volatile_id = monitor.add_position(volatile).await.unwrap();
+        let mut stable = eth_position(10, 14000);
+        stable.risk_overrides = Some(stable_params);
+        let stable_id = monitor.add_position(stable).await.unwrap();
+
+        let alerts = monitor.monitor_positions().await;
+        let health: Vec<Decimal> = [default_id, volatile_id, stable_id].iter()
+            .map(|id| monitor.health_history.get(id).unwrap().back().unwrap().1.value)
+            .collect();
+        assert!(health.iter().all(|value| *value == health[0]));
+
+        assert_eq!(*monitor.risk_levels.get(&default_id).unwrap(), RiskLevel::Warning);
+        assert_eq!(*monitor.risk_levels.get(&volatile_id).unwrap(), RiskLevel::Emergency);
+        assert_eq!(*monitor.risk_levels.get(&stable_id).unwrap(), RiskLevel::Safe);
+
+        // Only the volatile position crosses its own critical threshold
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].position_id, volatile_id);
+        assert_eq!(alerts[0].risk_level, RiskLevel::Emergency);
+    }
+}
@@ -0,0 +1,525 @@
+use crate::liquidation::monitor::{AlertSystem, HealthRecord};
+use crate::monitoring::AlertAuditEntry;
+use crate::types::{Position, PositionId, RiskAlert};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+/// Durable storage for monitoring state that must survive restarts
+#[async_trait::async_trait]
+pub trait PersistenceBackend: Send + Sync {
+    async fn append_health_records(&self, records: &[HealthRecord]) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// All persisted health records, oldest first
+    async fn load_health_records(&self) -> Result<Vec<HealthRecord>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Drop all but the newest `keep` health records
+    async fn truncate_health_records(&self, keep: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Backend that keeps records in process memory, for tests and single-process deployments
+#[derive(Default)]
+pub struct InMemoryPersistenceBackend {
+    health_records: RwLock<Vec<HealthRecord>>,
+}
+
+impl InMemoryPersistenceBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl PersistenceBackend for InMemoryPersistenceBackend {
+    async fn append_health_records(&self, records: &[HealthRecord]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.health_records.write().await.extend_from_slice(records);
+        Ok(())
+    }
+
+    async fn load_health_records(&self) -> Result<Vec<HealthRecord>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.health_records.read().await.clone())
+    }
+
+    async fn truncate_health_records(&self, keep: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut records = self.health_records.write().await;
+        let excess = records.len().saturating_sub(keep);
+        records.drain(..excess);
+        Ok(())
+    }
+}
+
+/// How health history is written to a persistence backend
+#[derive(Clone)]
+pub struct HealthHistoryPersistence {
+    pub backend: Arc<dyn PersistenceBackend>,
+    /// How often buffered records are written out
+    pub flush_interval: Duration,
+    /// Maximum number of records kept in the backend
+    pub retention: usize,
+}
+
+impl fmt::Debug for HealthHistoryPersistence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthHistoryPersistence")
+            .field("flush_interval", &self.flush_interval)
+            .field("retention", &self.retention)
+            .finish()
+    }
+}
+
+/// Durable storage for monitored positions and the alerts raised on them
+#[async_trait::async_trait]
+pub trait PositionStore: Send + Sync {
+    /// Insert or replace a position
+    async fn save(&self, position: &Position) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Every stored position, oldest first, yielded one at a time so large books need not
+    /// be held in memory twice
+    fn load_all(&self) -> BoxStream<'_, Result<Position, Box<dyn std::error::Error + Send + Sync>>>;
+
+    /// Remove a position together with its alerts; unknown ids are not an error
+    async fn delete(&self, position_id: PositionId) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Insert or replace an alert
+    async fn save_alert(&self, alert: &RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    async fn load_alerts(&self) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Append an entry to an alert's audit trail. Entries outlive their alert's position.
+    async fn append_audit_entry(&self, entry: &AlertAuditEntry) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Every stored audit entry, oldest first
+    async fn load_audit_entries(&self) -> Result<Vec<AlertAuditEntry>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl fmt::Debug for dyn PositionStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PositionStore")
+    }
+}
+
+/// Alerts appended to the log before it is folded into a fresh snapshot
+pub const DEFAULT_ALERT_LOG_LIMIT: usize = 1000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredState {
+    positions: HashMap<PositionId, Position>,
+    alerts: HashMap<Uuid, RiskAlert>,
+    #[serde(default)]
+    audit: Vec<AlertAuditEntry>,
+    /// Generation of the alert log holding alerts saved since this snapshot
+    #[serde(default)]
+    alert_log: u64,
+    /// Alerts and audit entries appended to the current log
+    #[serde(skip)]
+    logged_alerts: usize,
+}
+
+/// One line of the alert log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum AlertLogEntry {
+    Alert(RiskAlert),
+    Audit(AlertAuditEntry),
+}
+
+/// Position store keeping a JSON snapshot plus a log of alerts saved since.
+///
+/// Positions change rarely, and each change rewrites the snapshot: the new contents go to
+/// a temporary file next to it, which is then renamed over the original, so a crash
+/// mid-write leaves the previous state intact. Alerts are raised and acknowledged far
+/// more often, so they are appended one JSON line at a time to `<path>.alerts.<n>`
+/// instead, together with alert audit entries, and folded into the snapshot after
+/// `alert_log_limit` of them. Each snapshot
+/// names the log generation it is followed by, so a log already folded in is never
+/// replayed.
+pub struct JsonFilePositionStore {
+    path: PathBuf,
+    state: Mutex<StoredState>,
+    alert_log_limit: usize,
+}
+
+impl JsonFilePositionStore {
+    /// Open the store at `path`, starting empty if the file does not exist yet
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref().to_path_buf();
+        let mut state: StoredState = match tokio::fs::read(&path).await {
+            Ok(contents) => serde_json::from_slice(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => StoredState::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let log = match tokio::fs::read_to_string(Self::alert_log_path(&path, state.alert_log)).await {
+            Ok(log) => log,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let lines: Vec<&str> = log.split_terminator('\n').collect();
+        for (index, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<AlertLogEntry>(line) {
+                Ok(AlertLogEntry::Alert(alert)) => {
+                    state.alerts.insert(alert.id, alert);
+                    state.logged_alerts += 1;
+                }
+                Ok(AlertLogEntry::Audit(entry)) => {
+                    state.audit.push(entry);
+                    state.logged_alerts += 1;
+                }
+                // A crash mid-append leaves a partial last line; that alert was never saved
+                Err(_) if index + 1 == lines.len() && !log.ends_with('\n') => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+            alert_log_limit: DEFAULT_ALERT_LOG_LIMIT,
+        })
+    }
+
+    /// Alerts and audit entries appended before the log is folded into a new snapshot
+    pub fn with_alert_log_limit(mut self, limit: usize) -> Self {
+        self.alert_log_limit = limit;
+        self
+    }
+
+    fn alert_log_path(path: &Path, generation: u64) -> PathBuf {
+        let mut log_path = path.to_path_buf().into_os_string();
+        log_path.push(format!(".alerts.{}", generation));
+        PathBuf::from(log_path)
+    }
+
+    /// Write `state` as the new snapshot, followed by a new, empty alert log
+    async fn write(&self, state: &mut StoredState) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let folded_log = Self::alert_log_path(&self.path, state.alert_log);
+        state.alert_log += 1;
+        if let Err(e) = self.write_snapshot(state).await {
+            state.alert_log -= 1;
+            return Err(e);
+        }
+        state.logged_alerts = 0;
+
+        // Every alert in the old log is in the snapshot now
+        if let Err(e) = tokio::fs::remove_file(&folded_log).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove folded alert log {}: {}", folded_log.display(), e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_snapshot(&self, state: &StoredState) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+
+        let contents = serde_json::to_vec_pretty(state)?;
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &contents).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        tokio::fs::rename(&temp_path, &self.path).await?;
+        Ok(())
+    }
+
+    async fn append_log_entry(&self, state: &StoredState, entry: &AlertLogEntry) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::alert_log_path(&self.path, state.alert_log))
+            .await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &line).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl PositionStore for JsonFilePositionStore {
+    async fn save(&self, position: &Position) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = self.state.lock().await;
+        let previous = state.positions.insert(position.id, position.clone());
+        if let Err(e) = self.write(&mut state).await {
+            // Keep memory in step with the file that is still on disk
+            match previous {
+                Some(previous) => state.positions.insert(position.id, previous),
+                None => state.positions.remove(&position.id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn load_all(&self) -> BoxStream<'_, Result<Position, Box<dyn std::error::Error + Send + Sync>>> {
+        futures::stream::once(async move {
+            let mut positions: Vec<Position> = self.state.lock().await.positions.values().cloned().collect();
+            positions.sort_by_key(|position| position.created_at);
+            futures::stream::iter(positions.into_iter().map(Ok))
+        })
+        .flatten()
+        .boxed()
+    }
+
+    async fn delete(&self, position_id: PositionId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = self.state.lock().await;
+        if !state.positions.contains_key(&position_id) && !state.alerts.values().any(|a| a.position_id == position_id) {
+            return Ok(());
+        }
+
+        let mut next = StoredState {
+            positions: state.positions.clone(),
+            alerts: state.alerts.clone(),
+            audit: state.audit.clone(),
+            alert_log: state.alert_log,
+            logged_alerts: state.logged_alerts,
+        };
+        next.positions.remove(&position_id);
+        next.alerts.retain(|_, alert| alert.position_id != position_id);
+        self.write(&mut next).await?;
+        *state = next;
+        Ok(())
+    }
+
+    async fn save_alert(&self, alert: &RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = self.state.lock().await;
+        if state.logged_alerts < self.alert_log_limit {
+            self.append_log_entry(&state, &AlertLogEntry::Alert(alert.clone())).await?;
+            state.alerts.insert(alert.id, alert.clone());
+            state.logged_alerts += 1;
+            return Ok(());
+        }
+
+        let previous = state.alerts.insert(alert.id, alert.clone());
+        if let Err(e) = self.write(&mut state).await {
+            match previous {
+                Some(previous) => state.alerts.insert(alert.id, previous),
+                None => state.alerts.remove(&alert.id),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn load_alerts(&self) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut alerts: Vec<RiskAlert> = self.state.lock().await.alerts.values().cloned().collect();
+        alerts.sort_by_key(|alert| alert.created_at);
+        Ok(alerts)
+    }
+
+    async fn append_audit_entry(&self, entry: &AlertAuditEntry) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut state = self.state.lock().await;
+        if state.logged_alerts < self.alert_log_limit {
+            self.append_log_entry(&state, &AlertLogEntry::Audit(entry.clone())).await?;
+            state.audit.push(entry.clone());
+            state.logged_alerts += 1;
+            return Ok(());
+        }
+
+        state.audit.push(entry.clone());
+        if let Err(e) = self.write(&mut state).await {
+            state.audit.pop();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn load_audit_entries(&self) -> Result<Vec<AlertAuditEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.state.lock().await.audit.clone())
+    }
+}
+
+/// Alert system decorator that records every alert, and later acknowledgements, in a
+/// position store before handing it to the wrapped system
+pub struct PersistingAlertSystem {
+    inner: Arc<dyn AlertSystem>,
+    store: Arc<dyn PositionStore>,
+}
+
+impl PersistingAlertSystem {
+    pub fn new(inner: Arc<dyn AlertSystem>, store: Arc<dyn PositionStore>) -> Self {
+        Self { inner, store }
+    }
+}
+
+#[async_trait::async_trait]
+impl AlertSystem for PersistingAlertSystem {
+    async fn send_alert(&self, alert: RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Err(e) = self.store.save_alert(&alert).await {
+            tracing::warn!("Failed to persist alert {}: {}", alert.id, e);
+        }
+        self.inner.send_alert(alert).await
+    }
+
+    async fn get_alerts(&self, position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.get_alerts(position_id).await
+    }
+
+    async fn acknowledge_alert_with_note(&self, alert_id: Uuid, acknowledged_by: &str, note: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.acknowledge_alert_with_note(alert_id, acknowledged_by, note).await?;
+        let acknowledged = self.inner.get_alerts(None).await?
+            .into_iter()
+            .find(|alert| alert.id == alert_id);
+        if let Some(alert) = acknowledged {
+            self.store.save_alert(&alert).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AlertType, HealthFactor, RiskLevel};
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+
+    fn position() -> Position {
+        Position {
+            id: Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::new(),
+            debt_tokens: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            risk_overrides: None,
+        }
+    }
+
+    fn alert(position_id: PositionId) -> RiskAlert {
+        let now = Utc::now();
+        RiskAlert {
+            id: Uuid::new_v4(),
+            position_id,
+            alert_type: AlertType::LiquidationRisk,
+            risk_level: RiskLevel::Critical,
+            health_factor: HealthFactor {
+                value: Decimal::ONE,
+                liquidation_threshold: Decimal::ONE,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: now,
+            },
+            message: "test".to_string(),
+            created_at: now,
+            occurrence_count: 1,
+            last_seen: now,
+            acknowledged: false,
+            protocol: None,
+            related_tokens: vec![],
+            projected_seconds_to_liquidation: None,
+        }
+    }
+
+    async fn store_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aegis-store-{}", Uuid::new_v4()));
+        tokio::fs::create_dir(&dir).await.unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_json_file_store_survives_reopen() {
+        let dir = store_dir().await;
+        let path = dir.join("positions.json");
+        let kept = position();
+        let deleted = position();
+
+        let store = JsonFilePositionStore::open(&path).await.unwrap();
+        store.save(&kept).await.unwrap();
+        store.save(&deleted).await.unwrap();
+        store.save_alert(&alert(kept.id)).await.unwrap();
+        store.save_alert(&alert(deleted.id)).await.unwrap();
+        store.delete(deleted.id).await.unwrap();
+
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        assert!(!Path::new(&temp_path).exists());
+
+        let reopened = JsonFilePositionStore::open(&path).await.unwrap();
+        let positions: Vec<Position> = futures::TryStreamExt::try_collect(reopened.load_all()).await.unwrap();
+        assert_eq!(positions.iter().map(|p| p.id).collect::<Vec<_>>(), vec![kept.id]);
+        let alerts = reopened.load_alerts().await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].position_id, kept.id);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_alerts_are_appended_and_folded_into_the_snapshot() {
+        let dir = store_dir().await;
+        let path = dir.join("positions.json");
+        let owner = position();
+        let mut first = alert(owner.id);
+
+        let store = JsonFilePositionStore::open(&path).await.unwrap().with_alert_log_limit(2);
+        store.save(&owner).await.unwrap();
+        let snapshot = tokio::fs::read(&path).await.unwrap();
+        store.save_alert(&first).await.unwrap();
+        first.acknowledged = true;
+        store.save_alert(&first).await.unwrap();
+        // Both saves went to the log and left the snapshot alone
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), snapshot);
+
+        let reopened = JsonFilePositionStore::open(&path).await.unwrap();
+        let alerts = reopened.load_alerts().await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].acknowledged);
+
+        // Past the limit the log is folded into a new snapshot and a new log started
+        store.save_alert(&alert(owner.id)).await.unwrap();
+        assert_ne!(tokio::fs::read(&path).await.unwrap(), snapshot);
+        store.save_alert(&alert(owner.id)).await.unwrap();
+        let mut files = Vec::new();
+        let mut entries = tokio::fs::read_dir(&dir).await.unwrap();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            files.push(entry.file_name().into_string().unwrap());
+        }
+        files.sort();
+        assert_eq!(files, vec!["positions.json", "positions.json.alerts.2"]);
+
+        let reopened = JsonFilePositionStore::open(&path).await.unwrap();
+        assert_eq!(reopened.load_alerts().await.unwrap().len(), 3);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_partially_appended_alert_is_ignored() {
+        let dir = store_dir().await;
+        let path = dir.join("positions.json");
+        let saved = alert(Uuid::new_v4());
+
+        let store = JsonFilePositionStore::open(&path).await.unwrap();
+        store.save_alert(&saved).await.unwrap();
+        let log_path = dir.join("positions.json.alerts.0");
+        let mut log = tokio::fs::read(&log_path).await.unwrap();
+        log.extend_from_slice(br#"{"id":"#);
+        tokio::fs::write(&log_path, &log).await.unwrap();
+
+        let reopened = JsonFilePositionStore::open(&path).await.unwrap();
+        let alerts = reopened.load_alerts().await.unwrap();
+        assert_eq!(alerts.iter().map(|alert| alert.id).collect::<Vec<_>>(), vec![saved.id]);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_store_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("aegis-positions-{}.json", Uuid::new_v4()));
+        tokio::fs::write(&path, b"{ truncated").await.unwrap();
+
+        assert!(JsonFilePositionStore::open(&path).await.is_err());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
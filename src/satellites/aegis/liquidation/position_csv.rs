@@ -0,0 +1,368 @@
+use crate::types::{Position, PositionId, PositionToken, RiskParameters};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Columns of a position CSV; each row holds one collateral or debt token of a position.
+/// The trailing risk override columns repeat on every row of a position and are all empty
+/// when it has no overrides.
+pub const POSITION_CSV_HEADER: &str = concat!(
+    "position_id,protocol,created_at,updated_at,side,token_address,amount,value_usd,price_per_token,entry_price_usd,",
+    "safe_health_threshold,warning_health_threshold,critical_health_threshold,emergency_health_threshold,",
+    "max_position_size_usd,max_protocol_exposure_percent,max_price_age_secs",
+);
+
+/// Columns of files written before risk overrides were exported; still accepted on import
+const TOKEN_COLUMNS: usize = 10;
+const OVERRIDE_COLUMNS: usize = 7;
+
+/// A CSV row that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionCsvRowError {
+    /// 1-based line number in the CSV
+    pub line: usize,
+    /// Position the row belongs to, when its id could be read
+    pub position_id: Option<PositionId>,
+    pub message: String,
+}
+
+/// Outcome of a bulk position import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PositionImportReport {
+    pub imported: Vec<PositionId>,
+    /// Rows that were rejected, in line order. A position with any rejected row is not
+    /// imported at all, so a partially read position never gets monitored.
+    pub errors: Vec<PositionCsvRowError>,
+}
+
+struct ParsedPosition {
+    first_line: usize,
+    position: Position,
+    valid: bool,
+}
+
+/// Parse position CSV `contents` into positions, each paired with the line it starts on,
+/// and the errors of rows that were rejected.
+///
+/// Rows sharing a `position_id` are merged into one position. The header row is optional
+/// and blank lines are skipped. Rows without the risk override columns import as
+/// positions without overrides.
+pub fn parse_positions_csv(contents: &str) -> (Vec<(usize, Position)>, Vec<PositionCsvRowError>) {
+    let mut parsed: Vec<ParsedPosition> = Vec::new();
+    let mut index_by_id: HashMap<PositionId, usize> = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("position_id,") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let position_id = match fields.first().map(|id| Uuid::parse_str(id)) {
+            Some(Ok(id)) => id,
+            _ => {
+                errors.push(PositionCsvRowError {
+                    line: line_number,
+                    position_id: None,
+                    message: format!("invalid position id {:?}", fields.first().copied().unwrap_or_default()),
+                });
+                continue;
+            }
+        };
+
+        let slot = *index_by_id.entry(position_id).or_insert_with(|| {
+            let now = Utc::now();
+            parsed.push(ParsedPosition {
+                first_line: line_number,
+                position: Position {
+                    id: position_id,
+                    protocol: String::new(),
+                    collateral_tokens: HashMap::new(),
+                    debt_tokens: HashMap::new(),
+                    created_at: now,
+                    updated_at: now,
+                    risk_overrides: None,
+                },
+                valid: true,
+            });
+            parsed.len() - 1
+        });
+        let entry = &mut parsed[slot];
+        let is_first_row = entry.first_line == line_number;
+
+        if let Err(message) = apply_row(&mut entry.position, &fields, is_first_row) {
+            entry.valid = false;
+            errors.push(PositionCsvRowError { line: line_number, position_id: Some(position_id), message });
+        }
+    }
+
+    let positions = parsed.into_iter()
+        .filter(|entry| entry.valid)
+        .map(|entry| (entry.first_line, entry.position))
+        .collect();
+    (positions, errors)
+}
+
+/// Validate one row and add its token to `position`. The first row of a position sets its
+/// protocol, timestamps and risk overrides; later rows must agree with them.
+fn apply_row(position: &mut Position, fields: &[&str], is_first_row: bool) -> Result<(), String> {
+    if fields.len() != TOKEN_COLUMNS && fields.len() != TOKEN_COLUMNS + OVERRIDE_COLUMNS {
+        return Err(format!(
+            "expected {} or {} columns but found {}",
+            TOKEN_COLUMNS, TOKEN_COLUMNS + OVERRIDE_COLUMNS, fields.len()
+        ));
+    }
+    let risk_overrides = parse_risk_overrides(&fields[TOKEN_COLUMNS..])?;
+    if is_first_row {
+        position.risk_overrides = risk_overrides;
+    } else if position.risk_overrides != risk_overrides {
+        return Err("risk overrides differ from the position's first row".to_string());
+    }
+
+    let protocol = fields[1];
+    if protocol.is_empty() {
+        return Err("protocol is empty".to_string());
+    }
+    let created_at = parse_timestamp("created_at", fields[2])?;
+    let updated_at = parse_timestamp("updated_at", fields[3])?;
+    if is_first_row {
+        position.protocol = protocol.to_string();
+        position.created_at = created_at;
+        position.updated_at = updated_at;
+    } else if position.protocol != protocol || position.created_at != created_at || position.updated_at != updated_at {
+        return Err("protocol and timestamps differ from the position's first row".to_string());
+    }
+
+    let token_address = fields[5];
+    if token_address.is_empty() {
+        return Err("token_address is empty".to_string());
+    }
+    let amount = parse_decimal("amount", fields[6])?;
+    if amount <= Decimal::ZERO {
+        return Err(format!("amount must be positive, found {}", amount));
+    }
+    let value_usd = parse_decimal("value_usd", fields[7])?;
+    let price_per_token = parse_decimal("price_per_token", fields[8])?;
+    if value_usd < Decimal::ZERO || price_per_token < Decimal::ZERO {
+        return Err("value_usd and price_per_token must not be negative".to_string());
+    }
+    let entry_price_usd = match fields[9] {
+        "" => None,
+        price => Some(parse_decimal("entry_price_usd", price)?),
+    };
+
+    let tokens = match fields[4] {
+        "collateral" => &mut position.collateral_tokens,
+        "debt" => &mut position.debt_tokens,
+        side => return Err(format!("side must be collateral or debt, found {:?}", side)),
+    };
+    if tokens.contains_key(token_address) {
+        return Err(format!("{} is listed twice as {}", token_address, fields[4]));
+    }
+    tokens.insert(token_address.to_string(), PositionToken {
+        token_address: token_address.to_string(),
+        amount,
+        value_usd,
+        price_per_token,
+        entry_price_usd,
+    });
+    Ok(())
+}
+
+/// Risk overrides from the trailing columns, which must be all set or all empty
+fn parse_risk_overrides(fields: &[&str]) -> Result<Option<RiskParameters>, String> {
+    if fields.iter().all(|field| field.is_empty()) {
+        return Ok(None);
+    }
+    if fields.iter().any(|field| field.is_empty()) {
+        return Err("risk override columns must be all set or all empty".to_string());
+    }
+    let max_price_age = fields[6].parse::<f64>().ok()
+        .and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("invalid max_price_age_secs {:?}", fields[6]))?;
+    Ok(Some(RiskParameters {
+        safe_health_threshold: parse_decimal("safe_health_threshold", fields[0])?,
+        warning_health_threshold: parse_decimal("warning_health_threshold", fields[1])?,
+        critical_health_threshold: parse_decimal("critical_health_threshold", fields[2])?,
+        emergency_health_threshold: parse_decimal("emergency_health_threshold", fields[3])?,
+        max_position_size_usd: parse_decimal("max_position_size_usd", fields[4])?,
+        max_protocol_exposure_percent: parse_decimal("max_protocol_exposure_percent", fields[5])?,
+        max_price_age,
+    }))
+}
+
+fn parse_timestamp(column: &str, value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|e| format!("invalid {} {:?}: {}", column, value, e))
+}
+
+fn parse_decimal(column: &str, value: &str) -> Result<Decimal, String> {
+    Decimal::from_str(value).map_err(|e| format!("invalid {} {:?}: {}", column, value, e))
+}
+
+/// Write `positions` as CSV with a header row, oldest position first and tokens sorted
+/// within each side. Returns the number of positions written. Every position is checked
+/// before anything is written, so a field that can't be written leaves `writer` untouched.
+pub fn write_positions_csv(positions: &[Position], mut writer: impl Write) -> std::io::Result<usize> {
+    let mut positions: Vec<&Position> = positions.iter().collect();
+    positions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+    let mut rows = Vec::new();
+    for position in &positions {
+        if contains_separator(&position.protocol) {
+            return Err(invalid_field(position.id, &position.protocol));
+        }
+        let overrides = match &position.risk_overrides {
+            Some(params) => format!(
+                "{},{},{},{},{},{},{}",
+                params.safe_health_threshold,
+                params.warning_health_threshold,
+                params.critical_health_threshold,
+                params.emergency_health_threshold,
+                params.max_position_size_usd,
+                params.max_protocol_exposure_percent,
+                params.max_price_age.as_secs_f64(),
+            ),
+            None => ",".repeat(OVERRIDE_COLUMNS - 1),
+        };
+        for (side, tokens) in [("collateral", &position.collateral_tokens), ("debt", &position.debt_tokens)] {
+            let mut tokens: Vec<&PositionToken> = tokens.values().collect();
+            tokens.sort_by(|a, b| a.token_address.cmp(&b.token_address));
+            for token in tokens {
+                if contains_separator(&token.token_address) {
+                    return Err(invalid_field(position.id, &token.token_address));
+                }
+                rows.push(format!(
+                    "{},{},{},{},{},{},{},{},{},{},{}",
+                    position.id,
+                    position.protocol,
+                    position.created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    position.updated_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    side,
+                    token.token_address,
+                    token.amount,
+                    token.value_usd,
+                    token.price_per_token,
+                    token.entry_price_usd.map(|price| price.to_string()).unwrap_or_default(),
+                    overrides,
+                ));
+            }
+        }
+    }
+
+    writeln!(writer, "{}", POSITION_CSV_HEADER)?;
+    for row in rows {
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(positions.len())
+}
+
+fn contains_separator(value: &str) -> bool {
+    value.contains(',') || value.contains('\n') || value.contains('\r')
+}
+
+fn invalid_field(position_id: PositionId, value: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("position {} has a field that cannot be written as CSV: {:?}", position_id, value),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION_A: &str = "7d8a3c2e-1f4b-4e5a-9c6d-0b1a2c3d4e5f";
+    const POSITION_B: &str = "0c1d2e3f-4a5b-4c6d-8e7f-9a0b1c2d3e4f";
+
+    #[test]
+    fn test_bad_rows_reject_only_their_position() {
+        let csv = [
+            POSITION_CSV_HEADER.to_string(),
+            format!("{},aave,2024-03-01T00:00:00Z,2024-03-01T00:00:00Z,collateral,ETH,10,20000,2000,", POSITION_A),
+            format!("{},aave,2024-03-01T00:00:00Z,2024-03-01T00:00:00Z,debt,USDC,5000,5000,1,", POSITION_A),
+            format!("{},compound,2024-03-02T00:00:00Z,2024-03-02T00:00:00Z,collateral,WBTC,1,60000,60000,", POSITION_B),
+            format!("{},compound,2024-03-02T00:00:00Z,2024-03-02T00:00:00Z,debt,USDC,-5,0,1,", POSITION_B),
+            "not-a-uuid,aave,2024-03-01T00:00:00Z,2024-03-01T00:00:00Z,collateral,ETH,1,0,0,".to_string(),
+        ].join("\n");
+
+        let (positions, errors) = parse_positions_csv(&csv);
+
+        assert_eq!(positions.len(), 1);
+        let (line, position) = &positions[0];
+        assert_eq!(*line, 2);
+        assert_eq!(position.id.to_string(), POSITION_A);
+        assert_eq!(position.collateral_tokens["ETH"].amount, Decimal::from(10));
+        assert_eq!(position.debt_tokens["USDC"].amount, Decimal::from(5000));
+
+        let rejected: Vec<(usize, Option<String>)> = errors.iter()
+            .map(|error| (error.line, error.position_id.map(|id| id.to_string())))
+            .collect();
+        assert_eq!(rejected, vec![(5, Some(POSITION_B.to_string())), (6, None)]);
+        assert!(errors[0].message.contains("amount must be positive"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn test_risk_overrides_round_trip() {
+        let created_at = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let token = |address: &str, amount: i64| (address.to_string(), PositionToken {
+            token_address: address.to_string(),
+            amount: Decimal::from(amount),
+            value_usd: Decimal::ZERO,
+            price_per_token: Decimal::ZERO,
+            entry_price_usd: None,
+        });
+        let overridden = Position {
+            id: Uuid::parse_str(POSITION_A).unwrap(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 5000)]),
+            created_at,
+            updated_at: created_at,
+            risk_overrides: Some(RiskParameters {
+                safe_health_threshold: Decimal::new(20, 1),
+                max_price_age: std::time::Duration::from_millis(90_500),
+                ..RiskParameters::default()
+            }),
+        };
+        let plain = Position { id: Uuid::parse_str(POSITION_B).unwrap(), risk_overrides: None, ..overridden.clone() };
+
+        let mut written = Vec::new();
+        write_positions_csv(&[overridden.clone(), plain], &mut written).unwrap();
+        let (positions, errors) = parse_positions_csv(&String::from_utf8(written).unwrap());
+        assert!(errors.is_empty(), "{:?}", errors);
+        let by_id: HashMap<PositionId, Position> = positions.into_iter().map(|(_, position)| (position.id, position)).collect();
+        assert_eq!(by_id[&overridden.id].risk_overrides, overridden.risk_overrides);
+        assert_eq!(by_id[&Uuid::parse_str(POSITION_B).unwrap()].risk_overrides, None);
+
+        // Partly filled override columns reject the position
+        let row = format!("{},aave,2024-03-01T00:00:00Z,2024-03-01T00:00:00Z,collateral,ETH,10,0,0,,2,,,,,,", POSITION_A);
+        let (positions, errors) = parse_positions_csv(&row);
+        assert!(positions.is_empty());
+        assert!(errors[0].message.contains("all set or all empty"), "{}", errors[0].message);
+    }
+
+    #[test]
+    fn test_unwritable_field_writes_nothing() {
+        let csv = format!("{},aave,2024-03-01T00:00:00Z,2024-03-01T00:00:00Z,collateral,ETH,10,0,0,", POSITION_A);
+        let (mut positions, _) = parse_positions_csv(&csv);
+        let mut bad = positions[0].1.clone();
+        bad.id = Uuid::parse_str(POSITION_B).unwrap();
+        bad.created_at += chrono::Duration::days(1);
+        bad.protocol = "aave,v3".to_string();
+        positions.push((0, bad));
+        let positions: Vec<Position> = positions.into_iter().map(|(_, position)| position).collect();
+
+        let mut written = Vec::new();
+        let error = write_positions_csv(&positions, &mut written).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(written.is_empty());
+    }
+}
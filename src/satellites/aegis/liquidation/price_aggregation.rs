@@ -0,0 +1,262 @@
+use crate::liquidation::monitor::PriceFeedProvider;
+use crate::types::{PriceData, TokenAddress};
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// How the quotes of several sources are combined into one price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PriceAggregation {
+    #[default]
+    Median,
+    Mean,
+    /// Mean weighted by each quote's `confidence`, falling back to the plain mean when
+    /// every confidence is zero
+    ConfidenceWeighted,
+}
+
+impl PriceAggregation {
+    fn aggregate(&self, token_address: &str, quotes: &[&PriceData]) -> Decimal {
+        let mean = || quotes.iter().map(|q| q.price_usd).sum::<Decimal>() / Decimal::from(quotes.len());
+
+        match self {
+            PriceAggregation::Median => {
+                let mut prices: Vec<Decimal> = quotes.iter().map(|q| q.price_usd).collect();
+                prices.sort();
+                let mid = prices.len() / 2;
+                if prices.len().is_multiple_of(2) {
+                    (prices[mid - 1] + prices[mid]) / Decimal::TWO
+                } else {
+                    prices[mid]
+                }
+            }
+            PriceAggregation::Mean => mean(),
+            PriceAggregation::ConfidenceWeighted => {
+                let total_confidence: Decimal = quotes.iter().map(|q| q.confidence.max(Decimal::ZERO)).sum();
+                if total_confidence.is_zero() {
+                    warn!("All price sources for {} report zero confidence, using the plain mean", token_address);
+                    return mean();
+                }
+                quotes.iter()
+                    .map(|q| q.price_usd * q.confidence.max(Decimal::ZERO))
+                    .sum::<Decimal>() / total_confidence
+            }
+        }
+    }
+}
+
+/// Combines several price feeds into one price per token.
+///
+/// Sources that fail, omit a token or quote it at zero are discarded for that token. A
+/// token priced by fewer than `min_sources` sources is an error rather than a price from
+/// a single, possibly compromised, oracle.
+pub struct AggregatedPriceFeedProvider {
+    sources: Vec<Arc<dyn PriceFeedProvider>>,
+    min_sources: usize,
+    aggregation: PriceAggregation,
+}
+
+impl AggregatedPriceFeedProvider {
+    pub fn new(sources: Vec<Arc<dyn PriceFeedProvider>>, min_sources: usize, aggregation: PriceAggregation) -> Self {
+        Self {
+            sources,
+            min_sources: min_sources.max(1),
+            aggregation,
+        }
+    }
+
+    fn aggregation_name(&self) -> &'static str {
+        match self.aggregation {
+            PriceAggregation::Median => "median",
+            PriceAggregation::Mean => "mean",
+            PriceAggregation::ConfidenceWeighted => "confidence_weighted",
+        }
+    }
+}
+
+/// Aggregated feed returning the median quote per token
+pub struct MedianPriceFeedProvider(AggregatedPriceFeedProvider);
+
+impl MedianPriceFeedProvider {
+    pub fn new(sources: Vec<Arc<dyn PriceFeedProvider>>, min_sources: usize) -> Self {
+        Self(AggregatedPriceFeedProvider::new(sources, min_sources, PriceAggregation::Median))
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceFeedProvider for MedianPriceFeedProvider {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        self.0.get_prices(token_addresses).await
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        self.0.get_price(token_address).await
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceFeedProvider for AggregatedPriceFeedProvider {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let responses = join_all(self.sources.iter().map(|source| source.get_prices(token_addresses))).await;
+
+        let mut quotes: Vec<HashMap<TokenAddress, PriceData>> = Vec::with_capacity(responses.len());
+        for (index, response) in responses.into_iter().enumerate() {
+            match response {
+                Ok(prices) => quotes.push(prices),
+                Err(e) => warn!("Discarding price source {}: {}", index, e),
+            }
+        }
+
+        let mut prices = HashMap::new();
+        for token_address in token_addresses {
+            let mut contributing: Vec<&PriceData> = Vec::new();
+            for price_data in quotes.iter().filter_map(|q| q.get(token_address)) {
+                if price_data.price_usd > Decimal::ZERO {
+                    contributing.push(price_data);
+                } else {
+                    warn!("Discarding zero price for {} from source {}", token_address, price_data.source);
+                }
+            }
+
+            if contributing.len() < self.min_sources {
+                return Err(format!(
+                    "Only {} of {} price sources returned a usable price for {} (minimum {})",
+                    contributing.len(), self.sources.len(), token_address, self.min_sources
+                ).into());
+            }
+
+            let sources: Vec<&str> = contributing.iter().map(|p| p.source.as_str()).collect();
+            prices.insert(token_address.clone(), PriceData {
+                token_address: token_address.clone(),
+                price_usd: self.aggregation.aggregate(token_address, &contributing),
+                // Report the oldest quote so staleness checks see the weakest input
+                timestamp: contributing.iter().map(|p| p.timestamp).min().unwrap_or_else(chrono::Utc::now),
+                source: format!("{}({})", self.aggregation_name(), sources.join(", ")),
+                confidence: contributing.iter().map(|p| p.confidence).min().unwrap_or(Decimal::ZERO),
+            });
+        }
+
+        Ok(prices)
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        self.get_prices(std::slice::from_ref(token_address)).await?
+            .remove(token_address)
+            .ok_or_else(|| format!("No price for {}", token_address).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Quote {
+        Price(i64),
+        Missing,
+        Failing,
+    }
+
+    struct FixedFeed {
+        name: &'static str,
+        quote: Quote,
+        confidence: Decimal,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for FixedFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let price_usd = match self.quote {
+                Quote::Price(price) => Decimal::from(price),
+                Quote::Missing => return Ok(HashMap::new()),
+                Quote::Failing => return Err(format!("{} is down", self.name).into()),
+            };
+            Ok(token_addresses.iter()
+                .map(|token| (token.clone(), PriceData {
+                    token_address: token.clone(),
+                    price_usd,
+                    timestamp: chrono::Utc::now(),
+                    source: self.name.to_string(),
+                    confidence: self.confidence,
+                }))
+                .collect())
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            self.get_prices(std::slice::from_ref(token_address)).await?
+                .remove(token_address)
+                .ok_or_else(|| "missing".into())
+        }
+    }
+
+    fn feed(name: &'static str, quote: Quote) -> Arc<dyn PriceFeedProvider> {
+        weighted_feed(name, quote, Decimal::ONE)
+    }
+
+    fn weighted_feed(name: &'static str, quote: Quote, confidence: Decimal) -> Arc<dyn PriceFeedProvider> {
+        Arc::new(FixedFeed { name, quote, confidence })
+    }
+
+    #[tokio::test]
+    async fn test_median_ignores_outlier_and_bad_sources() {
+        let provider = MedianPriceFeedProvider::new(vec![
+            feed("chainlink", Quote::Price(2000)),
+            feed("pyth", Quote::Price(2010)),
+            feed("manipulated", Quote::Price(9000)),
+            feed("broken", Quote::Failing),
+            feed("zero", Quote::Price(0)),
+            feed("stale", Quote::Missing),
+        ], 3);
+
+        let price = provider.get_price(&"ETH".to_string()).await.unwrap();
+        assert_eq!(price.price_usd, Decimal::from(2010));
+        assert_eq!(price.source, "median(chainlink, pyth, manipulated)");
+
+        // An even number of sources averages the middle pair
+        let provider = MedianPriceFeedProvider::new(vec![
+            feed("a", Quote::Price(2000)),
+            feed("b", Quote::Price(2010)),
+        ], 2);
+        assert_eq!(provider.get_price(&"ETH".to_string()).await.unwrap().price_usd, Decimal::from(2005));
+    }
+
+    #[tokio::test]
+    async fn test_too_few_sources_is_an_error() {
+        let provider = MedianPriceFeedProvider::new(vec![
+            feed("chainlink", Quote::Price(2000)),
+            feed("broken", Quote::Failing),
+            feed("zero", Quote::Price(0)),
+        ], 2);
+
+        let error = provider.get_prices(&["ETH".to_string()]).await.unwrap_err();
+        assert_eq!(error.to_string(), "Only 1 of 3 price sources returned a usable price for ETH (minimum 2)");
+    }
+
+    #[tokio::test]
+    async fn test_confidence_weighted_favours_high_confidence_source() {
+        let sources = || vec![
+            weighted_feed("chainlink", Quote::Price(2000), Decimal::new(9, 1)),
+            weighted_feed("dex_spot", Quote::Price(2100), Decimal::new(1, 1)),
+        ];
+
+        let weighted = AggregatedPriceFeedProvider::new(sources(), 2, PriceAggregation::ConfidenceWeighted);
+        let price = weighted.get_price(&"ETH".to_string()).await.unwrap();
+        // (2000 * 0.9 + 2100 * 0.1) / 1.0
+        assert_eq!(price.price_usd, Decimal::from(2010));
+        assert_eq!(price.source, "confidence_weighted(chainlink, dex_spot)");
+
+        let mean = AggregatedPriceFeedProvider::new(sources(), 2, PriceAggregation::Mean);
+        assert_eq!(mean.get_price(&"ETH".to_string()).await.unwrap().price_usd, Decimal::from(2050));
+    }
+
+    #[tokio::test]
+    async fn test_confidence_weighted_without_confidence_falls_back_to_mean() {
+        let provider = AggregatedPriceFeedProvider::new(vec![
+            weighted_feed("a", Quote::Price(2000), Decimal::ZERO),
+            weighted_feed("b", Quote::Price(2100), Decimal::ZERO),
+        ], 2, PriceAggregation::ConfidenceWeighted);
+
+        assert_eq!(provider.get_price(&"ETH".to_string()).await.unwrap().price_usd, Decimal::from(2050));
+    }
+}
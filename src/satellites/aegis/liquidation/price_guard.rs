@@ -0,0 +1,255 @@
+use crate::types::{Position, PriceData, TokenAddress, CalculationError};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+
+/// Secondary price reference (e.g. a TWAP) used to sanity-check the primary feed
+#[async_trait::async_trait]
+pub trait ReferencePriceProvider: Send + Sync {
+    async fn get_reference_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A token whose primary price diverged from its reference beyond the guard's bound
+#[derive(Debug, Clone)]
+pub struct PriceDeviation {
+    pub token_address: TokenAddress,
+    pub spot_price: Decimal,
+    pub reference_price: Decimal,
+    pub deviation: Decimal,
+    pub price_used: Decimal,
+}
+
+/// Guards health calculations against oracle manipulation.
+///
+/// When the spot price of a token deviates from its reference price by more than
+/// `max_deviation` (a fraction, 0.05 = 5%), the price that is worse for the position is
+/// used instead: the lower of the two for collateral, the higher for debt.
+pub struct PriceDeviationGuard {
+    reference: Arc<dyn ReferencePriceProvider>,
+    max_deviation: Decimal,
+}
+
+impl PriceDeviationGuard {
+    pub fn new(reference: Arc<dyn ReferencePriceProvider>, max_deviation: Decimal) -> Self {
+        Self {
+            reference,
+            max_deviation,
+        }
+    }
+
+    pub async fn fetch_reference_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, CalculationError> {
+        self.reference.get_reference_prices(token_addresses).await
+            .map_err(|e| CalculationError::CalculationFailed {
+                message: format!("Failed to fetch reference prices: {}", e)
+            })
+    }
+
+    /// Conservative prices for the position, along with every deviation that triggered
+    /// an override. Tokens without a reference price are left untouched.
+    pub fn guard(
+        &self,
+        position: &Position,
+        prices: &HashMap<TokenAddress, PriceData>,
+        reference_prices: &HashMap<TokenAddress, PriceData>,
+    ) -> (HashMap<TokenAddress, PriceData>, Vec<PriceDeviation>) {
+        let mut guarded = prices.clone();
+        let mut deviations = Vec::new();
+
+        let sides = position.collateral_tokens.keys().map(|token| (token, true))
+            .chain(position.debt_tokens.keys().map(|token| (token, false)));
+
+        for (token_address, is_collateral) in sides {
+            let (spot, reference) = match (prices.get(token_address), reference_prices.get(token_address)) {
+                (Some(spot), Some(reference)) if reference.price_usd > Decimal::ZERO => (spot, reference),
+                _ => continue,
+            };
+
+            let deviation = (spot.price_usd - reference.price_usd).abs() / reference.price_usd;
+            if deviation <= self.max_deviation {
+                continue;
+            }
+
+            let price_used = if is_collateral {
+                spot.price_usd.min(reference.price_usd)
+            } else {
+                spot.price_usd.max(reference.price_usd)
+            };
+
+            // A token on both sides of the position ends up priced for the debt side
+            if let Some(price_data) = guarded.get_mut(token_address) {
+                price_data.price_usd = price_used;
+                price_data.source = format!("{} (deviation guard: {})", spot.source, reference.source);
+            }
+            if !deviations.iter().any(|d: &PriceDeviation| &d.token_address == token_address) {
+                deviations.push(PriceDeviation {
+                    token_address: token_address.clone(),
+                    spot_price: spot.price_usd,
+                    reference_price: reference.price_usd,
+                    deviation,
+                    price_used,
+                });
+            }
+        }
+
+        (guarded, deviations)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PriceCircuitBreakerConfig {
+    /// Largest accepted move from the last accepted price, as a fraction (0.5 = 50%)
+    pub max_move: Decimal,
+    /// Moves beyond `max_move` are only rejected when they happen within this window
+    pub window: std::time::Duration,
+}
+
+impl Default for PriceCircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_move: Decimal::new(5, 1),
+            window: std::time::Duration::from_secs(300),
+        }
+    }
+}
+
+/// A price update rejected by the breaker
+#[derive(Debug, Clone)]
+pub struct PriceTrip {
+    pub token_address: TokenAddress,
+    pub last_accepted_price: Decimal,
+    pub rejected_price: Decimal,
+    /// Relative move from the last accepted price
+    pub change: Decimal,
+    /// Accepted-price timestamp the move was measured against
+    pub last_accepted_at: DateTime<Utc>,
+    /// False when the token was already tripped by an earlier update
+    pub newly_tripped: bool,
+}
+
+struct TokenPriceState {
+    accepted: PriceData,
+    tripped: bool,
+}
+
+/// Rejects implausible price jumps, such as a single oracle glitch reporting a 90% drop.
+///
+/// Each price is compared against the last one accepted for its token. A move larger than
+/// `max_move` within `window` of that price trips the breaker and the update is rejected.
+/// The breaker resets on the first price back within `max_move` of the last accepted one,
+/// or once a moved price has persisted past the window, at which point it is taken as real.
+/// Gradual moves are accepted step by step however far they go in total.
+pub struct PriceCircuitBreaker {
+    config: PriceCircuitBreakerConfig,
+    tokens: DashMap<TokenAddress, TokenPriceState>,
+}
+
+impl PriceCircuitBreaker {
+    pub fn new(config: PriceCircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            tokens: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &PriceCircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Check the prices of `token_addresses`, accepting each plausible one, and return every
+    /// rejection in token order. Tokens missing from `prices` are skipped.
+    pub fn check(&self, prices: &HashMap<TokenAddress, PriceData>, token_addresses: &[TokenAddress]) -> Vec<PriceTrip> {
+        let mut trips = Vec::new();
+        for token_address in Self::distinct(token_addresses) {
+            let price = match prices.get(token_address) {
+                Some(price) => price,
+                None => continue,
+            };
+            let mut state = match self.tokens.get_mut(token_address) {
+                Some(state) => state,
+                None => {
+                    self.tokens.insert(token_address.clone(), TokenPriceState { accepted: price.clone(), tripped: false });
+                    continue;
+                }
+            };
+
+            match self.trip(token_address, &state, price) {
+                Some(trip) => {
+                    state.tripped = true;
+                    trips.push(trip);
+                }
+                None => {
+                    if state.tripped {
+                        info!("Price circuit breaker for {} reset at ${}", token_address, price.price_usd);
+                    }
+                    *state = TokenPriceState { accepted: price.clone(), tripped: false };
+                }
+            }
+        }
+
+        trips
+    }
+
+    /// The rejections `check` would return for these prices, without accepting any price
+    /// or tripping the breaker
+    pub fn would_trip(&self, prices: &HashMap<TokenAddress, PriceData>, token_addresses: &[TokenAddress]) -> Vec<PriceTrip> {
+        Self::distinct(token_addresses).into_iter()
+            .filter_map(|token_address| {
+                let state = self.tokens.get(token_address)?;
+                self.trip(token_address, &state, prices.get(token_address)?)
+            })
+            .collect()
+    }
+
+    fn distinct(token_addresses: &[TokenAddress]) -> Vec<&TokenAddress> {
+        let mut token_addresses: Vec<&TokenAddress> = token_addresses.iter().collect();
+        token_addresses.sort();
+        token_addresses.dedup();
+        token_addresses
+    }
+
+    /// The rejection `price` meets against the token's last accepted price, if any
+    fn trip(&self, token_address: &TokenAddress, state: &TokenPriceState, price: &PriceData) -> Option<PriceTrip> {
+        let (last_price, last_accepted_at) = (state.accepted.price_usd, state.accepted.timestamp);
+        let change = if last_price > Decimal::ZERO {
+            (price.price_usd - last_price).abs() / last_price
+        } else {
+            Decimal::ZERO
+        };
+        // A price stamped before the accepted one counts as within the window
+        let within_window = match (price.timestamp - last_accepted_at).to_std() {
+            Ok(elapsed) => elapsed <= self.config.window,
+            Err(_) => true,
+        };
+
+        (change > self.config.max_move && within_window).then(|| PriceTrip {
+            token_address: token_address.clone(),
+            last_accepted_price: last_price,
+            rejected_price: price.price_usd,
+            change,
+            last_accepted_at,
+            newly_tripped: !state.tripped,
+        })
+    }
+
+    /// Price the token's next update is measured against
+    pub fn last_accepted_price(&self, token_address: &TokenAddress) -> Option<Decimal> {
+        self.tokens.get(token_address).map(|state| state.accepted.price_usd)
+    }
+
+    pub fn is_tripped(&self, token_address: &TokenAddress) -> bool {
+        self.tokens.get(token_address).is_some_and(|state| state.tripped)
+    }
+
+    /// Tokens whose latest update was rejected
+    pub fn tripped_tokens(&self) -> Vec<TokenAddress> {
+        let mut tokens: Vec<TokenAddress> = self.tokens.iter()
+            .filter(|state| state.tripped)
+            .map(|state| state.key().clone())
+            .collect();
+        tokens.sort();
+        tokens
+    }
+}
@@ -0,0 +1,82 @@
+use crate::types::{Position, TokenAddress, CalculationError};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Supplies the current accrual index of rebasing or yield-bearing tokens (stETH, aTokens, ...)
+#[async_trait::async_trait]
+pub trait RebaseIndexProvider: Send + Sync {
+    async fn get_indices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, Decimal>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Valuation hook for rebasing collateral.
+///
+/// Collateral amounts of the configured tokens are treated as scaled balances (balance at
+/// index 1) and multiplied by the token's current index before health is computed, so
+/// accrued yield is reflected even while the spot price stays flat.
+pub struct RebasingValuation {
+    index_provider: Arc<dyn RebaseIndexProvider>,
+    rebasing_tokens: HashSet<TokenAddress>,
+}
+
+impl RebasingValuation {
+    pub fn new(
+        index_provider: Arc<dyn RebaseIndexProvider>,
+        rebasing_tokens: impl IntoIterator<Item = TokenAddress>,
+    ) -> Self {
+        Self {
+            index_provider,
+            rebasing_tokens: rebasing_tokens.into_iter().collect(),
+        }
+    }
+
+    pub fn is_rebasing(&self, token_address: &str) -> bool {
+        self.rebasing_tokens.contains(token_address)
+    }
+
+    /// Fetch current indices for every rebasing collateral token held by the given positions
+    pub async fn fetch_indices(&self, positions: &[Position]) -> Result<HashMap<TokenAddress, Decimal>, CalculationError> {
+        let mut tokens: Vec<TokenAddress> = positions.iter()
+            .flat_map(|p| p.collateral_tokens.keys())
+            .filter(|token| self.is_rebasing(token))
+            .cloned()
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+
+        if tokens.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        self.index_provider.get_indices(&tokens).await
+            .map_err(|e| CalculationError::CalculationFailed {
+                message: format!("Failed to fetch rebase indices: {}", e)
+            })
+    }
+
+    /// Return a copy of the position with rebasing collateral scaled by its index
+    pub fn apply(&self, position: &Position, indices: &HashMap<TokenAddress, Decimal>) -> Result<Position, CalculationError> {
+        let mut adjusted = position.clone();
+
+        for (token_address, token_position) in adjusted.collateral_tokens.iter_mut() {
+            if !self.is_rebasing(token_address) {
+                continue;
+            }
+
+            let index = indices.get(token_address)
+                .ok_or_else(|| CalculationError::CalculationFailed {
+                    message: format!("Missing rebase index for token: {}", token_address)
+                })?;
+            if *index <= Decimal::ZERO {
+                return Err(CalculationError::InvalidPosition {
+                    message: format!("Rebase index for {} must be positive, got {}", token_address, index)
+                });
+            }
+
+            token_position.amount *= *index;
+            token_position.value_usd = token_position.amount * token_position.price_per_token;
+        }
+
+        Ok(adjusted)
+    }
+}
@@ -0,0 +1,179 @@
+use crate::liquidation::monitor::PriceFeedProvider;
+use crate::liquidation::price_guard::ReferencePriceProvider;
+use crate::types::{PriceData, TokenAddress};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// Serves a rolling time-weighted average of another feed's prices.
+///
+/// Every spot quote is recorded per token and holds until the next one, so the average
+/// over `window` weights each price by how long it stood. The window ends at the latest
+/// quote's timestamp. Until a token has history reaching back a full window its spot price
+/// is served unchanged.
+///
+/// The latest spot prices stay available through `ReferencePriceProvider`, so the feed can
+/// back a `PriceDeviationGuard` comparing the average against spot.
+pub struct TwapPriceFeed {
+    inner: Arc<dyn PriceFeedProvider>,
+    window: chrono::Duration,
+    samples: DashMap<TokenAddress, VecDeque<PriceData>>,
+}
+
+impl TwapPriceFeed {
+    pub fn new(inner: Arc<dyn PriceFeedProvider>, window: std::time::Duration) -> Self {
+        Self {
+            inner,
+            window: chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX),
+            samples: DashMap::new(),
+        }
+    }
+
+    /// Most recent spot quote recorded for `token_address`
+    pub fn latest_spot(&self, token_address: &TokenAddress) -> Option<PriceData> {
+        self.samples.get(token_address).and_then(|samples| samples.back().cloned())
+    }
+
+    /// Record `spot` and return the price to serve for its token
+    fn record(&self, spot: PriceData) -> PriceData {
+        let mut samples = self.samples.entry(spot.token_address.clone()).or_default();
+        // Repeated or out-of-order quotes add no information about how long a price held
+        if samples.back().is_none_or(|last| spot.timestamp > last.timestamp) {
+            samples.push_back(spot.clone());
+        }
+
+        let latest = samples.back().map(|sample| sample.timestamp).unwrap_or(spot.timestamp);
+        let window_start = latest - self.window;
+        // Keep the last quote at or before the window start: it sets the price the window opens with
+        while samples.len() > 1 && samples[1].timestamp <= window_start {
+            samples.pop_front();
+        }
+
+        match Self::average(&samples, window_start) {
+            Some(twap) => PriceData {
+                price_usd: twap,
+                source: format!("twap({}, {}s)", spot.source, self.window.num_seconds()),
+                ..spot
+            },
+            None => spot,
+        }
+    }
+
+    /// Time-weighted average from `window_start` to the last sample, or `None` while the
+    /// history doesn't cover the whole window
+    fn average(samples: &VecDeque<PriceData>, window_start: DateTime<Utc>) -> Option<Decimal> {
+        let first = samples.front()?;
+        let end = samples.back()?.timestamp;
+        if first.timestamp > window_start || end <= window_start {
+            return None;
+        }
+
+        let mut weighted = Decimal::ZERO;
+        for (sample, next) in samples.iter().zip(samples.iter().skip(1)) {
+            let held_from = sample.timestamp.max(window_start);
+            let held_ms = (next.timestamp - held_from).num_milliseconds();
+            weighted += sample.price_usd * Decimal::from(held_ms.max(0));
+        }
+        Some(weighted / Decimal::from((end - window_start).num_milliseconds()))
+    }
+}
+
+#[async_trait::async_trait]
+impl PriceFeedProvider for TwapPriceFeed {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let spot = self.inner.get_prices(token_addresses).await?;
+        Ok(spot.into_iter()
+            .map(|(token_address, price_data)| (token_address, self.record(price_data)))
+            .collect())
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        let spot = self.inner.get_price(token_address).await?;
+        Ok(self.record(spot))
+    }
+}
+
+#[async_trait::async_trait]
+impl ReferencePriceProvider for TwapPriceFeed {
+    /// Latest recorded spot prices, fetching tokens that haven't been quoted yet
+    async fn get_reference_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut prices = HashMap::new();
+        let mut unquoted = Vec::new();
+        for token_address in token_addresses {
+            match self.latest_spot(token_address) {
+                Some(spot) => {
+                    prices.insert(token_address.clone(), spot);
+                }
+                None => unquoted.push(token_address.clone()),
+            }
+        }
+        if !unquoted.is_empty() {
+            prices.extend(self.inner.get_prices(&unquoted).await?);
+        }
+        Ok(prices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays a fixed series of (seconds offset, price) quotes for ETH
+    struct ScriptedFeed {
+        start: DateTime<Utc>,
+        quotes: std::sync::Mutex<VecDeque<(i64, i64)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for ScriptedFeed {
+        async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut prices = HashMap::new();
+            for token in token_addresses {
+                prices.insert(token.clone(), self.get_price(token).await?);
+            }
+            Ok(prices)
+        }
+
+        async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+            let (offset_secs, price) = self.quotes.lock().unwrap().pop_front().ok_or("script exhausted")?;
+            Ok(PriceData {
+                token_address: token_address.clone(),
+                price_usd: Decimal::from(price),
+                timestamp: self.start + chrono::Duration::seconds(offset_secs),
+                source: "scripted".to_string(),
+                confidence: Decimal::ONE,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_twap_matches_hand_computed_average() {
+        let inner = Arc::new(ScriptedFeed {
+            start: Utc::now() - chrono::Duration::minutes(5),
+            quotes: std::sync::Mutex::new(VecDeque::from([(0, 100), (20, 130), (40, 160), (60, 90), (70, 90)])),
+        });
+        let feed = TwapPriceFeed::new(inner, std::time::Duration::from_secs(60));
+        let eth = "ETH".to_string();
+
+        // Spot is served until the history spans the 60s window
+        for expected in [100, 130, 160] {
+            assert_eq!(feed.get_price(&eth).await.unwrap().price_usd, Decimal::from(expected));
+        }
+
+        // Window [0s, 60s]: 100, 130 and 160 held for 20s each
+        let at_60 = feed.get_price(&eth).await.unwrap();
+        assert_eq!(at_60.price_usd, Decimal::from(130));
+        assert_eq!(at_60.source, "twap(scripted, 60s)");
+
+        // Window [10s, 70s]: 100 for 10s, 130 and 160 for 20s each, then 90 for 10s
+        let at_70 = feed.get_price(&eth).await.unwrap();
+        let expected = Decimal::from(100 * 10 + 130 * 20 + 160 * 20 + 90 * 10) / Decimal::from(60);
+        assert!((at_70.price_usd - expected).abs() < Decimal::new(1, 12), "{} != {}", at_70.price_usd, expected);
+
+        // Spot is still there for deviation checks
+        let reference = feed.get_reference_prices(std::slice::from_ref(&eth)).await.unwrap();
+        assert_eq!(reference[&eth].price_usd, Decimal::from(90));
+    }
+}
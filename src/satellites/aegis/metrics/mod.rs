@@ -0,0 +1,3 @@
+pub mod prometheus;
+
+pub use prometheus::*;
@@ -0,0 +1,143 @@
+use crate::monitoring::AegisMetrics;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone)]
+pub struct PrometheusExporterConfig {
+    /// Loopback by default; bind a routable address only behind a firewall or proxy
+    pub bind_address: SocketAddr,
+    /// Path the metrics are served on; every other path returns 404
+    pub path: String,
+}
+
+impl Default for PrometheusExporterConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([127, 0, 0, 1], 9464)),
+            path: "/metrics".to_string(),
+        }
+    }
+}
+
+/// Serves the satellite's metrics to Prometheus scrapers over plain HTTP.
+///
+/// Gauges are read as they were last set, so values reflect the most recent
+/// monitoring pass rather than being recomputed on each scrape.
+pub struct PrometheusExporter {
+    metrics: Arc<AegisMetrics>,
+    config: PrometheusExporterConfig,
+}
+
+/// A running exporter; dropping it leaves the listener running until `shutdown`
+pub struct MetricsServer {
+    local_address: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Address actually bound, useful when the configured port was 0
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Stop accepting scrapes, letting requests in flight finish
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.handle.await;
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new(metrics: Arc<AegisMetrics>, config: PrometheusExporterConfig) -> Self {
+        Self { metrics, config }
+    }
+
+    /// Current metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        self.metrics.render_prometheus()
+    }
+
+    /// Router serving `GET` on the configured path
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route(&self.config.path, get(scrape))
+            .with_state(self.metrics.clone())
+    }
+
+    pub async fn serve(self) -> std::io::Result<MetricsServer> {
+        let listener = TcpListener::bind(self.config.bind_address).await?;
+        let local_address = listener.local_addr()?;
+        info!("Serving Prometheus metrics on http://{}{}", local_address, self.config.path);
+
+        let (shutdown, stopped) = oneshot::channel();
+        let router = self.router();
+        let handle = tokio::spawn(async move {
+            let server = axum::serve(listener, router).with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+            if let Err(e) = server.await {
+                warn!("Metrics server stopped: {}", e);
+            }
+        });
+
+        Ok(MetricsServer { local_address, shutdown, handle })
+    }
+}
+
+async fn scrape(State(metrics): State<Arc<AegisMetrics>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], metrics.render_prometheus())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_expected_metric_names() {
+        let metrics = Arc::new(AegisMetrics::new());
+        metrics.set_total_positions(3);
+        metrics.set_active_alerts(2);
+        metrics.set_simulation_cache_hit_ratio(0.75);
+        metrics.record_health_calculation(Duration::from_millis(7));
+        metrics.record_health_calculation(Duration::from_millis(2_000));
+
+        let exporter = PrometheusExporter::new(metrics, PrometheusExporterConfig {
+            bind_address: SocketAddr::from(([127, 0, 0, 1], 0)),
+            ..PrometheusExporterConfig::default()
+        });
+        let rendered = exporter.render();
+        assert!(rendered.contains("# TYPE aegis_total_positions gauge\naegis_total_positions 3\n"));
+        assert!(rendered.contains("# TYPE aegis_active_alerts gauge\naegis_active_alerts 2\n"));
+        assert!(rendered.contains("aegis_simulation_cache_hit_ratio 0.75\n"));
+        assert!(rendered.contains("# TYPE aegis_health_calculation_duration_ms histogram\n"));
+        assert!(rendered.contains("aegis_health_calculation_duration_ms_bucket{le=\"5\"} 0\n"));
+        assert!(rendered.contains("aegis_health_calculation_duration_ms_bucket{le=\"10\"} 1\n"));
+        assert!(rendered.contains("aegis_health_calculation_duration_ms_bucket{le=\"1000\"} 1\n"));
+        assert!(rendered.contains("aegis_health_calculation_duration_ms_bucket{le=\"+Inf\"} 2\n"));
+
+        let server = exporter.serve().await.unwrap();
+        let base = format!("http://{}", server.local_address());
+        let response = reqwest::get(format!("{}/metrics", base)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), rendered);
+
+        let missing = reqwest::get(format!("{}/health", base)).await.unwrap();
+        assert_eq!(missing.status(), 404);
+        let posted = reqwest::Client::new().post(format!("{}/metrics", base)).send().await.unwrap();
+        assert_eq!(posted.status(), 405);
+
+        server.shutdown().await;
+        assert!(reqwest::get(format!("{}/metrics", base)).await.is_err());
+    }
+}
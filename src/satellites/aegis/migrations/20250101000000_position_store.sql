@@ -0,0 +1,22 @@
+-- Positions and alerts shared by every Aegis instance pointed at the same database.
+-- Rows keep the serialized value in `body`; the extracted columns exist for indexed lookups.
+
+CREATE TABLE IF NOT EXISTS aegis_positions (
+    id UUID PRIMARY KEY,
+    protocol TEXT NOT NULL,
+    body JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS aegis_positions_protocol_idx ON aegis_positions (protocol);
+CREATE INDEX IF NOT EXISTS aegis_positions_created_at_idx ON aegis_positions (created_at);
+
+CREATE TABLE IF NOT EXISTS aegis_alerts (
+    id UUID PRIMARY KEY,
+    position_id UUID NOT NULL,
+    body JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS aegis_alerts_position_id_idx ON aegis_alerts (position_id);
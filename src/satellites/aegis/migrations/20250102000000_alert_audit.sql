@@ -0,0 +1,11 @@
+-- Actions taken on alerts, kept for compliance reporting. Entries are never deleted,
+-- not even with the position their alert was raised on.
+
+CREATE TABLE IF NOT EXISTS aegis_alert_audit (
+    id BIGSERIAL PRIMARY KEY,
+    alert_id UUID NOT NULL,
+    body JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS aegis_alert_audit_alert_id_idx ON aegis_alert_audit (alert_id);
@@ -1,11 +1,15 @@
-use crate::types::{RiskAlert, RiskLevel, PositionId, AlertType};
+use crate::types::{RiskAlert, RiskLevel, PositionId, AlertType, RetryPolicy};
+use crate::liquidation::PositionStore;
+use crate::monitoring::incidents::{Incident, IncidentTracker};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::ops::Bound;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, RwLock, Notify};
+use tokio::sync::{broadcast, mpsc, RwLock, Notify};
 use tokio::time::{interval, Instant};
 use tracing::{info, warn, error, debug};
 use uuid::Uuid;
@@ -17,6 +21,17 @@ pub struct AlertConfiguration {
     pub notification_channels: Vec<NotificationChannel>,
     pub rate_limiting: RateLimitConfig,
     pub acknowledgment_timeout: Duration,
+    pub retry_policy: RetryPolicy,
+    /// Alerts sharing a cause within this window are grouped into one incident; `None`, the default, disables grouping
+    pub incident_window: Option<Duration>,
+    /// De-escalates alerts that stay active without worsening; `None` disables decay
+    pub severity_decay: Option<SeverityDecayPolicy>,
+    /// Promotes alerts left unacknowledged; `None`, the default, keeps their original level
+    pub unacknowledged_escalation: Option<UnacknowledgedEscalationPolicy>,
+    /// Repeats of an unacknowledged alert (same position and type, no higher severity)
+    /// seen within this window of its last occurrence are counted on it instead of
+    /// raised again; `None`, the default, disables deduplication
+    pub duplicate_cooldown: Option<Duration>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +77,68 @@ pub struct RateLimitConfig {
     pub burst_allowance: u32,
 }
 
+/// Lowers the effective level of an active alert by one step for every
+/// `decay_interval` it stays active without worsening, never below `floor`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityDecayPolicy {
+    pub decay_interval: Duration,
+    pub floor: RiskLevel,
+}
+
+impl Default for SeverityDecayPolicy {
+    fn default() -> Self {
+        Self {
+            decay_interval: Duration::from_secs(1800), // 30 minutes
+            floor: RiskLevel::Warning,
+        }
+    }
+}
+
+impl SeverityDecayPolicy {
+    pub fn decayed_level(&self, level: &RiskLevel, stable_for: Duration) -> RiskLevel {
+        if level <= &self.floor || self.decay_interval.is_zero() {
+            return level.clone();
+        }
+
+        const LADDER: [RiskLevel; 4] = [RiskLevel::Safe, RiskLevel::Warning, RiskLevel::Critical, RiskLevel::Emergency];
+        let steps = (stable_for.as_secs_f64() / self.decay_interval.as_secs_f64()) as usize;
+        let current = LADDER.iter().position(|l| l == level).unwrap_or(0);
+        let floor = LADDER.iter().position(|l| l == &self.floor).unwrap_or(0);
+
+        LADDER[current.saturating_sub(steps).max(floor)].clone()
+    }
+}
+
+/// Raises an alert to at least `Critical` once it has gone unacknowledged for
+/// `to_critical`, and to `Emergency` after `to_emergency`, both measured from when it was raised
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnacknowledgedEscalationPolicy {
+    pub to_critical: Duration,
+    pub to_emergency: Duration,
+}
+
+impl Default for UnacknowledgedEscalationPolicy {
+    fn default() -> Self {
+        Self {
+            to_critical: Duration::from_secs(900),   // 15 minutes
+            to_emergency: Duration::from_secs(2700), // 45 minutes
+        }
+    }
+}
+
+impl UnacknowledgedEscalationPolicy {
+    pub fn promoted_level(&self, level: &RiskLevel, unacknowledged_for: Duration) -> RiskLevel {
+        let minimum = if unacknowledged_for >= self.to_emergency {
+            RiskLevel::Emergency
+        } else if unacknowledged_for >= self.to_critical {
+            RiskLevel::Critical
+        } else {
+            return level.clone();
+        };
+        level.clone().max(minimum)
+    }
+}
+
 impl Default for AlertConfiguration {
     fn default() -> Self {
         let mut escalation_rules = HashMap::new();
@@ -111,6 +188,16 @@ impl Default for AlertConfiguration {
                 burst_allowance: 10,
             },
             acknowledgment_timeout: Duration::from_secs(600), // 10 minutes
+            retry_policy: RetryPolicy {
+                max_attempts: 5,
+                base_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(30),
+                jitter_factor: 0.5,
+            },
+            incident_window: None,
+            severity_decay: None,
+            unacknowledged_escalation: None,
+            duplicate_cooldown: None,
         }
     }
 }
@@ -122,44 +209,214 @@ struct AlertState {
     pub last_sent: Instant,
     pub next_escalation: Instant,
     pub acknowledgment_required: bool,
+    pub incident_id: Option<Uuid>,
+    /// Highest level seen for this alert's position while it has been active
+    pub peak_level: RiskLevel,
+    /// Last time conditions worsened; severity decay is measured from here
+    pub stable_since: Instant,
+    pub raised_at: Instant,
+}
+
+impl AlertState {
+    fn effective_level(&self, config: &AlertConfiguration, now: Instant) -> RiskLevel {
+        match &config.severity_decay {
+            Some(policy) => policy.decayed_level(&self.peak_level, now.saturating_duration_since(self.stable_since)),
+            None => self.peak_level.clone(),
+        }
+    }
 }
 
 pub struct EscalatingAlertSystem {
     config: Arc<RwLock<AlertConfiguration>>,
-    active_alerts: DashMap<Uuid, AlertState>,
-    alert_history: DashMap<Uuid, RiskAlert>,
+    active_alerts: Arc<DashMap<Uuid, AlertState>>,
+    alert_history: Arc<DashMap<Uuid, RiskAlert>>,
+    /// `alert_history` in query order, so a page is read without sorting the whole history
+    alert_index: Arc<std::sync::RwLock<AlertTimeIndex>>,
+    /// Unacknowledged alerts in `alert_history` by position and type, for finding the alert
+    /// a repeat folds into
+    duplicate_index: DashMap<(PositionId, AlertType), Vec<Uuid>>,
+    alert_audit: Arc<std::sync::Mutex<AuditTrails>>,
+    /// Where every audit entry, and every alert promoted for going unacknowledged, is
+    /// written as it is recorded. Shared with the escalation worker, which starts before
+    /// `with_audit_store` can set it.
+    audit_store: Arc<std::sync::RwLock<Option<Arc<dyn PositionStore>>>>,
     notification_sender: mpsc::UnboundedSender<AlertNotification>,
     rate_limiter: RateLimiter,
     escalation_notify: Arc<Notify>,
+    dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    incidents: IncidentTracker,
+    sinks: NotificationSinks,
+    /// Every alert recorded in the history, for live subscribers
+    alert_stream: broadcast::Sender<RiskAlert>,
 }
 
+/// Alerts buffered per subscriber before the slowest one starts missing alerts
+const ALERT_STREAM_CAPACITY: usize = 1024;
+
+/// Id of the sink an `EscalatingAlertSystem` is constructed with, for removing or
+/// replacing it at runtime
+pub const DEFAULT_NOTIFICATION_SINK_ID: Uuid = Uuid::nil();
+
+/// Registered sinks with their ids, in registration order
+type NotificationSinks = Arc<RwLock<Vec<(Uuid, Arc<dyn NotificationSink>)>>>;
+
+/// Alerts whose audit trail is kept in memory
+pub const DEFAULT_AUDIT_TRAIL_CAPACITY: usize = 10_000;
+
+/// Append-only log of actions per alert, oldest first, for the most recent `capacity`
+/// alerts
+struct AuditTrails {
+    trails: HashMap<Uuid, Vec<AlertAuditEntry>>,
+    /// Alerts in the order their trails began, for evicting the oldest
+    order: VecDeque<Uuid>,
+    capacity: usize,
+}
+
+impl AuditTrails {
+    fn new(capacity: usize) -> Self {
+        Self { trails: HashMap::new(), order: VecDeque::new(), capacity }
+    }
+
+    fn push(&mut self, entry: AlertAuditEntry) {
+        if !self.trails.contains_key(&entry.alert_id) {
+            self.order.push_back(entry.alert_id);
+        }
+        self.trails.entry(entry.alert_id).or_default().push(entry);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.trails.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlertNotification {
+    pub alert: RiskAlert,
+    pub channel: NotificationChannel,
+    pub escalation_level: u32,
+    pub is_escalation: bool,
+    pub incident_id: Option<Uuid>,
+}
+
+/// Selects a page of recorded alerts. Unset filters match every alert; results are ordered
+/// newest first, then `offset` alerts are skipped and at most `limit` returned.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertQuery {
+    pub position_id: Option<PositionId>,
+    pub risk_level: Option<RiskLevel>,
+    pub alert_type: Option<AlertType>,
+    /// Only alerts created at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only alerts created before this time
+    pub until: Option<DateTime<Utc>>,
+    pub acknowledged: Option<bool>,
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl AlertQuery {
+    pub fn matches(&self, alert: &RiskAlert) -> bool {
+        self.position_id.is_none_or(|position_id| alert.position_id == position_id)
+            && self.risk_level.as_ref().is_none_or(|level| alert.risk_level == *level)
+            && self.alert_type.as_ref().is_none_or(|alert_type| alert.alert_type == *alert_type)
+            && self.since.is_none_or(|since| alert.created_at >= since)
+            && self.until.is_none_or(|until| alert.created_at < until)
+            && self.acknowledged.is_none_or(|acknowledged| alert.acknowledged == acknowledged)
+    }
+}
+
+/// Recorded alerts keyed newest first, ties broken by id
+type AlertTimeIndex = BTreeSet<(Reverse<DateTime<Utc>>, Uuid)>;
+
+/// Actor recorded for actions Aegis takes on its own, such as raising an alert
+pub const SYSTEM_ACTOR: &str = "aegis";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertAuditAction {
+    Raised,
+    Acknowledged,
+}
+
+/// One action taken on an alert, kept for compliance reporting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertAuditEntry {
+    pub alert_id: Uuid,
+    pub action: AlertAuditAction,
+    /// Who took the action, `SYSTEM_ACTOR` for Aegis itself
+    pub actor: String,
+    pub note: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A notification that exhausted its delivery retries
 #[derive(Debug, Clone)]
-struct AlertNotification {
-    alert: RiskAlert,
-    channel: NotificationChannel,
-    escalation_level: u32,
-    is_escalation: bool,
+pub struct DeadLetter {
+    pub notification: AlertNotification,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Destination that alert notifications are delivered to
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    async fn deliver(&self, notification: &AlertNotification) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Default sink dispatching on the notification's configured channel type
+pub struct ChannelNotificationSink;
+
+#[async_trait]
+impl NotificationSink for ChannelNotificationSink {
+    async fn deliver(&self, notification: &AlertNotification) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        EscalatingAlertSystem::send_notification(notification).await
+    }
 }
 
 impl EscalatingAlertSystem {
     pub fn new(config: AlertConfiguration) -> Self {
+        Self::with_sink(config, Arc::new(ChannelNotificationSink))
+    }
+
+    pub fn with_sink(config: AlertConfiguration, sink: Arc<dyn NotificationSink>) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
         let rate_limiter = RateLimiter::new(config.rate_limiting.clone());
         let escalation_notify = Arc::new(Notify::new());
 
         let system = Self {
             config: Arc::new(RwLock::new(config)),
-            active_alerts: DashMap::new(),
-            alert_history: DashMap::new(),
+            active_alerts: Arc::new(DashMap::new()),
+            alert_history: Arc::new(DashMap::new()),
+            alert_index: Arc::new(std::sync::RwLock::new(BTreeSet::new())),
+            duplicate_index: DashMap::new(),
+            alert_audit: Arc::new(std::sync::Mutex::new(AuditTrails::new(DEFAULT_AUDIT_TRAIL_CAPACITY))),
+            audit_store: Arc::new(std::sync::RwLock::new(None)),
             notification_sender: tx,
             rate_limiter,
             escalation_notify: escalation_notify.clone(),
+            dead_letters: Arc::new(RwLock::new(Vec::new())),
+            incidents: IncidentTracker::new(),
+            sinks: Arc::new(RwLock::new(vec![(DEFAULT_NOTIFICATION_SINK_ID, sink)])),
+            alert_stream: broadcast::channel(ALERT_STREAM_CAPACITY).0,
         };
 
         // Start background tasks
-        tokio::spawn(Self::notification_worker(rx));
+        tokio::spawn(Self::notification_worker(
+            rx,
+            system.sinks.clone(),
+            system.config.clone(),
+            system.dead_letters.clone(),
+        ));
         tokio::spawn(Self::escalation_worker(
             system.active_alerts.clone(),
+            system.alert_history.clone(),
+            system.audit_store.clone(),
             system.config.clone(),
             system.notification_sender.clone(),
             escalation_notify,
@@ -169,7 +426,9 @@ impl EscalatingAlertSystem {
     }
 
     async fn escalation_worker(
-        active_alerts: DashMap<Uuid, AlertState>,
+        active_alerts: Arc<DashMap<Uuid, AlertState>>,
+        alert_history: Arc<DashMap<Uuid, RiskAlert>>,
+        audit_store: Arc<std::sync::RwLock<Option<Arc<dyn PositionStore>>>>,
         config: Arc<RwLock<AlertConfiguration>>,
         notification_sender: mpsc::UnboundedSender<AlertNotification>,
         escalation_notify: Arc<Notify>,
@@ -179,6 +438,7 @@ impl EscalatingAlertSystem {
         loop {
             tokio::select! {
                 _ = escalation_interval.tick() => {
+                    Self::promote_unacknowledged(&active_alerts, &alert_history, &audit_store, &config, &notification_sender).await;
                     Self::process_escalations(&active_alerts, &config, &notification_sender).await;
                 }
                 _ = escalation_notify.notified() => {
@@ -189,6 +449,79 @@ impl EscalatingAlertSystem {
         }
     }
 
+    /// Raise the level of alerts that have gone unacknowledged for too long, updating the
+    /// alert in history and in the audit store, and notifying channels enabled for the new level
+    async fn promote_unacknowledged(
+        active_alerts: &DashMap<Uuid, AlertState>,
+        alert_history: &DashMap<Uuid, RiskAlert>,
+        audit_store: &std::sync::RwLock<Option<Arc<dyn PositionStore>>>,
+        config: &Arc<RwLock<AlertConfiguration>>,
+        notification_sender: &mpsc::UnboundedSender<AlertNotification>,
+    ) {
+        let now = Instant::now();
+        let config_guard = config.read().await;
+        let policy = match &config_guard.unacknowledged_escalation {
+            Some(policy) => policy,
+            None => return,
+        };
+
+        let mut promoted = Vec::new();
+        for mut alert_state_ref in active_alerts.iter_mut() {
+            let alert_state = alert_state_ref.value_mut();
+            let new_level = policy.promoted_level(&alert_state.alert.risk_level, now.saturating_duration_since(alert_state.raised_at));
+            if new_level <= alert_state.alert.risk_level {
+                continue;
+            }
+
+            info!("Alert {} unacknowledged for {:?}, promoting from {:?} to {:?}",
+                  alert_state.alert.id, now.saturating_duration_since(alert_state.raised_at),
+                  alert_state.alert.risk_level, new_level);
+            alert_state.alert.risk_level = new_level.clone();
+            alert_state.peak_level = alert_state.peak_level.clone().max(new_level.clone());
+            alert_state.stable_since = now;
+            alert_state.escalation_count = 0;
+            alert_state.next_escalation = match config_guard.escalation_rules.get(&new_level) {
+                Some(rule) => now + rule.initial_delay,
+                None => now,
+            };
+
+            for channel in &config_guard.notification_channels {
+                if channel.enabled_for_levels.contains(&new_level) {
+                    let notification = AlertNotification {
+                        alert: alert_state.alert.clone(),
+                        channel: channel.clone(),
+                        escalation_level: 0,
+                        is_escalation: true,
+                        incident_id: alert_state.incident_id,
+                    };
+                    if let Err(e) = notification_sender.send(notification) {
+                        error!("Failed to send promotion notification: {}", e);
+                    }
+                }
+            }
+            promoted.push((alert_state.alert.id, new_level));
+        }
+
+        // History is updated after the active alerts iteration has released its shards
+        let mut promoted_alerts = Vec::new();
+        for (alert_id, new_level) in promoted {
+            if let Some(mut alert) = alert_history.get_mut(&alert_id) {
+                alert.risk_level = new_level;
+                promoted_alerts.push(alert.clone());
+            }
+        }
+
+        // Stored so a restart doesn't drop the alert back to its original level
+        let store = audit_store.read().ok().and_then(|store| store.clone());
+        if let Some(store) = store {
+            for alert in &promoted_alerts {
+                if let Err(e) = store.save_alert(alert).await {
+                    warn!("Failed to persist promotion of alert {}: {}", alert.id, e);
+                }
+            }
+        }
+    }
+
     async fn process_escalations(
         active_alerts: &DashMap<Uuid, AlertState>,
         config: &Arc<RwLock<AlertConfiguration>>,
@@ -201,18 +534,20 @@ impl EscalatingAlertSystem {
             let alert_state = alert_state_ref.value_mut();
             
             if now >= alert_state.next_escalation {
-                let escalation_rule = config_guard.escalation_rules.get(&alert_state.alert.risk_level);
+                let effective_level = alert_state.effective_level(&config_guard, now);
+                let escalation_rule = config_guard.escalation_rules.get(&effective_level);
                 
                 if let Some(rule) = escalation_rule {
                     if alert_state.escalation_count < rule.max_escalations {
                         // Send escalation
                         for channel in &config_guard.notification_channels {
-                            if channel.enabled_for_levels.contains(&alert_state.alert.risk_level) {
+                            if channel.enabled_for_levels.contains(&effective_level) {
                                 let notification = AlertNotification {
                                     alert: alert_state.alert.clone(),
                                     channel: channel.clone(),
                                     escalation_level: alert_state.escalation_count + 1,
                                     is_escalation: true,
+                                    incident_id: alert_state.incident_id,
                                 };
                                 
                                 if let Err(e) = notification_sender.send(notification) {
@@ -239,13 +574,287 @@ impl EscalatingAlertSystem {
         }
     }
 
-    async fn notification_worker(mut rx: mpsc::UnboundedReceiver<AlertNotification>) {
+    async fn notification_worker(
+        mut rx: mpsc::UnboundedReceiver<AlertNotification>,
+        sinks: NotificationSinks,
+        config: Arc<RwLock<AlertConfiguration>>,
+        dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    ) {
         while let Some(notification) = rx.recv().await {
-            if let Err(e) = Self::send_notification(&notification).await {
-                error!("Failed to send notification for alert {}: {}", 
-                       notification.alert.id, e);
+            let retry_policy = config.read().await.retry_policy.clone();
+            // Snapshot the registered sinks; registration changes apply to later notifications
+            let current_sinks: Vec<Arc<dyn NotificationSink>> = sinks.read().await.iter()
+                .map(|(_, sink)| sink.clone())
+                .collect();
+
+            for sink in current_sinks {
+                let notification = notification.clone();
+                let retry_policy = retry_policy.clone();
+                let dead_letters = dead_letters.clone();
+
+                // Deliver off the queue so one retrying endpoint doesn't hold up the rest
+                tokio::spawn(async move {
+                    Self::deliver_with_retry(sink.as_ref(), notification, &retry_policy, &dead_letters).await;
+                });
+            }
+        }
+    }
+
+    /// Register an additional sink for subsequent notifications, returning its id. A sink
+    /// that is already registered keeps its id and is still delivered each notification once.
+    pub async fn add_notification_sink(&self, sink: Arc<dyn NotificationSink>) -> Uuid {
+        let mut sinks = self.sinks.write().await;
+        if let Some((sink_id, _)) = sinks.iter().find(|(_, registered)| Arc::ptr_eq(registered, &sink)) {
+            return *sink_id;
+        }
+        let sink_id = Uuid::new_v4();
+        sinks.push((sink_id, sink));
+        info!("Registered notification sink {}", sink_id);
+        sink_id
+    }
+
+    /// Stop delivering subsequent notifications to a sink. Deliveries already in
+    /// flight to it run to completion. Returns false if the id is unknown.
+    pub async fn remove_notification_sink(&self, sink_id: Uuid) -> bool {
+        let mut sinks = self.sinks.write().await;
+        let before = sinks.len();
+        sinks.retain(|(id, _)| *id != sink_id);
+        let removed = sinks.len() < before;
+        if removed {
+            info!("Removed notification sink {}", sink_id);
+        }
+        removed
+    }
+
+    pub async fn notification_sink_ids(&self) -> Vec<Uuid> {
+        self.sinks.read().await.iter().map(|(id, _)| *id).collect()
+    }
+
+    async fn deliver_with_retry(
+        sink: &dyn NotificationSink,
+        notification: AlertNotification,
+        retry_policy: &RetryPolicy,
+        dead_letters: &RwLock<Vec<DeadLetter>>,
+    ) {
+        let max_attempts = retry_policy.max_attempts.max(1);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            match sink.deliver(&notification).await {
+                Ok(()) => return,
+                Err(e) if attempt >= max_attempts => {
+                    error!("Giving up on notification for alert {} after {} attempts: {}", 
+                           notification.alert.id, attempt, e);
+                    dead_letters.write().await.push(DeadLetter {
+                        notification,
+                        attempts: attempt,
+                        last_error: e.to_string(),
+                        failed_at: Utc::now(),
+                    });
+                    return;
+                }
+                Err(e) => {
+                    let delay = retry_policy.backoff_delay(attempt, &mut rand::thread_rng());
+                    warn!("Notification for alert {} failed (attempt {}/{}), retrying in {:?}: {}", 
+                          notification.alert.id, attempt, max_attempts, delay, e);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Notifications that could not be delivered within the retry policy
+    pub async fn get_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.read().await.clone()
+    }
+
+    /// Remove and return all dead-lettered notifications, e.g. for replay
+    pub async fn drain_dead_letters(&self) -> Vec<DeadLetter> {
+        std::mem::take(&mut *self.dead_letters.write().await)
+    }
+
+    /// Number of alerts still being tracked for escalation
+    pub fn active_alert_count(&self) -> usize {
+        self.active_alerts.len()
+    }
+
+    /// Receive each new alert as it is recorded. Duplicates folded into an existing alert
+    /// are not repeated, and a subscriber that falls behind skips the oldest alerts.
+    pub fn subscribe(&self) -> broadcast::Receiver<RiskAlert> {
+        self.alert_stream.subscribe()
+    }
+
+    /// Recorded alerts selected by `query`, newest first
+    pub fn query_alerts(&self, query: &AlertQuery) -> Vec<RiskAlert> {
+        if let (Some(since), Some(until)) = (query.since, query.until) {
+            if since >= until {
+                return Vec::new();
+            }
+        }
+        // Only the alerts inside the time window are visited, newest first
+        let last_id = Uuid::from_u128(u128::MAX);
+        let newest = match query.until {
+            Some(until) => Bound::Excluded((Reverse(until), last_id)),
+            None => Bound::Unbounded,
+        };
+        let oldest = match query.since {
+            Some(since) => Bound::Included((Reverse(since), last_id)),
+            None => Bound::Unbounded,
+        };
+
+        let index = match self.alert_index.read() {
+            Ok(index) => index,
+            Err(_) => return Vec::new(),
+        };
+        index.range((newest, oldest))
+            .filter_map(|(_, id)| self.alert_history.get(id).map(|alert| alert.value().clone()))
+            .filter(|alert| query.matches(alert))
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    fn record_history(&self, alert: RiskAlert) {
+        if let Ok(mut index) = self.alert_index.write() {
+            index.insert((Reverse(alert.created_at), alert.id));
+        }
+        if !alert.acknowledged {
+            self.duplicate_index.entry((alert.position_id, alert.alert_type.clone()))
+                .or_default()
+                .push(alert.id);
+        }
+        self.alert_history.insert(alert.id, alert);
+    }
+
+    /// Write every audit entry and alert promotion to `store` as well, so trails and
+    /// promoted levels survive restarts and outlive the in-memory capacity
+    pub fn with_audit_store(self, store: Arc<dyn PositionStore>) -> Self {
+        if let Ok(mut audit_store) = self.audit_store.write() {
+            *audit_store = Some(store);
+        }
+        self
+    }
+
+    /// Keep audit trails in memory for the most recent `capacity` alerts
+    pub fn with_audit_trail_capacity(self, capacity: usize) -> Self {
+        if let Ok(mut trails) = self.alert_audit.lock() {
+            trails.capacity = capacity;
+            trails.evict();
+        }
+        self
+    }
+
+    /// Actions taken on `alert_id` in the order they happened. Only the most recent alerts'
+    /// trails are held in memory; older ones are in the audit store, if one is set.
+    pub fn get_alert_audit(&self, alert_id: Uuid) -> Vec<AlertAuditEntry> {
+        match self.alert_audit.lock() {
+            Ok(trails) => trails.trails.get(&alert_id).cloned().unwrap_or_default(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    async fn record_audit(&self, alert_id: Uuid, action: AlertAuditAction, actor: &str, note: Option<&str>) {
+        let entry = AlertAuditEntry {
+            alert_id,
+            action,
+            actor: actor.to_string(),
+            note: note.map(str::to_string),
+            recorded_at: Utc::now(),
+        };
+        let store = self.audit_store.read().ok().and_then(|store| store.clone());
+        if let Some(store) = store {
+            if let Err(e) = store.append_audit_entry(&entry).await {
+                warn!("Failed to persist audit entry for alert {}: {}", alert_id, e);
+            }
+        }
+        if let Ok(mut trails) = self.alert_audit.lock() {
+            trails.push(entry);
+        }
+    }
+
+    /// Load previously persisted audit entries, oldest first, without writing them back
+    pub fn restore_alert_audit(&self, entries: Vec<AlertAuditEntry>) {
+        if let Ok(mut trails) = self.alert_audit.lock() {
+            for entry in entries {
+                trails.push(entry);
+            }
+        }
+    }
+
+    /// Load previously persisted alerts into the history without notifying or escalating them
+    pub fn restore_alert_history(&self, alerts: Vec<RiskAlert>) {
+        for alert in alerts {
+            self.record_history(alert);
+        }
+    }
+
+    /// Current notification level of an active alert, after any severity decay
+    pub async fn effective_risk_level(&self, alert_id: Uuid) -> Option<RiskLevel> {
+        let config = self.config.read().await;
+        self.active_alerts.get(&alert_id)
+            .map(|state| state.effective_level(&config, Instant::now()))
+    }
+
+    /// Reset decay on active alerts for the same position when a new alert shows
+    /// conditions getting worse, so they re-escalate from their peak level
+    fn reescalate_if_worsened(&self, alert: &RiskAlert) -> bool {
+        let now = Instant::now();
+        let mut worsened = false;
+
+        for mut state in self.active_alerts.iter_mut() {
+            if state.alert.position_id != alert.position_id {
+                continue;
+            }
+
+            if alert.risk_level > state.peak_level || alert.health_factor.value < state.alert.health_factor.value {
+                if alert.risk_level > state.peak_level {
+                    state.peak_level = alert.risk_level.clone();
+                }
+                state.alert.health_factor = alert.health_factor.clone();
+                state.stable_since = now;
+                state.next_escalation = now;
+                worsened = true;
             }
         }
+
+        worsened
+    }
+
+    /// Count `alert` against an unacknowledged alert it repeats, returning the id of the
+    /// alert it was folded into
+    fn record_duplicate(&self, alert: &RiskAlert, cooldown: Duration) -> Option<Uuid> {
+        let cooldown = chrono::Duration::from_std(cooldown).unwrap_or(chrono::Duration::MAX);
+        let mut candidates = self.duplicate_index.get_mut(&(alert.position_id, alert.alert_type.clone()))?;
+        // Acknowledged alerts never take repeats again
+        candidates.retain(|id| self.alert_history.get(id).is_some_and(|existing| !existing.acknowledged));
+        let existing_id = candidates.iter()
+            .filter_map(|id| self.alert_history.get(id))
+            .filter(|existing| alert.risk_level <= existing.risk_level && alert.created_at - existing.last_seen <= cooldown)
+            .max_by_key(|existing| existing.last_seen)
+            .map(|existing| existing.id)?;
+        drop(candidates);
+
+        let mut existing = self.alert_history.get_mut(&existing_id)?;
+
+        existing.occurrence_count += 1;
+        existing.last_seen = alert.created_at;
+        let (alert_id, occurrence_count, last_seen) = (existing.id, existing.occurrence_count, existing.last_seen);
+        drop(existing);
+
+        if let Some(mut state) = self.active_alerts.get_mut(&alert_id) {
+            state.alert.occurrence_count = occurrence_count;
+            state.alert.last_seen = last_seen;
+        }
+        Some(alert_id)
+    }
+
+    pub async fn get_incidents(&self) -> Vec<Incident> {
+        self.incidents.get_incidents().await
+    }
+
+    pub async fn get_incident(&self, incident_id: Uuid) -> Option<Incident> {
+        self.incidents.get_incident(incident_id).await
     }
 
     async fn send_notification(notification: &AlertNotification) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -295,6 +904,10 @@ impl EscalatingAlertSystem {
                 notification.alert.position_id,
                 notification.alert.message);
 
+        if let Some(incident_id) = notification.incident_id {
+            println!("   part of incident {}", incident_id);
+        }
+
         if notification.alert.risk_level == RiskLevel::Emergency {
             println!("🚨🚨🚨 IMMEDIATE ACTION REQUIRED 🚨🚨🚨");
         }
@@ -351,8 +964,33 @@ impl crate::liquidation::AlertSystem for EscalatingAlertSystem {
         let config = self.config.read().await;
         let escalation_rule = config.escalation_rules.get(&alert.risk_level);
 
+        if self.reescalate_if_worsened(&alert) {
+            info!("Conditions worsened for position {}, re-escalating active alerts", alert.position_id);
+            self.escalation_notify.notify_one();
+        }
+
+        if let Some(cooldown) = config.duplicate_cooldown {
+            if let Some(existing_id) = self.record_duplicate(&alert, cooldown) {
+                debug!("Alert {} repeats unacknowledged alert {}", alert.id, existing_id);
+                return Ok(());
+            }
+        }
+
         // Store in history
-        self.alert_history.insert(alert.id, alert.clone());
+        self.record_history(alert.clone());
+        self.record_audit(alert.id, AlertAuditAction::Raised, SYSTEM_ACTOR, None).await;
+        // No subscribers is the common case and not an error
+        let _ = self.alert_stream.send(alert.clone());
+
+        // Group into an incident; follow-up alerts that don't raise the incident's
+        // severity are not notified on their own, but still escalate if left unacknowledged
+        let (incident_id, notify) = match config.incident_window {
+            Some(window) => {
+                let assignment = self.incidents.assign(&alert, window).await;
+                (Some(assignment.incident_id()), assignment.should_notify())
+            }
+            None => (None, true),
+        };
 
         // Create alert state for escalation tracking
         if let Some(rule) = escalation_rule {
@@ -363,10 +1001,21 @@ impl crate::liquidation::AlertSystem for EscalatingAlertSystem {
                 last_sent: now,
                 next_escalation: now + rule.initial_delay,
                 acknowledgment_required: rule.required_acknowledgment,
+                incident_id,
+                peak_level: alert.risk_level.clone(),
+                stable_since: now,
+                raised_at: now,
             };
             self.active_alerts.insert(alert.id, alert_state);
         }
 
+        if !notify {
+            if let Some(incident_id) = incident_id {
+                debug!("Alert {} grouped into incident {}", alert.id, incident_id);
+            }
+            return Ok(());
+        }
+
         // Send initial notifications
         for channel in &config.notification_channels {
             if channel.enabled_for_levels.contains(&alert.risk_level) {
@@ -375,6 +1024,7 @@ impl crate::liquidation::AlertSystem for EscalatingAlertSystem {
                     channel: channel.clone(),
                     escalation_level: 0,
                     is_escalation: false,
+                    incident_id,
                 };
                 
                 if let Err(e) = self.notification_sender.send(notification) {
@@ -407,17 +1057,21 @@ impl crate::liquidation::AlertSystem for EscalatingAlertSystem {
         Ok(alerts)
     }
 
-    async fn acknowledge_alert(&self, alert_id: Uuid) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Some(mut alert_state) = self.active_alerts.get_mut(&alert_id) {
-            if let Some(mut alert) = self.alert_history.get_mut(&alert_id) {
-                alert.acknowledged = true;
-                info!("Alert {} acknowledged", alert_id);
-            }
-            
-            // Remove from active alerts to stop escalation
-            drop(alert_state);
-            self.active_alerts.remove(&alert_id);
-            
+    async fn acknowledge_alert_with_note(
+        &self,
+        alert_id: Uuid,
+        acknowledged_by: &str,
+        note: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(mut alert) = self.alert_history.get_mut(&alert_id) {
+            alert.acknowledged = true;
+            drop(alert);
+            self.record_audit(alert_id, AlertAuditAction::Acknowledged, acknowledged_by, note).await;
+            info!("Alert {} acknowledged by {}", alert_id, acknowledged_by);
+        }
+
+        // Remove from active alerts to stop escalation
+        if self.active_alerts.remove(&alert_id).is_some() {
             info!("Alert {} removed from active escalation", alert_id);
         }
 
@@ -484,4 +1138,572 @@ impl ToString for RiskLevel {
             RiskLevel::Emergency => "emergency".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::HealthFactor;
+    use rand::SeedableRng;
+    use rust_decimal::Decimal;
+    use std::sync::Mutex;
+
+    /// Sink that fails a fixed number of times before succeeding, recording each attempt.
+    struct FlappingSink {
+        failures_remaining: Mutex<u32>,
+        attempts: Mutex<Vec<Instant>>,
+    }
+
+    impl FlappingSink {
+        fn new(failures: u32) -> Self {
+            Self {
+                failures_remaining: Mutex::new(failures),
+                attempts: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl NotificationSink for FlappingSink {
+        async fn deliver(&self, _notification: &AlertNotification) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.attempts.lock().unwrap().push(Instant::now());
+            let mut remaining = self.failures_remaining.lock().unwrap();
+            if *remaining > 0 {
+                *remaining -= 1;
+                return Err("endpoint unavailable".into());
+            }
+            Ok(())
+        }
+    }
+
+    fn test_notification() -> AlertNotification {
+        let now = Utc::now();
+        AlertNotification {
+            alert: RiskAlert {
+                id: Uuid::new_v4(),
+                position_id: Uuid::new_v4(),
+                alert_type: AlertType::LiquidationRisk,
+                risk_level: RiskLevel::Critical,
+                health_factor: HealthFactor {
+                    value: Decimal::ONE,
+                    liquidation_threshold: Decimal::ONE,
+                    collateral_value: Decimal::ZERO,
+                    debt_value: Decimal::ZERO,
+                    calculated_at: now,
+                },
+                message: "test".to_string(),
+                created_at: now,
+                occurrence_count: 1,
+                last_seen: now,
+                acknowledged: false,
+                protocol: None,
+                related_tokens: vec![],
+                projected_seconds_to_liquidation: None,
+            },
+            channel: AlertConfiguration::default().notification_channels[0].clone(),
+            escalation_level: 0,
+            is_escalation: false,
+            incident_id: None,
+        }
+    }
+
+    fn fast_retry_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(20),
+            max_delay: Duration::from_secs(1),
+            jitter_factor: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay_is_jittered_within_bounds() {
+        let policy = fast_retry_policy(5);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        // Third attempt backs off 20ms * 2^2 = 80ms, jittered down by at most half
+        let delays: Vec<Duration> = (0..20).map(|_| policy.backoff_delay(3, &mut rng)).collect();
+        for delay in &delays {
+            assert!(*delay >= Duration::from_millis(40) && *delay <= Duration::from_millis(80));
+        }
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[tokio::test]
+    async fn test_flapping_sink_retries_with_backoff() {
+        let sink = FlappingSink::new(2);
+        let dead_letters = RwLock::new(Vec::new());
+
+        EscalatingAlertSystem::deliver_with_retry(&sink, test_notification(), &fast_retry_policy(5), &dead_letters).await;
+
+        let attempts = sink.attempts.lock().unwrap().clone();
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts[1] - attempts[0] >= Duration::from_millis(10));
+        assert!(attempts[2] - attempts[1] >= Duration::from_millis(20));
+        assert!(dead_letters.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_land_in_dead_letter_queue() {
+        let sink = FlappingSink::new(u32::MAX);
+        let dead_letters = RwLock::new(Vec::new());
+        let notification = test_notification();
+
+        EscalatingAlertSystem::deliver_with_retry(&sink, notification.clone(), &fast_retry_policy(3), &dead_letters).await;
+
+        let dead_letters = dead_letters.read().await;
+        assert_eq!(sink.attempts.lock().unwrap().len(), 3);
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].attempts, 3);
+        assert_eq!(dead_letters[0].notification.alert.id, notification.alert.id);
+        assert_eq!(dead_letters[0].last_error, "endpoint unavailable");
+    }
+
+    fn position_alert(position_id: PositionId, risk_level: RiskLevel, health_percent: i64) -> RiskAlert {
+        let now = Utc::now();
+        RiskAlert {
+            id: Uuid::new_v4(),
+            position_id,
+            alert_type: AlertType::LiquidationRisk,
+            risk_level,
+            health_factor: HealthFactor {
+                value: Decimal::new(health_percent, 2),
+                liquidation_threshold: Decimal::ONE,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: now,
+            },
+            message: "test".to_string(),
+            created_at: now,
+            occurrence_count: 1,
+            last_seen: now,
+            acknowledged: false,
+            protocol: None,
+            related_tokens: vec![],
+            projected_seconds_to_liquidation: None,
+        }
+    }
+
+    #[test]
+    fn test_decayed_level_steps_down_to_floor() {
+        let policy = SeverityDecayPolicy {
+            decay_interval: Duration::from_secs(60),
+            floor: RiskLevel::Warning,
+        };
+
+        assert_eq!(policy.decayed_level(&RiskLevel::Emergency, Duration::from_secs(59)), RiskLevel::Emergency);
+        assert_eq!(policy.decayed_level(&RiskLevel::Emergency, Duration::from_secs(60)), RiskLevel::Critical);
+        assert_eq!(policy.decayed_level(&RiskLevel::Emergency, Duration::from_secs(150)), RiskLevel::Warning);
+        assert_eq!(policy.decayed_level(&RiskLevel::Emergency, Duration::from_secs(3600)), RiskLevel::Warning);
+        assert_eq!(policy.decayed_level(&RiskLevel::Warning, Duration::from_secs(3600)), RiskLevel::Warning);
+    }
+
+    #[tokio::test]
+    async fn test_stable_alert_decays_and_reescalates_on_worsening() {
+        use crate::liquidation::AlertSystem;
+
+        let config = AlertConfiguration {
+            severity_decay: Some(SeverityDecayPolicy {
+                decay_interval: Duration::from_millis(50),
+                floor: RiskLevel::Warning,
+            }),
+            ..AlertConfiguration::default()
+        };
+        let system = EscalatingAlertSystem::new(config);
+        let position_id = Uuid::new_v4();
+
+        let alert = position_alert(position_id, RiskLevel::Emergency, 105);
+        system.send_alert(alert.clone()).await.unwrap();
+        assert_eq!(system.effective_risk_level(alert.id).await, Some(RiskLevel::Emergency));
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(system.effective_risk_level(alert.id).await, Some(RiskLevel::Warning));
+
+        // The stored record keeps its original severity
+        let history = system.get_alerts(Some(position_id)).await.unwrap();
+        assert_eq!(history[0].risk_level, RiskLevel::Emergency);
+
+        // A lower health factor for the same position re-escalates to the peak level
+        system.send_alert(position_alert(position_id, RiskLevel::Critical, 102)).await.unwrap();
+        assert_eq!(system.effective_risk_level(alert.id).await, Some(RiskLevel::Emergency));
+    }
+
+    /// Sink recording the ids of the alerts it was handed.
+    #[derive(Default)]
+    struct RecordingSink {
+        delivered: Mutex<Vec<Uuid>>,
+    }
+
+    impl RecordingSink {
+        fn delivered(&self) -> Vec<Uuid> {
+            self.delivered.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl NotificationSink for RecordingSink {
+        async fn deliver(&self, notification: &AlertNotification) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.delivered.lock().unwrap().push(notification.alert.id);
+            Ok(())
+        }
+    }
+
+    async fn wait_for_delivery(sink: &RecordingSink, alert_id: Uuid) {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while !sink.delivered().contains(&alert_id) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("notification was not delivered");
+    }
+
+    #[tokio::test]
+    async fn test_sinks_can_be_added_and_removed_at_runtime() {
+        use crate::liquidation::AlertSystem;
+
+        let base = Arc::new(RecordingSink::default());
+        let system = EscalatingAlertSystem::with_sink(AlertConfiguration::default(), base.clone());
+
+        let added = Arc::new(RecordingSink::default());
+        let sink_id = system.add_notification_sink(added.clone()).await;
+        assert_eq!(system.notification_sink_ids().await.len(), 2);
+
+        let first = position_alert(Uuid::new_v4(), RiskLevel::Critical, 110);
+        system.send_alert(first.clone()).await.unwrap();
+        wait_for_delivery(&added, first.id).await;
+        wait_for_delivery(&base, first.id).await;
+
+        assert!(system.remove_notification_sink(sink_id).await);
+        assert!(!system.remove_notification_sink(sink_id).await);
+
+        let second = position_alert(Uuid::new_v4(), RiskLevel::Critical, 110);
+        system.send_alert(second.clone()).await.unwrap();
+        wait_for_delivery(&base, second.id).await;
+        assert!(!added.delivered().contains(&second.id));
+    }
+
+    #[tokio::test]
+    async fn test_registering_a_sink_twice_keeps_one_registration() {
+        let base = Arc::new(RecordingSink::default());
+        let system = EscalatingAlertSystem::with_sink(AlertConfiguration::default(), base.clone());
+        assert_eq!(system.notification_sink_ids().await, vec![DEFAULT_NOTIFICATION_SINK_ID]);
+
+        assert_eq!(system.add_notification_sink(base.clone()).await, DEFAULT_NOTIFICATION_SINK_ID);
+        let added = Arc::new(RecordingSink::default());
+        let added_id = system.add_notification_sink(added.clone()).await;
+        assert_eq!(system.add_notification_sink(added).await, added_id);
+        assert_eq!(system.notification_sink_ids().await, vec![DEFAULT_NOTIFICATION_SINK_ID, added_id]);
+
+        // The default sink can be swapped out like any other
+        assert!(system.remove_notification_sink(DEFAULT_NOTIFICATION_SINK_ID).await);
+        assert_eq!(system.notification_sink_ids().await, vec![added_id]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_sink_registration_during_delivery() {
+        use crate::liquidation::AlertSystem;
+
+        let base = Arc::new(RecordingSink::default());
+        let system = Arc::new(EscalatingAlertSystem::with_sink(AlertConfiguration::default(), base.clone()));
+
+        let churn = {
+            let system = system.clone();
+            tokio::spawn(async move {
+                for _ in 0..50 {
+                    let sink_id = system.add_notification_sink(Arc::new(RecordingSink::default())).await;
+                    tokio::task::yield_now().await;
+                    system.remove_notification_sink(sink_id).await;
+                }
+            })
+        };
+
+        let mut alert_ids = Vec::new();
+        for _ in 0..20 {
+            let alert = position_alert(Uuid::new_v4(), RiskLevel::Critical, 110);
+            alert_ids.push(alert.id);
+            system.send_alert(alert).await.unwrap();
+        }
+        churn.await.unwrap();
+
+        for alert_id in alert_ids {
+            wait_for_delivery(&base, alert_id).await;
+        }
+        assert_eq!(system.notification_sink_ids().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_alert_is_counted_instead_of_raised() {
+        use crate::liquidation::AlertSystem;
+
+        let system = EscalatingAlertSystem::new(AlertConfiguration {
+            duplicate_cooldown: Some(Duration::from_secs(300)),
+            ..AlertConfiguration::default()
+        });
+        let position_id = Uuid::new_v4();
+
+        for _ in 0..5 {
+            system.send_alert(position_alert(position_id, RiskLevel::Critical, 108)).await.unwrap();
+        }
+        let alerts = system.get_alerts(Some(position_id)).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].occurrence_count, 5);
+        assert!(alerts[0].last_seen > alerts[0].created_at);
+
+        // A more severe alert and an alert for another position are raised on their own
+        system.send_alert(position_alert(position_id, RiskLevel::Emergency, 104)).await.unwrap();
+        system.send_alert(position_alert(Uuid::new_v4(), RiskLevel::Critical, 108)).await.unwrap();
+        assert_eq!(system.get_alerts(Some(position_id)).await.unwrap().len(), 2);
+
+        // Once acknowledged, the next occurrence starts a fresh alert
+        for alert in system.get_alerts(Some(position_id)).await.unwrap() {
+            system.acknowledge_alert(alert.id).await.unwrap();
+        }
+        system.send_alert(position_alert(position_id, RiskLevel::Critical, 108)).await.unwrap();
+        let alerts = system.get_alerts(Some(position_id)).await.unwrap();
+        assert_eq!(alerts.len(), 3);
+        assert_eq!(alerts.iter().filter(|alert| !alert.acknowledged).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_alerts_are_raised_without_cooldown() {
+        use crate::liquidation::AlertSystem;
+
+        // Deduplication is off by default, so repeated critical alerts all reach the operator
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default());
+        let position_id = Uuid::new_v4();
+
+        for _ in 0..3 {
+            system.send_alert(position_alert(position_id, RiskLevel::Critical, 108)).await.unwrap();
+        }
+        let alerts = system.get_alerts(Some(position_id)).await.unwrap();
+        assert_eq!(alerts.len(), 3);
+        assert!(alerts.iter().all(|alert| alert.occurrence_count == 1));
+    }
+
+    #[tokio::test]
+    async fn test_alert_grouped_into_incident_still_escalates() {
+        use crate::liquidation::AlertSystem;
+
+        let system = EscalatingAlertSystem::new(AlertConfiguration {
+            incident_window: Some(Duration::from_secs(600)),
+            ..AlertConfiguration::default()
+        });
+        let mut first = position_alert(Uuid::new_v4(), RiskLevel::Critical, 108);
+        first.related_tokens = vec!["ETH".to_string()];
+        let mut grouped = position_alert(Uuid::new_v4(), RiskLevel::Critical, 107);
+        grouped.related_tokens = vec!["ETH".to_string()];
+
+        system.send_alert(first.clone()).await.unwrap();
+        system.send_alert(grouped.clone()).await.unwrap();
+
+        let incidents = system.get_incidents().await;
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].alert_ids, vec![first.id, grouped.id]);
+        // Not notified on its own, but tracked for escalation like the first
+        assert!(system.active_alerts.contains_key(&grouped.id));
+        assert_eq!(system.active_alerts.get(&grouped.id).unwrap().incident_id, Some(incidents[0].id));
+    }
+
+    #[tokio::test]
+    async fn test_alerts_are_not_grouped_by_default() {
+        use crate::liquidation::AlertSystem;
+
+        // Without an incident window every position's first alert is notified on its own
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default());
+        let mut first = position_alert(Uuid::new_v4(), RiskLevel::Critical, 108);
+        first.related_tokens = vec!["ETH".to_string()];
+        let mut second = position_alert(Uuid::new_v4(), RiskLevel::Critical, 107);
+        second.related_tokens = vec!["ETH".to_string()];
+
+        system.send_alert(first.clone()).await.unwrap();
+        system.send_alert(second.clone()).await.unwrap();
+
+        assert!(system.get_incidents().await.is_empty());
+        assert_eq!(system.active_alerts.get(&second.id).unwrap().incident_id, None);
+    }
+
+    #[test]
+    fn test_stored_alert_without_last_seen_was_last_seen_when_raised() {
+        let mut alert = position_alert(Uuid::new_v4(), RiskLevel::Warning, 125);
+        alert.created_at -= chrono::Duration::days(3);
+        let mut stored = serde_json::to_value(&alert).unwrap();
+        let fields = stored.as_object_mut().unwrap();
+        fields.remove("last_seen");
+        fields.remove("occurrence_count");
+
+        let restored: RiskAlert = serde_json::from_value(stored).unwrap();
+        assert_eq!(restored.last_seen, alert.created_at);
+        assert_eq!(restored.occurrence_count, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_unacknowledged_warning_is_promoted_over_time() {
+        use crate::liquidation::AlertSystem;
+
+        let config = AlertConfiguration {
+            unacknowledged_escalation: Some(UnacknowledgedEscalationPolicy {
+                to_critical: Duration::from_secs(600),
+                to_emergency: Duration::from_secs(1800),
+            }),
+            ..AlertConfiguration::default()
+        };
+        // The store keeps alert logs next to its file
+        let dir = std::env::temp_dir().join(format!("aegis-promotions-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let store = Arc::new(crate::liquidation::JsonFilePositionStore::open(dir.join("positions.json")).await.unwrap());
+        let system = EscalatingAlertSystem::new(config).with_audit_store(store.clone());
+
+        let alert = position_alert(Uuid::new_v4(), RiskLevel::Warning, 125);
+        system.send_alert(alert.clone()).await.unwrap();
+        let acknowledged = position_alert(Uuid::new_v4(), RiskLevel::Warning, 125);
+        system.send_alert(acknowledged.clone()).await.unwrap();
+        system.acknowledge_alert(acknowledged.id).await.unwrap();
+
+        let level = |system: &EscalatingAlertSystem, alert_id: Uuid| {
+            system.alert_history.get(&alert_id).map(|alert| alert.risk_level.clone())
+        };
+
+        tokio::time::sleep(Duration::from_secs(599)).await;
+        assert_eq!(level(&system, alert.id), Some(RiskLevel::Warning));
+
+        // The worker checks every 30s, so promotion lands within one tick of the threshold
+        tokio::time::sleep(Duration::from_secs(31)).await;
+        assert_eq!(level(&system, alert.id), Some(RiskLevel::Critical));
+        assert_eq!(system.effective_risk_level(alert.id).await, Some(RiskLevel::Critical));
+
+        tokio::time::sleep(Duration::from_secs(1200)).await;
+        assert_eq!(level(&system, alert.id), Some(RiskLevel::Emergency));
+        assert_eq!(level(&system, acknowledged.id), Some(RiskLevel::Warning));
+
+        // The promotion is in the store for the next start
+        let stored = store.load_alerts().await.unwrap();
+        assert_eq!(stored.iter().find(|stored| stored.id == alert.id).map(|stored| stored.risk_level.clone()), Some(RiskLevel::Emergency));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unacknowledged_escalation_is_opt_in() {
+        assert!(AlertConfiguration::default().unacknowledged_escalation.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_alert_query_filters_and_pages_newest_first() {
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default());
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let position_id = Uuid::new_v4();
+        // Minute i: every third alert is Critical, odd minutes are exposure alerts, the first two are acknowledged
+        let alerts: Vec<RiskAlert> = (0..6)
+            .map(|i| {
+                let level = if i % 3 == 0 { RiskLevel::Critical } else { RiskLevel::Warning };
+                let mut alert = position_alert(if i < 4 { position_id } else { Uuid::new_v4() }, level, 120);
+                alert.created_at = start + chrono::Duration::minutes(i);
+                if i % 2 == 1 {
+                    alert.alert_type = AlertType::ProtocolExposureExceeded;
+                }
+                alert.acknowledged = i < 2;
+                alert
+            })
+            .collect();
+        system.restore_alert_history(alerts.clone());
+        let minutes = |query: AlertQuery| -> Vec<i64> {
+            system.query_alerts(&query).iter()
+                .map(|alert| (alert.created_at - start).num_minutes())
+                .collect()
+        };
+
+        assert_eq!(minutes(AlertQuery::default()), vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(minutes(AlertQuery { position_id: Some(position_id), ..Default::default() }), vec![3, 2, 1, 0]);
+        assert_eq!(minutes(AlertQuery { risk_level: Some(RiskLevel::Critical), ..Default::default() }), vec![3, 0]);
+        assert_eq!(
+            minutes(AlertQuery { alert_type: Some(AlertType::ProtocolExposureExceeded), ..Default::default() }),
+            vec![5, 3, 1],
+        );
+        assert_eq!(minutes(AlertQuery { acknowledged: Some(true), ..Default::default() }), vec![1, 0]);
+        assert_eq!(minutes(AlertQuery { acknowledged: Some(false), ..Default::default() }), vec![5, 4, 3, 2]);
+        // `since` is inclusive and `until` exclusive
+        assert_eq!(
+            minutes(AlertQuery {
+                since: Some(alerts[2].created_at),
+                until: Some(alerts[4].created_at),
+                ..Default::default()
+            }),
+            vec![3, 2],
+        );
+        assert!(minutes(AlertQuery { since: Some(start), until: Some(start), ..Default::default() }).is_empty());
+
+        // Pages follow the newest-first order; pages past the end are empty
+        let page = |offset: usize, limit: Option<usize>| minutes(AlertQuery { offset, limit, ..Default::default() });
+        assert_eq!(page(0, Some(4)), vec![5, 4, 3, 2]);
+        assert_eq!(page(4, Some(4)), vec![1, 0]);
+        assert_eq!(page(5, None), vec![0]);
+        assert_eq!(page(6, Some(4)), Vec::<i64>::new());
+        assert_eq!(page(100, None), Vec::<i64>::new());
+        assert_eq!(page(0, Some(0)), Vec::<i64>::new());
+
+        // Filters apply before paging
+        assert_eq!(
+            minutes(AlertQuery { acknowledged: Some(false), offset: 1, limit: Some(2), ..Default::default() }),
+            vec![4, 3],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acknowledgement_note_is_recorded_in_audit_trail() {
+        use crate::liquidation::AlertSystem;
+
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default());
+        let alert = position_alert(Uuid::new_v4(), RiskLevel::Critical, 104);
+        system.send_alert(alert.clone()).await.unwrap();
+        system.acknowledge_alert_with_note(alert.id, "risk-desk", Some("collateral top-up already in flight")).await.unwrap();
+
+        let audit = system.get_alert_audit(alert.id);
+        let actions: Vec<(AlertAuditAction, &str)> = audit.iter()
+            .map(|entry| (entry.action.clone(), entry.actor.as_str()))
+            .collect();
+        assert_eq!(actions, vec![
+            (AlertAuditAction::Raised, SYSTEM_ACTOR),
+            (AlertAuditAction::Acknowledged, "risk-desk"),
+        ]);
+        assert_eq!(audit[1].note.as_deref(), Some("collateral top-up already in flight"));
+        assert!(audit[0].recorded_at <= audit[1].recorded_at);
+        assert!(audit.iter().all(|entry| entry.alert_id == alert.id));
+
+        // Unknown alerts have no trail and acknowledging them records nothing
+        let unknown = Uuid::new_v4();
+        system.acknowledge_alert_with_note(unknown, "risk-desk", None).await.unwrap();
+        assert!(system.get_alert_audit(unknown).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_acknowledgement_without_actor_is_unattributed() {
+        use crate::liquidation::{AlertSystem, UNATTRIBUTED_ACTOR};
+
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default());
+        let alert = position_alert(Uuid::new_v4(), RiskLevel::Critical, 104);
+        system.send_alert(alert.clone()).await.unwrap();
+        system.acknowledge_alert(alert.id).await.unwrap();
+
+        let audit = system.get_alert_audit(alert.id);
+        assert_eq!(audit[1].action, AlertAuditAction::Acknowledged);
+        assert_eq!((audit[1].actor.as_str(), audit[1].note.as_deref()), (UNATTRIBUTED_ACTOR, None));
+        assert!(system.get_alerts(None).await.unwrap()[0].acknowledged);
+    }
+
+    #[tokio::test]
+    async fn test_audit_trails_beyond_capacity_drop_oldest_first() {
+        use crate::liquidation::AlertSystem;
+
+        let system = EscalatingAlertSystem::new(AlertConfiguration::default()).with_audit_trail_capacity(2);
+        let alerts: Vec<RiskAlert> = (0..3).map(|_| position_alert(Uuid::new_v4(), RiskLevel::Warning, 125)).collect();
+        for alert in &alerts {
+            system.send_alert(alert.clone()).await.unwrap();
+        }
+        // Acknowledging adds to an existing trail without starting a new one
+        system.acknowledge_alert_with_note(alerts[1].id, "risk-desk", None).await.unwrap();
+
+        assert!(system.get_alert_audit(alerts[0].id).is_empty());
+        assert_eq!(system.get_alert_audit(alerts[1].id).len(), 2);
+        assert_eq!(system.get_alert_audit(alerts[2].id).len(), 1);
+    }
+}
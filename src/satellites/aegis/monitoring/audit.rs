@@ -0,0 +1,190 @@
+use crate::types::{Position, PositionId, RiskAlert};
+use crate::liquidation::HealthRecord;
+use crate::risk::AutomatedActionExecution;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::ops::Range;
+use uuid::Uuid;
+
+/// Secret key sealing exported documents, so an edited document can't be resealed by
+/// anyone without it
+#[derive(Clone)]
+pub struct IntegrityKey(Vec<u8>);
+
+impl IntegrityKey {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self(key.into())
+    }
+
+    /// A random 256-bit key. Documents sealed with it only verify while it is kept.
+    pub fn generate() -> Self {
+        use rand::RngCore;
+
+        let mut key = vec![0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+}
+
+impl fmt::Debug for IntegrityKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IntegrityKey(..)")
+    }
+}
+
+/// Links an automated action to the records that justified it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditDecision {
+    pub action_id: Uuid,
+    pub position_id: PositionId,
+    pub alert_ids: Vec<Uuid>,
+    pub health_record_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditBundle {
+    pub id: Uuid,
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+    pub positions: Vec<Position>,
+    pub health_records: Vec<HealthRecord>,
+    pub alerts: Vec<RiskAlert>,
+    pub actions: Vec<AutomatedActionExecution>,
+    pub decisions: Vec<AuditDecision>,
+    pub generated_at: DateTime<Utc>,
+    /// Hex HMAC-SHA256 over the canonical JSON of every other field, keyed with the
+    /// exporting satellite's `IntegrityKey`
+    pub integrity_hash: String,
+}
+
+impl AuditBundle {
+    pub fn new(
+        range: Range<DateTime<Utc>>,
+        positions: Vec<Position>,
+        mut health_records: Vec<HealthRecord>,
+        mut alerts: Vec<RiskAlert>,
+        mut actions: Vec<AutomatedActionExecution>,
+        key: &IntegrityKey,
+    ) -> Self {
+        health_records.retain(|record| range.contains(&record.recorded_at));
+        alerts.retain(|alert| range.contains(&alert.created_at));
+        actions.retain(|action| range.contains(&action.executed_at));
+
+        health_records.sort_by_key(|record| record.recorded_at);
+        alerts.sort_by_key(|alert| alert.created_at);
+        actions.sort_by_key(|action| action.executed_at);
+
+        // Only keep positions that some record in the range refers to
+        let mut positions: Vec<Position> = positions.into_iter()
+            .filter(|position| {
+                health_records.iter().any(|r| r.position_id == position.id)
+                    || alerts.iter().any(|a| a.position_id == position.id)
+                    || actions.iter().any(|a| a.position_id == position.id)
+            })
+            .collect();
+        positions.sort_by_key(|position| position.id);
+
+        let decisions = actions.iter()
+            .map(|action| AuditDecision {
+                action_id: action.id,
+                position_id: action.position_id,
+                alert_ids: alerts.iter()
+                    .filter(|alert| alert.position_id == action.position_id && alert.created_at <= action.executed_at)
+                    .map(|alert| alert.id)
+                    .collect(),
+                health_record_id: health_records.iter()
+                    .rev()
+                    .find(|record| record.position_id == action.position_id && record.recorded_at <= action.executed_at)
+                    .map(|record| record.id),
+            })
+            .collect();
+
+        let bundle = Self {
+            id: Uuid::new_v4(),
+            range_start: range.start,
+            range_end: range.end,
+            positions,
+            health_records,
+            alerts,
+            actions,
+            decisions,
+            generated_at: Utc::now(),
+            integrity_hash: String::new(),
+        };
+        // Decimals are exported as floats, so seal the bundle as it will read back
+        let mut bundle = serde_json::to_value(&bundle)
+            .and_then(serde_json::from_value::<Self>)
+            .unwrap_or(bundle);
+        bundle.integrity_hash = bundle.compute_integrity_hash(key);
+        bundle
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Whether the bundle contents still match the integrity hash recorded under `key`
+    pub fn verify_integrity(&self, key: &IntegrityKey) -> bool {
+        let mut content = self.clone();
+        content.integrity_hash = String::new();
+
+        content_mac_matches(key, &content, &self.integrity_hash)
+    }
+
+    fn compute_integrity_hash(&self, key: &IntegrityKey) -> String {
+        let mut content = self.clone();
+        content.integrity_hash = String::new();
+
+        content_mac(key, &content)
+    }
+}
+
+fn canonical_json<T: Serialize>(content: &T) -> String {
+    // Going through `Value` sorts object keys, so the result doesn't depend on HashMap
+    // iteration order
+    serde_json::to_value(content)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+fn keyed_mac(key: &IntegrityKey, content: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key.0).expect("HMAC accepts keys of any length");
+    mac.update(content.as_bytes());
+    mac
+}
+
+/// Hex HMAC-SHA256 of the canonical JSON of `content`
+pub(crate) fn content_mac<T: Serialize>(key: &IntegrityKey, content: &T) -> String {
+    keyed_mac(key, &canonical_json(content))
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Whether `expected_hex` is the `content_mac` of `content`, compared in constant time
+pub(crate) fn content_mac_matches<T: Serialize>(key: &IntegrityKey, content: &T, expected_hex: &str) -> bool {
+    let expected = match decode_hex(expected_hex) {
+        Some(expected) => expected,
+        None => return false,
+    };
+    keyed_mac(key, &canonical_json(content)).verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
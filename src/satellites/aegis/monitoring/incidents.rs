@@ -0,0 +1,260 @@
+use crate::types::{RiskAlert, RiskLevel, PositionId, ProtocolId, TokenAddress};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Shared cause that a group of alerts is clustered under
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum IncidentCause {
+    Token(TokenAddress),
+    Protocol(ProtocolId),
+    Position(PositionId),
+}
+
+/// A group of related alerts raised within the same time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: Uuid,
+    pub cause: IncidentCause,
+    pub alert_ids: Vec<Uuid>,
+    pub position_ids: Vec<PositionId>,
+    pub highest_risk_level: RiskLevel,
+    pub opened_at: DateTime<Utc>,
+    pub last_alert_at: DateTime<Utc>,
+}
+
+/// How long a closed incident stays queryable before it is dropped
+pub const DEFAULT_INCIDENT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Incident {
+    fn is_open_at(&self, at: DateTime<Utc>, window: Duration) -> bool {
+        match chrono::Duration::from_std(window) {
+            Ok(window) => self.last_alert_at + window >= at,
+            Err(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncidentAssignment {
+    Opened(Uuid),
+    Joined { incident_id: Uuid, severity_raised: bool },
+}
+
+impl IncidentAssignment {
+    pub fn incident_id(&self) -> Uuid {
+        match self {
+            IncidentAssignment::Opened(id) => *id,
+            IncidentAssignment::Joined { incident_id, .. } => *incident_id,
+        }
+    }
+
+    /// Whether responders should be notified about the alert that produced this assignment
+    pub fn should_notify(&self) -> bool {
+        match self {
+            IncidentAssignment::Opened(_) => true,
+            IncidentAssignment::Joined { severity_raised, .. } => *severity_raised,
+        }
+    }
+}
+
+pub struct IncidentTracker {
+    incidents: RwLock<HashMap<Uuid, Incident>>,
+    retention: Duration,
+}
+
+impl IncidentTracker {
+    pub fn new() -> Self {
+        Self {
+            incidents: RwLock::new(HashMap::new()),
+            retention: DEFAULT_INCIDENT_RETENTION,
+        }
+    }
+
+    /// Keep closed incidents for `retention` after they close instead of the default day
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Candidate causes for an alert, most specific shared cause first
+    fn causes_for(alert: &RiskAlert) -> Vec<IncidentCause> {
+        let mut tokens = alert.related_tokens.clone();
+        tokens.sort();
+        tokens.dedup();
+
+        let mut causes: Vec<IncidentCause> = tokens.into_iter().map(IncidentCause::Token).collect();
+        if let Some(protocol) = &alert.protocol {
+            causes.push(IncidentCause::Protocol(protocol.clone()));
+        }
+        causes.push(IncidentCause::Position(alert.position_id));
+        causes
+    }
+
+    /// Attach the alert to an open incident sharing its cause, or open a new one. Incidents
+    /// closed for longer than the retention are dropped on the way.
+    pub async fn assign(&self, alert: &RiskAlert, window: Duration) -> IncidentAssignment {
+        let causes = Self::causes_for(alert);
+        let mut incidents = self.incidents.write().await;
+        let kept_for = window.saturating_add(self.retention);
+        incidents.retain(|_, incident| incident.is_open_at(alert.created_at, kept_for));
+
+        for cause in &causes {
+            let open = incidents.values_mut()
+                .filter(|incident| &incident.cause == cause && incident.is_open_at(alert.created_at, window))
+                .max_by_key(|incident| incident.last_alert_at);
+
+            if let Some(incident) = open {
+                incident.alert_ids.push(alert.id);
+                if !incident.position_ids.contains(&alert.position_id) {
+                    incident.position_ids.push(alert.position_id);
+                }
+                if alert.created_at > incident.last_alert_at {
+                    incident.last_alert_at = alert.created_at;
+                }
+
+                let severity_raised = alert.risk_level > incident.highest_risk_level;
+                if severity_raised {
+                    incident.highest_risk_level = alert.risk_level.clone();
+                }
+
+                return IncidentAssignment::Joined {
+                    incident_id: incident.id,
+                    severity_raised,
+                };
+            }
+        }
+
+        let incident = Incident {
+            id: Uuid::new_v4(),
+            cause: causes[0].clone(),
+            alert_ids: vec![alert.id],
+            position_ids: vec![alert.position_id],
+            highest_risk_level: alert.risk_level.clone(),
+            opened_at: alert.created_at,
+            last_alert_at: alert.created_at,
+        };
+        let id = incident.id;
+        incidents.insert(id, incident);
+
+        IncidentAssignment::Opened(id)
+    }
+
+    /// All incidents, most recently active first
+    pub async fn get_incidents(&self) -> Vec<Incident> {
+        let mut incidents: Vec<Incident> = self.incidents.read().await.values().cloned().collect();
+        incidents.sort_by_key(|incident| std::cmp::Reverse(incident.last_alert_at));
+        incidents
+    }
+
+    pub async fn get_incident(&self, incident_id: Uuid) -> Option<Incident> {
+        self.incidents.read().await.get(&incident_id).cloned()
+    }
+}
+
+impl Default for IncidentTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AlertType, HealthFactor};
+    use rust_decimal::Decimal;
+
+    fn alert(position_id: PositionId, token: &str, risk_level: RiskLevel, created_at: DateTime<Utc>) -> RiskAlert {
+        RiskAlert {
+            id: Uuid::new_v4(),
+            position_id,
+            alert_type: AlertType::LiquidationRisk,
+            risk_level,
+            health_factor: HealthFactor {
+                value: Decimal::ONE,
+                liquidation_threshold: Decimal::ONE,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: created_at,
+            },
+            message: "test".to_string(),
+            created_at,
+            occurrence_count: 1,
+            last_seen: created_at,
+            acknowledged: false,
+            protocol: Some("aave".to_string()),
+            related_tokens: vec![token.to_string()],
+            projected_seconds_to_liquidation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_crash_alerts_group_into_single_incident() {
+        let tracker = IncidentTracker::new();
+        let window = Duration::from_secs(600);
+        let now = Utc::now();
+
+        let positions: Vec<PositionId> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let eth_alerts = [
+            alert(positions[0], "ETH", RiskLevel::Warning, now),
+            alert(positions[1], "ETH", RiskLevel::Warning, now + chrono::Duration::seconds(30)),
+            alert(positions[2], "ETH", RiskLevel::Critical, now + chrono::Duration::seconds(60)),
+        ];
+
+        let first = tracker.assign(&eth_alerts[0], window).await;
+        let second = tracker.assign(&eth_alerts[1], window).await;
+        let third = tracker.assign(&eth_alerts[2], window).await;
+
+        let incident_id = first.incident_id();
+        assert_eq!(first, IncidentAssignment::Opened(incident_id));
+        assert_eq!(second, IncidentAssignment::Joined { incident_id, severity_raised: false });
+        assert_eq!(third, IncidentAssignment::Joined { incident_id, severity_raised: true });
+        assert!(!second.should_notify());
+        assert!(third.should_notify());
+
+        let incident = tracker.get_incident(incident_id).await.unwrap();
+        assert_eq!(incident.cause, IncidentCause::Token("ETH".to_string()));
+        assert_eq!(incident.alert_ids, eth_alerts.iter().map(|a| a.id).collect::<Vec<_>>());
+        assert_eq!(incident.position_ids, positions);
+        assert_eq!(incident.highest_risk_level, RiskLevel::Critical);
+
+        // An unrelated token opens its own incident
+        let wbtc = tracker.assign(&alert(Uuid::new_v4(), "WBTC", RiskLevel::Warning, now), window).await;
+        assert!(matches!(wbtc, IncidentAssignment::Opened(id) if id != incident_id));
+        assert_eq!(tracker.get_incidents().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_alert_outside_window_opens_new_incident() {
+        let tracker = IncidentTracker::new();
+        let window = Duration::from_secs(600);
+        let now = Utc::now();
+
+        let first = tracker.assign(&alert(Uuid::new_v4(), "ETH", RiskLevel::Warning, now), window).await;
+        let later = alert(Uuid::new_v4(), "ETH", RiskLevel::Warning, now + chrono::Duration::seconds(601));
+        let second = tracker.assign(&later, window).await;
+
+        assert!(matches!(second, IncidentAssignment::Opened(id) if id != first.incident_id()));
+    }
+
+    #[tokio::test]
+    async fn test_closed_incidents_expire_after_retention() {
+        let tracker = IncidentTracker::new().with_retention(Duration::from_secs(3600));
+        let window = Duration::from_secs(600);
+        let now = Utc::now();
+
+        let first = tracker.assign(&alert(Uuid::new_v4(), "ETH", RiskLevel::Warning, now), window).await;
+
+        // Closed but within retention: still listed
+        let closed_at = now + chrono::Duration::seconds(600);
+        tracker.assign(&alert(Uuid::new_v4(), "WBTC", RiskLevel::Warning, closed_at + chrono::Duration::seconds(3600)), window).await;
+        assert!(tracker.get_incident(first.incident_id()).await.is_some());
+
+        tracker.assign(&alert(Uuid::new_v4(), "WBTC", RiskLevel::Warning, closed_at + chrono::Duration::seconds(3601)), window).await;
+        assert!(tracker.get_incident(first.incident_id()).await.is_none());
+        assert_eq!(tracker.get_incidents().await.len(), 1);
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// Reports how much memory the process is currently using
+pub trait MemoryProbe: Send + Sync {
+    fn used_bytes(&self) -> Option<u64>;
+}
+
+/// Resident set size from `/proc/self/statm`; reports nothing on platforms without procfs
+pub struct ProcessMemoryProbe;
+
+impl MemoryProbe for ProcessMemoryProbe {
+    fn used_bytes(&self) -> Option<u64> {
+        let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+        let resident_pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+        Some(resident_pages * page_size())
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> u64 {
+    // SAFETY: sysconf only reads a system constant
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> u64 {
+    4096
+}
+
+/// Soft memory limit above which in-memory data is shed before the process runs out
+#[derive(Clone)]
+pub struct MemoryBudget {
+    pub soft_limit_bytes: u64,
+    /// Fraction of the limit (0.0 - 1.0) at which shedding starts
+    pub pressure_ratio: f64,
+    /// Fraction of the limit usage must fall below before another pressure event can shed
+    /// and alert; at most `pressure_ratio`
+    pub relief_ratio: f64,
+    /// Fraction of each shed-able store dropped per pressure event
+    pub shed_fraction: f64,
+    pub probe: Arc<dyn MemoryProbe>,
+}
+
+impl MemoryBudget {
+    pub fn new(soft_limit_bytes: u64) -> Self {
+        Self {
+            soft_limit_bytes,
+            pressure_ratio: 0.9,
+            relief_ratio: 0.75,
+            shed_fraction: 0.5,
+            probe: Arc::new(ProcessMemoryProbe),
+        }
+    }
+
+    /// Current usage if it has reached the pressure point
+    pub fn usage_under_pressure(&self) -> Option<u64> {
+        let used = self.probe.used_bytes()?;
+        let pressure_point = (self.soft_limit_bytes as f64 * self.pressure_ratio) as u64;
+        (used >= pressure_point).then_some(used)
+    }
+
+    /// Whether usage has fallen below the relief point. Freed memory is not always handed
+    /// back to the OS, so usage can stay above the pressure point after a shed.
+    pub fn relieved(&self) -> bool {
+        let relief_point = (self.soft_limit_bytes as f64 * self.relief_ratio) as u64;
+        self.probe.used_bytes().is_some_and(|used| used < relief_point)
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryBudget")
+            .field("soft_limit_bytes", &self.soft_limit_bytes)
+            .field("pressure_ratio", &self.pressure_ratio)
+            .field("relief_ratio", &self.relief_ratio)
+            .field("shed_fraction", &self.shed_fraction)
+            .finish()
+    }
+}
+
+/// What was dropped in response to memory pressure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryPressureReport {
+    pub used_bytes: u64,
+    pub soft_limit_bytes: u64,
+    pub shed_simulation_cache_entries: usize,
+    pub shed_health_records: usize,
+    /// Points dropped from per-position health factor charts
+    pub shed_health_history_entries: usize,
+}
@@ -0,0 +1,282 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Timer,
+}
+
+/// A metric exported by the satellite, shared by every output format
+#[derive(Debug, Clone, Copy)]
+pub struct MetricDefinition {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+}
+
+pub const POSITIONS_MONITORED: MetricDefinition = MetricDefinition {
+    name: "positions_monitored",
+    help: "Number of positions evaluated in the latest monitoring pass",
+    kind: MetricKind::Gauge,
+};
+
+pub const HEALTH_CALCULATIONS: MetricDefinition = MetricDefinition {
+    name: "health_calculations",
+    help: "Health factor calculations performed",
+    kind: MetricKind::Counter,
+};
+
+pub const ALERTS_RAISED: MetricDefinition = MetricDefinition {
+    name: "alerts_raised",
+    help: "Risk alerts raised by the liquidation monitor",
+    kind: MetricKind::Counter,
+};
+
+pub const HEALTH_CALCULATION_DURATION: MetricDefinition = MetricDefinition {
+    name: "health_calculation_duration_ms",
+    help: "Time taken by a single health factor calculation in milliseconds",
+    kind: MetricKind::Timer,
+};
+
+pub const TOTAL_POSITIONS: MetricDefinition = MetricDefinition {
+    name: "total_positions",
+    help: "Number of positions currently tracked by the satellite",
+    kind: MetricKind::Gauge,
+};
+
+pub const ACTIVE_ALERTS: MetricDefinition = MetricDefinition {
+    name: "active_alerts",
+    help: "Number of alerts that have not been resolved",
+    kind: MetricKind::Gauge,
+};
+
+pub const SIMULATION_CACHE_HIT_RATIO: MetricDefinition = MetricDefinition {
+    name: "simulation_cache_hit_ratio",
+    help: "Fraction of simulation cache lookups served from the cache",
+    kind: MetricKind::Gauge,
+};
+
+/// Upper bounds of the health calculation latency histogram buckets, in milliseconds
+pub const HEALTH_CALCULATION_BUCKETS_MS: [u64; 9] = [1, 5, 10, 25, 50, 100, 250, 500, 1000];
+
+/// Timer samples kept for the next StatsD flush; older ones are dropped once it is full
+pub const MAX_PENDING_TIMER_SAMPLES: usize = 1024;
+
+/// In-process metric values recorded by the monitoring components
+#[derive(Default)]
+pub struct AegisMetrics {
+    positions_monitored: AtomicU64,
+    health_calculations: AtomicU64,
+    alerts_raised: AtomicU64,
+    health_calculation_count: AtomicU64,
+    health_calculation_sum_ms: AtomicU64,
+    /// Samples per latency bucket, not cumulative; the last slot holds samples above every bound
+    health_calculation_buckets: [AtomicU64; HEALTH_CALCULATION_BUCKETS_MS.len() + 1],
+    total_positions: AtomicU64,
+    active_alerts: AtomicU64,
+    /// `f64` bits of the latest hit ratio
+    simulation_cache_hit_ratio: AtomicU64,
+    /// Latest timer samples not yet pushed to a StatsD endpoint, at most
+    /// `MAX_PENDING_TIMER_SAMPLES` so nothing grows while no exporter is flushing
+    pending_health_calculation_ms: Mutex<VecDeque<u64>>,
+}
+
+impl AegisMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_positions_monitored(&self, count: usize) {
+        self.positions_monitored.store(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_health_calculation(&self, duration: Duration) {
+        let millis = duration.as_millis() as u64;
+        self.health_calculations.fetch_add(1, Ordering::Relaxed);
+        self.health_calculation_count.fetch_add(1, Ordering::Relaxed);
+        self.health_calculation_sum_ms.fetch_add(millis, Ordering::Relaxed);
+        let bucket = HEALTH_CALCULATION_BUCKETS_MS.iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(HEALTH_CALCULATION_BUCKETS_MS.len());
+        self.health_calculation_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let mut pending = self.pending_health_calculation_ms.lock().unwrap();
+        if pending.len() == MAX_PENDING_TIMER_SAMPLES {
+            pending.pop_front();
+        }
+        pending.push_back(millis);
+    }
+
+    pub fn record_alerts_raised(&self, count: usize) {
+        self.alerts_raised.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_total_positions(&self, count: usize) {
+        self.total_positions.store(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_active_alerts(&self, count: usize) {
+        self.active_alerts.store(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_simulation_cache_hit_ratio(&self, ratio: f64) {
+        self.simulation_cache_hit_ratio.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn simulation_cache_hit_ratio(&self) -> f64 {
+        f64::from_bits(self.simulation_cache_hit_ratio.load(Ordering::Relaxed))
+    }
+
+    fn value(&self, definition: &MetricDefinition) -> u64 {
+        let value = match definition.name {
+            "positions_monitored" => &self.positions_monitored,
+            "health_calculations" => &self.health_calculations,
+            "alerts_raised" => &self.alerts_raised,
+            "total_positions" => &self.total_positions,
+            "active_alerts" => &self.active_alerts,
+            _ => return 0,
+        };
+        value.load(Ordering::Relaxed)
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut output = String::new();
+
+        for definition in [POSITIONS_MONITORED, TOTAL_POSITIONS, ACTIVE_ALERTS, HEALTH_CALCULATIONS, ALERTS_RAISED] {
+            let (name, metric_type) = match definition.kind {
+                MetricKind::Counter => (format!("aegis_{}_total", definition.name), "counter"),
+                _ => (format!("aegis_{}", definition.name), "gauge"),
+            };
+            output.push_str(&format!("# HELP {} {}\n", name, definition.help));
+            output.push_str(&format!("# TYPE {} {}\n", name, metric_type));
+            output.push_str(&format!("{} {}\n", name, self.value(&definition)));
+        }
+
+        let name = format!("aegis_{}", SIMULATION_CACHE_HIT_RATIO.name);
+        output.push_str(&format!("# HELP {} {}\n", name, SIMULATION_CACHE_HIT_RATIO.help));
+        output.push_str(&format!("# TYPE {} gauge\n", name));
+        output.push_str(&format!("{} {}\n", name, self.simulation_cache_hit_ratio()));
+
+        let name = format!("aegis_{}", HEALTH_CALCULATION_DURATION.name);
+        output.push_str(&format!("# HELP {} {}\n", name, HEALTH_CALCULATION_DURATION.help));
+        output.push_str(&format!("# TYPE {} histogram\n", name));
+        let mut cumulative = 0;
+        for (bound, bucket) in HEALTH_CALCULATION_BUCKETS_MS.iter().zip(&self.health_calculation_buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            output.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", name, bound, cumulative));
+        }
+        let count = self.health_calculation_count.load(Ordering::Relaxed);
+        output.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, count));
+        output.push_str(&format!("{}_sum {}\n", name, self.health_calculation_sum_ms.load(Ordering::Relaxed)));
+        output.push_str(&format!("{}_count {}\n", name, count));
+
+        output
+    }
+}
+
+/// Transport for StatsD datagrams
+pub trait StatsdSender: Send + Sync {
+    fn send(&self, payload: &str) -> std::io::Result<()>;
+}
+
+pub struct UdpStatsdSender {
+    socket: UdpSocket,
+    endpoint: String,
+}
+
+impl UdpStatsdSender {
+    pub fn new(endpoint: &str) -> std::io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind("0.0.0.0:0")?,
+            endpoint: endpoint.to_string(),
+        })
+    }
+}
+
+impl StatsdSender for UdpStatsdSender {
+    fn send(&self, payload: &str) -> std::io::Result<()> {
+        self.socket.send_to(payload.as_bytes(), &self.endpoint).map(|_| ())
+    }
+}
+
+/// Pushes metric values to a StatsD endpoint.
+///
+/// Counters are sent as the delta since the previous flush, gauges as their current
+/// value, and each timer sample recorded since the previous flush is sent individually.
+pub struct StatsdEmitter {
+    sender: Arc<dyn StatsdSender>,
+    prefix: String,
+    last_counter_values: Mutex<HashMap<&'static str, u64>>,
+}
+
+impl StatsdEmitter {
+    pub fn new(sender: Arc<dyn StatsdSender>, prefix: &str) -> Self {
+        Self {
+            sender,
+            prefix: prefix.to_string(),
+            last_counter_values: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// StatsD lines for everything that changed since the last flush
+    pub fn collect_lines(&self, metrics: &AegisMetrics) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{}.{}:{}|g",
+            self.prefix, POSITIONS_MONITORED.name, metrics.value(&POSITIONS_MONITORED)
+        )];
+
+        let mut last_counter_values = self.last_counter_values.lock().unwrap();
+        for definition in [HEALTH_CALCULATIONS, ALERTS_RAISED] {
+            let current = metrics.value(&definition);
+            let previous = last_counter_values.insert(definition.name, current).unwrap_or(0);
+            if current > previous {
+                lines.push(format!("{}.{}:{}|c", self.prefix, definition.name, current - previous));
+            }
+        }
+
+        let samples = std::mem::take(&mut *metrics.pending_health_calculation_ms.lock().unwrap());
+        for millis in samples {
+            lines.push(format!("{}.{}:{}|ms", self.prefix, HEALTH_CALCULATION_DURATION.name, millis));
+        }
+
+        lines
+    }
+
+    /// Send pending metrics, one datagram per line
+    pub fn flush(&self, metrics: &AegisMetrics) -> usize {
+        let lines = self.collect_lines(metrics);
+        let mut sent = 0;
+        for line in &lines {
+            match self.sender.send(line) {
+                Ok(()) => sent += 1,
+                Err(e) => warn!("Failed to send StatsD metric '{}': {}", line, e),
+            }
+        }
+        sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_timer_samples_keep_only_the_latest() {
+        let metrics = AegisMetrics::new();
+        for millis in 0..(MAX_PENDING_TIMER_SAMPLES as u64 + 10) {
+            metrics.record_health_calculation(Duration::from_millis(millis));
+        }
+
+        let pending = metrics.pending_health_calculation_ms.lock().unwrap();
+        assert_eq!(pending.len(), MAX_PENDING_TIMER_SAMPLES);
+        assert_eq!(pending.front(), Some(&10));
+        // The histogram still counts every sample
+        assert_eq!(metrics.health_calculation_count.load(Ordering::Relaxed), MAX_PENDING_TIMER_SAMPLES as u64 + 10);
+    }
+}
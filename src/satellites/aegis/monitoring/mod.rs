@@ -1,3 +1,15 @@
 pub mod alert_system;
+pub mod audit;
+pub mod incidents;
+pub mod memory;
+pub mod metrics;
+#[cfg(feature = "otlp")]
+pub mod telemetry;
 
-pub use alert_system::*;
\ No newline at end of file
+pub use alert_system::*;
+pub use audit::*;
+pub use incidents::*;
+pub use memory::*;
+pub use metrics::*;
+#[cfg(feature = "otlp")]
+pub use telemetry::*;
@@ -0,0 +1,60 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing::warn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// gRPC endpoint of the OpenTelemetry collector
+    pub endpoint: String,
+    /// Reported as `service.name`, which is how Jaeger groups traces
+    pub service_name: String,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:4317".to_string(),
+            service_name: "aegis-satellite".to_string(),
+        }
+    }
+}
+
+/// Keeps the OTLP pipeline alive; spans still buffered are flushed when this is dropped
+pub struct OtlpGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            warn!("Failed to flush OTLP spans: {}", e);
+        }
+    }
+}
+
+/// Install a global subscriber that logs to stdout and exports every span to an
+/// OpenTelemetry collector. Must be called from within a Tokio runtime, and fails if
+/// a global subscriber has already been set.
+pub fn init_otlp_tracing(config: &OtlpConfig) -> Result<OtlpGuard, Box<dyn std::error::Error + Send + Sync>> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(config.endpoint.clone())
+        .build()?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())]))
+        .build();
+    let tracer = provider.tracer("aegis-satellite");
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+
+    Ok(OtlpGuard { provider })
+}
@@ -1,10 +1,13 @@
 use crate::security::{Vulnerability, VulnerabilitySeverity, VulnerabilityCategory};
+use crate::types::{AlertType, RiskAlert, RiskLevel};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc, Duration};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
 use log::{info, warn, error, debug};
 
 /// Asset price data point
@@ -64,6 +67,39 @@ pub struct CorrelationMatrix {
     pub timestamp: DateTime<Utc>,
     pub time_window_days: u32,
     pub confidence_level: f64,
+    /// Pairs whose correlation was carried over from an earlier matrix because of a data gap
+    #[serde(default)]
+    pub stale_pairs: Vec<StaleCorrelation>,
+}
+
+impl CorrelationMatrix {
+    /// Mean of the off-diagonal correlations, or 0.0 for fewer than two assets
+    pub fn average_pairwise_correlation(&self) -> f64 {
+        let mut total = 0.0;
+        let mut pairs = 0;
+        for i in 0..self.matrix.len() {
+            for j in (i + 1)..self.matrix[i].len() {
+                total += self.matrix[i][j];
+                pairs += 1;
+            }
+        }
+        if pairs == 0 { 0.0 } else { total / pairs as f64 }
+    }
+
+    pub fn is_stale(&self, asset1: &str, asset2: &str) -> bool {
+        self.stale_pairs.iter().any(|pair| {
+            (pair.asset1 == asset1 && pair.asset2 == asset2) || (pair.asset1 == asset2 && pair.asset2 == asset1)
+        })
+    }
+}
+
+/// A correlation reused from the last matrix in which both assets had fresh data
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StaleCorrelation {
+    pub asset1: String,
+    pub asset2: String,
+    pub correlation: f64,
+    pub computed_at: DateTime<Utc>,
 }
 
 /// Correlation analysis result
@@ -73,6 +109,12 @@ pub struct CorrelationAnalysis {
     pub high_correlations: Vec<HighCorrelation>,
     pub diversification_score: f64,
     pub concentration_risk: f64,
+    /// Weighted average volatility over portfolio volatility; 1.0 means no diversification benefit
+    #[serde(default)]
+    pub diversification_ratio: f64,
+    /// Herfindahl-Hirschman index of position values, from 1/n (equal weights) to 1.0
+    #[serde(default)]
+    pub herfindahl_index: f64,
     pub recommendations: Vec<RebalancingRecommendation>,
     pub stress_test_results: StressTestResult,
 }
@@ -149,11 +191,20 @@ pub struct TailRiskAnalysis {
     pub risk_mitigation_strategies: Vec<String>,
 }
 
+/// Last correlation computed for each asset pair, and when
+type PairCorrelations = HashMap<(String, String), (f64, DateTime<Utc>)>;
+
 /// Portfolio Correlation Analysis System
 pub struct CorrelationAnalysisSystem {
     assets: Arc<RwLock<HashMap<String, Asset>>>,
     portfolios: Arc<RwLock<HashMap<String, Vec<PortfolioPosition>>>>,
+    /// Matrices keyed by the requested symbols, window and the latest price among them
     correlation_cache: Arc<RwLock<HashMap<String, CorrelationMatrix>>>,
+    cache_hits: Arc<AtomicUsize>,
+    cache_misses: Arc<AtomicUsize>,
+    last_known_correlations: Arc<RwLock<PairCorrelations>>,
+    /// Token address the price feed knows an asset by, for assets where it isn't the symbol
+    token_addresses: Arc<RwLock<HashMap<String, String>>>,
     config: CorrelationAnalysisConfig,
 }
 
@@ -168,6 +219,25 @@ pub struct CorrelationAnalysisConfig {
     pub stress_test_scenarios: Vec<StressTestScenario>,
     pub rebalancing_threshold: f64,
     pub max_concentration_percentage: f64,
+    /// Assets whose latest price point is older than this are treated as having a data gap
+    pub max_price_age_hours: Option<u32>,
+    pub data_gap_policy: DataGapPolicy,
+    /// Rolling window and step used to watch for correlation regime breaks
+    pub regime_window_hours: u32,
+    pub regime_step_hours: u32,
+    /// Trailing windows averaged into the baseline the latest window is compared against
+    pub regime_baseline_windows: usize,
+    /// Rise in average pairwise correlation over the baseline that raises a `CorrelationSpike` alert
+    pub correlation_spike_delta: f64,
+}
+
+/// How assets with missing recent price data are handled in the correlation matrix
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DataGapPolicy {
+    /// Leave the asset out of the matrix
+    DropAsset,
+    /// Keep the asset using its last-known correlations, flagged as stale
+    UseLastKnown,
 }
 
 impl Default for CorrelationAnalysisConfig {
@@ -187,6 +257,12 @@ impl Default for CorrelationAnalysisConfig {
             ],
             rebalancing_threshold: 0.1,
             max_concentration_percentage: 25.0,
+            max_price_age_hours: None,
+            data_gap_policy: DataGapPolicy::DropAsset,
+            regime_window_hours: 72,
+            regime_step_hours: 24,
+            regime_baseline_windows: 7,
+            correlation_spike_delta: 0.3,
         }
     }
 }
@@ -202,12 +278,66 @@ pub enum StressTestScenario {
     Custom(String),
 }
 
+/// Herfindahl-Hirschman index of the given position values: the sum of squared portfolio
+/// shares, from 1/n for equal weights up to 1.0 for a single position
+pub fn herfindahl_index(values: &[f64]) -> f64 {
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    values.iter().map(|value| (value / total).powi(2)).sum()
+}
+
+/// Diversification ratio of a portfolio: the value-weighted average of the asset volatilities
+/// divided by the portfolio volatility.
+///
+/// `values` and `volatilities` are indexed like `symbols`; correlations are looked up in
+/// `matrix` and pairs missing from it are treated as uncorrelated. A portfolio without
+/// volatility has a ratio of 1.0.
+pub fn diversification_ratio(symbols: &[String], values: &[f64], volatilities: &[f64], matrix: &CorrelationMatrix) -> f64 {
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return 1.0;
+    }
+    let weights: Vec<f64> = values.iter().map(|value| value / total).collect();
+
+    let weighted_volatility: f64 = weights.iter().zip(volatilities).map(|(w, v)| w * v).sum();
+    let portfolio_volatility = portfolio_volatility(symbols, &weights, volatilities, matrix);
+    if portfolio_volatility <= f64::EPSILON {
+        return 1.0;
+    }
+    weighted_volatility / portfolio_volatility
+}
+
+fn portfolio_volatility(symbols: &[String], weights: &[f64], volatilities: &[f64], matrix: &CorrelationMatrix) -> f64 {
+    let index = |symbol: &String| matrix.assets.iter().position(|a| a == symbol);
+    let mut variance = 0.0;
+    for i in 0..symbols.len() {
+        for j in 0..symbols.len() {
+            let correlation = if i == j {
+                1.0
+            } else {
+                match (index(&symbols[i]), index(&symbols[j])) {
+                    (Some(idx_i), Some(idx_j)) => matrix.matrix[idx_i][idx_j],
+                    _ => 0.0,
+                }
+            };
+            variance += weights[i] * weights[j] * correlation * volatilities[i] * volatilities[j];
+        }
+    }
+    variance.max(0.0).sqrt()
+}
+
 impl CorrelationAnalysisSystem {
     pub fn new(config: CorrelationAnalysisConfig) -> Self {
         Self {
             assets: Arc::new(RwLock::new(HashMap::new())),
             portfolios: Arc::new(RwLock::new(HashMap::new())),
             correlation_cache: Arc::new(RwLock::new(HashMap::new())),
+            cache_hits: Arc::new(AtomicUsize::new(0)),
+            cache_misses: Arc::new(AtomicUsize::new(0)),
+            last_known_correlations: Arc::new(RwLock::new(HashMap::new())),
+            token_addresses: Arc::new(RwLock::new(HashMap::new())),
             config,
         }
     }
@@ -216,6 +346,7 @@ impl CorrelationAnalysisSystem {
     pub async fn add_asset(&self, asset: Asset) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut assets = self.assets.write().await;
         assets.insert(asset.symbol.clone(), asset);
+        self.correlation_cache.write().await.clear();
         Ok(())
     }
 
@@ -235,10 +366,37 @@ impl CorrelationAnalysisSystem {
             
             // Update volatility
             asset.volatility = self.calculate_volatility(&asset.price_history).await?;
+            self.correlation_cache.write().await.clear();
         }
         Ok(())
     }
 
+    /// Record `price_point` for `symbol` unless its latest price is less than `min_spacing`
+    /// older, so sampling a live feed keeps the history at its usual cadence. Returns whether
+    /// the price was recorded.
+    pub async fn sample_asset_price(
+        &self,
+        symbol: &str,
+        price_point: PricePoint,
+        min_spacing: Duration,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let due = match self.assets.read().await.get(symbol) {
+            Some(asset) => asset.price_history.iter()
+                .map(|point| point.timestamp)
+                .max()
+                .is_none_or(|latest| price_point.timestamp - latest >= min_spacing),
+            None => false,
+        };
+        if due {
+            self.update_asset_price(symbol, price_point).await?;
+        }
+        Ok(due)
+    }
+
+    pub async fn get_asset(&self, symbol: &str) -> Option<Asset> {
+        self.assets.read().await.get(symbol).cloned()
+    }
+
     /// Calculate correlation matrix for assets
     pub async fn calculate_correlation_matrix(
         &self,
@@ -246,43 +404,106 @@ impl CorrelationAnalysisSystem {
         time_window_days: Option<u32>,
     ) -> Result<CorrelationMatrix, Box<dyn std::error::Error + Send + Sync>> {
         let window_days = time_window_days.unwrap_or(self.config.default_time_window_days);
-        let cache_key = format!("{}_days", window_days);
-        
-        // Check cache first
+        let assets = self.assets.read().await;
+
+        // A new price point moves the latest timestamp and so misses the cache
+        let latest_price = asset_symbols.iter()
+            .filter_map(|symbol| assets.get(symbol))
+            .filter_map(|asset| asset.price_history.iter().map(|p| p.timestamp).max())
+            .max();
+        let cache_key = format!(
+            "{}|{}_days|{}",
+            asset_symbols.join(","),
+            window_days,
+            latest_price.map(|t| t.to_rfc3339()).unwrap_or_default()
+        );
+
         let cache = self.correlation_cache.read().await;
         if let Some(cached_matrix) = cache.get(&cache_key) {
-            if cached_matrix.timestamp >= Utc::now() - Duration::hours(1) {
-                return Ok(cached_matrix.clone());
-            }
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(cached_matrix.clone());
         }
         drop(cache);
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
 
-        let assets = self.assets.read().await;
         let mut matrix_data = Vec::new();
         let mut valid_assets = Vec::new();
+        let mut gap_assets = Vec::new();
 
         for symbol in asset_symbols {
             if let Some(asset) = assets.get(symbol) {
-                if asset.price_history.len() >= self.config.minimum_data_points {
+                if self.has_recent_data(asset) {
                     valid_assets.push(symbol.clone());
                     let returns = self.calculate_returns(&asset.price_history).await?;
                     matrix_data.push(returns);
+                } else {
+                    gap_assets.push(symbol.clone());
                 }
             }
         }
+        drop(assets);
 
         if matrix_data.len() < 2 {
             return Err("Insufficient data for correlation analysis".into());
         }
 
-        let correlation_matrix = self.compute_correlation_matrix(&matrix_data).await?;
+        let mut correlation_matrix = self.compute_correlation_matrix(&matrix_data).await?;
+        let now = Utc::now();
+
+        // Remember freshly computed pairs so later data gaps can fall back to them
+        let mut last_known = self.last_known_correlations.write().await;
+        for i in 0..valid_assets.len() {
+            for j in (i + 1)..valid_assets.len() {
+                last_known.insert(
+                    Self::pair_key(&valid_assets[i], &valid_assets[j]),
+                    (correlation_matrix[i][j], now),
+                );
+            }
+        }
+
+        let mut stale_pairs = Vec::new();
+        if self.config.data_gap_policy == DataGapPolicy::UseLastKnown {
+            for symbol in gap_assets {
+                // The asset can only be kept if every pair with an included asset is known
+                let known: Option<Vec<(f64, DateTime<Utc>)>> = valid_assets.iter()
+                    .map(|other| last_known.get(&Self::pair_key(&symbol, other)).copied())
+                    .collect();
+
+                let known = match known {
+                    Some(known) => known,
+                    None => {
+                        warn!("No last-known correlations for {}, dropping it from the matrix", symbol);
+                        continue;
+                    }
+                };
+
+                for (row, (correlation, _)) in correlation_matrix.iter_mut().zip(&known) {
+                    row.push(*correlation);
+                }
+                let mut new_row: Vec<f64> = known.iter().map(|(correlation, _)| *correlation).collect();
+                new_row.push(1.0);
+                correlation_matrix.push(new_row);
+
+                for (other, (correlation, computed_at)) in valid_assets.iter().zip(&known) {
+                    stale_pairs.push(StaleCorrelation {
+                        asset1: symbol.clone(),
+                        asset2: other.clone(),
+                        correlation: *correlation,
+                        computed_at: *computed_at,
+                    });
+                }
+                valid_assets.push(symbol);
+            }
+        }
+        drop(last_known);
 
         let matrix = CorrelationMatrix {
             assets: valid_assets,
             matrix: correlation_matrix,
-            timestamp: Utc::now(),
+            timestamp: now,
             time_window_days: window_days,
             confidence_level: self.config.confidence_level,
+            stale_pairs,
         };
 
         // Cache the result
@@ -292,6 +513,211 @@ impl CorrelationAnalysisSystem {
         Ok(matrix)
     }
 
+    /// Correlation cache size and hit/miss counts since the system was created
+    pub async fn get_cache_stats(&self) -> Result<HashMap<String, usize>, Box<dyn std::error::Error + Send + Sync>> {
+        let cache = self.correlation_cache.read().await;
+        Ok(HashMap::from([
+            ("correlation_cache_entries".to_string(), cache.len()),
+            ("correlation_cache_hits".to_string(), self.cache_hits.load(Ordering::Relaxed)),
+            ("correlation_cache_misses".to_string(), self.cache_misses.load(Ordering::Relaxed)),
+        ]))
+    }
+
+    /// Correlation matrices for a `window` slid across the assets' shared price history in
+    /// `step` increments, each keyed by the end of its window.
+    ///
+    /// Within a window the price series are paired from the most recent point backwards, as
+    /// `calculate_correlation_matrix` pairs full histories. Windows with fewer than three
+    /// prices for some asset are skipped.
+    pub async fn rolling_correlation(
+        &self,
+        asset_symbols: &[String],
+        window: Duration,
+        step: Duration,
+    ) -> Result<Vec<(DateTime<Utc>, CorrelationMatrix)>, Box<dyn std::error::Error + Send + Sync>> {
+        if window <= Duration::zero() || step <= Duration::zero() {
+            return Err("Rolling correlation window and step must be positive".into());
+        }
+
+        let assets = self.assets.read().await;
+        let mut series: Vec<(String, Vec<PricePoint>)> = Vec::new();
+        for symbol in asset_symbols {
+            if let Some(asset) = assets.get(symbol) {
+                if !asset.price_history.is_empty() {
+                    let mut history = asset.price_history.clone();
+                    history.sort_by_key(|point| point.timestamp);
+                    series.push((symbol.clone(), history));
+                }
+            }
+        }
+        drop(assets);
+
+        if series.len() < 2 {
+            return Err("Insufficient data for correlation analysis".into());
+        }
+
+        // Only the span covered by every asset can be windowed
+        let start = series.iter().map(|(_, history)| history[0].timestamp).max().unwrap();
+        let end = series.iter().map(|(_, history)| history[history.len() - 1].timestamp).min().unwrap();
+        let symbols: Vec<String> = series.iter().map(|(symbol, _)| symbol.clone()).collect();
+
+        let mut matrices = Vec::new();
+        let mut window_start = start;
+        while window_start + window <= end {
+            let window_end = window_start + window;
+            let prices: Vec<Vec<PricePoint>> = series.iter()
+                .map(|(_, history)| {
+                    history.iter()
+                        .filter(|point| point.timestamp >= window_start && point.timestamp <= window_end)
+                        .cloned()
+                        .collect()
+                })
+                .collect();
+
+            let shared = prices.iter().map(Vec::len).min().unwrap_or(0);
+            if shared >= 3 {
+                let mut returns_data = Vec::with_capacity(prices.len());
+                for history in &prices {
+                    returns_data.push(self.calculate_returns(&history[history.len() - shared..]).await?);
+                }
+
+                matrices.push((window_end, CorrelationMatrix {
+                    assets: symbols.clone(),
+                    matrix: self.compute_correlation_matrix(&returns_data).await?,
+                    timestamp: window_end,
+                    time_window_days: window.num_days() as u32,
+                    confidence_level: self.config.confidence_level,
+                    stale_pairs: Vec::new(),
+                }));
+            } else {
+                debug!("Skipping rolling correlation window ending {}: only {} shared prices", window_end, shared);
+            }
+
+            window_start += step;
+        }
+
+        Ok(matrices)
+    }
+
+    /// Compare the average pairwise correlation of the latest rolling window with the average
+    /// over the windows that ended before it began, returning a `CorrelationSpike` alert when
+    /// it has risen by more than `correlation_spike_delta`
+    pub async fn detect_correlation_spike(
+        &self,
+        asset_symbols: &[String],
+    ) -> Result<Option<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        let window = Duration::hours(self.config.regime_window_hours as i64);
+        let step = Duration::hours(self.config.regime_step_hours as i64);
+        let rolling = self.rolling_correlation(asset_symbols, window, step).await?;
+
+        let (latest_end, latest) = match rolling.last() {
+            Some(latest) => latest,
+            None => return Ok(None),
+        };
+        // Windows overlapping the latest one would dilute the baseline with the spike itself
+        let latest_start = *latest_end - window;
+        let baseline: Vec<f64> = rolling.iter()
+            .rev()
+            .filter(|(end, _)| *end <= latest_start)
+            .take(self.config.regime_baseline_windows.max(1))
+            .map(|(_, matrix)| matrix.average_pairwise_correlation())
+            .collect();
+        if baseline.is_empty() {
+            return Ok(None);
+        }
+
+        let baseline = baseline.iter().sum::<f64>() / baseline.len() as f64;
+        let current = latest.average_pairwise_correlation();
+        if current - baseline <= self.config.correlation_spike_delta {
+            return Ok(None);
+        }
+
+        warn!("Average correlation of {} rose from {:.2} to {:.2}", latest.assets.join(", "), baseline, current);
+        let risk_level = if current >= self.config.correlation_threshold_critical {
+            RiskLevel::Critical
+        } else {
+            RiskLevel::Warning
+        };
+        let mut alert = RiskAlert::portfolio(AlertType::CorrelationSpike, risk_level, format!(
+            "Average correlation of {} rose from {:.2} to {:.2} in the {}h window ending {}",
+            latest.assets.join(", "), baseline, current, self.config.regime_window_hours, latest_end
+        ));
+        alert.related_tokens = latest.assets.clone();
+        Ok(Some(alert))
+    }
+
+    /// Beta of every stored asset against `benchmark`: cov(asset, benchmark) / var(benchmark)
+    /// over their returns, paired from the most recent price backwards.
+    ///
+    /// Assets with fewer than `minimum_data_points` prices are left out; a benchmark without
+    /// enough prices, or whose price never moves, is an error.
+    pub async fn calculate_betas(&self, benchmark: &Asset) -> Result<HashMap<String, Decimal>, Box<dyn std::error::Error + Send + Sync>> {
+        if benchmark.price_history.len() < self.config.minimum_data_points.max(3) {
+            return Err(format!(
+                "Insufficient price data for benchmark {}: {} points, need {}",
+                benchmark.symbol, benchmark.price_history.len(), self.config.minimum_data_points.max(3)
+            ).into());
+        }
+        let benchmark_returns = self.calculate_returns(&benchmark.price_history).await?;
+
+        let assets = self.assets.read().await;
+        let mut betas = HashMap::new();
+        for (symbol, asset) in assets.iter() {
+            if asset.price_history.len() < self.config.minimum_data_points.max(3) {
+                debug!("Skipping beta of {}: only {} price points", symbol, asset.price_history.len());
+                continue;
+            }
+            let asset_returns = self.calculate_returns(&asset.price_history).await?;
+
+            let shared = asset_returns.len().min(benchmark_returns.len());
+            let asset_returns = &asset_returns[asset_returns.len() - shared..];
+            let market_returns = &benchmark_returns[benchmark_returns.len() - shared..];
+
+            let n = shared as f64;
+            let asset_mean = asset_returns.iter().sum::<f64>() / n;
+            let market_mean = market_returns.iter().sum::<f64>() / n;
+            let covariance = asset_returns.iter().zip(market_returns)
+                .map(|(a, m)| (a - asset_mean) * (m - market_mean))
+                .sum::<f64>() / n;
+            let variance = market_returns.iter()
+                .map(|m| (m - market_mean).powi(2))
+                .sum::<f64>() / n;
+            if variance <= f64::EPSILON {
+                return Err(format!("Benchmark {} has no price variance", benchmark.symbol).into());
+            }
+
+            let beta = Decimal::from_f64(covariance / variance)
+                .ok_or_else(|| format!("Beta of {} against {} is not finite", symbol, benchmark.symbol))?;
+            betas.insert(symbol.clone(), beta);
+        }
+
+        Ok(betas)
+    }
+
+    /// Symbols of all assets with price data in the system
+    pub async fn asset_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.assets.read().await.keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Price `symbol` under `token_address` when sampling a price feed
+    pub async fn set_asset_token_address(&self, symbol: &str, token_address: &str) {
+        self.token_addresses.write().await.insert(symbol.to_string(), token_address.to_string());
+    }
+
+    /// The token address each asset is priced under, by symbol. Assets without one set are
+    /// priced under their symbol.
+    pub async fn asset_token_addresses(&self) -> Vec<(String, String)> {
+        let token_addresses = self.token_addresses.read().await;
+        self.asset_symbols().await.into_iter()
+            .map(|symbol| {
+                let token_address = token_addresses.get(&symbol).cloned().unwrap_or_else(|| symbol.clone());
+                (symbol, token_address)
+            })
+            .collect()
+    }
+
     /// Perform comprehensive correlation analysis
     pub async fn analyze_portfolio_correlation(
         &self,
@@ -316,6 +742,8 @@ impl CorrelationAnalysisSystem {
 
         // Calculate concentration risk
         let concentration_risk = self.calculate_concentration_risk(portfolio).await?;
+        let hhi = herfindahl_index(&Self::position_values(portfolio));
+        let diversification_ratio = self.portfolio_diversification_ratio(portfolio, &matrix).await?;
 
         // Generate rebalancing recommendations
         let recommendations = self.generate_rebalancing_recommendations(
@@ -335,11 +763,74 @@ impl CorrelationAnalysisSystem {
             high_correlations,
             diversification_score,
             concentration_risk,
+            diversification_ratio,
+            herfindahl_index: hhi,
             recommendations,
             stress_test_results,
         })
     }
 
+    /// Diversification ratio of a stored portfolio, using the assets' volatilities and the
+    /// current correlation matrix
+    pub async fn diversification_ratio(&self, portfolio_id: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        let portfolio = self.portfolio(portfolio_id).await?;
+        let asset_symbols: Vec<String> = portfolio.iter().map(|p| p.asset_symbol.clone()).collect();
+        let matrix = self.calculate_correlation_matrix(&asset_symbols, None).await?;
+        self.portfolio_diversification_ratio(&portfolio, &matrix).await
+    }
+
+    /// Herfindahl-Hirschman concentration index of a stored portfolio's position values
+    pub async fn concentration_index(&self, portfolio_id: &str) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        let portfolio = self.portfolio(portfolio_id).await?;
+        Ok(herfindahl_index(&Self::position_values(&portfolio)))
+    }
+
+    async fn portfolio(&self, portfolio_id: &str) -> Result<Vec<PortfolioPosition>, Box<dyn std::error::Error + Send + Sync>> {
+        let portfolios = self.portfolios.read().await;
+        portfolios.get(portfolio_id)
+            .cloned()
+            .ok_or_else(|| "Portfolio not found".into())
+    }
+
+    fn position_values(portfolio: &[PortfolioPosition]) -> Vec<f64> {
+        portfolio.iter().map(|p| p.value_usd).collect()
+    }
+
+    async fn portfolio_diversification_ratio(
+        &self,
+        portfolio: &[PortfolioPosition],
+        matrix: &CorrelationMatrix,
+    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        let symbols: Vec<String> = portfolio.iter().map(|p| p.asset_symbol.clone()).collect();
+        let mut volatilities = Vec::with_capacity(portfolio.len());
+        for symbol in &symbols {
+            volatilities.push(self.get_asset_volatility(symbol).await?);
+        }
+        Ok(diversification_ratio(&symbols, &Self::position_values(portfolio), &volatilities, matrix))
+    }
+
+    /// Whether the asset has enough, sufficiently recent price data to compute correlations
+    fn has_recent_data(&self, asset: &Asset) -> bool {
+        if asset.price_history.len() < self.config.minimum_data_points {
+            return false;
+        }
+
+        match (self.config.max_price_age_hours, asset.price_history.last()) {
+            (Some(max_age_hours), Some(latest)) => {
+                latest.timestamp >= Utc::now() - Duration::hours(max_age_hours as i64)
+            }
+            _ => true,
+        }
+    }
+
+    fn pair_key(asset1: &str, asset2: &str) -> (String, String) {
+        if asset1 <= asset2 {
+            (asset1.to_string(), asset2.to_string())
+        } else {
+            (asset2.to_string(), asset1.to_string())
+        }
+    }
+
     /// Calculate asset returns from price history
     async fn calculate_returns(&self, price_history: &[PricePoint]) -> Result<Vec<f64>, Box<dyn std::error::Error + Send + Sync>> {
         if price_history.len() < 2 {
@@ -523,11 +1014,7 @@ impl CorrelationAnalysisSystem {
             return Ok(0.0);
         }
 
-        // Calculate Herfindahl-Hirschman Index (HHI)
-        let total_value: f64 = portfolio.iter().map(|p| p.value_usd).sum();
-        let hhi: f64 = portfolio.iter()
-            .map(|p| (p.value_usd / total_value).powi(2))
-            .sum();
+        let hhi = herfindahl_index(&Self::position_values(portfolio));
 
         // Convert HHI to concentration risk (0 = no concentration, 1 = maximum concentration)
         let concentration_risk = (hhi - 1.0 / portfolio.len() as f64) / (1.0 - 1.0 / portfolio.len() as f64);
@@ -739,38 +1226,14 @@ impl CorrelationAnalysisSystem {
         matrix: &CorrelationMatrix,
     ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         let total_value: f64 = portfolio.iter().map(|p| p.value_usd).sum();
-        let mut portfolio_variance = 0.0;
-
-        for i in 0..portfolio.len() {
-            for j in 0..portfolio.len() {
-                let weight_i = portfolio[i].value_usd / total_value;
-                let weight_j = portfolio[j].value_usd / total_value;
-                
-                let correlation = if i == j {
-                    1.0
-                } else {
-                    // Find correlation in matrix
-                    let asset_i = &portfolio[i].asset_symbol;
-                    let asset_j = &portfolio[j].asset_symbol;
-                    
-                    if let (Some(idx_i), Some(idx_j)) = (
-                        matrix.assets.iter().position(|a| a == asset_i),
-                        matrix.assets.iter().position(|a| a == asset_j),
-                    ) {
-                        matrix.matrix[idx_i][idx_j]
-                    } else {
-                        0.0 // Assume no correlation if not in matrix
-                    }
-                };
-
-                let volatility_i = self.get_asset_volatility(&portfolio[i].asset_symbol).await?;
-                let volatility_j = self.get_asset_volatility(&portfolio[j].asset_symbol).await?;
-                
-                portfolio_variance += weight_i * weight_j * correlation * volatility_i * volatility_j;
-            }
+        let symbols: Vec<String> = portfolio.iter().map(|p| p.asset_symbol.clone()).collect();
+        let weights: Vec<f64> = portfolio.iter().map(|p| p.value_usd / total_value).collect();
+        let mut volatilities = Vec::with_capacity(portfolio.len());
+        for symbol in &symbols {
+            volatilities.push(self.get_asset_volatility(symbol).await?);
         }
 
-        Ok(portfolio_variance.sqrt())
+        Ok(portfolio_volatility(&symbols, &weights, &volatilities, matrix))
     }
 
     /// Get asset volatility
@@ -937,4 +1400,297 @@ impl Default for CorrelationAnalysisSystem {
     fn default() -> Self {
         Self::new(CorrelationAnalysisConfig::default())
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hourly prices ending `end_hours_ago` hours before now, driven by `price_at(i)`.
+    fn asset(symbol: &str, points: usize, end_hours_ago: i64, price_at: impl Fn(usize) -> f64) -> Asset {
+        let end = Utc::now() - Duration::hours(end_hours_ago);
+        let price_history = (0..points)
+            .map(|i| PricePoint {
+                timestamp: end - Duration::hours((points - 1 - i) as i64),
+                price: price_at(i),
+                volume: 1_000.0,
+                market_cap: None,
+            })
+            .collect();
+
+        Asset {
+            symbol: symbol.to_string(),
+            name: symbol.to_string(),
+            asset_type: AssetType::Cryptocurrency,
+            price_history,
+            volatility: 0.0,
+            beta: 1.0,
+            market_cap: None,
+        }
+    }
+
+    fn wave(i: usize) -> f64 {
+        100.0 + (i as f64 * 0.7).sin() * 5.0
+    }
+
+    fn gap_config(policy: DataGapPolicy) -> CorrelationAnalysisConfig {
+        CorrelationAnalysisConfig {
+            minimum_data_points: 30,
+            max_price_age_hours: Some(24),
+            data_gap_policy: policy,
+            ..CorrelationAnalysisConfig::default()
+        }
+    }
+
+    async fn system_with_gap(policy: DataGapPolicy) -> (CorrelationAnalysisSystem, Vec<String>, CorrelationMatrix) {
+        let system = CorrelationAnalysisSystem::new(gap_config(policy));
+        let symbols: Vec<String> = vec!["ETH".to_string(), "BTC".to_string(), "SOL".to_string()];
+
+        system.add_asset(asset("ETH", 40, 0, wave)).await.unwrap();
+        system.add_asset(asset("BTC", 40, 0, |i| wave(i) * 2.0 + i as f64 * 0.1)).await.unwrap();
+        system.add_asset(asset("SOL", 40, 0, |i| 200.0 - wave(i))).await.unwrap();
+        let fresh = system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+
+        // SOL's feed stops: its latest point is now two days old
+        system.add_asset(asset("SOL", 40, 48, |i| 200.0 - wave(i))).await.unwrap();
+
+        (system, symbols, fresh)
+    }
+
+    #[tokio::test]
+    async fn test_data_gap_reuses_last_known_correlation() {
+        let (system, symbols, fresh) = system_with_gap(DataGapPolicy::UseLastKnown).await;
+
+        let matrix = system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+
+        assert_eq!(matrix.assets.len(), 3);
+        assert!(matrix.assets.contains(&"SOL".to_string()));
+        assert!(matrix.is_stale("SOL", "ETH"));
+        assert!(matrix.is_stale("BTC", "SOL"));
+        assert!(!matrix.is_stale("ETH", "BTC"));
+        assert_eq!(matrix.stale_pairs.len(), 2);
+
+        let index = |m: &CorrelationMatrix, symbol: &str| m.assets.iter().position(|a| a == symbol).unwrap();
+        for other in ["ETH", "BTC"] {
+            let expected = fresh.matrix[index(&fresh, "SOL")][index(&fresh, other)];
+            assert_eq!(matrix.matrix[index(&matrix, "SOL")][index(&matrix, other)], expected);
+            assert_eq!(matrix.matrix[index(&matrix, other)][index(&matrix, "SOL")], expected);
+        }
+        assert_eq!(matrix.matrix[index(&matrix, "SOL")][index(&matrix, "SOL")], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_data_gap_drops_asset_by_default() {
+        let (system, symbols, _) = system_with_gap(DataGapPolicy::DropAsset).await;
+
+        let matrix = system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+
+        assert_eq!(matrix.assets, vec!["ETH".to_string(), "BTC".to_string()]);
+        assert!(matrix.stale_pairs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rolling_correlation_detects_decorrelation() {
+        // BTC tracks ETH's returns for the first 100 hours, then follows an unrelated cycle
+        let mut eth = vec![100.0];
+        let mut btc = vec![200.0];
+        for i in 1..200 {
+            let eth_price = eth[i - 1] * (1.0 + 0.01 * (0.7 * i as f64).sin());
+            eth.push(eth_price);
+            btc.push(if i < 100 {
+                eth_price * 2.0
+            } else {
+                btc[i - 1] * (1.0 + 0.01 * (1.9 * i as f64 + 1.0).sin())
+            });
+        }
+
+        let system = CorrelationAnalysisSystem::default();
+        system.add_asset(asset("ETH", 200, 0, |i| eth[i])).await.unwrap();
+        system.add_asset(asset("BTC", 200, 0, |i| btc[i])).await.unwrap();
+        let symbols = vec!["ETH".to_string(), "BTC".to_string()];
+
+        let rolling = system.rolling_correlation(&symbols, Duration::hours(48), Duration::hours(12)).await.unwrap();
+        assert_eq!(rolling.len(), 13);
+        assert!(rolling.windows(2).all(|pair| pair[1].0 - pair[0].0 == Duration::hours(12)));
+
+        let correlation = |matrix: &CorrelationMatrix| matrix.matrix[0][1];
+        let first_end = rolling[0].0;
+        assert!(correlation(&rolling[0].1) > 0.99);
+        assert!(correlation(&rolling[rolling.len() - 1].1).abs() < 0.5);
+
+        // The shift is only visible once a window reaches past the 100th hour
+        let shift = rolling.iter().position(|(_, matrix)| correlation(matrix) < 0.99).unwrap();
+        assert!(rolling[shift].0 - first_end > Duration::hours(100 - 48));
+        assert!(rolling[..shift].iter().all(|(end, _)| *end - first_end <= Duration::hours(100 - 48)));
+    }
+
+    fn portfolio_position(symbol: &str, value_usd: f64) -> PortfolioPosition {
+        PortfolioPosition {
+            asset_symbol: symbol.to_string(),
+            quantity: 1.0,
+            value_usd,
+            allocation_percentage: 0.0,
+            entry_price: value_usd,
+            current_price: value_usd,
+            unrealized_pnl: 0.0,
+            risk_score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_concentration_and_diversification_ratio_of_uncorrelated_assets() {
+        let symbols: Vec<String> = ["ETH", "BTC", "SOL", "AAVE"].iter().map(|s| s.to_string()).collect();
+        let identity = CorrelationMatrix {
+            assets: symbols.clone(),
+            matrix: (0..4).map(|i| (0..4).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect(),
+            timestamp: Utc::now(),
+            time_window_days: 90,
+            confidence_level: 0.95,
+            stale_pairs: Vec::new(),
+        };
+        let volatilities = [0.5; 4];
+
+        let equal_weight = [2500.0; 4];
+        assert!((herfindahl_index(&equal_weight) - 0.25).abs() < 1e-12);
+        // Four independent, equally volatile assets halve the volatility
+        assert!((diversification_ratio(&symbols, &equal_weight, &volatilities, &identity) - 2.0).abs() < 1e-12);
+
+        let concentrated = [8500.0, 500.0, 500.0, 500.0];
+        assert!((herfindahl_index(&concentrated) - 0.73).abs() < 1e-12);
+        assert!((diversification_ratio(&symbols, &concentrated, &volatilities, &identity) - 1.0 / 0.73f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(herfindahl_index(&[]), 0.0);
+        assert_eq!(diversification_ratio(&symbols, &[0.0; 4], &volatilities, &identity), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_system_reports_portfolio_concentration_and_diversification() {
+        let system = CorrelationAnalysisSystem::default();
+        for (symbol, phase) in [("ETH", 0.0), ("BTC", 1.5), ("SOL", 3.0)] {
+            let mut asset = asset(symbol, 40, 0, |i| 100.0 + (i as f64 * 0.7 + phase).sin() * 5.0);
+            asset.volatility = 0.5;
+            system.add_asset(asset).await.unwrap();
+        }
+        system.add_portfolio("equal", vec![
+            portfolio_position("ETH", 1000.0),
+            portfolio_position("BTC", 1000.0),
+            portfolio_position("SOL", 1000.0),
+        ]).await.unwrap();
+        system.add_portfolio("concentrated", vec![
+            portfolio_position("ETH", 9000.0),
+            portfolio_position("BTC", 500.0),
+            portfolio_position("SOL", 500.0),
+        ]).await.unwrap();
+
+        let equal_index = system.concentration_index("equal").await.unwrap();
+        let concentrated_index = system.concentration_index("concentrated").await.unwrap();
+        assert!((equal_index - 1.0 / 3.0).abs() < 1e-12);
+        assert!((concentrated_index - 0.815).abs() < 1e-12);
+
+        let equal_ratio = system.diversification_ratio("equal").await.unwrap();
+        let concentrated_ratio = system.diversification_ratio("concentrated").await.unwrap();
+        assert!(equal_ratio > concentrated_ratio, "{} <= {}", equal_ratio, concentrated_ratio);
+        assert!(concentrated_ratio > 1.0);
+
+        assert!(system.concentration_index("missing").await.is_err());
+    }
+
+    /// Two hourly series whose returns correlate at about 0.2, or 0.9 after hour 168 when `spike`
+    async fn regime_system(spike: bool) -> CorrelationAnalysisSystem {
+        let mut eth = vec![100.0];
+        let mut btc = vec![100.0];
+        for i in 1..242 {
+            let common = 0.01 * (0.7 * i as f64).sin();
+            let own = 0.01 * (1.9 * i as f64 + 1.0).sin();
+            let rho: f64 = if spike && i > 168 { 0.9 } else { 0.2 };
+            eth.push(eth[i - 1] * (1.0 + common));
+            btc.push(btc[i - 1] * (1.0 + rho * common + (1.0 - rho * rho).sqrt() * own));
+        }
+
+        let system = CorrelationAnalysisSystem::new(CorrelationAnalysisConfig {
+            regime_window_hours: 72,
+            regime_step_hours: 24,
+            regime_baseline_windows: 3,
+            correlation_spike_delta: 0.3,
+            correlation_threshold_critical: 0.8,
+            ..CorrelationAnalysisConfig::default()
+        });
+        system.add_asset(asset("ETH", 242, 0, |i| eth[i])).await.unwrap();
+        system.add_asset(asset("BTC", 242, 0, |i| btc[i])).await.unwrap();
+        system
+    }
+
+    #[tokio::test]
+    async fn test_correlation_spike_raises_alert() {
+        let system = regime_system(true).await;
+        let symbols = system.asset_symbols().await;
+
+        let alert = system.detect_correlation_spike(&symbols).await.unwrap().expect("spike detected");
+        assert!(matches!(alert.alert_type, AlertType::CorrelationSpike));
+        assert_eq!(alert.risk_level, RiskLevel::Critical);
+        assert_eq!(alert.related_tokens, vec!["BTC".to_string(), "ETH".to_string()]);
+        assert!(alert.message.contains("to 0.90"), "{}", alert.message);
+
+        let steady = regime_system(false).await;
+        assert!(steady.detect_correlation_spike(&symbols).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_correlation_matrix_is_cached_until_a_new_price_arrives() {
+        let system = CorrelationAnalysisSystem::default();
+        system.add_asset(asset("ETH", 40, 1, wave)).await.unwrap();
+        system.add_asset(asset("BTC", 40, 1, |i| wave(i) * 2.0 + i as f64 * 0.1)).await.unwrap();
+        let symbols = vec!["ETH".to_string(), "BTC".to_string()];
+        let stat = |stats: &HashMap<String, usize>, name: &str| stats[name];
+
+        let first = system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+        let second = system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+        assert_eq!(first.timestamp, second.timestamp);
+        let stats = system.get_cache_stats().await.unwrap();
+        assert_eq!(stat(&stats, "correlation_cache_hits"), 1);
+        assert_eq!(stat(&stats, "correlation_cache_misses"), 1);
+        assert_eq!(stat(&stats, "correlation_cache_entries"), 1);
+
+        system.update_asset_price("ETH", PricePoint {
+            timestamp: Utc::now(),
+            price: wave(40),
+            volume: 1_000.0,
+            market_cap: None,
+        }).await.unwrap();
+        system.update_asset_price("BTC", PricePoint {
+            timestamp: Utc::now(),
+            price: wave(40) * 2.0 + 4.0,
+            volume: 1_000.0,
+            market_cap: None,
+        }).await.unwrap();
+
+        system.calculate_correlation_matrix(&symbols, None).await.unwrap();
+        let stats = system.get_cache_stats().await.unwrap();
+        assert_eq!(stat(&stats, "correlation_cache_hits"), 1);
+        assert_eq!(stat(&stats, "correlation_cache_misses"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_beta_against_benchmark() {
+        let system = CorrelationAnalysisSystem::default();
+        let benchmark = asset("ETH", 40, 0, wave);
+
+        // Every hourly return of LEVERAGED is twice ETH's
+        let mut leveraged = vec![100.0];
+        for i in 1..40 {
+            let eth_return = (wave(i) - wave(i - 1)) / wave(i - 1);
+            leveraged.push(leveraged[i - 1] * (1.0 + 2.0 * eth_return));
+        }
+        system.add_asset(asset("LEVERAGED", 40, 0, |i| leveraged[i])).await.unwrap();
+        system.add_asset(benchmark.clone()).await.unwrap();
+
+        let betas = system.calculate_betas(&benchmark).await.unwrap();
+        let beta = |symbol: &str| rust_decimal::prelude::ToPrimitive::to_f64(&betas[symbol]).unwrap();
+        assert!((beta("LEVERAGED") - 2.0).abs() < 1e-9, "{}", beta("LEVERAGED"));
+        assert!((beta("ETH") - 1.0).abs() < 1e-9);
+
+        let short = asset("WBTC", 5, 0, wave);
+        let error = system.calculate_betas(&short).await.unwrap_err();
+        assert!(error.to_string().contains("benchmark WBTC"), "{}", error);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::risk::{ExecutionResult, ProposedTrade, TradeExecutor};
+use crate::types::PositionId;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::info;
+
+/// How long a completed keyed trade is remembered by default
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(600);
+
+/// Identifies one intended trade, so a repeated submission can be recognised
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IdempotencyKey {
+    pub position_id: PositionId,
+    pub action: String,
+    /// Distinguishes separate intents for the same action, e.g. the rule that triggered
+    /// it and when
+    pub nonce: String,
+}
+
+impl IdempotencyKey {
+    pub fn new(position_id: PositionId, trade: &ProposedTrade, nonce: impl ToString) -> Self {
+        Self {
+            position_id,
+            action: trade.action().to_string(),
+            nonce: nonce.to_string(),
+        }
+    }
+}
+
+struct KeyedResult {
+    created_at: Instant,
+    result: OnceCell<ExecutionResult>,
+}
+
+/// Runs each keyed trade at most once within `ttl`, returning the first result to any
+/// repeat of the key. Concurrent submissions of a key wait for the one in flight. Failed
+/// trades, whether an error or a result without `success`, are not remembered, so they can
+/// be resubmitted under the same key.
+///
+/// Keys are held in memory; protection across restarts depends on the caller reusing
+/// keys that were persisted with the pending action.
+pub struct IdempotentTradeExecutor {
+    inner: Arc<dyn TradeExecutor>,
+    ttl: Duration,
+    results: DashMap<IdempotencyKey, Arc<KeyedResult>>,
+}
+
+impl IdempotentTradeExecutor {
+    pub fn new(inner: Arc<dyn TradeExecutor>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            results: DashMap::new(),
+        }
+    }
+
+    /// Keys currently remembered, including ones still executing
+    pub fn tracked_keys(&self) -> usize {
+        self.results.len()
+    }
+
+    fn entry(&self, key: &IdempotencyKey) -> Arc<KeyedResult> {
+        self.results.retain(|_, entry| entry.created_at.elapsed() < self.ttl);
+        self.results.entry(key.clone())
+            .or_insert_with(|| Arc::new(KeyedResult { created_at: Instant::now(), result: OnceCell::new() }))
+            .clone()
+    }
+}
+
+#[async_trait]
+impl TradeExecutor for IdempotentTradeExecutor {
+    async fn execute_position_reduction(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.inner.execute_position_reduction(position_id, token_address, amount).await
+    }
+
+    async fn emergency_exit_position(
+        &self,
+        position_id: PositionId,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.inner.emergency_exit_position(position_id).await
+    }
+
+    async fn add_collateral(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.inner.add_collateral(position_id, token_address, amount).await
+    }
+
+    async fn repay_debt(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.inner.repay_debt(position_id, token_address, amount).await
+    }
+
+    async fn execute_keyed(
+        &self,
+        key: &IdempotencyKey,
+        trade: &ProposedTrade,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        let entry = self.entry(key);
+        if let Some(result) = entry.result.get() {
+            info!("Skipping duplicate {} for position {} (nonce {})", key.action, key.position_id, key.nonce);
+            return Ok(result.clone());
+        }
+
+        // An unsuccessful result fails the init so it isn't cached, but is still returned
+        let mut unsuccessful = None;
+        let outcome = entry.result.get_or_try_init(|| async {
+            let result = self.inner.execute_keyed(key, trade).await?;
+            if result.success {
+                return Ok(result);
+            }
+            unsuccessful = Some(result);
+            Err(Box::<dyn Error + Send + Sync>::from("trade was not successful"))
+        }).await;
+
+        match (outcome, unsuccessful) {
+            (Ok(result), _) => Ok(result.clone()),
+            (Err(_), Some(result)) => Ok(result),
+            (Err(e), None) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use uuid::Uuid;
+
+    struct CountingExecutor {
+        calls: AtomicU32,
+        /// Calls answered with an unsuccessful result before trades start succeeding
+        unsuccessful_calls: u32,
+    }
+
+    impl CountingExecutor {
+        fn new(unsuccessful_calls: u32) -> Self {
+            Self { calls: AtomicU32::new(0), unsuccessful_calls }
+        }
+
+        async fn execute(&self) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            // Long enough for a concurrent duplicate to arrive mid-execution
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(ExecutionResult {
+                success: call > self.unsuccessful_calls,
+                transaction_hash: Some(format!("0x{:02x}", call)),
+                amount_executed: None,
+                actual_price_impact: None,
+                gas_used: None,
+                error_message: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl TradeExecutor for CountingExecutor {
+        async fn execute_position_reduction(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.execute().await
+        }
+
+        async fn emergency_exit_position(&self, _position_id: PositionId) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.execute().await
+        }
+
+        async fn add_collateral(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.execute().await
+        }
+
+        async fn repay_debt(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.execute().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_key_executes_once() {
+        let counting = Arc::new(CountingExecutor::new(0));
+        let executor = IdempotentTradeExecutor::new(counting.clone(), DEFAULT_IDEMPOTENCY_TTL);
+        let position_id = Uuid::new_v4();
+        let trade = ProposedTrade::ReducePosition { token_address: "ETH".to_string(), amount: Decimal::ONE };
+        let key = IdempotencyKey::new(position_id, &trade, "execution-1");
+
+        // A duplicate sent while the first is still executing waits for its result
+        let (first, second) = tokio::join!(executor.execute_keyed(&key, &trade), executor.execute_keyed(&key, &trade));
+        let again = executor.execute_keyed(&key, &trade).await.unwrap();
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+        for result in [first.unwrap(), second.unwrap(), again] {
+            assert_eq!(result.transaction_hash.as_deref(), Some("0x01"));
+        }
+
+        // A new nonce or action is a new trade
+        executor.execute_keyed(&IdempotencyKey::new(position_id, &trade, "execution-2"), &trade).await.unwrap();
+        let exit = ProposedTrade::EmergencyExit;
+        executor.execute_keyed(&IdempotencyKey::new(position_id, &exit, "execution-1"), &exit).await.unwrap();
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_key_expires_after_ttl() {
+        let counting = Arc::new(CountingExecutor::new(0));
+        let executor = IdempotentTradeExecutor::new(counting.clone(), Duration::from_millis(50));
+        let trade = ProposedTrade::EmergencyExit;
+        let key = IdempotencyKey::new(Uuid::new_v4(), &trade, 1);
+
+        executor.execute_keyed(&key, &trade).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let result = executor.execute_keyed(&key, &trade).await.unwrap();
+        assert_eq!(result.transaction_hash.as_deref(), Some("0x02"));
+        assert_eq!(executor.tracked_keys(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unsuccessful_result_is_not_remembered() {
+        let counting = Arc::new(CountingExecutor::new(1));
+        let executor = IdempotentTradeExecutor::new(counting.clone(), DEFAULT_IDEMPOTENCY_TTL);
+        let trade = ProposedTrade::EmergencyExit;
+        let key = IdempotencyKey::new(Uuid::new_v4(), &trade, "rule:1");
+
+        let failed = executor.execute_keyed(&key, &trade).await.unwrap();
+        assert!(!failed.success);
+        let retried = executor.execute_keyed(&key, &trade).await.unwrap();
+        assert!(retried.success);
+        let duplicate = executor.execute_keyed(&key, &trade).await.unwrap();
+        assert_eq!(duplicate.transaction_hash.as_deref(), Some("0x02"));
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+    }
+}
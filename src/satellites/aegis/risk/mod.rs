@@ -1,7 +1,13 @@
 pub mod price_impact;
 pub mod position_manager;
 pub mod correlation_analysis;
+pub mod policy;
+pub mod idempotency;
+pub mod trade_retry;
 
 pub use price_impact::*;
 pub use position_manager::*;
-pub use correlation_analysis::*;
\ No newline at end of file
+pub use correlation_analysis::*;
+pub use policy::*;
+pub use idempotency::*;
+pub use trade_retry::*;
\ No newline at end of file
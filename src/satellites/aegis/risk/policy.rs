@@ -0,0 +1,141 @@
+use crate::types::RiskParameters;
+use crate::risk::position_manager::AutomationConfig;
+use crate::monitoring::audit::{content_mac, content_mac_matches, IntegrityKey};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Schema version written into exported policy documents
+pub const RISK_POLICY_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RiskPolicyError {
+    #[error("Unsupported policy format version: {version}")]
+    UnsupportedVersion { version: u32 },
+    #[error("Policy integrity hash does not match its contents")]
+    IntegrityMismatch,
+    #[error("No integrity key configured to seal or verify risk policies")]
+    MissingIntegrityKey,
+    #[error("Invalid risk policy: {message}")]
+    Invalid { message: String },
+    #[error("Failed to parse risk policy: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// The complete risk configuration as a reviewable, sealed document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskPolicyDocument {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub risk_parameters: RiskParameters,
+    pub automation: AutomationConfig,
+    /// Hex HMAC-SHA256 over the canonical JSON of every other field, keyed with the
+    /// exporting satellite's `IntegrityKey`
+    pub integrity_hash: String,
+}
+
+/// A single setting that differs between two policies
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PolicyChange {
+    /// Dotted path of the setting, e.g. `risk_parameters.critical_health_threshold`
+    pub path: String,
+    pub current: Option<Value>,
+    pub proposed: Option<Value>,
+}
+
+impl RiskPolicyDocument {
+    pub fn new(risk_parameters: RiskParameters, automation: AutomationConfig, key: &IntegrityKey) -> Self {
+        let mut document = Self {
+            format_version: RISK_POLICY_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            risk_parameters,
+            automation,
+            integrity_hash: String::new(),
+        };
+        document.integrity_hash = content_mac(key, &document);
+        document
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, RiskPolicyError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Whether the document still matches the integrity hash recorded under `key`
+    pub fn verify_integrity(&self, key: &IntegrityKey) -> bool {
+        let mut content = self.clone();
+        content.integrity_hash = String::new();
+        content_mac_matches(key, &content, &self.integrity_hash)
+    }
+
+    /// Check the document was sealed with `key`, is intact and its settings are coherent
+    pub fn validate(&self, key: &IntegrityKey) -> Result<(), RiskPolicyError> {
+        if self.format_version != RISK_POLICY_FORMAT_VERSION {
+            return Err(RiskPolicyError::UnsupportedVersion { version: self.format_version });
+        }
+        if !self.verify_integrity(key) {
+            return Err(RiskPolicyError::IntegrityMismatch);
+        }
+
+        let params = &self.risk_parameters;
+        let ordered = params.emergency_health_threshold > Decimal::ZERO
+            && params.emergency_health_threshold <= params.critical_health_threshold
+            && params.critical_health_threshold <= params.warning_health_threshold
+            && params.warning_health_threshold <= params.safe_health_threshold;
+        if !ordered {
+            return Err(RiskPolicyError::Invalid {
+                message: "health thresholds must be positive and ordered emergency <= critical <= warning <= safe".to_string()
+            });
+        }
+        if params.max_protocol_exposure_percent <= Decimal::ZERO || params.max_protocol_exposure_percent > Decimal::from(100) {
+            return Err(RiskPolicyError::Invalid {
+                message: format!("max protocol exposure must be within (0, 100], got {}", params.max_protocol_exposure_percent)
+            });
+        }
+
+        let safety = &self.automation.safety_thresholds;
+        if safety.emergency_exit_threshold > safety.auto_reduce_threshold {
+            return Err(RiskPolicyError::Invalid {
+                message: "emergency exit threshold must not exceed the auto-reduce threshold".to_string()
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Settings that would change if this document replaced `current`
+    pub fn diff(&self, current: &RiskPolicyDocument) -> Vec<PolicyChange> {
+        let mut changes = Vec::new();
+        let sections = [
+            ("risk_parameters", serde_json::to_value(&current.risk_parameters), serde_json::to_value(&self.risk_parameters)),
+            ("automation", serde_json::to_value(&current.automation), serde_json::to_value(&self.automation)),
+        ];
+        for (name, current_value, proposed_value) in sections {
+            diff_values(name, current_value.ok().as_ref(), proposed_value.ok().as_ref(), &mut changes);
+        }
+        changes
+    }
+}
+
+fn diff_values(path: &str, current: Option<&Value>, proposed: Option<&Value>, changes: &mut Vec<PolicyChange>) {
+    match (current, proposed) {
+        (Some(Value::Object(current)), Some(Value::Object(proposed))) => {
+            let mut keys: Vec<&String> = current.keys().chain(proposed.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                diff_values(&format!("{}.{}", path, key), current.get(key), proposed.get(key), changes);
+            }
+        }
+        _ if current != proposed => changes.push(PolicyChange {
+            path: path.to_string(),
+            current: current.cloned(),
+            proposed: proposed.cloned(),
+        }),
+        _ => {}
+    }
+}
@@ -1,12 +1,13 @@
 use crate::types::{
-    PositionId, Position, HealthFactor, RiskParameters, RiskLevel, RiskAlert, AlertType
+    PositionId, Position, HealthFactor, RiskLevel, RiskAlert, AlertType, PriceData, TokenAddress
 };
 use crate::liquidation::{LiquidationMonitor, AlertSystem};
 use crate::risk::price_impact::{PriceImpactSimulator, TradeSimulation, RecommendedAction};
+use crate::risk::idempotency::IdempotencyKey;
 use async_trait::async_trait;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{RwLock, Mutex};
@@ -106,9 +107,11 @@ pub struct ExecutionLimits {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApprovalRequirements {
-    pub require_human_approval_above_usd: Decimal,
-    pub auto_approve_emergency_exits: bool,
-    pub approval_timeout: Duration,
+    pub require_human_approval_above_usd: Decimal, // Zero queues every trade for approval
+    pub auto_approve_emergency_exits: bool, // Execute emergency exits once `emergency_auto_approve_timeout` lapses
+    pub approval_timeout: Duration,         // Non-emergency requests are cancelled after this
+    #[serde(default)]
+    pub emergency_auto_approve_timeout: Duration, // Auto-approved emergency exits wait this long; zero executes at once
     pub escalation_contacts: Vec<String>,
 }
 
@@ -169,6 +172,7 @@ impl Default for AutomationConfig {
                 require_human_approval_above_usd: Decimal::from(50_000),
                 auto_approve_emergency_exits: true,
                 approval_timeout: Duration::from_secs(300), // 5 minutes
+                emergency_auto_approve_timeout: Duration::ZERO,
                 escalation_contacts: vec!["risk-manager@yieldsensei.com".to_string()],
             },
         }
@@ -189,6 +193,10 @@ pub struct AutomatedActionExecution {
     pub approval_required: bool,
     pub approved_by: Option<String>,
     pub approved_at: Option<DateTime<Utc>>,
+    /// Key the trade is submitted under, fixed when the trade is first proposed so an
+    /// approval or a repeat of the same trigger reuses it
+    #[serde(default)]
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -212,6 +220,61 @@ pub struct ExecutionResult {
     pub error_message: Option<String>,
 }
 
+/// Trade held back by an approval request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProposedTrade {
+    ReducePosition {
+        token_address: TokenAddress,
+        amount: Decimal,
+    },
+    EmergencyExit,
+}
+
+impl ProposedTrade {
+    /// Action name used in idempotency keys
+    pub fn action(&self) -> &'static str {
+        match self {
+            ProposedTrade::ReducePosition { .. } => "reduce_position",
+            ProposedTrade::EmergencyExit => "emergency_exit",
+        }
+    }
+}
+
+/// Automated action queued until a reviewer approves or rejects it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionApproval {
+    /// Same id as the queued execution
+    pub id: Uuid,
+    pub execution: AutomatedActionExecution,
+    pub trade: ProposedTrade,
+    pub trade_value_usd: Decimal,
+    pub requested_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Execute at `expires_at` instead of cancelling; only set for emergency exits
+    pub auto_approve_on_timeout: bool,
+}
+
+/// Recorded as `approved_by` when an emergency exit is approved by timeout
+pub const AUTO_APPROVER: &str = "auto-approve-timeout";
+
+/// Smallest sale of one collateral token whose proceeds, repaid against the debt, restore
+/// a target health factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafeReduction {
+    pub position_id: PositionId,
+    pub token_address: TokenAddress,
+    pub target_health: Decimal,
+    /// Collateral tokens to sell; zero when the position already meets the target
+    pub amount: Decimal,
+    pub resulting_health: Decimal,
+    pub proceeds_usd: Decimal,
+    /// Oracle value of the sold collateral lost to price impact
+    pub slippage_cost_usd: Decimal,
+}
+
+/// Bisection steps when searching for the minimal safe sale
+const SAFE_REDUCTION_ITERATIONS: usize = 48;
+
 pub struct AutomatedPositionManager {
     config: Arc<RwLock<AutomationConfig>>,
     liquidation_monitor: Arc<LiquidationMonitor>,
@@ -221,6 +284,7 @@ pub struct AutomatedPositionManager {
     trade_executor: Arc<dyn TradeExecutor>,
     last_action_time: Arc<RwLock<HashMap<PositionId, Instant>>>,
     daily_execution_stats: Arc<RwLock<DailyExecutionStats>>,
+    pending_approvals: Arc<RwLock<HashMap<Uuid, ActionApproval>>>,
 }
 
 #[derive(Debug, Default)]
@@ -228,6 +292,9 @@ struct DailyExecutionStats {
     trades_today: u32,
     value_traded_today: Decimal,
     last_reset_date: DateTime<Utc>,
+    /// Trades already counted today, so a duplicate answered from an earlier result
+    /// isn't counted again
+    counted_trades: HashSet<IdempotencyKey>,
 }
 
 impl AutomatedPositionManager {
@@ -246,22 +313,31 @@ impl AutomatedPositionManager {
             trade_executor,
             last_action_time: Arc::new(RwLock::new(HashMap::new())),
             daily_execution_stats: Arc::new(RwLock::new(DailyExecutionStats::default())),
+            pending_approvals: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     pub async fn start_monitoring(&self) {
+        // Expired approvals are resolved more often, so auto-approved emergency exits
+        // aren't held back by the evaluation interval
+        let mut approvals = interval(Duration::from_secs(1));
         let mut interval = interval(Duration::from_secs(30)); // Check every 30 seconds
-        
+
         loop {
-            interval.tick().await;
-            
-            if let Err(e) = self.evaluate_all_positions().await {
-                error!("Error during position evaluation: {}", e);
+            tokio::select! {
+                _ = approvals.tick() => {
+                    self.process_expired_approvals().await;
+                }
+                _ = interval.tick() => {
+                    if let Err(e) = self.evaluate_all_positions().await {
+                        error!("Error during position evaluation: {}", e);
+                    }
+                }
             }
         }
     }
 
-    async fn evaluate_all_positions(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub(crate) async fn evaluate_all_positions(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let config = self.config.read().await;
         
         if !config.enabled {
@@ -387,6 +463,7 @@ impl AutomatedPositionManager {
                 approval_required: false,
                 approved_by: None,
                 approved_at: None,
+                idempotency_key: None,
             };
 
             self.execute_automated_action(execution, position, health_factor).await?;
@@ -415,7 +492,12 @@ impl AutomatedPositionManager {
                     health_factor: health_factor.clone(),
                     message: format!("Automated intervention triggered: {}", execution.triggered_by_rule),
                     created_at: Utc::now(),
+                    occurrence_count: 1,
+                    last_seen: Utc::now(),
                     acknowledged: !require_acknowledgment,
+                    protocol: Some(position.protocol.clone()),
+                    related_tokens: position.collateral_tokens.keys().cloned().collect(),
+                    projected_seconds_to_liquidation: None,
                 };
 
                 self.alert_system.send_alert(alert).await?;
@@ -480,104 +562,171 @@ impl AutomatedPositionManager {
         percentage: Decimal,
         max_price_impact: Decimal,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Check execution limits
-        if !self.check_execution_limits().await? {
-            execution.status = ExecutionStatus::Failed;
-            execution.result = Some(ExecutionResult {
-                success: false,
-                transaction_hash: None,
-                amount_executed: None,
-                actual_price_impact: None,
-                gas_used: None,
-                error_message: Some("Execution limits exceeded".to_string()),
-            });
-            return Ok(());
-        }
-
-        // Simulate the trade first
         let collateral_token = position.collateral_tokens.iter().next();
         if let Some((token_address, token_position)) = collateral_token {
             let reduction_amount = token_position.amount * percentage / Decimal::from(100);
-            
-            let simulation = self.price_impact_simulator
-                .simulate_liquidation_trade(position.id, token_address, reduction_amount)
-                .await?;
-
-            execution.simulation_result = Some(simulation.clone());
-
-            // Check if price impact is acceptable
-            if simulation.expected_outcome.total_price_impact > max_price_impact {
-                warn!("Price impact {:.2}% exceeds maximum {:.2}% for position {}", 
-                      simulation.expected_outcome.total_price_impact, max_price_impact, position.id);
-                execution.status = ExecutionStatus::Failed;
-                execution.result = Some(ExecutionResult {
-                    success: false,
-                    transaction_hash: None,
-                    amount_executed: None,
-                    actual_price_impact: Some(simulation.expected_outcome.total_price_impact),
-                    gas_used: None,
-                    error_message: Some("Price impact too high".to_string()),
-                });
+            if !self.check_reduction(execution, token_address, reduction_amount, max_price_impact).await? {
                 return Ok(());
             }
 
             // Check if approval is required
-            let config = self.config.read().await;
             let trade_value = reduction_amount * token_position.price_per_token;
-            if trade_value > config.approval_requirements.require_human_approval_above_usd {
-                execution.approval_required = true;
-                execution.status = ExecutionStatus::AwaitingApproval;
-                // In a real implementation, this would trigger approval workflow
-                warn!("Trade value ${:.2} requires human approval for position {}", 
-                      trade_value, position.id);
+            let trade = ProposedTrade::ReducePosition {
+                token_address: token_address.clone(),
+                amount: reduction_amount,
+            };
+            if self.requires_approval(trade_value).await {
+                self.request_approval(execution, trade, trade_value).await;
                 return Ok(());
             }
 
-            // Execute the trade
-            execution.status = ExecutionStatus::Executing;
-            match self.trade_executor.execute_position_reduction(position.id, token_address, reduction_amount).await {
-                Ok(result) => {
-                    execution.status = ExecutionStatus::Completed;
-                    execution.completed_at = Some(Utc::now());
-                    execution.result = Some(result);
-                    
-                    // Update daily stats
-                    self.update_daily_stats(trade_value).await;
-                    
-                    info!("Successfully reduced position {} by {:.2}%", position.id, percentage);
-                }
-                Err(e) => {
-                    execution.status = ExecutionStatus::Failed;
-                    execution.result = Some(ExecutionResult {
-                        success: false,
-                        transaction_hash: None,
-                        amount_executed: None,
-                        actual_price_impact: None,
-                        gas_used: None,
-                        error_message: Some(e.to_string()),
-                    });
-                    error!("Failed to reduce position {}: {}", position.id, e);
-                }
-            }
+            self.execute_trade(execution, &trade, trade_value).await;
         }
 
         Ok(())
     }
 
+    /// Check the daily execution limits and simulate selling `amount` of `token_address`,
+    /// failing `execution` if either rules the reduction out. Returns whether it may go ahead.
+    async fn check_reduction(
+        &self,
+        execution: &mut AutomatedActionExecution,
+        token_address: &TokenAddress,
+        amount: Decimal,
+        max_price_impact: Decimal,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.check_execution_limits().await? {
+            Self::fail_execution(execution, "Execution limits exceeded".to_string(), None);
+            return Ok(false);
+        }
+
+        let simulation = self.price_impact_simulator
+            .simulate_liquidation_trade(execution.position_id, token_address, amount)
+            .await?;
+        let price_impact = simulation.expected_outcome.total_price_impact;
+        execution.simulation_result = Some(simulation);
+
+        if price_impact > max_price_impact {
+            warn!("Price impact {:.2}% exceeds maximum {:.2}% for position {}",
+                  price_impact, max_price_impact, execution.position_id);
+            Self::fail_execution(execution, "Price impact too high".to_string(), Some(price_impact));
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
     async fn execute_emergency_exit(
         &self,
         execution: &mut AutomatedActionExecution,
         position: &Position,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Executing emergency exit for position {}", position.id);
-        
+
+        let trade_value: Decimal = position.collateral_tokens.values()
+            .map(|token| token.amount * token.price_per_token)
+            .sum();
+        if self.requires_approval(trade_value).await {
+            let approval = self.config.read().await.approval_requirements.clone();
+            if !(approval.auto_approve_emergency_exits && approval.emergency_auto_approve_timeout.is_zero()) {
+                self.request_approval(execution, ProposedTrade::EmergencyExit, trade_value).await;
+                return Ok(());
+            }
+            warn!("Auto-approving emergency exit for position {} without waiting for approval", position.id);
+            execution.approval_required = true;
+            execution.status = ExecutionStatus::Approved;
+            execution.approved_by = Some(AUTO_APPROVER.to_string());
+            execution.approved_at = Some(Utc::now());
+        }
+
+        self.execute_trade(execution, &ProposedTrade::EmergencyExit, trade_value).await;
+        Ok(())
+    }
+
+    async fn requires_approval(&self, trade_value: Decimal) -> bool {
+        let config = self.config.read().await;
+        trade_value > config.approval_requirements.require_human_approval_above_usd
+    }
+
+    /// Queue the trade behind an approval request instead of executing it
+    async fn request_approval(
+        &self,
+        execution: &mut AutomatedActionExecution,
+        trade: ProposedTrade,
+        trade_value: Decimal,
+    ) {
+        let config = self.config.read().await;
+        let auto_approve_on_timeout = matches!(trade, ProposedTrade::EmergencyExit)
+            && config.approval_requirements.auto_approve_emergency_exits;
+        let timeout = if auto_approve_on_timeout {
+            config.approval_requirements.emergency_auto_approve_timeout
+        } else {
+            config.approval_requirements.approval_timeout
+        };
+        let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
+        drop(config);
+
+        execution.approval_required = true;
+        execution.status = ExecutionStatus::AwaitingApproval;
+        self.assign_idempotency_key(execution, &trade).await;
+
+        let requested_at = Utc::now();
+        let approval = ActionApproval {
+            id: execution.id,
+            execution: execution.clone(),
+            trade,
+            trade_value_usd: trade_value,
+            requested_at,
+            expires_at: requested_at.checked_add_signed(timeout).unwrap_or(DateTime::<Utc>::MAX_UTC),
+            auto_approve_on_timeout,
+        };
+
+        let mut pending = self.pending_approvals.write().await;
+        let queued = pending.values().find(|queued| {
+            queued.execution.position_id == execution.position_id && queued.trade.action() == approval.trade.action()
+        });
+        if let Some(queued) = queued {
+            info!("Not queueing {} for position {}: request {} is already awaiting approval",
+                  approval.trade.action(), execution.position_id, queued.id);
+            Self::cancel_execution(execution, format!("Approval {} already pending", queued.id));
+            return;
+        }
+
+        warn!("Trade value ${:.2} requires human approval for position {} (request {})",
+              trade_value, execution.position_id, approval.id);
+        pending.insert(approval.id, approval);
+    }
+
+    /// Hand the trade to the executor and record the outcome on `execution`
+    async fn execute_trade(
+        &self,
+        execution: &mut AutomatedActionExecution,
+        trade: &ProposedTrade,
+        trade_value: Decimal,
+    ) {
+        let position_id = execution.position_id;
         execution.status = ExecutionStatus::Executing;
-        match self.trade_executor.emergency_exit_position(position.id).await {
+        let key = self.assign_idempotency_key(execution, trade).await;
+        let outcome = self.trade_executor.execute_keyed(&key, trade).await;
+
+        match outcome {
             Ok(result) => {
+                let success = result.success;
                 execution.status = ExecutionStatus::Completed;
                 execution.completed_at = Some(Utc::now());
                 execution.result = Some(result);
-                info!("Emergency exit completed for position {}", position.id);
+
+                match trade {
+                    ProposedTrade::ReducePosition { token_address, amount } => {
+                        if success {
+                            self.update_daily_stats(&key, trade_value).await;
+                        }
+                        info!("Successfully reduced position {} by {} {}", position_id, amount, token_address);
+                    }
+                    ProposedTrade::EmergencyExit => {
+                        info!("Emergency exit completed for position {}", position_id);
+                    }
+                }
             }
             Err(e) => {
                 execution.status = ExecutionStatus::Failed;
@@ -589,11 +738,163 @@ impl AutomatedPositionManager {
                     gas_used: None,
                     error_message: Some(e.to_string()),
                 });
-                error!("Emergency exit failed for position {}: {}", position.id, e);
+                error!("Automated trade failed for position {}: {}", position_id, e);
             }
         }
+    }
+
+    /// Set the execution's idempotency key if it has none yet, returning it. The key is
+    /// derived from the position, the action and the rule that fired, plus the cooldown
+    /// period it fired in: the rule firing again on the same position before its cooldown
+    /// has passed is the same intent, while a later firing is a new trade.
+    async fn assign_idempotency_key(&self, execution: &mut AutomatedActionExecution, trade: &ProposedTrade) -> IdempotencyKey {
+        if let Some(key) = &execution.idempotency_key {
+            return key.clone();
+        }
+        let cooldown_secs = self.config.read().await.safety_thresholds.cooldown_period.as_secs().max(1) as i64;
+        let period = execution.executed_at.timestamp().div_euclid(cooldown_secs);
+        let key = IdempotencyKey::new(
+            execution.position_id,
+            trade,
+            format!("{}:{}", execution.triggered_by_rule, period),
+        );
+        execution.idempotency_key = Some(key.clone());
+        key
+    }
 
-        Ok(())
+    /// Actions waiting for approval, oldest first
+    pub async fn get_pending_approvals(&self) -> Vec<ActionApproval> {
+        let pending = self.pending_approvals.read().await;
+        let mut approvals: Vec<ActionApproval> = pending.values().cloned().collect();
+        approvals.sort_by_key(|approval| approval.requested_at);
+        approvals
+    }
+
+    /// Approve a queued action and run its trade. A reduction is checked against the
+    /// execution limits and its maximum price impact again first, since both may have
+    /// moved while it waited.
+    pub async fn approve_action(
+        &self,
+        id: Uuid,
+        approved_by: &str,
+    ) -> Result<AutomatedActionExecution, Box<dyn std::error::Error + Send + Sync>> {
+        let approval = self.take_pending_approval(id).await?;
+        let mut execution = approval.execution;
+        execution.status = ExecutionStatus::Approved;
+        execution.approved_by = Some(approved_by.to_string());
+        execution.approved_at = Some(Utc::now());
+        info!("Action {} approved by {}", id, approved_by);
+
+        if let ProposedTrade::ReducePosition { token_address, amount } = &approval.trade {
+            let max_price_impact = match &execution.action {
+                AutomatedAction::ReducePosition { max_price_impact, .. } => *max_price_impact,
+                _ => Decimal::MAX,
+            };
+            match self.check_reduction(&mut execution, token_address, *amount, max_price_impact).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.record_execution(execution.clone()).await;
+                    return Ok(execution);
+                }
+                Err(e) => {
+                    Self::fail_execution(&mut execution, e.to_string(), None);
+                    self.record_execution(execution.clone()).await;
+                    return Ok(execution);
+                }
+            }
+        }
+
+        self.execute_trade(&mut execution, &approval.trade, approval.trade_value_usd).await;
+        self.record_execution(execution.clone()).await;
+        Ok(execution)
+    }
+
+    /// Reject a queued action; its trade never reaches the executor
+    pub async fn reject_action(
+        &self,
+        id: Uuid,
+        rejected_by: &str,
+    ) -> Result<AutomatedActionExecution, Box<dyn std::error::Error + Send + Sync>> {
+        let approval = self.take_pending_approval(id).await?;
+        let mut execution = approval.execution;
+        Self::cancel_execution(&mut execution, format!("Rejected by {}", rejected_by));
+        info!("Action {} rejected by {}", id, rejected_by);
+
+        self.record_execution(execution.clone()).await;
+        Ok(execution)
+    }
+
+    /// Resolve requests past their timeout: emergency exits flagged for auto-approval execute,
+    /// everything else is cancelled. Returns the resolved executions.
+    pub async fn process_expired_approvals(&self) -> Vec<AutomatedActionExecution> {
+        let now = Utc::now();
+        let expired: Vec<ActionApproval> = {
+            let mut pending = self.pending_approvals.write().await;
+            let ids: Vec<Uuid> = pending.values()
+                .filter(|approval| approval.expires_at <= now)
+                .map(|approval| approval.id)
+                .collect();
+            ids.iter().filter_map(|id| pending.remove(id)).collect()
+        };
+
+        let mut resolved = Vec::with_capacity(expired.len());
+        for approval in expired {
+            let mut execution = approval.execution;
+            if approval.auto_approve_on_timeout {
+                warn!("Auto-approving emergency action {} after approval timeout", approval.id);
+                execution.status = ExecutionStatus::Approved;
+                execution.approved_by = Some(AUTO_APPROVER.to_string());
+                execution.approved_at = Some(now);
+                self.execute_trade(&mut execution, &approval.trade, approval.trade_value_usd).await;
+            } else {
+                warn!("Approval request {} timed out", approval.id);
+                Self::cancel_execution(&mut execution, "Approval timed out".to_string());
+            }
+
+            self.record_execution(execution.clone()).await;
+            resolved.push(execution);
+        }
+
+        resolved
+    }
+
+    async fn take_pending_approval(&self, id: Uuid) -> Result<ActionApproval, Box<dyn std::error::Error + Send + Sync>> {
+        let mut pending = self.pending_approvals.write().await;
+        pending.remove(&id).ok_or_else(|| format!("No pending approval {}", id).into())
+    }
+
+    fn fail_execution(execution: &mut AutomatedActionExecution, reason: String, actual_price_impact: Option<Decimal>) {
+        execution.status = ExecutionStatus::Failed;
+        execution.result = Some(ExecutionResult {
+            success: false,
+            transaction_hash: None,
+            amount_executed: None,
+            actual_price_impact,
+            gas_used: None,
+            error_message: Some(reason),
+        });
+    }
+
+    fn cancel_execution(execution: &mut AutomatedActionExecution, reason: String) {
+        execution.status = ExecutionStatus::Cancelled;
+        execution.completed_at = Some(Utc::now());
+        execution.result = Some(ExecutionResult {
+            success: false,
+            transaction_hash: None,
+            amount_executed: None,
+            actual_price_impact: None,
+            gas_used: None,
+            error_message: Some(reason),
+        });
+    }
+
+    /// Replace the history entry for this execution, or append it
+    async fn record_execution(&self, execution: AutomatedActionExecution) {
+        let mut history = self.execution_history.lock().await;
+        match history.iter_mut().find(|existing| existing.id == execution.id) {
+            Some(existing) => *existing = execution,
+            None => history.push(execution),
+        }
     }
 
     async fn check_execution_limits(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
@@ -606,6 +907,7 @@ impl AutomatedPositionManager {
             stats.trades_today = 0;
             stats.value_traded_today = Decimal::ZERO;
             stats.last_reset_date = now;
+            stats.counted_trades.clear();
         }
 
         // Check daily limits
@@ -623,8 +925,12 @@ impl AutomatedPositionManager {
         Ok(true)
     }
 
-    async fn update_daily_stats(&self, trade_value: Decimal) {
+    async fn update_daily_stats(&self, key: &IdempotencyKey, trade_value: Decimal) {
         let mut stats = self.daily_execution_stats.write().await;
+        if !stats.counted_trades.insert(key.clone()) {
+            debug!("Not counting duplicate {} for position {} (nonce {})", key.action, key.position_id, key.nonce);
+            return;
+        }
         stats.trades_today += 1;
         stats.value_traded_today += trade_value;
     }
@@ -634,6 +940,112 @@ impl AutomatedPositionManager {
         history.clone()
     }
 
+    /// Binary-search the smallest amount of `token_address` collateral to sell, repaying debt
+    /// with the simulated proceeds, that lifts the position to `target_health`.
+    ///
+    /// The price impact of the sale itself is included, so a thin market means selling more.
+    /// Proceeds repay every debt token in proportion to its value.
+    pub async fn minimal_safe_reduction(
+        &self,
+        position_id: PositionId,
+        token_address: &TokenAddress,
+        target_health: Decimal,
+    ) -> Result<SafeReduction, Box<dyn std::error::Error + Send + Sync>> {
+        let (position, prices) = self.liquidation_monitor.priced_position(position_id).await?;
+        let available = match position.collateral_tokens.get(token_address) {
+            Some(token) => token.amount,
+            None => return Err(format!("Position {} has no {} collateral", position_id, token_address).into()),
+        };
+        let oracle_price = prices.get(token_address)
+            .map(|price| price.price_usd)
+            .ok_or_else(|| format!("No price for {}", token_address))?;
+
+        let current = self.liquidation_monitor.calculate_health_with_prices(&position, &prices)?;
+        if current.value >= target_health {
+            return Ok(SafeReduction {
+                position_id,
+                token_address: token_address.clone(),
+                target_health,
+                amount: Decimal::ZERO,
+                resulting_health: current.value,
+                proceeds_usd: Decimal::ZERO,
+                slippage_cost_usd: Decimal::ZERO,
+            });
+        }
+
+        // Selling everything is the most the position can do
+        let (mut best_health, mut best_proceeds) = self.health_after_sale(&position, &prices, token_address, available).await?;
+        if best_health < target_health {
+            return Err(format!(
+                "Selling all {} {} only reaches health factor {}, short of {}",
+                available, token_address, best_health, target_health
+            ).into());
+        }
+
+        let mut low = Decimal::ZERO;
+        let mut high = available;
+        for _ in 0..SAFE_REDUCTION_ITERATIONS {
+            let mid = (low + high) / Decimal::TWO;
+            let (health, proceeds) = self.health_after_sale(&position, &prices, token_address, mid).await?;
+            if health >= target_health {
+                high = mid;
+                best_health = health;
+                best_proceeds = proceeds;
+            } else {
+                low = mid;
+            }
+        }
+
+        Ok(SafeReduction {
+            position_id,
+            token_address: token_address.clone(),
+            target_health,
+            amount: high,
+            resulting_health: best_health,
+            proceeds_usd: best_proceeds,
+            slippage_cost_usd: (high * oracle_price - best_proceeds).max(Decimal::ZERO),
+        })
+    }
+
+    /// Health factor and USD proceeds after selling `amount` of collateral and repaying debt
+    async fn health_after_sale(
+        &self,
+        position: &Position,
+        prices: &HashMap<TokenAddress, PriceData>,
+        token_address: &TokenAddress,
+        amount: Decimal,
+    ) -> Result<(Decimal, Decimal), Box<dyn std::error::Error + Send + Sync>> {
+        let simulation = self.price_impact_simulator
+            .simulate_liquidation_trade(position.id, token_address, amount)
+            .await?;
+        let proceeds = simulation.expected_outcome.estimated_proceeds_usd;
+
+        let mut reduced = position.clone();
+        if let Some(token) = reduced.collateral_tokens.get_mut(token_address) {
+            token.amount -= amount;
+        }
+
+        let debt_values: Vec<(TokenAddress, Decimal)> = reduced.debt_tokens.iter()
+            .filter_map(|(token, debt)| prices.get(token).map(|price| (token.clone(), debt.amount * price.price_usd)))
+            .collect();
+        let total_debt: Decimal = debt_values.iter().map(|(_, value)| *value).sum();
+        if total_debt > Decimal::ZERO {
+            let repaid_fraction = (proceeds / total_debt).min(Decimal::ONE);
+            for (token, _) in &debt_values {
+                if let Some(debt) = reduced.debt_tokens.get_mut(token) {
+                    debt.amount -= debt.amount * repaid_fraction;
+                }
+            }
+        }
+
+        let health = self.liquidation_monitor.calculate_health_with_prices(&reduced, prices)?;
+        Ok((health.value, proceeds))
+    }
+
+    pub async fn get_config(&self) -> AutomationConfig {
+        self.config.read().await.clone()
+    }
+
     pub async fn update_config(&self, new_config: AutomationConfig) {
         let mut config = self.config.write().await;
         *config = new_config;
@@ -668,4 +1080,384 @@ pub trait TradeExecutor: Send + Sync {
         token_address: &str,
         amount: Decimal,
     ) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>>;
-}
\ No newline at end of file
+
+    /// Execute `trade` on behalf of `key`. Executors that track keys run each key at most
+    /// once; by default the trade is simply dispatched to the matching method.
+    async fn execute_keyed(
+        &self,
+        key: &IdempotencyKey,
+        trade: &ProposedTrade,
+    ) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+        match trade {
+            ProposedTrade::ReducePosition { token_address, amount } => {
+                self.execute_position_reduction(key.position_id, token_address, *amount).await
+            }
+            ProposedTrade::EmergencyExit => self.emergency_exit_position(key.position_id).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::price_impact::LiquidityCurve;
+    use crate::test_support::{NoHistory, NullAlertSystem, StaticPriceFeed, UnusedExecutor};
+    use crate::types::PositionToken;
+    use rust_decimal::prelude::ToPrimitive;
+
+    /// Records every trade that reaches the executor
+    #[derive(Default)]
+    struct RecordingExecutor {
+        trades: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl RecordingExecutor {
+        fn record(&self, trade: String) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.trades.lock().unwrap().push(trade);
+            Ok(ExecutionResult {
+                success: true,
+                transaction_hash: Some("0xabc".to_string()),
+                amount_executed: None,
+                actual_price_impact: None,
+                gas_used: None,
+                error_message: None,
+            })
+        }
+
+        fn trades(&self) -> Vec<String> {
+            self.trades.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl TradeExecutor for RecordingExecutor {
+        async fn execute_position_reduction(&self, _position_id: PositionId, token_address: &str, amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.record(format!("reduce {} {}", amount.normalize(), token_address))
+        }
+
+        async fn emergency_exit_position(&self, _position_id: PositionId) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.record("exit".to_string())
+        }
+
+        async fn add_collateral(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.record("add collateral".to_string())
+        }
+
+        async fn repay_debt(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+            self.record("repay".to_string())
+        }
+    }
+
+    fn token(address: &str, amount: i64) -> (TokenAddress, PositionToken) {
+        (address.to_string(), PositionToken {
+            token_address: address.to_string(),
+            amount: Decimal::from(amount),
+            value_usd: Decimal::ZERO,
+            price_per_token: Decimal::ZERO,
+            entry_price_usd: None,
+        })
+    }
+
+    /// 10 ETH against 17,000 USDC on Aave (health ~0.94), sold into a 1,000 ETH / 2M USDC pool
+    async fn manager_with_position() -> (AutomatedPositionManager, PositionId) {
+        manager_with_executor(Arc::new(UnusedExecutor)).await
+    }
+
+    async fn manager_with_executor(trade_executor: Arc<dyn TradeExecutor>) -> (AutomatedPositionManager, PositionId) {
+        let alert_system: Arc<dyn AlertSystem> = Arc::new(NullAlertSystem);
+        let monitor = Arc::new(LiquidationMonitor::new(Arc::new(StaticPriceFeed), alert_system.clone()));
+        let position = Position {
+            id: Uuid::new_v4(),
+            protocol: "aave".to_string(),
+            collateral_tokens: HashMap::from([token("ETH", 10)]),
+            debt_tokens: HashMap::from([token("USDC", 17_000)]),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            risk_overrides: None,
+        };
+        let position_id = monitor.add_position(position).await.unwrap();
+
+        let simulator = PriceImpactSimulator::new(Box::new(NoHistory)).with_liquidity_curve(
+            "ETH".to_string(),
+            LiquidityCurve::ConstantProduct {
+                token_reserve: Decimal::from(1_000),
+                quote_reserve: Decimal::from(2_000_000),
+            },
+        );
+        let manager = AutomatedPositionManager::new(monitor, Arc::new(simulator), alert_system, trade_executor);
+        (manager, position_id)
+    }
+
+    #[tokio::test]
+    async fn test_minimal_safe_reduction_at_several_targets() {
+        let (manager, position_id) = manager_with_position().await;
+
+        // (target, expected ETH sold with price impact, ETH sold at the oracle price, slippage cost)
+        let cases = [
+            (Decimal::new(11, 1), 4.576444244, 4.5, 41.69686),
+            (Decimal::new(15, 1), 6.886646121, 6.785714286, 94.20305),
+            (Decimal::TWO, 7.595426026, 7.5, 114.51123),
+        ];
+        let mut previous_amount = Decimal::ZERO;
+        for (target, expected_amount, frictionless_amount, expected_slippage) in cases {
+            let reduction = manager.minimal_safe_reduction(position_id, &"ETH".to_string(), target).await.unwrap();
+            let amount = reduction.amount.to_f64().unwrap();
+
+            assert!((amount - expected_amount).abs() < 1e-6, "target {}: sold {}", target, amount);
+            assert!(amount > frictionless_amount);
+            assert!(reduction.resulting_health >= target);
+            assert!((reduction.resulting_health - target).abs() < Decimal::new(1, 6));
+            assert!((reduction.slippage_cost_usd.to_f64().unwrap() - expected_slippage).abs() < 1e-3);
+            assert!(reduction.amount > previous_amount);
+            previous_amount = reduction.amount;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_minimal_safe_reduction_when_already_safe() {
+        let (manager, position_id) = manager_with_position().await;
+
+        let reduction = manager.minimal_safe_reduction(position_id, &"ETH".to_string(), Decimal::new(9, 1)).await.unwrap();
+        assert_eq!(reduction.amount, Decimal::ZERO);
+        assert!(reduction.resulting_health > Decimal::new(9, 1));
+
+        assert!(manager.minimal_safe_reduction(position_id, &"WBTC".to_string(), Decimal::TWO).await.is_err());
+    }
+
+    /// Manager that queues every trade for approval, emergency exits included, with the
+    /// test position priced at $2000/ETH
+    async fn gated_manager(
+        approval_timeout: Duration,
+    ) -> (AutomatedPositionManager, Arc<RecordingExecutor>, Position, HealthFactor) {
+        let executor = Arc::new(RecordingExecutor::default());
+        let (manager, position_id) = manager_with_executor(executor.clone()).await;
+
+        let mut config = manager.get_config().await;
+        config.approval_requirements.require_human_approval_above_usd = Decimal::ZERO;
+        config.approval_requirements.approval_timeout = approval_timeout;
+        config.approval_requirements.emergency_auto_approve_timeout = approval_timeout;
+        manager.update_config(config).await;
+
+        let mut position = manager.liquidation_monitor.list_positions().into_iter()
+            .find(|position| position.id == position_id)
+            .unwrap();
+        for token in position.collateral_tokens.values_mut() {
+            token.price_per_token = Decimal::from(2000);
+        }
+        let health = manager.liquidation_monitor.calculate_health(position_id).await.unwrap();
+        (manager, executor, position, health)
+    }
+
+    async fn propose(
+        manager: &AutomatedPositionManager,
+        position: &Position,
+        health: &HealthFactor,
+        action: AutomatedAction,
+    ) -> Uuid {
+        let execution = AutomatedActionExecution {
+            id: Uuid::new_v4(),
+            position_id: position.id,
+            action,
+            triggered_by_rule: "test".to_string(),
+            status: ExecutionStatus::Pending,
+            simulation_result: None,
+            executed_at: Utc::now(),
+            completed_at: None,
+            result: None,
+            approval_required: false,
+            approved_by: None,
+            approved_at: None,
+            idempotency_key: None,
+        };
+        let id = execution.id;
+        manager.execute_automated_action(execution, position, health).await.unwrap();
+        id
+    }
+
+    fn reduce_by_fifth() -> AutomatedAction {
+        AutomatedAction::ReducePosition {
+            percentage: Decimal::from(20),
+            max_price_impact: Decimal::from(100),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_approved_action_reaches_executor() {
+        let (manager, executor, position, health) = gated_manager(Duration::from_secs(300)).await;
+
+        let id = propose(&manager, &position, &health, reduce_by_fifth()).await;
+        assert!(executor.trades().is_empty());
+
+        let pending = manager.get_pending_approvals().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, id);
+        assert_eq!(pending[0].trade_value_usd, Decimal::from(4000));
+        assert!(!pending[0].auto_approve_on_timeout);
+        assert!(matches!(pending[0].execution.status, ExecutionStatus::AwaitingApproval));
+        assert!(manager.process_expired_approvals().await.is_empty());
+
+        let execution = manager.approve_action(id, "risk-officer").await.unwrap();
+        assert!(matches!(execution.status, ExecutionStatus::Completed));
+        assert_eq!(execution.approved_by.as_deref(), Some("risk-officer"));
+        assert_eq!(executor.trades(), vec!["reduce 2 ETH".to_string()]);
+        assert!(manager.get_pending_approvals().await.is_empty());
+
+        let history = manager.get_execution_history().await;
+        assert_eq!(history.len(), 1);
+        assert!(matches!(history[0].status, ExecutionStatus::Completed));
+        assert!(history[0].approval_required);
+
+        assert!(manager.approve_action(id, "risk-officer").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_approval_rechecks_limits_and_queues_once_per_action() {
+        let (manager, executor, position, health) = gated_manager(Duration::from_secs(300)).await;
+
+        let id = propose(&manager, &position, &health, reduce_by_fifth()).await;
+        let duplicate = propose(&manager, &position, &health, reduce_by_fifth()).await;
+        assert_eq!(manager.get_pending_approvals().await.len(), 1);
+        let history = manager.get_execution_history().await;
+        let duplicate = history.iter().find(|execution| execution.id == duplicate).unwrap();
+        assert!(matches!(duplicate.status, ExecutionStatus::Cancelled));
+        assert_eq!(
+            duplicate.result.as_ref().unwrap().error_message,
+            Some(format!("Approval {} already pending", id))
+        );
+
+        // The day's limit is used up while the request waits
+        let mut config = manager.get_config().await;
+        config.execution_limits.max_trades_per_day = 0;
+        manager.update_config(config).await;
+
+        let execution = manager.approve_action(id, "risk-officer").await.unwrap();
+        assert!(matches!(execution.status, ExecutionStatus::Failed));
+        assert_eq!(execution.result.unwrap().error_message.as_deref(), Some("Execution limits exceeded"));
+        assert!(executor.trades().is_empty());
+        assert!(manager.get_pending_approvals().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_trigger_trades_and_counts_once() {
+        let executor = Arc::new(RecordingExecutor::default());
+        let idempotent = Arc::new(crate::risk::IdempotentTradeExecutor::new(executor.clone(), crate::risk::DEFAULT_IDEMPOTENCY_TTL));
+        let (manager, position_id) = manager_with_executor(idempotent).await;
+        let position = manager.liquidation_monitor.list_positions().into_iter()
+            .find(|position| position.id == position_id)
+            .unwrap();
+        let health = manager.liquidation_monitor.calculate_health(position_id).await.unwrap();
+
+        // The same rule firing twice within one cooldown period
+        let triggered_at = Utc::now();
+        for _ in 0..2 {
+            let execution = AutomatedActionExecution {
+                id: Uuid::new_v4(),
+                position_id,
+                action: reduce_by_fifth(),
+                triggered_by_rule: "critical_health_reduction".to_string(),
+                status: ExecutionStatus::Pending,
+                simulation_result: None,
+                executed_at: triggered_at,
+                completed_at: None,
+                result: None,
+                approval_required: false,
+                approved_by: None,
+                approved_at: None,
+                idempotency_key: None,
+            };
+            manager.execute_automated_action(execution, &position, &health).await.unwrap();
+        }
+
+        assert_eq!(executor.trades(), vec!["reduce 2 ETH".to_string()]);
+        let history = manager.get_execution_history().await;
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|execution| matches!(execution.status, ExecutionStatus::Completed)));
+        assert_eq!(history[0].idempotency_key, history[1].idempotency_key);
+        assert_eq!(history[0].idempotency_key.as_ref().unwrap().nonce, format!(
+            "critical_health_reduction:{}", triggered_at.timestamp().div_euclid(300)
+        ));
+        assert_eq!(manager.daily_execution_stats.read().await.trades_today, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_action_never_executes() {
+        let (manager, executor, position, health) = gated_manager(Duration::from_secs(300)).await;
+
+        let id = propose(&manager, &position, &health, AutomatedAction::EmergencyExit { accept_high_slippage: true }).await;
+        assert!(manager.get_pending_approvals().await[0].auto_approve_on_timeout);
+
+        let execution = manager.reject_action(id, "risk-officer").await.unwrap();
+        assert!(matches!(execution.status, ExecutionStatus::Cancelled));
+        assert_eq!(execution.result.unwrap().error_message.as_deref(), Some("Rejected by risk-officer"));
+        assert!(executor.trades().is_empty());
+        assert!(manager.get_pending_approvals().await.is_empty());
+        assert!(matches!(manager.get_execution_history().await[0].status, ExecutionStatus::Cancelled));
+
+        assert!(manager.approve_action(id, "risk-officer").await.is_err());
+        assert!(manager.reject_action(Uuid::new_v4(), "risk-officer").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_auto_approves_emergency_exits_only() {
+        let (manager, executor, position, health) = gated_manager(Duration::from_millis(1)).await;
+
+        let reduction = propose(&manager, &position, &health, reduce_by_fifth()).await;
+        let exit = propose(&manager, &position, &health, AutomatedAction::EmergencyExit { accept_high_slippage: true }).await;
+        assert_eq!(manager.get_pending_approvals().await.len(), 2);
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let resolved = manager.process_expired_approvals().await;
+        assert_eq!(resolved.len(), 2);
+        assert!(manager.get_pending_approvals().await.is_empty());
+        assert_eq!(executor.trades(), vec!["exit".to_string()]);
+
+        let history = manager.get_execution_history().await;
+        let exit = history.iter().find(|execution| execution.id == exit).unwrap();
+        assert!(matches!(exit.status, ExecutionStatus::Completed));
+        assert_eq!(exit.approved_by.as_deref(), Some(AUTO_APPROVER));
+        let reduction = history.iter().find(|execution| execution.id == reduction).unwrap();
+        assert!(matches!(reduction.status, ExecutionStatus::Cancelled));
+        assert!(reduction.approved_by.is_none());
+
+        // Without the emergency override, an expired exit is cancelled too
+        let mut config = manager.get_config().await;
+        config.approval_requirements.auto_approve_emergency_exits = false;
+        manager.update_config(config).await;
+        propose(&manager, &position, &health, AutomatedAction::EmergencyExit { accept_high_slippage: true }).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let resolved = manager.process_expired_approvals().await;
+        assert!(matches!(resolved[0].status, ExecutionStatus::Cancelled));
+        assert_eq!(executor.trades().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_emergency_exit_above_threshold_executes_within_auto_approve_timeout() {
+        let (manager, executor, position, health) = gated_manager(Duration::from_secs(300)).await;
+        let exit = || AutomatedAction::EmergencyExit { accept_high_slippage: true };
+
+        // By default the exit isn't queued at all
+        let mut config = manager.get_config().await;
+        config.approval_requirements.emergency_auto_approve_timeout = AutomationConfig::default().approval_requirements.emergency_auto_approve_timeout;
+        manager.update_config(config.clone()).await;
+        let id = propose(&manager, &position, &health, exit()).await;
+        assert!(manager.get_pending_approvals().await.is_empty());
+        assert_eq!(executor.trades(), vec!["exit".to_string()]);
+        let executed = manager.get_execution_history().await.into_iter().find(|execution| execution.id == id).unwrap();
+        assert!(matches!(executed.status, ExecutionStatus::Completed));
+        assert_eq!(executed.approved_by.as_deref(), Some(AUTO_APPROVER));
+
+        // With a timeout, it waits that long rather than the general approval timeout
+        let timeout = Duration::from_millis(50);
+        config.approval_requirements.emergency_auto_approve_timeout = timeout;
+        manager.update_config(config).await;
+        propose(&manager, &position, &health, exit()).await;
+        let pending = manager.get_pending_approvals().await;
+        assert_eq!(pending[0].expires_at - pending[0].requested_at, chrono::Duration::from_std(timeout).unwrap());
+        assert!(manager.process_expired_approvals().await.is_empty());
+
+        tokio::time::sleep(timeout).await;
+        let resolved = manager.process_expired_approvals().await;
+        assert!(matches!(resolved[0].status, ExecutionStatus::Completed));
+        assert_eq!(executor.trades(), vec!["exit".to_string(), "exit".to_string()]);
+    }
+}
@@ -1,7 +1,10 @@
+use crate::data::VolatilityTracker;
 use crate::types::{TokenAddress, AssetPrice, PositionId};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use thiserror::Error;
 
@@ -36,11 +39,47 @@ pub struct TradeSimulation {
     pub trade_type: TradeType,
     pub token_address: TokenAddress,
     pub amount: Decimal,
+    /// Average price received per token after slippage
+    #[serde(default)]
+    pub effective_execution_price: AssetPrice,
+    /// Shortfall of the execution price below the pre-trade price, in percent
+    #[serde(default)]
+    pub price_impact_percent: Decimal,
+    /// Per-hop breakdown when the trade is routed through several pools
+    #[serde(default)]
+    pub route: Option<RouteSimulation>,
     pub expected_outcome: TradeOutcome,
     pub risk_factors: Vec<RiskFactor>,
     pub recommended_action: RecommendedAction,
 }
 
+/// A trade chained through one pool per consecutive pair of `path`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSimulation {
+    pub path: Vec<TokenAddress>,
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+    /// Output had every hop filled at its spot price
+    pub spot_amount_out: Decimal,
+    /// Shortfall of `amount_out` below `spot_amount_out`, in percent
+    pub cumulative_slippage_percent: Decimal,
+    pub hops: Vec<HopImpact>,
+    /// Index into `hops` of the hop with the largest price impact
+    pub dominant_hop: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HopImpact {
+    pub from: TokenAddress,
+    pub to: TokenAddress,
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+    /// Units of `to` per unit of `from` before the trade
+    pub spot_price: Decimal,
+    pub execution_price: Decimal,
+    pub price_impact_percent: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TradeType {
     Liquidation,
@@ -92,10 +131,128 @@ pub enum RecommendedAction {
     Abort,
 }
 
+/// AMM pricing curve of the pool a token is sold into, with the token quoted in USD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LiquidityCurve {
+    /// Uniswap v2-style `x * y = k` pool
+    ConstantProduct { token_reserve: Decimal, quote_reserve: Decimal },
+    /// Curve-style two-coin StableSwap pool for a token pegged 1:1 to the quote asset,
+    /// flatter than constant product around balance as `amplification` grows
+    StableSwap { token_reserve: Decimal, quote_reserve: Decimal, amplification: Decimal },
+}
+
+impl LiquidityCurve {
+    /// The same pool seen from the other side, selling the quote asset for the token
+    pub fn reversed(&self) -> Self {
+        match self {
+            LiquidityCurve::ConstantProduct { token_reserve, quote_reserve } => LiquidityCurve::ConstantProduct {
+                token_reserve: *quote_reserve,
+                quote_reserve: *token_reserve,
+            },
+            LiquidityCurve::StableSwap { token_reserve, quote_reserve, amplification } => LiquidityCurve::StableSwap {
+                token_reserve: *quote_reserve,
+                quote_reserve: *token_reserve,
+                amplification: *amplification,
+            },
+        }
+    }
+
+    fn reserves(&self) -> (Decimal, Decimal) {
+        match self {
+            LiquidityCurve::ConstantProduct { token_reserve, quote_reserve }
+            | LiquidityCurve::StableSwap { token_reserve, quote_reserve, .. } => (*token_reserve, *quote_reserve),
+        }
+    }
+
+    /// Marginal price of the token before any trade
+    pub fn spot_price(&self) -> Result<AssetPrice, PriceImpactError> {
+        let (token_reserve, quote_reserve) = self.reserves();
+        if token_reserve <= Decimal::ZERO || quote_reserve <= Decimal::ZERO {
+            return Err(PriceImpactError::SimulationFailed {
+                message: "Pool reserves must be positive".to_string(),
+            });
+        }
+
+        match self {
+            LiquidityCurve::ConstantProduct { .. } => Ok(quote_reserve / token_reserve),
+            // Price a trade one millionth of the pool for the slope at the current balance
+            LiquidityCurve::StableSwap { .. } => {
+                let probe = token_reserve / Decimal::from(1_000_000);
+                Ok(self.sell_proceeds(probe)? / probe)
+            }
+        }
+    }
+
+    /// Quote received for selling `amount` of the token into the pool
+    pub fn sell_proceeds(&self, amount: Decimal) -> Result<Decimal, PriceImpactError> {
+        let (token_reserve, quote_reserve) = self.reserves();
+        if amount <= Decimal::ZERO || token_reserve <= Decimal::ZERO || quote_reserve <= Decimal::ZERO {
+            return Err(PriceImpactError::SimulationFailed {
+                message: format!("Cannot sell {} into a pool with reserves {}/{}", amount, token_reserve, quote_reserve),
+            });
+        }
+
+        match self {
+            LiquidityCurve::ConstantProduct { .. } => Ok(quote_reserve * amount / (token_reserve + amount)),
+            LiquidityCurve::StableSwap { amplification, .. } => {
+                let to_f64 = |value: Decimal| value.to_f64().unwrap_or(0.0);
+                let (x, y, a) = (to_f64(token_reserve), to_f64(quote_reserve), to_f64(*amplification).max(1.0));
+                let d = stable_swap_invariant(x, y, a);
+                let new_y = stable_swap_balance(x + to_f64(amount), d, a);
+                Decimal::from_f64((y - new_y).max(0.0)).ok_or_else(|| PriceImpactError::SimulationFailed {
+                    message: "StableSwap proceeds out of range".to_string(),
+                })
+            }
+        }
+    }
+
+    /// Total pool value in USD at the spot price
+    pub fn liquidity_usd(&self) -> Result<Decimal, PriceImpactError> {
+        let (token_reserve, quote_reserve) = self.reserves();
+        Ok(token_reserve * self.spot_price()? + quote_reserve)
+    }
+}
+
+/// StableSwap invariant `D` of a two-coin pool, by Newton's method as in the Curve contracts
+fn stable_swap_invariant(x: f64, y: f64, amplification: f64) -> f64 {
+    let ann = amplification * 4.0;
+    let sum = x + y;
+    let mut d = sum;
+    for _ in 0..255 {
+        let d_product = d * d / (x * 2.0) * d / (y * 2.0);
+        let previous = d;
+        d = (ann * sum + d_product * 2.0) * d / ((ann - 1.0) * d + 3.0 * d_product);
+        if (d - previous).abs() <= f64::EPSILON * d {
+            break;
+        }
+    }
+    d
+}
+
+/// Balance of the other coin that keeps the invariant at `d` once one coin's balance is `x`
+fn stable_swap_balance(x: f64, d: f64, amplification: f64) -> f64 {
+    let ann = amplification * 4.0;
+    let c = d * d / (x * 2.0) * d / (ann * 2.0);
+    let b = x + d / ann;
+    let mut y = d;
+    for _ in 0..255 {
+        let previous = y;
+        y = (y * y + c) / (2.0 * y + b - d);
+        if (y - previous).abs() <= f64::EPSILON * d {
+            break;
+        }
+    }
+    y
+}
+
 pub struct PriceImpactSimulator {
     dex_liquidity_providers: HashMap<String, Box<dyn LiquidityProvider>>,
     historical_data: Box<dyn HistoricalDataProvider>,
     volatility_analyzer: VolatilityAnalyzer,
+    /// Tokens priced off an AMM curve instead of aggregated order-book depth
+    liquidity_curves: HashMap<TokenAddress, LiquidityCurve>,
+    /// Pools between token pairs for routed trades, keyed by (token, quote) of the curve
+    pools: HashMap<(TokenAddress, TokenAddress), LiquidityCurve>,
 }
 
 impl PriceImpactSimulator {
@@ -113,24 +270,156 @@ impl PriceImpactSimulator {
             dex_liquidity_providers: liquidity_providers,
             historical_data,
             volatility_analyzer: VolatilityAnalyzer::new(),
+            liquidity_curves: HashMap::new(),
+            pools: HashMap::new(),
         }
     }
 
+    /// Judge volatility risk from realized volatility in `tracker` where it has enough prices
+    pub fn with_volatility_tracker(mut self, tracker: Arc<VolatilityTracker>) -> Self {
+        self.volatility_analyzer = VolatilityAnalyzer { tracker: Some(tracker) };
+        self
+    }
+
+    /// Register a pool selling `token` for `quote`; routes may cross it in either direction
+    pub fn with_pool(mut self, token: TokenAddress, quote: TokenAddress, curve: LiquidityCurve) -> Self {
+        self.pools.insert((token, quote), curve);
+        self
+    }
+
+    fn pool(&self, from: &TokenAddress, to: &TokenAddress) -> Option<LiquidityCurve> {
+        self.pools.get(&(from.clone(), to.clone()))
+            .cloned()
+            .or_else(|| self.pools.get(&(to.clone(), from.clone())).map(LiquidityCurve::reversed))
+    }
+
+    /// Sell `amount` of `path[0]` through one registered pool per consecutive pair of
+    /// `path`, feeding each hop's output into the next
+    pub fn simulate_route(&self, path: &[TokenAddress], amount: Decimal) -> Result<RouteSimulation, PriceImpactError> {
+        if path.len() < 2 {
+            return Err(PriceImpactError::SimulationFailed {
+                message: format!("A route needs at least two tokens, got {}", path.len()),
+            });
+        }
+
+        let mut hops = Vec::with_capacity(path.len() - 1);
+        let mut amount_in = amount;
+        let mut spot_amount_out = amount;
+        for pair in path.windows(2) {
+            let curve = self.pool(&pair[0], &pair[1]).ok_or_else(|| PriceImpactError::SimulationFailed {
+                message: format!("No pool between {} and {}", pair[0], pair[1]),
+            })?;
+            let spot_price = curve.spot_price()?;
+            let amount_out = curve.sell_proceeds(amount_in)?;
+            let execution_price = amount_out / amount_in;
+
+            hops.push(HopImpact {
+                from: pair[0].clone(),
+                to: pair[1].clone(),
+                amount_in,
+                amount_out,
+                spot_price,
+                execution_price,
+                price_impact_percent: ((spot_price - execution_price) / spot_price) * Decimal::from(100),
+            });
+            spot_amount_out *= spot_price;
+            amount_in = amount_out;
+        }
+
+        let dominant_hop = hops.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.price_impact_percent.cmp(&b.price_impact_percent))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+
+        Ok(RouteSimulation {
+            path: path.to_vec(),
+            amount_in: amount,
+            amount_out: amount_in,
+            spot_amount_out,
+            cumulative_slippage_percent: ((spot_amount_out - amount_in) / spot_amount_out) * Decimal::from(100),
+            hops,
+            dominant_hop,
+        })
+    }
+
+    /// Simulate liquidating `amount` of `path[0]` along `path`, with the last token taken
+    /// as the USD quote
+    pub async fn simulate_liquidation_route(
+        &self,
+        position_id: PositionId,
+        path: &[TokenAddress],
+        amount: Decimal,
+    ) -> Result<TradeSimulation, PriceImpactError> {
+        let route = self.simulate_route(path, amount)?;
+
+        // The thinnest pool on the route, valued in the output token
+        let mut liquidity = None;
+        for (index, pair) in path.windows(2).enumerate() {
+            let curve = self.pool(&pair[0], &pair[1]).ok_or_else(|| PriceImpactError::SimulationFailed {
+                message: format!("No pool between {} and {}", pair[0], pair[1]),
+            })?;
+            let to_output: Decimal = route.hops[index + 1..].iter().map(|hop| hop.spot_price).product();
+            let pool_liquidity = curve.liquidity_usd()? * to_output;
+            liquidity = Some(liquidity.map_or(pool_liquidity, |min: Decimal| min.min(pool_liquidity)));
+        }
+
+        let current_price = route.spot_amount_out / amount;
+        let execution_price = route.amount_out / amount;
+        let price_impact_sim = PriceImpactSimulation {
+            token_address: path[0].clone(),
+            trade_size_usd: route.spot_amount_out,
+            current_price,
+            estimated_execution_price: execution_price,
+            price_impact_percent: route.cumulative_slippage_percent,
+            slippage_percent: ((execution_price - current_price) / current_price) * Decimal::from(100),
+            liquidity_depth: LiquidityDepth {
+                total_liquidity_usd: liquidity.unwrap_or(Decimal::ZERO),
+                depth_levels: Vec::new(),
+            },
+            simulation_timestamp: Utc::now(),
+        };
+
+        self.trade_simulation(position_id, amount, price_impact_sim, Some(route)).await
+    }
+
+    pub fn with_liquidity_curve(mut self, token_address: TokenAddress, curve: LiquidityCurve) -> Self {
+        self.liquidity_curves.insert(token_address, curve);
+        self
+    }
+
     pub async fn simulate_price_impact(
         &self,
         token_address: &TokenAddress,
         trade_size_usd: Decimal,
     ) -> Result<PriceImpactSimulation, PriceImpactError> {
-        // Get current market data
-        let current_price = self.get_current_price(token_address).await?;
-        let liquidity_depth = self.aggregate_liquidity_depth(token_address).await?;
-        
-        // Calculate price impact based on liquidity depth
-        let (execution_price, price_impact) = self.calculate_price_impact(
-            &current_price,
-            trade_size_usd,
-            &liquidity_depth,
-        )?;
+        let (current_price, execution_price, price_impact, liquidity_depth) = match self.liquidity_curves.get(token_address) {
+            Some(curve) => {
+                // Selling into the pool: impact is the shortfall below the spot price
+                let spot_price = curve.spot_price()?;
+                let amount = trade_size_usd / spot_price;
+                let execution_price = curve.sell_proceeds(amount)? / amount;
+                let price_impact = ((spot_price - execution_price) / spot_price) * Decimal::from(100);
+                let liquidity_depth = LiquidityDepth {
+                    total_liquidity_usd: curve.liquidity_usd()?,
+                    depth_levels: Vec::new(),
+                };
+                (spot_price, execution_price, price_impact, liquidity_depth)
+            }
+            None => {
+                // Get current market data
+                let current_price = self.get_current_price(token_address).await?;
+                let liquidity_depth = self.aggregate_liquidity_depth(token_address).await?;
+
+                // Calculate price impact based on liquidity depth
+                let (execution_price, price_impact) = self.calculate_price_impact(
+                    &current_price,
+                    trade_size_usd,
+                    &liquidity_depth,
+                )?;
+                (current_price, execution_price, price_impact, liquidity_depth)
+            }
+        };
 
         let slippage_percent = ((execution_price - current_price) / current_price) * Decimal::from(100);
 
@@ -152,18 +441,32 @@ impl PriceImpactSimulator {
         token_address: &TokenAddress,
         amount: Decimal,
     ) -> Result<TradeSimulation, PriceImpactError> {
-        let current_price = self.get_current_price(token_address).await?;
+        let current_price = match self.liquidity_curves.get(token_address) {
+            Some(curve) => curve.spot_price()?,
+            None => self.get_current_price(token_address).await?,
+        };
         let trade_size_usd = amount * current_price;
         
         // Simulate price impact
         let price_impact_sim = self.simulate_price_impact(token_address, trade_size_usd).await?;
-        
+        self.trade_simulation(position_id, amount, price_impact_sim, None).await
+    }
+
+    async fn trade_simulation(
+        &self,
+        position_id: PositionId,
+        amount: Decimal,
+        price_impact_sim: PriceImpactSimulation,
+        route: Option<RouteSimulation>,
+    ) -> Result<TradeSimulation, PriceImpactError> {
+        let token_address = &price_impact_sim.token_address;
+
         // Analyze risk factors
         let risk_factors = self.analyze_risk_factors(token_address, &price_impact_sim).await?;
         
         // Calculate expected outcome
         let expected_proceeds = amount * price_impact_sim.estimated_execution_price;
-        let execution_time = self.estimate_execution_time(trade_size_usd, &price_impact_sim.liquidity_depth);
+        let execution_time = self.estimate_execution_time(price_impact_sim.trade_size_usd, &price_impact_sim.liquidity_depth);
         
         let expected_outcome = TradeOutcome {
             estimated_proceeds_usd: expected_proceeds,
@@ -180,6 +483,9 @@ impl PriceImpactSimulator {
             trade_type: TradeType::Liquidation,
             token_address: token_address.clone(),
             amount,
+            effective_execution_price: price_impact_sim.estimated_execution_price,
+            price_impact_percent: price_impact_sim.price_impact_percent,
+            route,
             expected_outcome,
             risk_factors,
             recommended_action,
@@ -441,14 +747,19 @@ impl LiquidityProvider for BalancerLiquidityProvider {
     }
 }
 
-struct VolatilityAnalyzer;
+struct VolatilityAnalyzer {
+    tracker: Option<Arc<VolatilityTracker>>,
+}
 
 impl VolatilityAnalyzer {
-    fn new() -> Self { Self }
+    fn new() -> Self { Self { tracker: None } }
     
-    async fn calculate_recent_volatility(&self, _token_address: &TokenAddress) -> Result<Decimal, PriceImpactError> {
-        // Placeholder: return 30% annualized volatility
-        Ok(Decimal::from(30))
+    /// Annualized volatility in percent
+    async fn calculate_recent_volatility(&self, token_address: &TokenAddress) -> Result<Decimal, PriceImpactError> {
+        let realized = self.tracker.as_ref()
+            .and_then(|tracker| tracker.annualized_volatility(token_address));
+        // Assume 30% until enough prices have been seen
+        Ok(realized.map(|volatility| volatility * Decimal::from(100)).unwrap_or(Decimal::from(30)))
     }
 }
 
@@ -462,4 +773,87 @@ pub enum PriceImpactError {
     SimulationFailed { message: String },
     #[error("Provider error: {0}")]
     ProviderError(#[from] Box<dyn std::error::Error + Send + Sync>),
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::NoHistory;
+
+    fn close(actual: Decimal, expected: f64, tolerance: f64) -> bool {
+        (actual.to_f64().unwrap() - expected).abs() < tolerance
+    }
+
+    #[tokio::test]
+    async fn test_constant_product_trade_of_ten_percent_of_pool() {
+        let simulator = PriceImpactSimulator::new(Box::new(NoHistory)).with_liquidity_curve(
+            "ETH".to_string(),
+            LiquidityCurve::ConstantProduct {
+                token_reserve: Decimal::from(1_000),
+                quote_reserve: Decimal::from(100_000),
+            },
+        );
+
+        let trade = simulator.simulate_liquidation_trade(uuid::Uuid::new_v4(), &"ETH".to_string(), Decimal::from(100)).await.unwrap();
+
+        // 100_000 * 100 / (1_000 + 100) = 9_090.91 for 100 ETH quoted at 100 each
+        assert!(close(trade.effective_execution_price, 100_000.0 / 1_100.0, 1e-9));
+        assert!(close(trade.price_impact_percent, 100.0 / 11.0, 1e-9));
+        assert!(close(trade.expected_outcome.estimated_proceeds_usd, 10_000_000.0 / 1_100.0, 1e-6));
+        assert!(matches!(trade.recommended_action, RecommendedAction::ExecuteWithCaution));
+    }
+
+    #[tokio::test]
+    async fn test_stable_swap_is_flatter_than_constant_product() {
+        let token_reserve = Decimal::from(1_000_000);
+        let quote_reserve = Decimal::from(1_000_000);
+        let stable = LiquidityCurve::StableSwap { token_reserve, quote_reserve, amplification: Decimal::from(100) };
+        let constant_product = LiquidityCurve::ConstantProduct { token_reserve, quote_reserve };
+
+        assert!(close(stable.spot_price().unwrap(), 1.0, 1e-4));
+
+        let amount = Decimal::from(100_000);
+        let stable_proceeds = stable.sell_proceeds(amount).unwrap();
+        let constant_product_proceeds = constant_product.sell_proceeds(amount).unwrap();
+        assert!(close(stable_proceeds, 99_949.78, 1.0), "{}", stable_proceeds);
+        assert!(stable_proceeds > constant_product_proceeds);
+
+        assert!(stable.sell_proceeds(Decimal::ZERO).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_two_hop_route_compounds_slippage_of_direct_route() {
+        let cp = |token_reserve: i64, quote_reserve: i64| LiquidityCurve::ConstantProduct {
+            token_reserve: Decimal::from(token_reserve),
+            quote_reserve: Decimal::from(quote_reserve),
+        };
+        // Both routes quote ILLIQ at 20 USDC; the direct pool is as deep as the ILLIQ/WETH pool
+        let simulator = PriceImpactSimulator::new(Box::new(NoHistory))
+            .with_pool("ILLIQ".to_string(), "WETH".to_string(), cp(10_000, 100))
+            .with_pool("USDC".to_string(), "WETH".to_string(), cp(2_000_000, 1_000))
+            .with_pool("ILLIQ".to_string(), "USDC".to_string(), cp(10_000, 200_000));
+        let amount = Decimal::from(1_000);
+
+        let direct = simulator.simulate_route(&["ILLIQ".to_string(), "USDC".to_string()], amount).unwrap();
+        assert!(close(direct.amount_out, 200_000.0 / 11.0, 1e-6));
+        assert!(close(direct.cumulative_slippage_percent, 100.0 / 11.0, 1e-9));
+
+        let path = ["ILLIQ".to_string(), "WETH".to_string(), "USDC".to_string()];
+        let routed = simulator.simulate_route(&path, amount).unwrap();
+        assert_eq!(routed.hops.len(), 2);
+        assert!(close(routed.spot_amount_out, 20_000.0, 1e-6));
+        // The first hop costs as much as the whole direct trade, the reversed WETH pool adds ~0.9%
+        assert!(close(routed.hops[0].price_impact_percent, 100.0 / 11.0, 1e-9));
+        assert!(close(routed.hops[1].price_impact_percent, 100.0 / 111.0, 1e-9));
+        assert_eq!(routed.dominant_hop, 0);
+        assert!(routed.cumulative_slippage_percent > direct.cumulative_slippage_percent);
+        assert!(close(routed.cumulative_slippage_percent, 100.0 * (1.0 - (10.0 / 11.0) * (110.0 / 111.0)), 1e-9));
+
+        let trade = simulator.simulate_liquidation_route(uuid::Uuid::new_v4(), &path, amount).await.unwrap();
+        assert_eq!(trade.route.as_ref().unwrap().dominant_hop, 0);
+        assert_eq!(trade.price_impact_percent, routed.cumulative_slippage_percent);
+        assert_eq!(trade.expected_outcome.estimated_proceeds_usd, routed.amount_out);
+
+        assert!(simulator.simulate_route(&["ILLIQ".to_string(), "DAI".to_string()], amount).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::risk::{ExecutionResult, IdempotencyKey, ProposedTrade, TradeExecutor};
+use crate::types::{PositionId, RetryPolicy};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TradeExecutionError {
+    /// Worth retrying, e.g. a connection refused before the transaction was submitted
+    #[error("Transient execution failure: {message}")]
+    Transient { message: String },
+    /// Retrying cannot help, e.g. insufficient balance or a reverted transaction
+    #[error("Execution rejected: {message}")]
+    Permanent { message: String },
+    #[error("{operation} for position {position_id} failed after {attempts} attempt(s): {source}")]
+    Failed {
+        operation: &'static str,
+        position_id: PositionId,
+        attempts: u32,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl TradeExecutionError {
+    /// Whether `error` is worth retrying. Executors signal this by returning
+    /// `TradeExecutionError::Transient`; failures to connect count as transient too.
+    /// Anything else is treated as permanent, since retrying an unknown failure risks
+    /// executing the same trade twice. That includes timeouts, which can come after the
+    /// transaction was broadcast.
+    pub fn is_retryable(error: &(dyn Error + Send + Sync + 'static)) -> bool {
+        if let Some(error) = error.downcast_ref::<TradeExecutionError>() {
+            return matches!(error, TradeExecutionError::Transient { .. });
+        }
+        if let Some(error) = error.downcast_ref::<std::io::Error>() {
+            return matches!(
+                error.kind(),
+                std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::Interrupted
+            );
+        }
+        if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            return error.is_connect() && !error.is_timeout();
+        }
+        false
+    }
+}
+
+/// Retries transient failures of the wrapped executor with exponential backoff.
+///
+/// Permanent failures are returned after the first attempt. Either way the final error
+/// is a `TradeExecutionError::Failed` carrying the attempt count and the last error.
+pub struct RetryingTradeExecutor {
+    inner: Arc<dyn TradeExecutor>,
+    policy: RetryPolicy,
+}
+
+impl RetryingTradeExecutor {
+    pub fn new(inner: Arc<dyn TradeExecutor>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    async fn with_retry<F, Fut>(
+        &self,
+        operation: &'static str,
+        position_id: PositionId,
+        mut execute: F,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<ExecutionResult, Box<dyn Error + Send + Sync>>>,
+    {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            match execute().await {
+                Ok(result) => {
+                    if attempt > 1 {
+                        info!("{} for position {} succeeded on attempt {}", operation, position_id, attempt);
+                    }
+                    return Ok(result);
+                }
+                Err(e) if attempt >= max_attempts || !TradeExecutionError::is_retryable(e.as_ref()) => {
+                    error!("Giving up on {} for position {} after {} attempt(s): {}", operation, position_id, attempt, e);
+                    return Err(Box::new(TradeExecutionError::Failed {
+                        operation,
+                        position_id,
+                        attempts: attempt,
+                        source: e,
+                    }));
+                }
+                Err(e) => {
+                    let delay = self.policy.backoff_delay(attempt, &mut rand::thread_rng());
+                    warn!("{} for position {} failed (attempt {}/{}), retrying in {:?}: {}",
+                          operation, position_id, attempt, max_attempts, delay, e);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TradeExecutor for RetryingTradeExecutor {
+    async fn execute_position_reduction(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.with_retry("Position reduction", position_id, move || {
+            self.inner.execute_position_reduction(position_id, token_address, amount)
+        }).await
+    }
+
+    async fn emergency_exit_position(
+        &self,
+        position_id: PositionId,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.with_retry("Emergency exit", position_id, move || self.inner.emergency_exit_position(position_id)).await
+    }
+
+    async fn add_collateral(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.with_retry("Collateral top-up", position_id, move || {
+            self.inner.add_collateral(position_id, token_address, amount)
+        }).await
+    }
+
+    async fn repay_debt(
+        &self,
+        position_id: PositionId,
+        token_address: &str,
+        amount: Decimal,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        self.with_retry("Debt repayment", position_id, move || {
+            self.inner.repay_debt(position_id, token_address, amount)
+        }).await
+    }
+
+    /// Forwarded so an inner executor that tracks keys still sees them on every attempt
+    async fn execute_keyed(
+        &self,
+        key: &IdempotencyKey,
+        trade: &ProposedTrade,
+    ) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+        let operation = match trade {
+            ProposedTrade::ReducePosition { .. } => "Position reduction",
+            ProposedTrade::EmergencyExit => "Emergency exit",
+        };
+        self.with_retry(operation, key.position_id, move || self.inner.execute_keyed(key, trade)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    /// Fails with the given error until `succeed_on` calls have been made
+    struct FlakyExecutor {
+        calls: AtomicU32,
+        succeed_on: u32,
+        permanent: bool,
+    }
+
+    impl FlakyExecutor {
+        fn attempt(&self) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call >= self.succeed_on {
+                return Ok(ExecutionResult {
+                    success: true,
+                    transaction_hash: Some(format!("0x{:02x}", call)),
+                    amount_executed: None,
+                    actual_price_impact: None,
+                    gas_used: None,
+                    error_message: None,
+                });
+            }
+            let message = format!("call {} failed", call);
+            Err(Box::new(if self.permanent {
+                TradeExecutionError::Permanent { message }
+            } else {
+                TradeExecutionError::Transient { message }
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl TradeExecutor for FlakyExecutor {
+        async fn execute_position_reduction(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.attempt()
+        }
+
+        async fn emergency_exit_position(&self, _position_id: PositionId) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.attempt()
+        }
+
+        async fn add_collateral(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.attempt()
+        }
+
+        async fn repay_debt(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn Error + Send + Sync>> {
+            self.attempt()
+        }
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried_until_success() {
+        let flaky = Arc::new(FlakyExecutor { calls: AtomicU32::new(0), succeed_on: 3, permanent: false });
+        let executor = RetryingTradeExecutor::new(flaky.clone(), policy(5));
+
+        let result = executor.emergency_exit_position(Uuid::new_v4()).await.unwrap();
+        assert_eq!(result.transaction_hash.as_deref(), Some("0x03"));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        // Two attempts are not enough, and the error reports how many were made
+        let flaky = Arc::new(FlakyExecutor { calls: AtomicU32::new(0), succeed_on: 3, permanent: false });
+        let executor = RetryingTradeExecutor::new(flaky.clone(), policy(2));
+        let error = executor.repay_debt(Uuid::new_v4(), "USDC", Decimal::ONE).await.unwrap_err();
+        match error.downcast_ref::<TradeExecutionError>() {
+            Some(TradeExecutionError::Failed { operation, attempts, source, .. }) => {
+                assert_eq!(*operation, "Debt repayment");
+                assert_eq!(*attempts, 2);
+                assert!(TradeExecutionError::is_retryable(source.as_ref()));
+            }
+            other => panic!("expected a failed execution, got {:?}", other),
+        }
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failures_are_not_retried() {
+        let flaky = Arc::new(FlakyExecutor { calls: AtomicU32::new(0), succeed_on: 3, permanent: true });
+        let executor = RetryingTradeExecutor::new(flaky.clone(), policy(5));
+
+        let error = executor.add_collateral(Uuid::new_v4(), "ETH", Decimal::ONE).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<TradeExecutionError>(),
+            Some(TradeExecutionError::Failed { attempts: 1, .. })
+        ));
+        assert!(error.to_string().contains("failed after 1 attempt(s): Execution rejected: call 1 failed"));
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+
+        // Errors from executors that don't classify them are not retried either
+        let plain: Box<dyn Error + Send + Sync> = "rpc returned garbage".into();
+        assert!(!TradeExecutionError::is_retryable(plain.as_ref()));
+        // A timeout may come after the transaction was broadcast
+        let timeout: Box<dyn Error + Send + Sync> = Box::new(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(!TradeExecutionError::is_retryable(timeout.as_ref()));
+        let refused: Box<dyn Error + Send + Sync> = Box::new(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert!(TradeExecutionError::is_retryable(refused.as_ref()));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::security::vulnerability_detector::{AuditDatabase, VulnerabilitySeverity};
+use crate::types::{RiskAlert, AlertType, RiskLevel, TokenAddress};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
+
+/// 2^255: allowances at or above this are treated as unlimited, matching the
+/// convention wallets and revoke tools use for "infinite" approvals
+const UNLIMITED_THRESHOLD: &str = "57896044618658097711785492504343953926634992332820282019728792003956564819968";
+
+/// One ERC-20 allowance held by a wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenApproval {
+    pub token: TokenAddress,
+    pub spender: String,
+    /// Raw uint256 allowance in the token's base units, decimal or 0x-prefixed hex
+    pub allowance: String,
+}
+
+impl TokenApproval {
+    pub fn is_unlimited(&self) -> bool {
+        let allowance = self.allowance.trim();
+        match allowance.strip_prefix("0x") {
+            Some(hex) => {
+                let digits = hex.trim_start_matches('0');
+                digits.len() > 64 || (digits.len() == 64 && digits.starts_with(|c: char| c >= '8'))
+            }
+            None => {
+                let digits = allowance.trim_start_matches('0');
+                digits.len() > UNLIMITED_THRESHOLD.len()
+                    || (digits.len() == UNLIMITED_THRESHOLD.len() && digits >= UNLIMITED_THRESHOLD)
+            }
+        }
+    }
+}
+
+/// Flags risky token approvals using spender reputation from an audit database.
+///
+/// Any allowance to a blacklisted or exploited spender is an emergency. Unlimited
+/// allowances are flagged when the spender has no audit on record or has open findings;
+/// bounded allowances to spenders without a bad reputation are left alone.
+pub struct ApprovalScanner {
+    audit_database: Arc<dyn AuditDatabase>,
+    blacklist: HashSet<String>,
+}
+
+impl ApprovalScanner {
+    pub fn new(audit_database: Arc<dyn AuditDatabase>) -> Self {
+        Self {
+            audit_database,
+            blacklist: HashSet::new(),
+        }
+    }
+
+    pub fn with_blacklisted_spender(mut self, spender: &str) -> Self {
+        self.blacklist.insert(spender.to_lowercase());
+        self
+    }
+
+    /// Alerts for a wallet's approvals, most severe first
+    pub async fn scan(&self, approvals: &[TokenApproval]) -> Vec<RiskAlert> {
+        let mut alerts = Vec::new();
+
+        for approval in approvals {
+            let unlimited = approval.is_unlimited();
+            let (risk_level, reason) = match self.assess_spender(&approval.spender, unlimited).await {
+                Some(assessment) => assessment,
+                None => continue,
+            };
+
+            let message = format!(
+                "{} approval of {} to {} spender {}",
+                if unlimited { "Unlimited" } else { "Bounded" },
+                approval.token, reason, approval.spender
+            );
+            alerts.push(RiskAlert {
+                related_tokens: vec![approval.token.clone()],
+                ..RiskAlert::portfolio(AlertType::ApprovalRisk, risk_level, message)
+            });
+        }
+
+        alerts.sort_by(|a, b| b.risk_level.cmp(&a.risk_level));
+        alerts
+    }
+
+    async fn assess_spender(&self, spender: &str, unlimited: bool) -> Option<(RiskLevel, &'static str)> {
+        if self.blacklist.contains(&spender.to_lowercase()) {
+            return Some((RiskLevel::Emergency, "blacklisted"));
+        }
+
+        let findings = match self.audit_database.check_contract(spender).await {
+            Ok(findings) => findings,
+            Err(e) => {
+                warn!("Audit lookup for spender {} failed: {}", spender, e);
+                Vec::new()
+            }
+        };
+        let worst = findings.iter().map(|finding| finding.severity.clone()).max_by_key(|severity| severity.score());
+
+        match worst {
+            Some(VulnerabilitySeverity::Critical) => return Some((RiskLevel::Emergency, "exploited or critically vulnerable")),
+            Some(VulnerabilitySeverity::High) if unlimited => return Some((RiskLevel::Critical, "vulnerable")),
+            _ => {}
+        }
+        if !unlimited {
+            return None;
+        }
+
+        let audited = match self.audit_database.has_audit(spender).await {
+            Ok(audited) => audited,
+            Err(e) => {
+                warn!("Audit lookup for spender {} failed: {}", spender, e);
+                false
+            }
+        };
+        if !audited {
+            Some((RiskLevel::Critical, "unaudited"))
+        } else if worst.is_some() {
+            Some((RiskLevel::Warning, "audited but flagged"))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use crate::security::audit_database::{AuditRecord, LocalAuditDatabase, SeveritySummary};
+
+    const MAX_UINT256: &str = "0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+
+    fn approval(token: &str, spender: &str, allowance: &str) -> TokenApproval {
+        TokenApproval {
+            token: token.to_string(),
+            spender: spender.to_string(),
+            allowance: allowance.to_string(),
+        }
+    }
+
+    fn audited(spender: &str, exploited: bool) -> AuditRecord {
+        AuditRecord {
+            contract_address: spender.to_string(),
+            auditor: "OpenZeppelin".to_string(),
+            audit_date: Utc::now(),
+            severity_summary: SeveritySummary::default(),
+            vulnerable_selectors: if exploited { vec!["0x3ccfd60b".to_string()] } else { vec![] },
+            exploited,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_unknown_spender_flagged_bounded_known_safe_ignored() {
+        let database = LocalAuditDatabase::new();
+        database.add_record(audited("0xRouter", false));
+        let scanner = ApprovalScanner::new(Arc::new(database));
+
+        let unknown = approval("0xusdc", "0xdrainer", MAX_UINT256);
+        let bounded = approval("0xweth", "0xrouter", "1000000000000000000");
+        assert!(unknown.is_unlimited());
+        assert!(!bounded.is_unlimited());
+        assert!(approval("0xdai", "0xrouter", UNLIMITED_THRESHOLD).is_unlimited());
+
+        let alerts = scanner.scan(&[bounded, unknown]).await;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::ApprovalRisk);
+        assert_eq!(alerts[0].risk_level, RiskLevel::Critical);
+        assert_eq!(alerts[0].related_tokens, vec!["0xusdc".to_string()]);
+        assert!(alerts[0].message.contains("unaudited spender 0xdrainer"));
+
+        // An unlimited approval to the audited router is fine too
+        assert!(scanner.scan(&[approval("0xweth", "0xrouter", MAX_UINT256)]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_alerts_ranked_by_severity() {
+        let database = LocalAuditDatabase::new();
+        database.add_record(audited("0xhacked", true));
+        let scanner = ApprovalScanner::new(Arc::new(database)).with_blacklisted_spender("0xPhisher");
+
+        let alerts = scanner.scan(&[
+            approval("0xusdc", "0xunknown", MAX_UINT256),
+            approval("0xweth", "0xphisher", "5"),
+            approval("0xdai", "0xhacked", "100"),
+        ]).await;
+
+        let levels: Vec<RiskLevel> = alerts.iter().map(|alert| alert.risk_level.clone()).collect();
+        assert_eq!(levels, vec![RiskLevel::Emergency, RiskLevel::Emergency, RiskLevel::Critical]);
+        assert!(alerts[0].message.contains("blacklisted"));
+        assert!(alerts[1].message.contains("exploited"));
+        assert!(alerts[2].message.contains("0xunknown"));
+    }
+}
@@ -3,7 +3,9 @@ use crate::security::vulnerability_detector::{
 };
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use dashmap::DashMap;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn, debug, error};
@@ -152,6 +154,136 @@ impl AuditDatabaseManager {
     }
 }
 
+/// Finding counts from one audit, by severity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeveritySummary {
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+impl SeveritySummary {
+    pub fn highest(&self) -> Option<VulnerabilitySeverity> {
+        if self.critical > 0 {
+            Some(VulnerabilitySeverity::Critical)
+        } else if self.high > 0 {
+            Some(VulnerabilitySeverity::High)
+        } else if self.medium > 0 {
+            Some(VulnerabilitySeverity::Medium)
+        } else if self.low > 0 {
+            Some(VulnerabilitySeverity::Low)
+        } else {
+            None
+        }
+    }
+}
+
+/// Known audit or exploit history for one contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub contract_address: String,
+    pub auditor: String,
+    pub audit_date: DateTime<Utc>,
+    pub severity_summary: SeveritySummary,
+    /// Function selectors with unresolved findings, e.g. `0xa9059cbb`
+    pub vulnerable_selectors: Vec<String>,
+    /// The contract has been exploited in the wild
+    #[serde(default)]
+    pub exploited: bool,
+}
+
+/// In-memory store of known audits and exploits, keyed by contract address.
+///
+/// Clones share the same records, so one copy can be registered with an
+/// `AuditDatabaseManager` while another keeps receiving `add_record` calls.
+#[derive(Debug, Clone, Default)]
+pub struct LocalAuditDatabase {
+    records: Arc<DashMap<String, AuditRecord>>,
+}
+
+impl LocalAuditDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load records from a JSON array of `AuditRecord`
+    pub async fn load_from_file(path: impl AsRef<Path>) -> Result<Self, VulnerabilityDetectionError> {
+        let path = path.as_ref();
+        let contents = tokio::fs::read(path).await.map_err(|e| VulnerabilityDetectionError::DatabaseError {
+            message: format!("Failed to read {}: {}", path.display(), e),
+        })?;
+        let records: Vec<AuditRecord> = serde_json::from_slice(&contents).map_err(|e| VulnerabilityDetectionError::DatabaseError {
+            message: format!("Invalid audit records in {}: {}", path.display(), e),
+        })?;
+
+        let database = Self::new();
+        for record in records {
+            database.add_record(record);
+        }
+        info!("Loaded {} audit records from {}", database.records.len(), path.display());
+        Ok(database)
+    }
+
+    /// Add or replace the record for a contract
+    pub fn add_record(&self, record: AuditRecord) {
+        self.records.insert(record.contract_address.to_lowercase(), record);
+    }
+
+    /// Address lookups ignore checksum casing
+    pub fn lookup(&self, contract_address: &str) -> Option<AuditRecord> {
+        self.records.get(&contract_address.to_lowercase()).map(|record| record.clone())
+    }
+}
+
+#[async_trait]
+impl AuditDatabase for LocalAuditDatabase {
+    async fn check_contract(&self, contract_address: &str) -> Result<Vec<Vulnerability>, Box<dyn std::error::Error + Send + Sync>> {
+        let record = match self.lookup(contract_address) {
+            Some(record) => record,
+            None => return Ok(vec![]),
+        };
+
+        let severity = if record.exploited {
+            VulnerabilitySeverity::Critical
+        } else {
+            record.severity_summary.highest().unwrap_or(VulnerabilitySeverity::Medium)
+        };
+        let vulnerabilities = record.vulnerable_selectors.iter()
+            .map(|selector| Vulnerability {
+                id: format!("known_{}_{}", record.auditor.to_lowercase().replace(' ', "_"), selector),
+                severity: severity.clone(),
+                category: VulnerabilityCategory::Other("known_finding".to_string()),
+                description: format!(
+                    "Function {} flagged by {} audit on {}",
+                    selector, record.auditor, record.audit_date.format("%Y-%m-%d")
+                ),
+                impact: if record.exploited {
+                    "Contract has been exploited through this function".to_string()
+                } else {
+                    "Unresolved audit finding".to_string()
+                },
+                confidence: if record.exploited { 100 } else { 90 },
+                cvss_score: None,
+                cwe_id: None,
+                affected_functions: vec![selector.clone()],
+                proof_of_concept: None,
+                remediation: Some("Avoid calling this function until the finding is resolved".to_string()),
+            })
+            .collect();
+
+        Ok(vulnerabilities)
+    }
+
+    fn name(&self) -> String {
+        "Local".to_string()
+    }
+
+    async fn has_audit(&self, contract_address: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.lookup(contract_address).is_some())
+    }
+}
+
 // CertiK Database Implementation
 #[derive(Debug)]
 pub struct CertiKDatabase {
@@ -614,4 +746,64 @@ impl RateLimiter {
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(address: &str) -> AuditRecord {
+        AuditRecord {
+            contract_address: address.to_string(),
+            auditor: "Trail of Bits".to_string(),
+            audit_date: Utc::now(),
+            severity_summary: SeveritySummary { high: 1, low: 3, ..SeveritySummary::default() },
+            vulnerable_selectors: vec!["0x2e1a7d4d".to_string(), "0xa9059cbb".to_string()],
+            exploited: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_hit_and_miss() {
+        let database = LocalAuditDatabase::new();
+        let shared = database.clone();
+        database.add_record(record("0xAbCd000000000000000000000000000000000001"));
+
+        let found = shared.lookup("0xabcd000000000000000000000000000000000001").unwrap();
+        assert_eq!(found.auditor, "Trail of Bits");
+        assert_eq!(found.severity_summary.highest(), Some(VulnerabilitySeverity::High));
+        assert!(shared.lookup("0xabcd000000000000000000000000000000000002").is_none());
+
+        let vulnerabilities = shared.check_contract("0xABCD000000000000000000000000000000000001").await.unwrap();
+        assert_eq!(vulnerabilities.len(), 2);
+        assert_eq!(vulnerabilities[0].affected_functions, vec!["0x2e1a7d4d".to_string()]);
+        assert!(matches!(vulnerabilities[0].severity, VulnerabilitySeverity::High));
+        assert!(shared.check_contract("0xabcd000000000000000000000000000000000002").await.unwrap().is_empty());
+
+        // A later exploit replaces the audit record and escalates its findings
+        let mut exploited = record("0xabcd000000000000000000000000000000000001");
+        exploited.exploited = true;
+        database.add_record(exploited);
+        let vulnerabilities = shared.check_contract("0xabcd000000000000000000000000000000000001").await.unwrap();
+        assert!(matches!(vulnerabilities[0].severity, VulnerabilitySeverity::Critical));
+    }
+
+    #[tokio::test]
+    async fn test_load_records_from_file() {
+        let path = std::env::temp_dir().join(format!("aegis-audits-{}.json", uuid::Uuid::new_v4()));
+        let records = vec![record("0x01"), record("0x02")];
+        tokio::fs::write(&path, serde_json::to_vec(&records).unwrap()).await.unwrap();
+
+        let database = LocalAuditDatabase::load_from_file(&path).await.unwrap();
+        assert_eq!(database.lookup("0x01"), Some(records[0].clone()));
+        assert_eq!(database.lookup("0x02"), Some(records[1].clone()));
+        assert!(database.lookup("0x03").is_none());
+
+        tokio::fs::write(&path, b"[{ truncated").await.unwrap();
+        assert!(matches!(
+            LocalAuditDatabase::load_from_file(&path).await,
+            Err(VulnerabilityDetectionError::DatabaseError { .. })
+        ));
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -1,5 +1,6 @@
-use crate::security::{SecurityAlert, SecurityAlertType, SecurityAlertSeverity, ExploitPattern};
-use crate::types::{RiskAlert, AlertType, RiskLevel, PositionId};
+use crate::security::{SecurityAlert, SecurityAlertType, SecurityAlertSeverity, ExploitPattern, TransactionData};
+use crate::liquidation::AlertSystem;
+use crate::types::{RiskAlert, AlertType, RiskLevel};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
@@ -12,8 +13,9 @@ use tracing::{info, warn, debug, error};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use reqwest::Client;
+use regex::Regex;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone)]
 pub struct ExploitDiscoveryMonitor {
     threat_intel_feeds: Vec<Box<dyn ThreatIntelligenceFeed>>,
     known_exploits: Arc<DashMap<String, KnownExploit>>,
@@ -22,6 +24,8 @@ pub struct ExploitDiscoveryMonitor {
     alert_sender: mpsc::UnboundedSender<SecurityAlert>,
     config: Arc<RwLock<ExploitMonitorConfig>>,
     client: Client,
+    signature_db: Option<Arc<ExploitSignatureDb>>,
+    alert_system: Option<Arc<dyn AlertSystem>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -173,6 +177,8 @@ impl ExploitDiscoveryMonitor {
             alert_sender,
             config: Arc::new(RwLock::new(ExploitMonitorConfig::default())),
             client: Client::new(),
+            signature_db: None,
+            alert_system: None,
         };
 
         // Initialize threat intelligence feeds
@@ -181,6 +187,55 @@ impl ExploitDiscoveryMonitor {
         (monitor, alert_receiver)
     }
 
+    /// Match incoming transactions against a hot-reloadable signature set
+    pub fn with_signature_db(mut self, signature_db: Arc<ExploitSignatureDb>) -> Self {
+        self.signature_db = Some(signature_db);
+        self
+    }
+
+    /// Raise `scan_transaction` detections through `alert_system`
+    pub fn with_alert_system(mut self, alert_system: Arc<dyn AlertSystem>) -> Self {
+        self.alert_system = Some(alert_system);
+        self
+    }
+
+    /// Match a transaction against the current exploit signatures, raising and returning one
+    /// `ContractVulnerability` alert per matching signature
+    pub async fn scan_transaction(&self, transaction: &TransactionData) -> Vec<RiskAlert> {
+        let signature_db = match &self.signature_db {
+            Some(signature_db) => signature_db,
+            None => return Vec::new(),
+        };
+
+        let version = signature_db.version().await;
+        let mut alerts = Vec::new();
+        for signature in signature_db.match_transaction(transaction).await {
+            warn!("Transaction {} matches exploit signature {} (v{})", transaction.hash, signature.id, version);
+            let risk_level = match signature.severity {
+                ExploitSeverity::Critical => RiskLevel::Emergency,
+                ExploitSeverity::High => RiskLevel::Critical,
+                _ => RiskLevel::Warning,
+            };
+            let mut alert = RiskAlert::portfolio(
+                AlertType::ContractVulnerability,
+                risk_level,
+                format!(
+                    "Transaction {} to {} matches exploit signature {} (v{}): {}",
+                    transaction.hash, transaction.to_address, signature.id, version, signature.description
+                ),
+            );
+            alert.related_tokens = vec![transaction.to_address.clone()];
+
+            if let Some(alert_system) = &self.alert_system {
+                if let Err(e) = alert_system.send_alert(alert.clone()).await {
+                    error!("Failed to raise exploit signature alert for {}: {}", transaction.hash, e);
+                }
+            }
+            alerts.push(alert);
+        }
+        alerts
+    }
+
     fn initialize_threat_feeds(&mut self) {
         // Add various threat intelligence sources
         self.threat_intel_feeds.push(Box::new(DeFiThreatFeed::new()));
@@ -448,6 +503,15 @@ impl ExploitDiscoveryMonitor {
     }
 }
 
+impl std::fmt::Debug for ExploitDiscoveryMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExploitDiscoveryMonitor")
+            .field("threat_intel_feeds", &self.threat_intel_feeds.len())
+            .field("signature_db", &self.signature_db)
+            .finish()
+    }
+}
+
 impl Clone for ExploitDiscoveryMonitor {
     fn clone(&self) -> Self {
         Self {
@@ -458,10 +522,107 @@ impl Clone for ExploitDiscoveryMonitor {
             alert_sender: self.alert_sender.clone(),
             config: self.config.clone(),
             client: self.client.clone(),
+            signature_db: self.signature_db.clone(),
+            alert_system: self.alert_system.clone(),
         }
     }
 }
 
+/// Known exploit call shape: a function selector plus a regex over the calldata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExploitSignature {
+    pub id: String,
+    /// 4-byte function selector, e.g. `0xa9059cbb`
+    pub selector: String,
+    /// Regex matched against the lowercase hex calldata, selector included
+    pub calldata_pattern: String,
+    pub description: String,
+    pub severity: ExploitSeverity,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SignatureFile {
+    signatures: Vec<ExploitSignature>,
+}
+
+struct SignatureSet {
+    version: u64,
+    signatures: Vec<(ExploitSignature, Regex)>,
+}
+
+/// Exploit signatures loaded from a JSON file of the form `{"signatures": [...]}`.
+///
+/// `reload` re-reads the file and swaps the whole set at once, bumping `version`; a file
+/// that fails to parse or compile leaves the current set and version in place.
+pub struct ExploitSignatureDb {
+    path: PathBuf,
+    current: RwLock<SignatureSet>,
+}
+
+impl std::fmt::Debug for ExploitSignatureDb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExploitSignatureDb").field("path", &self.path).finish()
+    }
+}
+
+impl ExploitSignatureDb {
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = path.as_ref().to_path_buf();
+        let signatures = Self::read_signatures(&path).await?;
+        info!("Loaded {} exploit signatures from {}", signatures.len(), path.display());
+
+        Ok(Self {
+            path,
+            current: RwLock::new(SignatureSet { version: 1, signatures }),
+        })
+    }
+
+    /// Re-read the signature file, returning the new version
+    pub async fn reload(&self) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let signatures = Self::read_signatures(&self.path).await?;
+        let mut current = self.current.write().await;
+        current.version += 1;
+        info!("Reloaded {} exploit signatures, now at version {}", signatures.len(), current.version);
+        current.signatures = signatures;
+        Ok(current.version)
+    }
+
+    pub async fn version(&self) -> u64 {
+        self.current.read().await.version
+    }
+
+    pub async fn signatures(&self) -> Vec<ExploitSignature> {
+        self.current.read().await.signatures.iter().map(|(signature, _)| signature.clone()).collect()
+    }
+
+    /// Signatures whose selector and calldata pattern both match `transaction`
+    pub async fn match_transaction(&self, transaction: &TransactionData) -> Vec<ExploitSignature> {
+        let calldata = transaction.input_data.to_lowercase();
+        let selector = match &transaction.function_selector {
+            Some(selector) => selector.to_lowercase(),
+            None => calldata.chars().take(10).collect(),
+        };
+
+        let current = self.current.read().await;
+        current.signatures.iter()
+            .filter(|(signature, pattern)| signature.selector.to_lowercase() == selector && pattern.is_match(&calldata))
+            .map(|(signature, _)| signature.clone())
+            .collect()
+    }
+
+    async fn read_signatures(path: &Path) -> Result<Vec<(ExploitSignature, Regex)>, Box<dyn std::error::Error + Send + Sync>> {
+        let contents = tokio::fs::read(path).await?;
+        let file: SignatureFile = serde_json::from_slice(&contents)?;
+
+        file.signatures.into_iter()
+            .map(|signature| match Regex::new(&signature.calldata_pattern) {
+                Ok(pattern) => Ok((signature, pattern)),
+                Err(e) => Err(format!("Invalid calldata pattern for signature {}: {}", signature.id, e).into()),
+            })
+            .collect()
+    }
+}
+
 // Threat Intelligence Feed trait and implementations
 #[async_trait]
 pub trait ThreatIntelligenceFeed: Send + Sync {
@@ -635,4 +796,93 @@ impl ThreatIntelligenceFeed for CTIFeed {
     fn name(&self) -> String {
         "Cyber Threat Intelligence Feed".to_string()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    // transferFrom(from, to, amount) with `from` left as the zero address
+    const TRANSFER_FROM: &str = "0x23b872dd";
+    const ZERO_FROM: &str = "^0x23b872dd0{64}";
+
+    fn signature_file(signatures: &[(&str, &str, &str)]) -> String {
+        let signatures: Vec<ExploitSignature> = signatures.iter()
+            .map(|(id, selector, pattern)| ExploitSignature {
+                id: id.to_string(),
+                selector: selector.to_string(),
+                calldata_pattern: pattern.to_string(),
+                description: format!("{} test signature", id),
+                severity: ExploitSeverity::Critical,
+            })
+            .collect();
+        serde_json::to_string(&SignatureFile { signatures }).unwrap()
+    }
+
+    fn call(input_data: String) -> TransactionData {
+        TransactionData {
+            hash: "0xtx".to_string(),
+            from_address: "0xattacker".to_string(),
+            to_address: "0xvault".to_string(),
+            value: Decimal::ZERO,
+            gas_used: 80_000,
+            gas_price: Decimal::from(30),
+            timestamp: Utc::now(),
+            function_selector: None,
+            input_data,
+            success: true,
+            block_number: 1,
+            transaction_index: 0,
+            raw_transaction: None,
+        }
+    }
+
+    fn transfer_from(from: &str) -> TransactionData {
+        call(format!("{}{:0>64}{:0>64}{:0>64}", TRANSFER_FROM, from, "beef", "64"))
+    }
+
+    #[tokio::test]
+    async fn test_scan_matches_selector_and_calldata() {
+        let path = std::env::temp_dir().join(format!("aegis-signatures-{}.json", Uuid::new_v4()));
+        tokio::fs::write(&path, signature_file(&[("zero_from", TRANSFER_FROM, ZERO_FROM)])).await.unwrap();
+        let db = Arc::new(ExploitSignatureDb::load(&path).await.unwrap());
+        let (monitor, _alerts) = ExploitDiscoveryMonitor::new();
+        let monitor = monitor.with_signature_db(db.clone());
+
+        let alerts = monitor.scan_transaction(&transfer_from("0")).await;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::ContractVulnerability);
+        assert_eq!(alerts[0].risk_level, RiskLevel::Emergency);
+        assert!(alerts[0].message.contains("zero_from (v1)"));
+        assert_eq!(alerts[0].related_tokens, vec!["0xvault".to_string()]);
+
+        // Same selector with a real sender, and a different selector, are both clean
+        assert!(monitor.scan_transaction(&transfer_from("abc")).await.is_empty());
+        assert!(monitor.scan_transaction(&call(format!("0xa9059cbb{:0>128}", "0"))).await.is_empty());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_bumps_version_and_swaps_signatures() {
+        let path = std::env::temp_dir().join(format!("aegis-signatures-{}.json", Uuid::new_v4()));
+        tokio::fs::write(&path, signature_file(&[])).await.unwrap();
+        let db = ExploitSignatureDb::load(&path).await.unwrap();
+        assert_eq!(db.version().await, 1);
+        assert!(db.match_transaction(&transfer_from("0")).await.is_empty());
+
+        tokio::fs::write(&path, signature_file(&[("zero_from", TRANSFER_FROM, ZERO_FROM)])).await.unwrap();
+        assert_eq!(db.reload().await.unwrap(), 2);
+        assert_eq!(db.version().await, 2);
+        assert_eq!(db.match_transaction(&transfer_from("0")).await[0].id, "zero_from");
+
+        // A broken update keeps the previous signatures and version
+        tokio::fs::write(&path, signature_file(&[("broken", TRANSFER_FROM, "(unclosed")])).await.unwrap();
+        assert!(db.reload().await.is_err());
+        assert_eq!(db.version().await, 2);
+        assert_eq!(db.signatures().await.len(), 1);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
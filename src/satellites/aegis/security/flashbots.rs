@@ -0,0 +1,325 @@
+use crate::security::{MevError, TransactionData};
+use k256::ecdsa::SigningKey;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// Identifier returned for a protected submission
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleHash {
+    /// Relay bundle hash, or the transaction hash when it went to the public mempool
+    pub hash: String,
+    pub via_public_mempool: bool,
+}
+
+/// Parameters of an `eth_sendBundle` call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlashbotsBundle {
+    /// Signed raw transactions, executed in order
+    pub txs: Vec<String>,
+    /// Target block as a 0x-prefixed hex quantity
+    pub block_number: String,
+}
+
+/// Blocks after the transaction's `block_number` that a submission targets by default
+pub const DEFAULT_TARGET_BLOCKS: u64 = 3;
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a, P: Serialize> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: Vec<P>,
+}
+
+/// Client submitting transactions as single-transaction bundles to a Flashbots relay.
+///
+/// Bundles are authenticated with the `X-Flashbots-Signature` header: an EIP-191 signature
+/// over the keccak hash of the request body. Transactions only reach the public mempool
+/// when a fallback RPC has been configured explicitly.
+pub struct FlashbotsRelay {
+    relay_url: String,
+    signing_key: SigningKey,
+    /// Public RPC used when the relay fails; `None` keeps transactions private
+    public_rpc_url: Option<String>,
+    /// Number of upcoming blocks each submission is bundled for
+    target_blocks: u64,
+    http_client: reqwest::Client,
+}
+
+impl FlashbotsRelay {
+    /// `signing_key` is a hex secp256k1 private key identifying the searcher to the relay;
+    /// it does not need to hold funds
+    pub fn new(relay_url: &str, signing_key: &str) -> Result<Self, MevError> {
+        let key_bytes = decode_hex(signing_key).map_err(|message| MevError::InvalidSigningKey { message })?;
+        let signing_key = SigningKey::from_slice(&key_bytes)
+            .map_err(|e| MevError::InvalidSigningKey { message: e.to_string() })?;
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(transport_error)?;
+
+        Ok(Self {
+            relay_url: relay_url.to_string(),
+            signing_key,
+            public_rpc_url: None,
+            target_blocks: DEFAULT_TARGET_BLOCKS,
+            http_client,
+        })
+    }
+
+    /// Bundle each submission for the next `blocks` blocks rather than `DEFAULT_TARGET_BLOCKS`;
+    /// at least one block is always targeted
+    pub fn with_target_blocks(mut self, blocks: u64) -> Self {
+        self.target_blocks = blocks.max(1);
+        self
+    }
+
+    /// Allow sending the raw transaction to `rpc_url` when the relay rejects the bundle
+    pub fn with_public_fallback(mut self, rpc_url: &str) -> Self {
+        self.public_rpc_url = Some(rpc_url.to_string());
+        self
+    }
+
+    /// Address derived from the signing key, as the relay sees it
+    pub fn signer_address(&self) -> String {
+        let point = self.signing_key.verifying_key().to_encoded_point(false);
+        let hash = keccak256(&point.as_bytes()[1..]);
+        format!("0x{}", encode_hex(&hash[12..]))
+    }
+
+    /// Submit `tx` privately, bundled for each of the blocks after its `block_number`. Succeeds
+    /// when the relay accepts any of them; the first accepted bundle's hash is returned.
+    pub async fn submit_protected(&self, tx: &TransactionData) -> Result<BundleHash, MevError> {
+        let bundles = bundles_for(tx, self.target_blocks)?;
+
+        let mut accepted = None;
+        let mut relay_error = None;
+        for bundle in &bundles {
+            match self.send_bundle(bundle).await {
+                Ok(hash) => {
+                    accepted.get_or_insert(hash);
+                }
+                Err(e) => {
+                    warn!("Relay rejected bundle for {} at block {}: {}", tx.hash, bundle.block_number, e);
+                    relay_error = Some(e);
+                }
+            }
+        }
+
+        match (accepted, relay_error) {
+            (Some(hash), _) => Ok(BundleHash { hash, via_public_mempool: false }),
+            (None, relay_error) => {
+                let relay_error = relay_error.unwrap_or_else(|| MevError::RelayRejected {
+                    message: "No bundles submitted".to_string(),
+                });
+                match &self.public_rpc_url {
+                    Some(rpc_url) => {
+                        warn!("Relay submission for {} failed ({}), falling back to public mempool", tx.hash, relay_error);
+                        let hash = self.send_raw_transaction(rpc_url, &bundles[0].txs[0]).await?;
+                        Ok(BundleHash { hash, via_public_mempool: true })
+                    }
+                    None => Err(relay_error),
+                }
+            }
+        }
+    }
+
+    /// `X-Flashbots-Signature` header value for a request body
+    pub fn signature_header(&self, body: &str) -> Result<String, MevError> {
+        let body_hash = format!("0x{}", encode_hex(&keccak256(body.as_bytes())));
+        Ok(format!("{}:{}", self.signer_address(), self.personal_sign(body_hash.as_bytes())?))
+    }
+
+    /// EIP-191 `personal_sign` signature as 0x-prefixed r || s || v
+    fn personal_sign(&self, message: &[u8]) -> Result<String, MevError> {
+        let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        prefixed.extend_from_slice(message);
+
+        let (signature, recovery_id) = self.signing_key
+            .sign_prehash_recoverable(&keccak256(&prefixed))
+            .map_err(|e| MevError::InvalidSigningKey { message: e.to_string() })?;
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        Ok(format!("0x{}", encode_hex(&bytes)))
+    }
+
+    async fn send_bundle(&self, bundle: &FlashbotsBundle) -> Result<String, MevError> {
+        let body = bundle_request_body(bundle)?;
+        let response = self.http_client
+            .post(&self.relay_url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", self.signature_header(&body)?)
+            .body(body)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let response: serde_json::Value = response.json().await.map_err(transport_error)?;
+        if let Some(error) = response.get("error") {
+            return Err(MevError::RelayRejected { message: error.to_string() });
+        }
+        response["result"]["bundleHash"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| MevError::RelayRejected { message: format!("No bundle hash in response: {}", response) })
+    }
+
+    async fn send_raw_transaction(&self, rpc_url: &str, raw_transaction: &str) -> Result<String, MevError> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
+            id: 1,
+            method: "eth_sendRawTransaction",
+            params: vec![raw_transaction],
+        };
+        let response: serde_json::Value = self.http_client
+            .post(rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(transport_error)?
+            .json()
+            .await
+            .map_err(transport_error)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(MevError::RelayRejected { message: error.to_string() });
+        }
+        response["result"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| MevError::RelayRejected { message: format!("No transaction hash in response: {}", response) })
+    }
+}
+
+/// Single-transaction bundles for `tx`, one for each of blocks
+/// `block_number + 1..=block_number + target_blocks`
+pub fn bundles_for(tx: &TransactionData, target_blocks: u64) -> Result<Vec<FlashbotsBundle>, MevError> {
+    let raw_transaction = match &tx.raw_transaction {
+        Some(raw) => raw.clone(),
+        None => return Err(MevError::MissingRawTransaction { hash: tx.hash.clone() }),
+    };
+
+    Ok((tx.block_number + 1..=tx.block_number + target_blocks)
+        .map(|block| FlashbotsBundle {
+            txs: vec![raw_transaction.clone()],
+            block_number: format!("0x{:x}", block),
+        })
+        .collect())
+}
+
+/// JSON-RPC body of the `eth_sendBundle` request; this exact string is what gets signed
+pub fn bundle_request_body(bundle: &FlashbotsBundle) -> Result<String, MevError> {
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0",
+        id: 1,
+        method: "eth_sendBundle",
+        params: vec![bundle],
+    };
+    serde_json::to_string(&request).map_err(|e| MevError::RelayRejected { message: e.to_string() })
+}
+
+fn transport_error(error: reqwest::Error) -> MevError {
+    MevError::Transport { message: error.to_string() }
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, String> {
+    let digits = value.trim_start_matches("0x");
+    if !digits.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_string());
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+
+    /// Example key from the web3.js `accounts` documentation
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn transaction(raw_transaction: Option<&str>) -> TransactionData {
+        TransactionData {
+            hash: "0xuser_swap".to_string(),
+            from_address: "0xuser".to_string(),
+            to_address: "0xpool".to_string(),
+            value: Decimal::from(10),
+            gas_used: 150_000,
+            gas_price: Decimal::from(30),
+            timestamp: Utc::now(),
+            function_selector: Some("0x7ff36ab5".to_string()),
+            input_data: "0x7ff36ab5".to_string(),
+            success: true,
+            block_number: 1000,
+            transaction_index: 0,
+            raw_transaction: raw_transaction.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_bundle_serialization_fixture() {
+        let bundles = bundles_for(&transaction(Some("0x02f86b0180843b9aca00")), 1).unwrap();
+        assert_eq!(
+            bundle_request_body(&bundles[0]).unwrap(),
+            r#"{"jsonrpc":"2.0","id":1,"method":"eth_sendBundle","params":[{"txs":["0x02f86b0180843b9aca00"],"blockNumber":"0x3e9"}]}"#
+        );
+
+        let parsed: FlashbotsBundle = serde_json::from_str(r#"{"txs":["0xabc"],"blockNumber":"0x10"}"#).unwrap();
+        assert_eq!(parsed, FlashbotsBundle { txs: vec!["0xabc".to_string()], block_number: "0x10".to_string() });
+
+        assert!(matches!(
+            bundles_for(&transaction(None), 1),
+            Err(MevError::MissingRawTransaction { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bundles_target_the_following_blocks() {
+        // Seen in block 1000, so the earliest block it can land in is 1001
+        let bundles = bundles_for(&transaction(Some("0xabc")), DEFAULT_TARGET_BLOCKS).unwrap();
+        let blocks: Vec<&str> = bundles.iter().map(|bundle| bundle.block_number.as_str()).collect();
+        assert_eq!(blocks, vec!["0x3e9", "0x3ea", "0x3eb"]);
+        assert!(bundles.iter().all(|bundle| bundle.txs == vec!["0xabc".to_string()]));
+
+        let relay = FlashbotsRelay::new("https://relay.flashbots.net", KEY).unwrap().with_target_blocks(0);
+        assert_eq!(relay.target_blocks, 1);
+    }
+
+    #[test]
+    fn test_signing_fixtures() {
+        assert_eq!(
+            encode_hex(&keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+
+        let relay = FlashbotsRelay::new("https://relay.flashbots.net", KEY).unwrap();
+        assert_eq!(relay.signer_address(), "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+        // web3.eth.accounts.sign("Some data", KEY)
+        assert_eq!(
+            relay.personal_sign(b"Some data").unwrap(),
+            "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"
+        );
+
+        let header = relay.signature_header("{}").unwrap();
+        let (address, signature) = header.split_once(':').unwrap();
+        assert_eq!(address, relay.signer_address());
+        assert_eq!(signature.len(), 2 + 65 * 2);
+
+        assert!(matches!(
+            FlashbotsRelay::new("https://relay.flashbots.net", "0x1234"),
+            Err(MevError::InvalidSigningKey { .. })
+        ));
+    }
+}
@@ -31,6 +31,20 @@ pub struct MevProtectionConfig {
     pub analysis_window_seconds: u64,
     /// Confidence threshold for MEV detection
     pub confidence_threshold: f64,
+    /// Minimum estimated extractable value (native token units) before a sandwich is reported
+    #[serde(default = "default_detection_threshold")]
+    pub detection_threshold: f64,
+    /// Protection level applied to submitted transactions
+    #[serde(default = "default_protection_level")]
+    pub protection_level: ProtectionLevel,
+}
+
+fn default_detection_threshold() -> f64 {
+    0.01
+}
+
+fn default_protection_level() -> ProtectionLevel {
+    ProtectionLevel::Enhanced
 }
 
 impl Default for MevProtectionConfig {
@@ -46,6 +60,8 @@ impl Default for MevProtectionConfig {
             enable_mev_resistant_relayers: true,
             analysis_window_seconds: 300, // 5 minutes
             confidence_threshold: 0.8,
+            detection_threshold: default_detection_threshold(),
+            protection_level: default_protection_level(),
         }
     }
 }
@@ -101,6 +117,22 @@ pub struct TransactionData {
     pub success: bool,
     pub block_number: u64,
     pub transaction_index: u32,
+    /// Signed, RLP-encoded transaction as 0x-prefixed hex; required for relay submission
+    #[serde(default)]
+    pub raw_transaction: Option<String>,
+}
+
+/// Errors from submitting a protected transaction
+#[derive(Debug, thiserror::Error)]
+pub enum MevError {
+    #[error("Transaction {hash} has no signed raw transaction to submit")]
+    MissingRawTransaction { hash: String },
+    #[error("Invalid signing key: {message}")]
+    InvalidSigningKey { message: String },
+    #[error("Relay rejected submission: {message}")]
+    RelayRejected { message: String },
+    #[error("Transport error: {message}")]
+    Transport { message: String },
 }
 
 /// MEV protection execution route
@@ -124,6 +156,43 @@ pub enum ProtectionLevel {
     Custom(u8),
 }
 
+impl ProtectionLevel {
+    /// Priority tip paid to builders, as a fraction of the base gas price
+    fn tip_fraction(&self) -> f64 {
+        match self {
+            ProtectionLevel::Basic => 0.05,
+            ProtectionLevel::Enhanced => 0.10,
+            ProtectionLevel::Maximum => 0.20,
+            ProtectionLevel::Custom(level) => 0.025 * *level as f64,
+        }
+    }
+
+    /// Share of sandwich attempts the protected route keeps away from the transaction
+    fn coverage(&self) -> f64 {
+        match self {
+            ProtectionLevel::Basic => 0.5,
+            ProtectionLevel::Enhanced => 0.8,
+            ProtectionLevel::Maximum => 0.95,
+            ProtectionLevel::Custom(level) => (*level as f64 / 10.0).min(1.0),
+        }
+    }
+}
+
+/// Cost of protecting a transaction against the sandwich losses it avoids, in native token units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectionCostEstimate {
+    pub protection_level: ProtectionLevel,
+    pub priority_fee_gwei: f64,
+    /// Extra priority fee paid for the protected route
+    pub priority_fee_overhead: f64,
+    /// Most a sandwich can take: value × max slippage tolerance
+    pub sandwich_exposure: f64,
+    /// Likelihood of being sandwiched unprotected, from the attacker's margin over gas
+    pub sandwich_probability: f64,
+    pub expected_savings: f64,
+    pub net_benefit: f64,
+}
+
 /// Execution strategies for MEV protection
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExecutionStrategy {
@@ -168,6 +237,16 @@ impl MevProtectionSystem {
     }
 
     /// Analyze transaction for MEV vulnerabilities
+    #[tracing::instrument(
+        name = "detect_mev",
+        skip_all,
+        fields(
+            transaction_hash = %transaction_data.hash,
+            to_address = %transaction_data.to_address,
+            recent_transactions = recent_transactions.len(),
+            threats = tracing::field::Empty,
+        )
+    )]
     pub async fn analyze_transaction_mev_risk(
         &self,
         transaction_data: &TransactionData,
@@ -204,6 +283,8 @@ impl MevProtectionSystem {
             threats.push(gas_threat);
         }
 
+        tracing::Span::current().record("threats", threats.len());
+
         // Store threats in history
         self.store_threats(&transaction_data.hash, &threats).await;
 
@@ -233,149 +314,100 @@ impl MevProtectionSystem {
         Ok(route)
     }
 
-    /// Detect sandwich attacks
-    async fn detect_sandwich_attack(
+    /// Detect a sandwich around `transaction`: one sender front-running it in the same
+    /// direction and back-running it in the opposite direction, against a pool the target
+    /// swaps through and within the same block. Swaps are decoded from Uniswap V2 router
+    /// calldata; anything else can't be part of a sandwich. Reported when the estimated
+    /// extractable value reaches `detection_threshold`.
+    pub async fn detect_sandwich_attack(
         &self,
         transaction: &TransactionData,
         recent_transactions: &[TransactionData],
     ) -> Result<Option<MevThreat>, Box<dyn std::error::Error + Send + Sync>> {
-        let window_start = transaction.timestamp - chrono::Duration::seconds(self.config.analysis_window_seconds as i64);
-        
-        // Filter transactions in the time window
-        let window_transactions: Vec<&TransactionData> = recent_transactions
+        let target_swap = match RouterSwap::decode(transaction) {
+            Some(swap) => swap,
+            None => return Ok(None),
+        };
+        let same_block: Vec<(&TransactionData, RouterSwap)> = recent_transactions
             .iter()
-            .filter(|tx| tx.timestamp >= window_start && tx.timestamp <= transaction.timestamp)
+            .filter(|tx| {
+                tx.hash != transaction.hash
+                    && tx.success
+                    && tx.block_number == transaction.block_number
+                    && tx.from_address != transaction.from_address
+            })
+            .filter_map(|tx| RouterSwap::decode(tx).map(|swap| (tx, swap)))
             .collect();
 
-        if window_transactions.len() < 3 {
-            return Ok(None);
-        }
-
-        // Look for sandwich pattern: high gas -> normal gas -> high gas
-        for i in 0..window_transactions.len() - 2 {
-            let before = &window_transactions[i];
-            let target = &window_transactions[i + 1];
-            let after = &window_transactions[i + 2];
-
-            // Check if target transaction is the one we're analyzing
-            if target.hash != transaction.hash {
-                continue;
-            }
+        let mut best: Option<(&TransactionData, &TransactionData)> = None;
+        for hop in &target_swap.hops {
+            for (front, front_swap) in same_block.iter().filter(|(tx, _)| tx.transaction_index < transaction.transaction_index) {
+                if !front_swap.hops.contains(hop) {
+                    continue;
+                }
 
-            // Check for sandwich pattern
-            if self.is_sandwich_pattern(before, target, after).await? {
-                let estimated_loss = self.estimate_sandwich_loss(before, target, after).await?;
-                
-                return Ok(Some(MevThreat {
-                    threat_type: MevThreatType::Sandwich,
-                    severity: self.determine_sandwich_severity(estimated_loss).await?,
-                    estimated_loss,
-                    description: format!(
-                        "Sandwich attack detected: {} -> {} -> {}",
-                        before.hash, target.hash, after.hash
-                    ),
-                    confidence: self.calculate_sandwich_confidence(before, target, after).await?,
-                    timestamp: Utc::now(),
-                    transaction_hash: Some(transaction.hash.clone()),
-                    affected_addresses: vec![
-                        before.from_address.clone(),
-                        target.from_address.clone(),
-                        after.from_address.clone(),
-                    ],
-                    mitigation_strategies: vec![
-                        "Use private mempool".to_string(),
-                        "Increase gas price".to_string(),
-                        "Use MEV-resistant relayer".to_string(),
-                    ],
-                }));
+                for (back, back_swap) in same_block.iter().filter(|(tx, _)| {
+                    tx.transaction_index > transaction.transaction_index && tx.from_address == front.from_address
+                }) {
+                    if !back_swap.hops.contains(&hop.reversed()) {
+                        continue;
+                    }
+
+                    // Prefer the tightest pair around the target
+                    let span = back.transaction_index - front.transaction_index;
+                    let tighter = match best {
+                        Some((best_front, best_back)) => span < best_back.transaction_index - best_front.transaction_index,
+                        None => true,
+                    };
+                    if tighter {
+                        best = Some((*front, *back));
+                    }
+                }
             }
         }
 
-        Ok(None)
-    }
+        let (front, back) = match best {
+            Some(pair) => pair,
+            None => return Ok(None),
+        };
 
-    /// Check if three transactions form a sandwich pattern
-    async fn is_sandwich_pattern(
-        &self,
-        before: &TransactionData,
-        target: &TransactionData,
-        after: &TransactionData,
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        // Check if transactions are in the same block or consecutive blocks
-        let block_gap = after.block_number.saturating_sub(before.block_number);
-        if block_gap > 2 {
-            return Ok(false);
+        let estimated_loss = self.estimate_sandwich_loss(&target_swap).await?;
+        if estimated_loss < self.config.detection_threshold {
+            debug!(
+                "Sandwich pattern around {} below detection threshold: {:.6} < {:.6}",
+                transaction.hash, estimated_loss, self.config.detection_threshold
+            );
+            return Ok(None);
         }
 
-        // Check if gas prices follow sandwich pattern (high -> low -> high)
-        let before_gas = before.gas_price;
-        let target_gas = target.gas_price;
-        let after_gas = after.gas_price;
-
-        let is_sandwich_gas = before_gas > target_gas && after_gas > target_gas;
-        
-        // Check if transactions involve the same token/contract
-        let same_target = before.to_address == target.to_address && target.to_address == after.to_address;
-        
-        // Check if function selectors are similar (same operation)
-        let similar_functions = self.are_functions_similar(before, target, after).await?;
-
-        Ok(is_sandwich_gas && same_target && similar_functions)
+        Ok(Some(MevThreat {
+            threat_type: MevThreatType::Sandwich,
+            severity: self.determine_sandwich_severity(estimated_loss).await?,
+            estimated_loss,
+            description: format!(
+                "Sandwich attack detected via {}: {} -> {} -> {}",
+                transaction.to_address, front.hash, transaction.hash, back.hash
+            ),
+            confidence: self.calculate_sandwich_confidence(front, transaction, back),
+            timestamp: Utc::now(),
+            transaction_hash: Some(transaction.hash.clone()),
+            affected_addresses: vec![
+                front.from_address.clone(),
+                transaction.from_address.clone(),
+            ],
+            mitigation_strategies: vec![
+                "Use private mempool".to_string(),
+                "Tighten slippage tolerance".to_string(),
+                "Use MEV-resistant relayer".to_string(),
+            ],
+        }))
     }
 
-    /// Check if function selectors are similar (same operation type)
-    async fn are_functions_similar(
-        &self,
-        before: &TransactionData,
-        target: &TransactionData,
-        after: &TransactionData,
-    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        // Simple check: if all have function selectors, they should be the same
-        if let (Some(before_sel), Some(target_sel), Some(after_sel)) = (
-            &before.function_selector,
-            &target.function_selector,
-            &after.function_selector,
-        ) {
-            return Ok(before_sel == target_sel && target_sel == after_sel);
-        }
-
-        // If no function selectors, check if input data patterns are similar
-        let before_pattern = self.extract_input_pattern(&before.input_data).await?;
-        let target_pattern = self.extract_input_pattern(&target.input_data).await?;
-        let after_pattern = self.extract_input_pattern(&after.input_data).await?;
-
-        Ok(before_pattern == target_pattern && target_pattern == after_pattern)
-    }
-
-    /// Extract pattern from input data
-    async fn extract_input_pattern(&self, input_data: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        // Simple pattern extraction: take first 8 characters (function selector)
-        if input_data.len() >= 10 {
-            Ok(input_data[..10].to_string())
-        } else {
-            Ok(input_data.to_string())
-        }
-    }
-
-    /// Estimate loss from sandwich attack
-    async fn estimate_sandwich_loss(
-        &self,
-        before: &TransactionData,
-        target: &TransactionData,
-        after: &TransactionData,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-        // Calculate gas cost difference
-        let before_cost = (before.gas_used as f64) * (before.gas_price.to_f64().unwrap_or(0.0));
-        let target_cost = (target.gas_used as f64) * (target.gas_price.to_f64().unwrap_or(0.0));
-        let after_cost = (after.gas_used as f64) * (after.gas_price.to_f64().unwrap_or(0.0));
-
-        // Estimate MEV profit (simplified)
-        let mev_profit = before_cost + after_cost - target_cost;
-        
-        // Add value-based estimation if available
-        let value_impact = target.value.to_f64().unwrap_or(0.0) * 0.01; // Assume 1% slippage
-
-        Ok(mev_profit + value_impact)
+    /// Value a sandwich can extract from the victim: the front-run can push the price up to
+    /// the victim's slippage limit, so the loss is bounded by the swap's native size × tolerance.
+    /// Token-to-token swaps have no native leg to size them by and estimate zero.
+    async fn estimate_sandwich_loss(&self, target: &RouterSwap) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(target.native_size.unwrap_or(0.0) * self.config.max_slippage_tolerance / 100.0)
     }
 
     /// Determine severity of sandwich attack
@@ -389,34 +421,27 @@ impl MevProtectionSystem {
     }
 
     /// Calculate confidence in sandwich detection
-    async fn calculate_sandwich_confidence(
+    fn calculate_sandwich_confidence(
         &self,
-        before: &TransactionData,
+        front: &TransactionData,
         target: &TransactionData,
-        after: &TransactionData,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-        let mut confidence = 0.5; // Base confidence
+        back: &TransactionData,
+    ) -> f64 {
+        let mut confidence = 0.6; // Same sender, pool and block with reversed directions
 
-        // Increase confidence based on gas price pattern
-        let gas_pattern_confidence = if before.gas_price > target.gas_price && after.gas_price > target.gas_price {
-            0.3
-        } else {
-            0.0
-        };
-
-        // Increase confidence based on timing
-        let time_diff = (after.timestamp - before.timestamp).num_seconds() as f64;
-        let timing_confidence = if time_diff < 60.0 { 0.2 } else { 0.0 };
+        // Front-run outbid the victim for ordering
+        if front.gas_price > target.gas_price {
+            confidence += 0.2;
+        }
 
-        // Increase confidence based on same target address
-        let target_confidence = if before.to_address == target.to_address && target.to_address == after.to_address {
-            0.2
-        } else {
-            0.0
-        };
+        // Nothing else squeezed between the legs
+        if front.transaction_index + 1 == target.transaction_index
+            && target.transaction_index + 1 == back.transaction_index
+        {
+            confidence += 0.2;
+        }
 
-        confidence += gas_pattern_confidence + timing_confidence + target_confidence;
-        Ok(confidence.min(1.0))
+        f64::min(confidence, 1.0)
     }
 
     /// Detect frontrunning attacks
@@ -600,6 +625,45 @@ impl MevProtectionSystem {
         }
     }
 
+    /// Estimate what protecting `transaction` costs in priority fees against the sandwich losses
+    /// it avoids, under current network conditions and the configured protection level.
+    ///
+    /// A sandwich needs two swaps of roughly the victim's gas, so it is only attempted when the
+    /// victim's slippage exposure outweighs that gas; congestion raises both the attacker's cost
+    /// and the builder tip.
+    pub async fn estimate_protection_cost(&self, transaction: &TransactionData) -> ProtectionCostEstimate {
+        let conditions = self.gas_optimizer.current_network_conditions.read().await.clone();
+        let level = self.config.protection_level.clone();
+        let gas_used = transaction.gas_used as f64;
+
+        let priority_fee_gwei = conditions.optimal_gas_price_gwei * level.tip_fraction() * (1.0 + conditions.network_congestion);
+        let priority_fee_overhead = gas_used * priority_fee_gwei / 1e9;
+
+        let sandwich_exposure = transaction.value.to_f64().unwrap_or(0.0) * self.config.max_slippage_tolerance / 100.0;
+        let attacker_gas_cost = 2.0 * gas_used * conditions.optimal_gas_price_gwei / 1e9;
+        let sandwich_probability = if sandwich_exposure > attacker_gas_cost {
+            1.0 - attacker_gas_cost / sandwich_exposure
+        } else {
+            0.0
+        };
+        let expected_savings = sandwich_exposure * sandwich_probability * level.coverage();
+
+        ProtectionCostEstimate {
+            protection_level: level,
+            priority_fee_gwei,
+            priority_fee_overhead,
+            sandwich_exposure,
+            sandwich_probability,
+            expected_savings,
+            net_benefit: expected_savings - priority_fee_overhead,
+        }
+    }
+
+    /// Update the network conditions used for gas and protection cost estimates
+    pub async fn update_network_conditions(&self, conditions: NetworkConditions) {
+        self.gas_optimizer.update_network_conditions(conditions).await;
+    }
+
     /// Assess execution risk for transaction
     async fn assess_execution_risk(
         &self,
@@ -790,6 +854,85 @@ impl MevProtectionSystem {
 
 // Supporting components
 
+/// One pool a router swap trades through, in the direction it trades
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PoolHop {
+    router: String,
+    token_in: String,
+    token_out: String,
+}
+
+impl PoolHop {
+    fn reversed(&self) -> Self {
+        Self {
+            router: self.router.clone(),
+            token_in: self.token_out.clone(),
+            token_out: self.token_in.clone(),
+        }
+    }
+}
+
+/// A Uniswap V2 router swap decoded from calldata
+#[derive(Debug, Clone)]
+struct RouterSwap {
+    hops: Vec<PoolHop>,
+    /// Native tokens spent or received: `value` on ETH-input swaps, the calldata's ETH
+    /// amount on ETH-output swaps; `None` for token-to-token swaps
+    native_size: Option<f64>,
+}
+
+impl RouterSwap {
+    /// Decode the `swap*` router functions; `None` for any other call or malformed calldata
+    fn decode(transaction: &TransactionData) -> Option<Self> {
+        let input = transaction.input_data.trim_start_matches("0x").to_lowercase();
+        if input.len() < 8 {
+            return None;
+        }
+        let (selector, args) = input.split_at(8);
+        let word = |index: usize| args.get(index * 64..(index + 1) * 64);
+        let uint = |index: usize| -> Option<u128> {
+            let word = word(index)?;
+            if !word[..32].bytes().all(|byte| byte == b'0') {
+                return None;
+            }
+            u128::from_str_radix(&word[32..], 16).ok()
+        };
+        let wei_to_native = |wei: u128| wei as f64 / 1e18;
+
+        // (index of the `path` argument, native size)
+        let (path_arg, native_size) = match selector {
+            // swapExactETHForTokens, swapETHForExactTokens, swapExactETHForTokensSupportingFeeOnTransferTokens
+            "7ff36ab5" | "fb3bdb41" | "b6f9de95" => (1, Some(transaction.value.to_f64().unwrap_or(0.0))),
+            // swapExactTokensForETH, swapExactTokensForETHSupportingFeeOnTransferTokens: amountOutMin
+            "18cbafe5" | "791ac947" => (2, Some(wei_to_native(uint(1)?))),
+            // swapTokensForExactETH: amountOut
+            "4a25d94a" => (2, Some(wei_to_native(uint(0)?))),
+            // swapExactTokensForTokens, swapTokensForExactTokens, swapExactTokensForTokensSupportingFeeOnTransferTokens
+            "38ed1739" | "8803dbee" | "5c11d795" => (2, None),
+            _ => return None,
+        };
+
+        let path_start = usize::try_from(uint(path_arg)?).ok()? / 32;
+        let path_len = usize::try_from(uint(path_start)?).ok()?;
+        let path = (0..path_len)
+            .map(|index| word(path_start + 1 + index).map(|word| format!("0x{}", &word[24..])))
+            .collect::<Option<Vec<String>>>()?;
+        if path.len() < 2 {
+            return None;
+        }
+
+        let router = transaction.to_address.to_lowercase();
+        let hops = path.windows(2)
+            .map(|pair| PoolHop {
+                router: router.clone(),
+                token_in: pair[0].clone(),
+                token_out: pair[1].clone(),
+            })
+            .collect();
+        Some(Self { hops, native_size })
+    }
+}
+
 /// Private mempool for MEV protection
 #[derive(Debug, Clone)]
 pub struct PrivateMempool {
@@ -890,4 +1033,228 @@ impl Default for GasOptimizer {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUY: &str = "7ff36ab5"; // swapExactETHForTokens(amountOutMin, path, to, deadline)
+    const BUY_EXACT: &str = "fb3bdb41"; // swapETHForExactTokens(amountOut, path, to, deadline)
+    const SELL: &str = "18cbafe5"; // swapExactTokensForETH(amountIn, amountOutMin, path, to, deadline)
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    const TOKEN: &str = "0x1f9840a85d5af5b96d2f07d2d2c2a9a9a4b2d1e5";
+    const OTHER_TOKEN: &str = "0x6b175474e89094c44da98b954eedeac495271d0f";
+    const ETH: u128 = 1_000_000_000_000_000_000;
+
+    /// Router calldata: the leading uint arguments, then `path`, `to` and `deadline`
+    fn calldata(selector: &str, leading: &[u128], path: &[&str]) -> String {
+        let mut words: Vec<String> = leading.iter().map(|amount| format!("{:064x}", amount)).collect();
+        words.push(format!("{:064x}", (leading.len() + 3) * 32));
+        words.push(format!("{:0>64}", "beef"));
+        words.push(format!("{:064x}", 1_700_000_000u64));
+        words.push(format!("{:064x}", path.len()));
+        words.extend(path.iter().map(|token| format!("{:0>64}", token.trim_start_matches("0x"))));
+        format!("0x{}{}", selector, words.concat())
+    }
+
+    fn transaction(hash: &str, from: &str, value: i64, input_data: String, gas_price: i64, index: u32) -> TransactionData {
+        TransactionData {
+            hash: hash.to_string(),
+            from_address: from.to_string(),
+            to_address: "0xrouter".to_string(),
+            value: Decimal::from(value),
+            gas_used: 150_000,
+            gas_price: Decimal::from(gas_price),
+            timestamp: Utc::now(),
+            function_selector: Some(input_data[..10].to_string()),
+            input_data,
+            success: true,
+            block_number: 1000,
+            transaction_index: index,
+            raw_transaction: None,
+        }
+    }
+
+    /// Spend `eth` on TOKEN
+    fn buy(hash: &str, from: &str, eth: i64, gas_price: i64, index: u32) -> TransactionData {
+        transaction(hash, from, eth, calldata(BUY, &[0], &[WETH, TOKEN]), gas_price, index)
+    }
+
+    /// Sell TOKEN for at least `eth`, sending no value
+    fn sell(hash: &str, from: &str, eth: u128, gas_price: i64, index: u32) -> TransactionData {
+        transaction(hash, from, 0, calldata(SELL, &[1_000 * ETH, eth * ETH], &[TOKEN, WETH]), gas_price, index)
+    }
+
+    #[tokio::test]
+    async fn test_detects_crafted_sandwich() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        let victim = buy("0xvictim", "0xuser", 10_000, 30, 1);
+        let block = vec![
+            buy("0xfront", "0xattacker", 5_000, 100, 0),
+            victim.clone(),
+            sell("0xback", "0xattacker", 5_000, 95, 2),
+            sell("0xlater", "0xother", 100, 20, 3),
+        ];
+
+        let threat = system.detect_sandwich_attack(&victim, &block).await.unwrap().expect("sandwich");
+        assert_eq!(threat.threat_type, MevThreatType::Sandwich);
+        // 0.5% slippage tolerance on a 10,000 trade
+        assert!((threat.estimated_loss - 50.0).abs() < 1e-9);
+        assert_eq!(threat.severity, MevThreatSeverity::Critical);
+        assert!((threat.confidence - 1.0).abs() < 1e-9);
+        assert_eq!(threat.affected_addresses, vec!["0xattacker".to_string(), "0xuser".to_string()]);
+        assert!(threat.description.contains("0xfront -> 0xvictim -> 0xback"));
+
+        let threats = system.analyze_transaction_mev_risk(&victim, &block).await.unwrap();
+        assert!(threats.iter().any(|threat| threat.threat_type == MevThreatType::Sandwich));
+    }
+
+    #[tokio::test]
+    async fn test_benign_pairs_do_not_trigger() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        let victim = buy("0xvictim", "0xuser", 10_000, 30, 1);
+
+        // Unrelated traders buying before and selling after
+        let unrelated = vec![
+            buy("0xa", "0xtrader_a", 5_000, 100, 0),
+            victim.clone(),
+            sell("0xb", "0xtrader_b", 5_000, 95, 2),
+        ];
+        assert!(system.detect_sandwich_attack(&victim, &unrelated).await.unwrap().is_none());
+
+        // One trader buying on both sides never unwinds the front-run
+        let accumulating = vec![
+            buy("0xa", "0xtrader", 5_000, 100, 0),
+            victim.clone(),
+            buy("0xb", "0xtrader", 5_000, 95, 2),
+        ];
+        assert!(system.detect_sandwich_attack(&victim, &accumulating).await.unwrap().is_none());
+
+        // A round trip split across blocks is not an atomic sandwich
+        let mut next_block = sell("0xb", "0xtrader", 5_000, 95, 0);
+        next_block.block_number = 1001;
+        let split = vec![buy("0xa", "0xtrader", 5_000, 100, 0), victim.clone(), next_block];
+        assert!(system.detect_sandwich_attack(&victim, &split).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_detects_sandwich_on_token_sale() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        // Selling tokens for at least 20 ETH sends no value
+        let victim = sell("0xvictim", "0xuser", 20, 30, 1);
+        let block = vec![
+            sell("0xfront", "0xattacker", 5, 100, 0),
+            victim.clone(),
+            buy("0xback", "0xattacker", 5, 95, 2),
+        ];
+
+        let threat = system.detect_sandwich_attack(&victim, &block).await.unwrap().expect("sandwich");
+        // 0.5% of the 20 ETH minimum output
+        assert!((threat.estimated_loss - 0.1).abs() < 1e-9);
+        assert!(threat.description.contains("0xfront -> 0xvictim -> 0xback"));
+    }
+
+    #[tokio::test]
+    async fn test_sandwich_matches_by_decoded_pool() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        let victim = buy("0xvictim", "0xuser", 10_000, 30, 1);
+
+        // Different buy functions on the same pool are the same direction, not a round trip
+        let exact_buy = transaction("0xback", "0xattacker", 5_000, calldata(BUY_EXACT, &[ETH], &[WETH, TOKEN]), 95, 2);
+        let two_buys = vec![buy("0xfront", "0xattacker", 5_000, 100, 0), victim.clone(), exact_buy.clone()];
+        assert!(system.detect_sandwich_attack(&victim, &two_buys).await.unwrap().is_none());
+
+        // ...and a front-run through a different selector still counts
+        let exact_front = transaction("0xfront", "0xattacker", 5_000, calldata(BUY_EXACT, &[ETH], &[WETH, TOKEN]), 100, 0);
+        let block = vec![exact_front, victim.clone(), sell("0xback", "0xattacker", 5_000, 95, 2)];
+        assert!(system.detect_sandwich_attack(&victim, &block).await.unwrap().is_some());
+
+        // The same router trading another pair is not the victim's pool
+        let other_pool = transaction(
+            "0xback", "0xattacker", 0, calldata(SELL, &[1_000 * ETH, ETH], &[OTHER_TOKEN, WETH]), 95, 2,
+        );
+        let elsewhere = vec![buy("0xfront", "0xattacker", 5_000, 100, 0), victim.clone(), other_pool];
+        assert!(system.detect_sandwich_attack(&victim, &elsewhere).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sandwich_below_detection_threshold() {
+        let config = MevProtectionConfig {
+            detection_threshold: 100.0,
+            ..MevProtectionConfig::default()
+        };
+        let system = MevProtectionSystem::new(config);
+        let victim = buy("0xvictim", "0xuser", 10_000, 30, 1);
+        let block = vec![
+            buy("0xfront", "0xattacker", 5_000, 100, 0),
+            victim.clone(),
+            sell("0xback", "0xattacker", 5_000, 95, 2),
+        ];
+
+        assert!(system.detect_sandwich_attack(&victim, &block).await.unwrap().is_none());
+    }
+
+    fn conditions(gas_price_gwei: f64, congestion: f64) -> NetworkConditions {
+        NetworkConditions {
+            optimal_gas_price_gwei: gas_price_gwei,
+            network_congestion: congestion,
+            ..NetworkConditions::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protection_cost_low_congestion() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        system.update_network_conditions(conditions(20.0, 0.2)).await;
+        let trade = buy("0xtrade", "0xuser", 10, 20, 0);
+
+        // 10 ETH at 0.5% tolerance exposes 0.05; two 150k-gas legs at 20 gwei cost 0.006
+        let estimate = system.estimate_protection_cost(&trade).await;
+        assert!((estimate.sandwich_exposure - 0.05).abs() < 1e-12);
+        assert!((estimate.sandwich_probability - 0.88).abs() < 1e-12);
+        // Enhanced: 10% tip scaled by congestion, 80% of attempts avoided
+        assert!((estimate.priority_fee_gwei - 2.4).abs() < 1e-12);
+        assert!((estimate.priority_fee_overhead - 0.00036).abs() < 1e-12);
+        assert!((estimate.expected_savings - 0.0352).abs() < 1e-12);
+        assert!((estimate.net_benefit - 0.03484).abs() < 1e-12);
+
+        // A tighter slippage limit leaves less to extract
+        let tight = MevProtectionSystem::new(MevProtectionConfig {
+            max_slippage_tolerance: 0.1,
+            ..MevProtectionConfig::default()
+        });
+        tight.update_network_conditions(conditions(20.0, 0.2)).await;
+        let tight_estimate = tight.estimate_protection_cost(&trade).await;
+        assert!((tight_estimate.sandwich_exposure - 0.01).abs() < 1e-12);
+        assert!(tight_estimate.expected_savings < estimate.expected_savings);
+    }
+
+    #[tokio::test]
+    async fn test_protection_cost_high_congestion() {
+        let system = MevProtectionSystem::new(MevProtectionConfig::default());
+        system.update_network_conditions(conditions(200.0, 0.9)).await;
+        let trade = buy("0xtrade", "0xuser", 10, 200, 0);
+
+        // Attacker gas (0.06) exceeds the 0.05 exposure, so protection only costs
+        let estimate = system.estimate_protection_cost(&trade).await;
+        assert_eq!(estimate.sandwich_probability, 0.0);
+        assert_eq!(estimate.expected_savings, 0.0);
+        assert!((estimate.priority_fee_gwei - 38.0).abs() < 1e-9);
+        assert!((estimate.net_benefit + 0.0057).abs() < 1e-12);
+
+        // A larger trade is worth sandwiching again, and a higher level pays more to avoid more
+        let large = buy("0xlarge", "0xuser", 100, 200, 0);
+        let enhanced = system.estimate_protection_cost(&large).await;
+        let maximum = MevProtectionSystem::new(MevProtectionConfig {
+            protection_level: ProtectionLevel::Maximum,
+            ..MevProtectionConfig::default()
+        });
+        maximum.update_network_conditions(conditions(200.0, 0.9)).await;
+        let maximum = maximum.estimate_protection_cost(&large).await;
+
+        assert!((enhanced.sandwich_probability - 0.88).abs() < 1e-12);
+        assert!(enhanced.net_benefit > 0.0);
+        assert!(maximum.priority_fee_overhead > enhanced.priority_fee_overhead);
+        assert!(maximum.expected_savings > enhanced.expected_savings);
+    }
+}
@@ -8,6 +8,9 @@ pub mod transaction_monitor;
 pub mod audit_database;
 pub mod real_time_scanner;
 pub mod exploit_monitor;
+pub mod approval_scanner;
+#[cfg(feature = "flashbots")]
+pub mod flashbots;
 
 // Re-export key types
 pub use vulnerability_detector::*;
@@ -16,4 +19,7 @@ pub use bytecode_analyzer::*;
 pub use transaction_monitor::*;
 pub use audit_database::*;
 pub use real_time_scanner::*;
-pub use exploit_monitor::*;
\ No newline at end of file
+pub use exploit_monitor::*;
+pub use approval_scanner::*;
+#[cfg(feature = "flashbots")]
+pub use flashbots::*;
\ No newline at end of file
@@ -5,6 +5,7 @@ use crate::security::{
 use crate::types::{PositionId, RiskAlert, AlertType, RiskLevel};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
@@ -91,6 +92,15 @@ pub struct ScanResult {
     pub errors: Vec<String>,
 }
 
+/// Outcome of scanning a batch of contracts within one monitoring cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanCycleSummary {
+    pub results: Vec<ScanResult>,
+    pub failures: Vec<(String, String)>, // (contract address, error)
+    pub deferred: Vec<ScanRequest>,      // Not started before the cycle budget ran out
+    pub duration_ms: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityAlert {
     pub id: Uuid,
@@ -136,6 +146,7 @@ pub struct ScannerConfig {
     pub alert_on_risk_score_increase: u8, // Minimum increase to trigger alert
     pub max_scan_queue_size: usize,
     pub cleanup_interval_hours: u64,
+    pub scan_cycle_budget_seconds: u64, // Time allowed for one batch of due contracts
 }
 
 impl Default for ScannerConfig {
@@ -150,6 +161,7 @@ impl Default for ScannerConfig {
             alert_on_risk_score_increase: 10,
             max_scan_queue_size: 1000,
             cleanup_interval_hours: 24,
+            scan_cycle_budget_seconds: 55, // Leave headroom within the one-minute monitoring tick
         }
     }
 }
@@ -159,9 +171,22 @@ impl RealTimeVulnerabilityScanner {
         vulnerability_detector: Arc<SmartContractVulnerabilityDetector>,
         transaction_monitor: Arc<AdvancedTransactionPatternMonitor>,
         audit_database_manager: Arc<AuditDatabaseManager>,
+    ) -> (Self, mpsc::UnboundedReceiver<SecurityAlert>) {
+        Self::with_config(
+            vulnerability_detector,
+            transaction_monitor,
+            audit_database_manager,
+            ScannerConfig::default(),
+        )
+    }
+
+    pub fn with_config(
+        vulnerability_detector: Arc<SmartContractVulnerabilityDetector>,
+        transaction_monitor: Arc<AdvancedTransactionPatternMonitor>,
+        audit_database_manager: Arc<AuditDatabaseManager>,
+        config: ScannerConfig,
     ) -> (Self, mpsc::UnboundedReceiver<SecurityAlert>) {
         let (alert_sender, alert_receiver) = mpsc::unbounded_channel();
-        let config = ScannerConfig::default();
         
         let scanner = Self {
             vulnerability_detector,
@@ -304,10 +329,9 @@ impl RealTimeVulnerabilityScanner {
                 }
             }
 
-            // Queue scans for contracts that need them
-            for contract_address in contracts_to_scan {
-                if let Some(contract) = self.monitored_contracts.get(&contract_address) {
-                    let scan_request = ScanRequest {
+            let scan_requests: Vec<ScanRequest> = contracts_to_scan.iter()
+                .filter_map(|contract_address| {
+                    self.monitored_contracts.get(contract_address).map(|contract| ScanRequest {
                         contract_address: contract_address.clone(),
                         priority: match contract.priority {
                             MonitoringPriority::Critical => AnalysisPriority::Critical,
@@ -318,18 +342,74 @@ impl RealTimeVulnerabilityScanner {
                         requested_by: Some("continuous_monitoring".to_string()),
                         position_ids: contract.associated_positions.clone(),
                         scan_type: ScanType::Incremental,
-                    };
+                    })
+                })
+                .collect();
 
-                    if let Err(e) = self.queue_scan(scan_request).await {
-                        warn!("Failed to queue scan for {}: {}", contract_address, e);
-                    }
+            if scan_requests.is_empty() {
+                continue;
+            }
+
+            let summary = self.scan_contracts(scan_requests).await;
+            debug!("Scanned {} contracts in {}ms ({} failed, {} deferred)", 
+                   summary.results.len(), summary.duration_ms, summary.failures.len(), summary.deferred.len());
+
+            // Anything that didn't fit in this cycle goes through the regular queue
+            for scan_request in summary.deferred {
+                let contract_address = scan_request.contract_address.clone();
+                if let Err(e) = self.queue_scan(scan_request).await {
+                    warn!("Failed to queue deferred scan for {}: {}", contract_address, e);
                 }
             }
+        }
+    }
+
+    /// Scan a batch of contracts with at most `max_concurrent_scans` in flight, stopping
+    /// once the cycle budget is spent. Requests not started in time are returned as deferred.
+    pub async fn scan_contracts(&self, requests: Vec<ScanRequest>) -> ScanCycleSummary {
+        let start_time = Instant::now();
+        let (workers, budget) = {
+            let config = self.config.read().await;
+            (config.max_concurrent_scans.max(1), Duration::from_secs(config.scan_cycle_budget_seconds))
+        };
+        let deadline = start_time + budget;
+
+        let mut pending: HashMap<String, ScanRequest> = requests.iter()
+            .map(|request| (request.contract_address.clone(), request.clone()))
+            .collect();
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
 
-            if !contracts_to_scan.is_empty() {
-                debug!("Queued {} contracts for scanning", contracts_to_scan.len());
+        let mut scans = stream::iter(requests)
+            .map(|request| async move {
+                let contract_address = request.contract_address.clone();
+                (contract_address, self.execute_scan(request).await)
+            })
+            .buffer_unordered(workers);
+
+        loop {
+            match tokio::time::timeout_at(deadline, scans.next()).await {
+                Ok(Some((contract_address, outcome))) => {
+                    pending.remove(&contract_address);
+                    match outcome {
+                        Ok(result) => results.push(result),
+                        Err(e) => failures.push((contract_address, e.to_string())),
+                    }
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    warn!("Scan cycle budget of {:?} exhausted with {} contracts outstanding", budget, pending.len());
+                    break;
+                }
             }
         }
+
+        ScanCycleSummary {
+            results,
+            failures,
+            deferred: pending.into_values().collect(),
+            duration_ms: start_time.elapsed().as_millis() as u64,
+        }
     }
 
     async fn process_scan_queue(&self) {
@@ -371,7 +451,7 @@ impl RealTimeVulnerabilityScanner {
         }
     }
 
-    async fn execute_scan(&self, request: ScanRequest) -> Result<(), VulnerabilityDetectionError> {
+    async fn execute_scan(&self, request: ScanRequest) -> Result<ScanResult, VulnerabilityDetectionError> {
         let start_time = Instant::now();
         let scan_id = Uuid::new_v4();
         
@@ -473,7 +553,7 @@ impl RealTimeVulnerabilityScanner {
             contract_address: request.contract_address.clone(),
             scan_id,
             scanned_at: Utc::now(),
-            scan_type: request.scan_type,
+            scan_type: request.scan_type.clone(),
             vulnerability_report: vulnerability_report.clone(),
             new_vulnerabilities: new_vulnerabilities.clone(),
             risk_score_change,
@@ -482,7 +562,7 @@ impl RealTimeVulnerabilityScanner {
         };
 
         // Store scan result
-        self.scan_results.insert(request.contract_address.clone(), scan_result);
+        self.scan_results.insert(request.contract_address.clone(), scan_result.clone());
 
         // Update last scanned time for monitored contract
         if let Some(mut contract) = self.monitored_contracts.get_mut(&request.contract_address) {
@@ -495,7 +575,7 @@ impl RealTimeVulnerabilityScanner {
         info!("Scan completed for {} in {}ms. Found {} new vulnerabilities", 
               request.contract_address, scan_duration.as_millis(), new_vulnerabilities.len());
 
-        Ok(())
+        Ok(scan_result)
     }
 
     async fn generate_alerts(
@@ -638,4 +718,110 @@ impl Clone for RealTimeVulnerabilityScanner {
             concurrency_limiter: self.concurrency_limiter.clone(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{AuditDatabase, AuditDatabaseConfig, Vulnerability, VulnerabilitySeverity, VulnerabilityCategory};
+
+    /// Time `SlowAuditDatabase` takes to check one contract
+    const CHECK_LATENCY: Duration = Duration::from_millis(150);
+
+    /// Audit source with a little latency that reports a finding for every third contract.
+    struct SlowAuditDatabase;
+
+    #[async_trait]
+    impl AuditDatabase for SlowAuditDatabase {
+        async fn check_contract(&self, contract_address: &str) -> Result<Vec<Vulnerability>, Box<dyn std::error::Error + Send + Sync>> {
+            tokio::time::sleep(CHECK_LATENCY).await;
+            let index: usize = contract_address.trim_start_matches("0xcontract").parse()?;
+            if !index.is_multiple_of(3) {
+                return Ok(vec![]);
+            }
+            Ok(vec![Vulnerability {
+                id: format!("finding_{}", index),
+                severity: VulnerabilitySeverity::High,
+                category: VulnerabilityCategory::Reentrancy,
+                description: "test finding".to_string(),
+                impact: "test impact".to_string(),
+                confidence: 90,
+                cvss_score: None,
+                cwe_id: None,
+                affected_functions: vec![],
+                proof_of_concept: None,
+                remediation: None,
+            }])
+        }
+
+        fn name(&self) -> String {
+            "slow_test_db".to_string()
+        }
+    }
+
+    fn scanner_with_workers(workers: usize) -> RealTimeVulnerabilityScanner {
+        let (scanner, _alerts) = RealTimeVulnerabilityScanner::with_config(
+            Arc::new(SmartContractVulnerabilityDetector::new(vec![Box::new(SlowAuditDatabase)])),
+            Arc::new(AdvancedTransactionPatternMonitor::new()),
+            Arc::new(AuditDatabaseManager::new(AuditDatabaseConfig::default())),
+            ScannerConfig {
+                max_concurrent_scans: workers,
+                scan_cycle_budget_seconds: 10,
+                ..ScannerConfig::default()
+            },
+        );
+        scanner
+    }
+
+    fn scan_requests(count: usize) -> Vec<ScanRequest> {
+        (0..count)
+            .map(|i| ScanRequest {
+                contract_address: format!("0xcontract{}", i),
+                priority: AnalysisPriority::Normal,
+                requested_at: Utc::now(),
+                requested_by: None,
+                position_ids: vec![],
+                scan_type: ScanType::Full,
+            })
+            .collect()
+    }
+
+    fn findings(summary: &ScanCycleSummary) -> HashMap<String, Vec<String>> {
+        summary.results.iter()
+            .map(|result| {
+                let mut ids = result.new_vulnerabilities.clone();
+                ids.sort();
+                (result.contract_address.clone(), ids)
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent_scan_matches_serial_findings() {
+        let serial = scanner_with_workers(1).scan_contracts(scan_requests(60)).await;
+        let concurrent = scanner_with_workers(16).scan_contracts(scan_requests(60)).await;
+
+        assert!(serial.deferred.is_empty() && concurrent.deferred.is_empty());
+        assert!(serial.failures.is_empty() && concurrent.failures.is_empty());
+        assert_eq!(concurrent.results.len(), 60);
+        assert_eq!(findings(&concurrent), findings(&serial));
+        assert_eq!(findings(&concurrent)["0xcontract3"], vec!["finding_3".to_string()]);
+        // One check at a time, against four rounds of 16
+        assert_eq!(serial.duration_ms, 60 * CHECK_LATENCY.as_millis() as u64);
+        assert_eq!(concurrent.duration_ms, 4 * CHECK_LATENCY.as_millis() as u64);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_defers_contracts_beyond_cycle_budget() {
+        let scanner = scanner_with_workers(1);
+        scanner.config.write().await.scan_cycle_budget_seconds = 1;
+
+        let summary = scanner.scan_contracts(scan_requests(15)).await;
+
+        // Six 150ms checks fit in the second; the seventh is cut off at the budget
+        assert_eq!(summary.results.len(), 6);
+        assert!(summary.failures.is_empty());
+        assert_eq!(summary.deferred.len(), 9);
+        assert_eq!(summary.duration_ms, 1_000);
+    }
+}
@@ -643,4 +643,10 @@ impl Clone for VulnerabilityScoringEngine {
 pub trait AuditDatabase: Send + Sync {
     async fn check_contract(&self, contract_address: &str) -> Result<Vec<Vulnerability>, Box<dyn std::error::Error + Send + Sync>>;
     fn name(&self) -> String;
+
+    /// Whether an audit of the contract is on record. Sources that only report
+    /// findings cannot tell a clean audit from no audit, and answer `false`.
+    async fn has_audit(&self, _contract_address: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(false)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,211 @@
+use super::stress_testing::SimulationPosition;
+use crate::risk::PriceImpactSimulator;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use log::debug;
+
+/// One forced sale in a liquidation cascade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CascadeLiquidation {
+    /// 1-based round the position fell below its threshold in; round 1 is the initial shock
+    pub round: usize,
+    /// Index of the position in the slice passed to `simulate`
+    pub position_index: usize,
+    pub token_address: String,
+    /// Health factor that triggered the liquidation
+    pub health_factor: f64,
+    /// Collateral value sold, at the price when the sale happened
+    pub collateral_value: f64,
+    /// Drop in the token's price caused by this sale, in percent
+    pub price_impact_percent: f64,
+    /// Token price once the sale has moved it
+    pub price_after: f64,
+}
+
+/// Outcome of a liquidation cascade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationCascade {
+    /// Liquidations in the order they happened
+    pub liquidations: Vec<CascadeLiquidation>,
+    /// Rounds that liquidated at least one position
+    pub rounds: usize,
+    /// Every input position repriced at the prices the cascade settled at
+    pub final_positions: Vec<SimulationPosition>,
+    /// Multiplier from each shocked or sold token's starting price to its settled price
+    pub price_multipliers: HashMap<String, f64>,
+}
+
+impl LiquidationCascade {
+    pub fn liquidated_indices(&self) -> Vec<usize> {
+        self.liquidations.iter().map(|liquidation| liquidation.position_index).collect()
+    }
+}
+
+/// Follows a price shock through the liquidations it forces.
+///
+/// Each round marks every remaining position whose health factor is below its
+/// `liquidation_threshold`, sells their collateral lowest health factor first, and lowers
+/// the sold token's price by the sale's impact from the `PriceImpactSimulator`. The rest
+/// are re-checked at the new prices until a round liquidates nothing. Positions holding the
+/// same token are linked this way, so one liquidation can push its neighbours under.
+///
+/// The simulator's pools are not drained by earlier sales, so each sale's impact is judged
+/// against the same liquidity.
+pub struct LiquidationCascadeSimulator {
+    price_impact: Arc<PriceImpactSimulator>,
+}
+
+impl LiquidationCascadeSimulator {
+    pub fn new(price_impact: Arc<PriceImpactSimulator>) -> Self {
+        Self { price_impact }
+    }
+
+    /// Apply `price_shocks` (token to fractional price change, e.g. -0.2) to `positions`
+    /// and run the cascade it sets off
+    pub async fn simulate(
+        &self,
+        positions: &[SimulationPosition],
+        price_shocks: &HashMap<String, f64>,
+    ) -> Result<LiquidationCascade, Box<dyn std::error::Error + Send + Sync>> {
+        let mut multipliers: HashMap<String, f64> = price_shocks.iter()
+            .map(|(token, shock)| (token.clone(), (1.0 + shock).max(0.0)))
+            .collect();
+        let mut liquidated = vec![false; positions.len()];
+        let mut liquidations = Vec::new();
+        let mut rounds = 0;
+
+        loop {
+            let mut marked: Vec<(usize, f64)> = positions.iter()
+                .enumerate()
+                .filter(|(index, _)| !liquidated[*index])
+                .filter_map(|(index, position)| {
+                    let health_factor = repriced(position, &multipliers).health_factor;
+                    (health_factor < position.liquidation_threshold).then_some((index, health_factor))
+                })
+                .collect();
+            if marked.is_empty() {
+                break;
+            }
+            marked.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            rounds += 1;
+
+            for (index, health_factor) in marked {
+                liquidated[index] = true;
+                // Sold at the price left by earlier sales this round
+                let sold = repriced(&positions[index], &multipliers);
+                let impact_percent = self.sale_impact_percent(&sold).await?;
+                let multiplier = multipliers.entry(sold.token_address.clone()).or_insert(1.0);
+                *multiplier *= 1.0 - impact_percent / 100.0;
+
+                debug!(
+                    "Cascade round {}: liquidated position {} ({}) at health factor {:.4}, price impact {:.2}%",
+                    rounds, index, sold.token_address, health_factor, impact_percent
+                );
+                liquidations.push(CascadeLiquidation {
+                    round: rounds,
+                    position_index: index,
+                    token_address: sold.token_address.clone(),
+                    health_factor,
+                    collateral_value: sold.collateral_value,
+                    price_impact_percent: impact_percent,
+                    price_after: positions[index].current_price * *multiplier,
+                });
+            }
+        }
+
+        Ok(LiquidationCascade {
+            liquidations,
+            rounds,
+            final_positions: positions.iter().map(|position| repriced(position, &multipliers)).collect(),
+            price_multipliers: multipliers,
+        })
+    }
+
+    /// Magnitude of the price move from selling all of `position`'s collateral, capped at 100%
+    async fn sale_impact_percent(&self, position: &SimulationPosition) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        let trade_size_usd = match Decimal::from_f64(position.collateral_value) {
+            Some(value) if value > Decimal::ZERO => value,
+            _ => return Ok(0.0),
+        };
+        let simulation = self.price_impact.simulate_price_impact(&position.token_address, trade_size_usd).await?;
+        Ok(simulation.price_impact_percent.abs().to_f64().unwrap_or(0.0).min(100.0))
+    }
+}
+
+/// `position` at its starting price times its token's multiplier. Positions without debt
+/// can't be liquidated and keep an infinite health factor.
+fn repriced(position: &SimulationPosition, multipliers: &HashMap<String, f64>) -> SimulationPosition {
+    let mut position = position.clone();
+    if let Some(multiplier) = multipliers.get(&position.token_address) {
+        position.current_price *= multiplier;
+        position.collateral_value = position.quantity * position.current_price;
+    }
+    position.health_factor = if position.debt_value > 0.0 {
+        position.collateral_value / position.debt_value
+    } else {
+        f64::INFINITY
+    };
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::LiquidityCurve;
+    use crate::test_support::NoHistory;
+
+    fn eth_position(debt_value: f64) -> SimulationPosition {
+        SimulationPosition {
+            token_address: "ETH".to_string(),
+            quantity: 10.0,
+            entry_price: 2000.0,
+            current_price: 2000.0,
+            collateral_value: 20_000.0,
+            debt_value,
+            liquidation_threshold: 1.0,
+            health_factor: 20_000.0 / debt_value,
+            debt_tokens: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_liquidation_impact_pushes_second_position_under() {
+        // A shallow 100 ETH / 200k USDC pool quoting ETH at 2000
+        let price_impact = PriceImpactSimulator::new(Box::new(NoHistory)).with_liquidity_curve(
+            "ETH".to_string(),
+            LiquidityCurve::ConstantProduct {
+                token_reserve: Decimal::from(100),
+                quote_reserve: Decimal::from(200_000),
+            },
+        );
+        let simulator = LiquidationCascadeSimulator::new(Arc::new(price_impact));
+        let positions = vec![eth_position(16_000.0), eth_position(14_000.0), eth_position(5_000.0)];
+
+        // At 1500, A is at 0.9375 while B holds at 1.07
+        let shocks = HashMap::from([("ETH".to_string(), -0.25)]);
+        let cascade = simulator.simulate(&positions, &shocks).await.unwrap();
+
+        assert_eq!(cascade.liquidated_indices(), vec![0, 1]);
+        assert_eq!(cascade.rounds, 2);
+
+        let (a, b) = (&cascade.liquidations[0], &cascade.liquidations[1]);
+        assert_eq!(a.round, 1);
+        assert!((a.health_factor - 0.9375).abs() < 1e-9);
+        // Selling A's 15k of ETH is 7.5 ETH into the pool: 7.5 / 107.5 short of spot
+        assert!((a.price_impact_percent - 750.0 / 107.5).abs() < 1e-6, "{}", a.price_impact_percent);
+        assert!((a.price_after - 1500.0 * 100.0 / 107.5).abs() < 1e-6, "{}", a.price_after);
+
+        assert_eq!(b.round, 2);
+        assert!(b.health_factor < 1.0, "{}", b.health_factor);
+        assert!((b.health_factor - 10.0 * a.price_after / 14_000.0).abs() < 1e-9);
+
+        // C's low debt rides out both sales
+        let survivor = &cascade.final_positions[2];
+        assert!(survivor.health_factor > survivor.liquidation_threshold);
+        assert!((survivor.current_price - b.price_after).abs() < 1e-9);
+        assert!((cascade.price_multipliers["ETH"] - b.price_after / 2000.0).abs() < 1e-12);
+    }
+}
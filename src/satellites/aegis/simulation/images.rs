@@ -0,0 +1,212 @@
+use super::visualization::{PortfolioChartData, RiskHeatmapData, VisualizationFramework};
+use plotters::prelude::*;
+
+const CHART_SIZE: (u32, u32) = (800, 480);
+const HEATMAP_CELL_PX: u32 = 48;
+
+/// Piecewise-linear color scale over a value range, e.g. correlations from -1 to 1 or
+/// health factors from 1 to 3. Values outside the stops take the nearest end color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorGradient {
+    /// `(value, rgb)` stops in ascending value order
+    stops: Vec<(f64, (u8, u8, u8))>,
+}
+
+impl ColorGradient {
+    pub fn new(mut stops: Vec<(f64, (u8, u8, u8))>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if stops.is_empty() || stops.iter().any(|(value, _)| !value.is_finite()) {
+            return Err("A color gradient needs at least one finite stop".into());
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { stops })
+    }
+
+    pub fn color_at(&self, value: f64) -> (u8, u8, u8) {
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if value.is_nan() || value <= first.0 {
+            return first.1;
+        }
+        if value >= last.0 {
+            return last.1;
+        }
+
+        let upper = self.stops.iter().position(|(stop, _)| *stop >= value).unwrap_or(self.stops.len() - 1);
+        let (low_value, low) = self.stops[upper - 1];
+        let (high_value, high) = self.stops[upper];
+        let t = (value - low_value) / (high_value - low_value);
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        (channel(low.0, high.0), channel(low.1, high.1), channel(low.2, high.2))
+    }
+}
+
+impl Default for ColorGradient {
+    /// Blue for -1 through white at 0 to red for 1, suited to correlations
+    fn default() -> Self {
+        Self {
+            stops: vec![(-1.0, (33, 102, 172)), (0.0, (247, 247, 247)), (1.0, (178, 24, 43))],
+        }
+    }
+}
+
+impl VisualizationFramework {
+    /// Line chart of the portfolio value curve as a PNG.
+    ///
+    /// Images carry no text, so the feature needs no font backend; titles and axis values
+    /// belong in the surrounding report.
+    pub fn export_chart_png(&self, chart: &PortfolioChartData) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let values: Vec<f64> = chart.portfolio_values.iter().map(|point| point.value).collect();
+        let (mut low, mut high) = values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &v| (low.min(v), high.max(v)));
+        if values.is_empty() {
+            (low, high) = (0.0, 1.0);
+        } else if low == high {
+            (low, high) = (low - 1.0, high + 1.0);
+        }
+        let x_max = values.len().saturating_sub(1).max(1) as f64;
+
+        let (width, height) = CHART_SIZE;
+        let mut pixels = vec![0u8; (width * height * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+            root.fill(&WHITE).map_err(|e| e.to_string())?;
+            let mut plot = ChartBuilder::on(&root)
+                .margin(24)
+                .build_cartesian_2d(0f64..x_max, low..high)
+                .map_err(|e| e.to_string())?;
+            plot.configure_mesh()
+                .x_labels(0)
+                .y_labels(0)
+                .draw()
+                .map_err(|e| e.to_string())?;
+            plot.draw_series(LineSeries::new(
+                values.iter().enumerate().map(|(index, value)| (index as f64, *value)),
+                ShapeStyle::from(&RGBColor(33, 102, 172)).stroke_width(2),
+            )).map_err(|e| e.to_string())?;
+            root.present().map_err(|e| e.to_string())?;
+        }
+
+        encode_png(&pixels, width, height)
+    }
+
+    /// Heatmap of `heatmap.correlation_matrix` as a PNG, one square cell per entry,
+    /// colored through `gradient`
+    pub fn export_heatmap_png(&self, heatmap: &RiskHeatmapData, gradient: &ColorGradient) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let rows = heatmap.correlation_matrix.len();
+        let columns = heatmap.correlation_matrix.iter().map(Vec::len).max().unwrap_or(0);
+        if rows == 0 || columns == 0 {
+            return Err("Cannot render an empty heatmap".into());
+        }
+
+        let (width, height) = (columns as u32 * HEATMAP_CELL_PX, rows as u32 * HEATMAP_CELL_PX);
+        let mut pixels = vec![0u8; (width * height * 3) as usize];
+        {
+            let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+            root.fill(&WHITE).map_err(|e| e.to_string())?;
+            for (row, values) in heatmap.correlation_matrix.iter().enumerate() {
+                for (column, value) in values.iter().enumerate() {
+                    let (r, g, b) = gradient.color_at(*value);
+                    let top_left = ((column as u32 * HEATMAP_CELL_PX) as i32, (row as u32 * HEATMAP_CELL_PX) as i32);
+                    let bottom_right = (top_left.0 + HEATMAP_CELL_PX as i32, top_left.1 + HEATMAP_CELL_PX as i32);
+                    root.draw(&Rectangle::new([top_left, bottom_right], RGBColor(r, g, b).filled()))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            root.present().map_err(|e| e.to_string())?;
+        }
+
+        encode_png(&pixels, width, height)
+    }
+}
+
+fn encode_png(rgb: &[u8], width: u32, height: u32) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(rgb)?;
+    }
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::ChartDataPoint;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    fn decode(png_bytes: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+        let mut reader = png::Decoder::new(png_bytes).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        (info, pixels)
+    }
+
+    #[test]
+    fn test_equity_curve_png() {
+        let framework = VisualizationFramework::new();
+        let chart = PortfolioChartData {
+            portfolio_values: [100.0, 104.0, 97.0, 110.0].iter()
+                .map(|value| ChartDataPoint { timestamp: Utc::now(), value: *value, label: None })
+                .collect(),
+            drawdown_curve: vec![],
+            risk_metrics: vec![],
+            position_performance: HashMap::new(),
+        };
+
+        let png_bytes = framework.export_chart_png(&chart).unwrap();
+        assert_eq!(&png_bytes[..8], &PNG_SIGNATURE);
+        let (info, _) = decode(&png_bytes);
+        assert_eq!((info.width, info.height), CHART_SIZE);
+
+        // A flat or empty curve still renders
+        let flat = PortfolioChartData { portfolio_values: chart.portfolio_values[..1].to_vec(), ..chart.clone() };
+        assert_eq!(&framework.export_chart_png(&flat).unwrap()[..8], &PNG_SIGNATURE);
+    }
+
+    #[test]
+    fn test_heatmap_png_uses_gradient() {
+        let framework = VisualizationFramework::new();
+        let heatmap = RiskHeatmapData {
+            correlation_matrix: vec![vec![1.0, -1.0], vec![-1.0, 1.0]],
+            asset_names: vec!["ETH".to_string(), "BTC".to_string()],
+            risk_scores: HashMap::new(),
+            concentration_metrics: HashMap::new(),
+        };
+        let gradient = ColorGradient::new(vec![(-1.0, (0, 0, 255)), (1.0, (255, 0, 0))]).unwrap();
+
+        let png_bytes = framework.export_heatmap_png(&heatmap, &gradient).unwrap();
+        assert_eq!(&png_bytes[..8], &PNG_SIGNATURE);
+        let (info, pixels) = decode(&png_bytes);
+        assert_eq!((info.width, info.height), (2 * HEATMAP_CELL_PX, 2 * HEATMAP_CELL_PX));
+
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * info.width + x) * 3) as usize;
+            (pixels[offset], pixels[offset + 1], pixels[offset + 2])
+        };
+        assert_eq!(pixel(10, 10), (255, 0, 0));
+        assert_eq!(pixel(HEATMAP_CELL_PX + 10, 10), (0, 0, 255));
+
+        let empty = RiskHeatmapData { correlation_matrix: vec![], ..heatmap };
+        assert!(framework.export_heatmap_png(&empty, &gradient).is_err());
+    }
+
+    #[test]
+    fn test_gradient_interpolates_between_stops() {
+        let gradient = ColorGradient::default();
+        assert_eq!(gradient.color_at(-1.0), (33, 102, 172));
+        assert_eq!(gradient.color_at(0.0), (247, 247, 247));
+        assert_eq!(gradient.color_at(5.0), (178, 24, 43));
+
+        // Health factors: red at 1.0 shading to green at 2.0, given out of order
+        let health = ColorGradient::new(vec![(2.0, (0, 200, 0)), (1.0, (200, 0, 0))]).unwrap();
+        assert_eq!(health.color_at(1.5), (100, 100, 0));
+        assert_eq!(health.color_at(0.5), (200, 0, 0));
+
+        assert!(ColorGradient::new(vec![]).is_err());
+        assert!(ColorGradient::new(vec![(f64::NAN, (0, 0, 0))]).is_err());
+    }
+}
@@ -1,5 +1,11 @@
 pub mod stress_testing;
+pub mod scenario_builder;
 pub mod visualization;
+pub mod cascade;
+#[cfg(feature = "images")]
+pub mod images;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
 
 pub use stress_testing::{
     StressTestingFramework,
@@ -7,18 +13,36 @@ pub use stress_testing::{
     SimulationPosition,
     SimulationScenario,
     SimulationResult,
+    HistoricalPricePoint,
     RiskMetrics,
     SimulationRecommendation,
     MonteCarloConfig,
+    MonteCarloExecutionMode,
+    MonteCarloSummary,
+    ProgressUpdate,
+    ProgressCallback,
+    SimulationError,
     CustomScenario,
+    DepegScenario,
     RecommendationType,
     RecommendationPriority,
 };
 
+pub use scenario_builder::{ScenarioBuilder, ScenarioError};
+
+pub use cascade::{LiquidationCascadeSimulator, LiquidationCascade, CascadeLiquidation};
+
 pub use visualization::{
     VisualizationFramework,
     SimulationReport,
     PortfolioChartData,
     RiskHeatmapData,
     ChartDataPoint,
-}; 
\ No newline at end of file
+    ReportAggregation,
+};
+
+#[cfg(feature = "images")]
+pub use images::ColorGradient;
+
+#[cfg(feature = "xlsx")]
+pub use xlsx::{HIGH_CORRELATION_THRESHOLD, SUMMARY_SHEET, POSITIONS_SHEET, CORRELATION_SHEET};
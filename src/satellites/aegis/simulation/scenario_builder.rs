@@ -0,0 +1,267 @@
+use crate::simulation::stress_testing::{CustomScenario, SimulationPosition, SimulationScenario};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScenarioError {
+    #[error("Shock on {asset}, which is not in the portfolio")]
+    UnknownAsset { asset: String },
+    #[error("Shock of {shock} on {asset} is not a valid price change (must be finite and at least -1.0)")]
+    InvalidShock { asset: String, shock: f64 },
+    #[error("{asset} is shocked by both {first} and {second}")]
+    ConflictingShock { asset: String, first: f64, second: f64 },
+    #[error("{asset} is correlated with {driver}, which has no direct shock")]
+    UnshockedDriver { asset: String, driver: String },
+    #[error("Time horizon must be at least one day")]
+    InvalidHorizon,
+    #[error("Liquidity drain of {factor} is outside 0.0-1.0")]
+    InvalidLiquidityDrain { factor: f64 },
+    #[error("Volatility multiplier of {multiplier} must be positive")]
+    InvalidVolatility { multiplier: f64 },
+}
+
+/// A shock on `asset` of `beta` times the direct shock on `driver`
+#[derive(Debug, Clone)]
+struct CorrelatedShock {
+    asset: String,
+    driver: String,
+    beta: f64,
+}
+
+/// Fluent construction of a validated `SimulationScenario::Custom` against a portfolio.
+///
+/// Every shocked asset must be held in the portfolio, and an asset may be shocked either
+/// directly or through a correlation, not both. Problems are reported by `build`.
+#[derive(Debug, Clone)]
+pub struct ScenarioBuilder {
+    name: String,
+    description: String,
+    portfolio: HashSet<String>,
+    shocks: Vec<(String, f64)>,
+    correlated_shocks: Vec<CorrelatedShock>,
+    duration_days: u32,
+    liquidity_drain: f64,
+    volatility_multiplier: f64,
+    correlation_breakdown: bool,
+}
+
+impl ScenarioBuilder {
+    pub fn new(name: &str, positions: &[SimulationPosition]) -> Self {
+        Self {
+            name: name.to_string(),
+            description: String::new(),
+            portfolio: positions.iter().map(|position| position.token_address.clone()).collect(),
+            shocks: Vec::new(),
+            correlated_shocks: Vec::new(),
+            duration_days: 1,
+            liquidity_drain: 0.0,
+            volatility_multiplier: 1.0,
+            correlation_breakdown: false,
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Price change for one asset, e.g. `-0.4` for a 40% drop
+    pub fn shock(mut self, asset: &str, shock: f64) -> Self {
+        self.shocks.push((asset.to_string(), shock));
+        self
+    }
+
+    /// Move `asset` by `beta` times the shock applied to `driver`
+    pub fn correlated_shock(mut self, asset: &str, driver: &str, beta: f64) -> Self {
+        self.correlated_shocks.push(CorrelatedShock {
+            asset: asset.to_string(),
+            driver: driver.to_string(),
+            beta,
+        });
+        self
+    }
+
+    pub fn time_horizon_days(mut self, days: u32) -> Self {
+        self.duration_days = days;
+        self
+    }
+
+    /// Fraction of collateral value lost when exiting into drained liquidity
+    pub fn liquidity_drain(mut self, factor: f64) -> Self {
+        self.liquidity_drain = factor;
+        self
+    }
+
+    pub fn volatility_multiplier(mut self, multiplier: f64) -> Self {
+        self.volatility_multiplier = multiplier;
+        self
+    }
+
+    /// Assume historical correlations stop holding during the scenario
+    pub fn correlation_breakdown(mut self) -> Self {
+        self.correlation_breakdown = true;
+        self
+    }
+
+    pub fn build(self) -> Result<SimulationScenario, ScenarioError> {
+        if self.duration_days == 0 {
+            return Err(ScenarioError::InvalidHorizon);
+        }
+        if !(0.0..=1.0).contains(&self.liquidity_drain) {
+            return Err(ScenarioError::InvalidLiquidityDrain { factor: self.liquidity_drain });
+        }
+        if !(self.volatility_multiplier.is_finite() && self.volatility_multiplier > 0.0) {
+            return Err(ScenarioError::InvalidVolatility { multiplier: self.volatility_multiplier });
+        }
+
+        let mut price_shocks: HashMap<String, f64> = HashMap::new();
+        for (asset, shock) in &self.shocks {
+            self.check_shock(asset, *shock)?;
+            match price_shocks.get(asset) {
+                Some(first) if first != shock => {
+                    return Err(ScenarioError::ConflictingShock { asset: asset.clone(), first: *first, second: *shock });
+                }
+                _ => {
+                    price_shocks.insert(asset.clone(), *shock);
+                }
+            }
+        }
+
+        let mut correlated: HashMap<String, f64> = HashMap::new();
+        for CorrelatedShock { asset, driver, beta } in &self.correlated_shocks {
+            let driver_shock = match price_shocks.get(driver) {
+                Some(shock) => *shock,
+                None => return Err(ScenarioError::UnshockedDriver { asset: asset.clone(), driver: driver.clone() }),
+            };
+            let shock = beta * driver_shock;
+            self.check_shock(asset, shock)?;
+            if let Some(first) = price_shocks.get(asset).or_else(|| correlated.get(asset)) {
+                if *first != shock {
+                    return Err(ScenarioError::ConflictingShock { asset: asset.clone(), first: *first, second: shock });
+                }
+            }
+            correlated.insert(asset.clone(), shock);
+        }
+        price_shocks.extend(correlated);
+
+        Ok(SimulationScenario::Custom(CustomScenario {
+            name: self.name,
+            description: self.description,
+            price_shocks,
+            volume_shocks: HashMap::new(),
+            volatility_multiplier: self.volatility_multiplier,
+            correlation_breakdown: self.correlation_breakdown,
+            liquidity_crisis: self.liquidity_drain > 0.0,
+            duration_days: self.duration_days,
+            liquidity_drain: self.liquidity_drain,
+        }))
+    }
+
+    fn check_shock(&self, asset: &str, shock: f64) -> Result<(), ScenarioError> {
+        if !self.portfolio.contains(asset) {
+            return Err(ScenarioError::UnknownAsset { asset: asset.to_string() });
+        }
+        if !shock.is_finite() || shock < -1.0 {
+            return Err(ScenarioError::InvalidShock { asset: asset.to_string(), shock });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::stress_testing::{StressTestingConfig, StressTestingFramework};
+
+    fn position(token: &str, quantity: f64, price: f64, debt_value: f64) -> SimulationPosition {
+        SimulationPosition {
+            token_address: token.to_string(),
+            quantity,
+            entry_price: price,
+            current_price: price,
+            collateral_value: quantity * price,
+            debt_value,
+            liquidation_threshold: 1.0,
+            health_factor: quantity * price / debt_value,
+            debt_tokens: HashMap::new(),
+        }
+    }
+
+    fn portfolio() -> Vec<SimulationPosition> {
+        vec![
+            position("ETH", 10.0, 2000.0, 10_000.0),
+            position("stETH", 10.0, 2000.0, 15_000.0),
+            position("BTC", 1.0, 60_000.0, 20_000.0),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_2008_style_scenario() {
+        let positions = portfolio();
+        let scenario = ScenarioBuilder::new("2008-style deleveraging", &positions)
+            .description("Slow grind lower as leverage unwinds across the market")
+            .shock("BTC", -0.5)
+            .shock("ETH", -0.6)
+            .correlated_shock("stETH", "ETH", 1.1)
+            .time_horizon_days(180)
+            .liquidity_drain(0.1)
+            .volatility_multiplier(3.0)
+            .correlation_breakdown()
+            .build()
+            .unwrap();
+
+        let custom = match &scenario {
+            SimulationScenario::Custom(custom) => custom.clone(),
+            other => panic!("expected a custom scenario, got {:?}", other),
+        };
+        assert_eq!(custom.duration_days, 180);
+        assert!(custom.liquidity_crisis);
+        assert!((custom.price_shocks["stETH"] + 0.66).abs() < 1e-12);
+
+        // After the 10% drain BTC keeps $27,000 against $20,000 of debt; both ETH legs go under
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let result = framework.run_stress_test(&positions, &scenario).await.unwrap();
+        assert_eq!(result.liquidated_positions, vec!["ETH".to_string(), "stETH".to_string()]);
+        assert_eq!(result.surviving_positions, vec!["BTC".to_string()]);
+        assert!(result.final_portfolio_value < result.initial_portfolio_value);
+    }
+
+    #[tokio::test]
+    async fn test_flash_crash_scenario() {
+        let positions = portfolio();
+        let scenario = ScenarioBuilder::new("Flash crash", &positions)
+            .shock("ETH", -0.3)
+            .correlated_shock("stETH", "ETH", 1.0)
+            .liquidity_drain(0.5)
+            .build()
+            .unwrap();
+        let custom = match &scenario {
+            SimulationScenario::Custom(custom) => custom.clone(),
+            other => panic!("expected a custom scenario, got {:?}", other),
+        };
+        assert_eq!(custom.duration_days, 1);
+        assert!(!custom.price_shocks.contains_key("BTC"));
+
+        // Drain alone halves BTC collateral: $30,000 still covers the $20,000 of debt
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let result = framework.run_stress_test(&positions, &scenario).await.unwrap();
+        assert_eq!(result.surviving_positions, vec!["BTC".to_string()]);
+
+        let invalid = [
+            ScenarioBuilder::new("unknown asset", &positions).shock("SOL", -0.2).build(),
+            ScenarioBuilder::new("conflict", &positions).shock("ETH", -0.3).shock("ETH", -0.5).build(),
+            ScenarioBuilder::new("double shock", &positions).shock("ETH", -0.3).shock("stETH", -0.1).correlated_shock("stETH", "ETH", 1.0).build(),
+            ScenarioBuilder::new("no driver", &positions).correlated_shock("stETH", "ETH", 1.0).build(),
+            ScenarioBuilder::new("below zero", &positions).shock("ETH", -1.5).build(),
+            ScenarioBuilder::new("no horizon", &positions).shock("ETH", -0.3).time_horizon_days(0).build(),
+            ScenarioBuilder::new("drain", &positions).liquidity_drain(1.5).build(),
+        ];
+        let errors: Vec<String> = invalid.into_iter().map(|result| result.unwrap_err().to_string()).collect();
+        assert_eq!(errors[0], "Shock on SOL, which is not in the portfolio");
+        assert_eq!(errors[1], "ETH is shocked by both -0.3 and -0.5");
+        assert_eq!(errors[2], "stETH is shocked by both -0.1 and -0.3");
+        assert_eq!(errors[3], "stETH is correlated with ETH, which has no direct shock");
+        assert!(errors[4].contains("-1.5 on ETH"));
+        assert_eq!(errors[5], "Time horizon must be at least one day");
+        assert_eq!(errors[6], "Liquidity drain of 1.5 is outside 0.0-1.0");
+    }
+}
@@ -1,13 +1,20 @@
 use crate::security::{Vulnerability, VulnerabilitySeverity, VulnerabilityCategory};
+use crate::data::VolatilityTracker;
+use crate::risk::{diversification_ratio, herfindahl_index, CorrelationMatrix};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc, Duration};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use log::{info, warn, error, debug};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use rand_distr::{Normal, Distribution};
+use rayon::prelude::*;
+use tokio_util::sync::CancellationToken;
 
 /// Simulation scenario types
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq)]
@@ -17,9 +24,66 @@ pub enum SimulationScenario {
     DeFiContagion,
     RegulatoryShock,
     BlackSwan,
+    StablecoinDepeg(DepegScenario),
+    Custom(CustomScenario),
 }
 
-/// Custom simulation scenario
+impl SimulationScenario {
+    /// Short label for logs and trace attributes; custom scenarios use their own name
+    pub fn name(&self) -> &str {
+        match self {
+            SimulationScenario::HistoricalMarketCrash => "historical_market_crash",
+            SimulationScenario::CryptoWinter => "crypto_winter",
+            SimulationScenario::DeFiContagion => "defi_contagion",
+            SimulationScenario::RegulatoryShock => "regulatory_shock",
+            SimulationScenario::BlackSwan => "black_swan",
+            SimulationScenario::StablecoinDepeg(_) => "stablecoin_depeg",
+            SimulationScenario::Custom(custom) => &custom.name,
+        }
+    }
+}
+
+/// A stablecoin losing its $1 peg and then partially recovering
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, Hash, PartialEq)]
+pub struct DepegScenario {
+    pub stablecoin: String,
+    /// Lowest price reached, in basis points of $1 (9000 = $0.90)
+    pub trough_price_bps: u32,
+    pub days_to_trough: u32,
+    pub recovery_days: u32,
+    /// Price once the recovery completes, in basis points of $1
+    pub recovered_price_bps: u32,
+}
+
+impl DepegScenario {
+    /// Price in basis points on the given day: a linear slide to the trough followed by a
+    /// linear recovery, holding at the recovered price afterwards
+    pub fn price_bps_at_day(&self, day: u32) -> u32 {
+        let interpolate = |from: u32, to: u32, step: u32, steps: u32| -> u32 {
+            if steps == 0 {
+                return to;
+            }
+            let (from, to) = (from as i64, to as i64);
+            (from + (to - from) * step.min(steps) as i64 / steps as i64) as u32
+        };
+
+        if day <= self.days_to_trough {
+            interpolate(10_000, self.trough_price_bps, day, self.days_to_trough)
+        } else {
+            interpolate(self.trough_price_bps, self.recovered_price_bps, day - self.days_to_trough, self.recovery_days)
+        }
+    }
+
+    pub fn price_at_day(&self, day: u32) -> f64 {
+        self.price_bps_at_day(day) as f64 / 10_000.0
+    }
+
+    pub fn duration_days(&self) -> u32 {
+        self.days_to_trough + self.recovery_days
+    }
+}
+
+/// Custom simulation scenario, usually assembled with a `ScenarioBuilder`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CustomScenario {
     pub name: String,
@@ -30,6 +94,41 @@ pub struct CustomScenario {
     pub correlation_breakdown: bool,
     pub liquidity_crisis: bool,
     pub duration_days: u32,
+    /// Fraction of collateral value lost to thin exit liquidity, 0.0-1.0
+    #[serde(default)]
+    pub liquidity_drain: f64,
+}
+
+impl CustomScenario {
+    /// Fields in a canonical form; floats compare by bit pattern so the scenario can be
+    /// a template key like the other `SimulationScenario` variants
+    fn canonical(&self) -> impl PartialEq + std::hash::Hash + '_ {
+        fn sorted_bits(shocks: &HashMap<String, f64>) -> Vec<(&str, u64)> {
+            let mut bits: Vec<(&str, u64)> = shocks.iter().map(|(token, shock)| (token.as_str(), shock.to_bits())).collect();
+            bits.sort_unstable();
+            bits
+        }
+        (
+            (&self.name, &self.description, self.duration_days),
+            (sorted_bits(&self.price_shocks), sorted_bits(&self.volume_shocks)),
+            (self.volatility_multiplier.to_bits(), self.liquidity_drain.to_bits()),
+            (self.correlation_breakdown, self.liquidity_crisis),
+        )
+    }
+}
+
+impl PartialEq for CustomScenario {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+impl Eq for CustomScenario {}
+
+impl std::hash::Hash for CustomScenario {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
 }
 
 /// Portfolio position for simulation
@@ -43,6 +142,9 @@ pub struct SimulationPosition {
     pub debt_value: f64,
     pub liquidation_threshold: f64,
     pub health_factor: f64,
+    /// USD value of `debt_value` owed in each token, where the debt's tokens are known
+    #[serde(default)]
+    pub debt_tokens: HashMap<String, f64>,
 }
 
 /// Simulation result
@@ -60,6 +162,10 @@ pub struct SimulationResult {
     pub recommendations: Vec<SimulationRecommendation>,
     pub simulation_duration_ms: u64,
     pub timestamp: DateTime<Utc>,
+    /// Base seed of the Monte Carlo run that produced this result; setting it as
+    /// `MonteCarloConfig::seed` replays the run exactly
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 /// Risk metrics
@@ -73,6 +179,18 @@ pub struct RiskMetrics {
     pub volatility: f64,
     pub beta: f64,
     pub correlation_matrix: Vec<Vec<f64>>,
+    /// Largest peak-to-trough fall of the equity curve as a positive fraction of the peak
+    #[serde(default)]
+    pub max_drawdown: f64,
+    /// Loss in USD not exceeded on 95% of simulated paths; zero outside Monte Carlo runs
+    #[serde(default)]
+    pub value_at_risk_95: f64,
+    /// Loss in USD not exceeded on 99% of simulated paths
+    #[serde(default)]
+    pub value_at_risk_99: f64,
+    /// Mean USD loss over the paths beyond `value_at_risk_95` (expected shortfall)
+    #[serde(default)]
+    pub conditional_var_95: f64,
 }
 
 /// Simulation recommendation
@@ -108,6 +226,100 @@ pub enum RecommendationPriority {
     Critical,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum SimulationError {
+    /// The run's cancellation token fired; nothing from the run is returned or cached
+    #[error("Simulation cancelled")]
+    Cancelled,
+}
+
+/// Progress of a running simulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    /// 0.0-100.0; 100.0 is reported exactly once, when the run completes
+    pub percent_complete: f64,
+    pub paths_processed: u64,
+    pub total_paths: u64,
+}
+
+/// Receives progress updates; may be called from simulation worker threads
+pub type ProgressCallback = Arc<dyn Fn(ProgressUpdate) + Send + Sync>;
+
+/// Throttles progress updates so fast paths don't flood the callback
+struct ProgressReporter {
+    callback: ProgressCallback,
+    interval: std::time::Duration,
+    total_paths: u64,
+    processed: AtomicU64,
+    started: std::time::Instant,
+    /// Nanoseconds after `started` at which the next update is due. Workers only reach
+    /// for `last_report` once it has passed.
+    next_report_nanos: AtomicU64,
+    /// Paths reported by the last update. Held while the callback runs so concurrent
+    /// workers report in increasing order.
+    last_report: std::sync::Mutex<u64>,
+}
+
+impl ProgressReporter {
+    fn new(callback: ProgressCallback, total_paths: u64, interval: std::time::Duration) -> Self {
+        Self {
+            callback,
+            interval,
+            total_paths,
+            processed: AtomicU64::new(0),
+            started: std::time::Instant::now(),
+            next_report_nanos: AtomicU64::new(interval.as_nanos() as u64),
+            last_report: std::sync::Mutex::new(0),
+        }
+    }
+
+    fn update(&self, paths_processed: u64) -> ProgressUpdate {
+        let percent_complete = match self.total_paths {
+            0 => 0.0,
+            total => paths_processed as f64 / total as f64 * 100.0,
+        };
+        ProgressUpdate { percent_complete, paths_processed, total_paths: self.total_paths }
+    }
+
+    fn start(&self) {
+        (self.callback)(self.update(0));
+    }
+
+    /// Record a finished path, reporting if the interval has passed. The final path is
+    /// left to `finish`, which runs once the results are assembled.
+    fn path_done(&self) {
+        let processed = self.processed.fetch_add(1, Ordering::Relaxed) + 1;
+        if processed >= self.total_paths {
+            return;
+        }
+        let now = self.started.elapsed().as_nanos() as u64;
+        if now < self.next_report_nanos.load(Ordering::Relaxed) {
+            return;
+        }
+        // A worker already holding the lock is sending an update that covers this path
+        let mut last_report = match self.last_report.try_lock() {
+            Ok(last_report) => last_report,
+            Err(_) => return,
+        };
+        let processed = self.processed.load(Ordering::Relaxed);
+        if now < self.next_report_nanos.load(Ordering::Relaxed) || processed <= *last_report || processed >= self.total_paths {
+            return;
+        }
+        self.next_report_nanos.store(now + self.interval.as_nanos() as u64, Ordering::Relaxed);
+        *last_report = processed;
+        (self.callback)(self.update(processed));
+    }
+
+    fn finish(&self) {
+        let _last_report = self.last_report.lock().unwrap();
+        (self.callback)(ProgressUpdate {
+            percent_complete: 100.0,
+            paths_processed: self.total_paths,
+            total_paths: self.total_paths,
+        });
+    }
+}
+
 /// Monte Carlo simulation configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonteCarloConfig {
@@ -115,8 +327,142 @@ pub struct MonteCarloConfig {
     pub time_horizon_days: u32,
     pub confidence_level: f64,
     pub price_volatility: f64,
-    pub correlation_matrix: Vec<Vec<f64>>,
     pub drift_rates: HashMap<String, f64>,
+    /// Seed for reproducible runs; a random seed is drawn when unset
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub execution_mode: MonteCarloExecutionMode,
+    /// Asset correlations for joint price shocks, e.g. from `CorrelationAnalysisSystem`.
+    /// Assets missing from the matrix move independently; when unset every position
+    /// is shocked independently.
+    #[serde(default)]
+    pub correlations: Option<CorrelationMatrix>,
+}
+
+/// One Monte Carlo path: the positions at the horizon, and the portfolio's net value at
+/// the start and after each simulated day
+struct SimulatedPath {
+    positions: Vec<SimulationPosition>,
+    equity_curve: Vec<f64>,
+}
+
+/// How per-asset price shocks are drawn on each Monte Carlo path
+enum ShockModel {
+    Independent,
+    /// Shocks are `L z` for independent normal draws `z`, one per asset, where `L` is
+    /// the Cholesky factor of the asset correlation matrix
+    Correlated {
+        /// Row of `cholesky` for each position's asset
+        asset_index: Vec<usize>,
+        cholesky: Vec<Vec<f64>>,
+    },
+}
+
+impl ShockModel {
+    fn new(positions: &[SimulationPosition], config: &MonteCarloConfig) -> Self {
+        let correlations = match &config.correlations {
+            Some(correlations) => correlations,
+            None => return ShockModel::Independent,
+        };
+
+        let mut assets: Vec<&str> = Vec::new();
+        let mut asset_index = Vec::with_capacity(positions.len());
+        for position in positions {
+            let index = match assets.iter().position(|asset| *asset == position.token_address) {
+                Some(index) => index,
+                None => {
+                    assets.push(&position.token_address);
+                    assets.len() - 1
+                }
+            };
+            asset_index.push(index);
+        }
+
+        let matrix_index: HashMap<&str, usize> = correlations.assets.iter()
+            .enumerate()
+            .map(|(index, asset)| (asset.as_str(), index))
+            .collect();
+        let correlation = |a: &str, b: &str| match (matrix_index.get(a), matrix_index.get(b)) {
+            (Some(&i), Some(&j)) => correlations.matrix.get(i).and_then(|row| row.get(j)).copied().unwrap_or(0.0),
+            _ => 0.0,
+        };
+        let matrix: Vec<Vec<f64>> = assets.iter().enumerate()
+            .map(|(i, a)| assets.iter().enumerate()
+                .map(|(j, b)| if i == j { 1.0 } else { correlation(a, b) })
+                .collect())
+            .collect();
+
+        match cholesky_decompose(&matrix) {
+            Some(cholesky) => ShockModel::Correlated { asset_index, cholesky },
+            None => {
+                warn!("Correlation matrix over {:?} is not positive-definite, drawing independent price shocks", assets);
+                ShockModel::Independent
+            }
+        }
+    }
+
+    /// Correlation between the shocks of every pair of the `positions` this model was
+    /// built for, recovered as `L Lᵀ` of the asset rows
+    fn position_correlations(&self, positions: usize) -> Vec<Vec<f64>> {
+        match self {
+            ShockModel::Independent => (0..positions)
+                .map(|i| (0..positions).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+                .collect(),
+            ShockModel::Correlated { asset_index, cholesky } => asset_index.iter()
+                .map(|&a| asset_index.iter()
+                    .map(|&b| cholesky[a].iter().zip(&cholesky[b]).map(|(x, y)| x * y).sum())
+                    .collect())
+                .collect(),
+        }
+    }
+}
+
+/// Lower-triangular `L` with `L Lᵀ = matrix`, or `None` when the square `matrix` is not
+/// symmetric positive-definite
+fn cholesky_decompose(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            if (matrix[i][j] - matrix[j][i]).abs() > 1e-9 {
+                return None;
+            }
+            let sum: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                let diagonal = matrix[i][i] - sum;
+                if diagonal.is_nan() || diagonal <= 0.0 {
+                    return None;
+                }
+                lower[i][i] = diagonal.sqrt();
+            } else {
+                lower[i][j] = (matrix[i][j] - sum) / lower[j][j];
+            }
+        }
+    }
+    Some(lower)
+}
+
+/// How Monte Carlo paths are scheduled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum MonteCarloExecutionMode {
+    #[default]
+    Serial,
+    /// Paths run on a rayon pool of `workers` threads, or one per core when zero. Each
+    /// path draws from its own seeded generator and results are reduced in fixed path
+    /// order, so aggregates are bit-identical to a serial run with the same seed.
+    DeterministicParallel { workers: usize },
+}
+
+/// Aggregate metrics over all Monte Carlo paths
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MonteCarloSummary {
+    pub paths: usize,
+    pub mean_return: f64,
+    pub return_volatility: f64,
+    pub var_95: f64,
+    pub cvar_95: f64,
+    pub worst_return: f64,
 }
 
 /// Stress testing configuration
@@ -128,6 +474,42 @@ pub struct StressTestingConfig {
     pub historical_data_years: u32,
     pub enable_visualization: bool,
     pub auto_recommendations: bool,
+    /// Annual risk-free rate that Sharpe and Sortino ratios are measured against
+    #[serde(default = "default_risk_free_rate")]
+    pub risk_free_rate: f64,
+    /// Herfindahl-Hirschman index of collateral above which diversifying is recommended
+    #[serde(default = "default_max_concentration_index")]
+    pub max_concentration_index: f64,
+    /// Minimum time between progress callbacks during a run
+    #[serde(default = "default_progress_interval_ms")]
+    pub progress_interval_ms: u64,
+    /// Cached stress test results kept before the least recently used is evicted
+    #[serde(default = "default_max_cache_entries")]
+    pub max_cache_entries: usize,
+    /// Age after which a cached result is recomputed
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+}
+
+fn default_max_cache_entries() -> usize {
+    1_000
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    3_600
+}
+
+fn default_progress_interval_ms() -> u64 {
+    250
+}
+
+fn default_risk_free_rate() -> f64 {
+    0.02
+}
+
+/// Fewer than two equally sized positions' worth of diversification
+fn default_max_concentration_index() -> f64 {
+    0.5
 }
 
 impl Default for StressTestingConfig {
@@ -145,13 +527,20 @@ impl Default for StressTestingConfig {
                 time_horizon_days: 30,
                 confidence_level: 0.95,
                 price_volatility: 0.5,
-                correlation_matrix: vec![vec![1.0]],
                 drift_rates: HashMap::new(),
+                seed: None,
+                execution_mode: MonteCarloExecutionMode::Serial,
+                correlations: None,
             },
             backtesting_enabled: true,
             historical_data_years: 3,
             enable_visualization: true,
             auto_recommendations: true,
+            risk_free_rate: default_risk_free_rate(),
+            max_concentration_index: default_max_concentration_index(),
+            progress_interval_ms: default_progress_interval_ms(),
+            max_cache_entries: default_max_cache_entries(),
+            cache_ttl_secs: default_cache_ttl_secs(),
         }
     }
 }
@@ -160,8 +549,127 @@ impl Default for StressTestingConfig {
 pub struct StressTestingFramework {
     config: StressTestingConfig,
     historical_data: Arc<RwLock<HashMap<String, Vec<HistoricalPricePoint>>>>,
-    simulation_cache: Arc<RwLock<HashMap<String, SimulationResult>>>,
+    simulation_cache: Arc<RwLock<SimulationCache>>,
     scenario_templates: HashMap<SimulationScenario, ScenarioTemplate>,
+    volatility_tracker: Option<Arc<VolatilityTracker>>,
+}
+
+/// Stress test results by cache key, with LRU eviction and time-based expiry
+struct SimulationCache {
+    entries: HashMap<String, CachedSimulation>,
+    /// Keys by `last_used`, so the least recently used entry is always first
+    recency: BTreeMap<u64, String>,
+    max_entries: usize,
+    ttl: std::time::Duration,
+    /// Incremented on every insert and hit; entries with the lowest `last_used` go first
+    clock: u64,
+    expired: usize,
+    evicted: usize,
+    hits: usize,
+    misses: usize,
+}
+
+struct CachedSimulation {
+    result: SimulationResult,
+    inserted_at: std::time::Instant,
+    last_used: u64,
+}
+
+impl SimulationCache {
+    fn new(max_entries: usize, ttl: std::time::Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            max_entries,
+            ttl,
+            clock: 0,
+            expired: 0,
+            evicted: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<SimulationResult> {
+        let ttl = self.ttl;
+        let expired = match self.entries.get(key) {
+            Some(entry) => entry.inserted_at.elapsed() >= ttl,
+            None => {
+                self.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            self.remove(key);
+            self.expired += 1;
+            self.misses += 1;
+            return None;
+        }
+
+        self.clock += 1;
+        self.hits += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(self.clock, key.to_string());
+        entry.last_used = self.clock;
+        Some(entry.result.clone())
+    }
+
+    fn insert(&mut self, key: &str, result: SimulationResult) {
+        self.remove_expired();
+        if self.max_entries == 0 {
+            return;
+        }
+        self.remove(key);
+        while self.entries.len() >= self.max_entries && self.evict_least_recently_used() {}
+
+        self.clock += 1;
+        self.recency.insert(self.clock, key.to_string());
+        self.entries.insert(key.to_string(), CachedSimulation {
+            result,
+            inserted_at: std::time::Instant::now(),
+            last_used: self.clock,
+        });
+    }
+
+    fn remove(&mut self, key: &str) -> Option<CachedSimulation> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry)
+    }
+
+    /// Drop the least recently used entry, returning false once the cache is empty
+    fn evict_least_recently_used(&mut self) -> bool {
+        match self.recency.pop_first() {
+            Some((_, key)) => {
+                self.entries.remove(&key);
+                self.evicted += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove_expired(&mut self) {
+        let ttl = self.ttl;
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+        if self.entries.len() < before {
+            let entries = &self.entries;
+            self.recency.retain(|_, key| entries.contains_key(key));
+            self.expired += before - self.entries.len();
+        }
+    }
+
+    /// Drop every entry and start the counters afresh
+    fn clear(&mut self) {
+        *self = Self::new(self.max_entries, self.ttl);
+    }
+
+    /// No entries and no lookups since the cache was created or cleared
+    fn is_unused(&self) -> bool {
+        self.entries.is_empty() && self.hits + self.misses + self.expired + self.evicted == 0
+    }
 }
 
 /// Historical price point
@@ -309,27 +817,110 @@ impl StressTestingFramework {
             }
         );
 
+        let simulation_cache = SimulationCache::new(
+            config.max_cache_entries,
+            std::time::Duration::from_secs(config.cache_ttl_secs),
+        );
+
         Self {
             config,
             historical_data: Arc::new(RwLock::new(HashMap::new())),
-            simulation_cache: Arc::new(RwLock::new(HashMap::new())),
+            simulation_cache: Arc::new(RwLock::new(simulation_cache)),
             scenario_templates,
+            volatility_tracker: None,
         }
     }
 
+    /// Use realized volatility from `tracker` for tokens it has seen, instead of the
+    /// configured `price_volatility`
+    pub fn with_volatility_tracker(mut self, tracker: Arc<VolatilityTracker>) -> Self {
+        self.volatility_tracker = Some(tracker);
+        self
+    }
+
+    /// Volatility of `token` over the horizon of `config`: its realized annualized volatility
+    /// scaled to `time_horizon_days` if tracked, otherwise `config.price_volatility`
+    pub fn token_volatility(&self, token: &str, config: &MonteCarloConfig) -> f64 {
+        let horizon_years = config.time_horizon_days.max(1) as f64 / 365.0;
+        self.volatility_tracker.as_ref()
+            .and_then(|tracker| tracker.annualized_volatility(&token.to_string()))
+            .and_then(|volatility| volatility.to_f64())
+            .map(|annualized| annualized * horizon_years.sqrt())
+            .unwrap_or(config.price_volatility)
+    }
+
+    /// `token_volatility` of each position's token, in position order
+    fn position_volatilities(&self, positions: &[SimulationPosition], config: &MonteCarloConfig) -> Vec<f64> {
+        positions.iter().map(|position| self.token_volatility(&position.token_address, config)).collect()
+    }
+
     /// Run stress test simulation
     pub async fn run_stress_test(
         &self,
         positions: &[SimulationPosition],
         scenario: &SimulationScenario,
     ) -> Result<SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.run_stress_test_with_progress(positions, scenario, None).await
+    }
+
+    /// Run a stress test, reporting progress to `progress`. A stress test is a single
+    /// path, so only the start and completion are reported.
+    pub async fn run_stress_test_with_progress(
+        &self,
+        positions: &[SimulationPosition],
+        scenario: &SimulationScenario,
+        progress: Option<ProgressCallback>,
+    ) -> Result<SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.run_stress_test_cancellable(positions, scenario, progress, CancellationToken::new()).await
+    }
+
+    /// Run a stress test that fails with `SimulationError::Cancelled` once `cancellation`
+    /// fires. A cancelled run is never cached.
+    pub async fn run_stress_test_cancellable(
+        &self,
+        positions: &[SimulationPosition],
+        scenario: &SimulationScenario,
+        progress: Option<ProgressCallback>,
+        cancellation: CancellationToken,
+    ) -> Result<SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        let progress = progress.map(|callback| ProgressReporter::new(callback, 1, self.progress_interval()));
+        if let Some(progress) = &progress {
+            progress.start();
+        }
+        let result = self.stress_test(positions, scenario, &cancellation).await?;
+        if let Some(progress) = &progress {
+            progress.finish();
+        }
+        Ok(result)
+    }
+
+    #[tracing::instrument(
+        name = "run_stress_test",
+        skip_all,
+        fields(
+            scenario = scenario.name(),
+            positions = positions.len(),
+            cached = tracing::field::Empty,
+            liquidated = tracing::field::Empty,
+        )
+    )]
+    async fn stress_test(
+        &self,
+        positions: &[SimulationPosition],
+        scenario: &SimulationScenario,
+        cancellation: &CancellationToken,
+    ) -> Result<SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        Self::check_cancelled(cancellation)?;
         let start_time = std::time::Instant::now();
         
         // Check cache first
         let cache_key = self.generate_cache_key(positions, scenario).await?;
+        let span = tracing::Span::current();
         if let Some(cached_result) = self.get_cached_simulation(&cache_key).await? {
+            span.record("cached", true);
             return Ok(cached_result);
         }
+        span.record("cached", false);
 
         let initial_portfolio_value = self.calculate_portfolio_value(positions).await?;
         
@@ -343,7 +934,7 @@ impl StressTestingFramework {
         let (liquidated, surviving) = self.identify_liquidated_positions(&shocked_positions).await?;
         
         // Calculate risk metrics
-        let risk_metrics = self.calculate_risk_metrics(positions, &shocked_positions).await?;
+        let risk_metrics = self.calculate_shock_risk_metrics(positions, &shocked_positions, self.scenario_duration_days(scenario)).await?;
         
         // Generate recommendations
         let recommendations = if self.config.auto_recommendations {
@@ -358,7 +949,7 @@ impl StressTestingFramework {
             scenario: scenario.clone(),
             initial_portfolio_value,
             final_portfolio_value,
-            max_drawdown: (final_portfolio_value - initial_portfolio_value) / initial_portfolio_value,
+            max_drawdown: risk_metrics.max_drawdown,
             var_95: self.calculate_var_95(positions, scenario).await?,
             cvar_95: self.calculate_cvar_95(positions, scenario).await?,
             liquidated_positions: liquidated.iter().map(|p| p.token_address.clone()).collect(),
@@ -367,9 +958,13 @@ impl StressTestingFramework {
             recommendations,
             simulation_duration_ms: simulation_duration,
             timestamp: Utc::now(),
+            seed: None,
         };
 
+        span.record("liquidated", result.liquidated_positions.len());
+
         // Cache the result
+        Self::check_cancelled(cancellation)?;
         self.cache_simulation(&cache_key, &result).await?;
         
         Ok(result)
@@ -381,16 +976,58 @@ impl StressTestingFramework {
         positions: &[SimulationPosition],
         config: &MonteCarloConfig,
     ) -> Result<Vec<SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
-        let mut results = Vec::new();
-        let mut rng = rand::thread_rng();
+        self.run_monte_carlo_simulation_with_progress(positions, config, None).await
+    }
+
+    /// Run Monte Carlo simulation, reporting simulated paths to `progress` at most once
+    /// per `progress_interval_ms`
+    pub async fn run_monte_carlo_simulation_with_progress(
+        &self,
+        positions: &[SimulationPosition],
+        config: &MonteCarloConfig,
+        progress: Option<ProgressCallback>,
+    ) -> Result<Vec<SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        self.run_monte_carlo_simulation_cancellable(positions, config, progress, CancellationToken::new()).await
+    }
+
+    /// Run Monte Carlo simulation, checking `cancellation` before every path and failing
+    /// with `SimulationError::Cancelled` once it fires. Paths simulated so far are dropped.
+    pub async fn run_monte_carlo_simulation_cancellable(
+        &self,
+        positions: &[SimulationPosition],
+        config: &MonteCarloConfig,
+        progress: Option<ProgressCallback>,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
+        // Drawn here rather than per path so an unseeded run can still be replayed
+        let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let progress = progress.map(|callback| {
+            Arc::new(ProgressReporter::new(callback, config.iterations as u64, self.progress_interval()))
+        });
+        if let Some(progress) = &progress {
+            progress.start();
+        }
+        // Path generation is CPU-bound, so keep it off the async workers
+        let paths = {
+            let positions = positions.to_vec();
+            let volatilities = self.position_volatilities(&positions, config);
+            let config = config.clone();
+            let progress = progress.clone();
+            tokio::task::spawn_blocking(move || {
+                Self::simulate_paths_with_progress(
+                    &positions, &volatilities, &config, seed, progress.as_deref(), Some(&cancellation),
+                )
+            }).await??
+        };
+        let initial_value = self.calculate_portfolio_value(positions).await?;
+        // Lines up with `surviving_positions`, which lists every position in input order
+        let correlation_matrix = ShockModel::new(positions, config).position_correlations(positions.len());
+        let mut results = Vec::with_capacity(paths.len());
         
-        for i in 0..config.iterations {
-            // Generate random price movements
-            let simulated_positions = self.simulate_price_movements(positions, config, &mut rng).await?;
-            
-            // Calculate portfolio performance
-            let initial_value = self.calculate_portfolio_value(positions).await?;
-            let final_value = self.calculate_portfolio_value(&simulated_positions).await?;
+        for (i, path) in paths.into_iter().enumerate() {
+            let final_value = path.equity_curve.last().copied().unwrap_or(initial_value);
+            // VaR fields are filled in from all results below
+            let risk_metrics = Self::risk_metrics_from_equity_curve(&path.equity_curve, self.config.risk_free_rate);
             
             let result = SimulationResult {
                 scenario: SimulationScenario::Custom(CustomScenario {
@@ -402,27 +1039,20 @@ impl StressTestingFramework {
                     correlation_breakdown: false,
                     liquidity_crisis: false,
                     duration_days: config.time_horizon_days,
+                    liquidity_drain: 0.0,
                 }),
                 initial_portfolio_value: initial_value,
                 final_portfolio_value: final_value,
-                max_drawdown: (final_value - initial_value) / initial_value,
+                max_drawdown: risk_metrics.max_drawdown,
                 var_95: 0.0, // Will be calculated from all results
                 cvar_95: 0.0, // Will be calculated from all results
                 liquidated_positions: Vec::new(),
-                surviving_positions: simulated_positions.iter().map(|p| p.token_address.clone()).collect(),
-                risk_metrics: RiskMetrics {
-                    sharpe_ratio: 0.0,
-                    sortino_ratio: 0.0,
-                    calmar_ratio: 0.0,
-                    max_drawdown_duration: 0,
-                    recovery_time_days: None,
-                    volatility: 0.0,
-                    beta: 0.0,
-                    correlation_matrix: vec![],
-                },
+                surviving_positions: path.positions.iter().map(|p| p.token_address.clone()).collect(),
+                risk_metrics,
                 recommendations: Vec::new(),
                 simulation_duration_ms: 0,
                 timestamp: Utc::now(),
+                seed: Some(seed),
             };
             
             results.push(result);
@@ -436,15 +1066,195 @@ impl StressTestingFramework {
         let var_95 = self.calculate_var_from_returns(&returns, 0.95).await?;
         let cvar_95 = self.calculate_cvar_from_returns(&returns, 0.95).await?;
         
+        let pnl: Vec<f64> = results.iter()
+            .map(|r| r.final_portfolio_value - r.initial_portfolio_value)
+            .collect();
+        let value_at_risk_95 = Self::value_at_risk(&pnl, 0.95);
+        let value_at_risk_99 = Self::value_at_risk(&pnl, 0.99);
+        let conditional_var_95 = Self::expected_shortfall(&pnl, 0.95);
+        
         // Update all results with calculated VaR and CVaR
         for result in &mut results {
             result.var_95 = var_95;
             result.cvar_95 = cvar_95;
+            result.risk_metrics.value_at_risk_95 = value_at_risk_95;
+            result.risk_metrics.value_at_risk_99 = value_at_risk_99;
+            result.risk_metrics.conditional_var_95 = conditional_var_95;
+            result.risk_metrics.correlation_matrix = correlation_matrix.clone();
+        }
+
+        if let Some(progress) = &progress {
+            progress.finish();
         }
         
         Ok(results)
     }
 
+    fn check_cancelled(cancellation: &CancellationToken) -> Result<(), SimulationError> {
+        if cancellation.is_cancelled() {
+            return Err(SimulationError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn progress_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.config.progress_interval_ms)
+    }
+
+    /// Summarize Monte Carlo results with a fixed-order reduction over path returns
+    pub fn summarize_monte_carlo(results: &[SimulationResult]) -> MonteCarloSummary {
+        let returns: Vec<f64> = results.iter()
+            .map(|r| (r.final_portfolio_value - r.initial_portfolio_value) / r.initial_portfolio_value)
+            .collect();
+        let paths = returns.len();
+        if paths == 0 {
+            return MonteCarloSummary {
+                paths,
+                mean_return: 0.0,
+                return_volatility: 0.0,
+                var_95: 0.0,
+                cvar_95: 0.0,
+                worst_return: 0.0,
+            };
+        }
+
+        let mean_return = Self::tree_sum(&returns) / paths as f64;
+        let squared_deviations: Vec<f64> = returns.iter().map(|r| (r - mean_return).powi(2)).collect();
+        let return_volatility = (Self::tree_sum(&squared_deviations) / paths as f64).sqrt();
+
+        let mut sorted_returns = returns.clone();
+        sorted_returns.sort_by(|a, b| a.total_cmp(b));
+        let var_index = ((1.0 - 0.95) * paths as f64) as usize;
+        let var_95 = sorted_returns[var_index.min(paths - 1)];
+        let cvar_95 = Self::tree_sum(&sorted_returns[..=var_index.min(paths - 1)]) / (var_index.min(paths - 1) + 1) as f64;
+
+        MonteCarloSummary {
+            paths,
+            mean_return,
+            return_volatility,
+            var_95,
+            cvar_95,
+            worst_return: sorted_returns[0],
+        }
+    }
+
+    /// Historical Value-at-Risk: the loss at the `confidence` percentile of the loss
+    /// distribution implied by `pnl`. The worst `(1 - confidence) * n` outcomes lie beyond
+    /// it. Negative when even that percentile is a gain.
+    pub fn value_at_risk(pnl: &[f64], confidence: f64) -> f64 {
+        let losses = Self::sorted_losses(pnl);
+        if losses.is_empty() {
+            return 0.0;
+        }
+        let tail = Self::tail_len(losses.len(), confidence);
+        losses[tail.min(losses.len() - 1)]
+    }
+
+    /// Expected shortfall: the mean loss over the outcomes beyond `value_at_risk`, or the
+    /// VaR itself when too few paths lie beyond it
+    pub fn expected_shortfall(pnl: &[f64], confidence: f64) -> f64 {
+        let losses = Self::sorted_losses(pnl);
+        let tail = Self::tail_len(losses.len(), confidence);
+        if tail == 0 {
+            return Self::value_at_risk(pnl, confidence);
+        }
+        Self::tree_sum(&losses[..tail]) / tail as f64
+    }
+
+    /// Losses (negated P&L), worst first
+    fn sorted_losses(pnl: &[f64]) -> Vec<f64> {
+        let mut losses: Vec<f64> = pnl.iter().map(|p| -p).collect();
+        losses.sort_by(|a, b| b.total_cmp(a));
+        losses
+    }
+
+    /// Number of outcomes strictly beyond the `confidence` percentile
+    fn tail_len(paths: usize, confidence: f64) -> usize {
+        // The epsilon keeps e.g. (1 - 0.9) * 10 = 0.99999... from flooring to 0
+        ((1.0 - confidence.clamp(0.0, 1.0)) * paths as f64 + 1e-9).floor() as usize
+    }
+
+    /// Pairwise sum in a fixed tree shape, so the floating-point result depends only
+    /// on the order of `values` and not on how they were produced.
+    fn tree_sum(values: &[f64]) -> f64 {
+        match values.len() {
+            0 => 0.0,
+            1 => values[0],
+            n => Self::tree_sum(&values[..n / 2]) + Self::tree_sum(&values[n / 2..]),
+        }
+    }
+
+    /// Derive an independent generator seed for a single path
+    fn path_seed(base_seed: u64, path: u64) -> u64 {
+        // SplitMix64 finalizer
+        let mut z = base_seed.wrapping_add(path.wrapping_add(1).wrapping_mul(0x9E3779B97F4A7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Every Monte Carlo path, in path order, with every token at `config.price_volatility`.
+    /// All randomness comes from generators derived from `base_seed`.
+    #[cfg(test)]
+    fn simulate_paths(
+        positions: &[SimulationPosition],
+        config: &MonteCarloConfig,
+        base_seed: u64,
+    ) -> Result<Vec<SimulatedPath>, Box<dyn std::error::Error + Send + Sync>> {
+        let volatilities = vec![config.price_volatility; positions.len()];
+        Self::simulate_paths_with_progress(positions, &volatilities, config, base_seed, None, None)
+    }
+
+    /// Every Monte Carlo path, with each position's price moving at its entry in
+    /// `volatilities` over the horizon
+    fn simulate_paths_with_progress(
+        positions: &[SimulationPosition],
+        volatilities: &[f64],
+        config: &MonteCarloConfig,
+        base_seed: u64,
+        progress: Option<&ProgressReporter>,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Vec<SimulatedPath>, Box<dyn std::error::Error + Send + Sync>> {
+        let shocks = ShockModel::new(positions, config);
+        let run_path = |path: u64| {
+            if let Some(cancellation) = cancellation {
+                Self::check_cancelled(cancellation)?;
+            }
+            let mut rng = StdRng::seed_from_u64(Self::path_seed(base_seed, path));
+            let simulated = Self::simulate_path(positions, volatilities, config, &shocks, &mut rng);
+            if let Some(progress) = progress {
+                progress.path_done();
+            }
+            simulated
+        };
+
+        match config.execution_mode {
+            MonteCarloExecutionMode::Serial => {
+                (0..config.iterations as u64).map(run_path).collect()
+            }
+            MonteCarloExecutionMode::DeterministicParallel { workers } => {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(workers)
+                    .thread_name(|index| format!("aegis-monte-carlo-{}", index))
+                    .build()?;
+
+                // Indexed collection keeps the output in path order regardless of which
+                // thread finishes first
+                pool.install(|| {
+                    (0..config.iterations as u64)
+                        .into_par_iter()
+                        .map(run_path)
+                        .collect()
+                })
+            }
+        }
+    }
+
+    /// Replace the price history `run_backtesting` replays for one token
+    pub async fn set_historical_prices(&self, token_address: &str, prices: Vec<HistoricalPricePoint>) {
+        self.historical_data.write().await.insert(token_address.to_string(), prices);
+    }
+
     /// Run backtesting simulation
     pub async fn run_backtesting(
         &self,
@@ -452,6 +1262,7 @@ impl StressTestingFramework {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<SimulationResult, Box<dyn std::error::Error + Send + Sync>> {
+        let start_time = std::time::Instant::now();
         let historical_data = self.historical_data.read().await;
         
         // Simulate portfolio performance using historical data
@@ -479,7 +1290,12 @@ impl StressTestingFramework {
         
         let initial_value = portfolio_values.first().unwrap_or(&0.0);
         let final_value = portfolio_values.last().unwrap_or(&0.0);
-        let max_drawdown = self.calculate_max_drawdown(&portfolio_values).await?;
+        let risk_metrics = Self::risk_metrics_from_equity_curve(&portfolio_values, self.config.risk_free_rate);
+        let recommendations = if self.config.auto_recommendations {
+            self.generate_recommendations(&current_positions, &risk_metrics, &[]).await?
+        } else {
+            Vec::new()
+        };
         
         Ok(SimulationResult {
             scenario: SimulationScenario::Custom(CustomScenario {
@@ -491,27 +1307,20 @@ impl StressTestingFramework {
                 correlation_breakdown: false,
                 liquidity_crisis: false,
                 duration_days: (end_date - start_date).num_days() as u32,
+                liquidity_drain: 0.0,
             }),
             initial_portfolio_value: *initial_value,
             final_portfolio_value: *final_value,
-            max_drawdown,
+            max_drawdown: risk_metrics.max_drawdown,
             var_95: 0.0, // Would need more sophisticated calculation
             cvar_95: 0.0, // Would need more sophisticated calculation
             liquidated_positions: Vec::new(),
             surviving_positions: current_positions.iter().map(|p| p.token_address.clone()).collect(),
-            risk_metrics: RiskMetrics {
-                sharpe_ratio: 0.0,
-                sortino_ratio: 0.0,
-                calmar_ratio: 0.0,
-                max_drawdown_duration: 0,
-                recovery_time_days: None,
-                volatility: 0.0,
-                beta: 0.0,
-                correlation_matrix: vec![],
-            },
-            recommendations: Vec::new(),
-            simulation_duration_ms: 0,
+            risk_metrics,
+            recommendations,
+            simulation_duration_ms: start_time.elapsed().as_millis() as u64,
             timestamp: Utc::now(),
+            seed: None,
         })
     }
 
@@ -534,35 +1343,63 @@ impl StressTestingFramework {
 
     /// Get cached simulation result
     async fn get_cached_simulation(&self, cache_key: &str) -> Result<Option<SimulationResult>, Box<dyn std::error::Error + Send + Sync>> {
-        let cache = self.simulation_cache.read().await;
-        if let Some(cached) = cache.get(cache_key) {
-            // Check if cache is still valid (within 1 hour)
-            if Utc::now() - cached.timestamp < Duration::hours(1) {
-                return Ok(Some(cached.clone()));
-            }
-        }
-        Ok(None)
+        // A hit updates recency, so even lookups take the write lock
+        let mut cache = self.simulation_cache.write().await;
+        Ok(cache.get(cache_key))
     }
 
     /// Cache simulation result
     async fn cache_simulation(&self, cache_key: &str, result: &SimulationResult) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut cache = self.simulation_cache.write().await;
-        cache.insert(cache_key.to_string(), result.clone());
+        cache.insert(cache_key, result.clone());
         Ok(())
     }
 
     /// Calculate portfolio value
     async fn calculate_portfolio_value(&self, positions: &[SimulationPosition]) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-        let total_value: f64 = positions.iter()
+        Ok(Self::net_value(positions))
+    }
+
+    /// Collateral less debt across `positions`
+    fn net_value(positions: &[SimulationPosition]) -> f64 {
+        positions.iter()
             .map(|p| p.collateral_value - p.debt_value)
-            .sum();
-        Ok(total_value)
+            .sum()
     }
 
     /// Apply scenario shocks to positions
     async fn apply_scenario_shocks(&self, positions: &[SimulationPosition], scenario: &SimulationScenario) -> Result<Vec<SimulationPosition>, Box<dyn std::error::Error + Send + Sync>> {
         let mut shocked_positions = positions.to_vec();
         
+        // A depegged stablecoin is repriced on both legs: held as collateral it loses value,
+        // owed as debt it costs less to repay
+        if let SimulationScenario::StablecoinDepeg(depeg) = scenario {
+            let trough_multiplier = depeg.price_at_day(depeg.days_to_trough);
+            for position in &mut shocked_positions {
+                if position.token_address == depeg.stablecoin {
+                    position.current_price *= trough_multiplier;
+                    position.collateral_value = position.quantity * position.current_price;
+                }
+                if let Some(owed) = position.debt_tokens.get_mut(&depeg.stablecoin) {
+                    let repriced = *owed * trough_multiplier;
+                    position.debt_value -= *owed - repriced;
+                    *owed = repriced;
+                }
+                position.health_factor = position.collateral_value / position.debt_value;
+            }
+            return Ok(shocked_positions);
+        }
+
+        if let SimulationScenario::Custom(custom) = scenario {
+            for position in &mut shocked_positions {
+                let shock_multiplier = 1.0 + custom.price_shocks.get(&position.token_address).copied().unwrap_or(0.0);
+                position.current_price *= shock_multiplier;
+                position.collateral_value = position.quantity * position.current_price * (1.0 - custom.liquidity_drain);
+                position.health_factor = position.collateral_value / position.debt_value;
+            }
+            return Ok(shocked_positions);
+        }
+        
         if let Some(template) = self.scenario_templates.get(scenario) {
             for position in &mut shocked_positions {
                 if let Some(price_shock) = template.price_shocks.get(&position.token_address) {
@@ -577,6 +1414,15 @@ impl StressTestingFramework {
         Ok(shocked_positions)
     }
 
+    /// Days the scenario's shock lasts, or 1 when the scenario has no template
+    fn scenario_duration_days(&self, scenario: &SimulationScenario) -> u32 {
+        match scenario {
+            SimulationScenario::StablecoinDepeg(depeg) => depeg.duration_days(),
+            SimulationScenario::Custom(custom) => custom.duration_days,
+            _ => self.scenario_templates.get(scenario).map_or(1, |template| template.duration_days),
+        }
+    }
+
     /// Identify liquidated positions
     async fn identify_liquidated_positions(&self, positions: &[SimulationPosition]) -> Result<(Vec<SimulationPosition>, Vec<SimulationPosition>), Box<dyn std::error::Error + Send + Sync>> {
         let mut liquidated = Vec::new();
@@ -593,30 +1439,52 @@ impl StressTestingFramework {
         Ok((liquidated, surviving))
     }
 
-    /// Calculate risk metrics
+    /// Calculate risk metrics, treating the move from `initial_positions` to
+    /// `final_positions` as a single day
+    #[cfg(test)]
     async fn calculate_risk_metrics(&self, initial_positions: &[SimulationPosition], final_positions: &[SimulationPosition]) -> Result<RiskMetrics, Box<dyn std::error::Error + Send + Sync>> {
+        self.calculate_shock_risk_metrics(initial_positions, final_positions, 1).await
+    }
+
+    /// Risk metrics for a shock that holds for `duration_days`: the daily equity curve
+    /// starts at the initial value and stays at the shocked value for each day after
+    async fn calculate_shock_risk_metrics(
+        &self,
+        initial_positions: &[SimulationPosition],
+        final_positions: &[SimulationPosition],
+        duration_days: u32,
+    ) -> Result<RiskMetrics, Box<dyn std::error::Error + Send + Sync>> {
         let initial_value = self.calculate_portfolio_value(initial_positions).await?;
         let final_value = self.calculate_portfolio_value(final_positions).await?;
         
         let return_rate = (final_value - initial_value) / initial_value;
         let volatility = 0.5; // Simplified calculation
-        let risk_free_rate = 0.02; // 2% risk-free rate
+        let risk_free_rate = self.config.risk_free_rate;
         
         let sharpe_ratio = if volatility > 0.0 {
             (return_rate - risk_free_rate) / volatility
         } else {
             0.0
         };
+
+        let mut equity_curve = vec![initial_value];
+        equity_curve.resize(duration_days.max(1) as usize + 1, final_value);
+        let (max_drawdown, max_drawdown_duration) = Self::drawdown(&equity_curve);
         
         Ok(RiskMetrics {
             sharpe_ratio,
             sortino_ratio: sharpe_ratio, // Simplified
             calmar_ratio: 0.0, // Would need more data
-            max_drawdown_duration: 0,
+            max_drawdown_duration,
             recovery_time_days: None,
             volatility,
             beta: 1.0, // Simplified
             correlation_matrix: vec![vec![1.0]],
+            max_drawdown,
+            // A single deterministic scenario has no P&L distribution
+            value_at_risk_95: 0.0,
+            value_at_risk_99: 0.0,
+            conditional_var_95: 0.0,
         })
     }
 
@@ -667,20 +1535,53 @@ impl StressTestingFramework {
                 confidence: 0.6,
             });
         }
+
+        // Check for concentration in a few tokens
+        let mut collateral_by_token: HashMap<&str, f64> = HashMap::new();
+        for position in positions {
+            *collateral_by_token.entry(position.token_address.as_str()).or_insert(0.0) += position.collateral_value;
+        }
+        let (tokens, collateral): (Vec<String>, Vec<f64>) = collateral_by_token.into_iter()
+            .map(|(token, value)| (token.to_string(), value))
+            .unzip();
+        let concentration = herfindahl_index(&collateral);
+        if concentration > self.config.max_concentration_index {
+            let mut description = format!(
+                "Collateral concentration index {:.2} exceeds {:.2}",
+                concentration, self.config.max_concentration_index
+            );
+            if let Some(correlations) = &self.config.monte_carlo_config.correlations {
+                let volatilities: Vec<f64> = tokens.iter()
+                    .map(|token| self.token_volatility(token, &self.config.monte_carlo_config))
+                    .collect();
+                let ratio = diversification_ratio(&tokens, &collateral, &volatilities, correlations);
+                description.push_str(&format!(" (diversification ratio {:.2})", ratio));
+            }
+
+            recommendations.push(SimulationRecommendation {
+                recommendation_type: RecommendationType::DiversifyPortfolio,
+                priority: RecommendationPriority::High,
+                description,
+                expected_impact: 0.3,
+                implementation_cost: 300.0,
+                time_to_implement: 7,
+                confidence: 0.8,
+            });
+        }
         
         Ok(recommendations)
     }
 
-    /// Calculate VaR at 95% confidence
+    /// Calculate VaR at 95% confidence, as a positive USD loss
     async fn calculate_var_95(&self, positions: &[SimulationPosition], scenario: &SimulationScenario) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         // Simplified VaR calculation
         let portfolio_value = self.calculate_portfolio_value(positions).await?;
         let volatility = 0.5; // Simplified
-        let var_95 = -1.645 * volatility * portfolio_value; // 95% confidence level
+        let var_95 = 1.645 * volatility * portfolio_value; // 95% confidence level
         Ok(var_95)
     }
 
-    /// Calculate CVaR at 95% confidence
+    /// Calculate CVaR at 95% confidence, as a positive USD loss
     async fn calculate_cvar_95(&self, positions: &[SimulationPosition], scenario: &SimulationScenario) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         // Simplified CVaR calculation
         let var_95 = self.calculate_var_95(positions, scenario).await?;
@@ -689,79 +1590,166 @@ impl StressTestingFramework {
     }
 
     /// Simulate price movements for Monte Carlo
+    #[cfg(test)]
     async fn simulate_price_movements(
         &self,
         positions: &[SimulationPosition],
         config: &MonteCarloConfig,
         rng: &mut impl Rng,
     ) -> Result<Vec<SimulationPosition>, Box<dyn std::error::Error + Send + Sync>> {
+        let volatilities = self.position_volatilities(positions, config);
+        let path = Self::simulate_path(positions, &volatilities, config, &ShockModel::new(positions, config), rng)?;
+        Ok(path.positions)
+    }
+
+    /// Walk `positions` through one price shock per day of the horizon. Each position's
+    /// entry in `volatilities` covers the whole horizon, so its daily shock has that
+    /// volatility over `sqrt(days)`.
+    fn simulate_path(
+        positions: &[SimulationPosition],
+        volatilities: &[f64],
+        config: &MonteCarloConfig,
+        shocks: &ShockModel,
+        rng: &mut impl Rng,
+    ) -> Result<SimulatedPath, Box<dyn std::error::Error + Send + Sync>> {
+        let steps = config.time_horizon_days.max(1);
+        let normal = Normal::new(0.0, 1.0)?;
+        let daily_volatilities: Vec<f64> = volatilities.iter()
+            .map(|volatility| volatility / (steps as f64).sqrt())
+            .collect();
         let mut simulated_positions = positions.to_vec();
-        
-        for position in &mut simulated_positions {
-            // Generate random price movement using normal distribution
-            let normal = Normal::new(0.0, config.price_volatility)?;
-            let price_change = normal.sample(rng);
-            
+        let mut equity_curve = Vec::with_capacity(steps as usize + 1);
+        equity_curve.push(Self::net_value(&simulated_positions));
+        let apply_shock = |position: &mut SimulationPosition, price_change: f64| {
             position.current_price *= (1.0 + price_change).max(0.01); // Prevent negative prices
             position.collateral_value = position.quantity * position.current_price;
             position.health_factor = position.collateral_value / position.debt_value;
+        };
+        
+        for _ in 0..steps {
+            match shocks {
+                ShockModel::Independent => {
+                    for (position, volatility) in simulated_positions.iter_mut().zip(&daily_volatilities) {
+                        apply_shock(position, volatility * normal.sample(rng));
+                    }
+                }
+                ShockModel::Correlated { asset_index, cholesky } => {
+                    let draws: Vec<f64> = (0..cholesky.len()).map(|_| normal.sample(rng)).collect();
+                    let asset_shocks: Vec<f64> = cholesky.iter()
+                        .map(|row| row.iter().zip(&draws).map(|(l, z)| l * z).sum())
+                        .collect();
+                    for ((position, &asset), volatility) in simulated_positions.iter_mut().zip(asset_index).zip(&daily_volatilities) {
+                        apply_shock(position, volatility * asset_shocks[asset]);
+                    }
+                }
+            }
+            equity_curve.push(Self::net_value(&simulated_positions));
         }
         
-        Ok(simulated_positions)
+        Ok(SimulatedPath { positions: simulated_positions, equity_curve })
     }
 
-    /// Calculate VaR from returns
+    /// Calculate VaR from returns, as a positive fractional loss
     async fn calculate_var_from_returns(&self, returns: &[f64], confidence_level: f64) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         let mut sorted_returns = returns.to_vec();
-        sorted_returns.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted_returns.sort_by(|a, b| a.total_cmp(b));
         
         let index = ((1.0 - confidence_level) * returns.len() as f64) as usize;
         let var = sorted_returns.get(index).unwrap_or(&0.0);
         
-        Ok(*var)
+        Ok(-*var)
     }
 
-    /// Calculate CVaR from returns
+    /// Calculate CVaR from returns, as a positive fractional loss
     async fn calculate_cvar_from_returns(&self, returns: &[f64], confidence_level: f64) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
         let var = self.calculate_var_from_returns(returns, confidence_level).await?;
         
-        let tail_returns: Vec<f64> = returns.iter()
-            .filter(|&&r| r <= var)
-            .cloned()
+        let tail_losses: Vec<f64> = returns.iter()
+            .map(|r| -r)
+            .filter(|&loss| loss >= var)
             .collect();
         
-        if tail_returns.is_empty() {
+        if tail_losses.is_empty() {
             return Ok(var);
         }
         
-        let cvar = tail_returns.iter().sum::<f64>() / tail_returns.len() as f64;
+        let cvar = tail_losses.iter().sum::<f64>() / tail_losses.len() as f64;
         Ok(cvar)
     }
 
-    /// Calculate maximum drawdown
-    async fn calculate_max_drawdown(&self, portfolio_values: &[f64]) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
-        if portfolio_values.is_empty() {
-            return Ok(0.0);
+    /// Drawdown, volatility, Sharpe and Sortino of an equity curve sampled once per day.
+    ///
+    /// Ratios are annualized over 365 periods, as crypto markets trade every day, with
+    /// `annual_risk_free_rate` spread evenly across them. A curve with no variation (or
+    /// no downside, for Sortino) reports a ratio of zero.
+    pub fn risk_metrics_from_equity_curve(equity_curve: &[f64], annual_risk_free_rate: f64) -> RiskMetrics {
+        const PERIODS_PER_YEAR: f64 = 365.0;
+
+        let (max_drawdown, max_drawdown_duration) = Self::drawdown(equity_curve);
+
+        let period_risk_free = annual_risk_free_rate / PERIODS_PER_YEAR;
+        let excess_returns: Vec<f64> = equity_curve.windows(2)
+            .filter(|pair| pair[0] != 0.0)
+            .map(|pair| pair[1] / pair[0] - 1.0 - period_risk_free)
+            .collect();
+
+        let (volatility, sharpe_ratio, sortino_ratio) = if excess_returns.is_empty() {
+            (0.0, 0.0, 0.0)
+        } else {
+            let periods = excess_returns.len() as f64;
+            let mean = excess_returns.iter().sum::<f64>() / periods;
+            let std_dev = (excess_returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / periods).sqrt();
+            let downside_dev = (excess_returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / periods).sqrt();
+            let annualize = PERIODS_PER_YEAR.sqrt();
+            let ratio = |deviation: f64| if deviation > 0.0 { mean / deviation * annualize } else { 0.0 };
+            (std_dev * annualize, ratio(std_dev), ratio(downside_dev))
+        };
+
+        RiskMetrics {
+            sharpe_ratio,
+            sortino_ratio,
+            calmar_ratio: 0.0,
+            max_drawdown_duration,
+            recovery_time_days: None,
+            volatility,
+            beta: 0.0,
+            correlation_matrix: vec![],
+            max_drawdown,
+            value_at_risk_95: 0.0,
+            value_at_risk_99: 0.0,
+            conditional_var_95: 0.0,
         }
-        
-        let mut max_drawdown = 0.0;
-        let mut peak = portfolio_values[0];
-        
+    }
+
+    /// Calculate maximum drawdown as a positive fraction of the running peak
+    #[cfg(test)]
+    async fn calculate_max_drawdown(&self, portfolio_values: &[f64]) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self::drawdown(portfolio_values).0)
+    }
+
+    /// Deepest fall from a running peak, as a positive fraction of that peak, and the
+    /// longest run of periods spent below a peak
+    fn drawdown(portfolio_values: &[f64]) -> (f64, u32) {
+        let mut max_drawdown: f64 = 0.0;
+        let mut max_drawdown_duration = 0u32;
+        let mut underwater = 0u32;
+        let mut peak = portfolio_values.first().copied().unwrap_or(0.0);
         for &value in portfolio_values {
-            if value > peak {
+            if value >= peak {
                 peak = value;
-            }
-            
-            let drawdown = (value - peak) / peak;
-            if drawdown < max_drawdown {
-                max_drawdown = drawdown;
+                underwater = 0;
+            } else {
+                underwater += 1;
+                max_drawdown_duration = max_drawdown_duration.max(underwater);
+                if peak > 0.0 {
+                    max_drawdown = max_drawdown.max((peak - value) / peak);
+                }
             }
         }
-        
-        Ok(max_drawdown)
+        (max_drawdown, max_drawdown_duration)
     }
 
-    /// Clear simulation cache
+    /// Clear simulation cache and its statistics
     pub async fn clear_cache(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut cache = self.simulation_cache.write().await;
         cache.clear();
@@ -769,13 +1757,42 @@ impl StressTestingFramework {
         Ok(())
     }
 
-    /// Get cache statistics
+    /// Drop the least recently used `fraction` of cached results, returning how many were
+    /// removed. Shed entries count as evictions in `get_cache_stats`.
+    pub async fn shed_cache(&self, fraction: f64) -> usize {
+        let mut cache = self.simulation_cache.write().await;
+        let to_remove = (cache.entries.len() as f64 * fraction.clamp(0.0, 1.0)).ceil() as usize;
+
+        (0..to_remove).take_while(|_| cache.evict_least_recently_used()).count()
+    }
+
+    /// Get cache statistics, including entries expired or evicted since the cache was
+    /// created or last cleared. Empty while the cache has not been used.
     pub async fn get_cache_stats(&self) -> Result<HashMap<String, usize>, Box<dyn std::error::Error + Send + Sync>> {
-        let cache = self.simulation_cache.read().await;
+        let mut cache = self.simulation_cache.write().await;
+        cache.remove_expired();
+        if cache.is_unused() {
+            return Ok(HashMap::new());
+        }
         Ok(HashMap::from([
-            ("simulation_cache_entries".to_string(), cache.len()),
+            ("simulation_cache_entries".to_string(), cache.entries.len()),
+            ("simulation_cache_expired".to_string(), cache.expired),
+            ("simulation_cache_evicted".to_string(), cache.evicted),
+            ("simulation_cache_hits".to_string(), cache.hits),
+            ("simulation_cache_misses".to_string(), cache.misses),
         ]))
     }
+
+    /// Fraction of cache lookups that returned a result, 0.0 before the first lookup
+    pub async fn cache_hit_ratio(&self) -> f64 {
+        let cache = self.simulation_cache.read().await;
+        let lookups = cache.hits + cache.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            cache.hits as f64 / lookups as f64
+        }
+    }
 }
 
 impl Default for StressTestingFramework {
@@ -784,5 +1801,7 @@ impl Default for StressTestingFramework {
     }
 }
 
+// The suite in tests.rs wraps itself in `mod tests`
 #[cfg(test)]
-mod tests; 
\ No newline at end of file
+#[path = "tests.rs"]
+mod framework_tests;
\ No newline at end of file
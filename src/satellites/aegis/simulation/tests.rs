@@ -1,3 +1,5 @@
+use super::*;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -24,6 +26,7 @@ mod tests {
             debt_value: 3000.0,
             liquidation_threshold: 0.8,
             health_factor: 1.83,
+            debt_tokens: HashMap::new(),
         };
 
         assert_eq!(position.token_address, "0x1234567890abcdef");
@@ -46,6 +49,7 @@ mod tests {
                 debt_value: 25000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -72,6 +76,7 @@ mod tests {
                 debt_value: 15000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -80,8 +85,10 @@ mod tests {
             time_horizon_days: 30,
             confidence_level: 0.95,
             price_volatility: 0.5,
-            correlation_matrix: vec![vec![1.0]],
             drift_rates: HashMap::new(),
+            seed: None,
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: None,
         };
 
         let results = framework.run_monte_carlo_simulation(&positions, &monte_carlo_config).await.unwrap();
@@ -106,15 +113,18 @@ mod tests {
                 debt_value: 5000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
         let start_date = Utc::now() - Duration::days(30);
         let end_date = Utc::now();
 
+        let started = std::time::Instant::now();
         let result = framework.run_backtesting(&positions, start_date, end_date).await.unwrap();
 
-        assert!(result.simulation_duration_ms > 0);
+        // A fast run can round down to 0ms, but never reports longer than it took
+        assert!(result.simulation_duration_ms <= started.elapsed().as_millis() as u64);
         assert!(!result.recommendations.is_empty());
     }
 
@@ -136,6 +146,7 @@ mod tests {
             correlation_breakdown: true,
             liquidity_crisis: false,
             duration_days: 7,
+            liquidity_drain: 0.0,
         };
 
         let scenario = SimulationScenario::Custom(custom_scenario);
@@ -149,6 +160,7 @@ mod tests {
                 debt_value: 25000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -173,6 +185,7 @@ mod tests {
                 debt_value: 1000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -213,6 +226,7 @@ mod tests {
                 debt_value: 500.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -226,6 +240,7 @@ mod tests {
                 debt_value: 500.0,
                 liquidation_threshold: 0.8,
                 health_factor: 1.6,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -236,6 +251,32 @@ mod tests {
         assert!(!risk_metrics.correlation_matrix.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_stress_drawdown_lasts_for_the_scenario() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let position = |token: &str| SimulationPosition {
+            token_address: token.to_string(),
+            quantity: 1.0,
+            entry_price: 50000.0,
+            current_price: 50000.0,
+            collateral_value: 50000.0,
+            debt_value: 20000.0,
+            liquidation_threshold: 0.8,
+            health_factor: 2.5,
+            debt_tokens: HashMap::new(),
+        };
+
+        // BTC halves for the 30 days of the crash: net value falls from 30k to 5k
+        let crash = framework.run_stress_test(&[position("BTC")], &SimulationScenario::HistoricalMarketCrash).await.unwrap();
+        assert!((crash.max_drawdown - 25000.0 / 30000.0).abs() < 1e-12);
+        assert_eq!(crash.risk_metrics.max_drawdown_duration, 30);
+
+        // The 7-day regulatory shock leaves an unlisted token alone
+        let untouched = framework.run_stress_test(&[position("LINK")], &SimulationScenario::RegulatoryShock).await.unwrap();
+        assert_eq!(untouched.max_drawdown, 0.0);
+        assert_eq!(untouched.risk_metrics.max_drawdown_duration, 0);
+    }
+
     #[tokio::test]
     async fn test_recommendation_generation() {
         let config = StressTestingConfig::default();
@@ -251,6 +292,7 @@ mod tests {
                 debt_value: 3000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 1.33, // Close to liquidation
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -263,6 +305,10 @@ mod tests {
             volatility: 0.4,
             beta: 1.2,
             correlation_matrix: vec![vec![1.0]],
+            max_drawdown: 0.0,
+            value_at_risk_95: 0.0,
+            value_at_risk_99: 0.0,
+            conditional_var_95: 0.0,
         };
 
         let liquidated_positions = vec![];
@@ -295,6 +341,7 @@ mod tests {
                 debt_value: 2000.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -335,6 +382,7 @@ mod tests {
                 debt_value: 250.0,
                 liquidation_threshold: 0.8,
                 health_factor: 2.0,
+                debt_tokens: HashMap::new(),
             }
         ];
 
@@ -343,8 +391,10 @@ mod tests {
             time_horizon_days: 30,
             confidence_level: 0.95,
             price_volatility: 0.3,
-            correlation_matrix: vec![vec![1.0]],
             drift_rates: HashMap::new(),
+            seed: None,
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: None,
         };
 
         let mut rng = rand::thread_rng();
@@ -357,4 +407,574 @@ mod tests {
             assert_ne!(original.current_price, simulated.current_price);
         }
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_deterministic_parallel_monte_carlo_matches_serial() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+
+        let positions = vec![
+            SimulationPosition {
+                token_address: "ETH".to_string(),
+                quantity: 10.0,
+                entry_price: 3000.0,
+                current_price: 3000.0,
+                collateral_value: 30000.0,
+                debt_value: 15000.0,
+                liquidation_threshold: 0.8,
+                health_factor: 2.0,
+                debt_tokens: HashMap::new(),
+            },
+            SimulationPosition {
+                token_address: "BTC".to_string(),
+                quantity: 1.0,
+                entry_price: 50000.0,
+                current_price: 50000.0,
+                collateral_value: 50000.0,
+                debt_value: 20000.0,
+                liquidation_threshold: 0.8,
+                health_factor: 2.5,
+                debt_tokens: HashMap::new(),
+            },
+        ];
+
+        let serial_config = MonteCarloConfig {
+            iterations: 1001,
+            time_horizon_days: 30,
+            confidence_level: 0.95,
+            price_volatility: 0.4,
+            drift_rates: HashMap::new(),
+            seed: Some(42),
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: None,
+        };
+        let parallel_config = MonteCarloConfig {
+            execution_mode: MonteCarloExecutionMode::DeterministicParallel { workers: 4 },
+            ..serial_config.clone()
+        };
+
+        let serial = framework.run_monte_carlo_simulation(&positions, &serial_config).await.unwrap();
+        let parallel = framework.run_monte_carlo_simulation(&positions, &parallel_config).await.unwrap();
+
+        let serial_values: Vec<f64> = serial.iter().map(|r| r.final_portfolio_value).collect();
+        let parallel_values: Vec<f64> = parallel.iter().map(|r| r.final_portfolio_value).collect();
+        assert_eq!(serial_values, parallel_values);
+
+        let serial_summary = StressTestingFramework::summarize_monte_carlo(&serial);
+        let parallel_summary = StressTestingFramework::summarize_monte_carlo(&parallel);
+        assert_eq!(serial_summary.paths, 1001);
+        assert_eq!(serial_summary.mean_return.to_bits(), parallel_summary.mean_return.to_bits());
+        assert_eq!(serial_summary.return_volatility.to_bits(), parallel_summary.return_volatility.to_bits());
+        assert_eq!(serial_summary, parallel_summary);
+
+        // A different seed produces a different run
+        let reseeded = MonteCarloConfig { seed: Some(7), ..serial_config };
+        let other = framework.run_monte_carlo_simulation(&positions, &reseeded).await.unwrap();
+        assert_ne!(StressTestingFramework::summarize_monte_carlo(&other), serial_summary);
+    }
+
+    #[test]
+    fn test_value_at_risk_and_expected_shortfall_on_known_distribution() {
+        // P&L of -1, -2, ..., -100, shuffled so the helpers must sort: losses 1..=100
+        let mut pnl: Vec<f64> = (1..=100).map(|loss| -(loss as f64)).collect();
+        pnl.reverse();
+        pnl.swap(3, 71);
+
+        // 95% of losses are at most 95; the 5 beyond it average (96 + ... + 100) / 5
+        assert_eq!(StressTestingFramework::value_at_risk(&pnl, 0.95), 95.0);
+        assert_eq!(StressTestingFramework::expected_shortfall(&pnl, 0.95), 98.0);
+        assert_eq!(StressTestingFramework::value_at_risk(&pnl, 0.99), 99.0);
+        assert_eq!(StressTestingFramework::expected_shortfall(&pnl, 0.99), 100.0);
+
+        // Gains at the percentile give a negative VaR
+        let gains: Vec<f64> = (1..=100).map(|gain| gain as f64).collect();
+        assert_eq!(StressTestingFramework::value_at_risk(&gains, 0.95), -6.0);
+        assert_eq!(StressTestingFramework::expected_shortfall(&gains, 0.95), -3.0);
+
+        assert_eq!(StressTestingFramework::value_at_risk(&[], 0.95), 0.0);
+        assert_eq!(StressTestingFramework::expected_shortfall(&[], 0.95), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_monte_carlo_reports_var_and_expected_shortfall() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let positions = vec![
+            SimulationPosition {
+                token_address: "ETH".to_string(),
+                quantity: 10.0,
+                entry_price: 3000.0,
+                current_price: 3000.0,
+                collateral_value: 30000.0,
+                debt_value: 15000.0,
+                liquidation_threshold: 0.8,
+                health_factor: 2.0,
+                debt_tokens: HashMap::new(),
+            },
+        ];
+        let config = MonteCarloConfig {
+            iterations: 2000,
+            time_horizon_days: 30,
+            confidence_level: 0.95,
+            price_volatility: 0.6,
+            drift_rates: HashMap::new(),
+            seed: Some(11),
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: None,
+        };
+
+        let results = framework.run_monte_carlo_simulation(&positions, &config).await.unwrap();
+        let pnl: Vec<f64> = results.iter().map(|r| r.final_portfolio_value - r.initial_portfolio_value).collect();
+        let metrics = &results[0].risk_metrics;
+
+        assert_eq!(metrics.value_at_risk_95, StressTestingFramework::value_at_risk(&pnl, 0.95));
+        assert!(metrics.value_at_risk_95 > 0.0);
+        assert!(metrics.value_at_risk_99 >= metrics.value_at_risk_95);
+        assert!(metrics.conditional_var_95 >= metrics.value_at_risk_95);
+        assert!(results.iter().all(|r| r.risk_metrics.conditional_var_95 == metrics.conditional_var_95));
+    }
+
+    #[tokio::test]
+    async fn test_monte_carlo_with_same_seed_is_reproducible() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let positions = vec![
+            SimulationPosition {
+                token_address: "ETH".to_string(),
+                quantity: 10.0,
+                entry_price: 3000.0,
+                current_price: 3000.0,
+                collateral_value: 30000.0,
+                debt_value: 15000.0,
+                liquidation_threshold: 0.8,
+                health_factor: 2.0,
+                debt_tokens: HashMap::new(),
+            },
+        ];
+        let config = MonteCarloConfig {
+            iterations: 500,
+            time_horizon_days: 30,
+            confidence_level: 0.95,
+            price_volatility: 0.4,
+            drift_rates: HashMap::new(),
+            seed: Some(1234),
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: None,
+        };
+        let outcome = |results: &[SimulationResult]| -> Vec<(u64, u64, u64)> {
+            results.iter()
+                .map(|r| (r.final_portfolio_value.to_bits(), r.var_95.to_bits(), r.risk_metrics.conditional_var_95.to_bits()))
+                .collect()
+        };
+
+        let first = framework.run_monte_carlo_simulation(&positions, &config).await.unwrap();
+        let second = framework.run_monte_carlo_simulation(&positions, &config).await.unwrap();
+        assert_eq!(outcome(&first), outcome(&second));
+        assert!(first.iter().all(|r| r.seed == Some(1234)));
+
+        // An unseeded run records the seed it drew, and replaying with it reproduces the run
+        let unseeded = MonteCarloConfig { seed: None, ..config };
+        let original = framework.run_monte_carlo_simulation(&positions, &unseeded).await.unwrap();
+        let recorded_seed = original[0].seed.expect("unseeded run records its seed");
+        let replay_config = MonteCarloConfig { seed: Some(recorded_seed), ..unseeded };
+        let replay = framework.run_monte_carlo_simulation(&positions, &replay_config).await.unwrap();
+        assert_eq!(outcome(&original), outcome(&replay));
+    }
+
+    #[test]
+    fn test_equity_curve_drawdown_and_ratios() {
+        // Daily returns of +10%, -10%, +10%, +10%
+        let equity_curve = [100.0, 110.0, 99.0, 108.9, 119.79];
+
+        let metrics = StressTestingFramework::risk_metrics_from_equity_curve(&equity_curve, 0.0);
+        // Peak 110 falls to 99, and the curve stays below 110 for two days
+        assert!((metrics.max_drawdown - 0.1).abs() < 1e-12);
+        assert_eq!(metrics.max_drawdown_duration, 2);
+
+        // Mean 0.05, population std dev sqrt(0.0075), downside deviation sqrt(0.01 / 4) = 0.05
+        let annualize = 365f64.sqrt();
+        assert!((metrics.volatility - 0.0075f64.sqrt() * annualize).abs() < 1e-9);
+        assert!((metrics.sharpe_ratio - annualize / 3f64.sqrt()).abs() < 1e-9);
+        assert!((metrics.sortino_ratio - annualize).abs() < 1e-9);
+
+        // A 3.65% annual risk-free rate takes 0.0001 off every daily return
+        let with_risk_free = StressTestingFramework::risk_metrics_from_equity_curve(&equity_curve, 0.0365);
+        assert!((with_risk_free.sharpe_ratio - 0.0499 / 0.0075f64.sqrt() * annualize).abs() < 1e-9);
+        assert_eq!(with_risk_free.max_drawdown, metrics.max_drawdown);
+
+        // A steadily rising curve has no drawdown and no downside
+        let rising = StressTestingFramework::risk_metrics_from_equity_curve(&[100.0, 101.0, 102.01], 0.0);
+        assert_eq!(rising.max_drawdown, 0.0);
+        assert_eq!(rising.max_drawdown_duration, 0);
+        assert_eq!(rising.sortino_ratio, 0.0);
+    }
+
+    fn correlated_config(correlation: f64) -> MonteCarloConfig {
+        MonteCarloConfig {
+            iterations: 4000,
+            time_horizon_days: 30,
+            confidence_level: 0.95,
+            price_volatility: 0.3,
+            drift_rates: HashMap::new(),
+            seed: Some(99),
+            execution_mode: MonteCarloExecutionMode::Serial,
+            correlations: Some(CorrelationMatrix {
+                assets: vec!["ETH".to_string(), "STETH".to_string()],
+                matrix: vec![vec![1.0, correlation], vec![correlation, 1.0]],
+                timestamp: Utc::now(),
+                time_window_days: 30,
+                confidence_level: 0.95,
+                stale_pairs: vec![],
+            }),
+        }
+    }
+
+    fn two_asset_positions() -> Vec<SimulationPosition> {
+        ["ETH", "STETH"].iter()
+            .map(|token| SimulationPosition {
+                token_address: token.to_string(),
+                quantity: 10.0,
+                entry_price: 3000.0,
+                current_price: 3000.0,
+                collateral_value: 30000.0,
+                debt_value: 15000.0,
+                liquidation_threshold: 0.8,
+                health_factor: 2.0,
+                debt_tokens: HashMap::new(),
+            })
+            .collect()
+    }
+
+    fn joint_loss_probability(paths: &[SimulatedPath]) -> f64 {
+        let joint_losses = paths.iter()
+            .filter(|path| path.positions.iter().all(|p| p.current_price < p.entry_price))
+            .count();
+        joint_losses as f64 / paths.len() as f64
+    }
+
+    #[test]
+    fn test_correlated_shocks_raise_joint_loss_probability() {
+        let positions = two_asset_positions();
+
+        // A single step, so each price falls exactly when its one shock is negative
+        let one_day = |correlation: f64| MonteCarloConfig { time_horizon_days: 1, ..correlated_config(correlation) };
+        let independent = StressTestingFramework::simulate_paths(&positions, &one_day(0.0), 99).unwrap();
+        let correlated = StressTestingFramework::simulate_paths(&positions, &one_day(0.95), 99).unwrap();
+
+        // Both fall together a quarter of the time when independent, and
+        // 1/4 + asin(0.95) / (2 * pi) ~ 0.45 of the time at a correlation of 0.95
+        let independent_probability = joint_loss_probability(&independent);
+        let correlated_probability = joint_loss_probability(&correlated);
+        assert!((independent_probability - 0.25).abs() < 0.03, "independent: {}", independent_probability);
+        assert!((correlated_probability - 0.45).abs() < 0.03, "correlated: {}", correlated_probability);
+    }
+
+    #[tokio::test]
+    async fn test_paths_use_each_tokens_tracked_volatility() {
+        // ETH moved 1% a day, about 19% annualized or 5.5% over the 30 day horizon
+        let tracker = Arc::new(crate::data::VolatilityTracker::default());
+        let start = Utc::now() - Duration::days(1);
+        for (price, timestamp) in [(3000, start), (3030, start + Duration::days(1))] {
+            tracker.record(&crate::types::PriceData {
+                token_address: "ETH".to_string(),
+                price_usd: rust_decimal::Decimal::from(price),
+                timestamp,
+                source: "test".to_string(),
+                confidence: rust_decimal::Decimal::ONE,
+            });
+        }
+        let framework = StressTestingFramework::new(StressTestingConfig::default()).with_volatility_tracker(tracker);
+        // Untracked STETH falls back to the configured volatility, here none at all
+        let config = MonteCarloConfig { price_volatility: 0.0, correlations: None, ..correlated_config(0.0) };
+        let horizon_volatility = 0.01 * (365.25_f64 * 30.0 / 365.0).sqrt();
+        assert!((framework.token_volatility("ETH", &config) - horizon_volatility).abs() < 1e-9);
+        assert_eq!(framework.token_volatility("STETH", &config), 0.0);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut eth_returns = Vec::new();
+        for _ in 0..2000 {
+            let simulated = framework.simulate_price_movements(&two_asset_positions(), &config, &mut rng).await.unwrap();
+            assert_eq!(simulated[1].current_price, 3000.0);
+            eth_returns.push((simulated[0].current_price / 3000.0).ln());
+        }
+        let mean = eth_returns.iter().sum::<f64>() / eth_returns.len() as f64;
+        let std_dev = (eth_returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / eth_returns.len() as f64).sqrt();
+        assert!((std_dev - horizon_volatility).abs() < 0.005, "{}", std_dev);
+    }
+
+    #[tokio::test]
+    async fn test_monte_carlo_risk_metrics_follow_the_daily_path() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let config = MonteCarloConfig { iterations: 200, ..correlated_config(0.0) };
+
+        let paths = StressTestingFramework::simulate_paths(&two_asset_positions(), &config, 7).unwrap();
+        assert!(paths.iter().all(|path| path.equity_curve.len() == 31));
+
+        let results = framework.run_monte_carlo_simulation(&two_asset_positions(), &config).await.unwrap();
+        assert!(results.iter().all(|r| r.risk_metrics.volatility > 0.0 && r.risk_metrics.sharpe_ratio != 0.0));
+        assert!(results.iter().all(|r| r.max_drawdown >= 0.0 && r.max_drawdown == r.risk_metrics.max_drawdown));
+        // Even paths that end up ahead dip below an earlier peak along the way
+        assert!(results.iter().any(|r| r.final_portfolio_value > r.initial_portfolio_value && r.max_drawdown > 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_monte_carlo_reports_the_shock_correlations() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let config = MonteCarloConfig { iterations: 10, ..correlated_config(0.6) };
+
+        let results = framework.run_monte_carlo_simulation(&two_asset_positions(), &config).await.unwrap();
+        let matrix = &results[0].risk_metrics.correlation_matrix;
+        assert!((matrix[0][0] - 1.0).abs() < 1e-12);
+        assert!((matrix[0][1] - 0.6).abs() < 1e-12);
+        assert!((matrix[1][0] - 0.6).abs() < 1e-12);
+
+        let independent = MonteCarloConfig { correlations: None, ..config };
+        let results = framework.run_monte_carlo_simulation(&two_asset_positions(), &independent).await.unwrap();
+        assert_eq!(results[0].risk_metrics.correlation_matrix, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_non_positive_definite_correlations_fall_back_to_independent_draws() {
+        let positions = two_asset_positions();
+        let invalid = correlated_config(1.5);
+        let unset = MonteCarloConfig { correlations: None, ..invalid.clone() };
+
+        let fallback = StressTestingFramework::simulate_paths(&positions, &invalid, 5).unwrap();
+        let independent = StressTestingFramework::simulate_paths(&positions, &unset, 5).unwrap();
+
+        let prices = |paths: &[SimulatedPath]| -> Vec<u64> {
+            paths.iter().flat_map(|path| &path.positions).map(|p| p.current_price.to_bits()).collect()
+        };
+        assert_eq!(prices(&fallback), prices(&independent));
+    }
+
+    #[test]
+    fn test_cholesky_decomposition() {
+        let lower = cholesky_decompose(&[vec![4.0, 2.0], vec![2.0, 5.0]]).unwrap();
+        assert_eq!(lower, vec![vec![2.0, 0.0], vec![1.0, 2.0]]);
+
+        assert!(cholesky_decompose(&[vec![1.0, 2.0], vec![2.0, 1.0]]).is_none());
+        assert!(cholesky_decompose(&[vec![1.0, 0.5], vec![0.2, 1.0]]).is_none());
+    }
+
+    fn collateral_position(token: &str, collateral_value: f64) -> SimulationPosition {
+        SimulationPosition {
+            token_address: token.to_string(),
+            quantity: 1.0,
+            entry_price: collateral_value,
+            current_price: collateral_value,
+            collateral_value,
+            debt_value: 0.0,
+            liquidation_threshold: 0.8,
+            health_factor: f64::INFINITY,
+            debt_tokens: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concentrated_portfolio_is_recommended_to_diversify() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let risk_metrics = StressTestingFramework::risk_metrics_from_equity_curve(&[100.0, 110.0, 121.0], 0.0);
+        let diversify_recommendations = |recommendations: &[SimulationRecommendation]| {
+            recommendations.iter()
+                .filter(|r| matches!(r.recommendation_type, RecommendationType::DiversifyPortfolio))
+                .count()
+        };
+
+        let equal_weight: Vec<SimulationPosition> = ["ETH", "BTC", "SOL", "AAVE"].iter()
+            .map(|token| collateral_position(token, 2500.0))
+            .collect();
+        let recommendations = framework.generate_recommendations(&equal_weight, &risk_metrics, &[]).await.unwrap();
+        assert_eq!(diversify_recommendations(&recommendations), 0);
+
+        // Two ETH positions count as one holding: 0.85^2 + 3 * 0.05^2 = 0.73
+        let concentrated = vec![
+            collateral_position("ETH", 4250.0),
+            collateral_position("ETH", 4250.0),
+            collateral_position("BTC", 500.0),
+            collateral_position("SOL", 500.0),
+            collateral_position("AAVE", 500.0),
+        ];
+        let recommendations = framework.generate_recommendations(&concentrated, &risk_metrics, &[]).await.unwrap();
+        assert_eq!(diversify_recommendations(&recommendations), 1);
+        let recommendation = recommendations.iter()
+            .find(|r| matches!(r.recommendation_type, RecommendationType::DiversifyPortfolio))
+            .unwrap();
+        assert!(recommendation.description.contains("0.73"), "{}", recommendation.description);
+    }
+
+    fn recording_callback() -> (ProgressCallback, Arc<std::sync::Mutex<Vec<ProgressUpdate>>>) {
+        let updates = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = updates.clone();
+        let callback: ProgressCallback = Arc::new(move |update| recorded.lock().unwrap().push(update));
+        (callback, updates)
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_reported_monotonically() {
+        let framework = StressTestingFramework::new(StressTestingConfig {
+            progress_interval_ms: 0,
+            ..StressTestingConfig::default()
+        });
+        let config = MonteCarloConfig {
+            execution_mode: MonteCarloExecutionMode::DeterministicParallel { workers: 4 },
+            correlations: None,
+            iterations: 300,
+            ..correlated_config(0.0)
+        };
+
+        let (callback, updates) = recording_callback();
+        let results = framework.run_monte_carlo_simulation_with_progress(&two_asset_positions(), &config, Some(callback)).await.unwrap();
+        assert_eq!(results.len(), 300);
+
+        let updates = updates.lock().unwrap().clone();
+        assert!(updates.len() > 2, "only {} updates", updates.len());
+        assert_eq!(updates[0].percent_complete, 0.0);
+        assert_eq!(updates[0].paths_processed, 0);
+        let last = updates.last().unwrap();
+        assert_eq!((last.percent_complete, last.paths_processed, last.total_paths), (100.0, 300, 300));
+        assert!(updates.windows(2).all(|pair| pair[0].paths_processed < pair[1].paths_processed));
+        assert!(updates.windows(2).all(|pair| pair[0].percent_complete < pair[1].percent_complete));
+
+        // A stress test is a single path: start and finish only
+        let (callback, updates) = recording_callback();
+        framework.run_stress_test_with_progress(&two_asset_positions(), &SimulationScenario::CryptoWinter, Some(callback)).await.unwrap();
+        let percents: Vec<f64> = updates.lock().unwrap().iter().map(|update| update.percent_complete).collect();
+        assert_eq!(percents, vec![0.0, 100.0]);
+
+        // Paths finishing before the interval is up report nothing in between
+        let patient = StressTestingFramework::new(StressTestingConfig {
+            progress_interval_ms: 60_000,
+            ..StressTestingConfig::default()
+        });
+        let (callback, updates) = recording_callback();
+        patient.run_monte_carlo_simulation_with_progress(&two_asset_positions(), &config, Some(callback)).await.unwrap();
+        let processed: Vec<u64> = updates.lock().unwrap().iter().map(|update| update.paths_processed).collect();
+        assert_eq!(processed, vec![0, 300]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_simulation_returns_promptly() {
+        let framework = StressTestingFramework::new(StressTestingConfig {
+            progress_interval_ms: 0,
+            ..StressTestingConfig::default()
+        });
+        // Far more paths than could finish in the time allowed below
+        let config = MonteCarloConfig {
+            execution_mode: MonteCarloExecutionMode::DeterministicParallel { workers: 4 },
+            correlations: None,
+            iterations: 50_000_000,
+            ..correlated_config(0.0)
+        };
+
+        let cancellation = CancellationToken::new();
+        let trigger = cancellation.clone();
+        let callback: ProgressCallback = Arc::new(move |update| {
+            if update.paths_processed >= 1_000 {
+                trigger.cancel();
+            }
+        });
+        let started = std::time::Instant::now();
+        let error = framework
+            .run_monte_carlo_simulation_cancellable(&two_asset_positions(), &config, Some(callback), cancellation)
+            .await
+            .unwrap_err();
+        assert!(matches!(error.downcast_ref::<SimulationError>(), Some(SimulationError::Cancelled)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5), "took {:?}", started.elapsed());
+
+        // A cancelled stress test leaves nothing in the cache, and the framework still works
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let error = framework
+            .run_stress_test_cancellable(&two_asset_positions(), &SimulationScenario::CryptoWinter, None, cancelled)
+            .await
+            .unwrap_err();
+        assert!(matches!(error.downcast_ref::<SimulationError>(), Some(SimulationError::Cancelled)));
+        assert!(framework.get_cache_stats().await.unwrap().is_empty());
+
+        framework.run_stress_test(&two_asset_positions(), &SimulationScenario::CryptoWinter).await.unwrap();
+        assert_eq!(framework.get_cache_stats().await.unwrap()["simulation_cache_entries"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_cached_results_expire_after_ttl() {
+        let framework = StressTestingFramework::new(StressTestingConfig {
+            cache_ttl_secs: 1,
+            ..StressTestingConfig::default()
+        });
+        let positions = two_asset_positions();
+
+        let first = framework.run_stress_test(&positions, &SimulationScenario::CryptoWinter).await.unwrap();
+        let cached = framework.run_stress_test(&positions, &SimulationScenario::CryptoWinter).await.unwrap();
+        assert_eq!(cached.timestamp, first.timestamp);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1_100)).await;
+        let stats = framework.get_cache_stats().await.unwrap();
+        assert_eq!(stats["simulation_cache_entries"], 0);
+        assert_eq!(stats["simulation_cache_expired"], 1);
+
+        let recomputed = framework.run_stress_test(&positions, &SimulationScenario::CryptoWinter).await.unwrap();
+        assert!(recomputed.timestamp > first.timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_result_is_evicted_at_capacity() {
+        let framework = StressTestingFramework::new(StressTestingConfig {
+            max_cache_entries: 2,
+            ..StressTestingConfig::default()
+        });
+        let positions = two_asset_positions();
+        let run = |scenario: SimulationScenario| {
+            let framework = &framework;
+            let positions = &positions;
+            async move { framework.run_stress_test(positions, &scenario).await.unwrap() }
+        };
+
+        let winter = run(SimulationScenario::CryptoWinter).await;
+        let swan = run(SimulationScenario::BlackSwan).await;
+        // Touch CryptoWinter so BlackSwan becomes the least recently used
+        assert_eq!(run(SimulationScenario::CryptoWinter).await.timestamp, winter.timestamp);
+        run(SimulationScenario::DeFiContagion).await;
+
+        let stats = framework.get_cache_stats().await.unwrap();
+        assert_eq!(stats["simulation_cache_entries"], 2);
+        assert_eq!(stats["simulation_cache_evicted"], 1);
+
+        assert_eq!(run(SimulationScenario::CryptoWinter).await.timestamp, winter.timestamp);
+        assert!(run(SimulationScenario::BlackSwan).await.timestamp > swan.timestamp);
+
+        // Shedding drops CryptoWinter, now the least recently used, and counts it as an eviction
+        assert_eq!(framework.shed_cache(0.5).await, 1);
+        let stats = framework.get_cache_stats().await.unwrap();
+        assert_eq!(stats["simulation_cache_entries"], 1);
+        assert_eq!(stats["simulation_cache_evicted"], 3);
+        assert!(run(SimulationScenario::CryptoWinter).await.timestamp > winter.timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_depeg_reprices_stablecoin_debt() {
+        let framework = StressTestingFramework::new(StressTestingConfig::default());
+        let positions = vec![SimulationPosition {
+            token_address: "ETH".to_string(),
+            quantity: 10.0,
+            entry_price: 2000.0,
+            current_price: 2000.0,
+            collateral_value: 20_000.0,
+            debt_value: 10_000.0,
+            liquidation_threshold: 1.0,
+            health_factor: 2.0,
+            debt_tokens: HashMap::from([("USDC".to_string(), 10_000.0)]),
+        }];
+        let scenario = SimulationScenario::StablecoinDepeg(DepegScenario {
+            stablecoin: "USDC".to_string(),
+            trough_price_bps: 9000,
+            days_to_trough: 1,
+            recovery_days: 10,
+            recovered_price_bps: 10_000,
+        });
+
+        let result = framework.run_stress_test(&positions, &scenario).await.unwrap();
+
+        // The USDC owed is worth $9,000 at the trough, so the position gains net value
+        assert!((result.initial_portfolio_value - 10_000.0).abs() < 1e-9);
+        assert!((result.final_portfolio_value - 11_000.0).abs() < 1e-9, "{}", result.final_portfolio_value);
+        assert!(result.liquidated_positions.is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use super::visualization::{SimulationReport, VisualizationFramework};
+use rust_xlsxwriter::{
+    Color, ConditionalFormatCell, ConditionalFormatCellRule, Format, Workbook, Worksheet, XlsxError,
+};
+use std::collections::BTreeSet;
+
+/// Correlations at or beyond this magnitude are highlighted in the correlation sheet
+pub const HIGH_CORRELATION_THRESHOLD: f64 = 0.7;
+
+pub const SUMMARY_SHEET: &str = "Summary";
+pub const POSITIONS_SHEET: &str = "Positions";
+pub const CORRELATION_SHEET: &str = "Correlations";
+
+impl VisualizationFramework {
+    /// Excel workbook of `report` with summary, per-position and correlation-matrix sheets.
+    ///
+    /// Numbers are written as numeric cells so they can be sorted and charted in Excel.
+    /// Correlations of magnitude `HIGH_CORRELATION_THRESHOLD` or more are highlighted.
+    pub fn export_report_xlsx(&self, report: &SimulationReport) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let header = Format::new().set_bold();
+        let mut workbook = Workbook::new();
+
+        write_summary_sheet(workbook.add_worksheet().set_name(SUMMARY_SHEET)?, report, &header)?;
+        write_positions_sheet(workbook.add_worksheet().set_name(POSITIONS_SHEET)?, report, &header)?;
+        write_correlation_sheet(workbook.add_worksheet().set_name(CORRELATION_SHEET)?, report, &header)?;
+
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+fn write_summary_sheet(sheet: &mut Worksheet, report: &SimulationReport, header: &Format) -> Result<(), XlsxError> {
+    sheet.write_string_with_format(0, 0, "Report", header)?;
+    sheet.write_string(0, 1, &report.report_id)?;
+    sheet.write_string_with_format(1, 0, "Scenario", header)?;
+    sheet.write_string(1, 1, format!("{:?}", report.scenario))?;
+    sheet.write_string_with_format(2, 0, "Generated", header)?;
+    sheet.write_string(2, 1, report.timestamp.to_rfc3339())?;
+
+    let summary = &report.summary;
+    let risk = &report.risk_analysis;
+    let mut metrics = vec![
+        ("Initial Portfolio Value", summary.initial_portfolio_value),
+        ("Final Portfolio Value", summary.final_portfolio_value),
+        ("Total Return", summary.total_return),
+        ("Max Drawdown", summary.max_drawdown),
+        ("VaR (95%)", summary.var_95),
+        ("CVaR (95%)", summary.cvar_95),
+        ("Liquidated Positions", summary.liquidated_positions_count as f64),
+        ("Surviving Positions", summary.surviving_positions_count as f64),
+        ("Simulation Duration (ms)", summary.simulation_duration_ms as f64),
+        ("Sharpe Ratio", risk.sharpe_ratio),
+        ("Sortino Ratio", risk.sortino_ratio),
+        ("Calmar Ratio", risk.calmar_ratio),
+        ("Volatility", risk.volatility),
+        ("Beta", risk.beta),
+        ("Max Drawdown Duration (days)", risk.max_drawdown_duration as f64),
+    ];
+    if let Some(days) = risk.recovery_time_days {
+        metrics.push(("Recovery Time (days)", days as f64));
+    }
+    let mut custom: Vec<(&str, f64)> = report.custom_metrics.iter()
+        .map(|(name, value)| (name.as_str(), *value))
+        .collect();
+    custom.sort_by(|a, b| a.0.cmp(b.0));
+    metrics.extend(custom);
+
+    sheet.write_string_with_format(4, 0, "Metric", header)?;
+    sheet.write_string_with_format(4, 1, "Value", header)?;
+    for (index, (name, value)) in metrics.into_iter().enumerate() {
+        let row = 5 + index as u32;
+        sheet.write_string(row, 0, name)?;
+        sheet.write_number(row, 1, value)?;
+    }
+    sheet.set_column_width(0, 30)?;
+    sheet.set_column_width(1, 24)?;
+    Ok(())
+}
+
+/// One row per position named anywhere in the report's charts or heatmaps
+fn write_positions_sheet(sheet: &mut Worksheet, report: &SimulationReport, header: &Format) -> Result<(), XlsxError> {
+    let heatmaps = &report.heatmaps;
+    let positions: BTreeSet<&String> = report.charts.position_performance.keys()
+        .chain(heatmaps.risk_scores.keys())
+        .chain(heatmaps.concentration_metrics.keys())
+        .collect();
+
+    for (column, title) in ["Position", "Risk Score", "Concentration", "Latest Value"].into_iter().enumerate() {
+        sheet.write_string_with_format(0, column as u16, title, header)?;
+    }
+    for (index, position) in positions.into_iter().enumerate() {
+        let row = 1 + index as u32;
+        sheet.write_string(row, 0, position.as_str())?;
+        // Missing values stay blank rather than reading as zero
+        if let Some(score) = heatmaps.risk_scores.get(position) {
+            sheet.write_number(row, 1, *score)?;
+        }
+        if let Some(concentration) = heatmaps.concentration_metrics.get(position) {
+            sheet.write_number(row, 2, *concentration)?;
+        }
+        if let Some(latest) = report.charts.position_performance.get(position).and_then(|points| points.last()) {
+            sheet.write_number(row, 3, latest.value)?;
+        }
+    }
+    sheet.set_column_width(0, 24)?;
+    Ok(())
+}
+
+fn write_correlation_sheet(sheet: &mut Worksheet, report: &SimulationReport, header: &Format) -> Result<(), XlsxError> {
+    let heatmap = &report.heatmaps;
+    let size = heatmap.correlation_matrix.len();
+    let name = |index: usize| heatmap.asset_names.get(index).map(String::as_str).unwrap_or("");
+
+    sheet.write_string_with_format(0, 0, "Asset", header)?;
+    for (index, values) in heatmap.correlation_matrix.iter().enumerate() {
+        let position = 1 + index as u16;
+        sheet.write_string_with_format(0, position, name(index), header)?;
+        sheet.write_string_with_format(position as u32, 0, name(index), header)?;
+        for (column, value) in values.iter().enumerate() {
+            sheet.write_number(position as u32, 1 + column as u16, *value)?;
+        }
+    }
+
+    let columns = heatmap.correlation_matrix.iter().map(Vec::len).max().unwrap_or(0);
+    if size > 0 && columns > 0 {
+        let highlight = Format::new().set_background_color(Color::RGB(0xFFC7CE)).set_font_color(Color::RGB(0x9C0006));
+        for rule in [
+            ConditionalFormatCellRule::GreaterThanOrEqualTo(HIGH_CORRELATION_THRESHOLD),
+            ConditionalFormatCellRule::LessThanOrEqualTo(-HIGH_CORRELATION_THRESHOLD),
+        ] {
+            let format = ConditionalFormatCell::new().set_rule(rule).set_format(&highlight);
+            for (first_row, first_column, last_row, last_column) in off_diagonal_ranges(size, columns) {
+                sheet.add_conditional_format(first_row, first_column, last_row, last_column, &format)?;
+            }
+        }
+    }
+    sheet.set_column_width(0, 16)?;
+    Ok(())
+}
+
+/// Sheet ranges covering the matrix cells left and right of the diagonal in each row, so
+/// an asset's correlation of 1.0 with itself isn't highlighted
+fn off_diagonal_ranges(size: usize, columns: usize) -> Vec<(u32, u16, u32, u16)> {
+    let mut ranges = Vec::new();
+    for index in 0..size {
+        let row = 1 + index as u32;
+        let diagonal = 1 + index as u16;
+        if diagonal > 1 {
+            ranges.push((row, 1, row, diagonal - 1));
+        }
+        if (diagonal as usize) < columns {
+            ranges.push((row, diagonal + 1, row, columns as u16));
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{RiskMetrics, SimulationResult, SimulationScenario};
+    use calamine::{open_workbook_from_rs, Data, Reader, Xlsx};
+    use chrono::Utc;
+    use std::io::Cursor;
+
+    fn simulation_result() -> SimulationResult {
+        SimulationResult {
+            scenario: SimulationScenario::CryptoWinter,
+            initial_portfolio_value: 100_000.0,
+            final_portfolio_value: 81_500.0,
+            max_drawdown: 0.25,
+            var_95: 0.12,
+            cvar_95: 0.18,
+            liquidated_positions: vec!["WBTC".to_string()],
+            surviving_positions: vec!["ETH".to_string(), "USDC".to_string()],
+            risk_metrics: RiskMetrics {
+                sharpe_ratio: -0.8,
+                sortino_ratio: -1.1,
+                calmar_ratio: -0.7,
+                max_drawdown_duration: 40,
+                recovery_time_days: None,
+                volatility: 0.65,
+                beta: 1.2,
+                correlation_matrix: vec![vec![1.0, 0.82], vec![0.82, 1.0]],
+                max_drawdown: 0.25,
+                value_at_risk_95: 0.0,
+                value_at_risk_99: 0.0,
+                conditional_var_95: 0.0,
+            },
+            recommendations: Vec::new(),
+            simulation_duration_ms: 250,
+            timestamp: Utc::now(),
+            seed: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workbook_has_named_sheets_with_numeric_cells() {
+        let framework = VisualizationFramework::new();
+        let report = framework.generate_report(&simulation_result(), "standard_report").await.unwrap();
+
+        let bytes = framework.export_report_xlsx(&report).unwrap();
+        let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes)).unwrap();
+        assert_eq!(workbook.sheet_names(), vec![SUMMARY_SHEET, POSITIONS_SHEET, CORRELATION_SHEET]);
+
+        let summary = workbook.worksheet_range(SUMMARY_SHEET).unwrap();
+        assert_eq!(summary.get_value((6, 0)), Some(&Data::String("Final Portfolio Value".to_string())));
+        assert_eq!(summary.get_value((6, 1)), Some(&Data::Float(81_500.0)));
+
+        let positions = workbook.worksheet_range(POSITIONS_SHEET).unwrap();
+        assert_eq!(positions.get_value((1, 0)), Some(&Data::String("ETH".to_string())));
+        assert_eq!(positions.get_value((1, 1)), Some(&Data::Float(0.5)));
+        assert_eq!(positions.get_value((2, 0)), Some(&Data::String("USDC".to_string())));
+
+        let correlations = workbook.worksheet_range(CORRELATION_SHEET).unwrap();
+        assert_eq!(correlations.get_value((0, 2)), Some(&Data::String("USDC".to_string())));
+        assert_eq!(correlations.get_value((1, 2)), Some(&Data::Float(0.82)));
+    }
+
+    #[test]
+    fn test_highlight_ranges_skip_the_diagonal() {
+        assert_eq!(
+            off_diagonal_ranges(3, 3),
+            vec![(1, 2, 1, 3), (2, 1, 2, 1), (2, 3, 2, 3), (3, 1, 3, 2)],
+        );
+        assert!(off_diagonal_ranges(1, 1).is_empty());
+    }
+}
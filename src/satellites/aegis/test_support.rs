@@ -0,0 +1,85 @@
+use crate::liquidation::{AlertSystem, PriceFeedProvider};
+use crate::risk::{ExecutionResult, HistoricalDataProvider, TradeExecutor};
+use crate::types::{AssetPrice, PositionId, PriceData, RiskAlert, TokenAddress};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Prices ETH at $2000 and every other token at $1, timestamped now
+pub(crate) struct StaticPriceFeed;
+
+#[async_trait]
+impl PriceFeedProvider for StaticPriceFeed {
+    async fn get_prices(&self, token_addresses: &[TokenAddress]) -> Result<HashMap<TokenAddress, PriceData>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut prices = HashMap::new();
+        for token in token_addresses {
+            prices.insert(token.clone(), self.get_price(token).await?);
+        }
+        Ok(prices)
+    }
+
+    async fn get_price(&self, token_address: &TokenAddress) -> Result<PriceData, Box<dyn std::error::Error + Send + Sync>> {
+        let price_usd = match token_address.as_str() {
+            "ETH" => Decimal::from(2000),
+            _ => Decimal::ONE,
+        };
+        Ok(PriceData {
+            token_address: token_address.clone(),
+            price_usd,
+            timestamp: chrono::Utc::now(),
+            source: "test".to_string(),
+            confidence: Decimal::ONE,
+        })
+    }
+}
+
+/// Executor for tests that never trade; every call fails
+pub(crate) struct UnusedExecutor;
+
+#[async_trait]
+impl TradeExecutor for UnusedExecutor {
+    async fn execute_position_reduction(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+        Err("not used".into())
+    }
+
+    async fn emergency_exit_position(&self, _position_id: PositionId) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+        Err("not used".into())
+    }
+
+    async fn add_collateral(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+        Err("not used".into())
+    }
+
+    async fn repay_debt(&self, _position_id: PositionId, _token_address: &str, _amount: Decimal) -> Result<ExecutionResult, Box<dyn std::error::Error + Send + Sync>> {
+        Err("not used".into())
+    }
+}
+
+/// Historical data provider with no history for any token
+pub(crate) struct NoHistory;
+
+#[async_trait]
+impl HistoricalDataProvider for NoHistory {
+    async fn get_historical_prices(&self, _token_address: &TokenAddress, _days: u32) -> Result<Vec<AssetPrice>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Alert system that drops every alert
+pub(crate) struct NullAlertSystem;
+
+#[async_trait]
+impl AlertSystem for NullAlertSystem {
+    async fn send_alert(&self, _alert: RiskAlert) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn get_alerts(&self, _position_id: Option<PositionId>) -> Result<Vec<RiskAlert>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Vec::new())
+    }
+
+    async fn acknowledge_alert_with_note(&self, _alert_id: Uuid, _acknowledged_by: &str, _note: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+}
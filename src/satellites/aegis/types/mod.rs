@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::time::Duration;
 
 pub type PositionId = Uuid;
 pub type ProtocolId = String;
@@ -17,6 +19,158 @@ pub struct Position {
     pub debt_tokens: HashMap<TokenAddress, PositionToken>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Thresholds for this position only, e.g. tighter bands for volatile collateral
+    #[serde(default)]
+    pub risk_overrides: Option<RiskParameters>,
+}
+
+impl Position {
+    /// The position's own risk parameters, or `default` when it has no overrides
+    pub fn risk_parameters<'a>(&'a self, default: &'a RiskParameters) -> &'a RiskParameters {
+        self.risk_overrides.as_ref().unwrap_or(default)
+    }
+
+    /// Check the invariants an externally supplied position must hold, reporting the first
+    /// failing field by its JSON path, e.g. `collateral_tokens.ETH.amount`
+    pub fn validate(&self) -> Result<(), PositionError> {
+        let invalid = |field: String, problem: String| Err(PositionError::Invalid {
+            message: format!("{}: {}", field, problem),
+        });
+
+        if self.protocol.trim().is_empty() {
+            return invalid("protocol".to_string(), "must not be empty".to_string());
+        }
+
+        for (side, tokens) in [("collateral_tokens", &self.collateral_tokens), ("debt_tokens", &self.debt_tokens)] {
+            let mut addresses: Vec<&TokenAddress> = tokens.keys().collect();
+            addresses.sort();
+            for address in addresses {
+                let token = &tokens[address];
+                if token.token_address != *address {
+                    return invalid(
+                        format!("{}.{}.token_address", side, address),
+                        format!("must match its key, got {:?}", token.token_address),
+                    );
+                }
+                let amounts = [
+                    ("amount", Some(token.amount)),
+                    ("value_usd", Some(token.value_usd)),
+                    ("price_per_token", Some(token.price_per_token)),
+                    ("entry_price_usd", token.entry_price_usd),
+                ];
+                for (field, value) in amounts {
+                    if let Some(value) = value.filter(|value| *value < Decimal::ZERO) {
+                        return invalid(format!("{}.{}.{}", side, address, field), format!("must not be negative, got {}", value));
+                    }
+                }
+            }
+        }
+
+        if let Some(overrides) = &self.risk_overrides {
+            let thresholds = [
+                ("safe_health_threshold", overrides.safe_health_threshold),
+                ("warning_health_threshold", overrides.warning_health_threshold),
+                ("critical_health_threshold", overrides.critical_health_threshold),
+                ("emergency_health_threshold", overrides.emergency_health_threshold),
+            ];
+            for (field, value) in thresholds {
+                if value <= Decimal::ZERO || value > Decimal::TWO {
+                    return invalid(format!("risk_overrides.{}", field), format!("must be within (0, 2], got {}", value));
+                }
+            }
+            // Risk levels are read off these from the top down, so each must sit below the last
+            for pair in thresholds.windows(2) {
+                let ((higher_field, higher), (field, value)) = (pair[0], pair[1]);
+                if value >= higher {
+                    return invalid(
+                        format!("risk_overrides.{}", field),
+                        format!("must be below {} ({}), got {}", higher_field, higher, value),
+                    );
+                }
+            }
+            if overrides.max_position_size_usd < Decimal::ZERO {
+                return invalid(
+                    "risk_overrides.max_position_size_usd".to_string(),
+                    format!("must not be negative, got {}", overrides.max_position_size_usd),
+                );
+            }
+            let exposure = overrides.max_protocol_exposure_percent;
+            if exposure <= Decimal::ZERO || exposure > Decimal::ONE_HUNDRED {
+                return invalid(
+                    "risk_overrides.max_protocol_exposure_percent".to_string(),
+                    format!("must be within (0, 100], got {}", exposure),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// JSON schema (draft 2020-12) of the `Position` payloads accepted by
+/// `AegisSatellite::add_position_json`
+pub fn position_schema() -> serde_json::Value {
+    let decimal = |description: &str| serde_json::json!({ "type": "number", "minimum": 0, "description": description });
+    let threshold = serde_json::json!({ "type": "number", "exclusiveMinimum": 0, "maximum": 2 });
+    let token = serde_json::json!({
+        "type": "object",
+        "required": ["token_address", "amount", "value_usd", "price_per_token"],
+        "properties": {
+            "token_address": { "type": "string", "minLength": 1 },
+            "amount": decimal("Token units held"),
+            "value_usd": decimal("USD value when the position was last updated"),
+            "price_per_token": decimal("USD price when the position was last updated"),
+            "entry_price_usd": {
+                "type": ["number", "null"],
+                "minimum": 0,
+                "description": "Price the position was entered at, used for PnL"
+            }
+        }
+    });
+    let tokens = |description: &str| serde_json::json!({
+        "type": "object",
+        "description": description,
+        "additionalProperties": token.clone()
+    });
+
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Position",
+        "type": "object",
+        "required": ["id", "protocol", "collateral_tokens", "debt_tokens", "created_at", "updated_at"],
+        "properties": {
+            "id": { "type": "string", "format": "uuid" },
+            "protocol": { "type": "string", "minLength": 1, "pattern": "\\S" },
+            "collateral_tokens": tokens("Collateral keyed by token address"),
+            "debt_tokens": tokens("Debt keyed by token address"),
+            "created_at": { "type": "string", "format": "date-time" },
+            "updated_at": { "type": "string", "format": "date-time" },
+            "risk_overrides": {
+                "type": ["object", "null"],
+                "description": "Thresholds for this position only; health thresholds must satisfy safe > warning > critical > emergency",
+                "required": [
+                    "safe_health_threshold", "warning_health_threshold", "critical_health_threshold",
+                    "emergency_health_threshold", "max_position_size_usd", "max_protocol_exposure_percent"
+                ],
+                "properties": {
+                    "safe_health_threshold": threshold.clone(),
+                    "warning_health_threshold": threshold.clone(),
+                    "critical_health_threshold": threshold.clone(),
+                    "emergency_health_threshold": threshold,
+                    "max_position_size_usd": decimal("Largest collateral value before the position is flagged"),
+                    "max_protocol_exposure_percent": { "type": "number", "exclusiveMinimum": 0, "maximum": 100 },
+                    "max_price_age": {
+                        "type": "object",
+                        "required": ["secs", "nanos"],
+                        "properties": {
+                            "secs": { "type": "integer", "minimum": 0 },
+                            "nanos": { "type": "integer", "minimum": 0 }
+                        }
+                    }
+                }
+            }
+        }
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +179,9 @@ pub struct PositionToken {
     pub amount: Decimal,
     pub value_usd: Decimal,
     pub price_per_token: Decimal,
+    /// Price at which the position was entered, if known; needed for PnL tracking
+    #[serde(default)]
+    pub entry_price_usd: Option<Decimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,7 +203,9 @@ impl HealthFactor {
     }
 
     pub fn risk_level(&self, risk_params: &RiskParameters) -> RiskLevel {
-        if self.value <= risk_params.critical_health_threshold {
+        if self.value <= risk_params.emergency_health_threshold {
+            RiskLevel::Emergency
+        } else if self.value <= risk_params.critical_health_threshold {
             RiskLevel::Critical
         } else if self.value <= risk_params.warning_health_threshold {
             RiskLevel::Warning
@@ -56,7 +215,7 @@ impl HealthFactor {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RiskParameters {
     pub safe_health_threshold: Decimal,
     pub warning_health_threshold: Decimal,
@@ -64,6 +223,13 @@ pub struct RiskParameters {
     pub emergency_health_threshold: Decimal,
     pub max_position_size_usd: Decimal,
     pub max_protocol_exposure_percent: Decimal,
+    /// Prices older than this are rejected rather than used for health calculations
+    #[serde(default = "default_max_price_age")]
+    pub max_price_age: std::time::Duration,
+}
+
+fn default_max_price_age() -> std::time::Duration {
+    std::time::Duration::from_secs(60 * 60)
 }
 
 impl Default for RiskParameters {
@@ -75,11 +241,13 @@ impl Default for RiskParameters {
             emergency_health_threshold: Decimal::from(105) / Decimal::from(100), // 1.05
             max_position_size_usd: Decimal::from(1_000_000), // $1M
             max_protocol_exposure_percent: Decimal::from(25), // 25%
+            max_price_age: default_max_price_age(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+// Variants are declared in order of increasing severity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum RiskLevel {
     Safe,
     Warning,
@@ -87,7 +255,12 @@ pub enum RiskLevel {
     Emergency,
 }
 
+fn default_occurrence_count() -> u32 {
+    1
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredRiskAlert")]
 pub struct RiskAlert {
     pub id: Uuid,
     pub position_id: PositionId,
@@ -96,10 +269,91 @@ pub struct RiskAlert {
     pub health_factor: HealthFactor,
     pub message: String,
     pub created_at: DateTime<Utc>,
+    /// Times this alert fired, counting repeats suppressed as duplicates
+    pub occurrence_count: u32,
+    /// When the alert last fired, including suppressed repeats
+    pub last_seen: DateTime<Utc>,
     pub acknowledged: bool,
+    pub protocol: Option<ProtocolId>,
+    pub related_tokens: Vec<TokenAddress>,
+    /// Seconds until liquidation at the recent rate of health decline, if it is declining
+    pub projected_seconds_to_liquidation: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A `RiskAlert` as written by older versions, which may lack the later fields. An alert
+/// stored before `last_seen` existed was last seen when it was raised.
+#[derive(Deserialize)]
+struct StoredRiskAlert {
+    id: Uuid,
+    position_id: PositionId,
+    alert_type: AlertType,
+    risk_level: RiskLevel,
+    health_factor: HealthFactor,
+    message: String,
+    created_at: DateTime<Utc>,
+    #[serde(default = "default_occurrence_count")]
+    occurrence_count: u32,
+    #[serde(default)]
+    last_seen: Option<DateTime<Utc>>,
+    acknowledged: bool,
+    #[serde(default)]
+    protocol: Option<ProtocolId>,
+    #[serde(default)]
+    related_tokens: Vec<TokenAddress>,
+    #[serde(default)]
+    projected_seconds_to_liquidation: Option<f64>,
+}
+
+impl From<StoredRiskAlert> for RiskAlert {
+    fn from(stored: StoredRiskAlert) -> Self {
+        Self {
+            id: stored.id,
+            position_id: stored.position_id,
+            alert_type: stored.alert_type,
+            risk_level: stored.risk_level,
+            health_factor: stored.health_factor,
+            message: stored.message,
+            created_at: stored.created_at,
+            occurrence_count: stored.occurrence_count,
+            last_seen: stored.last_seen.unwrap_or(stored.created_at),
+            acknowledged: stored.acknowledged,
+            protocol: stored.protocol,
+            related_tokens: stored.related_tokens,
+            projected_seconds_to_liquidation: stored.projected_seconds_to_liquidation,
+        }
+    }
+}
+
+impl RiskAlert {
+    /// An alert about the book as a whole rather than one position, carrying the nil
+    /// position id and a zeroed health factor
+    pub fn portfolio(alert_type: AlertType, risk_level: RiskLevel, message: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            position_id: Uuid::nil(),
+            alert_type,
+            risk_level,
+            health_factor: HealthFactor {
+                value: Decimal::ZERO,
+                liquidation_threshold: Decimal::ZERO,
+                collateral_value: Decimal::ZERO,
+                debt_value: Decimal::ZERO,
+                calculated_at: now,
+            },
+            message,
+            created_at: now,
+            occurrence_count: 1,
+            last_seen: now,
+            acknowledged: false,
+            protocol: None,
+            related_tokens: Vec::new(),
+            projected_seconds_to_liquidation: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AlertType {
     LiquidationRisk,
     PositionSizeExceeded,
@@ -107,6 +361,15 @@ pub enum AlertType {
     PriceImpactHigh,
     ContractVulnerability,
     MevExposure,
+    OracleDeviation,
+    UnpriceablePosition,
+    MemoryPressure,
+    /// Average pairwise correlation of tracked assets jumped above its trailing baseline
+    CorrelationSpike,
+    /// Token allowance granted to a blacklisted, exploited or unaudited spender
+    ApprovalRisk,
+    /// A price update was rejected as an implausible jump from the last accepted price
+    PriceCircuitBreaker,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,6 +380,22 @@ pub struct Protocol {
     pub loan_to_value_ratio: Decimal,
     pub supported_tokens: Vec<TokenAddress>,
     pub risk_score: Decimal, // 0-100
+    /// Per-asset liquidation thresholds overriding `liquidation_threshold`
+    #[serde(default)]
+    pub token_liquidation_thresholds: HashMap<TokenAddress, Decimal>,
+    /// Per-asset liquidation bonus paid to liquidators, e.g. 0.05 for 5%
+    #[serde(default)]
+    pub token_liquidation_bonuses: HashMap<TokenAddress, Decimal>,
+}
+
+impl Protocol {
+    pub fn liquidation_threshold_for(&self, token_address: &str) -> Decimal {
+        self.token_liquidation_thresholds.get(token_address).copied().unwrap_or(self.liquidation_threshold)
+    }
+
+    pub fn liquidation_bonus_for(&self, token_address: &str) -> Decimal {
+        self.token_liquidation_bonuses.get(token_address).copied().unwrap_or(Decimal::ZERO)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +407,37 @@ pub struct PriceData {
     pub confidence: Decimal, // 0-1
 }
 
+/// Attempt limit and exponential backoff for retrying a failed operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter_factor: f64, // 0-1, fraction of each backoff that is randomized away
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            jitter_factor: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff to wait after the given (1-based) failed attempt. Randomizing part of the
+    /// delay keeps many callers failing against the same endpoint from retrying in lockstep.
+    pub fn backoff_delay(&self, attempt: u32, rng: &mut impl Rng) -> Duration {
+        let exponential = self.base_delay.as_secs_f64() * 2f64.powi(attempt.saturating_sub(1) as i32);
+        let capped = exponential.min(self.max_delay.as_secs_f64());
+        let jitter = self.jitter_factor.clamp(0.0, 1.0) * rng.gen::<f64>();
+        Duration::from_secs_f64(capped * (1.0 - jitter))
+    }
+}
+
 pub trait HealthCalculator: Send + Sync {
     fn calculate_health(&self, position: &Position, prices: &HashMap<TokenAddress, PriceData>) -> Result<HealthFactor, CalculationError>;
     fn protocol(&self) -> &str;
@@ -137,12 +447,16 @@ pub trait HealthCalculator: Send + Sync {
 pub enum CalculationError {
     #[error("Missing price data for token: {token}")]
     MissingPriceData { token: TokenAddress },
+    #[error("Stale price data for token {token}: {age_secs}s old")]
+    StalePriceData { token: TokenAddress, age_secs: i64 },
     #[error("Invalid position data: {message}")]
     InvalidPosition { message: String },
     #[error("Protocol not supported: {protocol}")]
     UnsupportedProtocol { protocol: String },
     #[error("Calculation failed: {message}")]
     CalculationFailed { message: String },
+    #[error("Price update for {token} rejected by circuit breaker: ${rejected_price} vs last accepted ${last_accepted_price}")]
+    PriceCircuitOpen { token: TokenAddress, last_accepted_price: Decimal, rejected_price: Decimal },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -153,4 +467,6 @@ pub enum PositionError {
     AlreadyExists { id: PositionId },
     #[error("Invalid position: {message}")]
     Invalid { message: String },
+    #[error("Failed to persist position {id}: {message}")]
+    Persistence { id: PositionId, message: String },
 }
\ No newline at end of file
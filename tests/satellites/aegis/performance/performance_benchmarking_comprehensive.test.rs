@@ -93,6 +93,8 @@ mod performance_benchmarking_tests {
         latency_ms: u64,
         failure_rate: f64,
         request_count: Arc<RwLock<usize>>,
+        // Calls to `get_price` or `get_prices`, however many tokens each asks for
+        feed_calls: Arc<RwLock<usize>>,
     }
 
     impl HighPerformanceMockPriceFeedProvider {
@@ -114,6 +116,7 @@ mod performance_benchmarking_tests {
                 latency_ms,
                 failure_rate: 0.0,
                 request_count: Arc::new(RwLock::new(0)),
+                feed_calls: Arc::new(RwLock::new(0)),
             }
         }
 
@@ -123,12 +126,14 @@ mod performance_benchmarking_tests {
 
         async fn reset_request_count(&self) {
             *self.request_count.write().await = 0;
+            *self.feed_calls.write().await = 0;
         }
-    }
 
-    #[async_trait::async_trait]
-    impl PriceFeedProvider for HighPerformanceMockPriceFeedProvider {
-        async fn get_price(&self, token_address: &str) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
+        async fn get_feed_calls(&self) -> usize {
+            *self.feed_calls.read().await
+        }
+
+        async fn lookup_price(&self, token_address: &str) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
             // Increment request counter
             {
                 let mut count = self.request_count.write().await;
@@ -148,11 +153,20 @@ mod performance_benchmarking_tests {
                 .copied()
                 .ok_or_else(|| format!("Price not found for token: {}", token_address).into())
         }
+    }
+
+    #[async_trait::async_trait]
+    impl PriceFeedProvider for HighPerformanceMockPriceFeedProvider {
+        async fn get_price(&self, token_address: &str) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
+            *self.feed_calls.write().await += 1;
+            self.lookup_price(token_address).await
+        }
 
         async fn get_prices(&self, token_addresses: &[String]) -> Result<HashMap<String, Decimal>, Box<dyn std::error::Error + Send + Sync>> {
+            *self.feed_calls.write().await += 1;
             let mut result = HashMap::new();
             for token in token_addresses {
-                if let Ok(price) = self.get_price(token).await {
+                if let Ok(price) = self.lookup_price(token).await {
                     result.insert(token.clone(), price);
                 }
             }
@@ -975,6 +989,60 @@ mod performance_benchmarking_tests {
         println!("=== Scalability Testing Completed ===");
     }
 
+    #[tokio::test]
+    async fn test_batched_vs_looped_health_calculation() {
+        println!("=== Batched vs Looped Health Calculation Benchmark ===");
+
+        let (aegis, price_feed, _) = setup_performance_test_satellite(1) // 1ms latency
+            .await
+            .expect("Should setup performance test satellite");
+
+        let mut position_ids = Vec::new();
+        for position in create_performance_test_positions(1000) {
+            position_ids.push(aegis.add_position(position).await.expect("Should add position"));
+        }
+
+        // Looped: one price fetch per position
+        price_feed.reset_request_count().await;
+        let looped_start = Instant::now();
+        let mut looped_results = HashMap::new();
+        for position_id in &position_ids {
+            looped_results.insert(*position_id, aegis.get_position_health(*position_id).await);
+        }
+        let looped_duration = looped_start.elapsed();
+        let looped_requests = price_feed.get_request_count().await;
+        let looped_feed_calls = price_feed.get_feed_calls().await;
+
+        // Batched: every position valued against one price snapshot
+        price_feed.reset_request_count().await;
+        let batched_start = Instant::now();
+        let batched_results = aegis.get_positions_health(&position_ids).await;
+        let batched_duration = batched_start.elapsed();
+        let batched_requests = price_feed.get_request_count().await;
+        let batched_feed_calls = price_feed.get_feed_calls().await;
+
+        // Timings vary with the machine, so they are reported rather than asserted on
+        let speedup = looped_duration.as_secs_f64() / batched_duration.as_secs_f64().max(f64::EPSILON);
+
+        println!("  Looped:  {} ms, {} feed calls, {} price requests", looped_duration.as_millis(), looped_feed_calls, looped_requests);
+        println!("  Batched: {} ms, {} feed calls, {} price requests", batched_duration.as_millis(), batched_feed_calls, batched_requests);
+        println!("  Speedup: {:.1}x", speedup);
+
+        assert_eq!(batched_results.len(), position_ids.len());
+        for (position_id, looped) in &looped_results {
+            match (looped, &batched_results[position_id]) {
+                (Ok(looped), Ok(batched)) => assert_eq!(looped.value, batched.value),
+                (Err(_), Err(_)) => {}
+                _ => panic!("Batched and looped results disagree for {}", position_id),
+            }
+        }
+        assert!(batched_requests < looped_requests);
+        // Every position is valued against one price snapshot
+        assert_eq!(batched_feed_calls, 1);
+
+        println!("=== Batched vs Looped Health Calculation Benchmark Completed ===");
+    }
+
     #[tokio::test]
     async fn test_long_running_stability() {
         let (aegis, price_feed, trade_executor) = setup_performance_test_satellite(5) // 5ms latency